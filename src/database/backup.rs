@@ -0,0 +1,48 @@
+use crate::database::catalog::write_catalog;
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::table::data::Table;
+use std::path::PathBuf;
+use std::thread::{self, JoinHandle};
+
+pub struct BackupHandle {
+    handle: JoinHandle<Result<usize, DbError>>,
+}
+
+impl BackupHandle {
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    pub fn wait(self) -> Result<usize, DbError> {
+        self.handle
+            .join()
+            .map_err(|_| DbError::Other("Backup thread panicked".to_string()))?
+    }
+}
+
+impl Database {
+    // Writes the database in the catalog format, so the backup opens with `load`
+    pub fn backup_online(&self, path: &str) -> Result<BackupHandle, DbError> {
+        let dir = PathBuf::from(path);
+
+        // Snapshot the committed state of every persistent table up front; an open
+        // transaction's uncommitted writes are undone in the copy rather than in the live rows.
+        let tables: Vec<Table> = self
+            .persistent_tables()
+            .into_iter()
+            .map(|table| table.committed().into_owned())
+            .collect();
+        let views = self.views.clone();
+        let config = self.config.clone();
+
+        // File writes happen off the caller's thread, so the database is free again immediately
+        let handle = thread::spawn(move || {
+            let tables: Vec<&Table> = tables.iter().collect();
+            write_catalog(&dir, &config, &tables, &views)?;
+            Ok(tables.len())
+        });
+
+        Ok(BackupHandle { handle })
+    }
+}
@@ -0,0 +1,180 @@
+use crate::database::history::RowChange;
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::table::data::{FilterExpr, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchOp {
+    Insert {
+        table: String,
+        values: Vec<Value>,
+    },
+    Update {
+        table: String,
+        expr: FilterExpr,
+        updates: Vec<Option<Value>>,
+    },
+    Delete {
+        table: String,
+        expr: FilterExpr,
+    },
+}
+
+// Writes collected by Database::batch and applied together
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WriteBatch {
+    pub ops: Vec<BatchOp>,
+}
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        WriteBatch::default()
+    }
+
+    pub fn insert(&mut self, table: &str, values: Vec<Value>) -> &mut Self {
+        self.ops.push(BatchOp::Insert {
+            table: table.to_string(),
+            values,
+        });
+        self
+    }
+
+    pub fn update(
+        &mut self,
+        table: &str,
+        expr: &FilterExpr,
+        updates: Vec<Option<Value>>,
+    ) -> &mut Self {
+        self.ops.push(BatchOp::Update {
+            table: table.to_string(),
+            expr: expr.clone(),
+            updates,
+        });
+        self
+    }
+
+    pub fn delete(&mut self, table: &str, expr: &FilterExpr) -> &mut Self {
+        self.ops.push(BatchOp::Delete {
+            table: table.to_string(),
+            expr: expr.clone(),
+        });
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+impl Database {
+    // Collects writes in `build` and applies them as one batch; returns the number of rows affected
+    pub fn batch<F>(&mut self, build: F) -> Result<usize, DbError>
+    where
+        F: FnOnce(&mut WriteBatch),
+    {
+        let mut batch = WriteBatch::new();
+        build(&mut batch);
+        self.apply_batch(batch)
+    }
+
+    // Applies every operation to the in-memory rows, then flushes each touched table's engine
+    // once. If any operation fails, no table or engine is changed.
+    pub fn apply_batch(&mut self, batch: WriteBatch) -> Result<usize, DbError> {
+        // Each touched table, with the length of its transaction log before the batch
+        let mut touched: HashMap<String, Option<usize>> = HashMap::new();
+        // Engine writes are held back until every operation in the batch has succeeded
+        let mut changes: Vec<(String, RowChange)> = Vec::new();
+
+        let result = batch.ops.into_iter().try_fold(0, |affected, op| {
+            let table_name = match &op {
+                BatchOp::Insert { table, .. }
+                | BatchOp::Update { table, .. }
+                | BatchOp::Delete { table, .. } => table.clone(),
+            };
+            if !touched.contains_key(&table_name) {
+                let table = self
+                    .tables
+                    .get(&table_name)
+                    .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found", table_name)))?;
+                touched.insert(table_name.clone(), table.transaction_backup.as_ref().map(Vec::len));
+            }
+
+            let (count, op_changes) = self.apply_batch_op(op)?;
+            changes.extend(op_changes.into_iter().map(|c| (table_name.clone(), c)));
+            Ok::<usize, DbError>(affected + count)
+        });
+
+        let affected = match result {
+            Ok(affected) => affected,
+            // Operations either apply whole or not at all, so undoing the recorded
+            // changes restores every table
+            Err(e) => {
+                for (name, change) in changes.iter().rev() {
+                    change.undo(&mut self.tables.get_mut(name).unwrap().rows);
+                }
+                for (name, log_len) in touched {
+                    let table = self.tables.get_mut(&name).unwrap();
+                    if let (Some(log), Some(len)) = (&mut table.transaction_backup, log_len) {
+                        log.truncate(len);
+                    }
+                    table.rebuild_all_indexes();
+                }
+                return Err(e);
+            }
+        };
+
+        self.publish_changes(changes)?;
+        for name in touched.keys() {
+            self.flush(name)?;
+        }
+        Ok(affected)
+    }
+
+    // Indexes are kept current so later operations' constraint checks see earlier ones
+    fn apply_batch_op(&mut self, op: BatchOp) -> Result<(usize, Vec<RowChange>), DbError> {
+        match op {
+            BatchOp::Insert { table, values } => {
+                let table = self.tables.get_mut(&table).unwrap();
+                let row = table.prepare_insert(values)?;
+                table.push_row(row.clone());
+                Ok((1, vec![RowChange::Insert { row }]))
+            }
+            BatchOp::Update {
+                table,
+                expr,
+                updates,
+            } => {
+                let indices = self.filter_positions(&table, &expr)?;
+                let table = self.tables.get_mut(&table).unwrap();
+                let new_rows = table.prepare_updates(&indices, &updates)?;
+
+                let mut changes = vec![];
+                for (&position, after) in indices.iter().zip(new_rows) {
+                    let before = table.replace_row(position, after.clone());
+                    changes.push(RowChange::Update {
+                        position,
+                        before,
+                        after,
+                    });
+                }
+                Ok((indices.len(), changes))
+            }
+            BatchOp::Delete { table, expr } => {
+                let indices = self.filter_positions(&table, &expr)?;
+                let table = self.tables.get_mut(&table).unwrap();
+                let rows = indices.iter().map(|&i| table.rows[i].clone()).collect();
+                let count = table.delete_rows(&indices);
+                let change = RowChange::Delete {
+                    positions: indices,
+                    rows,
+                };
+                Ok((count, vec![change]))
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::table::data::{FilterExpr, FkAction, Options, Value};
+
+impl Database {
+    // Updates the matching rows of `table_name`, then applies each referencing column's
+    // ON UPDATE action to the child rows that held a changed primary key. Cascades follow
+    // on to grandchildren, and all of it is undone if any step fails.
+    pub fn update_table(
+        &mut self,
+        table_name: &str,
+        expr: &FilterExpr,
+        updates: Vec<Option<Value>>,
+    ) -> Result<usize, DbError> {
+        let indices = self.filter_positions(table_name, expr)?;
+
+        let started = self.transaction.is_none();
+        if started {
+            self.begin_transaction()?;
+        }
+        let savepoint = self.savepoint();
+        match self.cascade_update(table_name, &indices, &updates) {
+            Ok(count) => {
+                if started {
+                    self.commit()?;
+                }
+                Ok(count)
+            }
+            Err(e) => {
+                if started {
+                    self.rollback()?;
+                } else {
+                    self.rollback_to(savepoint);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    fn cascade_update(
+        &mut self,
+        table_name: &str,
+        indices: &[usize],
+        updates: &[Option<Value>],
+    ) -> Result<usize, DbError> {
+        let key_changes = self.key_changes(table_name, indices, updates)?;
+        let count = self.apply_update(table_name, indices, updates)?;
+        if key_changes.is_empty() {
+            return Ok(count);
+        }
+
+        // Child rows are found before any of them change, so keys that swap stay apart
+        let mut child_updates = vec![];
+        for (child, col_idx, action) in self.referencing_columns(table_name) {
+            let table = &self.tables[&child];
+            for (old, new) in &key_changes {
+                let positions: Vec<usize> = (0..table.rows.len())
+                    .filter(|&i| table.rows[i][col_idx] == *old)
+                    .collect();
+                if positions.is_empty() {
+                    continue;
+                }
+                let value = match action {
+                    FkAction::Restrict => {
+                        return Err(DbError::ConstraintViolation(format!(
+                            "Cannot change key {} of table '{}': referenced by '{}.{}'",
+                            old, table_name, child, table.columns[col_idx].name
+                        )))
+                    }
+                    FkAction::Cascade => new.clone(),
+                    FkAction::SetNull => Value::Null,
+                };
+                let mut row_updates = vec![None; table.columns.len()];
+                row_updates[col_idx] = Some(value);
+                child_updates.push((child.clone(), positions, row_updates));
+            }
+        }
+
+        for (child, positions, row_updates) in child_updates {
+            self.cascade_update(&child, &positions, &row_updates)?;
+        }
+        Ok(count)
+    }
+
+    // Old and new primary key of each row the update moves to a different key. Only
+    // single-column keys can be referenced.
+    fn key_changes(
+        &self,
+        table_name: &str,
+        indices: &[usize],
+        updates: &[Option<Value>],
+    ) -> Result<Vec<(Value, Value)>, DbError> {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found", table_name)))?;
+        let key_idx = match table.primary_key.as_deref() {
+            Some([key]) => table.resolve_column(key)?,
+            _ => return Ok(vec![]),
+        };
+        if updates.get(key_idx).is_none_or(Option::is_none) {
+            return Ok(vec![]);
+        }
+
+        let new_rows = table.prepare_updates(indices, updates)?;
+        Ok(indices
+            .iter()
+            .zip(new_rows)
+            .filter(|(&i, new_row)| table.rows[i][key_idx] != new_row[key_idx])
+            .map(|(&i, new_row)| (table.rows[i][key_idx].clone(), new_row[key_idx].clone()))
+            .collect())
+    }
+
+    // Every (table, column index, ON UPDATE action) whose column references `parent`
+    fn referencing_columns(&self, parent: &str) -> Vec<(String, usize, FkAction)> {
+        let mut columns = vec![];
+        for table in self.tables.values() {
+            for (i, column) in table.columns.iter().enumerate() {
+                if !column.options.contains(&Options::FK(parent.to_string())) {
+                    continue;
+                }
+                let action = column
+                    .options
+                    .iter()
+                    .find_map(|opt| match opt {
+                        Options::OnUpdate(action) => Some(*action),
+                        _ => None,
+                    })
+                    .unwrap_or_default();
+                columns.push((table.name.clone(), i, action));
+            }
+        }
+        columns
+    }
+}
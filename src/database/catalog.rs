@@ -0,0 +1,251 @@
+use crate::database::config::DatabaseConfig;
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::storage::atomic;
+use crate::storage::journal::{self, journal_path, Journal};
+use crate::table::data::{Expr, IndexType, Table, View};
+use crate::table::schema::{index_line, join_fields, parse_index, split_fields, TableSchema};
+use crate::table::sharding::remove_table_files;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const MANIFEST_FILE: &str = "MANIFEST";
+
+// One table's entry in the manifest, between its `table` and `end` lines
+struct TableEntry {
+    name: String,
+    schema: TableSchema,
+    indexes: Vec<(String, IndexType)>,
+}
+
+// A view's entry, between its `view` and `end` lines. The filter is kept as text until
+// the tables are loaded, so its literals can be typed by the columns they compare against.
+struct ViewEntry {
+    name: String,
+    view: View,
+    filter: Option<String>,
+}
+
+enum Entry {
+    Table(TableEntry),
+    View(ViewEntry),
+}
+
+impl Database {
+    // Writes every persistent table and a manifest describing the schema and views, so load
+    // needs nothing but the path. Engines, row policies and history are not saved.
+    pub fn save(&self, path: &str) -> Result<(), DbError> {
+        write_catalog(Path::new(path), &self.config, &self.persistent_tables(), &self.views)
+    }
+
+    // The tables that aren't memory only, in name order
+    pub(crate) fn persistent_tables(&self) -> Vec<&Table> {
+        let mut tables: Vec<&Table> = self
+            .tables
+            .iter()
+            .filter(|(name, _)| !self.memory_only.contains(*name))
+            .map(|(_, table)| table)
+            .collect();
+        tables.sort_by_key(|table| &table.name);
+        tables
+    }
+
+    pub fn load(path: &str) -> Result<Database, DbError> {
+        let dir = Path::new(path);
+        journal::recover(&catalog_journal(dir))?;
+        let (db_name, entries, views) = read_manifest(dir)?;
+
+        let mut db = Database::with_config(DatabaseConfig::new(&db_name, dir));
+        for entry in entries {
+            let schema = entry.schema;
+            let columns = schema.columns.clone();
+            let mut table = Table::load_from_dir(dir, &db_name, &entry.name, columns, schema.primary_key.clone())?;
+            schema.fill(&mut table);
+            // load_from_dir indexes every column; keep only the ones that were saved
+            table.indexes.clear();
+            for (column, kind) in &entry.indexes {
+                table.create_index_like(column, kind)?;
+            }
+            db.tables.insert(entry.name, table);
+        }
+
+        for entry in views {
+            let mut view = entry.view;
+            if let Some(text) = entry.filter {
+                // A view over a table that was not saved still loads, and fails when queried
+                view.filter = Some(match db.view_source_columns(&view) {
+                    Ok(columns) => Table::new(&entry.name, columns, None).parse_filter(&text)?,
+                    Err(_) => text.parse()?,
+                });
+            }
+            db.views.insert(entry.name, view);
+        }
+        Ok(db)
+    }
+}
+
+// The journal every write to the catalog's files goes through
+pub(crate) fn catalog_journal(dir: &Path) -> PathBuf {
+    journal_path(&dir.join(MANIFEST_FILE))
+}
+
+// Writes `tables` and `views` as a database's whole contents. Every table's files, the
+// manifest and the removal of dropped tables' files are made through one journal, so a
+// crash leaves the whole database as last saved or as saved now.
+pub(crate) fn write_catalog(
+    dir: &Path,
+    config: &DatabaseConfig,
+    tables: &[&Table],
+    views: &HashMap<String, View>,
+) -> Result<(), DbError> {
+    fs::create_dir_all(dir).map_err(|e| DbError::IoError(format!("Failed to create directory: {}", e)))?;
+
+    let mut journal = Journal::default();
+    if let Err(e) = stage_catalog(dir, config, tables, views, &mut journal) {
+        journal.abandon(dir);
+        return Err(e);
+    }
+    journal.commit(&catalog_journal(dir), config.sync)
+}
+
+pub(crate) fn stage_catalog(
+    dir: &Path,
+    config: &DatabaseConfig,
+    tables: &[&Table],
+    views: &HashMap<String, View>,
+    journal: &mut Journal,
+) -> Result<(), DbError> {
+    let mut lines = vec![join_fields(&["database", &config.name])];
+    for table in tables {
+        table.stage_save(dir, &config.name, config.sync, journal)?;
+        lines.extend(table_lines(table));
+    }
+    let mut views: Vec<(&String, &View)> = views.iter().collect();
+    views.sort_by_key(|(name, _)| *name);
+    for (name, view) in views {
+        lines.extend(view_lines(name, view));
+    }
+
+    // Tables dropped since the last save would otherwise linger next to the new manifest
+    if dir.join(MANIFEST_FILE).exists() {
+        let (db_name, previous, _) = read_manifest(dir)?;
+        for entry in previous.iter().filter(|e| tables.iter().all(|t| t.name != e.name)) {
+            remove_table_files(dir, &db_name, &entry.name, journal);
+        }
+    }
+
+    lines.push(String::new());
+    let manifest = dir.join(MANIFEST_FILE);
+    let temp = atomic::write_temp(&manifest, config.sync, |out| Ok(out.write_all(lines.join("\n").as_bytes())?))?;
+    journal.rename(&temp, &manifest);
+    Ok(())
+}
+
+fn table_lines(table: &Table) -> Vec<String> {
+    let mut lines = vec![join_fields(&["table", &table.name])];
+    lines.extend(TableSchema::lines(table));
+
+    let mut indexed: Vec<(&String, &IndexType)> = table.indexes.iter().collect();
+    indexed.sort_by_key(|(column, _)| *column);
+    lines.extend(indexed.into_iter().map(|(column, index)| index_line(column, index)));
+
+    lines.push("end".to_string());
+    lines
+}
+
+fn view_lines(name: &str, view: &View) -> Vec<String> {
+    let mut lines = vec![join_fields(&["view", name]), join_fields(&["from", &view.from])];
+    for (table, left, right) in &view.joins {
+        lines.push(join_fields(&["join", table, left, right]));
+    }
+    if let Some(filter) = &view.filter {
+        lines.push(join_fields(&["where", &filter.to_string()]));
+    }
+    if !view.columns.is_empty() {
+        let columns: Vec<String> = view.columns.iter().map(|expr| expr.to_string()).collect();
+        let mut fields = vec!["select"];
+        fields.extend(columns.iter().map(String::as_str));
+        lines.push(join_fields(&fields));
+    }
+    lines.push("end".to_string());
+    lines
+}
+
+// Reads a line inside a view entry, returning false if it is not one view_lines writes
+fn read_view_line(entry: &mut ViewEntry, fields: &[String]) -> Result<bool, DbError> {
+    match (fields[0].as_str(), &fields[1..]) {
+        ("from", [table]) => entry.view.from = table.clone(),
+        ("join", [table, left, right]) => entry.view.joins.push((table.clone(), left.clone(), right.clone())),
+        ("where", [filter]) => entry.filter = Some(filter.clone()),
+        ("select", columns) if !columns.is_empty() => {
+            for text in columns {
+                entry.view.columns.push(text.parse::<Expr>()?);
+            }
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn read_manifest(dir: &Path) -> Result<(String, Vec<TableEntry>, Vec<ViewEntry>), DbError> {
+    let contents = fs::read_to_string(dir.join(MANIFEST_FILE))
+        .map_err(|e| DbError::IoError(format!("Failed to read manifest: {}", e)))?;
+    let mut lines = contents.lines().filter(|l| !l.is_empty()).map(split_fields);
+
+    let db_name = match lines.next().as_deref() {
+        Some([tag, name]) if tag == "database" => name.clone(),
+        _ => return Err(DbError::SchemaError("Manifest does not start with a database line".to_string())),
+    };
+
+    let mut tables: Vec<TableEntry> = vec![];
+    let mut views: Vec<ViewEntry> = vec![];
+    let mut current: Option<Entry> = None;
+    for fields in lines {
+        match (fields[0].as_str(), current.as_mut()) {
+            ("table", None) if fields.len() == 2 => {
+                current = Some(Entry::Table(TableEntry {
+                    name: fields[1].clone(),
+                    schema: TableSchema::default(),
+                    indexes: vec![],
+                }));
+            }
+            ("view", None) if fields.len() == 2 => {
+                current = Some(Entry::View(ViewEntry {
+                    name: fields[1].clone(),
+                    view: View::new(""),
+                    filter: None,
+                }));
+            }
+            ("end", Some(_)) if fields.len() == 1 => match current.take() {
+                Some(Entry::Table(entry)) => tables.push(entry),
+                Some(Entry::View(entry)) => views.push(entry),
+                None => {}
+            },
+            ("index", Some(Entry::Table(entry))) => entry.indexes.push(parse_index(&fields)?),
+            (_, Some(Entry::Table(entry))) => {
+                if !entry.schema.read_line(&fields)? {
+                    return Err(malformed_manifest(&fields));
+                }
+            }
+            (_, Some(Entry::View(entry))) => {
+                if !read_view_line(entry, &fields)? {
+                    return Err(malformed_manifest(&fields));
+                }
+            }
+            _ => return Err(malformed_manifest(&fields)),
+        }
+    }
+
+    match current {
+        Some(Entry::Table(TableEntry { name, .. })) | Some(Entry::View(ViewEntry { name, .. })) => {
+            Err(DbError::SchemaError(format!("Manifest entry for '{}' is not terminated", name)))
+        }
+        None => Ok((db_name, tables, views)),
+    }
+}
+
+fn malformed_manifest(fields: &[String]) -> DbError {
+    DbError::SchemaError(format!("Unexpected manifest line '{}'", fields.join(" ")))
+}
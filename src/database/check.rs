@@ -0,0 +1,546 @@
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::storage::btree::{stored_key, DiskIndex};
+use crate::storage::compress;
+use crate::storage::file::read_rows;
+use crate::table::data::{GridIndex, IndexType, Options, Table, Value};
+use crate::table::schema::{schema_path, TableSchema};
+use csv::ReaderBuilder;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IssueKind {
+    Unreadable,
+    // A page or file that fails its checksum, or ends early
+    Corrupted,
+    MissingFile,
+    UnknownTable,
+    HeaderMismatch,
+    FieldCount,
+    TypeMismatch,
+    NotNull,
+    DuplicateKey,
+    IndexMismatch,
+}
+
+#[derive(Debug, Clone)]
+pub struct IntegrityIssue {
+    pub table: String,
+    pub kind: IssueKind,
+    // 1-based: the record within the file for problems in a data file, the table's row
+    // for index problems
+    pub row: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct IntegrityReport {
+    pub tables_checked: usize,
+    pub rows_checked: usize,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn push(&mut self, table: &str, kind: IssueKind, row: Option<usize>, message: String) {
+        self.issues.push(IntegrityIssue {
+            table: table.to_string(),
+            kind,
+            row,
+            message,
+        });
+    }
+}
+
+impl Database {
+    pub fn check(&self, path: &str) -> IntegrityReport {
+        let mut report = IntegrityReport::default();
+        let dir = Path::new(path);
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                report.push(
+                    path,
+                    IssueKind::Unreadable,
+                    None,
+                    format!("Failed to read directory: {}", e),
+                );
+                return report;
+            }
+        };
+
+        // Files are named "<db>.<table>.pages", "<db>.<table>.csv" or "<db>.<table>.shard<n>.csv",
+        // any of them perhaps compressed; views are skipped since they carry no schema
+        let mut files: HashMap<String, Vec<String>> = HashMap::new();
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let name = compress::strip_extension(&file_name);
+            let Some(stem) = name.strip_suffix(".pages").or_else(|| name.strip_suffix(".csv")) else {
+                continue;
+            };
+            if name.ends_with(".view.csv") {
+                continue;
+            }
+            let mut segments = stem.rsplit('.');
+            let mut table_name = segments.next().unwrap_or(stem);
+            if is_shard_segment(table_name) {
+                table_name = segments.next().unwrap_or(table_name);
+            }
+            files
+                .entry(table_name.to_string())
+                .or_default()
+                .push(entry.path().to_string_lossy().to_string());
+        }
+
+        for (table_name, file_paths) in &mut files {
+            match self.tables.get(table_name) {
+                Some(table) => {
+                    report.tables_checked += 1;
+                    check_checksums(table, &schema_path(dir, &self.config.name, table_name), dir, &mut report);
+                    // Keys are unique across all of a table's shards, not just within each
+                    let mut keys = KeyCheck::new(table);
+                    file_paths.sort();
+                    for file_path in file_paths.iter() {
+                        check_file(table, file_path, &mut keys, &mut report);
+                    }
+                }
+                None => report.push(
+                    table_name,
+                    IssueKind::UnknownTable,
+                    None,
+                    format!("File '{}' has no matching table in the catalog", file_paths[0]),
+                ),
+            }
+        }
+
+        let mut names: Vec<&String> = self.tables.keys().collect();
+        names.sort();
+        for name in names {
+            let table = &self.tables[name];
+            if !files.contains_key(name) {
+                report.push(
+                    name,
+                    IssueKind::MissingFile,
+                    None,
+                    format!("No data file found for table '{}'", name),
+                );
+            }
+            check_indexes(table, &mut report);
+        }
+
+        report
+    }
+}
+
+fn is_shard_segment(segment: &str) -> bool {
+    segment
+        .strip_prefix("shard")
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+// Against the checksums the sidecar recorded for CSV files when they were saved
+fn check_checksums(table: &Table, schema_file: &Path, dir: &Path, report: &mut IntegrityReport) {
+    let Ok(schema) = TableSchema::read(schema_file) else {
+        return;
+    };
+    match schema.verify_files(dir) {
+        Ok(()) => {}
+        Err(e @ DbError::Corruption { .. }) => report.push(&table.name, IssueKind::Corrupted, None, e.to_string()),
+        Err(e @ DbError::NotFound(_)) => report.push(&table.name, IssueKind::MissingFile, None, e.to_string()),
+        Err(e) => report.push(&table.name, IssueKind::Unreadable, None, e.to_string()),
+    }
+}
+
+fn check_file(table: &Table, file_path: &str, keys: &mut KeyCheck, report: &mut IntegrityReport) {
+    if compress::strip_extension(file_path).ends_with(".pages") {
+        return check_pages(table, file_path, keys, report);
+    }
+    let file = match compress::open(Path::new(file_path)) {
+        Ok(f) => f,
+        Err(e) => {
+            report.push(
+                &table.name,
+                IssueKind::Unreadable,
+                None,
+                format!("Failed to open file: {}", e),
+            );
+            return;
+        }
+    };
+
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .from_reader(file);
+
+    let headers = match rdr.headers() {
+        Ok(h) => h.clone(),
+        Err(e) => {
+            report.push(
+                &table.name,
+                IssueKind::Unreadable,
+                None,
+                format!("CSV parse error: {}", e),
+            );
+            return;
+        }
+    };
+
+    let expected: Vec<&str> = table.columns.iter().map(|c| c.name.as_str()).collect();
+    let actual: Vec<&str> = headers.iter().collect();
+    if expected != actual {
+        report.push(
+            &table.name,
+            IssueKind::HeaderMismatch,
+            None,
+            format!("Expected header {:?}, found {:?}", expected, actual),
+        );
+        return;
+    }
+
+    for (line_num, result) in rdr.records().enumerate() {
+        let row_num = line_num + 1;
+        report.rows_checked += 1;
+
+        let record = match result {
+            Ok(r) => r,
+            Err(e) => {
+                report.push(
+                    &table.name,
+                    IssueKind::Unreadable,
+                    Some(row_num),
+                    format!("CSV parse error: {}", e),
+                );
+                continue;
+            }
+        };
+
+        if record.len() != table.columns.len() {
+            report.push(
+                &table.name,
+                IssueKind::FieldCount,
+                Some(row_num),
+                format!(
+                    "Expected {} fields, found {}",
+                    table.columns.len(),
+                    record.len()
+                ),
+            );
+            continue;
+        }
+
+        let mut row = Vec::with_capacity(table.columns.len());
+        let mut row_ok = true;
+        for (i, col) in table.columns.iter().enumerate() {
+            let raw = &record[i];
+            let value = if raw == "NULL" {
+                Value::Null
+            } else {
+                match Value::from_str(raw, &col.datatype) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        report.push(
+                            &table.name,
+                            IssueKind::TypeMismatch,
+                            Some(row_num),
+                            format!("Column '{}' value '{}': {}", col.name, raw, e),
+                        );
+                        row_ok = false;
+                        Value::Null
+                    }
+                }
+            };
+
+            if value == Value::Null && row_ok && col.options.contains(&Options::NotNull) {
+                report.push(
+                    &table.name,
+                    IssueKind::NotNull,
+                    Some(row_num),
+                    format!("Column '{}' is NOT NULL but contains NULL", col.name),
+                );
+            }
+            row.push(value);
+        }
+
+        if !row_ok {
+            continue;
+        }
+
+        keys.check(table, &row, row_num, report);
+    }
+}
+
+// Page files hold typed values, so there is nothing to parse; each value's type is checked instead
+fn check_pages(table: &Table, file_path: &str, keys: &mut KeyCheck, report: &mut IntegrityReport) {
+    let rows = match read_rows(Path::new(file_path)) {
+        Ok(rows) => rows,
+        Err(e @ DbError::Corruption { .. }) => {
+            report.push(&table.name, IssueKind::Corrupted, None, e.to_string());
+            return;
+        }
+        Err(e) => {
+            report.push(&table.name, IssueKind::Unreadable, None, e.to_string());
+            return;
+        }
+    };
+
+    for (i, row) in rows.iter().enumerate() {
+        let row_num = i + 1;
+        report.rows_checked += 1;
+        if row.len() != table.columns.len() {
+            report.push(
+                &table.name,
+                IssueKind::FieldCount,
+                Some(row_num),
+                format!("Expected {} fields, found {}", table.columns.len(), row.len()),
+            );
+            continue;
+        }
+
+        let mut row_ok = true;
+        for (value, col) in row.iter().zip(&table.columns) {
+            if !Table::value_matches_type(value, &col.datatype) {
+                report.push(
+                    &table.name,
+                    IssueKind::TypeMismatch,
+                    Some(row_num),
+                    format!("Column '{}' value '{}' is not {:?}", col.name, value.to_display_string(), col.datatype),
+                );
+                row_ok = false;
+            } else if value == &Value::Null && col.options.contains(&Options::NotNull) {
+                report.push(
+                    &table.name,
+                    IssueKind::NotNull,
+                    Some(row_num),
+                    format!("Column '{}' is NOT NULL but contains NULL", col.name),
+                );
+            }
+        }
+        if row_ok {
+            keys.check(table, row, row_num, report);
+        }
+    }
+}
+
+// The primary key and unique column values seen so far across a table's files
+struct KeyCheck {
+    sets: Vec<Vec<usize>>,
+    seen: Vec<HashSet<Vec<Value>>>,
+}
+
+impl KeyCheck {
+    fn new(table: &Table) -> Self {
+        let sets = key_column_sets(table);
+        let seen = vec![HashSet::new(); sets.len()];
+        KeyCheck { sets, seen }
+    }
+
+    fn check(&mut self, table: &Table, row: &[Value], row_num: usize, report: &mut IntegrityReport) {
+        for (indices, seen) in self.sets.iter().zip(self.seen.iter_mut()) {
+            let key: Vec<Value> = indices.iter().map(|&i| row[i].clone()).collect();
+            if !seen.insert(key.clone()) {
+                let names: Vec<&str> = indices
+                    .iter()
+                    .map(|&i| table.columns[i].name.as_str())
+                    .collect();
+                report.push(
+                    &table.name,
+                    IssueKind::DuplicateKey,
+                    Some(row_num),
+                    format!(
+                        "Duplicate key {:?} on ({})",
+                        key.iter().map(|v| v.to_display_string()).collect::<Vec<_>>(),
+                        names.join(", ")
+                    ),
+                );
+            }
+        }
+    }
+}
+
+fn key_column_sets(table: &Table) -> Vec<Vec<usize>> {
+    let mut sets = vec![];
+
+    if let Some(pk) = &table.primary_key {
+        let indices: Vec<usize> = pk
+            .iter()
+            .filter_map(|name| table.columns.iter().position(|c| &c.name == name))
+            .collect();
+        if !indices.is_empty() {
+            sets.push(indices);
+        }
+    }
+
+    for (i, col) in table.columns.iter().enumerate() {
+        if col.options.contains(&Options::Unique) {
+            sets.push(vec![i]);
+        }
+    }
+
+    sets
+}
+
+fn check_indexes(table: &Table, report: &mut IntegrityReport) {
+    for (col_name, index) in &table.indexes {
+        let col_idx = match table.columns.iter().position(|c| &c.name == col_name) {
+            Some(i) => i,
+            None => {
+                report.push(
+                    &table.name,
+                    IssueKind::IndexMismatch,
+                    None,
+                    format!("Index on missing column '{}'", col_name),
+                );
+                continue;
+            }
+        };
+
+        let entries: Vec<(&Value, &Vec<usize>)> = match index {
+            IndexType::Hash(map) => map.iter().collect(),
+            IndexType::BTree(map) => map.iter().collect(),
+            IndexType::Grid(grid) => {
+                check_grid_index(table, col_name, col_idx, grid, report);
+                continue;
+            }
+            IndexType::Disk(disk) => {
+                check_disk_index(table, col_name, col_idx, disk, report);
+                continue;
+            }
+        };
+
+        let collation = table.columns[col_idx].collation();
+        let mut indexed = 0;
+        for (key, row_indices) in entries {
+            for &i in row_indices {
+                indexed += 1;
+                match table.rows.get(i) {
+                    Some(row) if collation.key(&row[col_idx]).as_ref() == key => {}
+                    _ => report.push(
+                        &table.name,
+                        IssueKind::IndexMismatch,
+                        Some(i + 1),
+                        format!(
+                            "Index '{}' maps '{}' to a row that does not hold it",
+                            col_name,
+                            key.to_display_string()
+                        ),
+                    ),
+                }
+            }
+        }
+
+        if indexed != table.rows.len() {
+            report.push(
+                &table.name,
+                IssueKind::IndexMismatch,
+                None,
+                format!(
+                    "Index '{}' covers {} rows but table has {}",
+                    col_name,
+                    indexed,
+                    table.rows.len()
+                ),
+            );
+        }
+    }
+}
+
+// A grid index holds every row with a point value, each under the cell containing it
+fn check_grid_index(
+    table: &Table,
+    col_name: &str,
+    col_idx: usize,
+    grid: &GridIndex,
+    report: &mut IntegrityReport,
+) {
+    let mut indexed = 0;
+    for (cell, row_indices) in &grid.cells {
+        for &i in row_indices {
+            indexed += 1;
+            let in_cell = table
+                .rows
+                .get(i)
+                .is_some_and(|row| grid.cell_of(&row[col_idx]) == Some(*cell));
+            if !in_cell {
+                report.push(
+                    &table.name,
+                    IssueKind::IndexMismatch,
+                    Some(i + 1),
+                    format!(
+                        "Grid index '{}' puts row {} in cell {:?} but its point is elsewhere",
+                        col_name, i + 1, cell
+                    ),
+                );
+            }
+        }
+    }
+
+    let points = table
+        .rows
+        .iter()
+        .filter(|row| grid.cell_of(&row[col_idx]).is_some())
+        .count();
+    if indexed != points {
+        report.push(
+            &table.name,
+            IssueKind::IndexMismatch,
+            None,
+            format!(
+                "Grid index '{}' covers {} rows but table has {} points",
+                col_name, indexed, points
+            ),
+        );
+    }
+}
+
+// Every row appears once in a disk index, under its key as the index stores it
+fn check_disk_index(table: &Table, col_name: &str, col_idx: usize, disk: &DiskIndex, report: &mut IntegrityReport) {
+    let Some(entries) = disk.entries() else {
+        report.push(
+            &table.name,
+            IssueKind::IndexMismatch,
+            None,
+            format!("Disk index '{}' at {} is stale or unreadable", col_name, disk.path().display()),
+        );
+        return;
+    };
+
+    let collation = table.columns[col_idx].collation();
+    for (key, i) in &entries {
+        let holds = table.rows.get(*i).is_some_and(|row| {
+            let expected = stored_key(&collation.key(&row[col_idx]));
+            expected.cmp(key).is_eq()
+        });
+        if !holds {
+            report.push(
+                &table.name,
+                IssueKind::IndexMismatch,
+                Some(i + 1),
+                format!(
+                    "Disk index '{}' maps '{}' to a row that does not hold it",
+                    col_name,
+                    key.to_display_string()
+                ),
+            );
+        }
+    }
+    if entries.len() != table.rows.len() {
+        report.push(
+            &table.name,
+            IssueKind::IndexMismatch,
+            None,
+            format!(
+                "Disk index '{}' covers {} rows but table has {}",
+                col_name,
+                entries.len(),
+                table.rows.len()
+            ),
+        );
+    }
+}
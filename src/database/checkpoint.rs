@@ -0,0 +1,63 @@
+use crate::database::catalog::{catalog_journal, stage_catalog};
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::storage::atomic;
+use crate::storage::journal::{self, Journal};
+use crate::table::data::Table;
+use std::borrow::Cow;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+const CHECKPOINT_FILE: &str = "CHECKPOINT";
+
+impl Database {
+    // Saves the committed state of every table in the catalog format and records the
+    // checkpoint number. Both go through the catalog's journal, which is folded in and
+    // removed once they're in place, so a crash leaves the last checkpoint or this one whole.
+    pub fn checkpoint(&self, path: &str) -> Result<u64, DbError> {
+        let dir = Path::new(path);
+        fs::create_dir_all(dir).map_err(|e| DbError::IoError(format!("Failed to create directory: {}", e)))?;
+        let lsn = Database::last_checkpoint(path)?.unwrap_or(0) + 1;
+
+        let committed: Vec<Cow<Table>> = self.persistent_tables().into_iter().map(Table::committed).collect();
+        let tables: Vec<&Table> = committed.iter().map(AsRef::as_ref).collect();
+        let mut journal = Journal::default();
+        if let Err(e) = self.stage_checkpoint(dir, &tables, lsn, &mut journal) {
+            journal.abandon(dir);
+            return Err(e);
+        }
+        journal.commit(&catalog_journal(dir), self.config.sync)?;
+        Ok(lsn)
+    }
+
+    fn stage_checkpoint(&self, dir: &Path, tables: &[&Table], lsn: u64, journal: &mut Journal) -> Result<(), DbError> {
+        stage_catalog(dir, &self.config, tables, &self.views, journal)?;
+
+        let record = dir.join(CHECKPOINT_FILE);
+        let temp = atomic::write_temp(&record, self.config.sync, |out| Ok(out.write_all(lsn.to_string().as_bytes())?))
+            .map_err(|e| DbError::IoError(format!("Failed to record checkpoint: {}", e)))?;
+        journal.rename(&temp, &record);
+        Ok(())
+    }
+
+    // Finishes a checkpoint a crash cut short first
+    pub fn last_checkpoint(path: &str) -> Result<Option<u64>, DbError> {
+        let file = Path::new(path).join(CHECKPOINT_FILE);
+        journal::recover(&catalog_journal(Path::new(path)))?;
+        if !file.exists() {
+            return Ok(None);
+        }
+
+        let contents =
+            fs::read_to_string(&file).map_err(|e| DbError::IoError(format!("Failed to read checkpoint: {}", e)))?;
+        contents
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| DbError::Corruption {
+                offset: 0,
+                message: format!("Corrupt checkpoint record: '{}'", contents.trim()),
+            })
+    }
+}
@@ -0,0 +1,269 @@
+use crate::database::history::{Change, DEFAULT_HISTORY_LIMIT};
+use crate::database::transaction::{DroppedTable, TableChange};
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::storage::atomic::SyncMode;
+use crate::table::data::{Column, Options, Table};
+use crate::table::functions::DEFAULT_DATA_DIR;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Durability {
+    Persistent,
+    MemoryOnly,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseConfig {
+    pub name: String,
+    pub data_dir: PathBuf,
+    // How far saves go to get table files onto the disk
+    pub sync: SyncMode,
+    // Row history entries kept per table
+    pub history_limit: usize,
+    // Key for MaskPolicy::Hash. Never saved with the database, so set it on every open.
+    pub mask_key: Vec<u8>,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            name: "default".to_string(),
+            data_dir: PathBuf::from(DEFAULT_DATA_DIR),
+            sync: SyncMode::default(),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            mask_key: vec![],
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn new(name: &str, data_dir: impl Into<PathBuf>) -> Self {
+        DatabaseConfig {
+            name: name.to_string(),
+            data_dir: data_dir.into(),
+            ..DatabaseConfig::default()
+        }
+    }
+}
+
+impl Database {
+    pub fn with_config(config: DatabaseConfig) -> Self {
+        Database {
+            config,
+            ..Database::new()
+        }
+    }
+
+    // Inside a transaction the table is created as part of it, and a rollback takes it away
+    pub fn create_table(&mut self, mut table: Table, durability: Durability) -> Result<(), DbError> {
+        if self.tables.contains_key(&table.name) {
+            return Err(DbError::SchemaError(format!("Table '{}' already exists", table.name)));
+        }
+        if self.views.contains_key(&table.name) {
+            return Err(DbError::SchemaError(format!("'{}' is already the name of a view", table.name)));
+        }
+        // CHECKs are resolved against the table they are defined on
+        table.columns.iter().try_for_each(|column| table.validate_checks(column))?;
+        if let Some(transaction) = &mut self.transaction {
+            table.begin_transaction()?;
+            transaction.tables.push(TableChange::Created(table.name.clone()));
+        }
+        if durability == Durability::MemoryOnly {
+            self.memory_only.insert(table.name.clone());
+        }
+        self.tables.insert(table.name.clone(), table);
+        Ok(())
+    }
+
+    // Forgets the table along with its engine, policies, history and cached plans.
+    // Files already saved to the data directory are left alone. Inside a transaction all
+    // of it is kept until commit, so a rollback can bring the table back.
+    pub fn drop_table(&mut self, table_name: &str) -> Result<Table, DbError> {
+        let table = self
+            .tables
+            .remove(table_name)
+            .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found", table_name)))?;
+        let engine = self.engines.remove(table_name);
+        let memory_only = self.memory_only.remove(table_name);
+        let policies = self.policies.remove(table_name);
+        let history = self.history.tables.remove(table_name);
+        self.plan_cache.invalidate_table(table_name);
+
+        match &mut self.transaction {
+            Some(transaction) => {
+                let mut dropped = table.clone();
+                dropped.transaction_backup = None;
+                transaction.tables.push(TableChange::Dropped(Box::new(DroppedTable {
+                    table,
+                    engine,
+                    memory_only,
+                    policies,
+                    history,
+                })));
+                Ok(dropped)
+            }
+            None => Ok(table),
+        }
+    }
+
+    // Moves the table and everything kept under its name, and points foreign keys in
+    // other tables at the new name. Refused while a view reads the table, since the view's
+    // column references would go stale, and inside a transaction, whose log is by name.
+    pub fn rename_table(&mut self, old_name: &str, new_name: &str) -> Result<(), DbError> {
+        if !self.tables.contains_key(old_name) {
+            return Err(DbError::NotFound(format!("Table '{}' not found", old_name)));
+        }
+        if self.tables.contains_key(new_name) || self.views.contains_key(new_name) {
+            return Err(DbError::SchemaError(format!("'{}' already exists", new_name)));
+        }
+        if let Some((view, _)) = self.views.iter().find(|(_, v)| v.tables().any(|t| t == old_name)) {
+            return Err(DbError::SchemaError(format!(
+                "View '{}' reads table '{}'; drop it before renaming",
+                view, old_name
+            )));
+        }
+        if self.in_transaction() {
+            return Err(DbError::TransactionError("Tables cannot be renamed inside a transaction".to_string()));
+        }
+
+        let mut table = self.tables.remove(old_name).unwrap();
+        table.name = new_name.to_string();
+        self.tables.insert(new_name.to_string(), table);
+        if let Some(engine) = self.engines.remove(old_name) {
+            self.engines.insert(new_name.to_string(), engine);
+        }
+        if self.memory_only.remove(old_name) {
+            self.memory_only.insert(new_name.to_string());
+        }
+        if let Some(policies) = self.policies.remove(old_name) {
+            self.policies.insert(new_name.to_string(), policies);
+        }
+        if let Some(history) = self.history.tables.remove(old_name) {
+            self.history.tables.insert(new_name.to_string(), history);
+        }
+        self.plan_cache.invalidate_table(old_name);
+
+        let old_fk = Options::FK(old_name.to_string());
+        for table in self.tables.values_mut() {
+            for option in table.columns.iter_mut().flat_map(|c| c.options.iter_mut()) {
+                if *option == old_fk {
+                    *option = Options::FK(new_name.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Runs `change` on the table as one step, for changes Database has no method of its
+    // own for, such as adding a column or an index. The table's engine is handed every row
+    // afterwards, since the change may have rewritten them all, and if either fails the
+    // table is put back. Refused inside a transaction, whose changes reach engines row by
+    // row.
+    pub fn alter_table<R>(
+        &mut self,
+        table_name: &str,
+        change: impl FnOnce(&mut Table) -> Result<R, DbError>,
+    ) -> Result<R, DbError> {
+        if self.in_transaction() {
+            return Err(DbError::TransactionError("Tables cannot be altered inside a transaction".to_string()));
+        }
+        let table = self
+            .tables
+            .get_mut(table_name)
+            .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found", table_name)))?;
+
+        let before = self.history.tables.contains_key(table_name).then(|| table.clone());
+        table.begin_transaction()?;
+        let result = change(table).and_then(|result| {
+            // A column a row policy depends on can't be renamed or dropped out from under it
+            for policy in self.policies.get(table_name).into_iter().flatten() {
+                policy.check_columns(table).map_err(|e| {
+                    DbError::SchemaError(format!("Table '{}' has a row policy that depends on it: {}", table_name, e))
+                })?;
+            }
+            if let Some(engine) = self.engines.get_mut(table_name) {
+                engine.replace(&table.rows)?;
+            }
+            Ok(result)
+        });
+        match result {
+            Ok(_) => table.commit_transaction()?,
+            Err(_) => table.rollback_transaction()?,
+        }
+        self.plan_cache.invalidate_table(table_name);
+        if let (Ok(_), Some(before)) = (&result, before) {
+            self.record([(table_name.to_string(), Change::Table(Box::new(before)))]);
+        }
+        result
+    }
+
+    // CREATE TABLE dst AS src, keeping the column definitions, constraints and indexes.
+    // Without data, identity columns start over. The copy has the source's durability
+    // but none of its engine, policies or history.
+    pub fn copy_table(&mut self, src: &str, dst: &str, with_data: bool) -> Result<(), DbError> {
+        let source = self
+            .tables
+            .get(src)
+            .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found", src)))?;
+        let mut copy = source.clone();
+        copy.name = dst.to_string();
+        copy.transaction_backup = None;
+        if !with_data {
+            copy.rows.clear();
+            copy.sequences.clear();
+            copy.rebuild_all_indexes();
+        }
+        let durability = self.durability(src).unwrap_or(Durability::Persistent);
+        self.create_table(copy, durability)
+    }
+
+    pub fn durability(&self, table_name: &str) -> Option<Durability> {
+        if !self.tables.contains_key(table_name) {
+            None
+        } else if self.memory_only.contains(table_name) {
+            Some(Durability::MemoryOnly)
+        } else {
+            Some(Durability::Persistent)
+        }
+    }
+
+    pub fn save_table(&self, table_name: &str) -> Result<(), DbError> {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found", table_name)))?;
+        table.save_to_dir_synced(&self.config.data_dir, &self.config.name, self.config.sync)
+    }
+
+    // Memory-only tables are skipped
+    pub fn save_all(&self) -> Result<(), DbError> {
+        let mut names: Vec<&String> = self
+            .tables
+            .keys()
+            .filter(|name| !self.memory_only.contains(*name))
+            .collect();
+        names.sort();
+        for name in names {
+            self.save_table(name)?;
+        }
+        Ok(())
+    }
+
+    pub fn load_table(
+        &mut self,
+        table_name: &str,
+        columns: Vec<Column>,
+        primary_key: Option<Vec<String>>,
+    ) -> Result<(), DbError> {
+        let table = Table::load_from_dir(
+            &self.config.data_dir,
+            &self.config.name,
+            table_name,
+            columns,
+            primary_key,
+        )?;
+        self.tables.insert(table_name.to_string(), table);
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+use crate::database::validators::Database;
+use crate::table::data::{Column, DataType, Options, Table};
+
+// What would turn one database's schema into another's. Columns are matched by name,
+// so a renamed column shows up as one removed and one added.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaDiff {
+    // Tables only the other database has, without their rows
+    pub added_tables: Vec<Table>,
+    pub removed_tables: Vec<String>,
+    pub changed_tables: Vec<TableDiff>,
+}
+
+pub type PrimaryKey = Option<Vec<String>>;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TableDiff {
+    pub table: String,
+    pub added_columns: Vec<Column>,
+    pub removed_columns: Vec<String>,
+    pub changed_columns: Vec<ColumnDiff>,
+    // The key before and after, when it changed
+    pub primary_key: Option<(PrimaryKey, PrimaryKey)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ColumnDiff {
+    pub column: String,
+    pub datatype: Option<(DataType, DataType)>,
+    pub added_options: Vec<Options>,
+    pub removed_options: Vec<Options>,
+}
+
+#[derive(Debug, Clone)]
+pub enum AlterOp {
+    CreateTable(Table),
+    DropTable(String),
+    AddColumn { table: String, column: Column },
+    DropColumn { table: String, column: String },
+    SetType { table: String, column: String, datatype: DataType },
+    AddOption { table: String, column: String, option: Options },
+    DropOption { table: String, column: String, option: Options },
+    // None drops the key
+    SetPrimaryKey { table: String, key: Option<Vec<String>> },
+}
+
+impl Database {
+    // Everything is listed in name order, so the same two schemas always give the same diff
+    pub fn diff_schema(&self, other: &Database) -> SchemaDiff {
+        let mut names: Vec<&String> = self.tables.keys().chain(other.tables.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        let mut diff = SchemaDiff::default();
+        for name in names {
+            match (self.tables.get(name), other.tables.get(name)) {
+                (Some(_), None) => diff.removed_tables.push(name.clone()),
+                (None, Some(table)) => diff.added_tables.push(schema_only(table)),
+                (Some(from), Some(to)) => {
+                    let table_diff = diff_table(from, to);
+                    if !table_diff.is_empty() {
+                        diff.changed_tables.push(table_diff);
+                    }
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+        diff
+    }
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_tables.is_empty() && self.removed_tables.is_empty() && self.changed_tables.is_empty()
+    }
+
+    // The diff as steps to run in order. New tables come first, each after the tables its
+    // foreign keys reference, so foreign keys added later can point at them too; within a
+    // table, the old key and options go before the columns they sit on, and the new ones
+    // are added once their columns exist.
+    pub fn alter_ops(&self) -> Vec<AlterOp> {
+        let mut ops: Vec<AlterOp> =
+            dependency_order(&self.added_tables).into_iter().cloned().map(AlterOp::CreateTable).collect();
+        for table_diff in &self.changed_tables {
+            ops.extend(table_diff.alter_ops());
+        }
+        ops.extend(self.removed_tables.iter().cloned().map(AlterOp::DropTable));
+        ops
+    }
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_columns.is_empty()
+            && self.removed_columns.is_empty()
+            && self.changed_columns.is_empty()
+            && self.primary_key.is_none()
+    }
+
+    fn alter_ops(&self) -> Vec<AlterOp> {
+        let table = || self.table.clone();
+        let mut ops = vec![];
+        if let Some((Some(_), _)) = &self.primary_key {
+            ops.push(AlterOp::SetPrimaryKey { table: table(), key: None });
+        }
+        for change in &self.changed_columns {
+            ops.extend(change.removed_options.iter().map(|option| AlterOp::DropOption {
+                table: table(),
+                column: change.column.clone(),
+                option: option.clone(),
+            }));
+        }
+        ops.extend(self.removed_columns.iter().map(|column| AlterOp::DropColumn {
+            table: table(),
+            column: column.clone(),
+        }));
+        ops.extend(self.added_columns.iter().map(|column| AlterOp::AddColumn {
+            table: table(),
+            column: column.clone(),
+        }));
+        for change in &self.changed_columns {
+            if let Some((_, datatype)) = &change.datatype {
+                ops.push(AlterOp::SetType {
+                    table: table(),
+                    column: change.column.clone(),
+                    datatype: datatype.clone(),
+                });
+            }
+            ops.extend(change.added_options.iter().map(|option| AlterOp::AddOption {
+                table: table(),
+                column: change.column.clone(),
+                option: option.clone(),
+            }));
+        }
+        if let Some((_, Some(key))) = &self.primary_key {
+            ops.push(AlterOp::SetPrimaryKey {
+                table: table(),
+                key: Some(key.clone()),
+            });
+        }
+        ops
+    }
+}
+
+fn schema_only(table: &Table) -> Table {
+    Table::new(&table.name, table.columns.clone(), table.primary_key.clone())
+}
+
+fn diff_table(from: &Table, to: &Table) -> TableDiff {
+    let mut diff = TableDiff {
+        table: from.name.clone(),
+        ..TableDiff::default()
+    };
+    for column in &from.columns {
+        match to.columns.iter().find(|c| c.name == column.name) {
+            None => diff.removed_columns.push(column.name.clone()),
+            Some(target) => {
+                let change = ColumnDiff {
+                    column: column.name.clone(),
+                    datatype: (column.datatype != target.datatype)
+                        .then(|| (column.datatype.clone(), target.datatype.clone())),
+                    added_options: missing_from(&target.options, &column.options),
+                    removed_options: missing_from(&column.options, &target.options),
+                };
+                if change.datatype.is_some() || !change.added_options.is_empty() || !change.removed_options.is_empty() {
+                    diff.changed_columns.push(change);
+                }
+            }
+        }
+    }
+    diff.added_columns = to
+        .columns
+        .iter()
+        .filter(|c| !from.columns.iter().any(|existing| existing.name == c.name))
+        .cloned()
+        .collect();
+    if from.primary_key != to.primary_key {
+        diff.primary_key = Some((from.primary_key.clone(), to.primary_key.clone()));
+    }
+    diff
+}
+
+// The options in `options` that `other` lacks; order on a column doesn't matter
+fn missing_from(options: &[Options], other: &[Options]) -> Vec<Options> {
+    options.iter().filter(|o| !other.contains(o)).cloned().collect()
+}
+
+// Sorted by name, except that a table comes after the tables its foreign keys reference.
+// Tables in a reference cycle can't all be ordered, and are taken as they come.
+pub(crate) fn dependency_order<'a>(tables: impl IntoIterator<Item = &'a Table>) -> Vec<&'a Table> {
+    let mut pending: Vec<&Table> = tables.into_iter().collect();
+    pending.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut ordered: Vec<&Table> = vec![];
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .position(|t| references(t).all(|target| target == t.name || !pending.iter().any(|p| p.name == target)))
+            .unwrap_or(0);
+        ordered.push(pending.remove(ready));
+    }
+    ordered
+}
+
+fn references(table: &Table) -> impl Iterator<Item = &str> {
+    table.columns.iter().flat_map(|c| &c.options).filter_map(|opt| match opt {
+        Options::FK(target) => Some(target.as_str()),
+        _ => None,
+    })
+}
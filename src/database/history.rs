@@ -0,0 +1,191 @@
+use crate::database::validators::Database;
+pub use crate::table::data::RowChange;
+use crate::table::data::{Table, Value};
+use chrono::{Local, NaiveDateTime};
+use std::collections::HashMap;
+
+// Entries kept per table by default; older transactions are forgotten whole
+pub const DEFAULT_HISTORY_LIMIT: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub txn: u64,
+    pub committed_at: NaiveDateTime,
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Row(RowChange),
+    // The table as it stood before alter_table changed it
+    Table(Box<Table>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableHistory {
+    pub since_txn: u64,
+    pub since: NaiveDateTime,
+    pub entries: Vec<HistoryEntry>,
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    pub tables: HashMap<String, TableHistory>,
+    pub last_txn: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AsOf {
+    Txn(u64),
+    Time(NaiveDateTime),
+}
+
+impl Database {
+    // Starts keeping row history for `table_name` so earlier states can be read back with
+    // table_as_of. Writes made through Database, sessions, batches, vacuum and alter_table
+    // are recorded, up to config.history_limit entries. Writes made to `tables` directly
+    // are not. Rows are found by value, so history reads back around such a write, but
+    // fails once a row it needs is gone.
+    pub fn retain_history(&mut self, table_name: &str) -> Result<(), String> {
+        if !self.tables.contains_key(table_name) {
+            return Err(format!("Table '{}' not found", table_name));
+        }
+        let since_txn = self.history.last_txn;
+        self.history
+            .tables
+            .entry(table_name.to_string())
+            .or_insert_with(|| TableHistory {
+                since_txn,
+                since: Local::now().naive_local(),
+                entries: Vec::new(),
+            });
+        Ok(())
+    }
+
+    pub fn drop_history(&mut self, table_name: &str) {
+        self.history.tables.remove(table_name);
+    }
+
+    // Id of the most recent write transaction, usable with AsOf::Txn
+    pub fn last_txn(&self) -> u64 {
+        self.history.last_txn
+    }
+
+    // A copy of the table as it stood after transaction `point` committed, or at time `point`
+    pub fn table_as_of(&self, table_name: &str, point: AsOf) -> Result<Table, String> {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| format!("Table '{}' not found", table_name))?;
+        let history = self
+            .history
+            .tables
+            .get(table_name)
+            .ok_or_else(|| format!("History is not retained for table '{}'", table_name))?;
+
+        let too_early = match point {
+            AsOf::Txn(txn) => txn < history.since_txn,
+            AsOf::Time(at) => at < history.since,
+        };
+        if too_early {
+            return Err(format!(
+                "History for table '{}' starts at transaction {} ({})",
+                table_name, history.since_txn, history.since
+            ));
+        }
+
+        let mut base = table;
+        let mut rows = table.rows.clone();
+        let later = history.entries.iter().rev().take_while(|entry| match point {
+            AsOf::Txn(txn) => entry.txn > txn,
+            AsOf::Time(at) => entry.committed_at > at,
+        });
+        for entry in later {
+            match &entry.change {
+                Change::Row(change) => undo(change, &mut rows).ok_or_else(|| {
+                    format!(
+                        "Table '{}' was changed outside its history after transaction {}",
+                        table_name, entry.txn
+                    )
+                })?,
+                Change::Table(before) => {
+                    base = before;
+                    rows = before.rows.clone();
+                }
+            }
+        }
+
+        let mut snapshot = base.clone();
+        snapshot.rows = rows;
+        snapshot.transaction_backup = None;
+        snapshot.rebuild_all_indexes();
+        Ok(snapshot)
+    }
+
+    // Records the changes of one committed transaction under a new transaction id
+    pub(crate) fn record_changes(&mut self, changes: Vec<(String, RowChange)>) {
+        self.record(changes.into_iter().map(|(name, change)| (name, Change::Row(change))));
+    }
+
+    pub(crate) fn record(&mut self, changes: impl IntoIterator<Item = (String, Change)>) {
+        self.history.last_txn += 1;
+        let txn = self.history.last_txn;
+        let committed_at = Local::now().naive_local();
+        let limit = self.config.history_limit;
+
+        for (table_name, change) in changes {
+            if let Some(history) = self.history.tables.get_mut(&table_name) {
+                history.entries.push(HistoryEntry {
+                    txn,
+                    committed_at,
+                    change,
+                });
+                history.trim(limit);
+            }
+        }
+    }
+}
+
+impl TableHistory {
+    // Drops the oldest transactions until at most `limit` entries are left. Whole
+    // transactions go, so every point still covered can be rebuilt.
+    fn trim(&mut self, limit: usize) {
+        let Some(excess) = self.entries.len().checked_sub(limit).filter(|&n| n > 0) else {
+            return;
+        };
+        let last = &self.entries[excess - 1];
+        let (txn, committed_at) = (last.txn, last.committed_at);
+        let end = self.entries.partition_point(|entry| entry.txn <= txn);
+        self.entries.drain(..end);
+        self.since_txn = txn;
+        self.since = committed_at;
+    }
+}
+
+// Undoes `change`, finding each row by its values and taking the recorded position as
+// a hint, so a row that has moved is still found. None when a row isn't there at all.
+fn undo(change: &RowChange, rows: &mut Vec<Vec<Value>>) -> Option<()> {
+    match change {
+        RowChange::Insert { row } => {
+            let i = find(rows, row, rows.len().checked_sub(1))?;
+            rows.remove(i);
+        }
+        RowChange::Update { position, before, after } => {
+            let i = find(rows, after, Some(*position))?;
+            rows[i] = before.clone();
+        }
+        RowChange::Delete { positions, rows: removed } => {
+            for (&position, row) in positions.iter().zip(removed) {
+                rows.insert(position.min(rows.len()), row.clone());
+            }
+        }
+    }
+    Some(())
+}
+
+fn find(rows: &[Vec<Value>], row: &[Value], hint: Option<usize>) -> Option<usize> {
+    match hint {
+        Some(i) if rows.get(i).is_some_and(|r| r == row) => Some(i),
+        _ => rows.iter().rposition(|r| r == row),
+    }
+}
@@ -0,0 +1,159 @@
+use crate::database::validators::Database;
+use crate::table::data::{Column, DataType, DefaultExpr, IndexType, Options, Table, Value};
+
+// Snapshots of the catalog as ordinary tables, so the schema can be filtered, joined and
+// sorted like any data. They are built on each call and don't follow later changes.
+#[derive(Debug, Clone)]
+pub struct InformationSchema {
+    // table_name, table_type, row_count, engine
+    pub tables: Table,
+    // table_name, column_name, ordinal_position, data_type, is_nullable, column_default
+    pub columns: Table,
+    // table_name, column_name, index_type
+    pub indexes: Table,
+    // table_name, column_name, constraint_type, detail
+    pub constraints: Table,
+}
+
+impl Database {
+    // Views are listed in `tables` only; their columns aren't known without running them
+    pub fn information_schema(&self) -> InformationSchema {
+        let mut names: Vec<&String> = self.tables.keys().collect();
+        names.sort();
+        let tables: Vec<&Table> = names.iter().map(|name| &self.tables[*name]).collect();
+
+        let mut listing: Vec<Vec<Value>> = tables
+            .iter()
+            .map(|table| {
+                vec![
+                    text(&table.name),
+                    text("BASE TABLE"),
+                    Value::BigInt(table.live_rows().count() as i64),
+                    self.engine_name(&table.name).map_or(Value::Null, text),
+                ]
+            })
+            .collect();
+        let mut views: Vec<&String> = self.views.keys().collect();
+        views.sort();
+        listing.extend(views.into_iter().map(|name| vec![text(name), text("VIEW"), Value::Null, Value::Null]));
+
+        let mut columns = vec![];
+        let mut indexes = vec![];
+        let mut constraints = vec![];
+        for table in tables {
+            let key = table.primary_key.clone().unwrap_or_default();
+            for (i, column) in table.columns.iter().enumerate() {
+                let nullable = !key.contains(&column.name) && !column.options.contains(&Options::NotNull);
+                columns.push(vec![
+                    text(&table.name),
+                    text(&column.name),
+                    Value::Int(i as i32 + 1),
+                    text(&format!("{:?}", column.datatype)),
+                    Value::Boolean(nullable),
+                    column_default(column),
+                ]);
+
+                if key.contains(&column.name) {
+                    constraints.push(constraint(table, column, "PRIMARY KEY", Some(&key.join(", "))));
+                }
+                for option in &column.options {
+                    let row = match option {
+                        Options::Unique => constraint(table, column, "UNIQUE", None),
+                        Options::NotNull => constraint(table, column, "NOT NULL", None),
+                        Options::FK(target) => constraint(table, column, "FOREIGN KEY", Some(target)),
+                        Options::Check(expr) => constraint(table, column, "CHECK", Some(&expr.to_string())),
+                        _ => continue,
+                    };
+                    constraints.push(row);
+                }
+            }
+
+            let mut indexed: Vec<(&String, &IndexType)> = table.indexes.iter().collect();
+            indexed.sort_by_key(|(column, _)| *column);
+            indexes.extend(indexed.into_iter().map(|(column, index)| {
+                let kind = match index {
+                    IndexType::Hash(_) => "HASH",
+                    IndexType::BTree(_) => "BTREE",
+                    IndexType::Grid(_) => "GRID",
+                    IndexType::Disk(_) => "DISK",
+                };
+                vec![text(&table.name), text(column), text(kind)]
+            }));
+        }
+
+        InformationSchema {
+            tables: synthetic(
+                "tables",
+                &[
+                    ("table_name", DataType::Text),
+                    ("table_type", DataType::Text),
+                    ("row_count", DataType::BigInt),
+                    ("engine", DataType::Text),
+                ],
+                listing,
+            ),
+            columns: synthetic(
+                "columns",
+                &[
+                    ("table_name", DataType::Text),
+                    ("column_name", DataType::Text),
+                    ("ordinal_position", DataType::Int),
+                    ("data_type", DataType::Text),
+                    ("is_nullable", DataType::Boolean),
+                    ("column_default", DataType::Text),
+                ],
+                columns,
+            ),
+            indexes: synthetic(
+                "indexes",
+                &[("table_name", DataType::Text), ("column_name", DataType::Text), ("index_type", DataType::Text)],
+                indexes,
+            ),
+            constraints: synthetic(
+                "constraints",
+                &[
+                    ("table_name", DataType::Text),
+                    ("column_name", DataType::Text),
+                    ("constraint_type", DataType::Text),
+                    ("detail", DataType::Text),
+                ],
+                constraints,
+            ),
+        }
+    }
+}
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+fn constraint(table: &Table, column: &Column, kind: &str, detail: Option<&str>) -> Vec<Value> {
+    vec![text(&table.name), text(&column.name), text(kind), detail.map_or(Value::Null, text)]
+}
+
+fn column_default(column: &Column) -> Value {
+    column
+        .options
+        .iter()
+        .find_map(|option| match option {
+            Options::Default(DefaultExpr::Literal(value)) => Some(text(&value.to_display_string())),
+            Options::Default(expr) => Some(text(&expr.to_string())),
+            _ => None,
+        })
+        .unwrap_or(Value::Null)
+}
+
+// Built straight from the rows, with no key, constraints or indexes
+fn synthetic(name: &str, columns: &[(&str, DataType)], rows: Vec<Vec<Value>>) -> Table {
+    let columns = columns
+        .iter()
+        .map(|(name, datatype)| Column {
+            name: name.to_string(),
+            datatype: datatype.clone(),
+            options: vec![],
+        })
+        .collect();
+    let mut table = Table::new(name, columns, None);
+    table.rows = rows;
+    table
+}
@@ -0,0 +1,179 @@
+use crate::database::config::Durability;
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::table::data::{Column, DataType, Table, Value};
+use std::fmt;
+
+// System table recording which migrations have run. It is an ordinary persistent
+// table, so it is saved and loaded along with the rest of the catalog.
+pub const MIGRATIONS_TABLE: &str = "_migrations";
+
+type MigrationStep = Box<dyn Fn(&mut Database) -> Result<(), DbError> + Send>;
+
+pub struct Migration {
+    pub version: i64,
+    pub name: String,
+    step: MigrationStep,
+}
+
+// Registered steps, kept sorted by version
+#[derive(Default)]
+pub struct Migrations {
+    steps: Vec<Migration>,
+}
+
+impl fmt::Debug for Migrations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.steps.iter().map(|m| (m.version, &m.name)))
+            .finish()
+    }
+}
+
+impl Migrations {
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn versions(&self) -> Vec<i64> {
+        self.steps.iter().map(|m| m.version).collect()
+    }
+}
+
+impl Database {
+    // Steps may be registered in any order; they always run by ascending version
+    pub fn add_migration<F>(&mut self, version: i64, name: &str, step: F) -> Result<(), DbError>
+    where
+        F: Fn(&mut Database) -> Result<(), DbError> + Send + 'static,
+    {
+        let at = match self.migrations.steps.binary_search_by_key(&version, |m| m.version) {
+            Ok(_) => {
+                return Err(DbError::InvalidArgument(format!(
+                    "Migration version {} is already registered",
+                    version
+                )))
+            }
+            Err(at) => at,
+        };
+        self.migrations.steps.insert(
+            at,
+            Migration {
+                version,
+                name: name.to_string(),
+                step: Box::new(step),
+            },
+        );
+        Ok(())
+    }
+
+    // Versions recorded in the system table, ascending
+    pub fn applied_migrations(&self) -> Vec<i64> {
+        let mut versions: Vec<i64> = self
+            .tables
+            .get(MIGRATIONS_TABLE)
+            .map(|table| {
+                table
+                    .live_rows()
+                    .filter_map(|row| match row[0] {
+                        Value::BigInt(version) => Some(version),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        versions.sort_unstable();
+        versions
+    }
+
+    pub fn pending_migrations(&self) -> Vec<i64> {
+        let applied = self.applied_migrations();
+        self.migrations
+            .versions()
+            .into_iter()
+            .filter(|v| applied.binary_search(v).is_err())
+            .collect()
+    }
+
+    // Runs each pending step in its own transaction and records its version in the
+    // same transaction, returning the versions applied. The first failing step is
+    // rolled back, along with any tables or views it created or dropped, and the
+    // steps after it are not run; the steps before it stay applied.
+    pub fn migrate(&mut self) -> Result<Vec<i64>, DbError> {
+        if self.in_transaction() {
+            return Err(DbError::TransactionError(
+                "Migrations cannot run inside a transaction".into(),
+            ));
+        }
+        if !self.tables.contains_key(MIGRATIONS_TABLE) {
+            self.create_table(migrations_table(), Durability::Persistent)?;
+        }
+
+        let pending = self.pending_migrations();
+        // The steps borrow the database mutably, so they are taken out while they run
+        let migrations = std::mem::take(&mut self.migrations);
+        let result = self.run_migrations(&migrations, &pending);
+        self.migrations = migrations;
+        result
+    }
+
+    fn run_migrations(&mut self, migrations: &Migrations, pending: &[i64]) -> Result<Vec<i64>, DbError> {
+        let mut applied = vec![];
+        for migration in migrations.steps.iter().filter(|m| pending.contains(&m.version)) {
+            let tables = self.tables.clone();
+            let views = self.views.clone();
+            let memory_only = self.memory_only.clone();
+
+            self.begin_transaction()?;
+            let result = (migration.step)(self).and_then(|_| {
+                if !self.in_transaction() {
+                    return Err(DbError::TransactionError(format!(
+                        "Migration {} ended the transaction it runs in",
+                        migration.version
+                    )));
+                }
+                self.insert(
+                    MIGRATIONS_TABLE,
+                    vec![Value::BigInt(migration.version), Value::Text(migration.name.clone())],
+                )?;
+                Ok(())
+            });
+
+            match result {
+                Ok(()) => self.commit()?,
+                Err(e) => {
+                    if self.in_transaction() {
+                        self.rollback()?;
+                    }
+                    self.engines.retain(|name, _| tables.contains_key(name));
+                    self.tables = tables;
+                    self.views = views;
+                    self.memory_only = memory_only;
+                    self.plan_cache.clear();
+                    return Err(e);
+                }
+            }
+            applied.push(migration.version);
+        }
+        Ok(applied)
+    }
+}
+
+fn migrations_table() -> Table {
+    let columns = vec![
+        Column {
+            name: "version".to_string(),
+            datatype: DataType::BigInt,
+            options: vec![],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Text,
+            options: vec![],
+        },
+    ];
+    Table::new(MIGRATIONS_TABLE, columns, Some(vec!["version".to_string()]))
+}
@@ -1 +1,20 @@
 pub mod validators;
+pub mod check;
+pub mod backup;
+pub mod shell;
+pub mod session;
+pub mod checkpoint;
+
+pub mod plan_cache;
+pub mod config;
+pub mod storage;
+pub mod batch;
+pub mod history;
+pub mod catalog;
+pub mod transaction;
+pub mod cascade;
+pub mod views;
+pub mod query;
+pub mod migrations;
+pub mod diff;
+pub mod information_schema;
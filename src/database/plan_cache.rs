@@ -0,0 +1,173 @@
+use crate::database::validators::Database;
+use crate::table::data::{FilterExpr, IndexType, Plan, Table, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::hash::{Hash, Hasher};
+use std::mem::{discriminant, Discriminant};
+
+// Words normalize_query folds to lowercase; everything else keeps its case
+const KEYWORDS: [&str; 19] = [
+    "SELECT", "FROM", "WHERE", "AND", "OR", "NOT", "IN", "IS", "NULL", "BETWEEN", "WITHIN", "BOX", "KM", "OF",
+    "TRUE", "FALSE", "ORDER", "BY", "LIMIT",
+];
+
+// The normalized text and the type of each value it compares with, in order, since
+// `id = 3` and `id = 3.0` print alike but may not plan alike
+type CacheKey = (String, Vec<Discriminant<Value>>);
+
+#[derive(Debug, Clone)]
+struct CachedPlan {
+    table: String,
+    schema: u64,
+    plan: Plan,
+}
+
+#[derive(Debug, Default)]
+pub struct PlanCache {
+    entries: HashMap<CacheKey, CachedPlan>,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl PlanCache {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, text: &str) -> bool {
+        let text = normalize_query(text);
+        self.entries.keys().any(|(key, _)| *key == text)
+    }
+
+    pub fn invalidate_table(&mut self, table_name: &str) {
+        self.entries.retain(|_, entry| entry.table != table_name);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl Database {
+    // Plans are reused until the table's columns, key or indexes change
+    pub fn prepare(&mut self, table_name: &str, expr: Option<&FilterExpr>) -> Result<Plan, String> {
+        let table = match self.tables.get(table_name) {
+            Some(table) => table,
+            None => {
+                self.plan_cache.invalidate_table(table_name);
+                return Err(format!("Table '{}' not found", table_name));
+            }
+        };
+
+        let key = (normalize_query(&query_text(table_name, expr)), expr.map(value_types).unwrap_or_default());
+        let schema = schema_fingerprint(table);
+
+        if let Some(entry) = self.plan_cache.entries.get(&key) {
+            if entry.schema == schema {
+                self.plan_cache.hits += 1;
+                return Ok(entry.plan.clone());
+            }
+        }
+
+        self.plan_cache.misses += 1;
+        let plan = table.plan(expr);
+        self.plan_cache.entries.insert(
+            key,
+            CachedPlan {
+                table: table_name.to_string(),
+                schema,
+                plan: plan.clone(),
+            },
+        );
+        Ok(plan)
+    }
+}
+
+pub fn query_text(table_name: &str, expr: Option<&FilterExpr>) -> String {
+    match expr {
+        Some(expr) => format!("SELECT * FROM {} WHERE {}", table_name, expr),
+        None => format!("SELECT * FROM {}", table_name),
+    }
+}
+
+fn value_types(expr: &FilterExpr) -> Vec<Discriminant<Value>> {
+    let mut types = vec![];
+    let Ok(_) = expr.try_map_values(&mut |value| {
+        types.push(discriminant(value));
+        Ok::<_, Infallible>(value.clone())
+    });
+    types
+}
+
+// Collapses whitespace outside single-quoted literals and lowercases keywords. Names
+// keep their case, as tables and columns are looked up by exact name.
+pub fn normalize_query(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut word = String::new();
+    let mut in_quote = false;
+    let mut pending_space = false;
+
+    let end_word = |out: &mut String, word: &mut String| {
+        if KEYWORDS.contains(&word.to_ascii_uppercase().as_str()) {
+            out.push_str(&word.to_ascii_lowercase());
+        } else {
+            out.push_str(word);
+        }
+        word.clear();
+    };
+
+    for c in text.trim().trim_end_matches(';').trim_end().chars() {
+        if !in_quote && (c.is_alphanumeric() || c == '_') {
+            if pending_space {
+                out.push(' ');
+                pending_space = false;
+            }
+            word.push(c);
+            continue;
+        }
+        end_word(&mut out, &mut word);
+        if c == '\'' {
+            in_quote = !in_quote;
+        }
+        if !in_quote && c.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        if pending_space {
+            out.push(' ');
+            pending_space = false;
+        }
+        out.push(c);
+    }
+    end_word(&mut out, &mut word);
+    out
+}
+
+fn schema_fingerprint(table: &Table) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    table.columns.hash(&mut hasher);
+    table.primary_key.hash(&mut hasher);
+
+    let mut indexes: Vec<(&String, &str)> = table
+        .indexes
+        .iter()
+        .map(|(name, index)| {
+            let kind = match index {
+                IndexType::Hash(_) => "hash",
+                IndexType::BTree(_) => "btree",
+                IndexType::Grid(_) => "grid",
+                IndexType::Disk(_) => "disk",
+            };
+            (name, kind)
+        })
+        .collect();
+    indexes.sort();
+    indexes.hash(&mut hasher);
+
+    hasher.finish()
+}
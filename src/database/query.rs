@@ -0,0 +1,138 @@
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::table::data::{FilterExpr, Options, OrderBy, QuerySpec, Table};
+
+impl QuerySpec {
+    pub fn new() -> Self {
+        QuerySpec::default()
+    }
+
+    pub fn join(mut self, table: &str) -> Self {
+        self.joins.push(table.to_string());
+        self
+    }
+
+    // Filtering more than once keeps the rows matching every filter
+    pub fn filter(mut self, expr: FilterExpr) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(existing) => existing.and(expr),
+            None => expr,
+        });
+        self
+    }
+
+    pub fn select(mut self, columns: &[&str]) -> Self {
+        self.columns = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    pub fn order_by(mut self, order: &[OrderBy]) -> Self {
+        self.order.extend(order.iter().cloned());
+        self
+    }
+
+    pub fn limit(mut self, count: usize) -> Self {
+        self.limit.count = Some(count);
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.limit.offset = offset;
+        self
+    }
+}
+
+impl Database {
+    // Runs `spec` against a table or, failing that, a view of that name. Joins follow
+    // foreign keys, which views do not have, so nothing can be joined to a view.
+    pub fn select(&self, table_name: &str, spec: QuerySpec) -> Result<Table, DbError> {
+        let view;
+        let base = match self.tables.get(table_name) {
+            Some(table) => table,
+            None if self.views.contains_key(table_name) => {
+                view = self.query_view(table_name)?;
+                &view
+            }
+            None => return Err(DbError::NotFound(format!("Table or view '{}' not found", table_name))),
+        };
+
+        let joined;
+        let source = if spec.joins.is_empty() {
+            base
+        } else {
+            let mut tables = vec![base];
+            let mut on = vec![];
+            for name in &spec.joins {
+                let table = self
+                    .tables
+                    .get(name)
+                    .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found", name)))?;
+                on.push(foreign_key_link(&tables, table)?);
+                tables.push(table);
+            }
+            let on: Vec<(&str, &str)> = on.iter().map(|(l, r)| (l.as_str(), r.as_str())).collect();
+            joined = Table::join_all(&tables, &on)?;
+            &joined
+        };
+
+        let mut query = source.query().order_by(&spec.order).offset(spec.limit.offset);
+        if let Some(expr) = spec.filter {
+            query = query.filter(expr);
+        }
+        if !spec.columns.is_empty() {
+            let columns: Vec<&str> = spec.columns.iter().map(String::as_str).collect();
+            query = query.select(&columns);
+        }
+        if let Some(count) = spec.limit.count {
+            query = query.limit(count);
+        }
+        query.run()
+    }
+
+    // INSERT INTO target SELECT ...: runs `spec` against `source` and inserts the result
+    // into `target` by column name, all rows or none
+    pub fn insert_into_select(&mut self, target: &str, source: &str, spec: QuerySpec) -> Result<usize, DbError> {
+        let selected = self.select(source, spec)?;
+        let rows = self
+            .tables
+            .get(target)
+            .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found", target)))?
+            .rows_from(&selected)?;
+        self.apply_insert_many(target, rows)
+    }
+}
+
+// The one foreign key between `table` and the tables already joined, as the pair of
+// columns to join on. The referenced side is its table's single-column primary key.
+fn foreign_key_link(joined: &[&Table], table: &Table) -> Result<(String, String), DbError> {
+    let mut links = vec![];
+    for other in joined {
+        links.extend(references(other, table));
+        links.extend(references(table, other));
+    }
+    match links.as_slice() {
+        [link] => Ok(link.clone()),
+        [] => Err(DbError::InvalidArgument(format!(
+            "No foreign key links '{}' to the tables before it",
+            table.name
+        ))),
+        _ => Err(DbError::InvalidArgument(format!(
+            "More than one foreign key links '{}' to the tables before it",
+            table.name
+        ))),
+    }
+}
+
+// (`child.column`, `parent.key`) for every column of `child` referencing `parent`
+fn references(child: &Table, parent: &Table) -> Vec<(String, String)> {
+    let key = match parent.primary_key.as_deref() {
+        Some([key]) => key,
+        _ => return vec![],
+    };
+    child
+        .columns
+        .iter()
+        .filter(|c| c.options.contains(&Options::FK(parent.name.clone())))
+        .map(|c| (format!("{}.{}", child.name, c.name), format!("{}.{}", parent.name, key)))
+        .collect()
+}
@@ -0,0 +1,240 @@
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::table::data::{FilterExpr, ResultSet, Table, Value};
+use std::collections::{HashMap, HashSet};
+
+pub const UNMASKED: &str = "unmasked";
+
+// Policies are filters a session's reads and writes of a table are held to. Some of a
+// policy's values may stand for a session attribute, bound when the policy is evaluated, so
+// Policy::attribute(FilterExpr::Eq, "tenant_id", "tenant_id") keeps each tenant to its own rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+    filter: FilterExpr,
+    // The attribute each of these value slots, counted in `try_map_values` order, stands for
+    attributes: Vec<(usize, String)>,
+}
+
+impl Policy {
+    // `column <op> attribute`, e.g. Policy::attribute(FilterExpr::Ge, "level", "clearance")
+    pub fn attribute(op: fn(String, Value) -> FilterExpr, column: &str, name: &str) -> Policy {
+        Policy {
+            filter: op(column.to_string(), Value::Null),
+            attributes: vec![(0, name.to_string())],
+        }
+    }
+
+    pub fn and(self, other: impl Into<Policy>) -> Policy {
+        self.combine(other.into(), FilterExpr::and)
+    }
+
+    pub fn or(self, other: impl Into<Policy>) -> Policy {
+        self.combine(other.into(), FilterExpr::or)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Policy {
+        Policy {
+            filter: self.filter.not(),
+            attributes: self.attributes,
+        }
+    }
+
+    fn combine(mut self, other: Policy, op: fn(FilterExpr, FilterExpr) -> FilterExpr) -> Policy {
+        let offset = value_slots(&self.filter);
+        self.attributes
+            .extend(other.attributes.into_iter().map(|(slot, name)| (slot + offset, name)));
+        Policy {
+            filter: op(self.filter, other.filter),
+            attributes: self.attributes,
+        }
+    }
+
+    pub fn check_columns(&self, table: &Table) -> Result<(), DbError> {
+        self.filter.check_columns(table)
+    }
+
+    // The filter with the session's attributes in place
+    fn bind(&self, attributes: &HashMap<String, Value>, table_name: &str) -> Result<FilterExpr, DbError> {
+        let mut slot = 0;
+        self.filter.try_map_values(&mut |value| {
+            let attribute = self.attributes.iter().find(|(s, _)| *s == slot);
+            slot += 1;
+            match attribute {
+                // A session without the attribute sees nothing rather than everything
+                Some((_, name)) => attributes.get(name).cloned().ok_or_else(|| {
+                    DbError::InvalidArgument(format!(
+                        "Session attribute '{}' is required for table '{}'",
+                        name, table_name
+                    ))
+                }),
+                None => Ok(value.clone()),
+            }
+        })
+    }
+}
+
+impl From<FilterExpr> for Policy {
+    fn from(filter: FilterExpr) -> Policy {
+        Policy {
+            filter,
+            attributes: vec![],
+        }
+    }
+}
+
+fn value_slots(filter: &FilterExpr) -> usize {
+    let mut count = 0;
+    let _ = filter.try_map_values::<()>(&mut |value| {
+        count += 1;
+        Ok(value.clone())
+    });
+    count
+}
+
+pub struct Session<'a> {
+    db: &'a mut Database,
+    pub attributes: HashMap<String, Value>,
+    pub privileges: HashSet<String>,
+}
+
+impl Database {
+    pub fn add_policy(&mut self, table_name: &str, policy: impl Into<Policy>) -> Result<(), DbError> {
+        let policy = policy.into();
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found", table_name)))?;
+        policy.check_columns(table)?;
+
+        self.policies
+            .entry(table_name.to_string())
+            .or_default()
+            .push(policy);
+        Ok(())
+    }
+
+    pub fn session(&mut self, attributes: HashMap<String, Value>) -> Session<'_> {
+        Session {
+            db: self,
+            attributes,
+            privileges: HashSet::new(),
+        }
+    }
+}
+
+impl Session<'_> {
+    pub fn grant(&mut self, privilege: &str) {
+        self.privileges.insert(privilege.to_string());
+    }
+
+    // Masked columns are only shown in the clear to sessions holding UNMASKED
+    pub fn select_result(&self, table_name: &str, expr: &FilterExpr) -> Result<ResultSet, DbError> {
+        let rows = self.visible_rows(table_name, expr)?;
+        let result = ResultSet::new(self.table(table_name)?.columns.clone(), rows);
+
+        if self.privileges.contains(UNMASKED) {
+            Ok(result)
+        } else {
+            Ok(result.apply_masks(&self.db.config.mask_key))
+        }
+    }
+
+    fn visible_rows(&self, table_name: &str, expr: &FilterExpr) -> Result<Vec<Vec<Value>>, DbError> {
+        let table = self.table(table_name)?;
+        let policies = self.bind_policies(table_name)?;
+        expr.check_columns(table)?;
+
+        let predicates: Vec<_> = policies.iter().map(|p| p.to_predicate(table)).collect();
+
+        Ok(table
+            .select_where_expr(expr)?
+            .into_iter()
+            .filter(|row| predicates.iter().all(|p| p(row)))
+            .cloned()
+            .collect())
+    }
+
+    pub fn insert(&mut self, table_name: &str, values: Vec<Value>) -> Result<(), DbError> {
+        let policies = self.bind_policies(table_name)?;
+        let table = self.table(table_name)?;
+
+        // New rows must stay visible to the session that wrote them
+        if let Some(policy) = policies.iter().find(|policy| !policy.to_predicate(table)(&values)) {
+            return Err(DbError::ConstraintViolation(format!("Row violates security policy {}", policy)));
+        }
+
+        self.db.apply_insert(table_name, values)
+    }
+
+    pub fn update(
+        &mut self,
+        table_name: &str,
+        expr: &FilterExpr,
+        updates: Vec<Option<Value>>,
+    ) -> Result<usize, DbError> {
+        let indices = self.matching_indices(table_name, expr)?;
+        let policies = self.bind_policies(table_name)?;
+        let table = self.table(table_name)?;
+
+        // Updates may not move a row out of the session's reach
+        let predicates: Vec<_> = policies.iter().map(|p| p.to_predicate(table)).collect();
+        for &i in &indices {
+            let row: Vec<Value> = table.rows[i]
+                .iter()
+                .enumerate()
+                .map(|(c, old)| updates.get(c).cloned().flatten().unwrap_or_else(|| old.clone()))
+                .collect();
+            if let Some(p) = predicates.iter().position(|p| !p(&row)) {
+                return Err(DbError::ConstraintViolation(format!(
+                    "Update violates security policy {}",
+                    policies[p]
+                )));
+            }
+        }
+
+        self.db.apply_update(table_name, &indices, &updates)
+    }
+
+    pub fn delete(&mut self, table_name: &str, expr: &FilterExpr) -> Result<usize, DbError> {
+        let indices = self.matching_indices(table_name, expr)?;
+        self.db.apply_delete(table_name, &indices)
+    }
+
+    fn table(&self, table_name: &str) -> Result<&Table, DbError> {
+        self.db
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found", table_name)))
+    }
+
+    // Each policy on the table with the session's attributes bound. A policy may have been
+    // left behind by a schema change made around `alter_table`, so its columns are checked.
+    fn bind_policies(&self, table_name: &str) -> Result<Vec<FilterExpr>, DbError> {
+        let table = self.table(table_name)?;
+        let policies = self.db.policies.get(table_name).map(Vec::as_slice).unwrap_or_default();
+        let mut bound = vec![];
+        for policy in policies {
+            policy.check_columns(table)?;
+            bound.push(policy.bind(&self.attributes, table_name)?);
+        }
+        Ok(bound)
+    }
+
+    fn matching_indices(&self, table_name: &str, expr: &FilterExpr) -> Result<Vec<usize>, DbError> {
+        let table = self.table(table_name)?;
+        let policies = self.bind_policies(table_name)?;
+        expr.check_columns(table)?;
+
+        let mut predicates = vec![expr.to_predicate(table)];
+        predicates.extend(policies.iter().map(|p| p.to_predicate(table)));
+
+        Ok(table
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| !table.is_expired(row) && predicates.iter().all(|p| p(row)))
+            .map(|(i, _)| i)
+            .collect())
+    }
+}
@@ -0,0 +1,245 @@
+use crate::database::validators::Database;
+use crate::sql::executor::QueryResult;
+use crate::table::data::{DefaultExpr, IndexType, Options, PagerOptions, ResultSet, Table};
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+
+pub struct Shell {
+    pub db: Database,
+    pub timing: bool,
+    pub pager: PagerOptions,
+}
+
+// What a command prints. Rows are paged when the shell runs interactively.
+enum Output {
+    Text(String),
+    Rows(ResultSet),
+}
+
+impl Shell {
+    pub fn new(db: Database) -> Self {
+        Shell {
+            db,
+            timing: false,
+            pager: PagerOptions::default(),
+        }
+    }
+
+    pub fn run<R: BufRead, W: Write>(&mut self, input: &mut R, output: &mut W) -> io::Result<()> {
+        loop {
+            write!(output, "db> ")?;
+            output.flush()?;
+
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                break;
+            }
+
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == ".quit" || line == ".exit" {
+                break;
+            }
+
+            let started = Instant::now();
+            let result = self.execute(line);
+            let elapsed = started.elapsed();
+            match result {
+                Ok(outputs) => {
+                    for out in outputs {
+                        match out {
+                            Output::Text(text) => write!(output, "{}", text)?,
+                            Output::Rows(rows) => {
+                                rows.page_through_with(&self.pager, input, output)?;
+                                writeln!(output, "({} rows)", rows.len())?;
+                            }
+                        }
+                    }
+                }
+                Err(e) => writeln!(output, "Error: {}", e)?,
+            }
+            if self.timing {
+                writeln!(output, "Run Time: {:.3} ms", elapsed.as_secs_f64() * 1000.0)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // The command's output as text, with any rows on a single page
+    pub fn run_command(&mut self, line: &str) -> Result<String, String> {
+        let outputs = self.execute(line)?;
+        Ok(outputs
+            .into_iter()
+            .map(|out| match out {
+                Output::Text(text) => text,
+                Output::Rows(rows) => {
+                    let options = PagerOptions {
+                        page_size: rows.len().max(1),
+                        ..self.pager.clone()
+                    };
+                    format!("{}({} rows)\n", rows.render_page(0, &options), rows.len())
+                }
+            })
+            .collect())
+    }
+
+    fn execute(&mut self, line: &str) -> Result<Vec<Output>, String> {
+        if !line.starts_with('.') {
+            return self.run_sql(line);
+        }
+        self.run_dot_command(line).map(|text| vec![Output::Text(text)])
+    }
+
+    fn run_dot_command(&mut self, line: &str) -> Result<String, String> {
+        let mut parts = line.split_whitespace();
+        let command = parts.next().unwrap_or("");
+        let arg = parts.next();
+
+        match (command, arg) {
+            (".tables", _) => {
+                let mut names: Vec<&String> = self.db.tables.keys().collect();
+                names.sort();
+                Ok(names
+                    .iter()
+                    .map(|n| format!("{}\n", n))
+                    .collect::<String>())
+            }
+            (".schema", Some(name)) => {
+                let mut out = self.table(name).map(describe_schema)?;
+                if self.db.memory_only.contains(name) {
+                    out.push_str("  MEMORY ONLY\n");
+                }
+                Ok(out)
+            }
+            (".indexes", Some(name)) => self.table(name).map(describe_indexes),
+            (".stats", _) => Ok(self.describe_stats()),
+            (".open", Some(path)) => {
+                self.db = Database::load(path).map_err(|e| e.to_string())?;
+                Ok(format!("Opened {}\n", path))
+            }
+            (".timing", Some("on")) => {
+                self.timing = true;
+                Ok(String::new())
+            }
+            (".timing", Some("off")) => {
+                self.timing = false;
+                Ok(String::new())
+            }
+            (".help", _) => Ok(
+                "<SQL statement>\n.open <path>\n.tables\n.schema <table>\n.indexes <table>\n.stats\n.timing on|off\n.quit\n"
+                    .to_string(),
+            ),
+            (".open", None) => Err("Usage: .open <path>".to_string()),
+            (".schema", None) | (".indexes", None) => {
+                Err(format!("Usage: {} <table>", command))
+            }
+            (".timing", _) => Err("Usage: .timing on|off".to_string()),
+            _ => Err(format!("Unknown command '{}'. Try .help", command)),
+        }
+    }
+
+    fn run_sql(&mut self, sql: &str) -> Result<Vec<Output>, String> {
+        Ok(self
+            .db
+            .execute_script(sql)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|result| match result {
+                QueryResult::Rows(rows) => Output::Rows(rows),
+                QueryResult::Affected(n) => Output::Text(format!("{} rows affected\n", n)),
+                QueryResult::Done => Output::Text("OK\n".to_string()),
+            })
+            .collect())
+    }
+
+    fn table(&self, name: &str) -> Result<&Table, String> {
+        self.db
+            .tables
+            .get(name)
+            .ok_or_else(|| format!("Table '{}' not found", name))
+    }
+
+    fn describe_stats(&self) -> String {
+        let mut names: Vec<&String> = self.db.tables.keys().collect();
+        names.sort();
+
+        let mut out = format!("tables: {}\n", names.len());
+        for name in names {
+            let table = &self.db.tables[name];
+            out.push_str(&format!(
+                "  {}: {} rows, {} columns, {} indexes{}\n",
+                name,
+                table.rows.len(),
+                table.columns.len(),
+                table.indexes.len(),
+                if self.db.memory_only.contains(name) { " (memory only)" } else { "" }
+            ));
+        }
+        out
+    }
+}
+
+fn describe_schema(table: &Table) -> String {
+    let mut out = format!("{}\n", table.name);
+    for col in &table.columns {
+        let options = col
+            .options
+            .iter()
+            .map(option_label)
+            .collect::<Vec<_>>()
+            .join(", ");
+        out.push_str(&format!(
+            "  {:<15} {:<10} {}\n",
+            col.name,
+            format!("{:?}", col.datatype),
+            options
+        ));
+    }
+    if let Some(pk) = &table.primary_key {
+        out.push_str(&format!("  PRIMARY KEY ({})\n", pk.join(", ")));
+    }
+    out
+}
+
+fn describe_indexes(table: &Table) -> String {
+    let mut names: Vec<&String> = table.indexes.keys().collect();
+    names.sort();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let (kind, keys) = match &table.indexes[name] {
+                IndexType::Hash(map) => ("HASH", map.len()),
+                IndexType::BTree(map) => ("BTREE", map.len()),
+                IndexType::Grid(grid) => ("GRID", grid.cells.len()),
+                IndexType::Disk(disk) => ("DISK", disk.entry_count().unwrap_or(0)),
+            };
+            format!("  {:<15} {:<6} {} keys\n", name, kind, keys)
+        })
+        .collect()
+}
+
+fn option_label(opt: &Options) -> String {
+    match opt {
+        Options::Unique => "UNIQUE".to_string(),
+        Options::NotNull => "NOT NULL".to_string(),
+        Options::FK(table) => format!("REFERENCES {}", table),
+        Options::Check(expr) => format!("CHECK ({})", expr),
+        Options::Generated(expr) => format!("GENERATED AS ({})", expr),
+        Options::Default(DefaultExpr::Literal(val)) => format!("DEFAULT {}", val.to_display_string()),
+        Options::Default(expr) => format!("DEFAULT {}", expr),
+        Options::Autoincrement => "AUTOINCREMENT".to_string(),
+        Options::Identity { start, increment } => {
+            format!("IDENTITY (START {} INCREMENT {})", start, increment)
+        }
+        Options::OnUpdateNow => "ON UPDATE NOW".to_string(),
+        Options::Ttl(seconds) => format!("TTL {}s", seconds),
+        Options::Mask(policy) => format!("MASK {:?}", policy),
+        Options::OnUpdate(action) => format!("ON UPDATE {}", action),
+        Options::Truncate => "TRUNCATE".to_string(),
+        Options::Collate(collation) => format!("COLLATE {}", collation),
+    }
+}
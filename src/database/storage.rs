@@ -0,0 +1,269 @@
+use crate::database::history::RowChange;
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::table::data::{FilterExpr, Table, Value};
+use std::fmt;
+
+// Where a table's rows live. The table keeps its in-memory rows for querying; the
+// engine receives every committed write made through Database and owns durability.
+// Writes made to `Database::tables` directly never reach it, so row writes go through
+// insert, update_where, delete_where and vacuum, and anything else through alter_table.
+pub trait StorageEngine: fmt::Debug + Send {
+    fn name(&self) -> &'static str;
+    fn scan(&self) -> Result<Vec<Vec<Value>>, String>;
+    fn insert(&mut self, row: &[Value]) -> Result<(), String>;
+    fn update(&mut self, position: usize, row: &[Value]) -> Result<(), String>;
+    // Positions are sorted, without repeats, and refer to row order before the delete
+    fn delete(&mut self, positions: &[usize]) -> Result<(), String>;
+    fn flush(&mut self) -> Result<(), String>;
+
+    // Swaps every stored row for `rows`, after a change that rewrote the table as a whole
+    fn replace(&mut self, rows: &[Vec<Value>]) -> Result<(), String> {
+        let stored = self.scan()?.len();
+        if stored > 0 {
+            self.delete(&(0..stored).collect::<Vec<_>>())?;
+        }
+        for row in rows {
+            self.insert(row)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryEngine {
+    rows: Vec<Vec<Value>>,
+}
+
+impl StorageEngine for MemoryEngine {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn scan(&self) -> Result<Vec<Vec<Value>>, String> {
+        Ok(self.rows.clone())
+    }
+
+    fn insert(&mut self, row: &[Value]) -> Result<(), String> {
+        self.rows.push(row.to_vec());
+        Ok(())
+    }
+
+    fn update(&mut self, position: usize, row: &[Value]) -> Result<(), String> {
+        let slot = self
+            .rows
+            .get_mut(position)
+            .ok_or_else(|| format!("Row {} out of range", position))?;
+        *slot = row.to_vec();
+        Ok(())
+    }
+
+    fn replace(&mut self, rows: &[Vec<Value>]) -> Result<(), String> {
+        self.rows = rows.to_vec();
+        Ok(())
+    }
+
+    fn delete(&mut self, positions: &[usize]) -> Result<(), String> {
+        let mut positions = positions.iter().peekable();
+        let mut i = 0;
+        self.rows.retain(|_| {
+            let gone = positions.next_if_eq(&&i).is_some();
+            i += 1;
+            !gone
+        });
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl Database {
+    // Registers `table` backed by `engine`. Rows already stored in the engine are loaded
+    // into the table; rows already in the table are written to an empty engine.
+    pub fn create_table_with_engine(
+        &mut self,
+        mut table: Table,
+        mut engine: Box<dyn StorageEngine>,
+    ) -> Result<(), DbError> {
+        if self.tables.contains_key(&table.name) {
+            return Err(DbError::SchemaError(format!("Table '{}' already exists", table.name)));
+        }
+        // The engine is written to straight away, which a rollback couldn't undo
+        if self.in_transaction() {
+            return Err(DbError::TransactionError(
+                "Tables with an engine cannot be created inside a transaction".to_string(),
+            ));
+        }
+        // CHECKs are resolved against the table they are defined on
+        table.columns.iter().try_for_each(|column| table.validate_checks(column))?;
+
+        let stored = engine.scan()?;
+        match (stored.is_empty(), table.rows.is_empty()) {
+            (_, true) => {
+                for row in stored {
+                    table.insert(row)?;
+                }
+            }
+            (true, false) => {
+                for row in &table.rows {
+                    engine.insert(row)?;
+                }
+            }
+            (false, false) => {
+                return Err(DbError::InvalidArgument(format!(
+                    "Table '{}' and its storage engine both already hold rows",
+                    table.name
+                )))
+            }
+        }
+
+        self.engines.insert(table.name.clone(), engine);
+        self.tables.insert(table.name.clone(), table);
+        Ok(())
+    }
+
+    // Tables registered without an engine behave as plain in-memory tables
+    pub fn engine_name(&self, table_name: &str) -> Option<&'static str> {
+        if !self.tables.contains_key(table_name) {
+            return None;
+        }
+        Some(self.engines.get(table_name).map_or("memory", |e| e.name()))
+    }
+
+    pub fn insert(&mut self, table_name: &str, values: Vec<Value>) -> Result<(), DbError> {
+        self.apply_insert(table_name, values)
+    }
+
+    pub fn update_where(
+        &mut self,
+        table_name: &str,
+        expr: &FilterExpr,
+        updates: Vec<Option<Value>>,
+    ) -> Result<usize, DbError> {
+        let indices = self.filter_positions(table_name, expr)?;
+        self.apply_update(table_name, &indices, &updates)
+    }
+
+    pub fn delete_where(&mut self, table_name: &str, expr: &FilterExpr) -> Result<usize, DbError> {
+        let indices = self.filter_positions(table_name, expr)?;
+        self.apply_delete(table_name, &indices)
+    }
+
+    // Deletes the rows past their TTL
+    pub fn vacuum(&mut self, table_name: &str) -> Result<usize, DbError> {
+        let expired = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DbError::NotFound(format!("Table \'{}\' not found", table_name)))?
+            .expired_positions();
+        self.apply_delete(table_name, &expired)
+    }
+
+    pub fn flush(&mut self, table_name: &str) -> Result<(), DbError> {
+        match self.engines.get_mut(table_name) {
+            Some(engine) => Ok(engine.flush()?),
+            None if self.tables.contains_key(table_name) => Ok(()),
+            None => Err(DbError::NotFound(format!("Table '{}' not found", table_name))),
+        }
+    }
+
+    pub fn flush_all(&mut self) -> Result<(), DbError> {
+        for engine in self.engines.values_mut() {
+            engine.flush()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn apply_insert(&mut self, table_name: &str, values: Vec<Value>) -> Result<(), DbError> {
+        let table = self
+            .tables
+            .get_mut(table_name)
+            .ok_or_else(|| DbError::NotFound(format!("Table \'{}\' not found", table_name)))?;
+        table.insert(values)?;
+        // Record the stored form, with defaults and identity values filled in
+        let row = table.rows.last().unwrap().clone();
+        self.publish_changes(vec![(table_name.to_string(), RowChange::Insert { row })])
+    }
+
+    // All rows or none, published as one change per row
+    pub(crate) fn apply_insert_many(&mut self, table_name: &str, rows: Vec<Vec<Value>>) -> Result<usize, DbError> {
+        let table = self
+            .tables
+            .get_mut(table_name)
+            .ok_or_else(|| DbError::NotFound(format!("Table \'{}\' not found", table_name)))?;
+        let start = table.rows.len();
+        let count = table.insert_many(rows)?;
+        let changes = table.rows[start..]
+            .iter()
+            .map(|row| (table_name.to_string(), RowChange::Insert { row: row.clone() }))
+            .collect();
+        self.publish_changes(changes)?;
+        Ok(count)
+    }
+
+    pub(crate) fn apply_update(
+        &mut self,
+        table_name: &str,
+        indices: &[usize],
+        updates: &[Option<Value>],
+    ) -> Result<usize, DbError> {
+        let table = self
+            .tables
+            .get_mut(table_name)
+            .ok_or_else(|| DbError::NotFound(format!("Table \'{}\' not found", table_name)))?;
+        let before: Vec<Vec<Value>> = indices.iter().map(|&i| table.rows[i].clone()).collect();
+        let count = table.update_rows(indices, updates)?;
+
+        let table = &self.tables[table_name];
+        let changes = indices
+            .iter()
+            .zip(before)
+            .map(|(&position, before)| {
+                let change = RowChange::Update {
+                    position,
+                    before,
+                    after: table.rows[position].clone(),
+                };
+                (table_name.to_string(), change)
+            })
+            .collect();
+        self.publish_changes(changes)?;
+        Ok(count)
+    }
+
+    pub(crate) fn apply_delete(&mut self, table_name: &str, indices: &[usize]) -> Result<usize, DbError> {
+        let table = self
+            .tables
+            .get_mut(table_name)
+            .ok_or_else(|| DbError::NotFound(format!("Table \'{}\' not found", table_name)))?;
+
+        let mut positions = indices.to_vec();
+        positions.sort_unstable();
+        positions.dedup();
+        let rows = positions.iter().map(|&i| table.rows[i].clone()).collect();
+        let count = table.delete_rows(indices);
+
+        self.publish_changes(vec![(table_name.to_string(), RowChange::Delete { positions, rows })])?;
+        Ok(count)
+    }
+
+    // Scans every live row, so positions come back in table order
+    pub(crate) fn filter_positions(&self, table_name: &str, expr: &FilterExpr) -> Result<Vec<usize>, DbError> {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DbError::NotFound(format!("Table \'{}\' not found", table_name)))?;
+        expr.check_columns(table)?;
+
+        let predicate = expr.to_predicate(table);
+        Ok(table
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| !table.is_expired(row) && predicate(row))
+            .map(|(i, _)| i)
+            .collect())
+    }
+}
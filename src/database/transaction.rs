@@ -0,0 +1,228 @@
+use crate::database::history::{RowChange, TableHistory};
+use crate::database::session::Policy;
+use crate::database::storage::StorageEngine;
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::table::data::Table;
+use std::collections::{HashMap, HashSet};
+
+// Writes made through the Database since begin_transaction. They are held back from the
+// storage engines and history until commit, so a rollback only has to restore the tables.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    pub changes: Vec<(String, RowChange)>,
+    // Tables created and dropped since begin_transaction, oldest first
+    pub(crate) tables: Vec<TableChange>,
+}
+
+#[derive(Debug)]
+pub(crate) enum TableChange {
+    Created(String),
+    Dropped(Box<DroppedTable>),
+}
+
+// Everything drop_table took away with the table, kept until commit
+#[derive(Debug)]
+pub(crate) struct DroppedTable {
+    pub(crate) table: Table,
+    pub(crate) engine: Option<Box<dyn StorageEngine>>,
+    pub(crate) memory_only: bool,
+    pub(crate) policies: Option<Vec<Policy>>,
+    pub(crate) history: Option<TableHistory>,
+}
+
+// How far the open transaction had got, so a failed multi-table write can be undone
+// without ending the transaction
+#[derive(Debug)]
+pub(crate) struct Savepoint {
+    changes: usize,
+    logs: HashMap<String, usize>,
+}
+
+impl Database {
+    // Opens a transaction over every table at once. Each table keeps an undo log of its
+    // own writes, which checkpoints and online backups already read past.
+    pub fn begin_transaction(&mut self) -> Result<(), DbError> {
+        if self.transaction.is_some() {
+            return Err(DbError::TransactionError("Transaction already in progress".into()));
+        }
+        if let Some(table) = self.tables.values().find(|t| t.transaction_backup.is_some()) {
+            return Err(DbError::TransactionError(format!(
+                "Table '{}' already has a transaction in progress",
+                table.name
+            )));
+        }
+
+        for table in self.tables.values_mut() {
+            table.begin_transaction()?;
+        }
+        self.transaction = Some(Transaction::default());
+        Ok(())
+    }
+
+    pub fn commit(&mut self) -> Result<(), DbError> {
+        let transaction = self
+            .transaction
+            .take()
+            .ok_or_else(|| DbError::TransactionError("No transaction to commit".into()))?;
+        let touched: HashSet<String> = transaction.changes.iter().map(|(name, _)| name.clone()).collect();
+
+        // The engines hear of the changes before the tables let go of their undo logs, so
+        // a failed write rolls the whole transaction back
+        if let Err(e) = self.write_to_engines(&transaction.changes) {
+            self.undo_table_changes(transaction.tables);
+            for table in self.tables.values_mut() {
+                if table.transaction_backup.is_some() {
+                    table.rollback_transaction()?;
+                }
+            }
+            self.restore_engines(&touched);
+            return Err(DbError::TransactionError(format!("Commit failed and was rolled back: {}", e)));
+        }
+        for table in self.tables.values_mut() {
+            if table.transaction_backup.is_some() {
+                table.commit_transaction()?;
+            }
+        }
+
+        self.record_changes(transaction.changes);
+        for name in touched {
+            if let Some(engine) = self.engines.get_mut(&name) {
+                engine.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn rollback(&mut self) -> Result<(), DbError> {
+        let transaction = self
+            .transaction
+            .take()
+            .ok_or_else(|| DbError::TransactionError("No transaction to rollback".into()))?;
+        self.undo_table_changes(transaction.tables);
+        for table in self.tables.values_mut() {
+            if table.transaction_backup.is_some() {
+                table.rollback_transaction()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    // Takes away tables the transaction created and puts back the ones it dropped, newest
+    // first, so a table dropped and created again under one name ends as the original.
+    // Their row changes are left for the tables' own undo logs.
+    fn undo_table_changes(&mut self, changes: Vec<TableChange>) {
+        for change in changes.into_iter().rev() {
+            match change {
+                TableChange::Created(name) => {
+                    self.tables.remove(&name);
+                    self.engines.remove(&name);
+                    self.memory_only.remove(&name);
+                    self.policies.remove(&name);
+                    self.drop_history(&name);
+                    self.plan_cache.invalidate_table(&name);
+                }
+                TableChange::Dropped(dropped) => {
+                    let DroppedTable {
+                        table,
+                        engine,
+                        memory_only,
+                        policies,
+                        history,
+                    } = *dropped;
+                    let name = table.name.clone();
+                    if let Some(engine) = engine {
+                        self.engines.insert(name.clone(), engine);
+                    }
+                    if memory_only {
+                        self.memory_only.insert(name.clone());
+                    }
+                    if let Some(policies) = policies {
+                        self.policies.insert(name.clone(), policies);
+                    }
+                    if let Some(history) = history {
+                        self.history.tables.insert(name.clone(), history);
+                    }
+                    self.plan_cache.invalidate_table(&name);
+                    self.tables.insert(name, table);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn savepoint(&self) -> Savepoint {
+        Savepoint {
+            changes: self.transaction.as_ref().map_or(0, |t| t.changes.len()),
+            logs: self
+                .tables
+                .iter()
+                .filter_map(|(name, table)| Some((name.clone(), table.transaction_backup.as_ref()?.len())))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn rollback_to(&mut self, savepoint: Savepoint) {
+        if let Some(transaction) = &mut self.transaction {
+            transaction.changes.truncate(savepoint.changes);
+        }
+        for (name, len) in savepoint.logs {
+            if let Some(table) = self.tables.get_mut(&name) {
+                table.rollback_to(len);
+            }
+        }
+    }
+
+    // Passes changes on to the storage engines and history, or holds them back while a
+    // transaction is open. The tables already hold the changes; if an engine fails to
+    // take them they are undone, so the tables never get ahead of what was stored.
+    pub(crate) fn publish_changes(&mut self, changes: Vec<(String, RowChange)>) -> Result<(), DbError> {
+        if let Some(transaction) = &mut self.transaction {
+            transaction.changes.extend(changes);
+            return Ok(());
+        }
+
+        if let Err(e) = self.write_to_engines(&changes) {
+            for (name, change) in changes.iter().rev() {
+                if let Some(table) = self.tables.get_mut(name) {
+                    change.undo(&mut table.rows);
+                }
+            }
+            self.restore_engines(&changes.iter().map(|(name, _)| name.clone()).collect());
+            return Err(e.into());
+        }
+        self.record_changes(changes);
+        Ok(())
+    }
+
+    fn write_to_engines(&mut self, changes: &[(String, RowChange)]) -> Result<(), String> {
+        for (name, change) in changes {
+            if let Some(engine) = self.engines.get_mut(name) {
+                match change {
+                    RowChange::Insert { row } => engine.insert(row)?,
+                    RowChange::Update {
+                        position, after, ..
+                    } => engine.update(*position, after)?,
+                    RowChange::Delete { positions, .. } => engine.delete(positions)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // After a failed write, brings the tables' indexes and engines back in line with the
+    // restored rows. An engine that fails again is left for the caller's error to report.
+    fn restore_engines(&mut self, names: &HashSet<String>) {
+        for name in names {
+            if let Some(table) = self.tables.get_mut(name) {
+                table.rebuild_all_indexes();
+                if let Some(engine) = self.engines.get_mut(name) {
+                    let _ = engine.replace(&table.rows);
+                }
+            }
+        }
+    }
+}
@@ -1,28 +1,60 @@
-use std::collections::HashMap;
-use crate::table::data::{Table, Options};
+use std::collections::{HashMap, HashSet};
+use crate::database::config::DatabaseConfig;
+use crate::database::history::History;
+use crate::database::migrations::Migrations;
+use crate::database::plan_cache::PlanCache;
+use crate::database::session::Policy;
+use crate::database::storage::StorageEngine;
+use crate::database::transaction::Transaction;
+use crate::table::data::{Table, Options, View};
+use crate::error::DbError;
 
 #[derive(Debug)]
 pub struct Database {
     pub tables: HashMap<String, Table>,
+    pub views: HashMap<String, View>,
+    pub policies: HashMap<String, Vec<Policy>>,
+    pub plan_cache: PlanCache,
+    pub config: DatabaseConfig,
+    pub engines: HashMap<String, Box<dyn StorageEngine>>,
+    pub memory_only: HashSet<String>,
+    pub history: History,
+    pub transaction: Option<Transaction>,
+    pub migrations: Migrations,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Database::new()
+    }
 }
 
 impl Database {
     pub fn new() -> Self {
         Database {
             tables: HashMap::new(),
+            views: HashMap::new(),
+            policies: HashMap::new(),
+            plan_cache: PlanCache::default(),
+            config: DatabaseConfig::default(),
+            engines: HashMap::new(),
+            memory_only: HashSet::new(),
+            history: History::default(),
+            transaction: None,
+            migrations: Migrations::default(),
         }
     }
 
-    pub fn validate_foreign_keys(&self) -> Result<(), String> {
+    pub fn validate_foreign_keys(&self) -> Result<(), DbError> {
         for table in self.tables.values() {
             for column in &table.columns {
                 for opt in &column.options {
                     if let Options::FK(ref foreign_table_name) = opt {
                         if !self.tables.contains_key(foreign_table_name) {
-                            return Err(format!(
+                            return Err(DbError::ConstraintViolation(format!(
                                 "Table '{}' has a foreign key to missing table '{}'.",
                                 table.name, foreign_table_name
-                            ));
+                            )));
                         }
                     }
                 }
@@ -0,0 +1,122 @@
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::table::data::{Column, Expr, FilterExpr, Table, View};
+
+impl View {
+    pub fn new(from: &str) -> Self {
+        View {
+            from: from.to_string(),
+            joins: vec![],
+            filter: None,
+            columns: vec![],
+        }
+    }
+
+    // `left` and `right` are written as table.column, e.g. ("orders.customer_id", "customers.id")
+    pub fn join(mut self, table: &str, left: &str, right: &str) -> Self {
+        self.joins.push((table.to_string(), left.to_string(), right.to_string()));
+        self
+    }
+
+    // Filtering more than once keeps the rows matching every filter
+    pub fn filter(mut self, expr: FilterExpr) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(existing) => existing.and(expr),
+            None => expr,
+        });
+        self
+    }
+
+    pub fn select(self, columns: &[&str]) -> Self {
+        self.select_exprs(columns.iter().map(|name| Expr::column(name)).collect())
+    }
+
+    pub fn select_exprs(mut self, exprs: Vec<Expr>) -> Self {
+        self.columns.extend(exprs);
+        self
+    }
+
+    pub(crate) fn tables(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.from.as_str()).chain(self.joins.iter().map(|(table, _, _)| table.as_str()))
+    }
+}
+
+impl Database {
+    // The definition is run once up front, so a view never starts out naming a missing
+    // table or column. Dropping or altering a table it reads can still break it later.
+    pub fn create_view(&mut self, name: &str, view: View) -> Result<(), DbError> {
+        if self.tables.contains_key(name) || self.views.contains_key(name) {
+            return Err(DbError::SchemaError(format!("'{}' already exists", name)));
+        }
+        self.evaluate_view(name, &view)?;
+        self.views.insert(name.to_string(), view);
+        Ok(())
+    }
+
+    pub fn drop_view(&mut self, name: &str) -> Result<View, DbError> {
+        self.views
+            .remove(name)
+            .ok_or_else(|| DbError::NotFound(format!("View '{}' not found", name)))
+    }
+
+    // Rows come from the tables as they are now; the result has no constraints or indexes
+    pub fn query_view(&self, name: &str) -> Result<Table, DbError> {
+        let view = self
+            .views
+            .get(name)
+            .ok_or_else(|| DbError::NotFound(format!("View '{}' not found", name)))?;
+        self.evaluate_view(name, view)
+    }
+
+    fn evaluate_view(&self, name: &str, view: &View) -> Result<Table, DbError> {
+        let tables = self.view_tables(view)?;
+        let joined;
+        let source = if view.joins.is_empty() {
+            tables[0]
+        } else {
+            let on: Vec<(&str, &str)> = view.joins.iter().map(|(_, l, r)| (l.as_str(), r.as_str())).collect();
+            joined = Table::join_all(&tables, &on)?;
+            &joined
+        };
+
+        let exprs = if view.columns.is_empty() {
+            source.columns.iter().map(|c| Expr::column(&c.name)).collect()
+        } else {
+            view.columns.clone()
+        };
+        let result = source.select_exprs(&exprs, view.filter.as_ref())?;
+
+        let columns = result.columns.into_iter().map(|c| Column { options: vec![], ..c }).collect();
+        let mut table = Table::new(name, columns, None);
+        table.rows = result.rows;
+        Ok(table)
+    }
+
+    // The columns a view's filter sees, named as join_all names them, without reading any rows
+    pub(crate) fn view_source_columns(&self, view: &View) -> Result<Vec<Column>, DbError> {
+        let tables = self.view_tables(view)?;
+        if view.joins.is_empty() {
+            return Ok(tables[0].columns.clone());
+        }
+        Ok(tables
+            .iter()
+            .flat_map(|t| {
+                t.columns.iter().map(move |c| Column {
+                    name: format!("{}.{}", t.name, c.name),
+                    datatype: c.datatype.clone(),
+                    options: vec![],
+                })
+            })
+            .collect())
+    }
+
+    fn view_tables(&self, view: &View) -> Result<Vec<&Table>, DbError> {
+        view.tables()
+            .map(|name| {
+                self.tables
+                    .get(name)
+                    .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found", name)))
+            })
+            .collect()
+    }
+}
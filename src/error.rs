@@ -0,0 +1,61 @@
+use std::io;
+use thiserror::Error;
+
+// Every variant carries the human-readable message; match on the variant to tell
+// failure modes apart instead of inspecting the text
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum DbError {
+    // NOT NULL, UNIQUE, primary key, CHECK, enum/set and foreign key rules
+    #[error("{0}")]
+    ConstraintViolation(String),
+    // A value that doesn't fit, or can't be parsed as, the column's type
+    #[error("{0}")]
+    TypeMismatch(String),
+    // An invalid table or column definition, or a DDL change that would make one
+    #[error("{0}")]
+    SchemaError(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    IoError(String),
+    // A file whose contents fail their checksum or end early; `offset` is the byte where
+    // the bad data starts
+    #[error("{message}")]
+    Corruption { offset: u64, message: String },
+    // Begin/commit/rollback called out of order
+    #[error("{0}")]
+    TransactionError(String),
+    #[error("{0}")]
+    InvalidArgument(String),
+    // Errors from modules that still report failures as plain strings
+    #[error("{0}")]
+    Other(String),
+}
+
+impl DbError {
+    pub fn message(&self) -> &str {
+        match self {
+            DbError::ConstraintViolation(msg)
+            | DbError::TypeMismatch(msg)
+            | DbError::SchemaError(msg)
+            | DbError::NotFound(msg)
+            | DbError::IoError(msg)
+            | DbError::TransactionError(msg)
+            | DbError::InvalidArgument(msg)
+            | DbError::Other(msg) => msg,
+            DbError::Corruption { message, .. } => message,
+        }
+    }
+}
+
+impl From<io::Error> for DbError {
+    fn from(e: io::Error) -> Self {
+        DbError::IoError(e.to_string())
+    }
+}
+
+impl From<String> for DbError {
+    fn from(msg: String) -> Self {
+        DbError::Other(msg)
+    }
+}
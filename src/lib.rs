@@ -1,3 +1,6 @@
 pub mod table;
 pub mod database;
+pub mod sql;
+pub mod storage;
+pub mod error;
 mod macros;
@@ -18,4 +18,63 @@ macro_rules! filter {
     (col $col_name:literal <= $val:expr) => {
         FilterExpr::Le($col_name.to_string(), $val.clone())
     };
+    // Combinators group to the right, so parenthesize to mix `and` with `or`:
+    // filter!((col "a" == x) and ((col "b" > y) or (col "c" < z)))
+    (not $($inner:tt)+) => {
+        FilterExpr::Not(Box::new($crate::filter!($($inner)+)))
+    };
+    (($($left:tt)+) and $($right:tt)+) => {
+        FilterExpr::And(Box::new($crate::filter!($($left)+)), Box::new($crate::filter!($($right)+)))
+    };
+    (($($left:tt)+) or $($right:tt)+) => {
+        FilterExpr::Or(Box::new($crate::filter!($($left)+)), Box::new($crate::filter!($($right)+)))
+    };
+    (($($inner:tt)+)) => {
+        $crate::filter!($($inner)+)
+    };
+}
+
+// Declares a struct and implements DbRow for it, mapping each field to a column of the
+// same name. Every field type must implement FromValue, ToValue and ColumnType:
+// db_row! { pub struct Person { pub id: i32, pub name: String, pub born: Option<NaiveDate> } }
+#[macro_export]
+macro_rules! db_row {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $ty:ty),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty),+
+        }
+
+        impl $crate::table::typed::DbRow for $name {
+            fn columns() -> Vec<$crate::table::data::Column> {
+                vec![$($crate::table::typed::column_for::<$ty>(stringify!($field))),+]
+            }
+
+            fn to_row(&self) -> Vec<$crate::table::data::Value> {
+                vec![$($crate::table::typed::ToValue::to_value(&self.$field)),+]
+            }
+
+            fn from_row(row: &[$crate::table::data::Value]) -> Result<Self, String> {
+                let fields = [$(stringify!($field)),+];
+                if row.len() != fields.len() {
+                    return Err(format!(
+                        "{} has {} fields but the row has {} values",
+                        stringify!($name),
+                        fields.len(),
+                        row.len()
+                    ));
+                }
+                let mut values = row.iter();
+                Ok($name {
+                    $($field: <$ty as $crate::table::typed::FromValue>::from_value(values.next().unwrap())
+                        .map_err(|e| format!("Field '{}': {}", stringify!($field), e))?),+
+                })
+            }
+        }
+    };
 }
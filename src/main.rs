@@ -1,13 +1,39 @@
 mod table;
 mod database;
+mod sql;
+mod storage;
+mod error;
 
-use crate::table::data::{Table, Column, Value, DataType, Options, FilterExpr};
+use crate::table::data::{Table, Column, Value, DataType, DefaultExpr, Options, FilterExpr};
 use crate::database::validators::Database;
+use crate::database::shell::Shell;
 use std::collections::HashMap;
 
 use chrono::NaiveDate;
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("shell") {
+        // `shell <path>` opens a saved database; without one the shell starts empty
+        let db = match args.get(2) {
+            Some(path) => match Database::load(path) {
+                Ok(db) => db,
+                Err(e) => {
+                    println!("Failed to open {} ❌: {}", path, e);
+                    return;
+                }
+            },
+            None => Database::new(),
+        };
+        let mut shell = Shell::new(db);
+        let stdin = std::io::stdin();
+        let stdout = std::io::stdout();
+        if let Err(e) = shell.run(&mut stdin.lock(), &mut stdout.lock()) {
+            println!("Shell error ❌: {}", e);
+        }
+        return;
+    }
+
     // Define columns
     let columns = vec![
         Column {
@@ -17,15 +43,15 @@ fn main() {
         },
         Column {
             name: "name".to_string(),
-            datatype: DataType::Varchar,
+            datatype: DataType::Varchar(255),
             options: vec![Options::NotNull, Options::Unique],
         },
         Column {
             name: "joined".to_string(),
             datatype: DataType::Date,
-            options: vec![Options::Default(Value::Date(
+            options: vec![Options::Default(DefaultExpr::Literal(Value::Date(
                 NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
-            ))],
+            )))],
         },
     ];
 
@@ -63,8 +89,7 @@ fn main() {
     }
 
     // Load table back
-    let loaded_columns = table.columns.clone();
-    let loaded_table = Table::load_from_file("testdb", "users", loaded_columns, Some(vec!["id".to_string()]));
+    let loaded_table = Table::load_from_file("testdb", "users");
     match loaded_table {
         Ok(t) => {
             println!("Loaded table:");
@@ -90,7 +115,10 @@ fn main() {
         rows: vec![],
         primary_key: None,
         indexes: HashMap::new(),
+        sequences: HashMap::new(),
         transaction_backup: None,
+        stats: None,
+        compression: None,
     };
     db.tables.insert("logins".to_string(), fk_table);
 
@@ -107,7 +135,7 @@ fn main() {
     let columns_not_null = vec![
         Column {
             name: "email".to_string(),
-            datatype: DataType::Varchar,
+            datatype: DataType::Varchar(255),
             options: vec![Options::NotNull],
         }
     ];
@@ -122,8 +150,8 @@ fn main() {
     let columns_check = vec![
         Column {
             name: "status".to_string(),
-            datatype: DataType::Varchar,
-            options: vec![Options::Check("status = active".to_string())],
+            datatype: DataType::Varchar(255),
+            options: vec![Options::Check("status = 'active'".parse().unwrap())],
         }
     ];
     let mut check_table = Table::new("checktest", columns_check, None);
@@ -134,32 +162,32 @@ fn main() {
     }
 
     // ENUM Constraint Violation
+    let allowed_roles = vec!["admin".to_string(), "user".to_string()];
     let columns_enum = vec![
         Column {
             name: "role".to_string(),
-            datatype: DataType::Enum,
+            datatype: DataType::Enum(allowed_roles),
             options: vec![],
         }
     ];
-    let allowed_roles = vec!["admin".to_string(), "user".to_string()];
     let mut enum_table = Table::new("enumtest", columns_enum, None);
-    let result = enum_table.insert(vec![Value::Enum("guest".to_string(), allowed_roles.clone())]);
+    let result = enum_table.insert(vec![Value::Enum("guest".to_string())]);
     match result {
         Ok(_) => println!("❌ ENUM constraint violation not caught!"),
         Err(e) => println!("✅ ENUM constraint test passed: {}", e),
     }
 
     // SET Constraint Violation
+    let allowed_tags = vec!["safe".to_string(), "reviewed".to_string()];
     let columns_set = vec![
         Column {
             name: "tags".to_string(),
-            datatype: DataType::Set,
+            datatype: DataType::Set(allowed_tags),
             options: vec![],
         }
     ];
-    let allowed_tags = vec!["safe".to_string(), "reviewed".to_string()];
     let mut set_table = Table::new("settest", columns_set, None);
-    let result = set_table.insert(vec![Value::Set(vec!["dangerous".to_string()], allowed_tags.clone())]);
+    let result = set_table.insert(vec![Value::Set(vec!["dangerous".to_string()])]);
     match result {
         Ok(_) => println!("❌ SET constraint violation not caught!"),
         Err(e) => println!("✅ SET constraint test passed: {}", e),
@@ -184,7 +212,7 @@ fn main() {
         Column {
             name: "joined".to_string(),
             datatype: DataType::Date,
-            options: vec![Options::Default(Value::Date(NaiveDate::from_ymd_opt(2023, 12, 25).unwrap()))],
+            options: vec![Options::Default(DefaultExpr::Literal(Value::Date(NaiveDate::from_ymd_opt(2023, 12, 25).unwrap())))],
         }
     ];
     let mut default_table = Table::new("defaulttest", columns_default, None);
@@ -281,7 +309,7 @@ fn main() {
     let combo_cols = vec![Column {
         name: "score".to_string(),
         datatype: DataType::Int,
-        options: vec![Options::NotNull, Options::Default(Value::Int(100))],
+        options: vec![Options::NotNull, Options::Default(DefaultExpr::Literal(Value::Int(100)))],
     }];
     let mut combo_table = Table::new("combo", combo_cols, None);
     let result = combo_table.insert(vec![Value::Null]);
@@ -302,12 +330,12 @@ fn main() {
         },
         Column {
             name: "role".to_string(),
-            datatype: DataType::Enum,
+            datatype: DataType::Enum(vec!["admin".to_string(), "user".to_string()]),
             options: vec![],
         },
         Column {
             name: "tags".to_string(),
-            datatype: DataType::Set,
+            datatype: DataType::Set(vec!["safe".to_string(), "reviewed".to_string(), "flagged".to_string()]),
             options: vec![],
         },
     ];
@@ -316,8 +344,8 @@ fn main() {
     // Insert with complex values
     let insert_result = complex_table.insert(vec![
         Value::Date(NaiveDate::from_ymd_opt(2024, 5, 20).unwrap()),
-        Value::Enum("admin".to_string(), vec!["admin".to_string(), "user".to_string()]),
-        Value::Set(vec!["safe".to_string(), "reviewed".to_string()], vec!["safe".to_string(), "reviewed".to_string(), "flagged".to_string()])
+        Value::Enum("admin".to_string()),
+        Value::Set(vec!["safe".to_string(), "reviewed".to_string()])
     ]);
 
     match insert_result {
@@ -332,7 +360,7 @@ fn main() {
     }
 
     // Load it back
-    let loaded_table = Table::load_from_file("testdb", "complex", complex_columns.clone(), None);
+    let loaded_table = Table::load_from_file("testdb", "complex");
     match loaded_table {
         Ok(t) => {
             println!("✅ Loaded complex table:");
@@ -350,14 +378,14 @@ fn main() {
         }
     ];
     let mut empty_table = Table::new("empty_delete", empty_columns.clone(), None);
-    empty_table.delete_where(&FilterExpr::Eq("id".to_string(), Value::Int(1)));
+    empty_table.delete_where(FilterExpr::Eq("id".to_string(), Value::Int(1))).unwrap();
     println!("✅ Safe delete on empty table passed");
     empty_table.print_table();
 
     // Delete a row that doesn’t exist
     let mut one_row_table = Table::new("delete_miss", empty_columns.clone(), None);
     one_row_table.insert(vec![Value::Int(1)]).unwrap();
-    one_row_table.delete_where(&FilterExpr::Eq("id".to_string(), Value::Int(999)));
+    one_row_table.delete_where(FilterExpr::Eq("id".to_string(), Value::Int(999))).unwrap();
     println!("✅ No rows deleted, as expected:");
     one_row_table.print_table();
 }
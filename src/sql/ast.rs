@@ -0,0 +1,124 @@
+use crate::error::DbError;
+use crate::table::data::{AggFunc, Column, DataType, FilterExpr, Limit, SortDirection, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    CreateTable {
+        name: String,
+        columns: Vec<Column>,
+        primary_key: Option<Vec<String>>,
+        if_not_exists: bool,
+    },
+    Insert {
+        table: String,
+        // None means every column, in table order
+        columns: Option<Vec<String>>,
+        rows: Vec<Vec<Literal>>,
+    },
+    Select(Select),
+    Update {
+        table: String,
+        assignments: Vec<(String, Literal)>,
+        filter: Option<WhereExpr>,
+    },
+    Delete {
+        table: String,
+        filter: Option<WhereExpr>,
+    },
+    DropTable {
+        name: String,
+        if_exists: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Select {
+    pub table: String,
+    pub items: Vec<SelectItem>,
+    pub filter: Option<WhereExpr>,
+    pub group_by: Option<String>,
+    pub order_by: Vec<(String, SortDirection)>,
+    pub limit: Limit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectItem {
+    Wildcard,
+    Column(String),
+    // `column` is None for COUNT(*)
+    Aggregate { func: AggFunc, column: Option<String> },
+}
+
+// Literals stay untyped until they meet the column they are compared with or stored in
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Null,
+    Bool(bool),
+    Number(String),
+    Str(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    // IS NULL and IS NOT NULL, whose value is always Literal::Null
+    IsNull,
+    IsNotNull,
+}
+
+// A WHERE clause; NOT binds tighter than AND, and AND tighter than OR
+#[derive(Debug, Clone, PartialEq)]
+pub enum WhereExpr {
+    Compare(Condition),
+    And(Box<WhereExpr>, Box<WhereExpr>),
+    Or(Box<WhereExpr>, Box<WhereExpr>),
+    Not(Box<WhereExpr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub column: String,
+    pub op: CompareOp,
+    pub value: Literal,
+}
+
+impl Literal {
+    pub fn to_value(&self, datatype: &DataType) -> Result<Value, DbError> {
+        match (self, datatype) {
+            (Literal::Null, _) => Ok(Value::Null),
+            (Literal::Bool(b), DataType::Boolean) => Ok(Value::Boolean(*b)),
+            (Literal::Bool(_), _) => Err(DbError::TypeMismatch(format!(
+                "Boolean literal used for a {:?} column",
+                datatype
+            ))),
+            // Kept verbatim: Value::from_str would trim the text
+            (Literal::Str(text), DataType::Varchar(_)) => Ok(Value::Varchar(text.clone())),
+            (Literal::Str(text), DataType::Text) => Ok(Value::Text(text.clone())),
+            (Literal::Str(text), DataType::Char(1)) if text.chars().count() == 1 => {
+                Ok(Value::Char(text.chars().next().unwrap()))
+            }
+            (Literal::Str(text), DataType::Char(n)) if *n > 1 => Ok(Value::Varchar(text.clone())),
+            (Literal::Str(text), _) | (Literal::Number(text), _) => Value::from_str(text, datatype),
+        }
+    }
+}
+
+impl CompareOp {
+    pub fn to_filter(self, column: String, value: Value) -> FilterExpr {
+        match self {
+            CompareOp::Eq => FilterExpr::Eq(column, value),
+            CompareOp::Ne => FilterExpr::Ne(column, value),
+            CompareOp::Lt => FilterExpr::Lt(column, value),
+            CompareOp::Le => FilterExpr::Le(column, value),
+            CompareOp::Gt => FilterExpr::Gt(column, value),
+            CompareOp::Ge => FilterExpr::Ge(column, value),
+            CompareOp::IsNull => FilterExpr::IsNull(column),
+            CompareOp::IsNotNull => FilterExpr::IsNotNull(column),
+        }
+    }
+}
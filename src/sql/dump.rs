@@ -0,0 +1,302 @@
+use crate::database::diff::{dependency_order, AlterOp};
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::sql::parser::parse;
+use crate::table::data::{Collation, Column, DataType, DefaultExpr, Options, Table, Value};
+use std::fmt;
+use std::fs;
+
+// Dumps are written in PostgreSQL's dialect. ENUM columns get a type of their own from
+// CREATE TYPE ... AS ENUM, SET columns are arrays of such a type, AUTOINCREMENT is an
+// identity column, and NOCASE and LATIN_FOLD are collations created at the top of the
+// dump. TTL, masking and ON UPDATE CURRENT_TIMESTAMP have no form there and are left out.
+impl Database {
+    // CREATE TABLE and INSERT statements for every persistent table, referenced tables first
+    pub fn sql_dump(&self) -> String {
+        let tables = self.dump_order();
+        let mut out = format!("-- Dump of database '{}'\n", self.config.name);
+        let mut collations: Vec<Collation> = tables.iter().flat_map(|t| &t.columns).map(Column::collation).collect();
+        collations.sort_by_key(|c| c.to_string());
+        collations.dedup();
+        for collation in collations {
+            out.extend(create_collation(collation));
+        }
+        for table in tables {
+            out.push('\n');
+            out.push_str(&create_statement(table));
+            // Generated columns can't be written to, so when there are any the others are named
+            let stored: Vec<usize> = (0..table.columns.len()).filter(|&i| table.columns[i].generated().is_none()).collect();
+            let target = if stored.len() == table.columns.len() {
+                quote_ident(&table.name)
+            } else {
+                let names: Vec<String> = stored.iter().map(|&i| quote_ident(&table.columns[i].name)).collect();
+                format!("{} ({})", quote_ident(&table.name), names.join(", "))
+            };
+            for row in &table.rows {
+                let values: Vec<String> = stored.iter().map(|&i| sql_literal(&row[i])).collect();
+                out.push_str(&format!("INSERT INTO {} VALUES ({});\n", target, values.join(", ")));
+            }
+        }
+        out
+    }
+
+    pub fn dump_sql(&self, path: &str) -> Result<(), DbError> {
+        fs::write(path, self.sql_dump()).map_err(|e| DbError::IoError(format!("Failed to write dump: {}", e)))
+    }
+
+    // Runs a dump written by dump_sql, or a script of supported statements such as a
+    // PostgreSQL or SQLite dump, against this database. Statements before a failing one
+    // keep their effects.
+    pub fn restore_sql(&mut self, path: &str) -> Result<(), DbError> {
+        let sql = fs::read_to_string(path).map_err(|e| DbError::IoError(format!("Failed to read dump: {}", e)))?;
+
+        for statement in parse(&sql)? {
+            self.execute_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    fn dump_order(&self) -> Vec<&Table> {
+        dependency_order(self.tables.values().filter(|t| !self.memory_only.contains(&t.name)))
+    }
+}
+
+// PostgreSQL statements. Constraints get the names PostgreSQL gives them, e.g.
+// users_email_key for a UNIQUE on users.email. Options with no SQL form are written as
+// comments.
+impl fmt::Display for AlterOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlterOp::CreateTable(table) => write!(f, "{}", create_statement(table).trim_end()),
+            AlterOp::DropTable(name) => write!(f, "DROP TABLE {};", quote_ident(name)),
+            AlterOp::AddColumn { table, column } => write!(
+                f,
+                "{}ALTER TABLE {} ADD COLUMN {};",
+                create_type(table, &column.name, &column.datatype),
+                quote_ident(table),
+                column_definition(table, column)
+            ),
+            AlterOp::DropColumn { table, column } => {
+                write!(f, "ALTER TABLE {} DROP COLUMN {};", quote_ident(table), quote_ident(column))
+            }
+            AlterOp::SetType { table, column, datatype } => write!(
+                f,
+                "{}ALTER TABLE {} ALTER COLUMN {} TYPE {};",
+                create_type(table, column, datatype),
+                quote_ident(table),
+                quote_ident(column),
+                sql_type(table, column, datatype)
+            ),
+            AlterOp::AddOption { table, column, option } => {
+                let alter = format!("ALTER TABLE {}", quote_ident(table));
+                let alter_column = format!("{} ALTER COLUMN {}", alter, quote_ident(column));
+                let constraint = |suffix: &str| quote_ident(&format!("{}_{}_{}", table, column, suffix));
+                match option {
+                    Options::NotNull => write!(f, "{} SET NOT NULL;", alter_column),
+                    Options::Unique => {
+                        write!(f, "{} ADD CONSTRAINT {} UNIQUE ({});", alter, constraint("key"), quote_ident(column))
+                    }
+                    Options::Check(expr) => write!(f, "{} ADD CONSTRAINT {} CHECK ({});", alter, constraint("check"), expr),
+                    Options::FK(target) => write!(
+                        f,
+                        "{} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {};",
+                        alter,
+                        constraint("fkey"),
+                        quote_ident(column),
+                        quote_ident(target)
+                    ),
+                    Options::Default(_) => {
+                        let default = sql_option(option).unwrap_or_default();
+                        write!(f, "{} SET {};", alter_column, default)
+                    }
+                    Options::Autoincrement | Options::Identity { .. } => {
+                        write!(f, "{} ADD {};", alter_column, sql_option(option).unwrap_or_default())
+                    }
+                    other => write!(f, "{} /* add {:?} */;", alter_column, other),
+                }
+            }
+            AlterOp::DropOption { table, column, option } => {
+                let alter = format!("ALTER TABLE {}", quote_ident(table));
+                let alter_column = format!("{} ALTER COLUMN {}", alter, quote_ident(column));
+                let constraint = |suffix: &str| quote_ident(&format!("{}_{}_{}", table, column, suffix));
+                match option {
+                    Options::NotNull => write!(f, "{} DROP NOT NULL;", alter_column),
+                    Options::Unique => write!(f, "{} DROP CONSTRAINT {};", alter, constraint("key")),
+                    Options::Check(_) => write!(f, "{} DROP CONSTRAINT {};", alter, constraint("check")),
+                    Options::FK(_) => write!(f, "{} DROP CONSTRAINT {};", alter, constraint("fkey")),
+                    Options::Default(_) => {
+                        write!(f, "{} DROP DEFAULT;", alter_column)
+                    }
+                    Options::Autoincrement | Options::Identity { .. } => write!(f, "{} DROP IDENTITY;", alter_column),
+                    Options::Generated(_) => write!(f, "{} DROP EXPRESSION;", alter_column),
+                    other => write!(f, "{} /* drop {:?} */;", alter_column, other),
+                }
+            }
+            AlterOp::SetPrimaryKey { table, key: None } => {
+                write!(f, "ALTER TABLE {} DROP CONSTRAINT {};", quote_ident(table), quote_ident(&format!("{}_pkey", table)))
+            }
+            AlterOp::SetPrimaryKey { table, key: Some(key) } => {
+                let names: Vec<String> = key.iter().map(|name| quote_ident(name)).collect();
+                write!(f, "ALTER TABLE {} ADD PRIMARY KEY ({});", quote_ident(table), names.join(", "))
+            }
+        }
+    }
+}
+
+fn create_statement(table: &Table) -> String {
+    let types: String = table
+        .columns
+        .iter()
+        .map(|column| create_type(&table.name, &column.name, &column.datatype))
+        .collect();
+    let mut lines: Vec<String> = table
+        .columns
+        .iter()
+        .map(|column| format!("  {}", column_definition(&table.name, column)))
+        .collect();
+    if let Some(pk) = &table.primary_key {
+        let names: Vec<String> = pk.iter().map(|name| quote_ident(name)).collect();
+        lines.push(format!("  PRIMARY KEY ({})", names.join(", ")));
+    }
+    format!("{}CREATE TABLE {} (\n{}\n);\n", types, quote_ident(&table.name), lines.join(",\n"))
+}
+
+// The type an ENUM or SET column's values are members of, named after the column
+fn create_type(table: &str, column: &str, datatype: &DataType) -> String {
+    match datatype {
+        DataType::Enum(values) | DataType::Set(values) => format!(
+            "CREATE TYPE {} AS ENUM ({});\n",
+            quote_ident(&enum_type_name(table, column)),
+            quote_list(values)
+        ),
+        _ => String::new(),
+    }
+}
+
+fn enum_type_name(table: &str, column: &str) -> String {
+    format!("{}_{}", table, column)
+}
+
+// NOCASE compares as ICU's secondary strength does, and LATIN_FOLD as its primary
+// strength with the text as written breaking ties
+fn create_collation(collation: Collation) -> Option<String> {
+    let level = match collation {
+        Collation::Binary => return None,
+        Collation::CaseInsensitive => 2,
+        Collation::LatinFold => 1,
+    };
+    Some(format!(
+        "CREATE COLLATION IF NOT EXISTS {} (provider = icu, locale = 'und-u-ks-level{}', deterministic = false);\n",
+        quote_ident(&collation.to_string().to_lowercase()),
+        level
+    ))
+}
+
+// COLLATE has to follow the type, and a foreign key's ON UPDATE its REFERENCES
+fn column_definition(table: &str, column: &Column) -> String {
+    let mut parts = vec![quote_ident(&column.name), sql_type(table, &column.name, &column.datatype)];
+    if column.collation() != Collation::Binary {
+        parts.push(format!("COLLATE {}", quote_ident(&column.collation().to_string().to_lowercase())));
+    }
+    for option in &column.options {
+        match option {
+            Options::Collate(_) | Options::OnUpdate(_) => {}
+            Options::FK(_) => {
+                parts.extend(sql_option(option));
+                parts.extend(column.options.iter().filter(|o| matches!(o, Options::OnUpdate(_))).filter_map(sql_option));
+            }
+            _ => parts.extend(sql_option(option)),
+        }
+    }
+    parts.join(" ")
+}
+
+fn sql_type(table: &str, column: &str, datatype: &DataType) -> String {
+    match datatype {
+        DataType::Char(length) => format!("CHAR({})", length),
+        DataType::Varchar(length) => format!("VARCHAR({})", length),
+        DataType::Text => "TEXT".to_string(),
+        DataType::Boolean => "BOOLEAN".to_string(),
+        DataType::Int => "INTEGER".to_string(),
+        DataType::BigInt => "BIGINT".to_string(),
+        DataType::Float => "REAL".to_string(),
+        DataType::Double => "DOUBLE PRECISION".to_string(),
+        DataType::Date => "DATE".to_string(),
+        DataType::Time => "TIME".to_string(),
+        DataType::DateTime => "TIMESTAMP".to_string(),
+        DataType::Point => "POINT".to_string(),
+        DataType::Decimal(precision, scale) => format!("DECIMAL({}, {})", precision, scale),
+        DataType::Enum(_) => quote_ident(&enum_type_name(table, column)),
+        DataType::Set(_) => format!("{}[]", quote_ident(&enum_type_name(table, column))),
+    }
+}
+
+fn sql_option(opt: &Options) -> Option<String> {
+    let sql = match opt {
+        Options::Unique => "UNIQUE".to_string(),
+        Options::NotNull => "NOT NULL".to_string(),
+        Options::Autoincrement => "GENERATED BY DEFAULT AS IDENTITY".to_string(),
+        Options::Default(expr) => format!("DEFAULT {}", sql_default(expr)),
+        Options::Identity { start, increment } => format!(
+            "GENERATED BY DEFAULT AS IDENTITY (START WITH {} INCREMENT BY {})",
+            start, increment
+        ),
+        Options::FK(target) => format!("REFERENCES {}", quote_ident(target)),
+        Options::Check(expr) => format!("CHECK ({})", expr),
+        Options::Generated(expr) => format!("GENERATED ALWAYS AS ({}) STORED", expr),
+        Options::OnUpdate(action) => format!("ON UPDATE {}", action),
+        Options::Collate(collation) => format!("COLLATE {}", quote_ident(&collation.to_string().to_lowercase())),
+        Options::OnUpdateNow | Options::Ttl(_) | Options::Mask(_) | Options::Truncate => return None,
+    };
+    Some(sql)
+}
+
+fn sql_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Boolean(true) => "TRUE".to_string(),
+        Value::Boolean(false) => "FALSE".to_string(),
+        Value::Int(n) => n.to_string(),
+        Value::BigInt(n) => n.to_string(),
+        Value::Float(f) if f.is_finite() => f.to_string(),
+        Value::Double(f) if f.is_finite() => f.to_string(),
+        Value::Decimal(d) => d.to_string(),
+        Value::Enum(val) => quote_str(val),
+        // An array literal, each member in double quotes
+        Value::Set(items) => {
+            let items: Vec<String> =
+                items.iter().map(|item| format!("\"{}\"", item.replace('\\', "\\\\").replace('"', "\\\""))).collect();
+            quote_str(&format!("{{{}}}", items.join(",")))
+        }
+        other => quote_str(&other.to_display_string()),
+    }
+}
+
+fn sql_default(expr: &DefaultExpr) -> String {
+    match expr {
+        DefaultExpr::Literal(value) => sql_literal(value),
+        // A date plus a number is days on, but a timestamp needs an interval
+        DefaultExpr::Add(base, n) => {
+            let sign = if *n < 0 { "-" } else { "+" };
+            match base.as_ref() {
+                DefaultExpr::CurrentTimestamp => {
+                    format!("{} {} INTERVAL '{} days'", sql_default(base), sign, n.unsigned_abs())
+                }
+                _ => format!("{} {} {}", sql_default(base), sign, n.unsigned_abs()),
+            }
+        }
+        other => other.to_string(),
+    }
+}
+
+fn quote_str(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn quote_list(values: &[String]) -> String {
+    values.iter().map(|v| quote_str(v)).collect::<Vec<_>>().join(", ")
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
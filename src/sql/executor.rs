@@ -0,0 +1,353 @@
+use crate::database::config::Durability;
+use crate::database::validators::Database;
+use crate::error::DbError;
+use crate::sql::ast::{Condition, Literal, Select, SelectItem, Statement, WhereExpr};
+use crate::sql::parser::{parse, parse_statement};
+use crate::table::data::{AggFunc, Column, DataType, FilterExpr, OrderBy, QueryStage, ResultSet, SortDirection, Table, Value};
+use crate::table::profiler::{self, returned, scanned};
+use crate::table::resolver::resolve_column_in;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone)]
+pub enum QueryResult {
+    Rows(ResultSet),
+    // Rows inserted, updated or deleted
+    Affected(usize),
+    // CREATE and DROP
+    Done,
+}
+
+impl QueryResult {
+    pub fn rows(&self) -> Option<&ResultSet> {
+        match self {
+            QueryResult::Rows(result) => Some(result),
+            _ => None,
+        }
+    }
+
+    pub fn affected(&self) -> Option<usize> {
+        match self {
+            QueryResult::Affected(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+impl Database {
+    // Runs a single statement, e.g. `SELECT name FROM people WHERE age >= 18 ORDER BY name`
+    // Wrapped in QueryProfile::capture, a SELECT reports the time spent in each stage
+    pub fn execute(&mut self, sql: &str) -> Result<QueryResult, DbError> {
+        let statement = profiler::stage(QueryStage::Parse, || parse_statement(sql)).map_err(DbError::InvalidArgument)?;
+        self.execute_statement(statement)
+    }
+
+    // Runs `;`-separated statements in order, stopping at the first error. Statements that
+    // ran before the error keep their effects.
+    pub fn execute_script(&mut self, sql: &str) -> Result<Vec<QueryResult>, DbError> {
+        profiler::stage(QueryStage::Parse, || parse(sql))
+            .map_err(DbError::InvalidArgument)?
+            .into_iter()
+            .map(|statement| self.execute_statement(statement))
+            .collect()
+    }
+
+    pub fn execute_statement(&mut self, statement: Statement) -> Result<QueryResult, DbError> {
+        match statement {
+            Statement::CreateTable {
+                name,
+                columns,
+                primary_key,
+                if_not_exists,
+                ..
+            } => {
+                if if_not_exists && self.tables.contains_key(&name) {
+                    return Ok(QueryResult::Done);
+                }
+                let table = Table::new(&name, columns, primary_key);
+                self.create_table(table, Durability::Persistent)?;
+                Ok(QueryResult::Done)
+            }
+            Statement::Insert {
+                table,
+                columns,
+                rows,
+            } => self.execute_insert(&table, columns, rows),
+            Statement::Select(select) => self.execute_select(&select).map(QueryResult::Rows),
+            Statement::Update {
+                table,
+                assignments,
+                filter,
+            } => {
+                let target = self.sql_table(&table)?;
+                let mut updates = vec![None; target.columns.len()];
+                for (column, literal) in &assignments {
+                    let i = target.resolve_column(column)?;
+                    updates[i] = Some(literal.to_value(&target.columns[i].datatype)?);
+                }
+
+                let indices = self.target_positions(&table, filter.as_ref())?;
+                self.apply_update(&table, &indices, &updates)
+                    .map(QueryResult::Affected)
+            }
+            Statement::Delete { table, filter } => {
+                let indices = self.target_positions(&table, filter.as_ref())?;
+                self.apply_delete(&table, &indices).map(QueryResult::Affected)
+            }
+            Statement::DropTable { name, if_exists } => {
+                if if_exists && !self.tables.contains_key(&name) {
+                    return Ok(QueryResult::Done);
+                }
+                self.drop_table(&name)?;
+                Ok(QueryResult::Done)
+            }
+        }
+    }
+
+    // All rows go in or none do
+    fn execute_insert(
+        &mut self,
+        table_name: &str,
+        columns: Option<Vec<String>>,
+        rows: Vec<Vec<Literal>>,
+    ) -> Result<QueryResult, DbError> {
+        let table = self.sql_table(table_name)?;
+        let targets = match &columns {
+            Some(names) => names
+                .iter()
+                .map(|name| table.resolve_column(name))
+                .collect::<Result<Vec<usize>, DbError>>()?,
+            None => (0..table.columns.len()).collect(),
+        };
+
+        let mut values = vec![];
+        for row in &rows {
+            if row.len() != targets.len() {
+                return Err(DbError::InvalidArgument(format!(
+                    "INSERT has {} values but {} columns",
+                    row.len(),
+                    targets.len()
+                )));
+            }
+            // Columns left out are NULL, so their defaults apply
+            let mut full = vec![Value::Null; table.columns.len()];
+            for (&i, literal) in targets.iter().zip(row) {
+                full[i] = literal.to_value(&table.columns[i].datatype)?;
+            }
+            values.push(full);
+        }
+
+        self.apply_insert_many(table_name, values).map(QueryResult::Affected)
+    }
+
+    fn execute_select(&self, select: &Select) -> Result<ResultSet, DbError> {
+        let table = self.sql_table(&select.table)?;
+        let filter = profiler::stage(QueryStage::Parse, || {
+            select
+                .filter
+                .as_ref()
+                .map(|filter| to_filter(table, filter))
+                .transpose()
+        })?;
+
+        let aggregated = select
+            .items
+            .iter()
+            .any(|item| matches!(item, SelectItem::Aggregate { .. }));
+        if aggregated || select.group_by.is_some() {
+            return select_grouped(table, select, filter.as_ref());
+        }
+
+        let mut rows: Vec<&Vec<Value>> = match &filter {
+            Some(expr) => table.select_where_expr(expr)?,
+            None => {
+                let rows = table.select_all();
+                scanned(rows.len());
+                rows
+            }
+        };
+        let keys = order_keys(&select.order_by, |name| table.resolve_column(name))?;
+        if !keys.is_empty() {
+            profiler::stage(QueryStage::Sort, || rows.sort_by(|a, b| compare_rows(&keys, &table.columns, a, b)));
+        }
+
+        let mut projection = vec![];
+        for item in &select.items {
+            match item {
+                SelectItem::Wildcard => projection.extend(0..table.columns.len()),
+                SelectItem::Column(name) => projection.push(table.resolve_column(name)?),
+                SelectItem::Aggregate { .. } => unreachable!(),
+            }
+        }
+
+        let columns = projection.iter().map(|&i| table.columns[i].clone()).collect();
+        let rows: Vec<Vec<Value>> = profiler::stage(QueryStage::Materialize, || {
+            select
+                .limit
+                .apply(rows)
+                .rows
+                .into_iter()
+                .map(|row| projection.iter().map(|&i| row[i].clone()).collect())
+                .collect()
+        });
+        returned(rows.len());
+        Ok(ResultSet::new(columns, rows))
+    }
+
+    fn sql_table(&self, name: &str) -> Result<&Table, DbError> {
+        self.tables
+            .get(name)
+            .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found", name)))
+    }
+
+    // Positions an UPDATE or DELETE applies to; every live row without a WHERE clause
+    fn target_positions(&self, table_name: &str, filter: Option<&WhereExpr>) -> Result<Vec<usize>, DbError> {
+        let table = self.sql_table(table_name)?;
+        match filter {
+            Some(filter) => {
+                let expr = to_filter(table, filter)?;
+                self.filter_positions(table_name, &expr)
+            }
+            None => Ok((0..table.rows.len())
+                .filter(|&i| !table.is_expired(&table.rows[i]))
+                .collect()),
+        }
+    }
+}
+
+fn to_filter(table: &Table, filter: &WhereExpr) -> Result<FilterExpr, DbError> {
+    Ok(match filter {
+        WhereExpr::Compare(condition) => compare_filter(table, condition)?,
+        WhereExpr::And(a, b) => FilterExpr::And(Box::new(to_filter(table, a)?), Box::new(to_filter(table, b)?)),
+        WhereExpr::Or(a, b) => FilterExpr::Or(Box::new(to_filter(table, a)?), Box::new(to_filter(table, b)?)),
+        WhereExpr::Not(inner) => FilterExpr::Not(Box::new(to_filter(table, inner)?)),
+    })
+}
+
+// Types the literal by the column it is compared with
+fn compare_filter(table: &Table, condition: &Condition) -> Result<FilterExpr, DbError> {
+    let column = &table.columns[table.resolve_column(&condition.column)?];
+    let value = condition
+        .value
+        .to_value(&column.datatype)
+        .map_err(|e| DbError::TypeMismatch(format!("Invalid literal for column '{}': {}", condition.column, e)))?;
+    Ok(condition.op.to_filter(condition.column.clone(), value))
+}
+
+// With GROUP BY every plain column must be the grouping column; without it the whole
+// filtered table is one group
+fn select_grouped(table: &Table, select: &Select, filter: Option<&FilterExpr>) -> Result<ResultSet, DbError> {
+    let group_col = select
+        .group_by
+        .as_deref()
+        .map(|name| table.canonical_column(name))
+        .transpose()?;
+
+    let mut columns = vec![];
+    let mut agg_cols: Vec<(&str, AggFunc)> = vec![];
+    for item in &select.items {
+        match item {
+            SelectItem::Column(name) if group_col.is_some() && table.canonical_column(name)? == group_col.unwrap() => {
+                let i = table.resolve_column(name)?;
+                columns.push(table.columns[i].clone());
+            }
+            SelectItem::Column(name) => {
+                return Err(DbError::SchemaError(format!(
+                    "Column '{}' must appear in GROUP BY or be aggregated",
+                    name
+                )))
+            }
+            SelectItem::Wildcard => {
+                return Err(DbError::InvalidArgument(
+                    "SELECT * cannot be combined with aggregates or GROUP BY".to_string(),
+                ))
+            }
+            SelectItem::Aggregate { func, column } => {
+                // COUNT(*) counts rows, so any column will do
+                let col = match column {
+                    Some(name) => table.canonical_column(name)?,
+                    None => table.columns.first().map(|c| c.name.as_str()).unwrap_or(""),
+                };
+                let datatype = match func {
+                    // COUNT(*) on a table without columns still has a type
+                    AggFunc::Count => DataType::BigInt,
+                    _ => func.datatype(&table.columns[table.resolve_column(col)?].datatype),
+                };
+                columns.push(Column {
+                    name: func.label(column.as_deref().unwrap_or("*")),
+                    datatype,
+                    options: vec![],
+                });
+                agg_cols.push((col, func.clone()));
+            }
+        }
+    }
+
+    let predicate = filter.map(|expr| expr.to_predicate(table));
+    let predicate = predicate.as_deref();
+
+    let mut groups: Vec<(Value, Vec<Value>)> = match group_col {
+        Some(group_col) => table
+            .aggregate_group(group_col, &agg_cols, predicate)?
+            .into_iter()
+            .map(|(key, results)| (key, results.iter().map(|r| r.as_value()).collect()))
+            .collect(),
+        None => {
+            let results = table.aggregate_all_where(&agg_cols, predicate)?;
+            vec![(Value::Null, results.iter().map(|r| r.as_value()).collect())]
+        }
+    };
+    scanned(table.rows.len());
+    // Groups come back in hash order; sort by key so output is stable before ORDER BY
+    profiler::stage(QueryStage::Sort, || groups.sort_by(|a, b| a.0.cmp(&b.0)));
+
+    let rows: Vec<Vec<Value>> = groups
+        .into_iter()
+        .map(|(key, aggregates)| {
+            let mut aggregates = aggregates.into_iter();
+            select
+                .items
+                .iter()
+                .map(|item| match item {
+                    SelectItem::Aggregate { .. } => aggregates.next().unwrap(),
+                    _ => key.clone(),
+                })
+                .collect()
+        })
+        .collect();
+
+    let keys = order_keys(&select.order_by, |name| resolve_column_in(&columns, &table.name, name))?;
+    let mut rows = rows;
+    if !keys.is_empty() {
+        profiler::stage(QueryStage::Sort, || rows.sort_by(|a, b| compare_rows(&keys, &columns, a, b)));
+    }
+    let rows = select.limit.apply(rows).rows;
+    returned(rows.len());
+    Ok(ResultSet::new(columns, rows))
+}
+
+fn order_keys<F>(order_by: &[(String, SortDirection)], resolve: F) -> Result<Vec<(usize, OrderBy)>, DbError>
+where
+    F: Fn(&str) -> Result<usize, DbError>,
+{
+    order_by
+        .iter()
+        .map(|(name, direction)| {
+            let spec = match direction {
+                SortDirection::Asc => OrderBy::asc(name),
+                SortDirection::Desc => OrderBy::desc(name),
+            };
+            Ok((resolve(name)?, spec))
+        })
+        .collect()
+}
+
+// Each key compares under its column's collation
+fn compare_rows(keys: &[(usize, OrderBy)], columns: &[Column], a: &[Value], b: &[Value]) -> Ordering {
+    keys.iter()
+        .map(|(i, spec)| {
+            let collation = columns[*i].collation();
+            spec.compare(&collation.key(&a[*i]), &collation.key(&b[*i]))
+        })
+        .find(|ord| *ord != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
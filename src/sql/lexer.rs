@@ -0,0 +1,121 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    // Keywords are identifiers too; the parser matches them case-insensitively
+    Ident(String),
+    // A "double quoted" identifier, never treated as a keyword
+    QuotedIdent(String),
+    Number(String),
+    Str(String),
+    Symbol(&'static str),
+}
+
+impl Token {
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Ident(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(word) | Token::Number(word) => write!(f, "{}", word),
+            Token::QuotedIdent(word) => write!(f, "\"{}\"", word),
+            Token::Str(text) => write!(f, "'{}'", text.replace('\'', "''")),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+// Longer symbols first so `<=` is not read as `<` then `=`
+const SYMBOLS: [&str; 18] = [
+    "<=", ">=", "!=", "<>", "(", ")", "[", "]", ",", ";", "*", "=", "<", ">", ".", "+", "-", "/",
+];
+
+pub fn tokenize(sql: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '-' && chars.get(i + 1) == Some(&'-') {
+            // Line comment
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '\'' || c == '"' {
+            let (text, end) = read_quoted(&chars, i)?;
+            tokens.push(if c == '\'' {
+                Token::Str(text)
+            } else {
+                Token::QuotedIdent(text)
+            });
+            i = end;
+        } else if c.is_ascii_digit() || (c == '-' && starts_number(&chars, i + 1) && !ends_operand(tokens.last())) {
+            let start = i;
+            i += 1;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit() || (chars[i] == '.' && starts_number(&chars, i + 1)))
+            {
+                i += 1;
+            }
+            tokens.push(Token::Number(chars[start..i].iter().collect()));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let symbol = SYMBOLS
+                .into_iter()
+                .find(|s| rest.starts_with(s))
+                .ok_or_else(|| format!("Unexpected character '{}' at position {}", c, i))?;
+            tokens.push(Token::Symbol(symbol));
+            i += symbol.len();
+        }
+    }
+
+    Ok(tokens)
+}
+
+// After a value or a name, `-` subtracts: `CURRENT_DATE-30` is CURRENT_DATE minus 30.
+// A minus after a keyword such as DEFAULT is read back as a sign by the parsers.
+fn ends_operand(token: Option<&Token>) -> bool {
+    matches!(
+        token,
+        Some(Token::Ident(_) | Token::QuotedIdent(_) | Token::Number(_) | Token::Str(_) | Token::Symbol(")" | "]"))
+    )
+}
+
+fn starts_number(chars: &[char], i: usize) -> bool {
+    chars.get(i).is_some_and(|c| c.is_ascii_digit())
+}
+
+// A doubled quote inside the literal stands for one quote character
+fn read_quoted(chars: &[char], start: usize) -> Result<(String, usize), String> {
+    let quote = chars[start];
+    let mut text = String::new();
+    let mut i = start + 1;
+
+    while i < chars.len() {
+        if chars[i] == quote {
+            if chars.get(i + 1) == Some(&quote) {
+                text.push(quote);
+                i += 2;
+                continue;
+            }
+            return Ok((text, i + 1));
+        }
+        text.push(chars[i]);
+        i += 1;
+    }
+
+    Err(format!("Unterminated {} starting at position {}", if quote == '\'' { "string" } else { "identifier" }, start))
+}
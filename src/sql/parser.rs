@@ -0,0 +1,734 @@
+use crate::sql::ast::{CompareOp, Condition, Literal, Select, SelectItem, Statement, WhereExpr};
+use crate::sql::lexer::{tokenize, Token};
+use crate::table::data::{AggFunc, Collation, Column, DataType, DefaultExpr, FkAction, Limit, Options, SortDirection, MAX_VARCHAR_LENGTH};
+use crate::table::decimal::MAX_PRECISION;
+use std::collections::HashMap;
+
+// Unquoted, these can't name a table or column; quote them ("order") to use them as names
+const RESERVED: [&str; 19] = [
+    "SELECT", "FROM", "WHERE", "GROUP", "ORDER", "BY", "INSERT", "INTO", "VALUES", "UPDATE", "SET",
+    "DELETE", "CREATE", "DROP", "LIMIT", "OFFSET", "AND", "OR", "NOT",
+];
+const AGGREGATES: [&str; 9] = ["count", "sum", "avg", "min", "max", "stddev", "var", "variance", "median"];
+
+pub fn parse(sql: &str) -> Result<Vec<Statement>, String> {
+    let mut parser = Parser {
+        tokens: tokenize(sql)?,
+        pos: 0,
+        types: HashMap::new(),
+    };
+
+    let mut statements = vec![];
+    loop {
+        while parser.eat_symbol(";") {}
+        if parser.peek().is_none() {
+            break;
+        }
+        statements.extend(parser.statement()?);
+        if parser.peek().is_some() && !parser.eat_symbol(";") {
+            return Err(parser.unexpected("';' or end of input"));
+        }
+    }
+    Ok(statements)
+}
+
+// Exactly one statement, with or without a trailing semicolon
+pub fn parse_statement(sql: &str) -> Result<Statement, String> {
+    let mut statements = parse(sql)?;
+    match statements.len() {
+        0 => Err("No SQL statement given".to_string()),
+        1 => Ok(statements.remove(0)),
+        n => Err(format!("Expected one statement, found {}", n)),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    // Enum types declared earlier in the script by CREATE TYPE, by name
+    types: HashMap<String, Vec<String>>,
+}
+
+impl Parser {
+    // None for a statement that only matters to other engines: the session settings,
+    // PRAGMAs, transaction markers and collations their dumps begin with. CREATE TYPE
+    // only declares a type for the statements after it.
+    fn statement(&mut self) -> Result<Option<Statement>, String> {
+        if ["SET", "PRAGMA", "BEGIN", "COMMIT"].iter().any(|keyword| self.eat_keyword(keyword)) {
+            self.skip_statement();
+            return Ok(None);
+        }
+        if self.eat_keyword("CREATE") {
+            if self.eat_keyword("TYPE") {
+                self.create_type()?;
+                return Ok(None);
+            }
+            if self.eat_keyword("COLLATION") {
+                self.skip_statement();
+                return Ok(None);
+            }
+            self.expect_keyword("TABLE")?;
+            return self.create_table().map(Some);
+        }
+        self.statement_with_effect().map(Some)
+    }
+
+    fn statement_with_effect(&mut self) -> Result<Statement, String> {
+        if self.eat_keyword("INSERT") {
+            self.expect_keyword("INTO")?;
+            self.insert()
+        } else if self.eat_keyword("SELECT") {
+            self.select().map(Statement::Select)
+        } else if self.eat_keyword("UPDATE") {
+            self.update()
+        } else if self.eat_keyword("DELETE") {
+            self.expect_keyword("FROM")?;
+            let table = self.ident()?;
+            let filter = self.where_clause()?;
+            Ok(Statement::Delete { table, filter })
+        } else if self.eat_keyword("DROP") {
+            self.expect_keyword("TABLE")?;
+            let if_exists = self.eat_keyword("IF");
+            if if_exists {
+                self.expect_keyword("EXISTS")?;
+            }
+            let name = self.ident()?;
+            Ok(Statement::DropTable { name, if_exists })
+        } else {
+            Err(self.unexpected("CREATE, INSERT, SELECT, UPDATE, DELETE or DROP"))
+        }
+    }
+
+    fn skip_statement(&mut self) {
+        while self.peek().is_some_and(|token| *token != Token::Symbol(";")) {
+            self.pos += 1;
+        }
+    }
+
+    // After CREATE TYPE: `name AS ENUM ('a', 'b')`
+    fn create_type(&mut self) -> Result<(), String> {
+        let name = self.ident()?;
+        self.expect_keyword("AS")?;
+        self.expect_keyword("ENUM")?;
+        let values = self.quoted_values()?;
+        self.types.insert(name, values);
+        Ok(())
+    }
+
+    fn create_table(&mut self) -> Result<Statement, String> {
+        let if_not_exists = self.eat_keyword("IF");
+        if if_not_exists {
+            self.expect_keyword("NOT")?;
+            self.expect_keyword("EXISTS")?;
+        }
+        let name = self.ident()?;
+        self.expect_symbol("(")?;
+
+        let mut columns = vec![];
+        let mut primary_key = None;
+        loop {
+            if self.eat_keyword("PRIMARY") {
+                self.expect_keyword("KEY")?;
+                if primary_key.is_some() {
+                    return Err(format!("Table '{}' declares more than one primary key", name));
+                }
+                primary_key = Some(self.ident_list()?);
+            } else {
+                let (column, is_key) = self.column_def()?;
+                if is_key {
+                    if primary_key.is_some() {
+                        return Err(format!("Table '{}' declares more than one primary key", name));
+                    }
+                    primary_key = Some(vec![column.name.clone()]);
+                }
+                columns.push(column);
+            }
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+        self.expect_symbol(")")?;
+
+        Ok(Statement::CreateTable {
+            name,
+            columns,
+            primary_key,
+            if_not_exists,
+        })
+    }
+
+    // Returns the column and a flag set for an inline PRIMARY KEY
+    fn column_def(&mut self) -> Result<(Column, bool), String> {
+        let name = self.ident()?;
+        let datatype = self.datatype()?;
+        let mut options = vec![];
+        let mut is_key = false;
+
+        loop {
+            if self.eat_keyword("PRIMARY") {
+                self.expect_keyword("KEY")?;
+                is_key = true;
+            } else if self.eat_keyword("NOT") {
+                self.expect_keyword("NULL")?;
+                options.push(Options::NotNull);
+            } else if self.eat_keyword("UNIQUE") {
+                options.push(Options::Unique);
+            } else if self.eat_keyword("AUTOINCREMENT") || self.eat_keyword("AUTO_INCREMENT") {
+                options.push(Options::Autoincrement);
+            } else if self.eat_keyword("DEFAULT") {
+                options.push(self.default_option(&datatype)?);
+            } else if self.eat_keyword("ON") {
+                self.expect_keyword("UPDATE")?;
+                options.push(self.on_update()?);
+            } else if self.eat_keyword("GENERATED") {
+                options.push(self.generated()?);
+            } else if self.eat_keyword("CHECK") {
+                options.push(Options::Check(self.check_expr()?.parse()?));
+            } else if self.eat_keyword("REFERENCES") {
+                options.push(Options::FK(self.ident()?));
+            } else if self.eat_keyword("COLLATE") {
+                options.push(Options::Collate(self.collation()?));
+            } else {
+                break;
+            }
+        }
+
+        Ok((
+            Column {
+                name,
+                datatype,
+                options,
+            },
+            is_key,
+        ))
+    }
+
+    // After GENERATED: `ALWAYS AS (expr)`, or an identity
+    fn generated(&mut self) -> Result<Options, String> {
+        if !self.eat_keyword("ALWAYS") {
+            return self.identity();
+        }
+        self.expect_keyword("AS")?;
+        let expr = self.check_expr()?.parse()?;
+        self.eat_keyword("STORED");
+        Ok(Options::Generated(expr))
+    }
+
+    // After GENERATED: `BY DEFAULT AS IDENTITY [(START WITH n INCREMENT BY n)]`
+    fn identity(&mut self) -> Result<Options, String> {
+        for keyword in ["BY", "DEFAULT", "AS", "IDENTITY"] {
+            self.expect_keyword(keyword)?;
+        }
+        // Without a sequence it is the same as AUTOINCREMENT
+        if !self.eat_symbol("(") {
+            return Ok(Options::Autoincrement);
+        }
+        self.expect_keyword("START")?;
+        self.expect_keyword("WITH")?;
+        let start = self.integer()?;
+        self.expect_keyword("INCREMENT")?;
+        self.expect_keyword("BY")?;
+        let increment = self.integer()?;
+        self.expect_symbol(")")?;
+        Ok(Options::Identity { start, increment })
+    }
+
+    // After DEFAULT: a literal, CURRENT_DATE or CURRENT_TIMESTAMP, optionally followed by
+    // `+ n` or `- n` days, or PostgreSQL's `+ INTERVAL 'n days'`
+    fn default_option(&mut self, datatype: &DataType) -> Result<Options, String> {
+        let base = if self.eat_keyword("CURRENT_DATE") {
+            DefaultExpr::CurrentDate
+        } else if self.eat_keyword("CURRENT_TIMESTAMP") {
+            DefaultExpr::CurrentTimestamp
+        } else {
+            DefaultExpr::Literal(self.literal()?.to_value(datatype).map_err(|e| e.to_string())?)
+        };
+
+        let offset = if self.eat_symbol("+") {
+            Some(self.days()?)
+        } else if self.eat_symbol("-") {
+            Some(-self.days()?)
+        } else {
+            None
+        };
+        Ok(match (base, offset) {
+            (base, None) => Options::Default(base),
+            (base, Some(n)) => Options::Default(DefaultExpr::Add(Box::new(base), n)),
+        })
+    }
+
+    fn days(&mut self) -> Result<i64, String> {
+        if !self.eat_keyword("INTERVAL") {
+            return self.integer();
+        }
+        let interval = match self.next() {
+            Some(Token::Str(text)) => text,
+            _ => return Err(self.unexpected_previous("an interval such as '30 days'")),
+        };
+        match interval.split_whitespace().collect::<Vec<_>>()[..] {
+            [n, "day" | "days"] => n.parse().map_err(|_| format!("Invalid interval '{}'", interval)),
+            _ => Err(format!("Only intervals in days are supported, not '{}'", interval)),
+        }
+    }
+
+    // After ON UPDATE: CURRENT_TIMESTAMP, or a foreign key's CASCADE, SET NULL,
+    // RESTRICT or NO ACTION (the same as RESTRICT)
+    fn on_update(&mut self) -> Result<Options, String> {
+        if self.eat_keyword("CURRENT_TIMESTAMP") {
+            return Ok(Options::OnUpdateNow);
+        }
+        let action = if self.eat_keyword("CASCADE") {
+            FkAction::Cascade
+        } else if self.eat_keyword("SET") {
+            self.expect_keyword("NULL")?;
+            FkAction::SetNull
+        } else if self.eat_keyword("RESTRICT") {
+            FkAction::Restrict
+        } else {
+            self.expect_keyword("NO")?;
+            self.expect_keyword("ACTION")?;
+            FkAction::Restrict
+        };
+        Ok(Options::OnUpdate(action))
+    }
+
+    // After COLLATE, by name with or without quotes; NOCASE is SQLite's name for
+    // case-insensitive comparison, and "C" PostgreSQL's for binary
+    fn collation(&mut self) -> Result<Collation, String> {
+        let name = match self.next() {
+            Some(Token::Ident(name) | Token::QuotedIdent(name)) => name.to_ascii_uppercase(),
+            _ => return Err(self.unexpected_previous("a collation")),
+        };
+        match name.as_str() {
+            "BINARY" | "C" => Ok(Collation::Binary),
+            "NOCASE" => Ok(Collation::CaseInsensitive),
+            "LATIN_FOLD" => Ok(Collation::LatinFold),
+            _ => Err(format!("Unsupported collation '{}'", name)),
+        }
+    }
+
+    // The expression is kept as text, its tokens separated by single spaces
+    fn check_expr(&mut self) -> Result<String, String> {
+        self.expect_symbol("(")?;
+        let mut parts = vec![];
+        let mut depth = 0;
+        loop {
+            match self.next() {
+                Some(Token::Symbol(")")) if depth == 0 => break,
+                Some(token) => {
+                    match token {
+                        Token::Symbol("(") => depth += 1,
+                        Token::Symbol(")") => depth -= 1,
+                        _ => {}
+                    }
+                    parts.push(token.to_string());
+                }
+                None => return Err(self.unexpected_previous("')'")),
+            }
+        }
+        Ok(parts.join(" "))
+    }
+
+    fn count(&mut self) -> Result<usize, String> {
+        match self.next() {
+            Some(Token::Number(n)) => n.parse().map_err(|_| format!("Expected a row count but found '{}'", n)),
+            _ => Err(self.unexpected_previous("a row count")),
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64, String> {
+        let sign = if self.eat_symbol("-") { "-" } else { "" };
+        match self.next() {
+            Some(Token::Number(n)) => {
+                format!("{}{}", sign, n).parse().map_err(|_| format!("Expected an integer but found '{}'", n))
+            }
+            _ => Err(self.unexpected_previous("an integer")),
+        }
+    }
+
+    // ENUM and SET list their allowed values, e.g. ENUM('small', 'large'). A type from
+    // CREATE TYPE is an ENUM, and an array of one a SET.
+    fn datatype(&mut self) -> Result<DataType, String> {
+        let word = match self.next() {
+            Some(Token::Ident(word) | Token::QuotedIdent(word)) if self.types.contains_key(&word) => {
+                let values = self.types[&word].clone();
+                if self.eat_symbol("[") {
+                    self.expect_symbol("]")?;
+                    return Ok(DataType::Set(values));
+                }
+                return Ok(DataType::Enum(values));
+            }
+            Some(Token::Ident(word)) => word.to_ascii_uppercase(),
+            _ => return Err(self.unexpected_previous("a column type")),
+        };
+        let datatype = match word.as_str() {
+            // CHAR alone is a single character; VARCHAR alone allows the longest strings
+            "CHAR" | "CHARACTER" | "VARCHAR" | "STRING" => {
+                let length = if self.eat_symbol("(") {
+                    let length = self.type_argument()?;
+                    self.expect_symbol(")")?;
+                    Some(length)
+                } else {
+                    None
+                };
+                let datatype = match word.as_str() {
+                    "CHAR" | "CHARACTER" => DataType::Char(length.unwrap_or(1)),
+                    _ => DataType::Varchar(length.unwrap_or(MAX_VARCHAR_LENGTH)),
+                };
+                return Ok(datatype);
+            }
+            "TEXT" => DataType::Text,
+            "BOOLEAN" | "BOOL" => DataType::Boolean,
+            "INT" | "INTEGER" => DataType::Int,
+            "BIGINT" => DataType::BigInt,
+            "FLOAT" | "REAL" => DataType::Float,
+            "DOUBLE" => {
+                self.eat_keyword("PRECISION");
+                DataType::Double
+            }
+            "DATE" => DataType::Date,
+            "TIME" => DataType::Time,
+            "DATETIME" | "TIMESTAMP" => DataType::DateTime,
+            "POINT" => DataType::Point,
+            // Without arguments: the widest precision and no fractional digits
+            "DECIMAL" | "NUMERIC" | "DEC" => {
+                let (mut precision, mut scale) = (MAX_PRECISION, 0);
+                if self.eat_symbol("(") {
+                    precision = self.type_argument()?;
+                    if self.eat_symbol(",") {
+                        scale = self.type_argument()?;
+                    }
+                    self.expect_symbol(")")?;
+                }
+                return Ok(DataType::Decimal(precision, scale));
+            }
+            "ENUM" | "SET" => {
+                let values = self.quoted_values()?;
+                return Ok(if word == "ENUM" { DataType::Enum(values) } else { DataType::Set(values) });
+            }
+            _ => return Err(format!("Unsupported column type '{}'", word)),
+        };
+
+        // Display widths such as INT(11) are accepted but not enforced
+        if self.eat_symbol("(") {
+            match self.next() {
+                Some(Token::Number(_)) => self.expect_symbol(")")?,
+                _ => return Err(self.unexpected_previous("a length")),
+            }
+        }
+        Ok(datatype)
+    }
+
+    // e.g. ('small', 'large')
+    fn quoted_values(&mut self) -> Result<Vec<String>, String> {
+        self.expect_symbol("(")?;
+        let mut values = vec![];
+        while !self.eat_symbol(")") {
+            if !values.is_empty() {
+                self.expect_symbol(",")?;
+            }
+            match self.next() {
+                Some(Token::Str(value)) => values.push(value),
+                _ => return Err(self.unexpected_previous("a quoted value")),
+            }
+        }
+        Ok(values)
+    }
+
+    fn type_argument(&mut self) -> Result<u32, String> {
+        match self.next() {
+            Some(Token::Number(n)) => n.parse().map_err(|_| format!("Invalid type argument '{}'", n)),
+            _ => Err(self.unexpected_previous("a number")),
+        }
+    }
+
+    fn insert(&mut self) -> Result<Statement, String> {
+        let table = self.ident()?;
+        let columns = if self.peek() == Some(&Token::Symbol("(")) {
+            Some(self.ident_list()?)
+        } else {
+            None
+        };
+        self.expect_keyword("VALUES")?;
+
+        let mut rows = vec![];
+        loop {
+            self.expect_symbol("(")?;
+            let mut row = vec![self.literal()?];
+            while self.eat_symbol(",") {
+                row.push(self.literal()?);
+            }
+            self.expect_symbol(")")?;
+            rows.push(row);
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+
+        Ok(Statement::Insert {
+            table,
+            columns,
+            rows,
+        })
+    }
+
+    fn select(&mut self) -> Result<Select, String> {
+        let mut items = vec![self.select_item()?];
+        while self.eat_symbol(",") {
+            items.push(self.select_item()?);
+        }
+        self.expect_keyword("FROM")?;
+        let table = self.ident()?;
+        let filter = self.where_clause()?;
+
+        let group_by = if self.eat_keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            Some(self.column_ref()?)
+        } else {
+            None
+        };
+
+        let mut order_by = vec![];
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let column = self.column_ref()?;
+                let direction = if self.eat_keyword("DESC") {
+                    SortDirection::Desc
+                } else {
+                    self.eat_keyword("ASC");
+                    SortDirection::Asc
+                };
+                order_by.push((column, direction));
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        // LIMIT n [OFFSET m], or OFFSET m alone
+        let mut limit = Limit::default();
+        if self.eat_keyword("LIMIT") {
+            limit.count = Some(self.count()?);
+        }
+        if self.eat_keyword("OFFSET") {
+            limit.offset = self.count()?;
+        }
+
+        Ok(Select {
+            table,
+            items,
+            filter,
+            group_by,
+            order_by,
+            limit,
+        })
+    }
+
+    fn select_item(&mut self) -> Result<SelectItem, String> {
+        if self.eat_symbol("*") {
+            return Ok(SelectItem::Wildcard);
+        }
+
+        let is_call = self.tokens.get(self.pos + 1) == Some(&Token::Symbol("("));
+        let mut func: AggFunc = match self.peek() {
+            Some(Token::Ident(word)) if is_call && AGGREGATES.contains(&word.to_ascii_lowercase().as_str()) => {
+                word.parse()?
+            }
+            _ => return self.column_ref().map(SelectItem::Column),
+        };
+
+        self.pos += 2;
+        if func == AggFunc::Count && self.eat_keyword("DISTINCT") {
+            func = AggFunc::CountDistinct;
+        }
+        let column = if self.eat_symbol("*") {
+            if func != AggFunc::Count {
+                return Err(format!("{}(*) is not supported; name a column", func.to_string().to_uppercase()));
+            }
+            None
+        } else {
+            Some(self.column_ref()?)
+        };
+        self.expect_symbol(")")?;
+        Ok(SelectItem::Aggregate { func, column })
+    }
+
+    fn update(&mut self) -> Result<Statement, String> {
+        let table = self.ident()?;
+        self.expect_keyword("SET")?;
+
+        let mut assignments = vec![];
+        loop {
+            let column = self.column_ref()?;
+            self.expect_symbol("=")?;
+            assignments.push((column, self.literal()?));
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+        let filter = self.where_clause()?;
+
+        Ok(Statement::Update {
+            table,
+            assignments,
+            filter,
+        })
+    }
+
+    fn where_clause(&mut self) -> Result<Option<WhereExpr>, String> {
+        if !self.eat_keyword("WHERE") {
+            return Ok(None);
+        }
+        self.or_expr().map(Some)
+    }
+
+    fn or_expr(&mut self) -> Result<WhereExpr, String> {
+        let mut expr = self.and_expr()?;
+        while self.eat_keyword("OR") {
+            expr = WhereExpr::Or(Box::new(expr), Box::new(self.and_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn and_expr(&mut self) -> Result<WhereExpr, String> {
+        let mut expr = self.not_expr()?;
+        while self.eat_keyword("AND") {
+            expr = WhereExpr::And(Box::new(expr), Box::new(self.not_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn not_expr(&mut self) -> Result<WhereExpr, String> {
+        if self.eat_keyword("NOT") {
+            return Ok(WhereExpr::Not(Box::new(self.not_expr()?)));
+        }
+        if self.eat_symbol("(") {
+            let expr = self.or_expr()?;
+            self.expect_symbol(")")?;
+            return Ok(expr);
+        }
+        self.condition().map(WhereExpr::Compare)
+    }
+
+    fn condition(&mut self) -> Result<Condition, String> {
+        let column = self.column_ref()?;
+        if self.eat_keyword("IS") {
+            let op = if self.eat_keyword("NOT") { CompareOp::IsNotNull } else { CompareOp::IsNull };
+            self.expect_keyword("NULL")?;
+            return Ok(Condition { column, op, value: Literal::Null });
+        }
+        let op = match self.next() {
+            Some(Token::Symbol("=")) => CompareOp::Eq,
+            Some(Token::Symbol("!=")) | Some(Token::Symbol("<>")) => CompareOp::Ne,
+            Some(Token::Symbol("<")) => CompareOp::Lt,
+            Some(Token::Symbol("<=")) => CompareOp::Le,
+            Some(Token::Symbol(">")) => CompareOp::Gt,
+            Some(Token::Symbol(">=")) => CompareOp::Ge,
+            _ => return Err(self.unexpected_previous("a comparison operator")),
+        };
+        let value = self.literal()?;
+
+        Ok(Condition { column, op, value })
+    }
+
+    fn literal(&mut self) -> Result<Literal, String> {
+        if self.eat_symbol("-") {
+            return match self.next() {
+                Some(Token::Number(n)) => Ok(Literal::Number(format!("-{}", n))),
+                _ => Err(self.unexpected_previous("a number")),
+            };
+        }
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Literal::Number(n)),
+            Some(Token::Str(s)) => Ok(Literal::Str(s)),
+            Some(token) if token.is_keyword("NULL") => Ok(Literal::Null),
+            Some(token) if token.is_keyword("TRUE") => Ok(Literal::Bool(true)),
+            Some(token) if token.is_keyword("FALSE") => Ok(Literal::Bool(false)),
+            _ => Err(self.unexpected_previous("a literal value")),
+        }
+    }
+
+    // `column` or `table.column`; the table resolves qualifiers itself
+    fn column_ref(&mut self) -> Result<String, String> {
+        let mut name = self.ident()?;
+        while self.eat_symbol(".") {
+            name.push('.');
+            name.push_str(&self.ident()?);
+        }
+        Ok(name)
+    }
+
+    fn ident_list(&mut self) -> Result<Vec<String>, String> {
+        self.expect_symbol("(")?;
+        let mut names = vec![self.column_ref()?];
+        while self.eat_symbol(",") {
+            names.push(self.column_ref()?);
+        }
+        self.expect_symbol(")")?;
+        Ok(names)
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Ident(name)) if !RESERVED.contains(&name.to_ascii_uppercase().as_str()) => Ok(name),
+            Some(Token::QuotedIdent(name)) => Ok(name),
+            _ => Err(self.unexpected_previous("a name")),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek().is_some_and(|t| t.is_keyword(keyword));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(keyword))
+        }
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), String> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", symbol)))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> String {
+        match self.peek() {
+            Some(token) => format!("Expected {} but found '{}'", expected, token),
+            None => format!("Expected {} but reached the end of input", expected),
+        }
+    }
+
+    // For errors raised after next() has already consumed the offending token
+    fn unexpected_previous(&mut self, expected: &str) -> String {
+        self.pos -= 1;
+        self.unexpected(expected)
+    }
+}
@@ -0,0 +1,89 @@
+use crate::error::DbError;
+use crate::storage::compress::{self, CompressedWriter, Compression};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+// How far a save goes to get its files onto the disk before returning. Every mode writes to
+// a temporary file and renames it over the old one, so a crash of the process leaves the
+// old file or the new one whole; the modes differ in what survives losing power.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncMode {
+    // No fsync: the OS may still hold the new file when the rename is done
+    Off,
+    // The file is synced before it's renamed into place
+    Normal,
+    // The directory is synced after the rename too, so the rename itself is durable
+    #[default]
+    Full,
+}
+
+// Where a file is written before it's renamed to `path`
+pub fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+// Writes `path` through a temporary file, compressed as its extension says, and renames it
+// into place once `write` and the sync are done. On failure the old file is untouched and
+// the temporary one is removed.
+pub fn write_file(
+    path: &Path,
+    sync: SyncMode,
+    write: impl FnOnce(&mut CompressedWriter) -> Result<(), DbError>,
+) -> Result<(), DbError> {
+    let temp = write_temp(path, sync, write)?;
+    let io_error = |e: io::Error| DbError::IoError(format!("Failed to write {}: {}", path.display(), e));
+    let result = (|| {
+        fs::rename(&temp, path).map_err(io_error)?;
+        if sync == SyncMode::Full {
+            sync_dir(path.parent().unwrap_or(Path::new("."))).map_err(io_error)?;
+        }
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+// Does what write_file does short of the rename, which is left to the caller, e.g. to make
+// through a journal along with a save's other files. Returns the temporary file.
+pub fn write_temp(
+    path: &Path,
+    sync: SyncMode,
+    write: impl FnOnce(&mut CompressedWriter) -> Result<(), DbError>,
+) -> Result<PathBuf, DbError> {
+    let temp = temp_path(path);
+    let io_error = |e: io::Error| DbError::IoError(format!("Failed to write {}: {}", path.display(), e));
+    let result = (|| {
+        let mut out = compress::wrap(File::create(&temp).map_err(io_error)?, Compression::of(path)).map_err(io_error)?;
+        write(&mut out)?;
+        let file = out.finish().map_err(io_error)?;
+        if sync != SyncMode::Off {
+            file.sync_all().map_err(io_error)?;
+        }
+        Ok(())
+    })();
+    match result {
+        Ok(()) => Ok(temp),
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
+pub fn write_bytes(path: &Path, bytes: &[u8], sync: SyncMode) -> Result<(), DbError> {
+    write_file(path, sync, |out| Ok(out.write_all(bytes)?))
+}
+
+// Only Unix can open a directory to sync it; elsewhere the rename is left to the OS
+pub(crate) fn sync_dir(dir: &Path) -> io::Result<()> {
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    if cfg!(unix) {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
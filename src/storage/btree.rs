@@ -0,0 +1,613 @@
+use crate::error::DbError;
+use crate::storage::atomic::SyncMode;
+use crate::storage::codec::{decode_value, encode_value};
+use crate::storage::file::{page_path, PageFile, Tree};
+use crate::storage::page::Page;
+use crate::table::data::{DataType, IndexType, Table, UndoEntry, Value};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+
+// A B+ tree kept in the table's own page file, next to its rows. The file's header names
+// the tree's root page and entry count (see file.rs); every node is a page whose first
+// cell is the node header and whose other cells are its entries in order:
+//   leaf      [0][next leaf: u32]     entries [row: u64][key]
+//   internal  [1][first child: u32]   entries [row: u64][key][child: u32]
+// Entries are (key, row) pairs, so a key held by many rows never outgrows a page. An
+// internal entry is the smallest pair under the child it names. Each write goes through
+// the page file's journal, and a node that fails its checksum is a DbError::Corruption.
+const LEAF: u8 = 0;
+const INTERNAL: u8 = 1;
+
+// A tree can always be built again from the rows, so its writes don't wait on the directory
+const SYNC: SyncMode = SyncMode::Normal;
+
+// Strings are indexed by their first MAX_KEY_BYTES bytes so every entry fits a page many
+// times over. A lookup can then return rows whose strings only share that prefix, which
+// callers already filter out, as they do for collated keys.
+pub const MAX_KEY_BYTES: usize = 256;
+
+// Writes that fail leave the tree out of step with the table, so the index turns stale
+// and stops answering lookups until it is rebuilt.
+#[derive(Debug)]
+pub struct DiskIndex {
+    path: PathBuf,
+    id: u32,
+    stale: bool,
+    detached: bool,
+}
+
+// The tree belongs to the table that built it. A copy of the handle, as a copy of the
+// table or its undo log holds, never reads or writes it, and rebuilding one gives the
+// copy an in-memory index instead (see Table::create_index_like).
+impl Clone for DiskIndex {
+    fn clone(&self) -> Self {
+        DiskIndex {
+            detached: true,
+            ..self.handle()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    key: Value,
+    row: usize,
+    // Unused in leaves
+    child: u32,
+}
+
+#[derive(Debug)]
+struct Node {
+    leaf: bool,
+    // The next leaf (0 for the last), or an internal node's first child
+    link: u32,
+    entries: Vec<Entry>,
+}
+
+impl DiskIndex {
+    // Builds the tree of `entries` in the page file at `path`, in place of the tree `id`
+    // if given, whose pages are freed
+    pub fn build(path: &Path, id: Option<u32>, entries: Vec<(Value, usize)>) -> Result<DiskIndex, DbError> {
+        let mut entries: Vec<Entry> = entries
+            .into_iter()
+            .map(|(key, row)| Entry {
+                key: stored_key(&key),
+                row,
+                child: 0,
+            })
+            .collect();
+        entries.sort_by(|a, b| compare(&a.key, a.row, &b.key, b.row));
+
+        let mut file = PageFile::open(path)?;
+        let id = id.unwrap_or_else(|| file.new_tree_id());
+        // An old tree that can't be followed is left where it is
+        if let Some(old) = file.tree(id) {
+            for page in tree_pages(&mut file, old.root).unwrap_or_default() {
+                file.free_page(page);
+            }
+        }
+        let tree = Tree {
+            root: 0,
+            entries: entries.len() as u64,
+        };
+
+        // Leaves are filled in order, then each level of internal nodes over the one below
+        // until a single node is left to be the root
+        let leaves = pack(entries, true);
+        let ids: Vec<u32> = leaves.iter().map(|_| file.allocate()).collect();
+        let mut level: Vec<(Entry, u32)> = vec![];
+        for (i, packed) in leaves.into_iter().enumerate() {
+            let mut leaf = packed.node;
+            leaf.link = ids.get(i + 1).copied().unwrap_or(0);
+            write_node(&mut file, ids[i], &leaf)?;
+            level.push((packed.first, ids[i]));
+        }
+        while level.len() > 1 {
+            let children: Vec<Entry> = level
+                .iter()
+                .map(|(first, id)| Entry {
+                    child: *id,
+                    ..first.clone()
+                })
+                .collect();
+            let mut next = vec![];
+            for node in pack(children, false) {
+                let id = file.allocate();
+                next.push((node.first.clone(), id));
+                write_node(&mut file, id, &node.node)?;
+            }
+            level = next;
+        }
+        file.set_tree(id, Tree { root: level[0].1, ..tree })?;
+        file.commit(SYNC)?;
+        Ok(DiskIndex {
+            path: path.to_path_buf(),
+            id,
+            stale: false,
+            detached: false,
+        })
+    }
+
+    // Stands for the tree `id` in the file at `path` without reading it, e.g. while a
+    // schema is loading
+    pub(crate) fn unbuilt(path: &Path, id: u32) -> DiskIndex {
+        DiskIndex {
+            path: path.to_path_buf(),
+            id,
+            stale: true,
+            detached: false,
+        }
+    }
+
+    // A stale handle on the same tree, for rebuilding it in place
+    pub(crate) fn handle(&self) -> DiskIndex {
+        DiskIndex {
+            path: self.path.clone(),
+            id: self.id,
+            stale: true,
+            detached: self.detached,
+        }
+    }
+
+    // The page file the tree is kept in
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    pub(crate) fn is_detached(&self) -> bool {
+        self.detached
+    }
+
+    // None when stale or unreadable, as are the lookups below
+    pub fn entry_count(&self) -> Option<usize> {
+        let file = self.open().ok()?;
+        file.tree(self.id).map(|tree| tree.entries as usize)
+    }
+
+    pub fn get(&self, key: &Value) -> Option<Vec<usize>> {
+        self.range(Bound::Included(key), Bound::Included(key))
+    }
+
+    pub fn range(&self, lower: Bound<&Value>, upper: Bound<&Value>) -> Option<Vec<usize>> {
+        let (lower, upper) = (truncated_bound(lower), truncated_bound(upper));
+        let mut rows = vec![];
+        self.scan(lower.as_ref(), |entry| {
+            let below_upper = match &upper {
+                Bound::Included(high) => entry.key.cmp(high).is_le(),
+                Bound::Excluded(high) => entry.key.cmp(high).is_lt(),
+                Bound::Unbounded => true,
+            };
+            if below_upper {
+                rows.push(entry.row);
+            }
+            below_upper
+        })
+        .ok()?;
+        Some(rows)
+    }
+
+    // Every (key, row) in order; keys are as stored, so long strings come back cut short
+    pub fn entries(&self) -> Option<Vec<(Value, usize)>> {
+        let mut entries = vec![];
+        self.scan(Bound::Unbounded, |entry| {
+            entries.push((entry.key.clone(), entry.row));
+            true
+        })
+        .ok()?;
+        Some(entries)
+    }
+
+    pub fn insert(&mut self, key: &Value, row: usize) {
+        if !self.stale && self.try_insert(stored_key(key), row).is_err() {
+            self.stale = true;
+        }
+    }
+
+    // Leaves are never merged; a leaf emptied by removals stays in the chain until a rebuild
+    pub fn remove(&mut self, key: &Value, row: usize) {
+        let removed = [(key.clone(), row)];
+        if !self.stale && self.try_remove(&removed, |row| row).is_err() {
+            self.stale = true;
+        }
+    }
+
+    // Takes out the (key, row) pairs of deleted rows and renumbers the other rows with
+    // `shift`, all in one journaled write
+    pub fn remove_rows(&mut self, removed: &[(Value, usize)], shift: impl Fn(usize) -> usize) {
+        if !self.stale && self.try_remove(removed, shift).is_err() {
+            self.stale = true;
+        }
+    }
+
+    // Frees the tree's pages, e.g. when another index takes its place
+    pub(crate) fn drop_tree(&self) -> Result<(), DbError> {
+        let mut file = PageFile::open(&self.path)?;
+        if let Some(tree) = file.tree(self.id) {
+            for page in tree_pages(&mut file, tree.root).unwrap_or_default() {
+                file.free_page(page);
+            }
+            file.remove_tree(self.id);
+        }
+        file.commit(SYNC)
+    }
+
+    fn open(&self) -> Result<PageFile, DbError> {
+        if self.stale || self.detached {
+            return Err(DbError::IoError("Disk index is stale".to_string()));
+        }
+        PageFile::open(&self.path)
+    }
+
+    fn tree(&self, file: &PageFile) -> Result<Tree, DbError> {
+        file.tree(self.id).ok_or_else(|| {
+            DbError::NotFound(format!("{} holds no index {}", self.path.display(), self.id))
+        })
+    }
+
+    // Calls `visit` on every entry from `lower` on, until it returns false
+    fn scan(&self, lower: Bound<&Value>, mut visit: impl FnMut(&Entry) -> bool) -> Result<(), DbError> {
+        let mut file = self.open()?;
+        let tree = self.tree(&file)?;
+        let mut node = read_node(&mut file, tree.root)?;
+        while !node.leaf {
+            let child = match lower {
+                Bound::Included(key) | Bound::Excluded(key) => node.child_for(key, 0).1,
+                Bound::Unbounded => node.link,
+            };
+            node = read_node(&mut file, child)?;
+        }
+        loop {
+            for entry in &node.entries {
+                let above_lower = match lower {
+                    Bound::Included(low) => entry.key.cmp(low).is_ge(),
+                    Bound::Excluded(low) => entry.key.cmp(low).is_gt(),
+                    Bound::Unbounded => true,
+                };
+                if above_lower && !visit(entry) {
+                    return Ok(());
+                }
+            }
+            if node.link == 0 {
+                return Ok(());
+            }
+            node = read_node(&mut file, node.link)?;
+        }
+    }
+
+    fn try_insert(&self, key: Value, row: usize) -> Result<(), DbError> {
+        let mut file = self.open()?;
+        let mut tree = self.tree(&file)?;
+        let entry = Entry { key, row, child: 0 };
+        if let Some(separator) = insert_into(&mut file, tree.root, entry)? {
+            // The root split; a new root sits over both halves
+            let node = Node {
+                leaf: false,
+                link: tree.root,
+                entries: vec![separator],
+            };
+            tree.root = file.allocate();
+            write_node(&mut file, tree.root, &node)?;
+        }
+        tree.entries += 1;
+        file.set_tree(self.id, tree)?;
+        file.commit(SYNC)
+    }
+
+    fn try_remove(&self, removed: &[(Value, usize)], shift: impl Fn(usize) -> usize) -> Result<(), DbError> {
+        let mut file = self.open()?;
+        let mut tree = self.tree(&file)?;
+        for (key, row) in removed {
+            let key = stored_key(key);
+            let mut page = tree.root;
+            let mut node = read_node(&mut file, page)?;
+            while !node.leaf {
+                page = node.child_for(&key, *row).1;
+                node = read_node(&mut file, page)?;
+            }
+            if let Some(at) = node.entries.iter().position(|e| compare(&e.key, e.row, &key, *row).is_eq()) {
+                node.entries.remove(at);
+                write_node(&mut file, page, &node)?;
+                tree.entries -= 1;
+            }
+        }
+
+        // Renumbering keeps every pair, separators included, in the same order, so only
+        // the pages holding a row that moves are written
+        let moved = |node: &Node| node.entries.iter().any(|e| shift(e.row) != e.row);
+        for page in tree_pages(&mut file, tree.root)? {
+            let mut node = read_node(&mut file, page)?;
+            if moved(&node) {
+                for entry in &mut node.entries {
+                    entry.row = shift(entry.row);
+                }
+                write_node(&mut file, page, &node)?;
+            }
+        }
+        file.set_tree(self.id, tree)?;
+        file.commit(SYNC)
+    }
+}
+
+// The separator for a new right sibling when the node at `page` had to split
+fn insert_into(file: &mut PageFile, page: u32, entry: Entry) -> Result<Option<Entry>, DbError> {
+    let mut node = read_node(file, page)?;
+    if node.leaf {
+        let at = node
+            .entries
+            .partition_point(|e| compare(&e.key, e.row, &entry.key, entry.row).is_lt());
+        node.entries.insert(at, entry);
+    } else {
+        let (at, child) = node.child_for(&entry.key, entry.row);
+        match insert_into(file, child, entry)? {
+            Some(separator) => node.entries.insert(at, separator),
+            None => return Ok(None),
+        }
+    }
+    if node.page().is_some() {
+        write_node(file, page, &node)?;
+        return Ok(None);
+    }
+
+    let right_id = file.allocate();
+    let mut right_entries = node.entries.split_off(node.entries.len() / 2);
+    let (right, separator) = if node.leaf {
+        let separator = Entry {
+            child: right_id,
+            ..right_entries[0].clone()
+        };
+        let right = Node {
+            leaf: true,
+            link: node.link,
+            entries: right_entries,
+        };
+        node.link = right_id;
+        (right, separator)
+    } else {
+        // The middle entry moves up; its child becomes the right node's first
+        let middle = right_entries.remove(0);
+        let right = Node {
+            leaf: false,
+            link: middle.child,
+            entries: right_entries,
+        };
+        (right, Entry { child: right_id, ..middle })
+    };
+    write_node(file, page, &node)?;
+    write_node(file, right_id, &right)?;
+    Ok(Some(separator))
+}
+
+// Every page of the tree under `root`
+fn tree_pages(file: &mut PageFile, root: u32) -> Result<Vec<u32>, DbError> {
+    let mut pages = vec![];
+    let mut seen = HashSet::new();
+    let mut pending = vec![root];
+    while let Some(page) = pending.pop() {
+        if !seen.insert(page) {
+            return Err(file.malformed(page));
+        }
+        pages.push(page);
+        let node = read_node(file, page)?;
+        if !node.leaf {
+            pending.push(node.link);
+            pending.extend(node.entries.iter().map(|e| e.child));
+        }
+    }
+    Ok(pages)
+}
+
+impl Node {
+    // Position in `entries` a pair would take, and the child whose range holds it
+    fn child_for(&self, key: &Value, row: usize) -> (usize, u32) {
+        let at = self
+            .entries
+            .partition_point(|e| compare(&e.key, e.row, key, row).is_le());
+        let child = if at == 0 { self.link } else { self.entries[at - 1].child };
+        (at, child)
+    }
+
+    fn cells(&self) -> Vec<Vec<u8>> {
+        let mut header = vec![if self.leaf { LEAF } else { INTERNAL }];
+        header.extend(self.link.to_le_bytes());
+        let mut cells = vec![header];
+        cells.extend(self.entries.iter().map(|entry| entry_cell(entry, self.leaf)));
+        cells
+    }
+
+    // None when the node is too big for one page
+    fn page(&self) -> Option<Page> {
+        let cells = self.cells();
+        if !Page::fits(cells.iter().map(Vec::len)) {
+            return None;
+        }
+        let mut page = Page::new();
+        for cell in &cells {
+            page.insert(cell)?;
+        }
+        Some(page)
+    }
+}
+
+impl Table {
+    // The tree goes in the table's page file in `dir_path`, where save_to_dir keeps its
+    // rows. Lookups read it a page at a time; the table keeps it up to date on every write.
+    pub fn create_disk_index(&mut self, column_name: &str, dir_path: &Path, db_name: &str) -> Result<(), DbError> {
+        let replaced = self.indexes.get(column_name).map(IndexType::empty_like);
+        let path = page_path(dir_path, db_name, &self.name);
+        // An index already in that file is built again under its own id
+        let id = match self.indexes.get(column_name) {
+            Some(IndexType::Disk(disk)) if disk.path() == path && !disk.is_detached() => Some(disk.id()),
+            _ => None,
+        };
+        self.build_disk_index(column_name, &path, id)?;
+        self.log_change(|_| UndoEntry::CreateIndex {
+            column: column_name.to_string(),
+            replaced,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn build_disk_index(&mut self, column_name: &str, path: &Path, id: Option<u32>) -> Result<(), DbError> {
+        let col_index = self
+            .columns
+            .iter()
+            .position(|c| c.name == column_name)
+            .ok_or_else(|| DbError::NotFound(format!("Column '{}' does not exist", column_name)))?;
+        if let DataType::Set(_) = self.columns[col_index].datatype {
+            return Err(DbError::SchemaError(format!(
+                "Set column '{}' cannot have a disk index",
+                column_name
+            )));
+        }
+
+        let collation = self.columns[col_index].collation();
+        let entries = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| (collation.key(&row[col_index]).into_owned(), i))
+            .collect();
+        let index = DiskIndex::build(path, id, entries)?;
+        self.set_index(column_name, IndexType::Disk(index));
+        Ok(())
+    }
+}
+
+struct Packed {
+    node: Node,
+    first: Entry,
+}
+
+// Splits entries, in order, into as few full nodes as fit a page each. For internal
+// nodes the first entry of each node becomes its link instead, and the node's first pair
+// is handed up for the level above.
+fn pack(entries: Vec<Entry>, leaf: bool) -> Vec<Packed> {
+    let mut nodes: Vec<Packed> = vec![];
+    let mut lens: Vec<usize> = vec![];
+    for entry in entries {
+        let len = entry_cell(&entry, leaf).len();
+        let fits = nodes.last().is_some_and(|_| Page::fits(lens.iter().copied().chain([len])));
+        if fits {
+            nodes.last_mut().unwrap().node.entries.push(entry);
+            lens.push(len);
+            continue;
+        }
+        lens = vec![5];
+        let node = if leaf {
+            lens.push(len);
+            Node {
+                leaf,
+                link: 0,
+                entries: vec![entry.clone()],
+            }
+        } else {
+            Node {
+                leaf,
+                link: entry.child,
+                entries: vec![],
+            }
+        };
+        nodes.push(Packed { node, first: entry });
+    }
+    if nodes.is_empty() {
+        nodes.push(Packed {
+            node: Node {
+                leaf,
+                link: 0,
+                entries: vec![],
+            },
+            first: Entry {
+                key: Value::Null,
+                row: 0,
+                child: 0,
+            },
+        });
+    }
+    nodes
+}
+
+fn entry_cell(entry: &Entry, leaf: bool) -> Vec<u8> {
+    let mut cell = (entry.row as u64).to_le_bytes().to_vec();
+    encode_value(&entry.key, &mut cell);
+    if !leaf {
+        cell.extend(entry.child.to_le_bytes());
+    }
+    cell
+}
+
+fn compare(a: &Value, a_row: usize, b: &Value, b_row: usize) -> Ordering {
+    a.cmp(b).then(a_row.cmp(&b_row))
+}
+
+// The form a key takes in the index: long strings cut to MAX_KEY_BYTES at a character boundary
+pub fn stored_key(value: &Value) -> Value {
+    let cut = |s: &str| {
+        let mut end = MAX_KEY_BYTES.min(s.len());
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s[..end].to_string()
+    };
+    match value {
+        Value::Varchar(s) if s.len() > MAX_KEY_BYTES => Value::Varchar(cut(s)),
+        Value::Text(s) if s.len() > MAX_KEY_BYTES => Value::Text(cut(s)),
+        Value::Enum(s) if s.len() > MAX_KEY_BYTES => Value::Enum(cut(s)),
+        value => value.clone(),
+    }
+}
+
+// A bound past a cut key can't exclude it: other strings share its prefix
+fn truncated_bound(bound: Bound<&Value>) -> Bound<Value> {
+    match bound {
+        Bound::Included(value) => Bound::Included(stored_key(value)),
+        Bound::Excluded(value) => match stored_key(value) {
+            cut if cut == *value => Bound::Excluded(cut),
+            cut => Bound::Included(cut),
+        },
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+fn read_node(file: &mut PageFile, id: u32) -> Result<Node, DbError> {
+    let page = file.read_page(id)?;
+    let mut cells = page.cells();
+    let header = cells.next().filter(|h| h.len() == 5).ok_or_else(|| file.malformed(id))?;
+    let leaf = match header[0] {
+        LEAF => true,
+        INTERNAL => false,
+        _ => return Err(file.malformed(id)),
+    };
+    let link = u32::from_le_bytes(header[1..5].try_into().unwrap());
+    let entries = cells
+        .map(|cell| {
+            let child_len = if leaf { 0 } else { 4 };
+            if cell.len() < 8 + child_len + 1 {
+                return Err(file.malformed(id));
+            }
+            let (row, rest) = cell.split_at(8);
+            let (key, child) = rest.split_at(rest.len() - child_len);
+            Ok(Entry {
+                key: decode_value(key)?,
+                row: u64::from_le_bytes(row.try_into().unwrap()) as usize,
+                child: if leaf { 0 } else { u32::from_le_bytes(child.try_into().unwrap()) },
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Node { leaf, link, entries })
+}
+
+fn write_node(file: &mut PageFile, id: u32, node: &Node) -> Result<(), DbError> {
+    let page = node.page().ok_or_else(|| file.malformed(id))?;
+    file.write_page(id, &page);
+    Ok(())
+}
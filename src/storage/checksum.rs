@@ -0,0 +1,63 @@
+use crate::error::DbError;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+// Files are checksummed in blocks, so a mismatch points at the block it's in
+pub const BLOCK_SIZE: usize = 64 * 1024;
+
+// A data file's length and a CRC32 of each block, taken of the bytes on disk
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileChecksum {
+    pub file_name: String,
+    pub len: u64,
+    pub blocks: Vec<u32>,
+}
+
+impl FileChecksum {
+    pub fn of(path: &Path) -> Result<Self, DbError> {
+        let (len, blocks) = block_checksums(path)?;
+        let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        Ok(FileChecksum { file_name, len, blocks })
+    }
+
+    // A file of another length, or with a block that changed, is a DbError::Corruption at
+    // the first byte missing or added, or at the start of the changed block
+    pub fn verify(&self, path: &Path) -> Result<(), DbError> {
+        let (len, blocks) = block_checksums(path)?;
+        let corrupt = |offset: u64, detail: String| DbError::Corruption {
+            offset,
+            message: format!("Corrupt file {} at byte {}: {}", path.display(), offset, detail),
+        };
+        if len < self.len {
+            return Err(corrupt(len, format!("the file ends {} bytes short", self.len - len)));
+        }
+        if len > self.len {
+            return Err(corrupt(self.len, "trailing bytes after the saved end".to_string()));
+        }
+        match self.blocks.iter().zip(&blocks).position(|(saved, found)| saved != found) {
+            Some(block) => {
+                let offset = (block * BLOCK_SIZE) as u64;
+                Err(corrupt(offset, format!("block {} fails its checksum", block)))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+fn block_checksums(path: &Path) -> Result<(u64, Vec<u32>), DbError> {
+    let io_error = |e: std::io::Error| DbError::IoError(format!("Failed to read {}: {}", path.display(), e));
+    let mut input = BufReader::new(File::open(path).map_err(io_error)?);
+    let mut block = Vec::with_capacity(BLOCK_SIZE);
+    let mut len = 0;
+    let mut blocks = vec![];
+    loop {
+        block.clear();
+        (&mut input).take(BLOCK_SIZE as u64).read_to_end(&mut block).map_err(io_error)?;
+        if block.is_empty() {
+            return Ok((len, blocks));
+        }
+        len += block.len() as u64;
+        blocks.push(crc32fast::hash(&block));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::error::DbError;
+use crate::table::data::{Decimal, Value};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Timelike};
+
+// A row is its value count (u32) and then each value as a tag byte and its payload,
+// little-endian. Strings are a u32 byte length and UTF-8; a Set is a u32 count of strings.
+const NULL: u8 = 0;
+const CHAR: u8 = 1;
+const VARCHAR: u8 = 2;
+const TEXT: u8 = 3;
+const ENUM: u8 = 4;
+const SET: u8 = 5;
+const BOOLEAN: u8 = 6;
+const INT: u8 = 7;
+const BIGINT: u8 = 8;
+const FLOAT: u8 = 9;
+const DOUBLE: u8 = 10;
+// Days from 0001-01-01
+const DATE: u8 = 11;
+// Seconds from midnight, then nanoseconds
+const TIME: u8 = 12;
+// Seconds from the Unix epoch, then nanoseconds
+const DATETIME: u8 = 13;
+const POINT: u8 = 14;
+// Mantissa (i128), then scale (u32)
+const DECIMAL: u8 = 15;
+
+pub fn encode_row(row: &[Value]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 * row.len() + 4);
+    out.extend((row.len() as u32).to_le_bytes());
+    for value in row {
+        encode_value(value, &mut out);
+    }
+    out
+}
+
+pub fn decode_row(bytes: &[u8]) -> Result<Vec<Value>, DbError> {
+    let mut reader = Reader { bytes, at: 0 };
+    let count = reader.u32()?;
+    let row = (0..count).map(|_| reader.value()).collect::<Result<Vec<_>, _>>()?;
+    if reader.at != bytes.len() {
+        return Err(corrupt("trailing bytes after the last value"));
+    }
+    Ok(row)
+}
+
+// One value, alone, as encode_value wrote it
+pub fn decode_value(bytes: &[u8]) -> Result<Value, DbError> {
+    let mut reader = Reader { bytes, at: 0 };
+    let value = reader.value()?;
+    if reader.at != bytes.len() {
+        return Err(corrupt("trailing bytes after the value"));
+    }
+    Ok(value)
+}
+
+pub fn encode_value(value: &Value, out: &mut Vec<u8>) {
+    let string = |out: &mut Vec<u8>, s: &str| {
+        out.extend((s.len() as u32).to_le_bytes());
+        out.extend(s.as_bytes());
+    };
+    match value {
+        Value::Null => out.push(NULL),
+        Value::Char(c) => {
+            out.push(CHAR);
+            out.extend((*c as u32).to_le_bytes());
+        }
+        Value::Varchar(s) => {
+            out.push(VARCHAR);
+            string(out, s);
+        }
+        Value::Text(s) => {
+            out.push(TEXT);
+            string(out, s);
+        }
+        Value::Enum(s) => {
+            out.push(ENUM);
+            string(out, s);
+        }
+        Value::Set(items) => {
+            out.push(SET);
+            out.extend((items.len() as u32).to_le_bytes());
+            for item in items {
+                string(out, item);
+            }
+        }
+        Value::Boolean(b) => out.extend([BOOLEAN, *b as u8]),
+        Value::Int(n) => {
+            out.push(INT);
+            out.extend(n.to_le_bytes());
+        }
+        Value::BigInt(n) => {
+            out.push(BIGINT);
+            out.extend(n.to_le_bytes());
+        }
+        Value::Float(f) => {
+            out.push(FLOAT);
+            out.extend(f.to_le_bytes());
+        }
+        Value::Double(f) => {
+            out.push(DOUBLE);
+            out.extend(f.to_le_bytes());
+        }
+        Value::Date(d) => {
+            out.push(DATE);
+            out.extend(d.num_days_from_ce().to_le_bytes());
+        }
+        Value::Time(t) => {
+            out.push(TIME);
+            out.extend(t.num_seconds_from_midnight().to_le_bytes());
+            out.extend(t.nanosecond().to_le_bytes());
+        }
+        Value::DateTime(dt) => {
+            let utc = dt.and_utc();
+            out.push(DATETIME);
+            out.extend(utc.timestamp().to_le_bytes());
+            out.extend(utc.timestamp_subsec_nanos().to_le_bytes());
+        }
+        Value::Point(lat, lon) => {
+            out.push(POINT);
+            out.extend(lat.to_le_bytes());
+            out.extend(lon.to_le_bytes());
+        }
+        Value::Decimal(d) => {
+            out.push(DECIMAL);
+            out.extend(d.mantissa.to_le_bytes());
+            out.extend(d.scale.to_le_bytes());
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DbError> {
+        let end = self.at + N;
+        let bytes = self.bytes.get(self.at..end).ok_or_else(|| corrupt("value cut short"))?;
+        self.at = end;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> Result<u32, DbError> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32, DbError> {
+        self.take().map(i32::from_le_bytes)
+    }
+
+    fn i64(&mut self) -> Result<i64, DbError> {
+        self.take().map(i64::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> Result<f64, DbError> {
+        self.take().map(f64::from_le_bytes)
+    }
+
+    fn string(&mut self) -> Result<String, DbError> {
+        let len = self.u32()? as usize;
+        let end = self.at.checked_add(len).ok_or_else(|| corrupt("string cut short"))?;
+        let bytes = self.bytes.get(self.at..end).ok_or_else(|| corrupt("string cut short"))?;
+        self.at = end;
+        String::from_utf8(bytes.to_vec()).map_err(|_| corrupt("string is not UTF-8"))
+    }
+
+    fn value(&mut self) -> Result<Value, DbError> {
+        let [tag] = self.take()?;
+        Ok(match tag {
+            NULL => Value::Null,
+            CHAR => Value::Char(char::from_u32(self.u32()?).ok_or_else(|| corrupt("invalid character"))?),
+            VARCHAR => Value::Varchar(self.string()?),
+            TEXT => Value::Text(self.string()?),
+            ENUM => Value::Enum(self.string()?),
+            SET => {
+                let count = self.u32()?;
+                Value::Set((0..count).map(|_| self.string()).collect::<Result<_, _>>()?)
+            }
+            BOOLEAN => {
+                let [b] = self.take()?;
+                Value::Boolean(b != 0)
+            }
+            INT => Value::Int(self.i32()?),
+            BIGINT => Value::BigInt(self.i64()?),
+            FLOAT => Value::Float(self.take().map(f32::from_le_bytes)?),
+            DOUBLE => Value::Double(self.f64()?),
+            DATE => Value::Date(
+                NaiveDate::from_num_days_from_ce_opt(self.i32()?).ok_or_else(|| corrupt("date out of range"))?,
+            ),
+            TIME => {
+                let (seconds, nanos) = (self.u32()?, self.u32()?);
+                Value::Time(
+                    NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanos)
+                        .ok_or_else(|| corrupt("time out of range"))?,
+                )
+            }
+            DATETIME => {
+                let (seconds, nanos) = (self.i64()?, self.u32()?);
+                let dt = DateTime::from_timestamp(seconds, nanos).ok_or_else(|| corrupt("timestamp out of range"))?;
+                Value::DateTime(dt.naive_utc())
+            }
+            POINT => Value::Point(self.f64()?, self.f64()?),
+            DECIMAL => {
+                let mantissa = self.take().map(i128::from_le_bytes)?;
+                Value::Decimal(Decimal {
+                    mantissa,
+                    scale: self.u32()?,
+                })
+            }
+            tag => return Err(corrupt(&format!("unknown value tag {}", tag))),
+        })
+    }
+}
+
+fn corrupt(detail: &str) -> DbError {
+    DbError::IoError(format!("Corrupt row data: {}", detail))
+}
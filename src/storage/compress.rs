@@ -0,0 +1,150 @@
+use crate::error::DbError;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+// Files are compressed whole, and which codec a file uses is told by its extension:
+// "t.pages.zst" and "t.shard0.csv.gz" hold what "t.pages" and "t.shard0.csv" would.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub const ALL: [Compression; 2] = [Compression::Gzip, Compression::Zstd];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Compression> {
+        Compression::ALL.into_iter().find(|c| c.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    // `path` with this compression's extension added after its own
+    pub fn path(self, path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".");
+        name.push(self.extension());
+        PathBuf::from(name)
+    }
+
+    pub fn of(path: &Path) -> Option<Compression> {
+        let extension = path.extension()?.to_str()?;
+        Compression::ALL.into_iter().find(|c| c.extension() == extension)
+    }
+}
+
+// Where a file saved with `compression` goes in place of `path`
+pub fn path_for(path: &Path, compression: Option<Compression>) -> PathBuf {
+    compression.map_or_else(|| path.to_path_buf(), |c| c.path(path))
+}
+
+// `path` and every compressed version of it, whether or not they exist
+pub fn variants(path: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![path.to_path_buf()];
+    paths.extend(Compression::ALL.map(|c| c.path(path)));
+    paths
+}
+
+// The first of `variants` that exists
+pub fn find(path: &Path) -> Option<PathBuf> {
+    variants(path).into_iter().find(|p| p.exists())
+}
+
+// A file name without its compression extension, if it has one
+pub fn strip_extension(file_name: &str) -> &str {
+    Compression::ALL
+        .into_iter()
+        .find_map(|c| file_name.strip_suffix(&format!(".{}", c.extension())))
+        .unwrap_or(file_name)
+}
+
+// Reads the file at `path`, decompressing it as its extension says
+pub fn open(path: &Path) -> io::Result<Box<dyn Read>> {
+    let file = File::open(path)?;
+    Ok(match Compression::of(path) {
+        None => Box::new(file),
+        Some(Compression::Gzip) => Box::new(GzDecoder::new(BufReader::new(file))),
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::new(file)?),
+    })
+}
+
+pub fn read(path: &Path) -> Result<Vec<u8>, DbError> {
+    let mut bytes = vec![];
+    open(path)
+        .and_then(|mut input| input.read_to_end(&mut bytes))
+        .map_err(|e| DbError::IoError(format!("Failed to read {}: {}", path.display(), e)))?;
+    Ok(bytes)
+}
+
+// Creates the file at `path`, compressing what's written as its extension says. Nothing is
+// complete until finish.
+pub fn create(path: &Path) -> io::Result<CompressedWriter> {
+    wrap(File::create(path)?, Compression::of(path))
+}
+
+pub fn wrap(file: File, compression: Option<Compression>) -> io::Result<CompressedWriter> {
+    Ok(match compression {
+        None => CompressedWriter::Plain(file),
+        Some(Compression::Gzip) => CompressedWriter::Gzip(GzEncoder::new(file, flate2::Compression::default())),
+        Some(Compression::Zstd) => CompressedWriter::Zstd(zstd::Encoder::new(file, zstd::DEFAULT_COMPRESSION_LEVEL)?),
+    })
+}
+
+pub fn write(path: &Path, bytes: &[u8]) -> Result<(), DbError> {
+    create(path)
+        .and_then(|mut out| {
+            out.write_all(bytes)?;
+            out.finish().map(drop)
+        })
+        .map_err(|e| DbError::IoError(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+pub enum CompressedWriter {
+    Plain(File),
+    Gzip(GzEncoder<File>),
+    Zstd(zstd::Encoder<'static, File>),
+}
+
+impl CompressedWriter {
+    // Ends the compressed stream, handing back the file for syncing
+    pub fn finish(self) -> io::Result<File> {
+        match self {
+            CompressedWriter::Plain(mut file) => file.flush().map(|_| file),
+            CompressedWriter::Gzip(encoder) => encoder.finish(),
+            CompressedWriter::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl Write for CompressedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            CompressedWriter::Plain(file) => file.write(buf),
+            CompressedWriter::Gzip(encoder) => encoder.write(buf),
+            CompressedWriter::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            CompressedWriter::Plain(file) => file.flush(),
+            CompressedWriter::Gzip(encoder) => encoder.flush(),
+            CompressedWriter::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
@@ -0,0 +1,561 @@
+use crate::error::DbError;
+use crate::storage::atomic::{self, SyncMode};
+use crate::storage::codec::{decode_row, encode_row};
+use crate::storage::compress::{self, Compression};
+use crate::storage::journal::{self, journal_path, Journal};
+use crate::storage::page::{Page, PAGE_SIZE};
+use crate::table::data::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+// Page 0 is the header; the other pages are found by following links from it:
+//   header     [magic][page size: u32][page count: u32][first row page: u32]
+//              [first free-list page: u32][tree count: u32]
+//              ([tree id: u32][root page: u32][entries: u64])... [crc32 of the rest of the page]
+//   row page   cells [next row page: u32], then rows as [flag][encoding, or a piece of it]
+//   free list  one cell [next free-list page: u32][free page: u32]...
+// Trees are the table's disk indexes, whose nodes are pages of the same file (see btree.rs).
+// A row larger than what's left of a page goes on over the next. Pages are changed in
+// place, but every write goes through a journal first (see journal.rs), so a crash never
+// leaves some of a save's pages written and others not. Freed pages are used again
+// before the file grows, and free pages at its end are cut off.
+const MAGIC: &[u8; 8] = b"DBPAGES3";
+// Files from before page links, with rows on the pages after the header in order
+const SEQUENTIAL_MAGIC: &[u8; 8] = b"DBPAGES2";
+// Files from before checksums: no page count, and pages without their CRC
+const UNSEALED_MAGIC: &[u8; 8] = b"DBPAGES1";
+const SEQUENTIAL_FIELDS: usize = 16;
+const HEADER_CRC: usize = PAGE_SIZE - 4;
+const LAST_PIECE: u8 = 0;
+const MORE_PIECES: u8 = 1;
+const FREE_IDS_PER_PAGE: usize = 1000;
+const TREES: usize = 28;
+const MAX_TREES: usize = (HEADER_CRC - TREES) / 16;
+
+pub(crate) fn page_path(dir_path: &Path, db_name: &str, table_name: &str) -> PathBuf {
+    dir_path.join(format!("{}.{}.pages", db_name, table_name))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Header {
+    page_count: u32,
+    first_row_page: u32,
+    free_list: u32,
+    trees: BTreeMap<u32, Tree>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Tree {
+    pub root: u32,
+    pub entries: u64,
+}
+
+impl Header {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![0; PAGE_SIZE];
+        bytes[..8].copy_from_slice(MAGIC);
+        bytes[8..12].copy_from_slice(&(PAGE_SIZE as u32).to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.page_count.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.first_row_page.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.free_list.to_le_bytes());
+        bytes[24..TREES].copy_from_slice(&(self.trees.len() as u32).to_le_bytes());
+        for (i, (id, tree)) in self.trees.iter().enumerate() {
+            let at = TREES + i * 16;
+            bytes[at..at + 4].copy_from_slice(&id.to_le_bytes());
+            bytes[at + 4..at + 8].copy_from_slice(&tree.root.to_le_bytes());
+            bytes[at + 8..at + 16].copy_from_slice(&tree.entries.to_le_bytes());
+        }
+        let crc = crc32fast::hash(&bytes[..HEADER_CRC]);
+        bytes[HEADER_CRC..].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    fn decode(bytes: &[u8], path: &Path) -> Result<Header, DbError> {
+        if bytes.len() < PAGE_SIZE {
+            return Err(corrupt(path, bytes.len() as u64, "the header page is cut short"));
+        }
+        if bytes[HEADER_CRC..PAGE_SIZE] != crc32fast::hash(&bytes[..HEADER_CRC]).to_le_bytes() {
+            return Err(corrupt(path, 0, "the header fails its checksum"));
+        }
+        if bytes[8..12] != (PAGE_SIZE as u32).to_le_bytes() {
+            return Err(unreadable(path, "written with a different page size"));
+        }
+        let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let count = u32_at(24) as usize;
+        if count > MAX_TREES {
+            return Err(corrupt(path, 24, "the header lists more trees than fit"));
+        }
+        let trees = (0..count)
+            .map(|i| TREES + i * 16)
+            .map(|at| {
+                let tree = Tree {
+                    root: u32_at(at + 4),
+                    entries: u64::from_le_bytes(bytes[at + 8..at + 16].try_into().unwrap()),
+                };
+                (u32_at(at), tree)
+            })
+            .collect();
+        Ok(Header {
+            page_count: u32_at(12),
+            first_row_page: u32_at(16),
+            free_list: u32_at(20),
+            trees,
+        })
+    }
+}
+
+// An open page file and the writes made to it since, held back until commit
+pub(crate) struct PageFile {
+    path: PathBuf,
+    file: Option<File>,
+    len: u64,
+    saved: Option<Vec<u8>>,
+    header: Header,
+    free: BTreeSet<u32>,
+    saved_free: BTreeSet<u32>,
+    dirty: BTreeMap<u32, Vec<u8>>,
+}
+
+impl PageFile {
+    // A page file with nothing in it yet, which replaces whatever is at `path` on commit
+    pub(crate) fn new(path: &Path) -> PageFile {
+        PageFile {
+            path: path.to_path_buf(),
+            file: None,
+            len: 0,
+            saved: None,
+            header: Header {
+                page_count: 1,
+                first_row_page: 0,
+                free_list: 0,
+                trees: BTreeMap::new(),
+            },
+            free: BTreeSet::new(),
+            saved_free: BTreeSet::new(),
+            dirty: BTreeMap::new(),
+        }
+    }
+
+    // Finishes any journal a crash left first. A missing file opens empty; one of an
+    // older format, or not a page file at all, is an error.
+    pub(crate) fn open(path: &Path) -> Result<PageFile, DbError> {
+        journal::recover(&journal_path(path))?;
+        let mut pages = PageFile::new(path);
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(pages),
+            Err(e) => return Err(io_error(path, e)),
+        };
+        let mut header = vec![];
+        (&mut file).take(PAGE_SIZE as u64).read_to_end(&mut header).map_err(|e| io_error(path, e))?;
+        if !header.starts_with(MAGIC) {
+            return Err(unreadable(path, "not a page file of this version"));
+        }
+        pages.header = Header::decode(&header, path)?;
+        pages.len = file.metadata().map_err(|e| io_error(path, e))?.len();
+        pages.file = Some(file);
+        pages.saved = Some(header);
+
+        let mut next = pages.header.free_list;
+        while next != 0 {
+            let page = pages.read_page(next)?;
+            let cell = page.cell(0).filter(|cell| cell.len() % 4 == 0 && !cell.is_empty());
+            let cell = cell.ok_or_else(|| pages.malformed(next))?;
+            let ids: Vec<u32> = cell.chunks(4).map(|id| u32::from_le_bytes(id.try_into().unwrap())).collect();
+            if ids[1..].iter().any(|id| *id == 0 || *id >= pages.header.page_count || !pages.free.insert(*id)) {
+                return Err(pages.malformed(next));
+            }
+            next = ids[0];
+        }
+        pages.saved_free = pages.free.clone();
+        Ok(pages)
+    }
+
+    // The page as last written here, or as on disk
+    pub(crate) fn read_page(&mut self, id: u32) -> Result<Page, DbError> {
+        if let Some(bytes) = self.dirty.get(&id) {
+            return Ok(Page::from_bytes(bytes.clone()).expect("pages are written whole"));
+        }
+        let offset = id as u64 * PAGE_SIZE as u64;
+        if id == 0 || id >= self.header.page_count {
+            return Err(self.malformed(id));
+        }
+        if offset + PAGE_SIZE as u64 > self.len {
+            let detail = format!("the file ends {} bytes short", self.header.page_count as u64 * PAGE_SIZE as u64 - self.len);
+            return Err(corrupt(&self.path, self.len, &detail));
+        }
+        let file = self.file.as_mut().ok_or_else(|| unreadable(&self.path, "nothing is saved"))?;
+        let mut bytes = vec![0; PAGE_SIZE];
+        file.seek(SeekFrom::Start(offset)).map_err(|e| io_error(&self.path, e))?;
+        file.read_exact(&mut bytes).map_err(|e| io_error(&self.path, e))?;
+        if !Page::checksum_matches(&bytes) {
+            return Err(corrupt(&self.path, offset, &format!("page {} fails its checksum", id)));
+        }
+        Page::from_bytes(bytes).ok_or_else(|| self.malformed(id))
+    }
+
+    pub(crate) fn write_page(&mut self, id: u32, page: &Page) {
+        self.dirty.insert(id, page.as_bytes().to_vec());
+    }
+
+    // A free page if there is one, else a new one at the end
+    pub(crate) fn allocate(&mut self) -> u32 {
+        if let Some(id) = self.free.pop_first() {
+            return id;
+        }
+        self.header.page_count += 1;
+        self.header.page_count - 1
+    }
+
+    pub(crate) fn free_page(&mut self, id: u32) {
+        self.dirty.remove(&id);
+        self.free.insert(id);
+    }
+
+    pub(crate) fn tree(&self, id: u32) -> Option<Tree> {
+        self.header.trees.get(&id).copied()
+    }
+
+    pub(crate) fn set_tree(&mut self, id: u32, tree: Tree) -> Result<(), DbError> {
+        if !self.header.trees.contains_key(&id) && self.header.trees.len() >= MAX_TREES {
+            return Err(DbError::SchemaError(format!(
+                "{} already holds {} indexes, the most it can",
+                self.path.display(),
+                MAX_TREES
+            )));
+        }
+        self.header.trees.insert(id, tree);
+        Ok(())
+    }
+
+    pub(crate) fn remove_tree(&mut self, id: u32) {
+        self.header.trees.remove(&id);
+    }
+
+    // An id no tree in the file has
+    pub(crate) fn new_tree_id(&self) -> u32 {
+        self.header.trees.keys().next_back().map_or(1, |id| id + 1)
+    }
+
+    fn row_pages(&mut self) -> Result<Vec<u32>, DbError> {
+        let mut ids = vec![];
+        let mut next = self.header.first_row_page;
+        while next != 0 {
+            if ids.len() >= self.header.page_count as usize {
+                return Err(self.malformed(next));
+            }
+            ids.push(next);
+            let page = self.read_page(next)?;
+            next = link(&page).ok_or_else(|| self.malformed(next))?;
+        }
+        Ok(ids)
+    }
+
+    // Writes only the row pages whose bytes change. Pages of the old rows that can't be
+    // followed, in a file that's been damaged, are left where they are.
+    pub(crate) fn replace_rows(&mut self, rows: &[Vec<Value>]) {
+        let old = self.row_pages().unwrap_or_default();
+        let pages = pack_rows(rows);
+        let ids: Vec<u32> = (0..pages.len())
+            .map(|i| old.get(i).copied().unwrap_or_else(|| self.allocate()))
+            .collect();
+        for (i, mut page) in pages.into_iter().enumerate() {
+            page.set_cell(0, &ids.get(i + 1).copied().unwrap_or(0).to_le_bytes());
+            let unchanged = i < old.len() && self.read_page(ids[i]).is_ok_and(|saved| saved == page);
+            if !unchanged {
+                self.write_page(ids[i], &page);
+            }
+        }
+        for &id in old.iter().skip(ids.len()) {
+            self.free_page(id);
+        }
+        self.header.first_row_page = ids.first().copied().unwrap_or(0);
+    }
+
+    // Adds the writes to `journal`, with the free list and header they leave. Returns how
+    // many pages they write.
+    pub(crate) fn stage(mut self, journal: &mut Journal) -> usize {
+        while self.header.page_count > 1 && self.free.remove(&(self.header.page_count - 1)) {
+            self.header.page_count -= 1;
+        }
+        let page_count = self.header.page_count;
+        self.dirty.retain(|id, _| *id < page_count);
+        if self.free != self.saved_free {
+            self.write_free_list();
+        }
+        let header = self.header.encode();
+        if self.saved.as_ref() != Some(&header) {
+            self.dirty.insert(0, header);
+        }
+        if self.dirty.is_empty() && self.len == page_count as u64 * PAGE_SIZE as u64 {
+            return 0;
+        }
+        let written = self.dirty.len();
+        journal.write_pages(&self.path, page_count, self.dirty.into_iter().collect());
+        written
+    }
+
+    // Stages the writes in a journal of their own and commits it
+    pub(crate) fn commit(self, sync: SyncMode) -> Result<(), DbError> {
+        let path = journal_path(&self.path);
+        let mut journal = Journal::default();
+        self.stage(&mut journal);
+        journal.commit(&path, sync)
+    }
+
+    // The list is kept on the lowest free pages themselves, which are used no differently
+    // from the rest once it's been read
+    fn write_free_list(&mut self) {
+        let ids: Vec<u32> = self.free.iter().copied().collect();
+        let chunks: Vec<&[u32]> = ids.chunks(FREE_IDS_PER_PAGE).collect();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let next = if i + 1 < chunks.len() { ids[i + 1] } else { 0 };
+            let mut cell = next.to_le_bytes().to_vec();
+            cell.extend(chunk.iter().flat_map(|id| id.to_le_bytes()));
+            let mut page = Page::new();
+            page.insert(&cell);
+            self.dirty.insert(ids[i], page.as_bytes().to_vec());
+        }
+        self.header.free_list = ids.first().copied().unwrap_or(0);
+    }
+
+    pub(crate) fn malformed(&self, id: u32) -> DbError {
+        let offset = id as u64 * PAGE_SIZE as u64;
+        corrupt(&self.path, offset, &format!("page {} is malformed or out of place", id))
+    }
+}
+
+// Each page starts with a cell for the link to the next, filled in once pages have ids.
+// Rows fill pages in order, and a row that doesn't fit in what's left of a page starts
+// the next one, so appending rows leaves every earlier page unchanged.
+pub fn pack_rows(rows: &[Vec<Value>]) -> Vec<Page> {
+    let new_page = || {
+        let mut page = Page::new();
+        page.insert(&[0; 4]);
+        page
+    };
+    let mut pages: Vec<Page> = vec![];
+    for row in rows {
+        let encoded = encode_row(row);
+        let mut rest = encoded.as_slice();
+        if pages.last().is_none_or(|page| page.slot_count() > 1 && page.room() < rest.len() + 1) {
+            pages.push(new_page());
+        }
+        loop {
+            let page = pages.last_mut().unwrap();
+            let take = rest.len().min(page.room() - 1);
+            let flag = if take == rest.len() { LAST_PIECE } else { MORE_PIECES };
+            let mut cell = vec![flag];
+            cell.extend(&rest[..take]);
+            page.insert(&cell);
+            rest = &rest[take..];
+            if flag == LAST_PIECE {
+                break;
+            }
+            pages.push(new_page());
+        }
+    }
+    pages
+}
+
+// Replaces the rows in the page file at `path`, writing only the pages that change.
+// Returns how many pages were written.
+pub fn write_rows(path: &Path, rows: &[Vec<Value>], sync: SyncMode) -> Result<usize, DbError> {
+    let mut journal = Journal::default();
+    let written = stage_rows(path, rows, &mut journal)?;
+    journal.commit(&journal_path(path), sync)?;
+    Ok(written)
+}
+
+// Adds the page writes of write_rows to `journal` rather than making them, so they can
+// be committed along with writes to other files
+pub fn stage_rows(path: &Path, rows: &[Vec<Value>], journal: &mut Journal) -> Result<usize, DbError> {
+    // A file that can't be read as pages is replaced whole; indexes it held go stale
+    let mut pages = match PageFile::open(path) {
+        Ok(pages) => pages,
+        Err(DbError::IoError(_) | DbError::Corruption { .. }) if path.exists() => PageFile::new(path),
+        Err(e) => return Err(e),
+    };
+    pages.replace_rows(rows);
+    Ok(pages.stage(journal))
+}
+
+// Whether the file at `path` is a page file holding disk indexes as well as rows
+pub(crate) fn holds_trees(path: &Path) -> bool {
+    PageFile::open(path).is_ok_and(|pages| !pages.header.trees.is_empty())
+}
+
+// Writes the whole file, compressed as the extension of `path` says, beside it for
+// `journal` to rename into place
+pub fn stage_compressed_rows(
+    path: &Path,
+    rows: &[Vec<Value>],
+    sync: SyncMode,
+    journal: &mut Journal,
+) -> Result<(), DbError> {
+    let mut pages = pack_rows(rows);
+    let count = pages.len() as u32;
+    for (i, page) in pages.iter_mut().enumerate() {
+        let next = if i as u32 + 1 < count { i as u32 + 2 } else { 0 };
+        page.set_cell(0, &next.to_le_bytes());
+    }
+    let header = Header {
+        page_count: count + 1,
+        first_row_page: if count == 0 { 0 } else { 1 },
+        free_list: 0,
+        trees: BTreeMap::new(),
+    };
+    let temp = atomic::write_temp(path, sync, |out| {
+        out.write_all(&header.encode())?;
+        for page in &pages {
+            out.write_all(page.as_bytes())?;
+        }
+        Ok(())
+    })?;
+    journal.rename(&temp, path);
+    Ok(())
+}
+
+// Decompresses the file first when its extension names a compression. Pages that fail
+// their checksum, or a file that ends early, are a DbError::Corruption at that offset.
+pub fn read_rows(path: &Path) -> Result<Vec<Vec<Value>>, DbError> {
+    if Compression::of(path).is_none() {
+        journal::recover(&journal_path(path))?;
+    }
+    let bytes = compress::read(path)?;
+    if bytes.starts_with(MAGIC) {
+        read_linked(&bytes, path)
+    } else if bytes.starts_with(SEQUENTIAL_MAGIC) || bytes.starts_with(UNSEALED_MAGIC) {
+        read_sequential(&bytes, path)
+    } else {
+        Err(unreadable(path, "not a page file"))
+    }
+}
+
+fn read_linked(bytes: &[u8], path: &Path) -> Result<Vec<Vec<Value>>, DbError> {
+    let header = Header::decode(bytes, path)?;
+    let expected = header.page_count as usize * PAGE_SIZE;
+    if bytes.len() < expected {
+        let detail = format!("the file ends {} bytes short", expected - bytes.len());
+        return Err(corrupt(path, bytes.len() as u64, &detail));
+    }
+
+    let mut rows = RowReader::default();
+    let mut next = header.first_row_page;
+    let mut followed = 0;
+    while next != 0 {
+        let offset = next as usize * PAGE_SIZE;
+        if next >= header.page_count || followed >= header.page_count {
+            return Err(corrupt(path, offset as u64, &format!("page {} is out of place", next)));
+        }
+        let block = &bytes[offset..offset + PAGE_SIZE];
+        if !Page::checksum_matches(block) {
+            return Err(corrupt(path, offset as u64, &format!("page {} fails its checksum", next)));
+        }
+        let malformed = || corrupt(path, offset as u64, &format!("page {} is malformed", next));
+        let page = Page::from_bytes(block.to_vec()).ok_or_else(malformed)?;
+        let link = link(&page).ok_or_else(malformed)?;
+        rows.read(page.cells().skip(1), |detail| corrupt(path, offset as u64, &format!("page {} {}", next, detail)))?;
+        next = link;
+        followed += 1;
+    }
+    rows.finish(|detail| corrupt(path, bytes.len() as u64, detail))
+}
+
+fn read_sequential(bytes: &[u8], path: &Path) -> Result<Vec<Vec<Value>>, DbError> {
+    let sealed = bytes.starts_with(SEQUENTIAL_MAGIC);
+    if bytes.len() < PAGE_SIZE {
+        return Err(corrupt(path, bytes.len() as u64, "the header page is cut short"));
+    }
+    if bytes[8..12] != (PAGE_SIZE as u32).to_le_bytes() {
+        return Err(unreadable(path, "written with a different page size"));
+    }
+    if sealed {
+        let fields = SEQUENTIAL_FIELDS;
+        if bytes[fields..fields + 4] != crc32fast::hash(&bytes[..fields]).to_le_bytes() {
+            return Err(corrupt(path, 0, "the header fails its checksum"));
+        }
+        let count = u32::from_le_bytes(bytes[12..fields].try_into().unwrap()) as usize;
+        let expected = (count + 1) * PAGE_SIZE;
+        if bytes.len() < expected {
+            let detail = format!("the file ends {} bytes short", expected - bytes.len());
+            return Err(corrupt(path, bytes.len() as u64, &detail));
+        }
+        if bytes.len() > expected {
+            return Err(corrupt(path, expected as u64, "trailing bytes after the last page"));
+        }
+    } else if !bytes.len().is_multiple_of(PAGE_SIZE) {
+        return Err(unreadable(path, "not a page file"));
+    }
+
+    let mut rows = RowReader::default();
+    for (i, block) in bytes.chunks(PAGE_SIZE).enumerate().skip(1) {
+        let offset = (i * PAGE_SIZE) as u64;
+        if sealed && !Page::checksum_matches(block) {
+            return Err(corrupt(path, offset, &format!("page {} fails its checksum", i)));
+        }
+        let page = if sealed {
+            Page::from_bytes(block.to_vec())
+        } else {
+            Page::from_unsealed_bytes(block.to_vec())
+        };
+        let page = page.ok_or_else(|| corrupt(path, offset, &format!("page {} is malformed", i)))?;
+        rows.read(page.cells(), |detail| corrupt(path, offset, &format!("page {} {}", i, detail)))?;
+    }
+    rows.finish(|detail| corrupt(path, bytes.len() as u64, detail))
+}
+
+// Joins the pieces of rows that span pages
+#[derive(Default)]
+struct RowReader {
+    rows: Vec<Vec<Value>>,
+    pending: Vec<u8>,
+}
+
+impl RowReader {
+    fn read<'a>(
+        &mut self,
+        cells: impl Iterator<Item = &'a [u8]>,
+        corrupt: impl Fn(&str) -> DbError,
+    ) -> Result<(), DbError> {
+        for cell in cells {
+            let (&flag, piece) = cell.split_first().ok_or_else(|| corrupt("has an empty cell"))?;
+            self.pending.extend_from_slice(piece);
+            match flag {
+                LAST_PIECE => self.rows.push(decode_row(&self.pending).map_err(|e| corrupt(e.message()))?),
+                MORE_PIECES => continue,
+                _ => return Err(corrupt(&format!("has an unknown cell flag {}", flag))),
+            }
+            self.pending.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(self, corrupt: impl Fn(&str) -> DbError) -> Result<Vec<Vec<Value>>, DbError> {
+        if !self.pending.is_empty() {
+            return Err(corrupt("the last row is cut short"));
+        }
+        Ok(self.rows)
+    }
+}
+
+fn link(page: &Page) -> Option<u32> {
+    Some(u32::from_le_bytes(page.cell(0)?.try_into().ok()?))
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> DbError {
+    DbError::IoError(format!("Failed to read {}: {}", path.display(), e))
+}
+
+fn unreadable(path: &Path, detail: &str) -> DbError {
+    DbError::IoError(format!("Corrupt page file {}: {}", path.display(), detail))
+}
+
+fn corrupt(path: &Path, offset: u64, detail: &str) -> DbError {
+    DbError::Corruption {
+        offset,
+        message: format!("Corrupt page file {} at byte {}: {}", path.display(), offset, detail),
+    }
+}
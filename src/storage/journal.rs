@@ -0,0 +1,271 @@
+use crate::error::DbError;
+use crate::storage::atomic::{sync_dir, SyncMode};
+use crate::storage::page::PAGE_SIZE;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+// A redo log of writes about to be made to files in one directory. The journal is made
+// durable first and the writes applied after, then it's removed. A journal found whole
+// when its files are next opened is applied again, and one cut short is thrown away, so
+// a crash leaves every file as it was before the writes or after all of them.
+//   [magic] [op count: u32] ops... [crc32 of everything before]
+//   pages op   [1][file name: u32 len, bytes][page count: u32][count: u32] ([page: u32][bytes])...
+//   rename op  [2][from: u32 len, bytes][to: u32 len, bytes]
+//   remove op  [3][file name: u32 len, bytes]
+// Files are named relative to the journal's directory. Whole files are written beside
+// their targets first, and a rename op moves each into place.
+const MAGIC: &[u8; 8] = b"DBJRNL01";
+const PAGES: u8 = 1;
+const RENAME: u8 = 2;
+const REMOVE: u8 = 3;
+
+#[derive(Debug, Default)]
+pub struct Journal {
+    ops: Vec<Op>,
+}
+
+#[derive(Debug)]
+enum Op {
+    // Pages written in place, after which the file is cut or grown to `page_count` pages
+    Pages {
+        file: String,
+        page_count: u32,
+        pages: Vec<(u32, Vec<u8>)>,
+    },
+    // Done already when `from` is gone
+    Rename {
+        from: String,
+        to: String,
+    },
+    Remove {
+        file: String,
+    },
+}
+
+// Where the journal for writes to `path` alone is kept
+pub fn journal_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".journal");
+    PathBuf::from(name)
+}
+
+impl Journal {
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    pub(crate) fn write_pages(&mut self, file: &Path, page_count: u32, pages: Vec<(u32, Vec<u8>)>) {
+        self.ops.push(Op::Pages {
+            file: file_name(file),
+            page_count,
+            pages,
+        });
+    }
+
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        self.ops.push(Op::Rename {
+            from: file_name(from),
+            to: file_name(to),
+        });
+    }
+
+    pub fn remove(&mut self, file: &Path) {
+        self.ops.push(Op::Remove { file: file_name(file) });
+    }
+
+    // Gives up on a journal that won't be committed, removing the files written for it
+    pub fn abandon(self, dir: &Path) {
+        for op in self.ops {
+            if let Op::Rename { from, .. } = op {
+                let _ = fs::remove_file(dir.join(from));
+            }
+        }
+    }
+
+    // Makes the journal at `path` durable as `sync` says, applies it and removes it
+    pub fn commit(self, path: &Path, sync: SyncMode) -> Result<(), DbError> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        self.write(path, sync)?;
+        let dir = parent(path);
+        self.apply(dir, sync)?;
+        fs::remove_file(path).map_err(|e| DbError::IoError(format!("Failed to remove {}: {}", path.display(), e)))?;
+        if sync == SyncMode::Full {
+            sync_dir(dir).map_err(|e| DbError::IoError(format!("Failed to sync {}: {}", dir.display(), e)))?;
+        }
+        Ok(())
+    }
+
+    // Only makes the journal durable; it's applied by commit or, after a crash, recover
+    pub fn write(&self, path: &Path, sync: SyncMode) -> Result<(), DbError> {
+        let io_error = |e: std::io::Error| DbError::IoError(format!("Failed to write {}: {}", path.display(), e));
+        let mut file = fs::File::create(path).map_err(io_error)?;
+        file.write_all(&self.encode()).map_err(io_error)?;
+        if sync != SyncMode::Off {
+            file.sync_all().map_err(io_error)?;
+        }
+        if sync == SyncMode::Full {
+            sync_dir(parent(path)).map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend((self.ops.len() as u32).to_le_bytes());
+        for op in &self.ops {
+            match op {
+                Op::Pages { file, page_count, pages } => {
+                    bytes.push(PAGES);
+                    put_str(&mut bytes, file);
+                    bytes.extend(page_count.to_le_bytes());
+                    bytes.extend((pages.len() as u32).to_le_bytes());
+                    for (id, page) in pages {
+                        bytes.extend(id.to_le_bytes());
+                        bytes.extend(page);
+                    }
+                }
+                Op::Rename { from, to } => {
+                    bytes.push(RENAME);
+                    put_str(&mut bytes, from);
+                    put_str(&mut bytes, to);
+                }
+                Op::Remove { file } => {
+                    bytes.push(REMOVE);
+                    put_str(&mut bytes, file);
+                }
+            }
+        }
+        let crc = crc32fast::hash(&bytes);
+        bytes.extend(crc.to_le_bytes());
+        bytes
+    }
+
+    // None for a journal cut short or not a journal at all
+    fn decode(bytes: &[u8]) -> Option<Journal> {
+        let (body, crc) = bytes.split_at_checked(bytes.len().checked_sub(4)?)?;
+        if !body.starts_with(MAGIC) || crc32fast::hash(body).to_le_bytes() != crc {
+            return None;
+        }
+        let mut reader = Reader { bytes: body, at: MAGIC.len() };
+        let count = reader.u32()?;
+        let mut ops = vec![];
+        for _ in 0..count {
+            match reader.take(1)?[0] {
+                PAGES => {
+                    let file = reader.string()?;
+                    let page_count = reader.u32()?;
+                    let pages = (0..reader.u32()?)
+                        .map(|_| Some((reader.u32()?, reader.take(PAGE_SIZE)?.to_vec())))
+                        .collect::<Option<Vec<_>>>()?;
+                    ops.push(Op::Pages { file, page_count, pages });
+                }
+                RENAME => {
+                    let from = reader.string()?;
+                    let to = reader.string()?;
+                    ops.push(Op::Rename { from, to });
+                }
+                REMOVE => ops.push(Op::Remove { file: reader.string()? }),
+                _ => return None,
+            }
+        }
+        Some(Journal { ops })
+    }
+
+    // Every op can be applied again over its own result, so a crash while applying is
+    // recovered by applying the whole journal once more
+    fn apply(&self, dir: &Path, sync: SyncMode) -> Result<(), DbError> {
+        for op in &self.ops {
+            match op {
+                Op::Pages { file, page_count, pages } => {
+                    let path = dir.join(file);
+                    let io_error = |e: std::io::Error| DbError::IoError(format!("Failed to write {}: {}", path.display(), e));
+                    let mut out = OpenOptions::new()
+                        .write(true)
+                        .create(true)
+                        .truncate(false)
+                        .open(&path)
+                        .map_err(io_error)?;
+                    for (id, page) in pages {
+                        out.seek(SeekFrom::Start(*id as u64 * PAGE_SIZE as u64)).map_err(io_error)?;
+                        out.write_all(page).map_err(io_error)?;
+                    }
+                    out.set_len(*page_count as u64 * PAGE_SIZE as u64).map_err(io_error)?;
+                    if sync != SyncMode::Off {
+                        out.sync_all().map_err(io_error)?;
+                    }
+                }
+                Op::Rename { from, to } => {
+                    let (from, to) = (dir.join(from), dir.join(to));
+                    if from.exists() {
+                        fs::rename(&from, &to)
+                            .map_err(|e| DbError::IoError(format!("Failed to write {}: {}", to.display(), e)))?;
+                    }
+                }
+                Op::Remove { file } => {
+                    let path = dir.join(file);
+                    match fs::remove_file(&path) {
+                        Err(e) if e.kind() != ErrorKind::NotFound => {
+                            return Err(DbError::IoError(format!("Failed to remove {}: {}", path.display(), e)))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Finishes or throws away a journal left at `path` by a crash. Returns whether one was
+// applied.
+pub fn recover(path: &Path) -> Result<bool, DbError> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(DbError::IoError(format!("Failed to read {}: {}", path.display(), e))),
+    };
+    let journal = Journal::decode(&bytes);
+    if let Some(journal) = &journal {
+        journal.apply(parent(path), SyncMode::Full)?;
+    }
+    fs::remove_file(path).map_err(|e| DbError::IoError(format!("Failed to remove {}: {}", path.display(), e)))?;
+    Ok(journal.is_some())
+}
+
+fn parent(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new(""))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn put_str(bytes: &mut Vec<u8>, s: &str) {
+    bytes.extend((s.len() as u32).to_le_bytes());
+    bytes.extend(s.as_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes.get(self.at..self.at.checked_add(len)?)?;
+        self.at += len;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+}
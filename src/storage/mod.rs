@@ -0,0 +1,9 @@
+pub mod page;
+pub mod codec;
+pub mod file;
+pub mod btree;
+pub mod spill;
+pub mod compress;
+pub mod checksum;
+pub mod atomic;
+pub mod journal;
@@ -0,0 +1,144 @@
+// Every page file is a run of PAGE_SIZE blocks
+pub const PAGE_SIZE: usize = 4096;
+
+// Slotted layout: a header of slot count and the start of cell data, then the slot
+// directory growing forwards and the cells growing backwards from the checksum, a CRC32
+// of everything before it, at the end of the page.
+//   [count: u16][data_start: u16][offset: u16, len: u16]...  free  ...[cell][cell][crc: u32]
+const HEADER_SIZE: usize = 4;
+const SLOT_SIZE: usize = 4;
+const CELLS_END: usize = PAGE_SIZE - 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    bytes: Vec<u8>,
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Page::new()
+    }
+}
+
+impl Page {
+    pub fn new() -> Self {
+        let mut page = Page {
+            bytes: vec![0; PAGE_SIZE],
+        };
+        page.set_u16(2, CELLS_END as u16);
+        page.seal();
+        page
+    }
+
+    // None unless the bytes are a whole page whose slots all point inside it. The checksum
+    // isn't looked at; see checksum_matches.
+    pub fn from_bytes(bytes: Vec<u8>) -> Option<Self> {
+        Page::parse(bytes, CELLS_END)
+    }
+
+    // For pages written before checksums, whose cells may run to the very end
+    pub fn from_unsealed_bytes(bytes: Vec<u8>) -> Option<Self> {
+        Page::parse(bytes, PAGE_SIZE)
+    }
+
+    pub fn checksum_matches(bytes: &[u8]) -> bool {
+        bytes.len() == PAGE_SIZE && crc32fast::hash(&bytes[..CELLS_END]).to_le_bytes() == bytes[CELLS_END..]
+    }
+
+    // Whether a page can hold cells of these lengths
+    pub fn fits(cell_lens: impl IntoIterator<Item = usize>) -> bool {
+        HEADER_SIZE + cell_lens.into_iter().map(|len| SLOT_SIZE + len).sum::<usize>() <= CELLS_END
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn slot_count(&self) -> usize {
+        self.u16_at(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slot_count() == 0
+    }
+
+    // Bytes a new cell can hold, after its slot is taken from the free space
+    pub fn room(&self) -> usize {
+        let directory_end = HEADER_SIZE + self.slot_count() * SLOT_SIZE;
+        (self.u16_at(2) as usize).saturating_sub(directory_end + SLOT_SIZE)
+    }
+
+    pub fn cell(&self, slot: usize) -> Option<&[u8]> {
+        if slot >= self.slot_count() {
+            return None;
+        }
+        let (offset, len) = self.slot(slot);
+        Some(&self.bytes[offset..offset + len])
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = &[u8]> {
+        (0..self.slot_count()).filter_map(|slot| self.cell(slot))
+    }
+
+    // The new cell's slot, or None when it doesn't fit
+    pub fn insert(&mut self, cell: &[u8]) -> Option<usize> {
+        if cell.len() > self.room() {
+            return None;
+        }
+        let slot = self.slot_count();
+        let offset = self.u16_at(2) as usize - cell.len();
+        self.bytes[offset..offset + cell.len()].copy_from_slice(cell);
+        self.set_u16(HEADER_SIZE + slot * SLOT_SIZE, offset as u16);
+        self.set_u16(HEADER_SIZE + slot * SLOT_SIZE + 2, cell.len() as u16);
+        self.set_u16(0, slot as u16 + 1);
+        self.set_u16(2, offset as u16);
+        self.seal();
+        Some(slot)
+    }
+
+    // Replaces a cell's bytes with as many others
+    pub fn set_cell(&mut self, slot: usize, cell: &[u8]) -> Option<()> {
+        if slot >= self.slot_count() || self.slot(slot).1 != cell.len() {
+            return None;
+        }
+        let (offset, len) = self.slot(slot);
+        self.bytes[offset..offset + len].copy_from_slice(cell);
+        self.seal();
+        Some(())
+    }
+
+    fn parse(bytes: Vec<u8>, cells_end: usize) -> Option<Self> {
+        if bytes.len() != PAGE_SIZE {
+            return None;
+        }
+        let page = Page { bytes };
+        let directory_end = HEADER_SIZE + page.slot_count() * SLOT_SIZE;
+        let data_start = page.u16_at(2) as usize;
+        if directory_end > data_start || data_start > cells_end {
+            return None;
+        }
+        let in_bounds = (0..page.slot_count()).all(|slot| {
+            let (offset, len) = page.slot(slot);
+            offset >= data_start && offset + len <= cells_end
+        });
+        in_bounds.then_some(page)
+    }
+
+    fn seal(&mut self) {
+        let crc = crc32fast::hash(&self.bytes[..CELLS_END]);
+        self.bytes[CELLS_END..].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    fn slot(&self, slot: usize) -> (usize, usize) {
+        let at = HEADER_SIZE + slot * SLOT_SIZE;
+        (self.u16_at(at) as usize, self.u16_at(at + 2) as usize)
+    }
+
+    fn u16_at(&self, at: usize) -> u16 {
+        u16::from_le_bytes([self.bytes[at], self.bytes[at + 1]])
+    }
+
+    fn set_u16(&mut self, at: usize, value: u16) {
+        self.bytes[at..at + 2].copy_from_slice(&value.to_le_bytes());
+    }
+}
@@ -0,0 +1,78 @@
+use crate::error::DbError;
+use crate::storage::codec::{decode_row, encode_row};
+use crate::table::data::Value;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+// A sorted run written to a temporary file, one entry after another: the key's length
+// (u32), the key as a row, then the position (u64). The file goes when the run is dropped.
+pub struct SpillRun {
+    path: PathBuf,
+}
+
+impl SpillRun {
+    pub fn write(entries: impl IntoIterator<Item = Result<(Vec<Value>, usize), DbError>>) -> Result<Self, DbError> {
+        let id = NEXT_RUN.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("sort-{}-{}.run", std::process::id(), id));
+        let io_error = |e: std::io::Error| DbError::IoError(format!("Failed to spill sort run: {}", e));
+        // Owned from here so an early return still removes the file
+        let run = SpillRun { path };
+        let mut out = BufWriter::new(File::create(&run.path).map_err(io_error)?);
+        for entry in entries {
+            let (key, position) = entry?;
+            let encoded = encode_row(&key);
+            out.write_all(&(encoded.len() as u32).to_le_bytes()).map_err(io_error)?;
+            out.write_all(&encoded).map_err(io_error)?;
+            out.write_all(&(position as u64).to_le_bytes()).map_err(io_error)?;
+        }
+        out.flush().map_err(io_error)?;
+        Ok(run)
+    }
+
+    // The entries in the order they were written
+    pub fn read(&self) -> Result<SpillReader, DbError> {
+        let file = File::open(&self.path)
+            .map_err(|e| DbError::IoError(format!("Failed to read sort run: {}", e)))?;
+        Ok(SpillReader {
+            input: BufReader::new(file),
+        })
+    }
+}
+
+impl Drop for SpillRun {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+pub struct SpillReader {
+    input: BufReader<File>,
+}
+
+impl Iterator for SpillReader {
+    type Item = Result<(Vec<Value>, usize), DbError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let io_error = |e: std::io::Error| DbError::IoError(format!("Failed to read sort run: {}", e));
+        let mut len = [0; 4];
+        match self.input.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(io_error(e))),
+        }
+        let mut encoded = vec![0; u32::from_le_bytes(len) as usize];
+        let mut position = [0; 8];
+        let entry = self
+            .input
+            .read_exact(&mut encoded)
+            .and_then(|_| self.input.read_exact(&mut position))
+            .map_err(io_error)
+            .and_then(|_| decode_row(&encoded))
+            .map(|key| (key, u64::from_le_bytes(position) as usize));
+        Some(entry)
+    }
+}
@@ -0,0 +1,241 @@
+use crate::error::DbError;
+use crate::table::data::{AggFunc, AggregationResult, DataType, Decimal, Value};
+use crate::table::decimal::{quotient_scale, MAX_PRECISION};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+impl AggregationResult {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            AggregationResult::Count(n) => Some(*n as f64),
+            AggregationResult::Sum(v)
+            | AggregationResult::Avg(v)
+            | AggregationResult::Min(v)
+            | AggregationResult::Max(v) => match v {
+                Value::Int(i) => Some(*i as f64),
+                Value::BigInt(i) => Some(*i as f64),
+                Value::Float(f) => Some(*f as f64),
+                Value::Double(f) => Some(*f),
+                Value::Decimal(d) => Some(d.to_f64()),
+                _ => None,
+            },
+            AggregationResult::StdDev(v)
+            | AggregationResult::Variance(v)
+            | AggregationResult::Median(v)
+            | AggregationResult::Percentile(v) => *v,
+            AggregationResult::CountDistinct(n) => Some(*n as f64),
+            AggregationResult::GroupConcat(_) => None,
+        }
+    }
+
+    pub fn as_count(&self) -> Option<usize> {
+        match self {
+            AggregationResult::Count(n) | AggregationResult::CountDistinct(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_value(&self) -> Value {
+        match self {
+            AggregationResult::Count(n) | AggregationResult::CountDistinct(n) => Value::BigInt(*n as i64),
+            AggregationResult::Sum(v)
+            | AggregationResult::Avg(v)
+            | AggregationResult::Min(v)
+            | AggregationResult::Max(v) => v.clone(),
+            AggregationResult::StdDev(v)
+            | AggregationResult::Variance(v)
+            | AggregationResult::Median(v)
+            | AggregationResult::Percentile(v) => v.map_or(Value::Null, Value::Double),
+            AggregationResult::GroupConcat(s) => s.clone().map_or(Value::Null, Value::Text),
+        }
+    }
+}
+
+impl AggFunc {
+    // NULLs are left out of everything except Count, which counts rows
+    pub fn apply(&self, rows: &[&Vec<Value>], idx: usize) -> Result<AggregationResult, DbError> {
+        let present = || rows.iter().map(|row| &row[idx]).filter(|v| **v != Value::Null);
+        let values: Vec<f64> = present()
+            .filter_map(|v| match v {
+                Value::Decimal(d) => Some(d.to_f64()),
+                _ => v.as_numeric().map(|n| n.to_f64()),
+            })
+            .collect();
+        // Decimal columns add up exactly
+        let decimals: Option<Vec<Decimal>> = present()
+            .map(|v| match v {
+                Value::Decimal(d) => Some(*d),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .filter(|decimals| !decimals.is_empty());
+
+        let agg = match self {
+            AggFunc::Sum => AggregationResult::Sum(match decimals {
+                Some(decimals) => Value::Decimal(decimal_sum(&decimals)?),
+                None => Value::Double(values.iter().sum()),
+            }),
+            AggFunc::Avg => AggregationResult::Avg(match decimals {
+                Some(decimals) => {
+                    let total = decimal_sum(&decimals)?;
+                    let count = Decimal::new(decimals.len() as i128, 0);
+                    let avg = total.checked_div(count, quotient_scale(total.scale));
+                    Value::Decimal(avg.ok_or_else(|| DbError::InvalidArgument("AVG is out of DECIMAL range".to_string()))?)
+                }
+                None if values.is_empty() => Value::Null,
+                None => Value::Double(values.iter().sum::<f64>() / values.len() as f64),
+            }),
+            AggFunc::Count => AggregationResult::Count(rows.len()),
+            AggFunc::CountDistinct => AggregationResult::CountDistinct(present().collect::<HashSet<_>>().len()),
+            AggFunc::Min => AggregationResult::Min(present().min().cloned().unwrap_or(Value::Null)),
+            AggFunc::Max => AggregationResult::Max(present().max().cloned().unwrap_or(Value::Null)),
+            AggFunc::StdDev => AggregationResult::StdDev(sample_variance(&values).map(f64::sqrt)),
+            AggFunc::Variance => AggregationResult::Variance(sample_variance(&values)),
+            AggFunc::Median => AggregationResult::Median(percentile(&values, 0.5)),
+            AggFunc::Percentile(p) if (0.0..=1.0).contains(p) => AggregationResult::Percentile(percentile(&values, *p)),
+            AggFunc::Percentile(p) => {
+                return Err(DbError::InvalidArgument(format!("Percentile {} must be between 0 and 1", p)))
+            }
+            AggFunc::GroupConcat(separator) => {
+                let parts: Vec<String> = present().map(Value::to_display_string).collect();
+                AggregationResult::GroupConcat((!parts.is_empty()).then(|| parts.join(separator)))
+            }
+        };
+        Ok(agg)
+    }
+
+    // The type of the values this gives over a column of type `column`
+    pub fn datatype(&self, column: &DataType) -> DataType {
+        match self {
+            AggFunc::Count | AggFunc::CountDistinct => DataType::BigInt,
+            AggFunc::Min | AggFunc::Max => column.clone(),
+            AggFunc::GroupConcat(_) => DataType::Text,
+            AggFunc::Sum => match column {
+                DataType::Decimal(_, scale) => DataType::Decimal(MAX_PRECISION, *scale),
+                _ => DataType::Double,
+            },
+            AggFunc::Avg => match column {
+                DataType::Decimal(_, scale) => DataType::Decimal(MAX_PRECISION, quotient_scale(*scale)),
+                _ => DataType::Double,
+            },
+            _ => DataType::Double,
+        }
+    }
+
+    // The name of the result column, e.g. "sum(amount)", "count(DISTINCT region)" or
+    // "percentile(amount, 0.9)"
+    pub fn label(&self, column: &str) -> String {
+        match self {
+            AggFunc::CountDistinct => format!("count(DISTINCT {})", column),
+            AggFunc::Percentile(p) => format!("percentile({}, {})", column, p),
+            AggFunc::GroupConcat(separator) => format!("group_concat({}, {})", column, separator),
+            _ => format!("{}({})", self, column),
+        }
+    }
+}
+
+fn decimal_sum(decimals: &[Decimal]) -> Result<Decimal, DbError> {
+    decimals
+        .iter()
+        .try_fold(Decimal::new(0, 0), |total, d| total.checked_add(*d))
+        .ok_or_else(|| DbError::InvalidArgument("SUM is out of DECIMAL range".to_string()))
+}
+
+// Divides by n - 1, so it takes two values to have one
+fn sample_variance(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    Some(values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0))
+}
+
+// Interpolates between the two nearest values, so the median of 1, 2, 3, 4 is 2.5
+fn percentile(values: &[f64], p: f64) -> Option<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let last = sorted.len().checked_sub(1)?;
+    let rank = p * last as f64;
+    let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[below] + (sorted[above] - sorted[below]) * (rank - below as f64))
+}
+
+impl fmt::Display for AggregationResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregationResult::Count(n) => write!(f, "{}", n),
+            AggregationResult::Sum(v)
+            | AggregationResult::Avg(v)
+            | AggregationResult::Min(v)
+            | AggregationResult::Max(v) => {
+                write!(f, "{}", v.to_display_string())
+            }
+            AggregationResult::StdDev(v)
+            | AggregationResult::Variance(v)
+            | AggregationResult::Median(v)
+            | AggregationResult::Percentile(v) => match v {
+                Some(v) => write!(f, "{}", v),
+                None => write!(f, "NULL"),
+            },
+            AggregationResult::CountDistinct(n) => write!(f, "{}", n),
+            AggregationResult::GroupConcat(s) => write!(f, "{}", s.as_deref().unwrap_or("NULL")),
+        }
+    }
+}
+
+impl fmt::Display for AggFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggFunc::Sum => write!(f, "sum"),
+            AggFunc::Avg => write!(f, "avg"),
+            AggFunc::Count => write!(f, "count"),
+            AggFunc::CountDistinct => write!(f, "count_distinct"),
+            AggFunc::Min => write!(f, "min"),
+            AggFunc::Max => write!(f, "max"),
+            AggFunc::StdDev => write!(f, "stddev"),
+            AggFunc::Variance => write!(f, "variance"),
+            AggFunc::Median => write!(f, "median"),
+            AggFunc::Percentile(p) => write!(f, "percentile({})", p),
+            AggFunc::GroupConcat(separator) => write!(f, "group_concat({})", separator),
+        }
+    }
+}
+
+// Reads what Display writes, in any case. "var" is short for variance, and a bare
+// "group_concat" joins with commas.
+impl FromStr for AggFunc {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        let func = match lower.as_str() {
+            "sum" => AggFunc::Sum,
+            "avg" => AggFunc::Avg,
+            "count" => AggFunc::Count,
+            "count_distinct" => AggFunc::CountDistinct,
+            "min" => AggFunc::Min,
+            "max" => AggFunc::Max,
+            "stddev" => AggFunc::StdDev,
+            "var" | "variance" => AggFunc::Variance,
+            "median" => AggFunc::Median,
+            "group_concat" => AggFunc::GroupConcat(",".to_string()),
+            _ if lower.starts_with("percentile(") => {
+                let p = call_arg(s, "percentile(").and_then(|p| p.trim().parse().ok());
+                AggFunc::Percentile(p.ok_or_else(|| format!("Invalid percentile in '{}'", s))?)
+            }
+            _ if lower.starts_with("group_concat(") => {
+                let separator = call_arg(s, "group_concat(").ok_or_else(|| format!("Missing ')' in '{}'", s))?;
+                AggFunc::GroupConcat(separator.to_string())
+            }
+            _ => return Err(format!("Unknown aggregation function '{}'", s)),
+        };
+        Ok(func)
+    }
+}
+
+// What is between `prefix` and the closing bracket, kept as written
+fn call_arg<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    s.get(prefix.len()..)?.strip_suffix(')')
+}
@@ -0,0 +1,118 @@
+use crate::table::data::{Collation, Column, FilterExpr, Options, Table, Value};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::fmt;
+
+impl Collation {
+    // The value `value` sorts and groups as. Binary keys are the values themselves, so
+    // they are only borrowed; non-string values are never changed.
+    pub fn key<'a>(&self, value: &'a Value) -> Cow<'a, Value> {
+        match (self, value) {
+            (Collation::Binary, _) => Cow::Borrowed(value),
+            (_, Value::Char(c)) => Cow::Owned(Value::Varchar(self.key_text(&c.to_string()))),
+            (_, Value::Varchar(s)) => Cow::Owned(Value::Varchar(self.key_text(s))),
+            (_, Value::Text(s)) => Cow::Owned(Value::Text(self.key_text(s))),
+            _ => Cow::Borrowed(value),
+        }
+    }
+
+    fn key_text(&self, text: &str) -> String {
+        match self {
+            Collation::Binary => text.to_string(),
+            Collation::CaseInsensitive => text.to_lowercase(),
+            // The folded text decides the order, and the text as written breaks ties, so
+            // only identical strings share a key
+            Collation::LatinFold => format!("{}\0{}", fold(text), text),
+        }
+    }
+
+    pub fn compare(&self, a: &Value, b: &Value) -> Ordering {
+        self.key(a).cmp(&self.key(b))
+    }
+}
+
+impl fmt::Display for Collation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Collation::Binary => "BINARY",
+            Collation::CaseInsensitive => "NOCASE",
+            Collation::LatinFold => "LATIN_FOLD",
+        })
+    }
+}
+
+impl FilterExpr {
+    // The same filter over collation keys: each value it compares with becomes its key
+    pub(crate) fn collated(&self, collation: Collation) -> FilterExpr {
+        let Ok(filter) = self.try_map_values(&mut |value| Ok::<_, Infallible>(collation.key(value).into_owned()));
+        filter
+    }
+}
+
+impl Value {
+    pub fn collated_cmp(&self, other: &Value, collation: Collation) -> Ordering {
+        collation.compare(self, other)
+    }
+}
+
+impl Column {
+    pub fn collation(&self) -> Collation {
+        self.options
+            .iter()
+            .find_map(|opt| match opt {
+                Options::Collate(collation) => Some(*collation),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Table {
+    pub(crate) fn column_collation(&self, column: &str) -> Collation {
+        self.columns
+            .iter()
+            .find(|c| c.name == column)
+            .map_or(Collation::Binary, Column::collation)
+    }
+
+    // The key `value` is filed under in the index on `column`
+    pub(crate) fn index_key<'a>(&self, column: &str, value: &'a Value) -> Cow<'a, Value> {
+        self.column_collation(column).key(value)
+    }
+}
+
+// Lowercase with the accents of Latin letters removed, e.g. "Émile" becomes "emile".
+// Letters of other scripts are only lowercased.
+fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        let base = match c {
+            'à'..='å' | 'ā' | 'ă' | 'ą' => "a",
+            'æ' => "ae",
+            'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+            'ð' | 'ď' | 'đ' => "d",
+            'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+            'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+            'ì'..='ï' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+            'ł' | 'ľ' | 'ĺ' | 'ļ' => "l",
+            'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+            'ò'..='ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+            'œ' => "oe",
+            'ŕ' | 'ř' | 'ŗ' => "r",
+            'ß' => "ss",
+            'ś' | 'ŝ' | 'ş' | 'š' => "s",
+            'ţ' | 'ť' => "t",
+            'þ' => "th",
+            'ù'..='ü' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+            'ý' | 'ÿ' => "y",
+            'ź' | 'ż' | 'ž' => "z",
+            _ => {
+                folded.push(c);
+                continue;
+            }
+        };
+        folded.push_str(base);
+    }
+    folded
+}
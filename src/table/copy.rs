@@ -0,0 +1,77 @@
+use crate::error::DbError;
+use crate::table::data::{DataType, Table, Value};
+
+impl Table {
+    // Inserts every live row of `source` as one batch, matching columns by name. Target
+    // columns the source lacks are filled as NULLs would be; a source column with no
+    // match, or whose type can never fit its match, fails before anything is inserted.
+    pub fn insert_from(&mut self, source: &Table) -> Result<usize, DbError> {
+        let rows = self.rows_from(source)?;
+        self.insert_many(rows)
+    }
+
+    // The rows insert_from would insert, laid out as this table's columns
+    pub(crate) fn rows_from(&self, source: &Table) -> Result<Vec<Vec<Value>>, DbError> {
+        let mut targets = vec![];
+        for column in &source.columns {
+            // Joined columns are named `table.column`; the qualifier is the source's business
+            let i = match (self.resolve_column(&column.name), column.name.rsplit_once('.')) {
+                (Err(_), Some((_, name))) => self.resolve_column(name)?,
+                (found, _) => found?,
+            };
+            if targets.contains(&i) {
+                return Err(DbError::InvalidArgument(format!(
+                    "More than one source column matches '{}'",
+                    self.columns[i].name
+                )));
+            }
+            if !assignable(&column.datatype, &self.columns[i].datatype) {
+                return Err(DbError::SchemaError(format!(
+                    "Column '{}' of type {:?} cannot be inserted into '{}' of type {:?}",
+                    column.name, column.datatype, self.columns[i].name, self.columns[i].datatype
+                )));
+            }
+            targets.push(i);
+        }
+
+        Ok(source
+            .live_rows()
+            .map(|row| {
+                let mut values = vec![Value::Null; self.columns.len()];
+                for (value, &i) in row.iter().zip(&targets) {
+                    values[i] = as_string_type(value.clone(), &self.columns[i].datatype);
+                }
+                values
+            })
+            .collect())
+    }
+}
+
+// Whether some value of type `from` could be stored in a column of type `to`. Numbers
+// that would lose precision and strings that are too long still fail row by row.
+fn assignable(from: &DataType, to: &DataType) -> bool {
+    let numeric = |t: &DataType| matches!(t, DataType::Int | DataType::BigInt | DataType::Float | DataType::Double);
+    let string = |t: &DataType| matches!(t, DataType::Char(_) | DataType::Varchar(_) | DataType::Text | DataType::Enum(_));
+    match (from, to) {
+        (DataType::Decimal(..), DataType::Decimal(..)) | (DataType::Set(_), DataType::Set(_)) => true,
+        _ if numeric(from) && numeric(to) => true,
+        _ if string(from) && string(to) => true,
+        _ => from == to,
+    }
+}
+
+// Re-wraps string values in the variant a string column of `datatype` stores
+fn as_string_type(value: Value, datatype: &DataType) -> Value {
+    let text = match &value {
+        Value::Char(c) => c.to_string(),
+        Value::Varchar(s) | Value::Text(s) | Value::Enum(s) => s.clone(),
+        _ => return value,
+    };
+    match (value, datatype) {
+        (Value::Char(c), DataType::Char(_)) => Value::Char(c),
+        (_, DataType::Char(_) | DataType::Varchar(_)) => Value::Varchar(text),
+        (_, DataType::Text) => Value::Text(text),
+        (_, DataType::Enum(_)) => Value::Enum(text),
+        (value, _) => value,
+    }
+}
@@ -0,0 +1,118 @@
+use crate::error::DbError;
+use crate::table::data::{Cursor, IndexType, NullsOrder, OrderBy, Options, SortDirection, SortKey, Table, Value};
+use crate::table::ordering::compare_sort_keys;
+use std::cmp::Ordering;
+use std::iter;
+
+// A row's sort key and its position in the table
+type Keyed = (Vec<Value>, usize);
+
+impl Table {
+    // The cursor does not borrow the table, so it can be held across writes or rebuilt
+    // from a saved `after` key
+    pub fn cursor(&self, order: &[OrderBy], page_size: usize) -> Result<Cursor, DbError> {
+        if page_size == 0 {
+            return Err(DbError::InvalidArgument("Cursor page size must be at least 1".to_string()));
+        }
+        let mut order = order.to_vec();
+        let mut sorted = vec![];
+        for spec in &order {
+            if let SortKey::Column(name) = &spec.key {
+                sorted.push(self.resolve_column(name)?);
+            }
+        }
+
+        // The primary key keeps ties in the same order across deletes; the row's position
+        // settles whatever is left
+        let unique = sorted.iter().any(|&i| self.columns[i].options.contains(&Options::Unique));
+        if let (false, Some(key)) = (unique, &self.primary_key) {
+            for column in key {
+                if !sorted.contains(&self.resolve_column(column)?) {
+                    order.push(OrderBy::asc(column));
+                }
+            }
+        }
+
+        Ok(Cursor {
+            order,
+            page_size,
+            after: None,
+        })
+    }
+}
+
+impl Cursor {
+    // The next rows in order as the table stands now. An empty page means the cursor has
+    // passed the last row, though rows inserted later past its key will still turn up.
+    pub fn next_page<'a>(&mut self, table: &'a Table) -> Result<Vec<&'a Vec<Value>>, DbError> {
+        let sort_key = table.sort_key(&self.order)?;
+        let next = |i: usize| {
+            let row = &table.rows[i];
+            if table.is_expired(row) {
+                return None;
+            }
+            let keyed = (sort_key(row), i);
+            let past = self.after.as_ref().is_none_or(|after| self.compare(&keyed, after).is_gt());
+            past.then_some(keyed)
+        };
+
+        let mut page = match self.seek(table, &next) {
+            Some(page) => page,
+            None => (0..table.rows.len()).filter_map(next).collect(),
+        };
+        if page.len() > self.page_size {
+            page.select_nth_unstable_by(self.page_size - 1, |a, b| self.compare(a, b));
+            page.truncate(self.page_size);
+        }
+        page.sort_by(|a, b| self.compare(a, b));
+
+        if let Some(last) = page.last() {
+            self.after = Some(last.clone());
+        }
+        Ok(page.into_iter().map(|(_, i)| &table.rows[i]).collect())
+    }
+
+    fn compare(&self, a: &Keyed, b: &Keyed) -> Ordering {
+        compare_sort_keys(&self.order, &a.0, &b.0).then(a.1.cmp(&b.1))
+    }
+
+    // With a B-tree index on the leading sort column, reads on from the cursor's key in
+    // index order, a whole group of equal keys at a time, until a page is filled. None
+    // when there's no such index to seek with.
+    fn seek(&self, table: &Table, next: &dyn Fn(usize) -> Option<Keyed>) -> Option<Vec<Keyed>> {
+        let spec = self.order.first()?;
+        let SortKey::Column(name) = &spec.key else {
+            return None;
+        };
+        let column = &table.columns[table.resolve_column(name).ok()?];
+        let Some(IndexType::BTree(map)) = table.indexes.get(&column.name) else {
+            return None;
+        };
+
+        // Index keys are collation keys, as the cursor's are
+        let start = self.after.as_ref().map(|(key, _)| &key[0]);
+        let values: Box<dyn Iterator<Item = (&Value, &Vec<usize>)>> = match (start, spec.direction) {
+            (Some(Value::Null), _) if spec.nulls == NullsOrder::Last => Box::new(iter::empty()),
+            (None | Some(Value::Null), SortDirection::Asc) => Box::new(map.iter()),
+            (None | Some(Value::Null), SortDirection::Desc) => Box::new(map.iter().rev()),
+            (Some(start), SortDirection::Asc) => Box::new(map.range(start..)),
+            (Some(start), SortDirection::Desc) => Box::new(map.range(..=start).rev()),
+        };
+        let values = values.filter(|(key, _)| !matches!(key, Value::Null)).map(|(_, group)| group);
+        let nulls = map.get(&Value::Null).into_iter();
+        let groups: Box<dyn Iterator<Item = &Vec<usize>>> = match spec.nulls {
+            NullsOrder::First if matches!(start, None | Some(Value::Null)) => Box::new(nulls.chain(values)),
+            NullsOrder::First => Box::new(values),
+            NullsOrder::Last => Box::new(values.chain(nulls)),
+        };
+
+        let mut page = vec![];
+        for group in groups {
+            if page.len() >= self.page_size {
+                break;
+            }
+            page.extend(group.iter().filter_map(|&i| next(i)));
+        }
+        Some(page)
+    }
+}
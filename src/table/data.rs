@@ -1,15 +1,26 @@
+use crate::storage::btree::DiskIndex;
+use crate::storage::compress::Compression;
 use chrono::{NaiveDate, NaiveTime, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::{HashMap, BTreeMap};
 use std::hash::{Hash, Hasher};
 use std::mem;
+use std::ops::Bound;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
-#[derive(Debug, Clone, PartialEq)]
+// Longest VARCHAR the SQL parser and CSV inference hand out when no length is given
+pub const MAX_VARCHAR_LENGTH: u32 = 65_535;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DataType {
-    Char, //Single character
-    Varchar, //Multiple characters
+    Char(u32), //Fixed number of characters; CHAR(1) holds Value::Char
+    Varchar(u32), //Up to this many characters
     Text, //Longer varchars
-    Enum, //Single object
-    Set, //0-64 objects
+    Enum(Vec<String>), //One of the listed values
+    Set(Vec<String>), //Any of the listed values
     Boolean, //True or False
     Int, //Integers
     BigInt, //Larger integers
@@ -18,15 +29,17 @@ pub enum DataType {
     Date, //YYYY-MM-DD
     Time, //HH:MM:SS
     DateTime, //YYYY-MM-DD HH:MM:SS
+    Point, //Latitude and longitude in degrees
+    Decimal(u32, u32), //Exact numbers: precision (total digits), scale (digits after the point)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Value {
     Char(char),
     Varchar(String),
     Text(String),
-    Enum(String, Vec<String>),
-    Set(Vec<String>, Vec<String>),
+    Enum(String),
+    Set(Vec<String>),
     Boolean(bool),
     Int(i32),
     BigInt(i64),
@@ -35,17 +48,27 @@ pub enum Value {
     Date(NaiveDate),
     Time(NaiveTime),
     DateTime(NaiveDateTime),
+    Point(f64, f64),
+    Decimal(Decimal),
     Null
 }
 
+// mantissa / 10^scale, held exactly. Values that differ only in trailing zeros (1.5 and
+// 1.50) compare and hash as equal.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Decimal {
+    pub mantissa: i128,
+    pub scale: u32,
+}
+
 impl Value {
     fn variant_index(&self) -> u8 {
         match self {
             Value::Char(_) => 0,
             Value::Varchar(_) => 1,
             Value::Text(_) => 2,
-            Value::Enum(_, _) => 3,
-            Value::Set(_, _) => 4,
+            Value::Enum(_) => 3,
+            Value::Set(_) => 4,
             Value::Boolean(_) => 5,
             Value::Int(_) => 6,
             Value::BigInt(_) => 7,
@@ -54,7 +77,9 @@ impl Value {
             Value::Date(_) => 10,
             Value::Time(_) => 11,
             Value::DateTime(_) => 12,
-            Value::Null => 13,
+            Value::Point(_, _) => 13,
+            Value::Decimal(_) => 14,
+            Value::Null => 15,
         }
     }
 }
@@ -62,38 +87,38 @@ impl Value {
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         use Value::*;
-        match (self, other) {
-            (Float(a), Float(b)) => a.to_bits() == b.to_bits(),
-            (Double(a), Double(b)) => a.to_bits() == b.to_bits(),
-            _ => mem::discriminant(self) == mem::discriminant(other) && {
-                match (self, other) {
-                    (Char(a), Char(b)) => a == b,
-                    (Varchar(a), Varchar(b)) => a == b,
-                    (Text(a), Text(b)) => a == b,
-                    (Enum(a1, e1), Enum(a2, e2)) => a1 == a2 && e1 == e2,
-                    (Set(s1, e1), Set(s2, e2)) => s1 == s2 && e1 == e2,
-                    (Boolean(a), Boolean(b)) => a == b,
-                    (Int(a), Int(b)) => a == b,
-                    (BigInt(a), BigInt(b)) => a == b,
-                    (Date(a), Date(b)) => a == b,
-                    (Time(a), Time(b)) => a == b,
-                    (DateTime(a), DateTime(b)) => a == b,
-                    (Null, Null) => true,
-                    _ => false,
+        if let (Some(a), Some(b)) = (self.as_numeric(), other.as_numeric()) {
+            return a.total_cmp(b).is_eq();
+        }
+        mem::discriminant(self) == mem::discriminant(other)
+            && match (self, other) {
+                (Char(a), Char(b)) => a == b,
+                (Varchar(a), Varchar(b)) => a == b,
+                (Text(a), Text(b)) => a == b,
+                (Enum(a), Enum(b)) => a == b,
+                (Set(a), Set(b)) => a == b,
+                (Boolean(a), Boolean(b)) => a == b,
+                (Date(a), Date(b)) => a == b,
+                (Time(a), Time(b)) => a == b,
+                (DateTime(a), DateTime(b)) => a == b,
+                (Point(a1, o1), Point(a2, o2)) => {
+                    a1.to_bits() == a2.to_bits() && o1.to_bits() == o2.to_bits()
                 }
+                (Value::Decimal(a), Value::Decimal(b)) => a == b,
+                (Null, Null) => true,
+                _ => false,
             }
-        }
     }
 }
 
 impl Eq for Value {}
 
+// NaN is unordered here, while Ord falls back to total_cmp so rows always sort
+#[allow(clippy::non_canonical_partial_ord_impl)]
 impl PartialOrd for Value {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        use Value::*;
-        match (self, other) {
-            (Float(a), Float(b)) => a.partial_cmp(b),
-            (Double(a), Double(b)) => a.partial_cmp(b),
+        match (self.as_numeric(), other.as_numeric()) {
+            (Some(a), Some(b)) => a.partial_cmp(b),
             _ => Some(self.cmp(other))
         }
     }
@@ -102,20 +127,24 @@ impl PartialOrd for Value {
 impl Ord for Value {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         use Value::*;
+        // Numbers of different widths compare by value; their variants sit side by side
+        if let (Some(a), Some(b)) = (self.as_numeric(), other.as_numeric()) {
+            return a.total_cmp(b);
+        }
         match (self, other) {
-            (Float(a), Float(b)) => a.to_bits().cmp(&b.to_bits()),
-            (Double(a), Double(b)) => a.to_bits().cmp(&b.to_bits()),
             (Char(a), Char(b)) => a.cmp(b),
             (Varchar(a), Varchar(b)) => a.cmp(b),
             (Text(a), Text(b)) => a.cmp(b),
-            (Enum(a1, e1), Enum(a2, e2)) => (a1, e1).cmp(&(a2, e2)),
-            (Set(s1, e1), Set(s2, e2)) => (s1, e1).cmp(&(s2, e2)),
+            (Enum(a), Enum(b)) => a.cmp(b),
+            (Set(a), Set(b)) => a.cmp(b),
             (Boolean(a), Boolean(b)) => a.cmp(b),
-            (Int(a), Int(b)) => a.cmp(b),
-            (BigInt(a), BigInt(b)) => a.cmp(b),
             (Date(a), Date(b)) => a.cmp(b),
             (Time(a), Time(b)) => a.cmp(b),
             (DateTime(a), DateTime(b)) => a.cmp(b),
+            (Point(a1, o1), Point(a2, o2)) => {
+                (a1.to_bits(), o1.to_bits()).cmp(&(a2.to_bits(), o2.to_bits()))
+            }
+            (Value::Decimal(a), Value::Decimal(b)) => a.cmp(b),
             (Null, Null) => std::cmp::Ordering::Equal,
             _ => self.variant_index().cmp(&other.variant_index()),
         }
@@ -125,43 +154,149 @@ impl Ord for Value {
 impl Hash for Value {
     fn hash<H: Hasher>(&self, state: &mut H) {
         use Value::*;
+        // Equal numbers hash alike whatever their width
+        if let Some(n) = self.as_numeric() {
+            return n.hash(state);
+        }
         mem::discriminant(self).hash(state);
         match self {
             Char(c) => c.hash(state),
             Varchar(s) => s.hash(state),
             Text(s) => s.hash(state),
-            Enum(val, all) => {
-                val.hash(state);
-                all.hash(state);
-            }
-            Set(vals, all) => {
-                vals.hash(state);
-                all.hash(state);
-            }
+            Enum(val) => val.hash(state),
+            Set(vals) => vals.hash(state),
             Boolean(b) => b.hash(state),
-            Int(i) => i.hash(state),
-            BigInt(i) => i.hash(state),
-            Float(f) => f.to_bits().hash(state),
-            Double(f) => f.to_bits().hash(state),
+            Int(_) | BigInt(_) | Float(_) | Double(_) => unreachable!(),
             Date(d) => d.hash(state),
             Time(t) => t.hash(state),
             DateTime(dt) => dt.hash(state),
+            Point(lat, lon) => {
+                lat.to_bits().hash(state);
+                lon.to_bits().hash(state);
+            }
+            Value::Decimal(d) => d.hash(state),
             Null => (),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Options {
     Unique,
     NotNull,
     FK(String),
-    Check(String),
-    Default(Value),
-    Autoincrement
+    Check(Expr),
+    // A fixed value is DefaultExpr::Literal; anything else is worked out at insert time
+    Default(DefaultExpr),
+    Autoincrement,
+    Identity { start: i64, increment: i64 },
+    OnUpdateNow,
+    Ttl(i64),
+    Mask(MaskPolicy),
+    // What a foreign key column does when the parent key it holds changes
+    OnUpdate(FkAction),
+    // Strings longer than the column's length are cut to fit instead of rejected
+    Truncate,
+    Collate(Collation),
+    // Worked out from the rest of the row on every insert and update; never set directly
+    Generated(Expr),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DefaultExpr {
+    Literal(Value),
+    CurrentDate,
+    CurrentTimestamp,
+    // Days for a date or timestamp, otherwise plain addition
+    Add(Box<DefaultExpr>, i64),
+}
+
+// A value worked out from a row, e.g. UPPER(name) or EXTRACT(YEAR FROM joined) >= 2020
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expr {
+    ColumnRef(String),
+    Literal(Value),
+    Call(ScalarFunc, Vec<Expr>),
+    Arith(Arithmetic, Box<Expr>, Box<Expr>),
+    // Comparisons and their combinations give Boolean, or NULL when unknown
+    Compare(Comparison, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScalarFunc {
+    Upper,
+    Lower,
+    Length,
+    // SUBSTR(text, start[, length]) counts characters from 1
+    Substr,
+    // ROUND(number[, digits]) rounds halves away from zero
+    Round,
+    Abs,
+    // DATE_ADD(date, days)
+    DateAdd,
+    Extract(DatePart),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DatePart {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+// Integers stay integers (division truncates), decimals stay exact except when divided,
+// floats win over both, and dates move by whole days
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Arithmetic {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FkAction {
+    #[default]
+    Restrict,
+    Cascade,
+    SetNull,
+}
+
+// How a string column compares its values when sorting, checking UNIQUE and keying indexes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Collation {
+    #[default]
+    Binary,
+    CaseInsensitive,
+    // Latin letters compare without case or accents first, and only then as written;
+    // other scripts only without case
+    LatinFold,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MaskPolicy {
+    Redact,
+    ShowLast(usize),
+    Hash,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Column {
     pub name: String,
     pub datatype: DataType,
@@ -177,10 +312,86 @@ pub struct Table {
     pub rows: Vec<Vec<Value>>,
     pub primary_key: Option<Vec<String>>,
     pub indexes: HashMap<String, IndexType>,
-    pub transaction_backup: Option<Vec<Vec<Value>>>,
+    // Furthest value each identity column has held. New ids step on from here, so ids
+    // freed by a delete are never handed out again.
+    pub sequences: HashMap<String, i64>,
+    // Undo log of the open transaction: every row and schema change since
+    // begin_transaction, oldest first
+    pub transaction_backup: Option<Vec<UndoEntry>>,
+    // What the last analyze found; later writes don't update it
+    pub stats: Option<TableStats>,
+    // How save_to_dir compresses the table's data files; None writes them plain
+    pub compression: Option<Compression>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableStats {
+    pub row_count: usize,
+    // In column order as of the analyze
+    pub columns: Vec<ColumnStats>,
 }
 
+// min and max are None when every value is NULL
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    pub column: String,
+    pub null_count: usize,
+    pub distinct_count: usize,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+}
+
+// One row-level write, with enough of the old state to undo it
+#[derive(Debug, Clone, PartialEq)]
+pub enum RowChange {
+    // The row was appended to the end of the table
+    Insert {
+        row: Vec<Value>,
+    },
+    Update {
+        position: usize,
+        before: Vec<Value>,
+        after: Vec<Value>,
+    },
+    // Positions refer to row order before the delete, in ascending order
+    Delete {
+        positions: Vec<usize>,
+        rows: Vec<Vec<Value>>,
+    },
+}
+
+// One step of a table's transaction undo log. Indexes are recorded by kind only; their
+// contents are rebuilt once the log has been undone.
 #[derive(Debug, Clone)]
+pub enum UndoEntry {
+    Row(RowChange),
+    // The column was appended after the last one
+    AddColumn,
+    DropColumn {
+        position: usize,
+        column: Column,
+        values: Vec<Value>,
+        index: Option<IndexType>,
+    },
+    RenameColumn {
+        from: String,
+        to: String,
+    },
+    // The column's options before some were added or dropped
+    SetOptions {
+        column: String,
+        options: Vec<Options>,
+    },
+    // The primary key before it was set or dropped
+    SetPrimaryKey(Option<Vec<String>>),
+    // The index the column had before, if any
+    CreateIndex {
+        column: String,
+        replaced: Option<IndexType>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FilterExpr {
     Eq(String, Value),
     Gt(String, Value),
@@ -188,33 +399,423 @@ pub enum FilterExpr {
     Ge(String, Value),
     Le(String, Value),
     Ne(String, Value),
+    // Both bounds are inclusive
+    Between(String, Value, Value),
+    In(String, Vec<Value>),
+    IsNull(String),
+    IsNotNull(String),
+    // Corners are Value::Point (south-west, north-east)
+    WithinBox(String, Value, Value),
+    // Center is a Value::Point; the radius is in kilometres
+    WithinRadius(String, Value, f64),
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    Not(Box<FilterExpr>),
 }
 
-pub struct View<'a> {
-    pub name: String,
-    pub builder: Box<dyn Fn() -> Result<Table, String> + 'a>,
+#[derive(Debug, Clone)]
+pub struct ResultSet {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PagerOptions {
+    pub page_size: usize,
+    pub max_column_width: usize,
+    pub show_row_numbers: bool,
+}
+
+impl Default for PagerOptions {
+    fn default() -> Self {
+        PagerOptions {
+            page_size: 20,
+            max_column_width: 20,
+            show_row_numbers: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryStage {
+    Parse,
+    IndexLookup,
+    Filter,
+    Sort,
+    Join,
+    Materialize,
+}
+
+#[derive(Debug, Clone)]
+pub struct StageTiming {
+    pub stage: QueryStage,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QueryProfile {
+    pub stages: Vec<StageTiming>,
+    pub rows_scanned: usize,
+    pub rows_returned: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlanNode {
+    SeqScan {
+        table: String,
+        rows: usize,
+    },
+    IndexScan {
+        table: String,
+        column: String,
+        rows: usize,
+    },
+    Filter {
+        predicate: String,
+        input: Box<PlanNode>,
+        rows: usize,
+    },
+    HashJoin {
+        on: (String, String),
+        left: Box<PlanNode>,
+        right: Box<PlanNode>,
+        rows: usize,
+    },
+    Sort {
+        keys: Vec<String>,
+        input: Box<PlanNode>,
+        rows: usize,
+    },
+    Limit {
+        count: usize,
+        input: Box<PlanNode>,
+        rows: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub root: PlanNode,
+}
+
+// How the planner reads the rows a filter may match; every row read is still checked
+// against the whole filter
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccessPath {
+    FullScan,
+    IndexLookup {
+        column: String,
+        value: Value,
+    },
+    // Every row holding any of the values, as for an IN list
+    IndexMultiLookup {
+        column: String,
+        values: Vec<Value>,
+    },
+    IndexRange {
+        column: String,
+        lower: Bound<Value>,
+        upper: Bound<Value>,
+    },
+    GridScan {
+        column: String,
+        south_west: (f64, f64),
+        north_east: (f64, f64),
+    },
+    Intersect(Box<AccessPath>, Box<AccessPath>),
+    Union(Box<AccessPath>, Box<AccessPath>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryPlan {
+    pub table: String,
+    pub filter: FilterExpr,
+    pub access: AccessPath,
+    pub estimated_rows: usize,
+}
+
+// A stored query, run against the current tables each time it is read: `from` joined
+// with every (table, left column, right column) in `joins`, filtered, then projected.
+// Once joined, columns are named `table.column`. No columns means all of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct View {
+    pub from: String,
+    pub joins: Vec<(String, String, String)>,
+    pub filter: Option<FilterExpr>,
+    pub columns: Vec<Expr>,
+}
+
+// A query for Database::select. Each table in `joins` is linked to the ones before it
+// through a foreign key; once joined, columns are named `table.column`. No columns
+// means all of them.
+#[derive(Debug, Clone, Default)]
+pub struct QuerySpec {
+    pub joins: Vec<String>,
+    pub filter: Option<FilterExpr>,
+    pub columns: Vec<String>,
+    pub order: Vec<OrderBy>,
+    pub limit: Limit,
 }
 
 #[derive(Debug, Clone)]
 pub enum IndexType {
     Hash(HashMap<Value, Vec<usize>>),
     BTree(BTreeMap<Value, Vec<usize>>),
+    Grid(GridIndex),
+    // A B+ tree kept in a page file rather than in memory
+    Disk(DiskIndex),
+}
+
+// Buckets Point values into square cells `cell_size` degrees wide
+#[derive(Debug, Clone)]
+pub struct GridIndex {
+    pub cell_size: f64,
+    pub cells: HashMap<(i64, i64), Vec<usize>>,
 }
 
 impl IndexType {
+    // For a grid index this returns every row in the key's cell, not only exact matches.
+    // A disk index holds nothing in memory to lend; use lookup.
     pub fn get(&self, key: &Value) -> Option<&Vec<usize>> {
         match self {
             IndexType::Hash(map) => map.get(key),
             IndexType::BTree(map) => map.get(key),
+            IndexType::Grid(grid) => grid.cells.get(&grid.cell_of(key)?),
+            IndexType::Disk(_) => None,
+        }
+    }
+
+    // Rows held under `key`, or None when the index can't say: a grid index, or a disk
+    // index gone stale. A disk index can also return rows whose long strings only share
+    // the key's prefix.
+    pub fn lookup(&self, key: &Value) -> Option<Cow<'_, [usize]>> {
+        match self {
+            IndexType::Hash(map) => Some(Cow::Borrowed(map.get(key).map_or(&[][..], Vec::as_slice))),
+            IndexType::BTree(map) => Some(Cow::Borrowed(map.get(key).map_or(&[][..], Vec::as_slice))),
+            IndexType::Grid(_) => None,
+            IndexType::Disk(disk) => disk.get(key).map(Cow::Owned),
+        }
+    }
+
+    pub fn empty_like(&self) -> IndexType {
+        match self {
+            IndexType::Hash(_) => IndexType::Hash(HashMap::new()),
+            IndexType::BTree(_) => IndexType::BTree(BTreeMap::new()),
+            IndexType::Grid(grid) => IndexType::Grid(GridIndex {
+                cell_size: grid.cell_size,
+                cells: HashMap::new(),
+            }),
+            IndexType::Disk(disk) => IndexType::Disk(disk.handle()),
+        }
+    }
+
+    // The kind of index a copy of the table gets: the same, but for a disk index, whose
+    // tree stays with the original while the copy keeps an in-memory B-tree
+    pub fn derived(&self) -> IndexType {
+        match self {
+            IndexType::Disk(_) => IndexType::BTree(BTreeMap::new()),
+            index => index.empty_like(),
+        }
+    }
+
+    // Takes out the (key, row) pairs of deleted rows and renumbers the rows left with `shift`
+    pub(crate) fn remove_rows(&mut self, removed: &[(Value, usize)], shift: impl Fn(usize) -> usize) {
+        fn remove_from(rows: Option<&mut Vec<usize>>, row: usize) -> bool {
+            rows.is_some_and(|rows| {
+                rows.retain(|&i| i != row);
+                rows.is_empty()
+            })
+        }
+        match self {
+            IndexType::Hash(map) => {
+                for (key, row) in removed {
+                    if remove_from(map.get_mut(key), *row) {
+                        map.remove(key);
+                    }
+                }
+                map.values_mut().flatten().for_each(|row| *row = shift(*row));
+            }
+            IndexType::BTree(map) => {
+                for (key, row) in removed {
+                    if remove_from(map.get_mut(key), *row) {
+                        map.remove(key);
+                    }
+                }
+                map.values_mut().flatten().for_each(|row| *row = shift(*row));
+            }
+            IndexType::Grid(grid) => {
+                for (key, row) in removed {
+                    grid.remove(key, *row);
+                }
+                grid.cells.values_mut().flatten().for_each(|row| *row = shift(*row));
+            }
+            IndexType::Disk(disk) => disk.remove_rows(removed, shift),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct GeneratorSpec {
+    pub seed: u64,
+    pub null_ratio: f64,
+    pub int_range: (i64, i64),
+    pub float_range: (f64, f64),
+    pub date_range: (NaiveDate, NaiveDate),
+    pub string_len: usize,
+    pub enum_values: HashMap<String, Vec<String>>,
+}
+
+impl Default for GeneratorSpec {
+    fn default() -> Self {
+        GeneratorSpec {
+            seed: 42,
+            null_ratio: 0.0,
+            int_range: (0, 1000),
+            float_range: (0.0, 1000.0),
+            date_range: (
+                NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2030, 12, 31).unwrap(),
+            ),
+            string_len: 8,
+            enum_values: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AggFunc {
+    Sum,
+    Avg,
+    // Every row, NULL or not
+    Count,
+    CountDistinct,
+    Min,
+    Max,
+    // Sample statistics, dividing by n - 1
+    StdDev,
+    Variance,
+    Median,
+    // p from 0 to 1, interpolating between values
+    Percentile(f64),
+    // Joined with the separator, in table order
+    GroupConcat(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum AggregationResult {
-    Sum(f64),
-    Avg(f64),
+    // A Decimal over decimal values, otherwise a Double; AVG of no values is NULL
+    Sum(Value),
+    Avg(Value),
     Count(usize),
     Min(Value),
     Max(Value),
+    // Sample statistics; None when there are too few non-NULL values to have one
+    StdDev(Option<f64>),
+    Variance(Option<f64>),
+    Median(Option<f64>),
+    Percentile(Option<f64>),
+    CountDistinct(usize),
+    // None when the group has no non-NULL values
+    GroupConcat(Option<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+pub type SortKeyFn = Arc<dyn Fn(&[Value]) -> Value + Send + Sync>;
+
+#[derive(Clone)]
+pub enum SortKey {
+    Column(String),
+    Computed(SortKeyFn),
+}
+
+#[derive(Debug, Clone)]
+pub struct OrderBy {
+    pub key: SortKey,
+    pub direction: SortDirection,
+    pub nulls: NullsOrder,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub rows: Vec<T>,
+    pub page: usize,
+    pub page_size: usize,
+    pub total: usize,
+}
+
+// Keyset pagination: each page starts after the sort key of the previous page's last
+// row rather than at an offset, so rows inserted or deleted in between shift nothing.
+// Unless it already sorts on a unique column, `order` ends with the primary key, and
+// rows equal on every key are told apart by their position in the table.
+#[derive(Debug, Clone)]
+pub struct Cursor {
+    pub order: Vec<OrderBy>,
+    pub page_size: usize,
+    // The last row handed out: its sort key and position
+    pub after: Option<(Vec<Value>, usize)>,
+}
+
+// LIMIT/OFFSET: skip `offset` results, then keep at most `count` (all when None)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Limit {
+    pub offset: usize,
+    pub count: Option<usize>,
+}
+
+// The results a Limit kept, with how many matched before it was applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limited<T> {
+    pub rows: Vec<T>,
+    pub offset: usize,
+    pub total: usize,
+}
+
+// A pair of joined rows; a side with no match comes back as NULLs
+pub type JoinRow<'a> = (Vec<&'a Value>, Vec<&'a Value>);
+pub type LeftJoinRow<'a> = (Vec<&'a Value>, Vec<Option<&'a Value>>);
+pub type RightJoinRow<'a> = (Vec<Option<&'a Value>>, Vec<&'a Value>);
+
+// How a two-table join pairs rows. A sort-merge join only holds the sorted row
+// references, and returns pairs in key order rather than table order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JoinStrategy {
+    #[default]
+    NestedLoop,
+    SortMerge,
+}
+
+// Lag and Lead read the named column that many rows back or ahead in the partition
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowFunc {
+    RowNumber,
+    Rank,
+    DenseRank,
+    Lag(String, usize),
+    Lead(String, usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalFormat {
+    Csv,
+    JsonLines,
+}
+
+// A table whose rows stay in a file and are read on every scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalTable {
+    pub name: String,
+    pub path: PathBuf,
+    pub format: ExternalFormat,
+    pub columns: Vec<Column>,
 }
@@ -0,0 +1,208 @@
+use crate::table::data::Decimal;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+// 10^38 is the largest power of ten an i128 holds
+pub const MAX_PRECISION: u32 = 38;
+
+// Digits a quotient keeps past its dividend's scale, as MySQL does, so AVG of a
+// DECIMAL(10, 2) column has six
+pub const DIV_SCALE_INCREMENT: u32 = 4;
+
+// The scale of a quotient of a decimal at `scale`
+pub fn quotient_scale(scale: u32) -> u32 {
+    (scale + DIV_SCALE_INCREMENT).min(MAX_PRECISION)
+}
+
+fn pow10(exp: u32) -> i128 {
+    10i128.pow(exp)
+}
+
+impl Decimal {
+    pub fn new(mantissa: i128, scale: u32) -> Self {
+        Decimal { mantissa, scale }
+    }
+
+    // The same number with trailing fractional zeros removed
+    pub fn normalized(self) -> Self {
+        let mut d = self;
+        while d.scale > 0 && d.mantissa % 10 == 0 {
+            d.mantissa /= 10;
+            d.scale -= 1;
+        }
+        d
+    }
+
+    // Moves to `scale` digits after the point, rounding half away from zero when digits
+    // are dropped. None if the result no longer fits.
+    pub fn rescale(self, scale: u32) -> Option<Self> {
+        if scale > MAX_PRECISION {
+            return None;
+        }
+        match scale.cmp(&self.scale) {
+            Ordering::Equal => Some(self),
+            Ordering::Greater => self
+                .mantissa
+                .checked_mul(pow10(scale - self.scale))
+                .map(|mantissa| Decimal::new(mantissa, scale)),
+            Ordering::Less => {
+                let divisor = pow10(self.scale - scale);
+                let mut mantissa = self.mantissa / divisor;
+                let remainder = (self.mantissa % divisor).abs();
+                if remainder >= divisor - remainder {
+                    mantissa += self.mantissa.signum();
+                }
+                Some(Decimal::new(mantissa, scale))
+            }
+        }
+    }
+
+    // Significant digits in the mantissa, counting a lone zero as one
+    pub fn digits(&self) -> u32 {
+        self.mantissa.unsigned_abs().checked_ilog10().map_or(1, |log| log + 1)
+    }
+
+    // Whether the value can be stored in a DECIMAL(precision, scale) column once rounded
+    // to its scale
+    pub fn fits(&self, precision: u32, scale: u32) -> bool {
+        self.rescale(scale).is_some_and(|d| d.digits() <= precision)
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let a = self.rescale(scale)?;
+        let b = other.rescale(scale)?;
+        a.mantissa.checked_add(b.mantissa).map(|mantissa| Decimal::new(mantissa, scale))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_add(Decimal::new(other.mantissa.checked_neg()?, other.scale))
+    }
+
+    // Exact unless the scales add up past MAX_PRECISION, which rounds the extra digits away
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let a = self.normalized();
+        let b = other.normalized();
+        let scale = a.scale + b.scale;
+        Decimal::new(a.mantissa.checked_mul(b.mantissa)?, scale).rescale(scale.min(MAX_PRECISION))
+    }
+
+    // The quotient rounded half away from zero to `scale` digits. None on a zero divisor
+    // or when the result doesn't fit.
+    pub fn checked_div(self, other: Self, scale: u32) -> Option<Self> {
+        if other.mantissa == 0 || scale > MAX_PRECISION {
+            return None;
+        }
+        let shift = scale as i64 + other.scale as i64 - self.scale as i64;
+        let (numerator, denominator) = if shift >= 0 {
+            (self.mantissa.checked_mul(10i128.checked_pow(shift as u32)?)?, other.mantissa)
+        } else {
+            (self.mantissa, other.mantissa.checked_mul(10i128.checked_pow(-shift as u32)?)?)
+        };
+        let mut mantissa = numerator / denominator;
+        let remainder = (numerator % denominator).unsigned_abs();
+        if remainder >= denominator.unsigned_abs() - remainder {
+            mantissa += numerator.signum() * denominator.signum();
+        }
+        Some(Decimal::new(mantissa, scale))
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.mantissa as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    // Whole part and fraction, both carrying the sign of the number
+    fn split(&self) -> (i128, i128) {
+        let divisor = pow10(self.scale);
+        (self.mantissa / divisor, self.mantissa % divisor)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Whole parts first, then fractions lined up to the longer scale. Neither step can
+// overflow, unlike rescaling both mantissas.
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (whole_a, frac_a) = self.split();
+        let (whole_b, frac_b) = other.split();
+        let scale = self.scale.max(other.scale);
+        whole_a.cmp(&whole_b).then_with(|| {
+            (frac_a * pow10(scale - self.scale)).cmp(&(frac_b * pow10(scale - other.scale)))
+        })
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let d = self.normalized();
+        d.mantissa.hash(state);
+        d.scale.hash(state);
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let (whole, frac) = self.split();
+        if self.scale == 0 {
+            return write!(f, "{}{}", sign, whole.unsigned_abs());
+        }
+        write!(
+            f,
+            "{}{}.{:0width$}",
+            sign,
+            whole.unsigned_abs(),
+            frac.unsigned_abs(),
+            width = self.scale as usize
+        )
+    }
+}
+
+// Plain decimal notation such as "-12.50"; no exponents
+impl FromStr for Decimal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (whole, frac) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && frac.is_empty()
+            || !whole.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+        {
+            return Err(format!("Invalid decimal '{}'", s));
+        }
+        if frac.len() as u32 > MAX_PRECISION {
+            return Err(format!("Decimal '{}' has more than {} fractional digits", s, MAX_PRECISION));
+        }
+
+        let mut mantissa: i128 = 0;
+        for c in whole.chars().chain(frac.chars()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(c.to_digit(10).unwrap() as i128))
+                .ok_or_else(|| format!("Decimal '{}' is out of range", s))?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+        Ok(Decimal::new(mantissa, frac.len() as u32))
+    }
+}
@@ -0,0 +1,124 @@
+use crate::table::data::{Column, ResultSet, Table, Value};
+use std::io::Write;
+
+pub trait JoinCell {
+    fn to_value(&self) -> Value;
+}
+
+impl JoinCell for &Value {
+    fn to_value(&self) -> Value {
+        (*self).clone()
+    }
+}
+
+impl JoinCell for Option<&Value> {
+    fn to_value(&self) -> Value {
+        self.cloned().unwrap_or(Value::Null)
+    }
+}
+
+impl ResultSet {
+    pub fn from_join<L: JoinCell, R: JoinCell>(
+        left: &Table,
+        right: &Table,
+        results: &[(Vec<L>, Vec<R>)],
+    ) -> Self {
+        let columns = left
+            .columns
+            .iter()
+            .map(|c| (left, c))
+            .chain(right.columns.iter().map(|c| (right, c)))
+            .map(|(table, c)| Column {
+                name: format!("{}.{}", table.name, c.name),
+                datatype: c.datatype.clone(),
+                options: vec![],
+            })
+            .collect();
+
+        let rows = results
+            .iter()
+            .map(|(l, r)| {
+                l.iter()
+                    .map(|v| v.to_value())
+                    .chain(r.iter().map(|v| v.to_value()))
+                    .collect()
+            })
+            .collect();
+
+        ResultSet { columns, rows }
+    }
+
+    pub fn to_csv<W: Write>(&self, writer: W) -> Result<(), String> {
+        let mut wtr = csv::Writer::from_writer(writer);
+
+        wtr.write_record(self.columns.iter().map(|c| c.name.as_str()))
+            .map_err(|e| format!("CSV write error: {}", e))?;
+
+        for row in &self.rows {
+            wtr.write_record(row.iter().map(|v| match v {
+                Value::Null => String::new(),
+                other => other.to_display_string(),
+            }))
+            .map_err(|e| format!("CSV write error: {}", e))?;
+        }
+
+        wtr.flush().map_err(|e| e.to_string())
+    }
+
+    pub fn to_json<W: Write>(&self, mut writer: W) -> Result<(), String> {
+        write!(writer, "[").map_err(|e| e.to_string())?;
+
+        for (i, row) in self.rows.iter().enumerate() {
+            if i > 0 {
+                write!(writer, ",").map_err(|e| e.to_string())?;
+            }
+            let fields = self
+                .columns
+                .iter()
+                .zip(row)
+                .map(|(c, v)| format!("{}:{}", json_string(&c.name), json_value(v)))
+                .collect::<Vec<_>>()
+                .join(",");
+            write!(writer, "{{{}}}", fields).map_err(|e| e.to_string())?;
+        }
+
+        writeln!(writer, "]").map_err(|e| e.to_string())
+    }
+}
+
+fn json_value(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::BigInt(i) => i.to_string(),
+        Value::Float(f) if f.is_finite() => f.to_string(),
+        Value::Double(f) if f.is_finite() => f.to_string(),
+        Value::Float(_) | Value::Double(_) => "null".to_string(),
+        Value::Set(items) => format!(
+            "[{}]",
+            items.iter().map(|s| json_string(s)).collect::<Vec<_>>().join(",")
+        ),
+        other => json_string(&other.to_display_string()),
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,838 @@
+use crate::error::DbError;
+use crate::sql::lexer::{tokenize, Token};
+use crate::table::data::{Arithmetic, Column, Comparison, DataType, DatePart, Decimal, Expr, Options, ScalarFunc, Table, Value, MAX_VARCHAR_LENGTH};
+use crate::table::decimal::{quotient_scale, MAX_PRECISION};
+use chrono::{Datelike, TimeDelta, Timelike};
+use std::fmt;
+use std::str::FromStr;
+
+// An expression with its columns looked up, ready to run against rows of one table
+pub type Evaluator<'a> = Box<dyn Fn(&[Value]) -> Result<Value, DbError> + 'a>;
+
+impl Expr {
+    pub fn column(name: &str) -> Expr {
+        Expr::ColumnRef(name.to_string())
+    }
+
+    pub fn and(self, other: Expr) -> Expr {
+        Expr::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Expr) -> Expr {
+        Expr::Or(Box::new(self), Box::new(other))
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Expr {
+        Expr::Not(Box::new(self))
+    }
+
+    // Resolves column names and checks argument counts once, so evaluating a row only
+    // indexes into it
+    pub fn compile<'a>(&'a self, table: &Table) -> Result<Evaluator<'a>, DbError> {
+        match self {
+            Expr::ColumnRef(name) => {
+                let i = table.resolve_column(name)?;
+                Ok(Box::new(move |row| Ok(row[i].clone())))
+            }
+            Expr::Literal(value) => Ok(Box::new(move |_| Ok(value.clone()))),
+            Expr::Call(func, args) => {
+                func.check_arity(args.len())?;
+                let args = args.iter().map(|arg| arg.compile(table)).collect::<Result<Vec<_>, _>>()?;
+                let func = *func;
+                Ok(Box::new(move |row| {
+                    let values = args.iter().map(|arg| arg(row)).collect::<Result<Vec<_>, _>>()?;
+                    func.apply(&values)
+                }))
+            }
+            Expr::Arith(op, a, b) => {
+                let (op, left, right) = (*op, a.compile(table)?, b.compile(table)?);
+                Ok(Box::new(move |row| op.apply(&left(row)?, &right(row)?)))
+            }
+            Expr::Compare(op, a, b) => {
+                let (op, left, right) = (*op, a.operand(b, table)?, b.operand(a, table)?);
+                Ok(Box::new(move |row| Ok(op.apply(&left(row)?, &right(row)?))))
+            }
+            Expr::And(a, b) => {
+                let (a, b) = (a.compile(table)?, b.compile(table)?);
+                Ok(Box::new(move |row| match truth(a(row)?)? {
+                    Some(false) => Ok(Value::Boolean(false)),
+                    left => Ok(match (left, truth(b(row)?)?) {
+                        (_, Some(false)) => Value::Boolean(false),
+                        (Some(true), Some(true)) => Value::Boolean(true),
+                        _ => Value::Null,
+                    }),
+                }))
+            }
+            Expr::Or(a, b) => {
+                let (a, b) = (a.compile(table)?, b.compile(table)?);
+                Ok(Box::new(move |row| match truth(a(row)?)? {
+                    Some(true) => Ok(Value::Boolean(true)),
+                    left => Ok(match (left, truth(b(row)?)?) {
+                        (_, Some(true)) => Value::Boolean(true),
+                        (Some(false), Some(false)) => Value::Boolean(false),
+                        _ => Value::Null,
+                    }),
+                }))
+            }
+            Expr::Not(inner) => {
+                let inner = inner.compile(table)?;
+                Ok(Box::new(move |row| Ok(truth(inner(row)?)?.map_or(Value::Null, |b| Value::Boolean(!b)))))
+            }
+        }
+    }
+
+    // One side of a comparison. A string literal facing a non-string column is read as
+    // that column's type, so `day >= '2024-01-01'` compares dates.
+    fn operand<'a>(&'a self, other: &Expr, table: &Table) -> Result<Evaluator<'a>, DbError> {
+        if let (Expr::Literal(Value::Varchar(text)), Expr::ColumnRef(name)) = (self, other) {
+            let dtype = &table.columns[table.resolve_column(name)?].datatype;
+            if !matches!(dtype, DataType::Char(_) | DataType::Varchar(_) | DataType::Text) {
+                let value = Value::from_str(text, dtype)?;
+                return Ok(Box::new(move |_| Ok(value.clone())));
+            }
+        }
+        self.compile(table)
+    }
+
+    pub fn evaluate(&self, table: &Table, row: &[Value]) -> Result<Value, DbError> {
+        self.compile(table)?(row)
+    }
+
+    // The type of the values this gives, for result columns
+    pub fn datatype(&self, table: &Table) -> Result<DataType, DbError> {
+        Ok(match self {
+            Expr::ColumnRef(name) => table.columns[table.resolve_column(name)?].datatype.clone(),
+            Expr::Literal(value) => literal_type(value),
+            Expr::Call(func, args) => {
+                func.check_arity(args.len())?;
+                let arg = args[0].datatype(table)?;
+                match (func, arg) {
+                    (ScalarFunc::Length | ScalarFunc::Extract(_), _) => DataType::Int,
+                    (ScalarFunc::Substr, DataType::Char(n) | DataType::Varchar(n)) => DataType::Varchar(n),
+                    (ScalarFunc::Substr, DataType::Text) => DataType::Text,
+                    (ScalarFunc::Substr, _) => DataType::Varchar(MAX_VARCHAR_LENGTH),
+                    // Rounding only ever drops digits after the point
+                    (ScalarFunc::Round, DataType::Decimal(precision, scale)) => {
+                        let digits = match args.get(1) {
+                            Some(Expr::Literal(Value::Int(d))) => (*d).max(0) as u32,
+                            _ => 0,
+                        };
+                        DataType::Decimal(precision, scale.min(digits))
+                    }
+                    (_, arg) => arg,
+                }
+            }
+            Expr::Arith(op, a, b) => op.datatype(a.datatype(table)?, b.datatype(table)?)?,
+            Expr::Compare(..) | Expr::And(..) | Expr::Or(..) | Expr::Not(_) => DataType::Boolean,
+        })
+    }
+
+    // How tightly each form binds when written out, loosest first
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Or(..) => 1,
+            Expr::And(..) => 2,
+            Expr::Not(_) => 3,
+            Expr::Compare(..) => 4,
+            Expr::Arith(Arithmetic::Add | Arithmetic::Sub, ..) => 5,
+            Expr::Arith(Arithmetic::Mul | Arithmetic::Div, ..) => 6,
+            Expr::ColumnRef(_) | Expr::Literal(_) | Expr::Call(..) => 7,
+        }
+    }
+}
+
+// Boolean values as true, false or unknown
+fn truth(value: Value) -> Result<Option<bool>, DbError> {
+    match value {
+        Value::Boolean(b) => Ok(Some(b)),
+        Value::Null => Ok(None),
+        other => Err(DbError::TypeMismatch(format!("Expected a boolean, found {}", other))),
+    }
+}
+
+fn literal_type(value: &Value) -> DataType {
+    match value {
+        Value::Char(_) => DataType::Char(1),
+        Value::Text(_) => DataType::Text,
+        Value::Boolean(_) => DataType::Boolean,
+        Value::Int(_) => DataType::Int,
+        Value::BigInt(_) => DataType::BigInt,
+        Value::Float(_) => DataType::Float,
+        Value::Double(_) => DataType::Double,
+        Value::Date(_) => DataType::Date,
+        Value::Time(_) => DataType::Time,
+        Value::DateTime(_) => DataType::DateTime,
+        Value::Point(..) => DataType::Point,
+        Value::Decimal(d) => DataType::Decimal(MAX_PRECISION, d.scale),
+        Value::Varchar(_) | Value::Enum(_) | Value::Set(_) | Value::Null => DataType::Varchar(MAX_VARCHAR_LENGTH),
+    }
+}
+
+impl Comparison {
+    // NULL on either side makes the answer unknown
+    fn apply(self, a: &Value, b: &Value) -> Value {
+        if *a == Value::Null || *b == Value::Null {
+            return Value::Null;
+        }
+        Value::Boolean(match self {
+            Comparison::Eq => a == b,
+            Comparison::Ne => a != b,
+            Comparison::Lt => a < b,
+            Comparison::Le => a <= b,
+            Comparison::Gt => a > b,
+            Comparison::Ge => a >= b,
+        })
+    }
+}
+
+impl Arithmetic {
+    fn apply(self, a: &Value, b: &Value) -> Result<Value, DbError> {
+        if *a == Value::Null || *b == Value::Null {
+            return Ok(Value::Null);
+        }
+        let overflow = || DbError::InvalidArgument(format!("{} {} {} is out of range", a, self, b));
+        let is_zero = match b {
+            Value::Decimal(d) => d.mantissa == 0,
+            _ => b.as_numeric().is_some_and(|n| n.to_f64() == 0.0),
+        };
+        if self == Arithmetic::Div && is_zero {
+            return Err(DbError::InvalidArgument(format!("{} {} {} divides by zero", a, self, b)));
+        }
+
+        match (a, b) {
+            (Value::Date(_) | Value::DateTime(_), Value::Int(_) | Value::BigInt(_)) => {
+                let days = integer(b)?;
+                let days = match self {
+                    Arithmetic::Add => Some(days),
+                    Arithmetic::Sub => days.checked_neg(),
+                    _ => return Err(self.mismatch(a, b)),
+                };
+                days.and_then(|days| add_days(a, days)).ok_or_else(overflow)
+            }
+            (Value::Int(x), Value::Int(y)) => self
+                .integers(*x as i64, *y as i64)
+                .and_then(|n| i32::try_from(n).ok())
+                .map(Value::Int)
+                .ok_or_else(overflow),
+            (Value::Int(_) | Value::BigInt(_), Value::Int(_) | Value::BigInt(_)) => {
+                self.integers(integer(a)?, integer(b)?).map(Value::BigInt).ok_or_else(overflow)
+            }
+            (Value::Decimal(_), Value::Decimal(_) | Value::Int(_) | Value::BigInt(_))
+            | (Value::Int(_) | Value::BigInt(_), Value::Decimal(_)) => {
+                let (x, y) = (exact(a).ok_or_else(overflow)?, exact(b).ok_or_else(overflow)?);
+                let result = match self {
+                    Arithmetic::Add => x.checked_add(y),
+                    Arithmetic::Sub => x.checked_sub(y),
+                    Arithmetic::Mul => x.checked_mul(y),
+                    Arithmetic::Div => x.checked_div(y, quotient_scale(x.scale)),
+                };
+                result.map(Value::Decimal).ok_or_else(overflow)
+            }
+            (Value::Float(x), Value::Float(y)) => Ok(Value::Float(self.floats(*x as f64, *y as f64) as f32)),
+            _ => match (float(a), float(b)) {
+                (Some(x), Some(y)) => Ok(Value::Double(self.floats(x, y))),
+                _ => Err(self.mismatch(a, b)),
+            },
+        }
+    }
+
+    fn integers(self, x: i64, y: i64) -> Option<i64> {
+        match self {
+            Arithmetic::Add => x.checked_add(y),
+            Arithmetic::Sub => x.checked_sub(y),
+            Arithmetic::Mul => x.checked_mul(y),
+            Arithmetic::Div => x.checked_div(y),
+        }
+    }
+
+    fn floats(self, x: f64, y: f64) -> f64 {
+        match self {
+            Arithmetic::Add => x + y,
+            Arithmetic::Sub => x - y,
+            Arithmetic::Mul => x * y,
+            Arithmetic::Div => x / y,
+        }
+    }
+
+    // Follows apply, so a result column can hold what it gives
+    fn datatype(self, a: DataType, b: DataType) -> Result<DataType, DbError> {
+        let integer = |t: &DataType| matches!(t, DataType::Int | DataType::BigInt);
+        let numeric = |t: &DataType| integer(t) || matches!(t, DataType::Float | DataType::Double | DataType::Decimal(..));
+        Ok(match (&a, &b) {
+            (DataType::Date | DataType::DateTime, t) if integer(t) && matches!(self, Arithmetic::Add | Arithmetic::Sub) => a,
+            (DataType::Int, DataType::Int) => DataType::Int,
+            (x, y) if integer(x) && integer(y) => DataType::BigInt,
+            (DataType::Decimal(_, s), DataType::Decimal(_, t)) => self.decimal_type(*s, *t),
+            (DataType::Decimal(_, s), t) if integer(t) => self.decimal_type(*s, 0),
+            (t, DataType::Decimal(_, s)) if integer(t) => self.decimal_type(0, *s),
+            (DataType::Float, DataType::Float) => DataType::Float,
+            (x, y) if numeric(x) && numeric(y) => DataType::Double,
+            _ => return Err(DbError::TypeMismatch(format!("Cannot {} {:?} and {:?}", self.verb(), a, b))),
+        })
+    }
+
+    fn decimal_type(self, s: u32, t: u32) -> DataType {
+        let scale = match self {
+            Arithmetic::Mul => (s + t).min(MAX_PRECISION),
+            Arithmetic::Div => quotient_scale(s),
+            _ => s.max(t),
+        };
+        DataType::Decimal(MAX_PRECISION, scale)
+    }
+
+    fn mismatch(self, a: &Value, b: &Value) -> DbError {
+        DbError::TypeMismatch(format!("Cannot {} {} and {}", self.verb(), a, b))
+    }
+
+    fn verb(self) -> &'static str {
+        match self {
+            Arithmetic::Add => "add",
+            Arithmetic::Sub => "subtract",
+            Arithmetic::Mul => "multiply",
+            Arithmetic::Div => "divide",
+        }
+    }
+}
+
+fn exact(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Decimal(d) => Some(*d),
+        Value::Int(i) => Some(Decimal::new(*i as i128, 0)),
+        Value::BigInt(i) => Some(Decimal::new(*i as i128, 0)),
+        _ => None,
+    }
+}
+
+// Only reached with a Float or Double on one side, which makes the result approximate
+fn float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Decimal(d) => Some(d.to_f64()),
+        _ => value.as_numeric().map(|n| n.to_f64()),
+    }
+}
+
+fn add_days(value: &Value, days: i64) -> Option<Value> {
+    let days = TimeDelta::try_days(days)?;
+    match value {
+        Value::Date(d) => d.checked_add_signed(days).map(Value::Date),
+        Value::DateTime(dt) => dt.checked_add_signed(days).map(Value::DateTime),
+        _ => None,
+    }
+}
+
+impl ScalarFunc {
+    fn check_arity(&self, count: usize) -> Result<(), DbError> {
+        let (min, max) = match self {
+            ScalarFunc::Substr => (2, 3),
+            ScalarFunc::Round => (1, 2),
+            ScalarFunc::DateAdd => (2, 2),
+            _ => (1, 1),
+        };
+        if (min..=max).contains(&count) {
+            Ok(())
+        } else {
+            Err(DbError::InvalidArgument(format!("{} takes {} arguments, not {}", self, arity(min, max), count)))
+        }
+    }
+
+    // Any NULL argument makes the result NULL
+    fn apply(self, args: &[Value]) -> Result<Value, DbError> {
+        if args.contains(&Value::Null) {
+            return Ok(Value::Null);
+        }
+        match (self, args) {
+            (ScalarFunc::Upper, [v]) => map_text(v, |s| s.to_uppercase()),
+            (ScalarFunc::Lower, [v]) => map_text(v, |s| s.to_lowercase()),
+            (ScalarFunc::Length, [v]) => Ok(Value::Int(text(v)?.chars().count() as i32)),
+            (ScalarFunc::Substr, [v, start, rest @ ..]) => {
+                let skip = (integer(start)?.max(1) - 1) as usize;
+                let length = match rest.first().map(integer).transpose()? {
+                    Some(n) if n < 0 => return Err(DbError::InvalidArgument("SUBSTR length cannot be negative".to_string())),
+                    Some(n) => n as usize,
+                    None => usize::MAX,
+                };
+                map_text(v, |s| s.chars().skip(skip).take(length).collect())
+            }
+            (ScalarFunc::Round, [v, rest @ ..]) => round(v, rest.first().map(integer).transpose()?.unwrap_or(0)),
+            (ScalarFunc::Abs, [v]) => abs(v),
+            (ScalarFunc::DateAdd, [v, days]) => {
+                if !matches!(v, Value::Date(_) | Value::DateTime(_)) {
+                    return Err(mismatch(self, v));
+                }
+                add_days(v, integer(days)?)
+                    .ok_or_else(|| DbError::InvalidArgument(format!("DATE_ADD({}, {}) is out of range", v, days)))
+            }
+            (ScalarFunc::Extract(part), [v]) => extract(v, part).ok_or_else(|| mismatch(self, v)),
+            _ => Err(DbError::InvalidArgument(format!("{} cannot take {} arguments", self, args.len()))),
+        }
+    }
+}
+
+fn arity(min: usize, max: usize) -> String {
+    if min == max {
+        min.to_string()
+    } else {
+        format!("{} to {}", min, max)
+    }
+}
+
+fn mismatch(func: ScalarFunc, value: &Value) -> DbError {
+    DbError::TypeMismatch(format!("{} cannot take {}", func, value))
+}
+
+fn text(value: &Value) -> Result<String, DbError> {
+    match value {
+        Value::Char(c) => Ok(c.to_string()),
+        Value::Varchar(s) | Value::Text(s) => Ok(s.clone()),
+        other => Err(DbError::TypeMismatch(format!("Expected text, found {}", other))),
+    }
+}
+
+// Keeps the kind of string it was given; a Char only stays one while it is one character
+fn map_text(value: &Value, f: impl Fn(&str) -> String) -> Result<Value, DbError> {
+    let mapped = f(&text(value)?);
+    Ok(match value {
+        Value::Text(_) => Value::Text(mapped),
+        Value::Char(_) if mapped.chars().count() == 1 => Value::Char(mapped.chars().next().unwrap()),
+        _ => Value::Varchar(mapped),
+    })
+}
+
+fn integer(value: &Value) -> Result<i64, DbError> {
+    match value {
+        Value::Int(i) => Ok(*i as i64),
+        Value::BigInt(i) => Ok(*i),
+        other => Err(DbError::TypeMismatch(format!("Expected an integer, found {}", other))),
+    }
+}
+
+// Halves round away from zero. Negative digits round to tens, hundreds and so on.
+fn round(value: &Value, digits: i64) -> Result<Value, DbError> {
+    let overflow = || DbError::InvalidArgument(format!("ROUND({}, {}) is out of range", value, digits));
+    let factor = 10f64.powi(digits.clamp(-308, 308) as i32);
+    match value {
+        Value::Float(f) => Ok(Value::Float(((*f as f64 * factor).round() / factor) as f32)),
+        Value::Double(f) => Ok(Value::Double((f * factor).round() / factor)),
+        Value::Int(i) => i32::try_from(round_integer(*i as i128, digits).ok_or_else(overflow)?)
+            .map(Value::Int)
+            .map_err(|_| overflow()),
+        Value::BigInt(i) => i64::try_from(round_integer(*i as i128, digits).ok_or_else(overflow)?)
+            .map(Value::BigInt)
+            .map_err(|_| overflow()),
+        Value::Decimal(d) if digits >= d.scale as i64 => Ok(Value::Decimal(*d)),
+        Value::Decimal(d) if digits >= 0 => d.rescale(digits as u32).map(Value::Decimal).ok_or_else(overflow),
+        Value::Decimal(d) => {
+            let whole = d.rescale(0).ok_or_else(overflow)?;
+            Ok(Value::Decimal(Decimal::new(round_integer(whole.mantissa, digits).ok_or_else(overflow)?, 0)))
+        }
+        other => Err(mismatch(ScalarFunc::Round, other)),
+    }
+}
+
+fn round_integer(i: i128, digits: i64) -> Option<i128> {
+    if digits >= 0 {
+        return Some(i);
+    }
+    let unit = match 10i128.checked_pow(digits.unsigned_abs().try_into().ok()?) {
+        Some(unit) => unit,
+        None => return Some(0),
+    };
+    let rest = i % unit;
+    let base = i - rest;
+    if rest.abs() * 2 >= unit {
+        base.checked_add(unit * i.signum())
+    } else {
+        Some(base)
+    }
+}
+
+fn abs(value: &Value) -> Result<Value, DbError> {
+    let overflow = || DbError::InvalidArgument(format!("ABS({}) is out of range", value));
+    match value {
+        Value::Int(i) => i.checked_abs().map(Value::Int).ok_or_else(overflow),
+        Value::BigInt(i) => i.checked_abs().map(Value::BigInt).ok_or_else(overflow),
+        Value::Float(f) => Ok(Value::Float(f.abs())),
+        Value::Double(f) => Ok(Value::Double(f.abs())),
+        Value::Decimal(d) => Ok(Value::Decimal(Decimal::new(d.mantissa.abs(), d.scale))),
+        other => Err(mismatch(ScalarFunc::Abs, other)),
+    }
+}
+
+fn extract(value: &Value, part: DatePart) -> Option<Value> {
+    let n = match (value, part) {
+        (Value::Date(d), DatePart::Year) => d.year(),
+        (Value::Date(d), DatePart::Month) => d.month() as i32,
+        (Value::Date(d), DatePart::Day) => d.day() as i32,
+        (Value::DateTime(dt), DatePart::Year) => dt.year(),
+        (Value::DateTime(dt), DatePart::Month) => dt.month() as i32,
+        (Value::DateTime(dt), DatePart::Day) => dt.day() as i32,
+        (Value::DateTime(dt), DatePart::Hour) => dt.hour() as i32,
+        (Value::DateTime(dt), DatePart::Minute) => dt.minute() as i32,
+        (Value::DateTime(dt), DatePart::Second) => dt.second() as i32,
+        (Value::Time(t), DatePart::Hour) => t.hour() as i32,
+        (Value::Time(t), DatePart::Minute) => t.minute() as i32,
+        (Value::Time(t), DatePart::Second) => t.second() as i32,
+        _ => return None,
+    };
+    Some(Value::Int(n))
+}
+
+impl Column {
+    pub fn generated(&self) -> Option<&Expr> {
+        self.options.iter().find_map(|opt| match opt {
+            Options::Generated(expr) => Some(expr),
+            _ => None,
+        })
+    }
+}
+
+impl Table {
+    // Works out each generated column from the row, in column order, so one can build on
+    // another to its left. `is_set` says which columns the caller gave values for.
+    pub(crate) fn fill_generated(&self, row: &mut [Value], is_set: impl Fn(usize) -> bool) -> Result<(), DbError> {
+        for (i, column) in self.columns.iter().enumerate() {
+            if let Some(expr) = column.generated() {
+                if is_set(i) {
+                    return Err(DbError::ConstraintViolation(format!(
+                        "Column '{}' is generated and cannot be set directly",
+                        column.name
+                    )));
+                }
+                row[i] = expr.evaluate(self, row)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::ColumnRef(name) if is_plain_name(name) => write!(f, "{}", name),
+            Expr::ColumnRef(name) => write!(f, "\"{}\"", name),
+            Expr::Literal(value) => match value {
+                Value::Null => write!(f, "NULL"),
+                Value::Boolean(b) => write!(f, "{}", if *b { "TRUE" } else { "FALSE" }),
+                // Debug keeps the point on whole floats, so they read back as floats
+                Value::Float(n) => write!(f, "{:?}", n),
+                Value::Double(n) => write!(f, "{:?}", n),
+                Value::Int(_) | Value::BigInt(_) | Value::Decimal(_) => write!(f, "{}", value),
+                other => write!(f, "'{}'", other.to_string().replace('\'', "''")),
+            },
+            Expr::Call(ScalarFunc::Extract(part), args) => {
+                write!(f, "EXTRACT({} FROM {})", part, args.first().map_or(String::new(), Expr::to_string))
+            }
+            Expr::Call(func, args) => {
+                let args: Vec<String> = args.iter().map(Expr::to_string).collect();
+                write!(f, "{}({})", func, args.join(", "))
+            }
+            Expr::Arith(op, a, b) => self.binary(f, a, &op.to_string(), b),
+            // Comparisons don't chain, so one inside another is always bracketed
+            Expr::Compare(op, a, b) => {
+                let level = self.precedence();
+                write!(f, "{} {} {}", Grouped(a, a.precedence() <= level), op, Grouped(b, b.precedence() <= level))
+            }
+            Expr::And(a, b) => self.binary(f, a, "AND", b),
+            Expr::Or(a, b) => self.binary(f, a, "OR", b),
+            Expr::Not(inner) => write!(f, "NOT {}", Grouped(inner, inner.precedence() < self.precedence())),
+        }
+    }
+}
+
+impl Expr {
+    // Everything binary reads left to right, so only a right side that binds as loosely
+    // as the operator needs brackets to keep its grouping
+    fn binary(&self, f: &mut fmt::Formatter<'_>, a: &Expr, op: &str, b: &Expr) -> fmt::Result {
+        let level = self.precedence();
+        write!(f, "{} {} {}", Grouped(a, a.precedence() < level), op, Grouped(b, b.precedence() <= level))
+    }
+}
+
+// An expression written in brackets when `.1` is set
+struct Grouped<'a>(&'a Expr, bool);
+
+impl fmt::Display for Grouped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.1 {
+            write!(f, "({})", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+fn is_plain_name(name: &str) -> bool {
+    let keyword = ["NULL", "TRUE", "FALSE", "AND", "OR", "NOT"].iter().any(|k| name.eq_ignore_ascii_case(k));
+    !keyword
+        && name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+}
+
+impl fmt::Display for ScalarFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ScalarFunc::Upper => "UPPER",
+            ScalarFunc::Lower => "LOWER",
+            ScalarFunc::Length => "LENGTH",
+            ScalarFunc::Substr => "SUBSTR",
+            ScalarFunc::Round => "ROUND",
+            ScalarFunc::Abs => "ABS",
+            ScalarFunc::DateAdd => "DATE_ADD",
+            ScalarFunc::Extract(_) => "EXTRACT",
+        })
+    }
+}
+
+impl fmt::Display for DatePart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DatePart::Year => "YEAR",
+            DatePart::Month => "MONTH",
+            DatePart::Day => "DAY",
+            DatePart::Hour => "HOUR",
+            DatePart::Minute => "MINUTE",
+            DatePart::Second => "SECOND",
+        })
+    }
+}
+
+impl fmt::Display for Arithmetic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Arithmetic::Add => "+",
+            Arithmetic::Sub => "-",
+            Arithmetic::Mul => "*",
+            Arithmetic::Div => "/",
+        })
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Comparison::Eq => "=",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        })
+    }
+}
+
+// Reads the forms Display writes, e.g. `LENGTH(name) > 3 AND NOT active`
+impl FromStr for Expr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = ExprParser { tokens: tokenize(s)?, pos: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected '{}' in expression '{}'", token, s)),
+        }
+    }
+}
+
+struct ExprParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, matches: impl Fn(&Token) -> bool) -> bool {
+        let found = self.tokens.get(self.pos).is_some_and(matches);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.eat(|t| t.is_keyword(keyword))
+    }
+
+    fn eat_symbol(&mut self, symbol: &'static str) -> bool {
+        self.eat(|t| *t == Token::Symbol(symbol))
+    }
+
+    fn expect_symbol(&mut self, symbol: &'static str) -> Result<(), String> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' in expression", symbol))
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat_keyword("OR") {
+            expr = expr.or(self.and()?);
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.eat_keyword("AND") {
+            expr = expr.and(self.not()?);
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.eat_keyword("NOT") {
+            return Ok(self.not()?.not());
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.sum()?;
+        let op = match self.tokens.get(self.pos) {
+            Some(Token::Symbol("=")) => Comparison::Eq,
+            Some(Token::Symbol("!=" | "<>")) => Comparison::Ne,
+            Some(Token::Symbol("<")) => Comparison::Lt,
+            Some(Token::Symbol("<=")) => Comparison::Le,
+            Some(Token::Symbol(">")) => Comparison::Gt,
+            Some(Token::Symbol(">=")) => Comparison::Ge,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        Ok(Expr::Compare(op, Box::new(left), Box::new(self.sum()?)))
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        loop {
+            let op = match self.tokens.get(self.pos) {
+                Some(Token::Symbol("+")) => Arithmetic::Add,
+                Some(Token::Symbol("-")) => Arithmetic::Sub,
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = Expr::Arith(op, Box::new(expr), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        loop {
+            let op = match self.tokens.get(self.pos) {
+                Some(Token::Symbol("*")) => Arithmetic::Mul,
+                Some(Token::Symbol("/")) => Arithmetic::Div,
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = Expr::Arith(op, Box::new(expr), Box::new(self.primary()?));
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(n)) => number(&n).map(Expr::Literal),
+            // A sign the lexer left apart, as after NOT or THEN
+            Some(Token::Symbol("-")) => match self.next() {
+                Some(Token::Number(n)) => number(&format!("-{}", n)).map(Expr::Literal),
+                _ => Err("Expected a number after '-'".to_string()),
+            },
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::Varchar(s))),
+            Some(Token::QuotedIdent(name)) => Ok(Expr::ColumnRef(name)),
+            Some(Token::Symbol("(")) => {
+                let expr = self.or()?;
+                self.expect_symbol(")")?;
+                Ok(expr)
+            }
+            Some(Token::Ident(word)) => {
+                let literal = match word.to_ascii_uppercase().as_str() {
+                    "NULL" => Some(Value::Null),
+                    "TRUE" => Some(Value::Boolean(true)),
+                    "FALSE" => Some(Value::Boolean(false)),
+                    _ => None,
+                };
+                if let Some(value) = literal {
+                    return Ok(Expr::Literal(value));
+                }
+                if self.eat_symbol("(") {
+                    return self.call(&word);
+                }
+                let mut name = word;
+                while self.eat_symbol(".") {
+                    match self.next() {
+                        Some(Token::Ident(part) | Token::QuotedIdent(part)) => name = format!("{}.{}", name, part),
+                        _ => return Err(format!("Expected a column name after '{}.'", name)),
+                    }
+                }
+                Ok(Expr::ColumnRef(name))
+            }
+            Some(token) => Err(format!("Unexpected '{}' in expression", token)),
+            None => Err("Expression ended early".to_string()),
+        }
+    }
+
+    // After `name(`
+    fn call(&mut self, name: &str) -> Result<Expr, String> {
+        let func = match name.to_ascii_uppercase().as_str() {
+            "UPPER" => ScalarFunc::Upper,
+            "LOWER" => ScalarFunc::Lower,
+            "LENGTH" => ScalarFunc::Length,
+            "SUBSTR" | "SUBSTRING" => ScalarFunc::Substr,
+            "ROUND" => ScalarFunc::Round,
+            "ABS" => ScalarFunc::Abs,
+            "DATE_ADD" => ScalarFunc::DateAdd,
+            "EXTRACT" => {
+                let part = match self.next() {
+                    Some(Token::Ident(word)) => date_part(&word)?,
+                    _ => return Err("EXTRACT needs YEAR, MONTH, DAY, HOUR, MINUTE or SECOND".to_string()),
+                };
+                if !self.eat_keyword("FROM") {
+                    return Err("Expected FROM in EXTRACT".to_string());
+                }
+                let arg = self.or()?;
+                self.expect_symbol(")")?;
+                return Ok(Expr::Call(ScalarFunc::Extract(part), vec![arg]));
+            }
+            _ => return Err(format!("Unknown function '{}'", name)),
+        };
+        let mut args = vec![];
+        if !self.eat_symbol(")") {
+            loop {
+                args.push(self.or()?);
+                if self.eat_symbol(")") {
+                    break;
+                }
+                self.expect_symbol(",")?;
+            }
+        }
+        Ok(Expr::Call(func, args))
+    }
+}
+
+fn date_part(word: &str) -> Result<DatePart, String> {
+    match word.to_ascii_uppercase().as_str() {
+        "YEAR" => Ok(DatePart::Year),
+        "MONTH" => Ok(DatePart::Month),
+        "DAY" => Ok(DatePart::Day),
+        "HOUR" => Ok(DatePart::Hour),
+        "MINUTE" => Ok(DatePart::Minute),
+        "SECOND" => Ok(DatePart::Second),
+        _ => Err(format!("Unknown date part '{}'", word)),
+    }
+}
+
+fn number(n: &str) -> Result<Value, String> {
+    if n.contains(['.', 'e', 'E']) {
+        n.parse().map(Value::Double).map_err(|_| format!("Invalid number '{}'", n))
+    } else if let Ok(i) = n.parse() {
+        Ok(Value::Int(i))
+    } else {
+        n.parse().map(Value::BigInt).map_err(|_| format!("Invalid number '{}'", n))
+    }
+}
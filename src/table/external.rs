@@ -0,0 +1,273 @@
+use crate::error::DbError;
+use crate::table::data::{Column, ExternalFormat, ExternalTable, Table, Value};
+use crate::table::filters::FilterExpr;
+use crate::table::resolver::resolve_column_in;
+use csv::ReaderBuilder;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+pub type ExternalRows = Box<dyn Iterator<Item = Result<Vec<Value>, DbError>>>;
+pub type ExternalJoinRow<'a> = (Vec<&'a Value>, Vec<Value>);
+
+impl ExternalTable {
+    pub fn new(
+        name: &str,
+        path: impl Into<PathBuf>,
+        format: ExternalFormat,
+        columns: Vec<Column>,
+    ) -> Self {
+        ExternalTable {
+            name: name.to_string(),
+            path: path.into(),
+            format,
+            columns,
+        }
+    }
+
+    // Opens the file and parses one row at a time as the iterator is advanced
+    pub fn scan(&self) -> Result<ExternalRows, DbError> {
+        let file = File::open(&self.path)
+            .map_err(|e| DbError::IoError(format!("Failed to open external table '{}': {}", self.name, e)))?;
+        let columns = self.columns.clone();
+
+        match self.format {
+            ExternalFormat::Csv => {
+                let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
+                let headers = rdr
+                    .headers()
+                    .map_err(|e| DbError::IoError(format!("CSV parse error: {}", e)))?
+                    .clone();
+                let positions = columns
+                    .iter()
+                    .map(|c| {
+                        headers
+                            .iter()
+                            .position(|h| h.trim() == c.name)
+                            .ok_or_else(|| DbError::NotFound(format!("Column '{}' not found in file header", c.name)))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(Box::new(rdr.into_records().enumerate().map(
+                    move |(line, record)| {
+                        let record = record.map_err(|e| DbError::IoError(format!("CSV parse error: {}", e)))?;
+                        columns
+                            .iter()
+                            .zip(&positions)
+                            .map(|(col, &pos)| {
+                                parse_field(record.get(pos).unwrap_or(""), col, line + 1)
+                            })
+                            .collect()
+                    },
+                )))
+            }
+            ExternalFormat::JsonLines => Ok(Box::new(
+                BufReader::new(file)
+                    .lines()
+                    .enumerate()
+                    .filter(|(_, line)| !matches!(line, Ok(l) if l.trim().is_empty()))
+                    .map(move |(line, text)| {
+                        let text = text.map_err(|e| DbError::IoError(format!("Read error: {}", e)))?;
+                        let object = parse_json_object(&text)
+                            .map_err(|e| DbError::TypeMismatch(format!("Line {}: {}", line + 1, e)))?;
+                        columns
+                            .iter()
+                            .map(|col| match object.get(&col.name) {
+                                Some(Some(raw)) => parse_field(raw, col, line + 1),
+                                _ => Ok(Value::Null),
+                            })
+                            .collect()
+                    }),
+            )),
+        }
+    }
+
+    pub fn select_where_expr(&self, expr: &FilterExpr) -> Result<Vec<Vec<Value>>, DbError> {
+        let mut positions = HashMap::new();
+        for col in expr.columns() {
+            positions.insert(col.as_str(), resolve_column_in(&self.columns, &self.name, col)?);
+        }
+        let position = |col: &str| positions.get(col).copied();
+
+        let mut rows = vec![];
+        for row in self.scan()? {
+            let row = row?;
+            if expr.matches_row(&row, &position) {
+                rows.push(row);
+            }
+        }
+        Ok(rows)
+    }
+
+    pub fn to_table(&self) -> Result<Table, DbError> {
+        let mut table = Table::new(&self.name, self.columns.clone(), None);
+        for row in self.scan()? {
+            table.insert(row?)?;
+        }
+        Ok(table)
+    }
+}
+
+impl Table {
+    // Hashes this table on its join column and streams the external file past it,
+    // so the external rows are never held in memory all at once
+    pub fn inner_join_external<'a>(
+        &'a self,
+        external: &ExternalTable,
+        on: (&str, &str),
+    ) -> Result<Vec<ExternalJoinRow<'a>>, DbError> {
+        let self_idx = self.resolve_column(on.0)?;
+        let other_idx = resolve_column_in(&external.columns, &external.name, on.1)?;
+
+        let mut by_key: HashMap<&Value, Vec<&Vec<Value>>> = HashMap::new();
+        for row in self.live_rows() {
+            by_key.entry(&row[self_idx]).or_default().push(row);
+        }
+
+        let mut result = vec![];
+        for right_row in external.scan()? {
+            let right_row = right_row?;
+            if let Some(left_rows) = by_key.get(&right_row[other_idx]) {
+                for left_row in left_rows {
+                    result.push((left_row.iter().collect(), right_row.clone()));
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn parse_field(raw: &str, col: &Column, line: usize) -> Result<Value, DbError> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() || trimmed.eq_ignore_ascii_case("null") {
+        return Ok(Value::Null);
+    }
+    Value::from_str(trimmed, &col.datatype).map_err(|e| {
+        DbError::TypeMismatch(format!(
+            "Error parsing value '{}' for column '{}' on row {}: {}",
+            raw, col.name, line, e
+        ))
+    })
+}
+
+// Parses one flat JSON object. Nested objects and arrays are not supported; null
+// maps to None and every other scalar is returned as its text.
+fn parse_json_object(text: &str) -> Result<HashMap<String, Option<String>>, String> {
+    let mut chars = text.trim().chars().peekable();
+    let mut fields = HashMap::new();
+
+    let skip_ws = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+    };
+
+    if chars.next() != Some('{') {
+        return Err("Expected a JSON object".to_string());
+    }
+
+    loop {
+        skip_ws(&mut chars);
+        match chars.peek() {
+            Some('}') => {
+                chars.next();
+                break;
+            }
+            Some('"') => {}
+            _ => return Err("Expected a field name".to_string()),
+        }
+
+        let key = parse_json_string(&mut chars)?;
+        skip_ws(&mut chars);
+        if chars.next() != Some(':') {
+            return Err(format!("Expected ':' after '{}'", key));
+        }
+        skip_ws(&mut chars);
+
+        let value = match chars.peek() {
+            Some('"') => Some(parse_json_string(&mut chars)?),
+            Some('{') | Some('[') => {
+                return Err(format!("Nested value for '{}' is not supported", key))
+            }
+            _ => {
+                let mut raw = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == ',' || c == '}' || c.is_whitespace() {
+                        break;
+                    }
+                    raw.push(c);
+                    chars.next();
+                }
+                if raw.is_empty() {
+                    return Err(format!("Missing value for '{}'", key));
+                }
+                if raw == "null" {
+                    None
+                } else {
+                    Some(raw)
+                }
+            }
+        };
+        fields.insert(key, value);
+
+        skip_ws(&mut chars);
+        match chars.next() {
+            Some(',') => continue,
+            Some('}') => break,
+            _ => return Err("Expected ',' or '}'".to_string()),
+        }
+    }
+
+    Ok(fields)
+}
+
+// Escapes as RFC 8259 has them; characters outside the BMP come as a \u surrogate pair
+fn parse_json_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    chars.next(); // opening quote
+    let mut out = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(out),
+            Some('\\') => match chars.next() {
+                Some(c @ ('"' | '\\' | '/')) => out.push(c),
+                Some('b') => out.push('\u{8}'),
+                Some('f') => out.push('\u{c}'),
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('r') => out.push('\r'),
+                Some('u') => {
+                    let code = match parse_hex4(chars)? {
+                        high @ 0xD800..=0xDBFF => {
+                            let low = match (chars.next(), chars.next()) {
+                                (Some('\\'), Some('u')) => parse_hex4(chars)?,
+                                _ => return Err(format!("Unpaired surrogate \\u{:04x}", high)),
+                            };
+                            if !(0xDC00..=0xDFFF).contains(&low) {
+                                return Err(format!("Unpaired surrogate \\u{:04x}", high));
+                            }
+                            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                        }
+                        low @ 0xDC00..=0xDFFF => return Err(format!("Unpaired surrogate \\u{:04x}", low)),
+                        code => code,
+                    };
+                    // Surrogates are ruled out above, so every code left is a char
+                    out.extend(char::from_u32(code));
+                }
+                Some(c) => return Err(format!("Invalid escape \\{}", c)),
+                None => return Err("Unterminated string".to_string()),
+            },
+            Some(c) if (c as u32) < 0x20 => return Err(format!("Unescaped control character \\u{:04x} in string", c as u32)),
+            Some(c) => out.push(c),
+            None => return Err("Unterminated string".to_string()),
+        }
+    }
+}
+
+fn parse_hex4(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<u32, String> {
+    let hex: String = chars.by_ref().take(4).collect();
+    if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid escape \\u{}", hex));
+    }
+    u32::from_str_radix(&hex, 16).map_err(|_| format!("Invalid escape \\u{}", hex))
+}
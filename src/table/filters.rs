@@ -1,8 +1,30 @@
-use crate::table::data::{Table, Value};
+use crate::error::DbError;
+use crate::table::data::{Collation, DataType, Table, Value};
+use crate::table::predicate::BoundPredicate;
 pub use crate::table::data::FilterExpr;
+use std::fmt;
+use std::str::FromStr;
+
+// A filter's value for a row: true, false, or None for SQL's unknown
+type Truth<'a> = Box<dyn Fn(&Vec<Value>) -> Option<bool> + 'a>;
 
 impl FilterExpr {
+    pub fn and(self, other: FilterExpr) -> FilterExpr {
+        FilterExpr::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: FilterExpr) -> FilterExpr {
+        FilterExpr::Or(Box::new(self), Box::new(other))
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> FilterExpr {
+        FilterExpr::Not(Box::new(self))
+    }
+
+    // Combinators report the value of their first comparison
     pub fn value(&self) -> &Value {
+        static NULL: Value = Value::Null;
         match self {
             FilterExpr::Eq(_, v)
             | FilterExpr::Ne(_, v)
@@ -10,39 +32,107 @@ impl FilterExpr {
             | FilterExpr::Lt(_, v)
             | FilterExpr::Ge(_, v)
             | FilterExpr::Le(_, v) => v,
+            // Between reports its lower bound, and In its first value or NULL
+            FilterExpr::Between(_, v, _) => v,
+            FilterExpr::In(_, values) => values.first().unwrap_or(&NULL),
+            FilterExpr::IsNull(_) | FilterExpr::IsNotNull(_) => &NULL,
+            // Spatial filters report their first point
+            FilterExpr::WithinBox(_, v, _) | FilterExpr::WithinRadius(_, v, _) => v,
+            FilterExpr::And(a, _) | FilterExpr::Or(a, _) | FilterExpr::Not(a) => a.value(),
         }
     }
 
-    pub fn to_predicate(&self, table: &Table) -> Box<dyn Fn(&Vec<Value>) -> bool + '_> {
-        let col_index = table.columns.iter().position(|c| c.name == *self.column()).unwrap();
+    // Rows are kept only when the filter is true; a comparison with NULL is unknown, as
+    // in SQL, so neither it nor its NOT keeps the row. IsNull and IsNotNull find NULLs.
+    pub fn to_predicate(&self, table: &Table) -> BoundPredicate<'_> {
+        let truth = self.to_truth(table);
+        Box::new(move |row| truth(row) == Some(true))
+    }
+
+    fn to_truth(&self, table: &Table) -> Truth<'_> {
         match self {
-            FilterExpr::Eq(_, v) => {
-                let val = v.clone();
-                Box::new(move |row| row[col_index] == val)
+            FilterExpr::And(a, b) => {
+                let (a, b) = (a.to_truth(table), b.to_truth(table));
+                Box::new(move |row| and(a(row), || b(row)))
             }
-            FilterExpr::Ne(_, v) => {
-                let val = v.clone();
-                Box::new(move |row| row[col_index] != val)
+            FilterExpr::Or(a, b) => {
+                let (a, b) = (a.to_truth(table), b.to_truth(table));
+                Box::new(move |row| or(a(row), || b(row)))
             }
-            FilterExpr::Gt(_, v) => {
-                let val = v.clone();
-                Box::new(move |row| row[col_index] > val)
+            FilterExpr::Not(a) => {
+                let a = a.to_truth(table);
+                Box::new(move |row| a(row).map(|t| !t))
             }
-            FilterExpr::Lt(_, v) => {
-                let val = v.clone();
-                Box::new(move |row| row[col_index] < val)
+            // A collated column compares the collation keys of its values
+            leaf => {
+                let col_index = table.resolve_column(leaf.column()).unwrap();
+                match table.columns[col_index].collation() {
+                    Collation::Binary => Box::new(move |row| leaf.truth(&row[col_index])),
+                    collation => {
+                        let keyed = leaf.collated(collation);
+                        Box::new(move |row| keyed.truth(&collation.key(&row[col_index])))
+                    }
+                }
             }
-            FilterExpr::Ge(_, v) => {
-                let val = v.clone();
-                Box::new(move |row| row[col_index] >= val)
-            }
-            FilterExpr::Le(_, v) => {
-                let val = v.clone();
-                Box::new(move |row| row[col_index] <= val)
+        }
+    }
+
+    // Like `matches` across a whole row; `position` finds each comparison's column
+    pub fn matches_row<F>(&self, row: &[Value], position: &F) -> bool
+    where
+        F: Fn(&str) -> Option<usize>,
+    {
+        self.truth_row(row, position) == Some(true)
+    }
+
+    fn truth_row<F>(&self, row: &[Value], position: &F) -> Option<bool>
+    where
+        F: Fn(&str) -> Option<usize>,
+    {
+        match self {
+            FilterExpr::And(a, b) => and(a.truth_row(row, position), || b.truth_row(row, position)),
+            FilterExpr::Or(a, b) => or(a.truth_row(row, position), || b.truth_row(row, position)),
+            FilterExpr::Not(a) => a.truth_row(row, position).map(|t| !t),
+            leaf => match position(leaf.column()) {
+                Some(i) => leaf.truth(&row[i]),
+                None => Some(false),
+            },
+        }
+    }
+
+    // Whether the filter is true for `value`; comparisons with NULL never are, even under
+    // NOT. Combinators apply every comparison to `value`.
+    pub fn matches(&self, value: &Value) -> bool {
+        self.truth(value) == Some(true)
+    }
+
+    fn truth(&self, value: &Value) -> Option<bool> {
+        let null = Value::Null;
+        match self {
+            FilterExpr::And(a, b) => and(a.truth(value), || b.truth(value)),
+            FilterExpr::Or(a, b) => or(a.truth(value), || b.truth(value)),
+            FilterExpr::Not(a) => a.truth(value).map(|t| !t),
+            FilterExpr::IsNull(_) => Some(*value == null),
+            FilterExpr::IsNotNull(_) => Some(*value != null),
+            _ if *value == null => None,
+            // A list holding NULL can only say a value is not in it when it is unknown
+            FilterExpr::In(_, values) if values.contains(value) => Some(true),
+            FilterExpr::In(_, values) => (!values.contains(&null)).then_some(false),
+            // x BETWEEN low AND high is x >= low AND x <= high
+            FilterExpr::Between(_, low, high) => {
+                and(compare(value, low, |a, b| a >= b), || compare(value, high, |a, b| a <= b))
             }
+            FilterExpr::Eq(_, v) => compare(value, v, |a, b| a == b),
+            FilterExpr::Ne(_, v) => compare(value, v, |a, b| a != b),
+            FilterExpr::Gt(_, v) => compare(value, v, |a, b| a > b),
+            FilterExpr::Lt(_, v) => compare(value, v, |a, b| a < b),
+            FilterExpr::Ge(_, v) => compare(value, v, |a, b| a >= b),
+            FilterExpr::Le(_, v) => compare(value, v, |a, b| a <= b),
+            FilterExpr::WithinBox(..) | FilterExpr::WithinRadius(..) => Some(self.matches_spatial(value)),
         }
     }
 
+    // Combinators report the column of their first comparison; see columns()
     pub fn column(&self) -> &String {
         match self {
             FilterExpr::Eq(col, _)
@@ -50,7 +140,451 @@ impl FilterExpr {
             | FilterExpr::Gt(col, _)
             | FilterExpr::Lt(col, _)
             | FilterExpr::Ge(col, _)
-            | FilterExpr::Le(col, _) => col,
+            | FilterExpr::Le(col, _)
+            | FilterExpr::Between(col, _, _)
+            | FilterExpr::In(col, _)
+            | FilterExpr::IsNull(col)
+            | FilterExpr::IsNotNull(col)
+            | FilterExpr::WithinBox(col, _, _)
+            | FilterExpr::WithinRadius(col, _, _) => col,
+            FilterExpr::And(a, _) | FilterExpr::Or(a, _) | FilterExpr::Not(a) => a.column(),
+        }
+    }
+
+    // Every column compared, in order, with repeats
+    pub fn columns(&self) -> Vec<&String> {
+        match self {
+            FilterExpr::And(a, b) | FilterExpr::Or(a, b) => {
+                let mut cols = a.columns();
+                cols.extend(b.columns());
+                cols
+            }
+            FilterExpr::Not(a) => a.columns(),
+            leaf => vec![leaf.column()],
+        }
+    }
+
+    pub fn is_compound(&self) -> bool {
+        matches!(self, FilterExpr::And(..) | FilterExpr::Or(..) | FilterExpr::Not(..))
+    }
+
+    // Fails on the first column the table does not have
+    pub fn check_columns(&self, table: &Table) -> Result<(), DbError> {
+        for col in self.columns() {
+            table.resolve_column(col)?;
+        }
+        Ok(())
+    }
+
+    // The same filter with every value it compares with replaced by `f`'s, failing on
+    // the first error
+    pub fn try_map_values<E>(&self, f: &mut impl FnMut(&Value) -> Result<Value, E>) -> Result<FilterExpr, E> {
+        Ok(match self {
+            FilterExpr::Eq(col, v) => FilterExpr::Eq(col.clone(), f(v)?),
+            FilterExpr::Ne(col, v) => FilterExpr::Ne(col.clone(), f(v)?),
+            FilterExpr::Gt(col, v) => FilterExpr::Gt(col.clone(), f(v)?),
+            FilterExpr::Lt(col, v) => FilterExpr::Lt(col.clone(), f(v)?),
+            FilterExpr::Ge(col, v) => FilterExpr::Ge(col.clone(), f(v)?),
+            FilterExpr::Le(col, v) => FilterExpr::Le(col.clone(), f(v)?),
+            FilterExpr::Between(col, low, high) => FilterExpr::Between(col.clone(), f(low)?, f(high)?),
+            FilterExpr::In(col, values) => FilterExpr::In(col.clone(), values.iter().map(&mut *f).collect::<Result<_, E>>()?),
+            FilterExpr::WithinBox(col, low, high) => FilterExpr::WithinBox(col.clone(), f(low)?, f(high)?),
+            FilterExpr::WithinRadius(col, center, km) => FilterExpr::WithinRadius(col.clone(), f(center)?, *km),
+            FilterExpr::And(a, b) => a.try_map_values(f)?.and(b.try_map_values(f)?),
+            FilterExpr::Or(a, b) => a.try_map_values(f)?.or(b.try_map_values(f)?),
+            FilterExpr::Not(a) => a.try_map_values(f)?.not(),
+            FilterExpr::IsNull(_) | FilterExpr::IsNotNull(_) => self.clone(),
+        })
+    }
+}
+
+// Unknown when either side is NULL
+fn compare(value: &Value, other: &Value, test: fn(&Value, &Value) -> bool) -> Option<bool> {
+    match (value, other) {
+        (Value::Null, _) | (_, Value::Null) => None,
+        _ => Some(test(value, other)),
+    }
+}
+
+// SQL's AND and OR over true, false and unknown; `b` is only evaluated when it can matter
+fn and(a: Option<bool>, b: impl FnOnce() -> Option<bool>) -> Option<bool> {
+    match a {
+        Some(false) => Some(false),
+        a => match (a, b()) {
+            (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        },
+    }
+}
+
+fn or(a: Option<bool>, b: impl FnOnce() -> Option<bool>) -> Option<bool> {
+    match a {
+        Some(true) => Some(true),
+        a => match (a, b()) {
+            (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        },
+    }
+}
+
+impl fmt::Display for FilterExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // AND binds tighter than OR, so only an OR inside an AND or NOT needs parentheses
+        let operand = |expr: &FilterExpr, parens: bool| {
+            if parens {
+                format!("({})", expr)
+            } else {
+                expr.to_string()
+            }
+        };
+        let op = match self {
+            FilterExpr::And(a, b) => {
+                let (pa, pb) = (matches!(**a, FilterExpr::Or(..)), matches!(**b, FilterExpr::Or(..) | FilterExpr::And(..)));
+                return write!(f, "{} AND {}", operand(a, pa), operand(b, pb));
+            }
+            FilterExpr::Or(a, b) => {
+                return write!(f, "{} OR {}", a, operand(b, matches!(**b, FilterExpr::Or(..))));
+            }
+            FilterExpr::Not(a) => return write!(f, "NOT {}", operand(a, a.is_compound())),
+            FilterExpr::Eq(_, _) => "=",
+            FilterExpr::Ne(_, _) => "!=",
+            FilterExpr::Gt(_, _) => ">",
+            FilterExpr::Lt(_, _) => "<",
+            FilterExpr::Ge(_, _) => ">=",
+            FilterExpr::Le(_, _) => "<=",
+            FilterExpr::Between(col, low, high) => {
+                return write!(f, "{} BETWEEN {} AND {}", col, literal(low), literal(high));
+            }
+            FilterExpr::In(col, values) => {
+                let values: Vec<String> = values.iter().map(literal).collect();
+                return write!(f, "{} IN ({})", col, values.join(", "));
+            }
+            FilterExpr::IsNull(col) => return write!(f, "{} IS NULL", col),
+            FilterExpr::IsNotNull(col) => return write!(f, "{} IS NOT NULL", col),
+            FilterExpr::WithinBox(col, sw, ne) => {
+                return write!(f, "{} WITHIN BOX {} {}", col, sw, ne);
+            }
+            FilterExpr::WithinRadius(col, center, km) => {
+                return write!(f, "{} WITHIN {} KM OF {}", col, km, center);
+            }
+        };
+        write!(f, "{} {} {}", self.column(), op, literal(self.value()))
+    }
+}
+
+// Quotes anything that would not read as a bare literal. A set is its quoted members in
+// braces, e.g. `{'a', 'b c'}`.
+fn literal(value: &Value) -> String {
+    let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+    match value {
+        Value::Null | Value::Boolean(_) | Value::Int(_) | Value::BigInt(_)
+        | Value::Float(_) | Value::Double(_) => value.to_string(),
+        Value::Set(items) => format!("{{{}}}", items.iter().map(|item| quote(item)).collect::<Vec<_>>().join(", ")),
+        other => quote(&other.to_display_string()),
+    }
+}
+
+// Parses the form produced by Display, e.g. `age >= 18 AND NOT name = 'O''Brien'`.
+// Literals are typed by their shape; use Table::parse_filter to type them by column.
+impl FromStr for FilterExpr {
+    type Err = DbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_compound(s, &parse_comparison)
+    }
+}
+
+fn parse_comparison(s: &str) -> Result<FilterExpr, DbError> {
+    if let Some(spatial) = parse_spatial(s) {
+        return spatial;
+    }
+    parse_leaf(s, |_, raw| {
+        if let Some(set) = parse_set_literal(raw) {
+            return set;
         }
+        match unquote(raw) {
+            Some(text) => Ok(Value::Varchar(text)),
+            None => parse_bare_literal(raw),
+        }
+    })
+}
+
+impl Table {
+    pub fn parse_filter(&self, s: &str) -> Result<FilterExpr, DbError> {
+        parse_compound(s, &|leaf| self.parse_comparison(leaf))
+    }
+
+    fn parse_comparison(&self, s: &str) -> Result<FilterExpr, DbError> {
+        if let Some(spatial) = parse_spatial(s) {
+            let expr = spatial?;
+            let col = &self.columns[self.resolve_column(expr.column())?];
+            if col.datatype != DataType::Point {
+                return Err(DbError::TypeMismatch(format!("Column '{}' is not a Point column", col.name)));
+            }
+            return Ok(expr);
+        }
+
+        parse_leaf(s, |column, raw| {
+            let col = &self.columns[self.resolve_column(column)?];
+            if raw.eq_ignore_ascii_case("null") {
+                return Ok(Value::Null);
+            }
+            if let Some(set) = parse_set_literal(raw) {
+                return match col.datatype {
+                    DataType::Set(_) => set,
+                    _ => Err(DbError::TypeMismatch(format!("Column '{}' is not a SET column", column))),
+                };
+            }
+            let text = unquote(raw).unwrap_or_else(|| raw.to_string());
+            Value::from_str(&text, &col.datatype)
+                .map_err(|e| DbError::TypeMismatch(format!("Invalid literal for column '{}': {}", column, e)))
+        })
+    }
+}
+
+// `col BETWEEN low AND high`, `col IN (a, b, ...)`, `col IS [NOT] NULL` or `col op value`,
+// with `literal` turning each raw literal into a value for the column
+fn parse_leaf<F>(s: &str, literal: F) -> Result<FilterExpr, DbError>
+where
+    F: Fn(&str, &str) -> Result<Value, DbError>,
+{
+    let s = s.trim();
+    if let Some((column, rest)) = s.split_once(char::is_whitespace) {
+        let rest = rest.trim_start();
+        if let Some(bounds) = strip_keyword(rest, "BETWEEN") {
+            return match split_keyword(bounds.trim(), "AND").as_slice() {
+                [low, high] => Ok(FilterExpr::Between(
+                    column.to_string(),
+                    literal(column, low.trim())?,
+                    literal(column, high.trim())?,
+                )),
+                _ => Err(DbError::InvalidArgument(format!("Expected 'BETWEEN low AND high' in '{}'", s))),
+            };
+        }
+        if let Some(list) = strip_keyword(rest, "IN") {
+            let list = list.trim();
+            if !list.starts_with('(') || closing_paren(list) != Some(list.len() - 1) {
+                return Err(DbError::InvalidArgument(format!("Expected a parenthesized list after IN in '{}'", s)));
+            }
+            let values = split_list(&list[1..list.len() - 1])
+                .into_iter()
+                .map(|raw| literal(column, raw))
+                .collect::<Result<Vec<Value>, DbError>>()?;
+            return Ok(FilterExpr::In(column.to_string(), values));
+        }
+        if let Some(null_test) = strip_keyword(rest, "IS") {
+            let null_test = null_test.split_whitespace().collect::<Vec<_>>().join(" ");
+            if null_test.eq_ignore_ascii_case("NULL") {
+                return Ok(FilterExpr::IsNull(column.to_string()));
+            }
+            if null_test.eq_ignore_ascii_case("NOT NULL") {
+                return Ok(FilterExpr::IsNotNull(column.to_string()));
+            }
+            return Err(DbError::InvalidArgument(format!("Expected 'IS NULL' or 'IS NOT NULL' in '{}'", s)));
+        }
+    }
+
+    let (column, op, raw) = split_filter(s)?;
+    Ok(build_filter(column, op, literal(column, raw)?))
+}
+
+// Comma-separated items outside quotes; an empty list has no items
+fn split_list(s: &str) -> Vec<&str> {
+    if s.trim().is_empty() {
+        return vec![];
+    }
+    let mut items = vec![];
+    let (mut quoted, mut depth, mut start) = (false, 0, 0);
+    for (i, c) in s.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            '{' if !quoted => depth += 1,
+            '}' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                items.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(s[start..].trim());
+    items
+}
+
+// OR binds loosest, then AND, then NOT; parentheses group. `comparison` parses the rest.
+fn parse_compound<F>(s: &str, comparison: &F) -> Result<FilterExpr, DbError>
+where
+    F: Fn(&str) -> Result<FilterExpr, DbError>,
+{
+    let s = s.trim();
+    for (keyword, combine) in [("OR", FilterExpr::or as fn(_, _) -> _), ("AND", FilterExpr::and)] {
+        let parts = split_keyword(s, keyword);
+        if parts.len() > 1 {
+            let mut parts = parts.into_iter();
+            let first = parse_compound(parts.next().unwrap(), comparison)?;
+            return parts.try_fold(first, |acc, part| Ok(combine(acc, parse_compound(part, comparison)?)));
+        }
+    }
+
+    if let Some(rest) = strip_keyword(s, "NOT") {
+        return Ok(parse_compound(rest, comparison)?.not());
+    }
+    if s.starts_with('(') && closing_paren(s) == Some(s.len() - 1) {
+        return parse_compound(&s[1..s.len() - 1], comparison);
+    }
+    comparison(s)
+}
+
+// Splits on a keyword standing alone outside quotes and parentheses. The AND of a
+// BETWEEN belongs to it, so splitting on AND skips that one.
+fn split_keyword<'a>(s: &'a str, keyword: &str) -> Vec<&'a str> {
+    let mut parts = vec![];
+    let (mut depth, mut quoted, mut start, mut between) = (0, false, 0, false);
+    for (i, c) in s.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            _ if quoted || depth != 0 || i == 0 || !s[..i].ends_with(char::is_whitespace) => {}
+            _ if strip_keyword(&s[i..], "BETWEEN").is_some() => between = true,
+            _ => {
+                if strip_keyword(&s[i..], keyword).is_some() {
+                    if keyword == "AND" && between {
+                        between = false;
+                    } else {
+                        parts.push(&s[start..i]);
+                        start = i + keyword.len();
+                    }
+                }
+            }
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+// The text after a leading keyword, which must be followed by a space or '('
+fn strip_keyword<'a>(s: &'a str, keyword: &str) -> Option<&'a str> {
+    let head = s.get(..keyword.len())?;
+    let rest = &s[keyword.len()..];
+    let separated = rest.starts_with(char::is_whitespace) || rest.starts_with('(');
+    (head.eq_ignore_ascii_case(keyword) && separated).then_some(rest)
+}
+
+// Byte position of the ')' closing the '(' that starts `s`
+fn closing_paren(s: &str) -> Option<usize> {
+    let (mut depth, mut quoted) = (0, false);
+    for (i, c) in s.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// `col WITHIN BOX (lat, lon) (lat, lon)` or `col WITHIN <km> KM OF (lat, lon)`;
+// None when the text is not a spatial filter at all
+fn parse_spatial(s: &str) -> Option<Result<FilterExpr, DbError>> {
+    let s = s.trim();
+    let upper = s.to_ascii_uppercase();
+    let at = upper.find(" WITHIN ")?;
+    let column = s[..at].trim().to_string();
+    let rest = s[at + " WITHIN ".len()..].trim();
+    let rest_upper = rest.to_ascii_uppercase();
+
+    let point = |s: &str| Value::from_point_str(s).map_err(DbError::InvalidArgument);
+    let parsed = if rest_upper.starts_with("BOX ") {
+        let points = rest["BOX ".len()..].trim();
+        match points.find(')') {
+            Some(end) => point(&points[..=end])
+                .and_then(|sw| point(&points[end + 1..]).map(|ne| FilterExpr::WithinBox(column, sw, ne))),
+            None => Err(DbError::InvalidArgument(format!("Expected two points after BOX in '{}'", s))),
+        }
+    } else if let Some(of) = rest_upper.find(" KM OF ") {
+        let km = rest[..of].trim();
+        km.parse::<f64>()
+            .map_err(|_| DbError::InvalidArgument(format!("Invalid radius '{}'", km)))
+            .and_then(|km| {
+                point(&rest[of + " KM OF ".len()..]).map(|center| FilterExpr::WithinRadius(column, center, km))
+            })
+    } else {
+        Err(DbError::InvalidArgument(format!("Expected BOX or '<km> KM OF' after WITHIN in '{}'", s)))
+    };
+    Some(parsed)
+}
+
+fn split_filter(s: &str) -> Result<(&str, &str, &str), DbError> {
+    let s = s.trim();
+    let op_start = s
+        .find(['=', '!', '<', '>'])
+        .ok_or_else(|| DbError::InvalidArgument(format!("No comparison operator in filter '{}'", s)))?;
+
+    let rest = &s[op_start..];
+    let op = [">=", "<=", "!=", "<>", "=", ">", "<"]
+        .into_iter()
+        .find(|op| rest.starts_with(op))
+        .ok_or_else(|| DbError::InvalidArgument(format!("Unknown operator in filter '{}'", s)))?;
+
+    let column = s[..op_start].trim();
+    let raw = rest[op.len()..].trim();
+    if column.is_empty() || raw.is_empty() {
+        return Err(DbError::InvalidArgument(format!("Filter '{}' must look like 'column op value'", s)));
+    }
+    Ok((column, op, raw))
+}
+
+fn unquote(raw: &str) -> Option<String> {
+    if raw.len() >= 2 && raw.starts_with('\'') && raw.ends_with('\'') {
+        Some(raw[1..raw.len() - 1].replace("''", "'"))
+    } else {
+        None
+    }
+}
+
+// `{'a', 'b'}`, as Display writes a set; None when `raw` isn't in braces
+fn parse_set_literal(raw: &str) -> Option<Result<Value, DbError>> {
+    let inner = raw.strip_prefix('{')?.strip_suffix('}')?;
+    let items = split_list(inner)
+        .into_iter()
+        .map(|item| unquote(item).ok_or_else(|| DbError::InvalidArgument(format!("Set member {} must be quoted in '{}'", item, raw))))
+        .collect::<Result<Vec<String>, DbError>>();
+    Some(items.map(Value::Set))
+}
+
+fn parse_bare_literal(raw: &str) -> Result<Value, DbError> {
+    if raw.eq_ignore_ascii_case("null") {
+        Ok(Value::Null)
+    } else if raw.eq_ignore_ascii_case("true") || raw.eq_ignore_ascii_case("false") {
+        Ok(Value::Boolean(raw.eq_ignore_ascii_case("true")))
+    } else if let Ok(i) = raw.parse::<i32>() {
+        Ok(Value::Int(i))
+    } else if let Ok(i) = raw.parse::<i64>() {
+        Ok(Value::BigInt(i))
+    } else if let Ok(f) = raw.parse::<f64>() {
+        Ok(Value::Double(f))
+    } else {
+        Err(DbError::InvalidArgument(format!("Unquoted literal '{}' is not a number, boolean or NULL", raw)))
+    }
+}
+
+fn build_filter(column: &str, op: &str, value: Value) -> FilterExpr {
+    let column = column.to_string();
+    match op {
+        "=" => FilterExpr::Eq(column, value),
+        "!=" | "<>" => FilterExpr::Ne(column, value),
+        ">" => FilterExpr::Gt(column, value),
+        "<" => FilterExpr::Lt(column, value),
+        ">=" => FilterExpr::Ge(column, value),
+        _ => FilterExpr::Le(column, value),
     }
 }
@@ -1,10 +1,31 @@
-use crate::table::data::{AggregationResult, Column, DataType, IndexType, Options, Table, Value};
+use crate::error::DbError;
+use crate::table::data::{
+    AggFunc, AggregationResult, Column, DataType, Expr, IndexType, JoinRow, JoinStrategy, LeftJoinRow, Limit, Limited, Options, QueryStage,
+    ResultSet, RightJoinRow, RowChange, Table, UndoEntry, Value,
+};
+use crate::table::joins::merge_join;
+use crate::table::profiler::{self, returned, scanned};
 use crate::table::filters::FilterExpr;
+use crate::table::predicate::{RowFilter, RowPredicate};
+use crate::table::update::RowUpdate;
+use crate::table::schema::{schema_path, TableSchema};
+use crate::storage::atomic::{self, SyncMode};
+use crate::storage::btree::DiskIndex;
+use crate::storage::compress::{self, CompressedWriter};
+use crate::storage::file::{page_path, read_rows, stage_compressed_rows, stage_rows};
+use crate::storage::journal::{self, journal_path, Journal};
+use crate::table::sharding::{read_shards, shard_paths, stage_stale_files};
 use csv::ReaderBuilder;
-use std::collections::{BTreeMap, HashMap};
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
 use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_DATA_DIR: &str = "db";
 
 impl Table {
     pub fn new(name: &str, columns: Vec<Column>, pk: Option<Vec<String>>) -> Self {
@@ -14,166 +35,450 @@ impl Table {
             rows: Vec::new(),
             primary_key: pk.clone(),
             indexes: HashMap::new(),
+            sequences: HashMap::new(),
             transaction_backup: None,
+            stats: None,
+            compression: None,
         };
 
-        if let Some(pk_cols) = &pk {
-            for col in pk_cols {
-                let _ = table.create_index(col, false);
+        // Primary key and unique columns are indexed so constraint checks don't scan
+        let mut indexed: Vec<String> = pk.clone().unwrap_or_default();
+        indexed.extend(
+            table
+                .columns
+                .iter()
+                .filter(|c| c.options.contains(&Options::Unique))
+                .map(|c| c.name.clone()),
+        );
+        for col in indexed {
+            if !table.indexes.contains_key(&col) {
+                let _ = table.create_index(&col, false);
             }
         }
 
         table
     }
 
-    pub fn insert(&mut self, values: Vec<Value>) -> Result<(), String> {
+    pub fn insert(&mut self, values: Vec<Value>) -> Result<(), DbError> {
+        let full_row = self.prepare_insert(values)?;
+        self.push_row(full_row);
+        Ok(())
+    }
+
+    // The row as stored, with defaults and identity values filled in
+    pub fn insert_returning(&mut self, values: Vec<Value>) -> Result<Vec<Value>, DbError> {
+        self.insert(values)?;
+        Ok(self.rows[self.rows.len() - 1].clone())
+    }
+
+    // Omitted columns are filled as NULLs would be: with their defaults, if any
+    pub fn insert_named(&mut self, values: HashMap<&str, Value>) -> Result<(), DbError> {
+        let row = self
+            .by_position(&values)?
+            .into_iter()
+            .map(|value| value.unwrap_or(Value::Null))
+            .collect();
+        self.insert(row)
+    }
+
+    // Validates every row before storing any, so either all of them go in or none do.
+    // Uniqueness is checked against the keys in use, collected once, and the indexes are
+    // only updated after the whole batch has passed.
+    pub fn insert_many(&mut self, rows: Vec<Vec<Value>>) -> Result<usize, DbError> {
+        let start = self.rows.len();
+        let sequences = self.sequences.clone();
+        if let Err(e) = self.append_unindexed(rows) {
+            self.rows.truncate(start);
+            self.sequences = sequences;
+            return Err(e);
+        }
+
+        for i in start..self.rows.len() {
+            self.update_indexes_for_row(i);
+            self.log_change(|table| {
+                UndoEntry::Row(RowChange::Insert {
+                    row: table.rows[i].clone(),
+                })
+            });
+        }
+        Ok(self.rows.len() - start)
+    }
+
+    // Identity counters move on with each row, so later rows in the batch get fresh ids
+    fn append_unindexed(&mut self, rows: Vec<Vec<Value>>) -> Result<(), DbError> {
+        let mut keys = self.unique_keys();
+        for values in rows {
+            let row = self.complete_row(values)?;
+            self.validate_values(&row)?;
+            for key in &mut keys {
+                key.claim(self, &row)?;
+            }
+            self.rows.push(row);
+            self.advance_sequences(self.rows.len() - 1);
+        }
+        Ok(())
+    }
+
+    // Stores an already validated row, keeping indexes, identity counters and the
+    // transaction log current
+    pub(crate) fn push_row(&mut self, row: Vec<Value>) {
+        self.rows.push(row);
+        let i = self.rows.len() - 1;
+        self.update_indexes_for_row(i);
+        self.advance_sequences(i);
+        self.log_change(|table| {
+            UndoEntry::Row(RowChange::Insert {
+                row: table.rows[i].clone(),
+            })
+        });
+    }
+
+    // Swaps in an already validated row and returns the one it replaced
+    pub(crate) fn replace_row(&mut self, i: usize, row: Vec<Value>) -> Vec<Value> {
+        self.remove_row_from_indexes(i);
+        let before = std::mem::replace(&mut self.rows[i], row);
+        self.update_indexes_for_row(i);
+        self.advance_sequences(i);
+        self.log_change(|table| {
+            UndoEntry::Row(RowChange::Update {
+                position: i,
+                before: before.clone(),
+                after: table.rows[i].clone(),
+            })
+        });
+        before
+    }
+
+    // Type checks, fills defaults and validates a row without storing it
+    pub(crate) fn prepare_insert(&self, values: Vec<Value>) -> Result<Vec<Value>, DbError> {
+        let full_row = self.complete_row(values)?;
+        self.validate_row(&full_row)?;
+        Ok(full_row)
+    }
+
+    // Type checks the values given and fills in defaults and generated columns
+    fn complete_row(&self, values: Vec<Value>) -> Result<Vec<Value>, DbError> {
         if values.len() != self.columns.len() {
-            return Err("Column count does not match".to_string());
+            return Err(DbError::InvalidArgument("Column count does not match".to_string()));
         }
 
         // Basic type check (can expand to enforce options)
         for (i, value) in values.iter().enumerate() {
             let col_type = &self.columns[i].datatype;
             if !Self::value_matches_type(value, col_type) {
-                return Err(format!(
+                return Err(DbError::TypeMismatch(format!(
                     "Type mismatch at column {}: expected {:?}, got {:?}",
                     self.columns[i].name, col_type, value
-                ));
+                )));
             }
         }
 
         // Apply defaults
-        let full_row = self.apply_defaults(&values)?;
+        let mut full_row = self.apply_defaults(&values)?;
+        self.fill_generated(&mut full_row, |i| values[i] != Value::Null)?;
+        self.fit_to_columns(&mut full_row)?;
+        Ok(full_row)
+    }
 
-        // Validate the fully constructed row
-        self.validate_row(&full_row)?;
+    pub fn select_all(&self) -> Vec<&Vec<Value>> {
+        self.live_rows().collect()
+    }
 
-        self.rows.push(full_row);
-        let i = self.rows.len() - 1;
-        self.update_indexes_for_row(i);
+    pub fn select_where<P: RowPredicate>(&self, predicate: P) -> Result<Vec<&Vec<Value>>, DbError> {
+        let positions = self.matching_positions(&predicate)?;
+        Ok(profiler::stage(QueryStage::Materialize, || {
+            let rows: Vec<&Vec<Value>> = positions.into_iter().map(|i| &self.rows[i]).collect();
+            returned(rows.len());
+            rows
+        }))
+    }
 
-        Ok(())
+    pub fn select_where_expr(&self, expr: &FilterExpr) -> Result<Vec<&Vec<Value>>, DbError> {
+        self.select_where(expr)
     }
 
-    pub fn select_all(&self) -> Vec<&Vec<Value>> {
-        self.rows.iter().collect()
-    }
-
-    pub fn select_where_expr(&self, expr: &FilterExpr) -> Vec<&Vec<Value>> {
-        let predicate = expr.to_predicate(self);
-
-        let col = expr.column();
-        if let Some(_col_idx) = self.columns.iter().position(|c| &c.name == col) {
-            if let Some(index) = self.indexes.get(col.as_str()) {
-                match (index, expr) {
-                    (IndexType::Hash(map), FilterExpr::Eq(_, val)) => {
-                        if let Some(indices) = map.get(val) {
-                            return indices
-                                .iter()
-                                .filter_map(|&i| self.rows.get(i))
-                                .filter(|row| predicate(row))
-                                .collect();
-                        }
-                    }
-                    (IndexType::BTree(map), FilterExpr::Lt(_, val)) => {
-                        return map
-                            .range(..val.clone())
-                            .flat_map(|(_, idxs)| idxs.iter())
-                            .filter_map(|&i| self.rows.get(i))
-                            .filter(|row| predicate(row))
-                            .collect();
-                    }
-                    (IndexType::BTree(map), FilterExpr::Gt(_, val)) => {
-                        return map
-                            .range(val.clone()..)
-                            .flat_map(|(_, idxs)| idxs.iter())
-                            .filter_map(|&i| self.rows.get(i))
-                            .filter(|row| predicate(row))
-                            .collect();
-                    }
-                    _ => {}
-                }
-            }
+    // Copies only the named columns out of each matching row, in the order given
+    pub fn select_columns(&self, columns: &[&str], filter: Option<&FilterExpr>) -> Result<ResultSet, DbError> {
+        if columns.is_empty() {
+            return Err(DbError::InvalidArgument("select_columns needs at least one column".to_string()));
         }
+        let exprs: Vec<Expr> = columns.iter().map(|name| Expr::column(name)).collect();
+        self.select_exprs(&exprs, filter)
+    }
+
+    // Plain columns keep their definitions in the result; anything else gets a column
+    // named after the expression, e.g. `UPPER(name)`
+    pub fn select_exprs(&self, exprs: &[Expr], filter: Option<&FilterExpr>) -> Result<ResultSet, DbError> {
+        if exprs.is_empty() {
+            return Err(DbError::InvalidArgument("select_exprs needs at least one expression".to_string()));
+        }
+        let evaluators = exprs.iter().map(|expr| expr.compile(self)).collect::<Result<Vec<_>, _>>()?;
+        let columns = exprs
+            .iter()
+            .map(|expr| match expr {
+                Expr::ColumnRef(name) => Ok(self.columns[self.resolve_column(name)?].clone()),
+                _ => Ok(Column {
+                    name: expr.to_string(),
+                    datatype: expr.datatype(self)?,
+                    options: vec![],
+                }),
+            })
+            .collect::<Result<Vec<Column>, DbError>>()?;
+
+        let project = |row: &Vec<Value>| evaluators.iter().map(|eval| eval(row)).collect::<Result<Vec<Value>, DbError>>();
+        let rows = match filter {
+            Some(expr) => self
+                .matching_positions(expr)?
+                .into_iter()
+                .map(|i| project(&self.rows[i]))
+                .collect::<Result<_, _>>()?,
+            None => self.live_rows().map(project).collect::<Result<_, _>>()?,
+        };
+        Ok(ResultSet::new(columns, rows))
+    }
+
+    // Visits matching rows in place and stops as soon as `f` breaks
+    pub fn for_each_where<P, B, F>(&self, predicate: P, mut f: F) -> Result<ControlFlow<B>, DbError>
+    where
+        P: RowPredicate,
+        F: FnMut(&Vec<Value>) -> ControlFlow<B>,
+    {
+        let matches = predicate.bind(self)?;
+        let live = |row: &&Vec<Value>| !self.is_expired(row) && matches(row);
+
+        Ok(match predicate.candidates(self) {
+            Some(candidates) => candidates
+                .into_iter()
+                .filter_map(|i| self.rows.get(i))
+                .filter(live)
+                .try_for_each(&mut f),
+            None => self.rows.iter().filter(live).try_for_each(&mut f),
+        })
+    }
+
+    // Positions of live rows matching `predicate`, in index order when an index is used
+    pub(crate) fn matching_positions<P: RowPredicate + ?Sized>(
+        &self,
+        predicate: &P,
+    ) -> Result<Vec<usize>, DbError> {
+        let matches = profiler::stage(QueryStage::Parse, || predicate.bind(self))?;
+        let positions = profiler::stage(QueryStage::IndexLookup, || {
+            predicate
+                .candidates(self)
+                .unwrap_or_else(|| (0..self.rows.len()).collect())
+        });
+        scanned(positions.len());
+
+        Ok(profiler::stage(QueryStage::Filter, || {
+            positions
+                .into_iter()
+                .filter(|&i| {
+                    self.rows
+                        .get(i)
+                        .is_some_and(|row| !self.is_expired(row) && matches(row))
+                })
+                .collect()
+        }))
+    }
 
-        self.rows.iter().filter(|row| predicate(row)).collect()
+    // Both return how many rows they changed. Indexes only narrow the scan; without one
+    // every live row is checked against the predicate.
+    pub fn update_where<P: RowPredicate, U: RowUpdate>(&mut self, predicate: P, updates: U) -> Result<usize, DbError> {
+        self.update_matching(predicate, updates).map(|indices| indices.len())
     }
 
-    pub fn update_where(
+    // The updated rows as stored, after defaults, generated columns and coercions
+    pub fn update_where_returning<P: RowPredicate, U: RowUpdate>(
         &mut self,
-        expr: &FilterExpr,
-        updates: Vec<Option<Value>>,
-    ) -> Result<(), String> {
-        let predicate = expr.to_predicate(self);
-        let _col_index = self
-            .columns
+        predicate: P,
+        updates: U,
+    ) -> Result<Vec<Vec<Value>>, DbError> {
+        let indices = self.update_matching(predicate, updates)?;
+        Ok(indices.into_iter().map(|i| self.rows[i].clone()).collect())
+    }
+
+    // Positions of the rows updated
+    fn update_matching<P: RowPredicate, U: RowUpdate>(&mut self, predicate: P, updates: U) -> Result<Vec<usize>, DbError> {
+        let indices = self.matching_positions(&predicate)?;
+        let updated_rows = {
+            let update = updates.bind(self)?;
+            let rows = indices
+                .iter()
+                .map(|&i| self.prepare_update(i, &update(&self.rows[i])?))
+                .collect::<Result<Vec<_>, DbError>>()?;
+            self.check_updated_unique(&indices, &rows)?;
+            rows
+        };
+
+        for (&i, new_row) in indices.iter().zip(updated_rows) {
+            self.replace_row(i, new_row);
+        }
+
+        Ok(indices)
+    }
+
+    // Applies the updates to the given rows, validating every new row before changing any
+    pub(crate) fn update_rows(
+        &mut self,
+        indices: &[usize],
+        updates: &[Option<Value>],
+    ) -> Result<usize, DbError> {
+        let updated_rows = self.prepare_updates(indices, updates)?;
+
+        for (&i, new_row) in indices.iter().zip(updated_rows) {
+            self.replace_row(i, new_row);
+        }
+
+        Ok(indices.len())
+    }
+
+    pub(crate) fn prepare_updates(
+        &self,
+        indices: &[usize],
+        updates: &[Option<Value>],
+    ) -> Result<Vec<Vec<Value>>, DbError> {
+        let rows = indices
             .iter()
-            .position(|c| c.name.as_str() == expr.column().as_str())
-            .ok_or_else(|| format!("Column '{}' not found", expr.column()))?;
-
-        let mut updated_rows = vec![];
-        let mut indices = vec![];
-
-        if let Some(index) = self.indexes.get(expr.column().as_str()) {
-            if let Some(row_indices) = index.get(&expr.value()) {
-                for &i in row_indices {
-                    if predicate(&self.rows[i]) {
-                        let mut new_row = self.rows[i].clone();
-                        for (j, update) in updates.iter().enumerate() {
-                            if let Some(val) = update {
-                                new_row[j] = val.clone();
-                            }
-                        }
-                        self.validate_row(&new_row)?;
-                        updated_rows.push(new_row);
-                        indices.push(i);
-                    }
-                }
+            .map(|&i| self.prepare_update(i, updates))
+            .collect::<Result<Vec<_>, DbError>>()?;
+        self.check_updated_unique(indices, &rows)?;
+        Ok(rows)
+    }
+
+    // The new rows may not collide with the rows left alone or with each other, so keys
+    // are claimed against every row outside the batch, as insert_many does. One row is
+    // probed through the indexes instead.
+    fn check_updated_unique(&self, indices: &[usize], rows: &[Vec<Value>]) -> Result<(), DbError> {
+        if let ([i], [row]) = (indices, rows) {
+            return self.validate_row_except(row, Some(*i));
+        }
+        let mut keys = self.unique_keys_except(&indices.iter().copied().collect());
+        for row in rows {
+            for key in &mut keys {
+                key.claim(self, row)?;
             }
         }
+        Ok(())
+    }
 
-        for (&i, new_row) in indices.iter().zip(updated_rows.into_iter()) {
-            self.rows[i] = new_row;
-            self.update_indexes_for_row(i);
+    fn prepare_update(&self, i: usize, updates: &[Option<Value>]) -> Result<Vec<Value>, DbError> {
+        let mut new_row = self.rows[i].clone();
+        for (j, update) in updates.iter().enumerate() {
+            if let Some(val) = update {
+                new_row[j] = val.clone();
+            }
+        }
+        self.stamp_on_update(&mut new_row, updates);
+        self.fill_generated(&mut new_row, |j| matches!(updates.get(j), Some(Some(_))))?;
+        self.fit_to_columns(&mut new_row)?;
+        self.validate_values(&new_row)?;
+        Ok(new_row)
+    }
+
+    // Columns marked OnUpdateNow get the current time unless the update sets them explicitly
+    fn stamp_on_update(&self, row: &mut [Value], updates: &[Option<Value>]) {
+        for (j, col) in self.columns.iter().enumerate() {
+            let explicitly_set = matches!(updates.get(j), Some(Some(_)));
+            if col.options.contains(&Options::OnUpdateNow) && !explicitly_set {
+                if let Some(now) = Value::now_for(&col.datatype) {
+                    row[j] = now;
+                }
+            }
         }
+    }
 
+    // Numbers take their column's numeric type, decimals are stored at their column's
+    // scale, so 2.5 in a DECIMAL(5, 2) column reads back as 2.50, TRUNCATE columns cut
+    // long strings to their length, and Set members are kept once each in the column's
+    // order. A number that would overflow fails here; anything else that doesn't fit is
+    // left for validation to reject.
+    fn fit_to_columns(&self, row: &mut [Value]) -> Result<(), DbError> {
+        for (value, col) in row.iter_mut().zip(&self.columns) {
+            if let Some(coerced) = value.coerce_numeric(&col.datatype)? {
+                *value = coerced;
+            }
+            match (&mut *value, &col.datatype) {
+                (Value::Decimal(d), DataType::Decimal(_, scale)) => {
+                    if let Some(rescaled) = d.rescale(*scale) {
+                        *d = rescaled;
+                    }
+                }
+                (Value::Varchar(s), DataType::Char(length) | DataType::Varchar(length))
+                    if col.options.contains(&Options::Truncate) =>
+                {
+                    if let Some((end, _)) = s.char_indices().nth(*length as usize) {
+                        s.truncate(end);
+                    }
+                }
+                (Value::Set(items), DataType::Set(allowed)) => {
+                    items.sort_by_key(|item| allowed.iter().position(|a| a == item));
+                    items.dedup();
+                }
+                _ => {}
+            }
+        }
         Ok(())
     }
 
-    pub fn delete_where(&mut self, expr: &FilterExpr) {
-        let predicate = expr.to_predicate(self);
-        let _col_index = self
-            .columns
-            .iter()
-            .position(|c| c.name.as_str() == expr.column().as_str())
-            .unwrap();
+    pub fn delete_where<P: RowPredicate>(&mut self, predicate: P) -> Result<usize, DbError> {
+        let to_remove = self.matching_positions(&predicate)?;
+        Ok(self.delete_rows(&to_remove))
+    }
 
-        if let Some(index) = self.indexes.get(expr.column().as_str()) {
-            if let Some(row_indices) = index.get(&expr.value()) {
-                let to_remove: HashMap<usize, ()> = row_indices
-                    .iter()
-                    .filter(|&&i| predicate(&self.rows[i]))
-                    .map(|&i| (i, ()))
-                    .collect();
+    pub fn delete_where_returning<P: RowPredicate>(&mut self, predicate: P) -> Result<Vec<Vec<Value>>, DbError> {
+        let to_remove = self.matching_positions(&predicate)?;
+        let removed = to_remove.iter().map(|&i| self.rows[i].clone()).collect();
+        self.delete_rows(&to_remove);
+        Ok(removed)
+    }
 
-                self.rows = self
-                    .rows
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, row)| {
-                        if to_remove.contains_key(&i) {
-                            None
-                        } else {
-                            Some(row.clone())
-                        }
-                    })
-                    .collect();
-
-                self.rebuild_all_indexes(); // simple for now
-            }
+    pub(crate) fn delete_rows(&mut self, indices: &[usize]) -> usize {
+        if indices.is_empty() {
+            return 0;
+        }
+
+        let mut positions = indices.to_vec();
+        positions.sort_unstable();
+        positions.dedup();
+        self.unindex_rows(&positions);
+        self.remove_rows(&positions)
+    }
+
+    // Takes the rows at `positions`, in order, out of every index and moves the rows after
+    // them down to where they'll be once the rows are gone
+    fn unindex_rows(&mut self, positions: &[usize]) {
+        let shift = |row: usize| row - positions.partition_point(|&p| p < row);
+        for (col_name, index) in &mut self.indexes {
+            let Some(col_idx) = self.columns.iter().position(|c| &c.name == col_name) else {
+                continue;
+            };
+            let collation = self.columns[col_idx].collation();
+            let removed: Vec<(Value, usize)> = positions
+                .iter()
+                .map(|&i| (collation.key(&self.rows[i][col_idx]).into_owned(), i))
+                .collect();
+            index.remove_rows(&removed, shift);
         }
     }
 
+    // Drops rows without touching indexes; callers update them
+    pub(crate) fn remove_rows(&mut self, indices: &[usize]) -> usize {
+        let to_remove: HashSet<usize> = indices.iter().copied().collect();
+        self.log_change(|table| {
+            let mut positions: Vec<usize> = to_remove.iter().copied().collect();
+            positions.sort_unstable();
+            let rows = positions.iter().map(|&i| table.rows[i].clone()).collect();
+            UndoEntry::Row(RowChange::Delete { positions, rows })
+        });
+        let mut i = 0;
+        self.rows.retain(|_| {
+            let keep = !to_remove.contains(&i);
+            i += 1;
+            keep
+        });
+        to_remove.len()
+    }
+
     pub fn print_table(&self) {
         println!("\nTable: {}", self.name);
         for col in &self.columns {
@@ -183,105 +488,129 @@ impl Table {
 
         for row in &self.rows {
             for val in row {
-                print!("| {:<15} ", val.to_display_string());
+                print!("| {:<15} ", val);
             }
             println!("|");
         }
     }
 
-    pub fn save_to_file(&self, db_name: &str) -> Result<(), String> {
-        let dir_path = Path::new("db");
+    pub fn save_to_file(&self, db_name: &str) -> Result<(), DbError> {
+        self.save_to_dir(Path::new(DEFAULT_DATA_DIR), db_name)
+    }
+
+    pub fn save_to_dir(&self, dir_path: &Path, db_name: &str) -> Result<(), DbError> {
+        self.save_to_dir_synced(dir_path, db_name, SyncMode::default())
+    }
+
+    // The data files, the schema and the removal of an older save's files are made through
+    // one journal, so a crash leaves the table as last saved or as saved now
+    pub fn save_to_dir_synced(&self, dir_path: &Path, db_name: &str, sync: SyncMode) -> Result<(), DbError> {
+        self.save_through_journal(dir_path, db_name, sync, |table, journal| {
+            table.stage_save(dir_path, db_name, sync, journal)
+        })
+    }
+
+    pub(crate) fn save_through_journal(
+        &self,
+        dir_path: &Path,
+        db_name: &str,
+        sync: SyncMode,
+        stage: impl FnOnce(&Table, &mut Journal) -> Result<(), DbError>,
+    ) -> Result<(), DbError> {
         if !dir_path.exists() {
             fs::create_dir_all(dir_path)
-                .map_err(|e| format!("Failed to create db directory: {}", e))?;
+                .map_err(|e| DbError::IoError(format!("Failed to create db directory: {}", e)))?;
         }
+        let mut journal = Journal::default();
+        if let Err(e) = stage(self, &mut journal) {
+            journal.abandon(dir_path);
+            return Err(e);
+        }
+        journal.commit(&journal_path(&page_path(dir_path, db_name, &self.name)), sync)
+    }
 
-        let file_path = dir_path.join(format!("{}.{}.csv", db_name, self.name));
-
-        let file = File::create(&file_path).map_err(|e| format!("Failed to create file: {}", e))?;
-        let mut writer = BufWriter::new(file);
-
-        // Write header
-        let header = self
-            .columns
-            .iter()
-            .map(|c| c.name.clone())
-            .collect::<Vec<_>>()
-            .join(",");
-        writeln!(writer, "{}", header).map_err(|e| e.to_string())?;
+    // Writes the table's files beside the ones they replace and adds to `journal` what puts
+    // them in place
+    pub(crate) fn stage_save(
+        &self,
+        dir_path: &Path,
+        db_name: &str,
+        sync: SyncMode,
+        journal: &mut Journal,
+    ) -> Result<(), DbError> {
+        let shards = self.shard_count();
+        if shards > 1 {
+            return self.stage_sharded(dir_path, db_name, shards, sync, journal);
+        }
 
-        // Write rows
-        for row in &self.rows {
-            let line = row
-                .iter()
-                .map(|v| match v {
-                    Value::Set(items, _) => {
-                        let inner = items.join(",");
-                        format!("\"{{{}}}\"", inner)
-                    }
-                    Value::Enum(val, _) => format!("\"{}\"", val),
-                    Value::Varchar(s) | Value::Text(s) => format!("\"{}\"", s),
-                    Value::Char(c) => format!("\"{}\"", c),
-                    Value::Boolean(b) => format!("\"{}\"", b),
-                    Value::Int(i) => format!("\"{}\"", i),
-                    Value::BigInt(i) => format!("\"{}\"", i),
-                    Value::Float(f) => format!("\"{}\"", f),
-                    Value::Double(f) => format!("\"{}\"", f),
-                    Value::Date(d) => format!("\"{}\"", d),
-                    Value::Time(t) => format!("\"{}\"", t),
-                    Value::DateTime(dt) => format!("\"{}\"", dt),
-                    Value::Null => "\"NULL\"".to_string(),
-                })
-                .collect::<Vec<_>>()
-                .join(",");
-            writeln!(writer, "{}", line).map_err(|e| e.to_string())?;
+        let pages = page_path(dir_path, db_name, &self.name);
+        let target = compress::path_for(&pages, self.compression);
+        if self.compression.is_some() {
+            stage_compressed_rows(&target, &self.rows, sync, journal)?;
+        } else {
+            stage_rows(&target, &self.rows, journal)?;
         }
+        TableSchema::write(self, &[], &schema_path(dir_path, db_name, &self.name), sync, journal)?;
 
-        Ok(())
+        // Shards, CSV files and page files of another compression from older saves
+        let mut stale = shard_paths(dir_path, db_name, &self.name);
+        stale.extend(compress::variants(&dir_path.join(format!("{}.{}.csv", db_name, self.name))));
+        stale.extend(compress::variants(&pages).into_iter().filter(|p| *p != target));
+        stage_stale_files(&stale, journal)
+    }
+
+    // Columns and primary key come from the `.schema` file save_to_file writes next to the pages
+    pub fn load_from_file(db_name: &str, name: &str) -> Result<Self, DbError> {
+        let dir_path = Path::new(DEFAULT_DATA_DIR);
+        let schema = TableSchema::read(&schema_path(dir_path, db_name, name))?;
+        Table::load_from_dir(dir_path, db_name, name, schema.columns, schema.primary_key)
     }
 
-    pub fn load_from_file(
-        dir: &str,
+    pub fn load_from_dir(
+        dir_path: &Path,
+        db_name: &str,
         name: &str,
         columns: Vec<Column>,
         primary_key: Option<Vec<String>>,
-    ) -> Result<Self, String> {
-        let file_path = format!("db/{}.{}.csv", dir, name);
-        let file = File::open(&file_path).map_err(|e| format!("Failed to open file: {}", e))?;
-
-        let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
-
-        let mut rows = Vec::new();
-
-        for (line_num, result) in rdr.records().enumerate() {
-            let record = result.map_err(|e| format!("CSV parse error: {}", e))?;
-
-            if record.len() != columns.len() {
-                return Err(format!(
-                    "Row {} has wrong number of fields: expected {}, got {}",
-                    line_num + 1,
-                    columns.len(),
-                    record.len()
-                ));
+    ) -> Result<Self, DbError> {
+        // A save a crash cut short is finished first
+        let page_file = page_path(dir_path, db_name, name);
+        journal::recover(&journal_path(&page_file))?;
+
+        // Tables saved before the page format, or too large to keep unsharded, are in CSV.
+        // Any of the files may be compressed, as its extension tells. The sidecar says which
+        // the rows are in, since a page file can stay behind to hold disk indexes.
+        let plain = dir_path.join(format!("{}.{}.csv", db_name, name));
+        let file_path = compress::find(&plain).unwrap_or(plain);
+        let shards = shard_paths(dir_path, db_name, name);
+        let schema_file = schema_path(dir_path, db_name, name);
+        let saved = if schema_file.exists() { Some(TableSchema::read(&schema_file)?) } else { None };
+        let pages = match &saved {
+            Some(saved) if !saved.checksums.is_empty() => None,
+            Some(saved) => Some(compress::path_for(&page_file, saved.compression))
+                .filter(|path| path.exists())
+                .or_else(|| compress::find(&page_file)),
+            None => compress::find(&page_file),
+        };
+        let rows = if let Some(pages) = pages {
+            read_page_rows(&pages, &columns)?
+        } else {
+            // CSV files carry no checksums of their own; the sidecar has them
+            if let Some(saved) = &saved {
+                saved.verify_files(dir_path)?;
             }
-
-            let mut row = Vec::new();
-            for (i, col) in columns.iter().enumerate() {
-                let raw = &record[i];
-                let value = Value::from_str(raw, &col.datatype).map_err(|e| {
-                    format!(
-                        "Error parsing value '{}' for column '{}': {}",
-                        raw, col.name, e
-                    )
-                })?;
-                row.push(value);
+            if shards.is_empty() || file_path.exists() {
+                read_csv_rows(&file_path, &columns)?
+            } else {
+                read_shards(&shards, &columns)?
             }
-
-            rows.push(row);
-        }
+        };
 
         let mut table = Table::new(name, columns.clone(), primary_key.clone());
         table.rows = rows;
+        if let Some(saved) = saved {
+            saved.fill(&mut table);
+        }
         let column_names: Vec<String> = table.columns.iter().map(|c| c.name.clone()).collect();
         for col in column_names {
             let _ = table.create_index(&col, false);
@@ -290,108 +619,210 @@ impl Table {
         Ok(table)
     }
 
-    fn value_matches_type(val: &Value, dtype: &DataType) -> bool {
+    pub(crate) fn value_matches_type(val: &Value, dtype: &DataType) -> bool {
         match (val, dtype) {
-            (Value::Char(_), DataType::Char) => true,
-            (Value::Varchar(_), DataType::Varchar) => true,
+            (Value::Char(_), DataType::Char(_)) => true,
+            (Value::Varchar(_), DataType::Char(_) | DataType::Varchar(_)) => true,
             (Value::Text(_), DataType::Text) => true,
-            (Value::Enum(_, _), DataType::Enum) => true,
-            (Value::Set(_, _), DataType::Set) => true,
+            (Value::Enum(_), DataType::Enum(_)) => true,
+            (Value::Set(_), DataType::Set(_)) => true,
             (Value::Boolean(_), DataType::Boolean) => true,
-            (Value::Int(_), DataType::Int) => true,
-            (Value::BigInt(_), DataType::BigInt) => true,
-            (Value::Float(_), DataType::Float) => true,
+            // Integers of either width go into any numeric column; see Value::coerce_numeric
+            (Value::Int(_) | Value::BigInt(_), DataType::Int | DataType::BigInt | DataType::Float | DataType::Double) => {
+                true
+            }
+            (Value::Float(_), DataType::Float | DataType::Double) => true,
             (Value::Double(_), DataType::Double) => true,
             (Value::Date(_), DataType::Date) => true,
             (Value::Time(_), DataType::Time) => true,
             (Value::DateTime(_), DataType::DateTime) => true,
+            (Value::Point(_, _), DataType::Point) => true,
+            (Value::Decimal(_), DataType::Decimal(_, _)) => true,
             (Value::Null, _) => true, // Allow null everywhere for now
             _ => false,
         }
     }
-    pub fn alter_add_column(&mut self, new_column: Column) -> Result<(), String> {
+    pub fn alter_add_column(&mut self, new_column: Column) -> Result<(), DbError> {
         if self.columns.iter().any(|col| col.name == new_column.name) {
-            return Err(format!(
+            return Err(DbError::SchemaError(format!(
                 "Column '{}' already exists in table '{}'",
                 new_column.name, self.name
-            ));
+            )));
         }
 
         new_column.validate()?;
 
-        let default_val = new_column.options.iter().find_map(|opt| {
-            if let Options::Default(ref val) = opt {
-                Some(val.clone())
-            } else {
-                None
-            }
-        });
+        // Existing rows get the default as worked out now
+        let default_val = new_column
+            .options
+            .iter()
+            .find_map(|opt| match opt {
+                Options::Default(expr) => Some(expr.evaluate(&new_column.datatype)),
+                _ => None,
+            })
+            .transpose()?;
 
         let default = if new_column.options.contains(&Options::NotNull) {
             default_val.ok_or_else(|| {
-                format!(
+                DbError::SchemaError(format!(
                     "Cannot add NOT NULL column '{}' without a default value",
                     new_column.name
-                )
+                ))
             })?
         } else {
             default_val.unwrap_or(Value::Null)
         };
 
+        // Its CHECKs can name the column itself, so they are resolved with it in place
+        self.columns.push(new_column);
+        if let Err(e) = self.validate_checks(&self.columns[self.columns.len() - 1]) {
+            self.columns.pop();
+            return Err(e);
+        }
         for row in &mut self.rows {
             row.push(default.clone());
         }
+        self.log_change(|_| UndoEntry::AddColumn);
 
-        self.columns.push(new_column);
+        Ok(())
+    }
 
+    pub fn create_index(&mut self, column_name: &str, use_btree: bool) -> Result<(), DbError> {
+        let replaced = self.indexes.get(column_name).map(IndexType::empty_like);
+        self.build_index(column_name, use_btree)?;
+        self.log_change(|_| UndoEntry::CreateIndex {
+            column: column_name.to_string(),
+            replaced,
+        });
         Ok(())
     }
 
-    pub fn create_index(&mut self, column_name: &str, use_btree: bool) -> Result<(), String> {
+    // Builds the index without logging it, e.g. when rebuilding existing indexes
+    fn build_index(&mut self, column_name: &str, use_btree: bool) -> Result<(), DbError> {
         let col_index = self
             .columns
             .iter()
             .position(|c| c.name == column_name)
-            .ok_or_else(|| format!("Column '{}' does not exist", column_name))?;
+            .ok_or_else(|| DbError::NotFound(format!("Column '{}' does not exist", column_name)))?;
 
+        // Collated columns are indexed by their collation keys
+        let collation = self.columns[col_index].collation();
         if use_btree {
             let mut index_map: BTreeMap<Value, Vec<usize>> = BTreeMap::new();
             for (i, row) in self.rows.iter().enumerate() {
-                let key = row[col_index].clone();
+                let key = collation.key(&row[col_index]).into_owned();
                 index_map.entry(key).or_default().push(i);
             }
-            self.indexes
-                .insert(column_name.to_string(), IndexType::BTree(index_map));
+            self.set_index(column_name, IndexType::BTree(index_map));
         } else {
             let mut index_map: HashMap<Value, Vec<usize>> = HashMap::new();
             for (i, row) in self.rows.iter().enumerate() {
-                let key = row[col_index].clone();
+                let key = collation.key(&row[col_index]).into_owned();
                 index_map.entry(key).or_default().push(i);
             }
-            self.indexes
-                .insert(column_name.to_string(), IndexType::Hash(index_map));
+            self.set_index(column_name, IndexType::Hash(index_map));
         }
 
         Ok(())
     }
 
-    fn update_indexes_for_row(&mut self, row_idx: usize) {
+    // Puts `index` on the column. A disk index it takes the place of has its tree freed.
+    pub(crate) fn set_index(&mut self, column_name: &str, index: IndexType) {
+        let same_tree = |old: &DiskIndex| match &index {
+            IndexType::Disk(new) => new.path() == old.path() && new.id() == old.id(),
+            _ => false,
+        };
+        if let Some(IndexType::Disk(old)) = self.indexes.get(column_name) {
+            if !old.is_detached() && !same_tree(old) {
+                let _ = old.drop_tree();
+            }
+        }
+        self.indexes.insert(column_name.to_string(), index);
+    }
+
+    pub(crate) fn remove_index(&mut self, column_name: &str) -> Option<IndexType> {
+        let index = self.indexes.remove(column_name);
+        if let Some(IndexType::Disk(disk)) = &index {
+            if !disk.is_detached() {
+                let _ = disk.drop_tree();
+            }
+        }
+        index
+    }
+
+    pub(crate) fn update_indexes_for_row(&mut self, row_idx: usize) {
         for (col_name, index_map) in &mut self.indexes {
             if let Some(col_idx) = self.columns.iter().position(|c| &c.name == col_name) {
-                let value = self.rows[row_idx][col_idx].clone();
+                let value = self.columns[col_idx].collation().key(&self.rows[row_idx][col_idx]).into_owned();
                 match index_map {
                     IndexType::Hash(map) => map.entry(value).or_default().push(row_idx),
                     IndexType::BTree(map) => map.entry(value).or_default().push(row_idx),
+                    IndexType::Grid(grid) => grid.insert(&value, row_idx),
+                    IndexType::Disk(disk) => disk.insert(&value, row_idx),
                 }
             }
         }
     }
 
-    fn rebuild_all_indexes(&mut self) {
-        let column_names: Vec<String> = self.indexes.keys().cloned().collect();
+    pub(crate) fn remove_row_from_indexes(&mut self, row_idx: usize) {
+        for (col_name, index_map) in &mut self.indexes {
+            if let Some(col_idx) = self.columns.iter().position(|c| &c.name == col_name) {
+                let key = self.columns[col_idx].collation().key(&self.rows[row_idx][col_idx]);
+                let value = key.as_ref();
+                match index_map {
+                    IndexType::Grid(grid) => {
+                        grid.remove(value, row_idx);
+                        continue;
+                    }
+                    IndexType::Disk(disk) => {
+                        disk.remove(value, row_idx);
+                        continue;
+                    }
+                    _ => {}
+                }
+                let emptied = match index_map {
+                    IndexType::Hash(map) => map.get_mut(value).map(|idxs| {
+                        idxs.retain(|&i| i != row_idx);
+                        idxs.is_empty()
+                    }),
+                    IndexType::BTree(map) => map.get_mut(value).map(|idxs| {
+                        idxs.retain(|&i| i != row_idx);
+                        idxs.is_empty()
+                    }),
+                    IndexType::Grid(_) | IndexType::Disk(_) => None,
+                };
+                if emptied == Some(true) {
+                    match index_map {
+                        IndexType::Hash(map) => map.remove(value),
+                        IndexType::BTree(map) => map.remove(value),
+                        IndexType::Grid(_) | IndexType::Disk(_) => None,
+                    };
+                }
+            }
+        }
+    }
+
+    pub(crate) fn rebuild_all_indexes(&mut self) {
+        let specs: Vec<(String, IndexType)> = self
+            .indexes
+            .iter()
+            .map(|(name, index)| (name.clone(), index.empty_like()))
+            .collect();
         self.indexes.clear();
-        for name in column_names {
-            let _ = self.create_index(&name, false);
+        for (name, kind) in specs {
+            let _ = self.create_index_like(&name, &kind);
+        }
+    }
+
+    // Builds an index on `column_name` of the same kind as `kind`
+    pub(crate) fn create_index_like(&mut self, column_name: &str, kind: &IndexType) -> Result<(), DbError> {
+        match kind {
+            IndexType::Hash(_) => self.build_index(column_name, false),
+            IndexType::BTree(_) => self.build_index(column_name, true),
+            IndexType::Grid(grid) => Ok(self.build_grid_index(column_name, grid.cell_size)?),
+            // A copy of a table indexes in memory what the original keeps on disk
+            IndexType::Disk(disk) if disk.is_detached() => self.build_index(column_name, true),
+            IndexType::Disk(disk) => self.build_disk_index(column_name, disk.path(), Some(disk.id())),
         }
     }
 
@@ -422,7 +853,10 @@ impl Table {
             rows,
             primary_key: self.primary_key.clone(),
             indexes: HashMap::new(),
+            sequences: HashMap::new(),
             transaction_backup: None,
+            stats: None,
+            compression: None,
         }
     }
 
@@ -430,113 +864,196 @@ impl Table {
         &'a self,
         other: &'a Table,
         on: (&str, &str),
-    ) -> Result<Vec<(Vec<&'a Value>, Vec<Option<&'a Value>>)>, String> {
-        let self_idx = self
-            .columns
-            .iter()
-            .position(|c| c.name == on.0)
-            .ok_or_else(|| format!("Column '{}' not found in '{}'", on.0, self.name))?;
-        let other_idx = other
-            .columns
-            .iter()
-            .position(|c| c.name == on.1)
-            .ok_or_else(|| format!("Column '{}' not found in '{}'", on.1, other.name))?;
+    ) -> Result<Vec<LeftJoinRow<'a>>, DbError> {
+        self.inner_join_filtered(other, on, None, None)
+    }
 
-        let mut result = vec![];
+    // Each side's filter runs through that table's indexes before any rows are paired
+    pub fn inner_join_filtered<'a>(
+        &'a self,
+        other: &'a Table,
+        on: (&str, &str),
+        left_filter: Option<&FilterExpr>,
+        right_filter: Option<&FilterExpr>,
+    ) -> Result<Vec<LeftJoinRow<'a>>, DbError> {
+        Ok(self.inner_join_limited(other, on, left_filter, right_filter, Limit::default())?.rows)
+    }
 
-        for left_row in &self.rows {
-            let left_val = &left_row[self_idx];
-            for right_row in &other.rows {
-                if &right_row[other_idx] == left_val {
-                    result.push((
-                        left_row.iter().collect(),
-                        right_row.iter().map(Some).collect(),
-                    ));
-                }
+    // Pairs outside the window are counted but never built
+    pub fn inner_join_limited<'a>(
+        &'a self,
+        other: &'a Table,
+        on: (&str, &str),
+        left_filter: Option<&FilterExpr>,
+        right_filter: Option<&FilterExpr>,
+        limit: Limit,
+    ) -> Result<Limited<LeftJoinRow<'a>>, DbError> {
+        let mut window = Limited { rows: vec![], offset: limit.offset, total: 0 };
+        let kind = (JoinKind::Inner, JoinStrategy::NestedLoop);
+        self.visit_join(other, on, left_filter, right_filter, kind, |left, right| {
+            if limit.contains(window.total) {
+                window.rows.push(left_pair(left, right, other));
             }
-        }
-
-        Ok(result)
+            window.total += 1;
+        })?;
+        Ok(window)
     }
 
     pub fn left_join<'a>(
         &'a self,
         other: &'a Table,
         on: (&str, &str),
-    ) -> Result<Vec<(Vec<&'a Value>, Vec<Option<&'a Value>>)>, String> {
-        let self_idx = self
-            .columns
-            .iter()
-            .position(|c| c.name == on.0)
-            .ok_or_else(|| format!("Column '{}' not found in '{}'", on.0, self.name))?;
-        let other_idx = other
-            .columns
-            .iter()
-            .position(|c| c.name == on.1)
-            .ok_or_else(|| format!("Column '{}' not found in '{}'", on.1, other.name))?;
-
-        let mut result = vec![];
+    ) -> Result<Vec<LeftJoinRow<'a>>, DbError> {
+        self.left_join_filtered(other, on, None, None)
+    }
 
-        for left_row in &self.rows {
-            let left_val = &left_row[self_idx];
-            let mut matched = false;
+    // The right filter narrows the candidates to match, so left rows it removes
+    // every partner from still come back padded with NULLs
+    pub fn left_join_filtered<'a>(
+        &'a self,
+        other: &'a Table,
+        on: (&str, &str),
+        left_filter: Option<&FilterExpr>,
+        right_filter: Option<&FilterExpr>,
+    ) -> Result<Vec<LeftJoinRow<'a>>, DbError> {
+        Ok(self.left_join_limited(other, on, left_filter, right_filter, Limit::default())?.rows)
+    }
 
-            for right_row in &other.rows {
-                if &right_row[other_idx] == left_val {
-                    result.push((
-                        left_row.iter().collect(),
-                        right_row.iter().map(Some).collect(),
-                    ));
-                    matched = true;
-                }
+    pub fn left_join_limited<'a>(
+        &'a self,
+        other: &'a Table,
+        on: (&str, &str),
+        left_filter: Option<&FilterExpr>,
+        right_filter: Option<&FilterExpr>,
+        limit: Limit,
+    ) -> Result<Limited<LeftJoinRow<'a>>, DbError> {
+        let mut window = Limited { rows: vec![], offset: limit.offset, total: 0 };
+        let kind = (JoinKind::Left, JoinStrategy::NestedLoop);
+        self.visit_join(other, on, left_filter, right_filter, kind, |left, right| {
+            if limit.contains(window.total) {
+                window.rows.push(left_pair(left, right, other));
             }
+            window.total += 1;
+        })?;
+        Ok(window)
+    }
 
-            if !matched {
-                result.push((left_row.iter().collect(), vec![None; other.columns.len()]));
-            }
-        }
+    pub fn right_join<'a>(
+        &'a self,
+        other: &'a Table,
+        on: (&str, &str),
+    ) -> Result<Vec<RightJoinRow<'a>>, DbError> {
+        self.right_join_filtered(other, on, None, None)
+    }
 
-        Ok(result)
+    pub fn right_join_filtered<'a>(
+        &'a self,
+        other: &'a Table,
+        on: (&str, &str),
+        left_filter: Option<&FilterExpr>,
+        right_filter: Option<&FilterExpr>,
+    ) -> Result<Vec<RightJoinRow<'a>>, DbError> {
+        Ok(self.right_join_limited(other, on, left_filter, right_filter, Limit::default())?.rows)
+    }
+
+    pub fn right_join_limited<'a>(
+        &'a self,
+        other: &'a Table,
+        on: (&str, &str),
+        left_filter: Option<&FilterExpr>,
+        right_filter: Option<&FilterExpr>,
+        limit: Limit,
+    ) -> Result<Limited<RightJoinRow<'a>>, DbError> {
+        let mut window = Limited { rows: vec![], offset: limit.offset, total: 0 };
+        let kind = (JoinKind::Right, JoinStrategy::NestedLoop);
+        self.visit_join(other, on, left_filter, right_filter, kind, |left, right| {
+            if limit.contains(window.total) {
+                window.rows.push(right_pair(left, right, self));
+            }
+            window.total += 1;
+        })?;
+        Ok(window)
     }
 
-    pub fn right_join<'a>(
+    // Calls `visit` for each joined pair in result order; None is the side an outer join
+    // pads with NULLs
+    pub(crate) fn visit_join<'a, F>(
         &'a self,
         other: &'a Table,
         on: (&str, &str),
-    ) -> Result<Vec<(Vec<Option<&'a Value>>, Vec<&'a Value>)>, String> {
-        let self_idx = self
-            .columns
-            .iter()
-            .position(|c| c.name == on.0)
-            .ok_or_else(|| format!("Column '{}' not found in '{}'", on.0, self.name))?;
-        let other_idx = other
-            .columns
-            .iter()
-            .position(|c| c.name == on.1)
-            .ok_or_else(|| format!("Column '{}' not found in '{}'", on.1, other.name))?;
+        left_filter: Option<&FilterExpr>,
+        right_filter: Option<&FilterExpr>,
+        (kind, strategy): (JoinKind, JoinStrategy),
+        mut visit: F,
+    ) -> Result<(), DbError>
+    where
+        F: FnMut(Option<&'a Vec<Value>>, Option<&'a Vec<Value>>),
+    {
+        let self_idx = self.resolve_column(on.0)?;
+        let other_idx = other.resolve_column(on.1)?;
 
-        let mut result = vec![];
+        let left_rows = self.filtered_rows(left_filter)?;
+        let right_rows = other.filtered_rows(right_filter)?;
 
-        for right_row in &other.rows {
-            let right_val = &right_row[other_idx];
-            let mut matched = false;
+        let mut pairs = 0;
+        let mut visit = |left, right| {
+            pairs += 1;
+            visit(left, right)
+        };
+        profiler::stage(QueryStage::Join, || {
+            Self::pair_rows(left_rows, right_rows, (self_idx, other_idx), (kind, strategy), &mut visit)
+        });
+        returned(pairs);
+        Ok(())
+    }
 
-            for left_row in &self.rows {
-                if &left_row[self_idx] == right_val {
-                    result.push((
-                        left_row.iter().map(Some).collect(),
-                        right_row.iter().collect(),
-                    ));
-                    matched = true;
+    fn pair_rows<'a>(
+        left_rows: Vec<&'a Vec<Value>>,
+        right_rows: Vec<&'a Vec<Value>>,
+        (self_idx, other_idx): (usize, usize),
+        (kind, strategy): (JoinKind, JoinStrategy),
+        mut visit: impl FnMut(Option<&'a Vec<Value>>, Option<&'a Vec<Value>>),
+    ) {
+        if strategy == JoinStrategy::SortMerge {
+            merge_join(left_rows, right_rows, (self_idx, other_idx), kind, visit);
+        } else if kind == JoinKind::Right {
+            for right_row in right_rows {
+                let mut matched = false;
+                for &left_row in &left_rows {
+                    if left_row[self_idx] == right_row[other_idx] {
+                        visit(Some(left_row), Some(right_row));
+                        matched = true;
+                    }
+                }
+                if !matched {
+                    visit(None, Some(right_row));
                 }
             }
-
-            if !matched {
-                result.push((vec![None; self.columns.len()], right_row.iter().collect()));
+        } else {
+            for left_row in left_rows {
+                let mut matched = false;
+                for &right_row in &right_rows {
+                    if right_row[other_idx] == left_row[self_idx] {
+                        visit(Some(left_row), Some(right_row));
+                        matched = true;
+                    }
+                }
+                if !matched && kind == JoinKind::Left {
+                    visit(Some(left_row), None);
+                }
             }
         }
+    }
 
-        Ok(result)
+    fn filtered_rows(&self, filter: Option<&FilterExpr>) -> Result<Vec<&Vec<Value>>, DbError> {
+        match filter {
+            Some(expr) => self.select_where_expr(expr),
+            None => {
+                let rows: Vec<&Vec<Value>> = self.live_rows().collect();
+                scanned(rows.len());
+                Ok(rows)
+            }
+        }
     }
 
     pub fn select_join_where<'a, F>(
@@ -544,7 +1061,7 @@ impl Table {
         other: &'a Table,
         on: (&str, &str),
         filter: F,
-    ) -> Result<Vec<(Vec<&'a Value>, Vec<&'a Value>)>, String>
+    ) -> Result<Vec<JoinRow<'a>>, DbError>
     where
         F: Fn(&[&Value], &[&Value]) -> bool,
     {
@@ -571,32 +1088,25 @@ impl Table {
         &'a self,
         other: &'a Table,
         on: &[(&str, &str)],
-    ) -> Result<Vec<(Vec<&'a Value>, Vec<&'a Value>)>, String> {
+    ) -> Result<Vec<JoinRow<'a>>, DbError> {
         let self_indices: Vec<_> = on
             .iter()
             .map(|(left, _)| {
-                self.columns
-                    .iter()
-                    .position(|c| &c.name == left)
-                    .ok_or_else(|| format!("Column '{}' not found in {}", left, self.name))
+                self.resolve_column(left)
             })
             .collect::<Result<_, _>>()?;
 
         let other_indices: Vec<_> = on
             .iter()
             .map(|(_, right)| {
-                other
-                    .columns
-                    .iter()
-                    .position(|c| &c.name == right)
-                    .ok_or_else(|| format!("Column '{}' not found in {}", right, other.name))
+                other.resolve_column(right)
             })
             .collect::<Result<_, _>>()?;
 
         let mut results = vec![];
 
-        for left_row in &self.rows {
-            for right_row in &other.rows {
+        for left_row in self.live_rows() {
+            for right_row in other.live_rows() {
                 let matches = self_indices
                     .iter()
                     .zip(&other_indices)
@@ -657,15 +1167,18 @@ impl Table {
             });
         }
 
-        let rows = results
-            .into_iter()
-            .map(|(l, r)| {
-                let mut merged = vec![];
-                merged.extend(l.into_iter().cloned());
-                merged.extend(r.into_iter().cloned());
-                merged
-            })
-            .collect();
+        let rows: Vec<Vec<Value>> = profiler::stage(QueryStage::Materialize, || {
+            results
+                .into_iter()
+                .map(|(l, r)| {
+                    let mut merged = vec![];
+                    merged.extend(l.into_iter().cloned());
+                    merged.extend(r.into_iter().cloned());
+                    merged
+                })
+                .collect()
+        });
+        returned(rows.len());
 
         Table {
             name: name.to_string(),
@@ -673,7 +1186,10 @@ impl Table {
             rows,
             primary_key: None,
             indexes: HashMap::new(),
+            sequences: HashMap::new(),
             transaction_backup: None,
+            stats: None,
+            compression: None,
         }
     }
 
@@ -681,34 +1197,27 @@ impl Table {
         &'a self,
         other: &'a Table,
         on: &[(&str, &str)],
-    ) -> Result<Vec<(Vec<&'a Value>, Vec<Option<&'a Value>>)>, String> {
+    ) -> Result<Vec<LeftJoinRow<'a>>, DbError> {
         let self_indices = on
             .iter()
             .map(|(l, _)| {
-                self.columns
-                    .iter()
-                    .position(|c| &c.name == l)
-                    .ok_or_else(|| format!("Column '{}' not in {}", l, self.name))
+                self.resolve_column(l)
             })
             .collect::<Result<Vec<_>, _>>()?;
 
         let other_indices = on
             .iter()
             .map(|(_, r)| {
-                other
-                    .columns
-                    .iter()
-                    .position(|c| &c.name == r)
-                    .ok_or_else(|| format!("Column '{}' not in {}", r, other.name))
+                other.resolve_column(r)
             })
             .collect::<Result<Vec<_>, _>>()?;
 
         let mut results = vec![];
 
-        for left_row in &self.rows {
+        for left_row in self.live_rows() {
             let mut matched = false;
 
-            for right_row in &other.rows {
+            for right_row in other.live_rows() {
                 let is_match = self_indices
                     .iter()
                     .zip(&other_indices)
@@ -735,34 +1244,27 @@ impl Table {
         &'a self,
         other: &'a Table,
         on: &[(&str, &str)],
-    ) -> Result<Vec<(Vec<Option<&'a Value>>, Vec<&'a Value>)>, String> {
+    ) -> Result<Vec<RightJoinRow<'a>>, DbError> {
         let self_indices = on
             .iter()
             .map(|(l, _)| {
-                self.columns
-                    .iter()
-                    .position(|c| &c.name == l)
-                    .ok_or_else(|| format!("Column '{}' not in {}", l, self.name))
+                self.resolve_column(l)
             })
             .collect::<Result<Vec<_>, _>>()?;
 
         let other_indices = on
             .iter()
             .map(|(_, r)| {
-                other
-                    .columns
-                    .iter()
-                    .position(|c| &c.name == r)
-                    .ok_or_else(|| format!("Column '{}' not in {}", r, other.name))
+                other.resolve_column(r)
             })
             .collect::<Result<Vec<_>, _>>()?;
 
         let mut results = vec![];
 
-        for right_row in &other.rows {
+        for right_row in other.live_rows() {
             let mut matched = false;
 
-            for left_row in &self.rows {
+            for left_row in self.live_rows() {
                 let is_match = self_indices
                     .iter()
                     .zip(&other_indices)
@@ -790,7 +1292,7 @@ impl Table {
         other: &'a Table,
         on: &[(&str, &str)],
         filter: F,
-    ) -> Result<Vec<(Vec<&'a Value>, Vec<&'a Value>)>, String>
+    ) -> Result<Vec<JoinRow<'a>>, DbError>
     where
         F: Fn(&[&Value], &[&Value]) -> bool,
     {
@@ -802,7 +1304,7 @@ impl Table {
         db_name: &str,
         view_name: &str,
         join_table: &Table,
-    ) -> Result<(), String> {
+    ) -> Result<(), DbError> {
         join_table.save_as_view(db_name, view_name)
     }
 
@@ -847,15 +1349,18 @@ impl Table {
             });
         }
 
-        let rows = results
-            .into_iter()
-            .map(|(l, r)| {
-                let mut merged = vec![];
-                merged.extend(l.into_iter().cloned());
-                merged.extend(r.into_iter().cloned());
-                merged
-            })
-            .collect();
+        let rows: Vec<Vec<Value>> = profiler::stage(QueryStage::Materialize, || {
+            results
+                .into_iter()
+                .map(|(l, r)| {
+                    let mut merged = vec![];
+                    merged.extend(l.into_iter().cloned());
+                    merged.extend(r.into_iter().cloned());
+                    merged
+                })
+                .collect()
+        });
+        returned(rows.len());
 
         Table {
             name: name.to_string(),
@@ -863,7 +1368,10 @@ impl Table {
             rows,
             primary_key: None,
             indexes: HashMap::new(),
+            sequences: HashMap::new(),
             transaction_backup: None,
+            stats: None,
+            compression: None,
         }
     }
 
@@ -873,7 +1381,7 @@ impl Table {
         right_alias: &str,
         view_name: &str,
         join_table: &Table,
-    ) -> Result<(), String> {
+    ) -> Result<(), DbError> {
         let view_name_combined = format!("{}.{}.{}", left_alias, right_alias, view_name);
         join_table.save_as_view(db_name, &view_name_combined)
     }
@@ -899,23 +1407,36 @@ impl Table {
         )
     }
 
-    pub fn rename_column(&mut self, old_name: &str, new_name: &str) -> Result<(), String> {
+    pub fn rename_column(&mut self, old_name: &str, new_name: &str) -> Result<(), DbError> {
         if self.columns.iter().any(|c| c.name == new_name) {
-            return Err(format!("Column '{}' already exists", new_name));
+            return Err(DbError::SchemaError(format!("Column '{}' already exists", new_name)));
         }
 
         let idx = self
             .columns
             .iter()
             .position(|c| c.name == old_name)
-            .ok_or_else(|| format!("Column '{}' not found", old_name))?;
+            .ok_or_else(|| DbError::NotFound(format!("Column '{}' not found", old_name)))?;
 
         self.columns[idx].name = new_name.to_string();
+        self.rename_references(old_name, new_name);
+        self.log_change(|_| UndoEntry::RenameColumn {
+            from: old_name.to_string(),
+            to: new_name.to_string(),
+        });
 
+        Ok(())
+    }
+
+    // Moves everything keyed by a column's name over to its new name
+    fn rename_references(&mut self, old_name: &str, new_name: &str) {
         // Update index if present
         if let Some(index) = self.indexes.remove(old_name) {
             self.indexes.insert(new_name.to_string(), index);
         }
+        if let Some(next) = self.sequences.remove(old_name) {
+            self.sequences.insert(new_name.to_string(), next);
+        }
 
         // Update primary key name if needed
         if let Some(pk) = &mut self.primary_key {
@@ -925,74 +1446,320 @@ impl Table {
                 }
             }
         }
-
-        Ok(())
     }
 
-    pub fn drop_column(&mut self, name: &str) -> Result<(), String> {
+    pub fn drop_column(&mut self, name: &str) -> Result<(), DbError> {
         let idx = self
             .columns
             .iter()
             .position(|c| c.name == name)
-            .ok_or_else(|| format!("Column '{}' not found", name))?;
+            .ok_or_else(|| DbError::NotFound(format!("Column '{}' not found", name)))?;
 
         // Disallow dropping primary key columns
         if let Some(pk) = &self.primary_key {
             if pk.contains(&name.to_string()) {
-                return Err(format!("Cannot drop primary key column '{}'", name));
+                return Err(DbError::SchemaError(format!("Cannot drop primary key column '{}'", name)));
             }
         }
 
-        self.columns.remove(idx);
-        for row in &mut self.rows {
-            row.remove(idx);
+        let column = self.columns.remove(idx);
+        let values: Vec<Value> = self.rows.iter_mut().map(|row| row.remove(idx)).collect();
+        let index = self.remove_index(name).map(|index| index.empty_like());
+        self.log_change(|_| UndoEntry::DropColumn {
+            position: idx,
+            column,
+            values,
+            index,
+        });
+
+        Ok(())
+    }
+
+    // Checks the option against every stored row before keeping it, so e.g. NOT NULL
+    // fails while the column holds NULLs. A UNIQUE column is also indexed. A column has
+    // at most one option of each kind, CHECKs aside.
+    pub fn add_option(&mut self, column: &str, option: Options) -> Result<(), DbError> {
+        let i = self.resolve_column(column)?;
+        let name = self.columns[i].name.clone();
+        if matches!(option, Options::Generated(_)) {
+            return Err(DbError::SchemaError(format!(
+                "Column '{}' cannot become generated; add a generated column instead",
+                name
+            )));
+        }
+        let taken = self.columns[i].options.iter().any(|existing| match existing {
+            Options::Check(_) => *existing == option,
+            _ => std::mem::discriminant(existing) == std::mem::discriminant(&option),
+        });
+        if taken {
+            return Err(DbError::SchemaError(format!("Column '{}' already has {:?}", name, option)));
+        }
+
+        let before = self.columns[i].options.clone();
+        self.columns[i].options.push(option.clone());
+        if let Err(e) = self.check_new_option(i, &option) {
+            self.columns[i].options = before;
+            return Err(e);
         }
+        self.log_change(|_| UndoEntry::SetOptions {
+            column: name.clone(),
+            options: before,
+        });
+
+        if matches!(option, Options::Collate(_)) {
+            self.rebuild_all_indexes();
+        }
+        if option == Options::Unique && !self.indexes.contains_key(&name) {
+            self.create_index(&name, false)?;
+        }
+        Ok(())
+    }
+
+    fn check_new_option(&self, i: usize, option: &Options) -> Result<(), DbError> {
+        self.columns[i].validate()?;
+        self.validate_checks(&self.columns[i])?;
+
+        if *option == Options::Unique {
+            let collation = self.columns[i].collation();
+            let mut seen = HashSet::new();
+            let mut duplicates = vec![];
+            for row in &self.rows {
+                let key = collation.key(&row[i]);
+                if !seen.insert(key.clone()) && !duplicates.contains(&key) {
+                    duplicates.push(key);
+                }
+            }
+            if !duplicates.is_empty() {
+                let values: Vec<String> = duplicates.iter().map(|v| v.to_display_string()).collect();
+                return Err(DbError::ConstraintViolation(format!(
+                    "Column '{}' has duplicate values: {}",
+                    self.columns[i].name,
+                    values.join(", ")
+                )));
+            }
+        }
+
+        self.rows.iter().try_for_each(|row| self.validate_values(row))
+    }
+
+    // Drops the column's option of the same kind as `option`, e.g. any Default for
+    // Options::Default(DefaultExpr::Literal(Value::Null)). CHECKs are matched by their text.
+    // Indexes stay.
+    pub fn drop_option(&mut self, column: &str, option: &Options) -> Result<Options, DbError> {
+        let i = self.resolve_column(column)?;
+        let position = self.columns[i]
+            .options
+            .iter()
+            .position(|existing| match option {
+                Options::Check(_) => existing == option,
+                _ => std::mem::discriminant(existing) == std::mem::discriminant(option),
+            })
+            .ok_or_else(|| {
+                DbError::NotFound(format!("Column '{}' has no {:?}", self.columns[i].name, option))
+            })?;
+
+        let before = self.columns[i].options.clone();
+        let dropped = self.columns[i].options.remove(position);
+        self.log_change(|table| UndoEntry::SetOptions {
+            column: table.columns[i].name.clone(),
+            options: before,
+        });
+        if matches!(dropped, Options::Collate(_)) {
+            self.rebuild_all_indexes();
+        }
+        Ok(dropped)
+    }
 
-        self.indexes.remove(name);
+    // Replaces the primary key, or drops it with None. The stored rows must already be
+    // unique and free of NULLs on the new key. Its columns are indexed as Table::new would.
+    pub fn set_primary_key(&mut self, key: Option<Vec<String>>) -> Result<(), DbError> {
+        let key = match key {
+            Some(names) => Some(self.check_primary_key(&names)?),
+            None => None,
+        };
+        let before = std::mem::replace(&mut self.primary_key, key.clone());
+        self.log_change(|_| UndoEntry::SetPrimaryKey(before));
 
+        for name in key.iter().flatten() {
+            if !self.indexes.contains_key(name) {
+                self.create_index(name, false)?;
+            }
+        }
         Ok(())
     }
 
-    pub fn begin_transaction(&mut self) -> Result<(), String> {
+    // The key's stored column names, once the rows are known to fit it
+    fn check_primary_key(&self, names: &[String]) -> Result<Vec<String>, DbError> {
+        if names.is_empty() {
+            return Err(DbError::SchemaError("A primary key needs at least one column".to_string()));
+        }
+        let mut positions = vec![];
+        for name in names {
+            let i = self.resolve_column(name)?;
+            if positions.contains(&i) {
+                return Err(DbError::SchemaError(format!("Column '{}' is listed twice in the primary key", name)));
+            }
+            positions.push(i);
+        }
+
+        let mut seen = HashSet::new();
+        for row in &self.rows {
+            if let Some(&i) = positions.iter().find(|&&i| matches!(row[i], Value::Null)) {
+                return Err(DbError::ConstraintViolation(format!(
+                    "Column '{}' holds NULLs, so it cannot be part of the primary key",
+                    self.columns[i].name
+                )));
+            }
+            let key: Vec<Value> = positions
+                .iter()
+                .map(|&i| self.columns[i].collation().key(&row[i]).into_owned())
+                .collect();
+            if !seen.insert(key) {
+                return Err(DbError::ConstraintViolation(
+                    "Primary key constraint violated: duplicate entry".to_string(),
+                ));
+            }
+        }
+        Ok(positions.iter().map(|&i| self.columns[i].name.clone()).collect())
+    }
+
+    // Only the rows a transaction touches are copied, into its undo log, along with any
+    // columns or indexes it adds, drops or renames. Writes that go around the table's
+    // methods, e.g. pushing onto `rows` directly, are not logged.
+    pub fn begin_transaction(&mut self) -> Result<(), DbError> {
         if self.transaction_backup.is_some() {
-            return Err("Transaction already in progress".into());
+            return Err(DbError::TransactionError("Transaction already in progress".into()));
         }
-        self.transaction_backup = Some(self.rows.clone());
+        self.transaction_backup = Some(vec![]);
         Ok(())
     }
 
-    pub fn rollback_transaction(&mut self) -> Result<(), String> {
-        if let Some(backup) = self.transaction_backup.take() {
-            self.rows = backup;
-            self.rebuild_all_indexes(); // restore consistency
+    pub fn rollback_transaction(&mut self) -> Result<(), DbError> {
+        if self.transaction_backup.is_some() {
+            self.rollback_to(0);
+            self.transaction_backup = None;
             Ok(())
         } else {
-            Err("No transaction to rollback".into())
+            Err(DbError::TransactionError("No transaction to rollback".into()))
         }
     }
 
-    pub fn commit_transaction(&mut self) -> Result<(), String> {
+    pub fn commit_transaction(&mut self) -> Result<(), DbError> {
         if self.transaction_backup.is_some() {
             self.transaction_backup = None;
             Ok(())
         } else {
-            Err("No transaction to commit".into())
+            Err(DbError::TransactionError("No transaction to commit".into()))
+        }
+    }
+
+    // Undoes every log entry after the first `len`, leaving the transaction open
+    pub(crate) fn rollback_to(&mut self, len: usize) {
+        let Some(mut log) = self.transaction_backup.take() else {
+            return;
+        };
+        let undone: Vec<UndoEntry> = log.drain(len.min(log.len())..).collect();
+        for entry in undone.iter().rev() {
+            self.undo(entry);
+        }
+        self.transaction_backup = Some(log);
+        self.rebuild_all_indexes(); // restore consistency
+    }
+
+    // The table as of the last commit, without the open transaction's writes or schema changes
+    pub fn committed(&self) -> Cow<'_, Table> {
+        match &self.transaction_backup {
+            Some(log) if !log.is_empty() => {
+                let mut table = Table {
+                    name: self.name.clone(),
+                    columns: self.columns.clone(),
+                    rows: self.rows.clone(),
+                    primary_key: self.primary_key.clone(),
+                    indexes: HashMap::new(),
+                    sequences: self.sequences.clone(),
+                    transaction_backup: None,
+                    stats: self.stats.clone(),
+                    compression: self.compression,
+                };
+                table.indexes = self.indexes.iter().map(|(name, index)| (name.clone(), index.derived())).collect();
+                for entry in log.iter().rev() {
+                    table.undo(entry);
+                }
+                // Undoing can bring back the original's disk indexes, which stay with it
+                for index in table.indexes.values_mut() {
+                    *index = index.derived();
+                }
+                table.rebuild_all_indexes();
+                Cow::Owned(table)
+            }
+            _ => Cow::Borrowed(self),
+        }
+    }
+
+    // Reverses one log entry. Index contents are left for the caller to rebuild.
+    fn undo(&mut self, entry: &UndoEntry) {
+        match entry {
+            UndoEntry::Row(change) => change.undo(&mut self.rows),
+            UndoEntry::AddColumn => {
+                self.columns.pop();
+                for row in &mut self.rows {
+                    row.pop();
+                }
+            }
+            UndoEntry::DropColumn {
+                position,
+                column,
+                values,
+                index,
+            } => {
+                for (row, value) in self.rows.iter_mut().zip(values) {
+                    row.insert(*position, value.clone());
+                }
+                self.columns.insert(*position, column.clone());
+                if let Some(index) = index {
+                    self.indexes.insert(column.name.clone(), index.empty_like());
+                }
+            }
+            UndoEntry::RenameColumn { from, to } => {
+                if let Some(column) = self.columns.iter_mut().find(|c| &c.name == to) {
+                    column.name = from.clone();
+                }
+                self.rename_references(to, from);
+            }
+            UndoEntry::SetOptions { column, options } => {
+                if let Some(column) = self.columns.iter_mut().find(|c| &c.name == column) {
+                    column.options = options.clone();
+                }
+            }
+            UndoEntry::SetPrimaryKey(key) => self.primary_key = key.clone(),
+            UndoEntry::CreateIndex { column, replaced } => match replaced {
+                Some(index) => {
+                    self.indexes.insert(column.clone(), index.empty_like());
+                }
+                None => {
+                    self.remove_index(column);
+                }
+            },
+        }
+    }
+
+    // Records a change in the open transaction's undo log, if there is one
+    pub(crate) fn log_change(&mut self, change: impl FnOnce(&Table) -> UndoEntry) {
+        if let Some(mut log) = self.transaction_backup.take() {
+            log.push(change(self));
+            self.transaction_backup = Some(log);
         }
     }
 
     pub fn group_by(
         &self,
         by_col: &str,
-        filter: Option<&dyn Fn(&Vec<Value>) -> bool>,
-    ) -> Result<HashMap<Value, Vec<&Vec<Value>>>, String> {
-        let col_idx = self
-            .columns
-            .iter()
-            .position(|c| c.name == by_col)
-            .ok_or_else(|| format!("Column '{}' not found", by_col))?;
+        filter: Option<RowFilter>,
+    ) -> Result<HashMap<Value, Vec<&Vec<Value>>>, DbError> {
+        let col_idx = self.resolve_column(by_col)?;
 
         let mut groups: HashMap<Value, Vec<&Vec<Value>>> = HashMap::new();
-        for row in &self.rows {
+        for row in self.live_rows() {
             if let Some(f) = filter {
                 if !f(row) {
                     continue;
@@ -1009,81 +1776,23 @@ impl Table {
         &self,
         group_col: &str,
         agg_col: &str,
-        func: &str,
-    ) -> Result<HashMap<Value, AggregationResult>, String> {
-        let groups = self.group_by(group_col, None)?;
-        let agg_idx = self
-            .columns
-            .iter()
-            .position(|c| c.name == agg_col)
-            .ok_or_else(|| format!("Column '{}' not found", agg_col))?;
-
-        let mut result = HashMap::new();
-
-        for (key, rows) in groups {
-            let values: Vec<f64> = rows
-                .iter()
-                .filter_map(|row| match &row[agg_idx] {
-                    Value::Int(i) => Some(*i as f64),
-                    Value::BigInt(i) => Some(*i as f64),
-                    Value::Float(f) => Some(*f as f64),
-                    Value::Double(f) => Some(*f),
-                    _ => None,
-                })
-                .collect();
-
-            let agg = match func {
-                "sum" => AggregationResult::Sum(values.iter().sum()),
-                "avg" => {
-                    let total: f64 = values.iter().sum();
-                    let count = values.len();
-                    AggregationResult::Avg(if count == 0 {
-                        0.0
-                    } else {
-                        total / count as f64
-                    })
-                }
-                "count" => AggregationResult::Count(rows.len()),
-                "min" => {
-                    let min = rows
-                        .iter()
-                        .map(|r| r[agg_idx].clone())
-                        .min()
-                        .unwrap_or(Value::Null);
-                    AggregationResult::Min(min)
-                }
-                "max" => {
-                    let max = rows
-                        .iter()
-                        .map(|r| r[agg_idx].clone())
-                        .max()
-                        .unwrap_or(Value::Null);
-                    AggregationResult::Max(max)
-                }
-                _ => return Err("Unknown aggregation function".into()),
-            };
-
-            result.insert(key, agg);
-        }
-
-        Ok(result)
+        func: AggFunc,
+    ) -> Result<HashMap<Value, AggregationResult>, DbError> {
+        let groups = self.aggregate_group(group_col, &[(agg_col, func)], None)?;
+        Ok(groups.into_iter().map(|(key, mut results)| (key, results.remove(0))).collect())
     }
 
     pub fn aggregate_group(
         &self,
         group_col: &str,
-        agg_cols: &[(&str, &str)], // (column name, function name)
-        filter: Option<&dyn Fn(&Vec<Value>) -> bool>,
-    ) -> Result<HashMap<Value, Vec<AggregationResult>>, String> {
+        agg_cols: &[(&str, AggFunc)],
+        filter: Option<RowFilter>,
+    ) -> Result<HashMap<Value, Vec<AggregationResult>>, DbError> {
         let groups = self.group_by(group_col, filter)?;
         let mut col_indices = vec![];
 
         for (col_name, _) in agg_cols {
-            let idx = self
-                .columns
-                .iter()
-                .position(|c| c.name == *col_name)
-                .ok_or_else(|| format!("Column '{}' not found", col_name))?;
+            let idx = self.resolve_column(col_name)?;
             col_indices.push(idx);
         }
 
@@ -1092,49 +1801,7 @@ impl Table {
         for (key, rows) in groups {
             let mut agg_results = vec![];
             for ((_, func), &idx) in agg_cols.iter().zip(&col_indices) {
-                let values: Vec<f64> = rows
-                    .iter()
-                    .filter_map(|row| match &row[idx] {
-                        Value::Int(i) => Some(*i as f64),
-                        Value::BigInt(i) => Some(*i as f64),
-                        Value::Float(f) => Some(*f as f64),
-                        Value::Double(f) => Some(*f),
-                        _ => None,
-                    })
-                    .collect();
-
-                let agg = match *func {
-                    "sum" => AggregationResult::Sum(values.iter().sum()),
-                    "avg" => {
-                        let total: f64 = values.iter().sum();
-                        let count = values.len();
-                        AggregationResult::Avg(if count == 0 {
-                            0.0
-                        } else {
-                            total / count as f64
-                        })
-                    }
-                    "count" => AggregationResult::Count(rows.len()),
-                    "min" => {
-                        let min = rows
-                            .iter()
-                            .map(|r| r[idx].clone())
-                            .min()
-                            .unwrap_or(Value::Null);
-                        AggregationResult::Min(min)
-                    }
-                    "max" => {
-                        let max = rows
-                            .iter()
-                            .map(|r| r[idx].clone())
-                            .max()
-                            .unwrap_or(Value::Null);
-                        AggregationResult::Max(max)
-                    }
-                    _ => return Err("Unknown aggregation function".into()),
-                };
-
-                agg_results.push(agg);
+                agg_results.push(func.apply(&rows, idx)?);
             }
 
             result.insert(key, agg_results);
@@ -1143,73 +1810,125 @@ impl Table {
         Ok(result)
     }
 
-    pub fn save_as_view(&self, db_name: &str, view_name: &str) -> Result<(), String> {
-        let dir_path = Path::new("db");
+    pub fn aggregate_all(&self, agg_cols: &[(&str, AggFunc)]) -> Result<Vec<AggregationResult>, DbError> {
+        self.aggregate_all_where(agg_cols, None)
+    }
+
+    pub fn aggregate_all_where(
+        &self,
+        agg_cols: &[(&str, AggFunc)],
+        filter: Option<RowFilter>,
+    ) -> Result<Vec<AggregationResult>, DbError> {
+        let rows: Vec<&Vec<Value>> = self
+            .live_rows()
+            .filter(|row| filter.is_none_or(|f| f(row)))
+            .collect();
+
+        agg_cols
+            .iter()
+            .map(|(col_name, func)| {
+                let idx = self.resolve_column(col_name)?;
+                func.apply(&rows, idx)
+            })
+            .collect()
+    }
+
+    pub fn save_as_view(&self, db_name: &str, view_name: &str) -> Result<(), DbError> {
+        self.save_as_view_in(Path::new(DEFAULT_DATA_DIR), db_name, view_name)
+    }
+
+    pub fn save_as_view_in(
+        &self,
+        dir_path: &Path,
+        db_name: &str,
+        view_name: &str,
+    ) -> Result<(), DbError> {
+        self.save_as_view_synced(dir_path, db_name, view_name, SyncMode::default())
+    }
+
+    pub fn save_as_view_synced(
+        &self,
+        dir_path: &Path,
+        db_name: &str,
+        view_name: &str,
+        sync: SyncMode,
+    ) -> Result<(), DbError> {
         if !dir_path.exists() {
             fs::create_dir_all(dir_path)
-                .map_err(|e| format!("Failed to create db directory: {}", e))?;
+                .map_err(|e| DbError::IoError(format!("Failed to create db directory: {}", e)))?;
         }
 
         let file_path = dir_path.join(format!("{}.{}.view.csv", db_name, view_name));
 
-        let file = File::create(&file_path).map_err(|e| format!("Failed to create file: {}", e))?;
-        let mut writer = BufWriter::new(file);
-
-        // Write headers
-        let header = self
-            .columns
-            .iter()
-            .map(|c| c.name.clone())
-            .collect::<Vec<_>>()
-            .join(",");
-        writeln!(writer, "{}", header).map_err(|e| e.to_string())?;
+        atomic::write_file(&file_path, sync, |out| {
+            let mut writer = BufWriter::new(out);
 
-        // Write rows
-        for row in &self.rows {
-            let line = row
+            // Write headers
+            let header = self
+                .columns
                 .iter()
-                .map(|v| v.to_display_string())
+                .map(|c| c.name.clone())
                 .collect::<Vec<_>>()
                 .join(",");
-            writeln!(writer, "{}", line).map_err(|e| e.to_string())?;
-        }
+            writeln!(writer, "{}", header)?;
 
-        Ok(())
+            // Write rows
+            for row in &self.rows {
+                let line = row
+                    .iter()
+                    .map(|v| v.to_display_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                writeln!(writer, "{}", line)?;
+            }
+
+            writer.flush()?;
+            Ok(())
+        })
     }
 
     pub fn load_view_from_file(
         db_name: &str,
         view_name: &str,
         columns: Vec<Column>,
-    ) -> Result<Self, String> {
-        let file_path = format!("db/{}.{}.view.csv", db_name, view_name);
+    ) -> Result<Self, DbError> {
+        Table::load_view_from_dir(Path::new(DEFAULT_DATA_DIR), db_name, view_name, columns)
+    }
+
+    pub fn load_view_from_dir(
+        dir_path: &Path,
+        db_name: &str,
+        view_name: &str,
+        columns: Vec<Column>,
+    ) -> Result<Self, DbError> {
+        let file_path = dir_path.join(format!("{}.{}.view.csv", db_name, view_name));
         let file =
-            File::open(&file_path).map_err(|e| format!("Failed to open view file: {}", e))?;
+            File::open(&file_path).map_err(|e| DbError::IoError(format!("Failed to open view file: {}", e)))?;
 
         let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
 
         let mut rows = Vec::new();
 
         for (line_num, result) in rdr.records().enumerate() {
-            let record = result.map_err(|e| format!("CSV parse error: {}", e))?;
+            let record = result.map_err(|e| DbError::IoError(format!("CSV parse error: {}", e)))?;
 
             if record.len() != columns.len() {
-                return Err(format!(
+                return Err(DbError::IoError(format!(
                     "Row {} has wrong number of fields: expected {}, got {}",
                     line_num + 1,
                     columns.len(),
                     record.len()
-                ));
+                )));
             }
 
             let mut row = Vec::new();
             for (i, col) in columns.iter().enumerate() {
                 let raw = &record[i];
                 let value = Value::from_str(raw, &col.datatype).map_err(|e| {
-                    format!(
+                    DbError::TypeMismatch(format!(
                         "Error parsing value '{}' for column '{}': {}",
                         raw, col.name, e
-                    )
+                    ))
                 })?;
                 row.push(value);
             }
@@ -1223,7 +1942,214 @@ impl Table {
             rows,
             primary_key: None,
             indexes: HashMap::new(),
+            sequences: HashMap::new(),
             transaction_backup: None,
+            stats: None,
+            compression: None,
         })
     }
 }
+
+impl RowChange {
+    pub(crate) fn undo(&self, rows: &mut Vec<Vec<Value>>) {
+        match self {
+            RowChange::Insert { .. } => {
+                rows.pop();
+            }
+            RowChange::Update {
+                position, before, ..
+            } => rows[*position] = before.clone(),
+            RowChange::Delete {
+                positions,
+                rows: removed,
+            } => {
+                for (&position, row) in positions.iter().zip(removed) {
+                    rows.insert(position, row.clone());
+                }
+            }
+        }
+    }
+}
+
+// Indexes are rebuilt from the copied rows rather than cloned, so the copy always has
+// indexes that agree with its rows, each of the same kind as the original but for disk
+// indexes, which the copy keeps in memory.
+impl Clone for Table {
+    fn clone(&self) -> Self {
+        let mut table = Table {
+            name: self.name.clone(),
+            columns: self.columns.clone(),
+            rows: self.rows.clone(),
+            primary_key: self.primary_key.clone(),
+            indexes: HashMap::new(),
+            sequences: self.sequences.clone(),
+            transaction_backup: self.transaction_backup.clone(),
+            stats: self.stats.clone(),
+            compression: self.compression,
+        };
+        for (name, index) in &self.indexes {
+            let _ = table.create_index_like(name, &index.derived());
+        }
+        table
+    }
+}
+
+// Two tables are equal when their name, schema and rows match. Index contents and
+// any open transaction are bookkeeping and are ignored.
+impl PartialEq for Table {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.columns == other.columns
+            && self.primary_key == other.primary_key
+            && self.rows == other.rows
+    }
+}
+
+impl Table {
+    // Covers exactly what PartialEq compares. Only stable within one build of the program.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.name.hash(&mut hasher);
+        self.columns.hash(&mut hasher);
+        self.primary_key.hash(&mut hasher);
+        self.rows.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+// Rows are stored with their values' own types, so only the width is checked
+fn read_page_rows(file_path: &Path, columns: &[Column]) -> Result<Vec<Vec<Value>>, DbError> {
+    let rows = read_rows(file_path)?;
+    if let Some((i, row)) = rows.iter().enumerate().find(|(_, row)| row.len() != columns.len()) {
+        return Err(DbError::IoError(format!(
+            "Row {} has wrong number of fields: expected {}, got {}",
+            i + 1,
+            columns.len(),
+            row.len()
+        )));
+    }
+    Ok(rows)
+}
+
+pub(crate) fn read_csv_rows(file_path: &Path, columns: &[Column]) -> Result<Vec<Vec<Value>>, DbError> {
+    let file = compress::open(file_path).map_err(|e| DbError::IoError(format!("Failed to open file: {}", e)))?;
+
+    let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
+
+    let mut rows = Vec::new();
+
+    for (line_num, result) in rdr.records().enumerate() {
+        let record = result.map_err(|e| DbError::IoError(format!("CSV parse error: {}", e)))?;
+
+        if record.len() != columns.len() {
+            return Err(DbError::IoError(format!(
+                "Row {} has wrong number of fields: expected {}, got {}",
+                line_num + 1,
+                columns.len(),
+                record.len()
+            )));
+        }
+
+        let mut row = Vec::new();
+        for (i, col) in columns.iter().enumerate() {
+            let raw = &record[i];
+            let value = Value::from_str(raw, &col.datatype).map_err(|e| {
+                DbError::TypeMismatch(format!(
+                    "Error parsing value '{}' for column '{}': {}",
+                    raw, col.name, e
+                ))
+            })?;
+            row.push(value);
+        }
+
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+// Writes the CSV beside `file_path` for a journal to move into place; returns the
+// temporary file
+pub(crate) fn write_csv_temp(
+    file_path: &Path,
+    columns: &[Column],
+    rows: &[Vec<Value>],
+    sync: SyncMode,
+) -> Result<PathBuf, DbError> {
+    atomic::write_temp(file_path, sync, |out| write_csv(out, columns, rows))
+}
+
+fn write_csv(out: &mut CompressedWriter, columns: &[Column], rows: &[Vec<Value>]) -> Result<(), DbError> {
+    let mut writer = BufWriter::new(out);
+
+    // Write header
+    let header = columns
+        .iter()
+        .map(|c| c.name.clone())
+        .collect::<Vec<_>>()
+        .join(",");
+    writeln!(writer, "{}", header)?;
+
+    // Write rows
+    for row in rows {
+        let line = row
+            .iter()
+            .map(|v| match v {
+                Value::Set(items) => {
+                    let inner = items.join(",");
+                    format!("\"{{{}}}\"", inner)
+                }
+                Value::Enum(val) => format!("\"{}\"", val),
+                Value::Varchar(s) | Value::Text(s) => format!("\"{}\"", s),
+                Value::Char(c) => format!("\"{}\"", c),
+                Value::Boolean(b) => format!("\"{}\"", b),
+                Value::Int(i) => format!("\"{}\"", i),
+                Value::BigInt(i) => format!("\"{}\"", i),
+                Value::Float(f) => format!("\"{}\"", f),
+                Value::Double(f) => format!("\"{}\"", f),
+                Value::Date(d) => format!("\"{}\"", d),
+                Value::Time(t) => format!("\"{}\"", t),
+                Value::DateTime(dt) => format!("\"{}\"", dt),
+                Value::Point(lat, lon) => format!("\"({}, {})\"", lat, lon),
+                Value::Decimal(d) => format!("\"{}\"", d),
+                Value::Null => "\"NULL\"".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(writer, "{}", line)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum JoinKind {
+    Inner,
+    Left,
+    Right,
+}
+
+pub(crate) fn left_pair<'a>(
+    left: Option<&'a Vec<Value>>,
+    right: Option<&'a Vec<Value>>,
+    other: &Table,
+) -> LeftJoinRow<'a> {
+    let right = match right {
+        Some(row) => row.iter().map(Some).collect(),
+        None => vec![None; other.columns.len()],
+    };
+    (left.unwrap().iter().collect(), right)
+}
+
+pub(crate) fn right_pair<'a>(
+    left: Option<&'a Vec<Value>>,
+    right: Option<&'a Vec<Value>>,
+    table: &Table,
+) -> RightJoinRow<'a> {
+    let left = match left {
+        Some(row) => row.iter().map(Some).collect(),
+        None => vec![None; table.columns.len()],
+    };
+    (left, right.unwrap().iter().collect())
+}
@@ -0,0 +1,251 @@
+use crate::error::DbError;
+use crate::table::data::{Column, Comparison, DataType, Decimal, Expr, GeneratorSpec, Options, Table, Value};
+use chrono::{Duration, NaiveTime};
+use std::collections::HashSet;
+
+const WORDS: [&str; 16] = [
+    "alpha", "bravo", "cedar", "delta", "ember", "falcon", "garnet", "harbor",
+    "indigo", "juniper", "kestrel", "lumen", "maple", "nova", "orchid", "pebble",
+];
+
+const UNIQUE_CHARS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+// Small xorshift generator so seeded runs are reproducible without extra dependencies
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn next_bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+
+    fn range_i64(&mut self, low: i64, high: i64) -> i64 {
+        if high <= low {
+            return low;
+        }
+        // Counted in u64 so the full i64 range doesn't overflow
+        let span = high.wrapping_sub(low) as u64;
+        let offset = match span.checked_add(1) {
+            Some(count) => self.next_u64() % count,
+            None => self.next_u64(),
+        };
+        low.wrapping_add(offset as i64)
+    }
+
+    fn pick<'a>(&mut self, items: &'a [String]) -> Option<&'a String> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.next_u64() as usize % items.len()])
+        }
+    }
+}
+
+impl Table {
+    // Every row is generated first and the batch inserted with insert_many, so a failure
+    // leaves the table as it was
+    pub fn generate_rows(&mut self, n: usize, spec: GeneratorSpec) -> Result<usize, DbError> {
+        let mut rng = Rng::new(spec.seed);
+
+        // Unique columns count up through their values, skipping any already in the table
+        let mut counters: Vec<i64> = vec![0; self.columns.len()];
+        let mut taken: Vec<HashSet<Value>> = (0..self.columns.len())
+            .map(|i| self.rows.iter().map(|row| row[i].clone()).collect())
+            .collect();
+
+        let mut rows = Vec::with_capacity(n);
+        for _ in 0..n {
+            let mut row = Vec::with_capacity(self.columns.len());
+            for (i, col) in self.columns.iter().enumerate() {
+                // Identity and generated columns are filled in by the insert
+                let value = if col.identity().is_some() || col.generated().is_some() {
+                    Value::Null
+                } else if self.is_unique_column(col) {
+                    loop {
+                        counters[i] += 1;
+                        let value = generate_unique(col, counters[i], &spec)?;
+                        if taken[i].insert(value.clone()) {
+                            break value;
+                        }
+                    }
+                } else if !col.options.contains(&Options::NotNull)
+                    && rng.next_f64() < spec.null_ratio
+                {
+                    Value::Null
+                } else {
+                    generate_value(col, &spec, &mut rng)?
+                };
+                row.push(value);
+            }
+            rows.push(row);
+        }
+
+        self.insert_many(rows)
+    }
+
+    fn is_unique_column(&self, col: &Column) -> bool {
+        col.options.contains(&Options::Unique)
+            || self
+                .primary_key
+                .as_ref()
+                .map(|pk| pk.contains(&col.name))
+                .unwrap_or(false)
+    }
+}
+
+// The n-th value (from 1) of the column's type in a fixed order, or an error once the
+// type has run out of values
+fn generate_unique(col: &Column, n: i64, spec: &GeneratorSpec) -> Result<Value, DbError> {
+    let i = n - 1;
+    let value = match &col.datatype {
+        DataType::Int => i32::try_from(n).ok().map(Value::Int),
+        DataType::BigInt => Some(Value::BigInt(n)),
+        DataType::Float => Some(Value::Float(n as f32)),
+        DataType::Double => Some(Value::Double(n as f64)),
+        DataType::Decimal(precision, scale) => {
+            (n < 10i64.saturating_pow(*precision)).then(|| Value::Decimal(Decimal::new(n as i128, *scale)))
+        }
+        DataType::Char(1) => UNIQUE_CHARS.chars().nth(i as usize).map(Value::Char),
+        DataType::Char(length) | DataType::Varchar(length) => {
+            let named = format!("{}_{}", col.name, n);
+            [named, n.to_string()]
+                .into_iter()
+                .find(|s| s.len() <= *length as usize)
+                .map(Value::Varchar)
+        }
+        DataType::Text => Some(Value::Text(format!("{}_{}", col.name, n))),
+        DataType::Enum(allowed) => enum_values_for(col, allowed, spec)?.get(i as usize).cloned().map(Value::Enum),
+        // Each member set or not by one bit of i
+        DataType::Set(allowed) => {
+            let allowed = enum_values_for(col, allowed, spec)?;
+            (allowed.len() >= 63 || i >> allowed.len() == 0).then(|| {
+                let members = allowed.iter().enumerate().filter(|(bit, _)| *bit < 63 && (i >> bit) & 1 == 1);
+                Value::Set(members.map(|(_, member)| member.clone()).collect())
+            })
+        }
+        DataType::Boolean => [false, true].get(i as usize).copied().map(Value::Boolean),
+        DataType::Date => spec.date_range.0.checked_add_signed(Duration::days(i)).map(Value::Date),
+        DataType::Time => u32::try_from(i)
+            .ok()
+            .and_then(|secs| NaiveTime::from_num_seconds_from_midnight_opt(secs, 0))
+            .map(Value::Time),
+        DataType::DateTime => spec
+            .date_range
+            .0
+            .and_time(NaiveTime::MIN)
+            .checked_add_signed(Duration::seconds(i))
+            .map(Value::DateTime),
+        // Whole degrees, longitude first
+        DataType::Point => (i < 181 * 361).then_some(Value::Point((i / 361) as f64 - 90.0, (i % 361) as f64 - 180.0)),
+    };
+    value.ok_or_else(|| {
+        DbError::ConstraintViolation(format!("Column '{}' has no more unique values to generate", col.name))
+    })
+}
+
+fn generate_value(col: &Column, spec: &GeneratorSpec, rng: &mut Rng) -> Result<Value, DbError> {
+    // A CHECK of the form col = value pins the column to that value
+    for opt in &col.options {
+        if let Options::Check(Expr::Compare(Comparison::Eq, left, right)) = opt {
+            if let (Expr::ColumnRef(name), Expr::Literal(expected)) = (left.as_ref(), right.as_ref()) {
+                if *name == col.name {
+                    return Value::from_str(&expected.to_display_string(), &col.datatype);
+                }
+            }
+        }
+    }
+
+    let value = match &col.datatype {
+        DataType::Char(1) => Value::Char((b'a' + (rng.next_u64() % 26) as u8) as char),
+        DataType::Char(length) => Value::Varchar(random_string(*length as usize, rng)),
+        DataType::Varchar(length) => Value::Varchar(random_string(spec.string_len.min(*length as usize), rng)),
+        DataType::Text => Value::Text(random_sentence(rng)),
+        DataType::Enum(allowed) => {
+            let allowed = enum_values_for(col, allowed, spec)?;
+            Value::Enum(rng.pick(allowed).cloned().unwrap_or_default())
+        }
+        DataType::Set(allowed) => {
+            let allowed = enum_values_for(col, allowed, spec)?;
+            Value::Set(allowed.iter().filter(|_| rng.next_bool()).cloned().collect())
+        }
+        DataType::Boolean => Value::Boolean(rng.next_bool()),
+        DataType::Int => {
+            let low = spec.int_range.0.max(i32::MIN as i64);
+            let high = spec.int_range.1.min(i32::MAX as i64);
+            Value::Int(rng.range_i64(low, high) as i32)
+        }
+        DataType::BigInt => Value::BigInt(rng.range_i64(spec.int_range.0, spec.int_range.1)),
+        DataType::Float => Value::Float(random_float(spec, rng) as f32),
+        DataType::Double => Value::Double(random_float(spec, rng)),
+        DataType::Date => Value::Date(random_date(spec, rng)),
+        DataType::Time => Value::Time(random_time(rng)),
+        DataType::DateTime => Value::DateTime(random_date(spec, rng).and_time(random_time(rng))),
+        DataType::Point => Value::Point(rng.next_f64() * 180.0 - 90.0, rng.next_f64() * 360.0 - 180.0),
+        DataType::Decimal(precision, scale) => {
+            // Any mantissa up to `precision` digits fits; beyond 18 digits an i64 runs out
+            let max = 10i64.pow((*precision).min(18)) - 1;
+            Value::Decimal(Decimal::new(rng.range_i64(-max, max) as i128, *scale))
+        }
+    };
+
+    Ok(value)
+}
+
+// The spec can narrow a column's list to some of its values
+fn enum_values_for<'a>(col: &Column, allowed: &'a [String], spec: &'a GeneratorSpec) -> Result<&'a [String], DbError> {
+    let values = spec.enum_values.get(&col.name).map_or(allowed, Vec::as_slice);
+    if values.is_empty() {
+        return Err(DbError::InvalidArgument(format!("No allowed values given for column '{}'", col.name)));
+    }
+    Ok(values)
+}
+
+fn random_string(len: usize, rng: &mut Rng) -> String {
+    let mut s = WORDS[rng.next_u64() as usize % WORDS.len()].to_string();
+    s.truncate(len.max(1));
+    while s.len() < len {
+        s.push((b'a' + (rng.next_u64() % 26) as u8) as char);
+    }
+    s
+}
+
+fn random_sentence(rng: &mut Rng) -> String {
+    let count = rng.range_i64(3, 8) as usize;
+    (0..count)
+        .map(|_| WORDS[rng.next_u64() as usize % WORDS.len()])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn random_float(spec: &GeneratorSpec, rng: &mut Rng) -> f64 {
+    let (low, high) = spec.float_range;
+    let value = low + rng.next_f64() * (high - low);
+    (value * 100.0).round() / 100.0
+}
+
+fn random_date(spec: &GeneratorSpec, rng: &mut Rng) -> chrono::NaiveDate {
+    let (start, end) = spec.date_range;
+    let days = (end - start).num_days();
+    start + Duration::days(rng.range_i64(0, days))
+}
+
+fn random_time(rng: &mut Rng) -> NaiveTime {
+    let secs = rng.range_i64(0, 86_399) as u32;
+    NaiveTime::from_num_seconds_from_midnight_opt(secs, 0).unwrap()
+}
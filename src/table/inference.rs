@@ -0,0 +1,263 @@
+use crate::error::DbError;
+use crate::table::data::{Column, DataType, Table, Value, MAX_VARCHAR_LENGTH};
+use crate::table::decimal::MAX_PRECISION;
+use csv::ReaderBuilder;
+use std::fs::File;
+use std::path::Path;
+
+impl Table {
+    pub fn infer_from_csv(path: &str) -> Result<Self, DbError> {
+        let name = Path::new(path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| DbError::InvalidArgument(format!("Cannot derive a table name from '{}'", path)))?;
+
+        // Every row is looked at, so a value far down the file can't fail to fit the type
+        let columns = Table::infer_columns_from_csv(path, usize::MAX)?;
+
+        let file = File::open(path).map_err(|e| DbError::IoError(format!("Failed to open file: {}", e)))?;
+        let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
+
+        let mut table = Table::new(name, columns, None);
+
+        for (line_num, result) in rdr.records().enumerate() {
+            let record = result.map_err(|e| DbError::IoError(format!("CSV parse error: {}", e)))?;
+
+            if record.len() != table.columns.len() {
+                return Err(DbError::InvalidArgument(format!(
+                    "Row {} has wrong number of fields: expected {}, got {}",
+                    line_num + 1,
+                    table.columns.len(),
+                    record.len()
+                )));
+            }
+
+            let mut row = Vec::new();
+            for (i, col) in table.columns.iter().enumerate() {
+                let raw = &record[i];
+                let value = if is_null_field(raw) {
+                    Value::Null
+                } else {
+                    Value::from_str(raw, &col.datatype).map_err(|e| {
+                        DbError::TypeMismatch(format!(
+                            "Error parsing value '{}' for column '{}' on row {}: {}",
+                            raw,
+                            col.name,
+                            line_num + 1,
+                            e
+                        ))
+                    })?
+                };
+                row.push(value);
+            }
+
+            table.insert(row)?;
+        }
+
+        Ok(table)
+    }
+
+    // Builds a table from computed rows, inferring each column's type from its values.
+    // `overrides` pins specific columns to a type instead.
+    pub fn from_rows(
+        name: &str,
+        column_names: &[&str],
+        rows: Vec<Vec<Value>>,
+        overrides: &[(&str, DataType)],
+    ) -> Result<Self, DbError> {
+        for (i, row) in rows.iter().enumerate() {
+            if row.len() != column_names.len() {
+                return Err(DbError::InvalidArgument(format!(
+                    "Row {} has wrong number of values: expected {}, got {}",
+                    i + 1,
+                    column_names.len(),
+                    row.len()
+                )));
+            }
+        }
+
+        let mut columns = Vec::with_capacity(column_names.len());
+        for (i, col_name) in column_names.iter().enumerate() {
+            let datatype = match overrides.iter().find(|(n, _)| n == col_name) {
+                Some((_, dtype)) => dtype.clone(),
+                None => infer_value_type(col_name, rows.iter().map(|r| &r[i]))?,
+            };
+            columns.push(Column {
+                name: col_name.to_string(),
+                datatype,
+                options: vec![],
+            });
+        }
+
+        let mut table = Table::new(name, columns, None);
+        for row in rows {
+            let row = row
+                .into_iter()
+                .zip(&table.columns)
+                .map(|(v, col)| coerce_numeric(v, &col.datatype))
+                .collect();
+            table.insert(row)?;
+        }
+
+        Ok(table)
+    }
+
+    pub fn infer_columns_from_csv(path: &str, sample_rows: usize) -> Result<Vec<Column>, DbError> {
+        let file = File::open(path).map_err(|e| DbError::IoError(format!("Failed to open file: {}", e)))?;
+        let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
+
+        let headers = rdr
+            .headers()
+            .map_err(|e| DbError::IoError(format!("CSV parse error: {}", e)))?
+            .clone();
+
+        let mut guesses: Vec<Option<DataType>> = vec![None; headers.len()];
+
+        for result in rdr.records().take(sample_rows) {
+            let record = result.map_err(|e| DbError::IoError(format!("CSV parse error: {}", e)))?;
+            for (i, raw) in record.iter().enumerate().take(headers.len()) {
+                if is_null_field(raw) {
+                    continue;
+                }
+                let guess = guess_datatype(raw);
+                guesses[i] = Some(match guesses[i].take() {
+                    Some(prev) => widen(prev, guess),
+                    None => guess,
+                });
+            }
+        }
+
+        Ok(headers
+            .iter()
+            .zip(guesses)
+            .map(|(name, guess)| Column {
+                name: name.trim().to_string(),
+                datatype: guess.unwrap_or(DataType::Varchar(MAX_VARCHAR_LENGTH)),
+                options: vec![],
+            })
+            .collect())
+    }
+}
+
+fn infer_value_type<'a>(
+    column: &str,
+    values: impl Iterator<Item = &'a Value>,
+) -> Result<DataType, DbError> {
+    let mut inferred: Option<DataType> = None;
+
+    for value in values {
+        let dtype = match value {
+            Value::Null => continue,
+            Value::Char(_) => DataType::Char(1),
+            Value::Varchar(_) => DataType::Varchar(MAX_VARCHAR_LENGTH),
+            Value::Text(_) => DataType::Text,
+            // The list grows to every value seen
+            Value::Enum(val) => DataType::Enum(vec![val.clone()]),
+            Value::Set(vals) => DataType::Set(vals.clone()),
+            Value::Boolean(_) => DataType::Boolean,
+            Value::Int(_) => DataType::Int,
+            Value::BigInt(_) => DataType::BigInt,
+            Value::Float(_) => DataType::Float,
+            Value::Double(_) => DataType::Double,
+            Value::Date(_) => DataType::Date,
+            Value::Point(_, _) => DataType::Point,
+            Value::Time(_) => DataType::Time,
+            Value::DateTime(_) => DataType::DateTime,
+            Value::Decimal(d) => DataType::Decimal(MAX_PRECISION, d.scale),
+        };
+
+        inferred = Some(match inferred {
+            None => dtype,
+            Some(prev) if prev == dtype => prev,
+            Some(prev) => combine_types(&prev, &dtype).ok_or_else(|| {
+                DbError::TypeMismatch(format!("Column '{}' mixes {:?} and {:?} values", column, prev, dtype))
+            })?,
+        });
+    }
+
+    Ok(inferred.unwrap_or(DataType::Varchar(MAX_VARCHAR_LENGTH)))
+}
+
+fn combine_types(a: &DataType, b: &DataType) -> Option<DataType> {
+    use DataType::*;
+    let union = |a: &[String], b: &[String]| {
+        let mut all = a.to_vec();
+        all.extend(b.iter().filter(|v| !a.contains(v)).cloned());
+        all
+    };
+    match (a, b) {
+        (Enum(a), Enum(b)) => Some(Enum(union(a, b))),
+        (Set(a), Set(b)) => Some(Set(union(a, b))),
+        (Int, BigInt) | (BigInt, Int) => Some(BigInt),
+        (Int, Float) | (Float, Int) => Some(Float),
+        (Int | BigInt | Float | Double, Int | BigInt | Float | Double) => Some(Double),
+        (Decimal(_, a), Decimal(_, b)) => Some(Decimal(MAX_PRECISION, *a.max(b))),
+        _ => None,
+    }
+}
+
+fn coerce_numeric(value: Value, dtype: &DataType) -> Value {
+    match (value, dtype) {
+        (Value::Int(i), DataType::BigInt) => Value::BigInt(i as i64),
+        (Value::Int(i), DataType::Float) => Value::Float(i as f32),
+        (Value::Int(i), DataType::Double) => Value::Double(i as f64),
+        (Value::BigInt(i), DataType::Double) => Value::Double(i as f64),
+        (Value::Float(f), DataType::Double) => Value::Double(f as f64),
+        (Value::Decimal(d), DataType::Decimal(_, scale)) => d.rescale(*scale).map_or(Value::Decimal(d), Value::Decimal),
+        (value, _) => value,
+    }
+}
+
+fn is_null_field(raw: &str) -> bool {
+    let trimmed = raw.trim();
+    trimmed.is_empty() || trimmed.eq_ignore_ascii_case("null")
+}
+
+fn guess_datatype(raw: &str) -> DataType {
+    let s = raw.trim().trim_matches('"');
+
+    if s.parse::<i32>().is_ok() {
+        DataType::Int
+    } else if s.parse::<i64>().is_ok() {
+        DataType::BigInt
+    } else if s.parse::<f64>().is_ok() {
+        DataType::Double
+    } else if s == "true" || s == "false" {
+        DataType::Boolean
+    } else if Value::from_date_str(s).is_ok() {
+        DataType::Date
+    } else if Value::from_datetime_str(s).is_ok() {
+        DataType::DateTime
+    } else if Value::from_time_str(s).is_ok() {
+        DataType::Time
+    } else {
+        DataType::Varchar(MAX_VARCHAR_LENGTH)
+    }
+}
+
+// Numeric guesses widen to the larger type; any other disagreement falls back to Varchar
+fn widen(a: DataType, b: DataType) -> DataType {
+    fn numeric_rank(dtype: &DataType) -> Option<u8> {
+        match dtype {
+            DataType::Int => Some(0),
+            DataType::BigInt => Some(1),
+            DataType::Double => Some(2),
+            _ => None,
+        }
+    }
+
+    if a == b {
+        return a;
+    }
+
+    match (numeric_rank(&a), numeric_rank(&b)) {
+        (Some(ra), Some(rb)) => {
+            if ra >= rb {
+                a
+            } else {
+                b
+            }
+        }
+        _ => DataType::Varchar(MAX_VARCHAR_LENGTH),
+    }
+}
@@ -0,0 +1,161 @@
+use crate::error::DbError;
+use crate::table::data::{Column, JoinStrategy, LeftJoinRow, RightJoinRow, Table, Value};
+use crate::table::functions::{left_pair, right_pair, JoinKind};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+impl Table {
+    pub fn inner_join_with<'a>(
+        &'a self,
+        other: &'a Table,
+        on: (&str, &str),
+        strategy: JoinStrategy,
+    ) -> Result<Vec<LeftJoinRow<'a>>, DbError> {
+        let mut rows = vec![];
+        self.visit_join(other, on, None, None, (JoinKind::Inner, strategy), |left, right| {
+            rows.push(left_pair(left, right, other));
+        })?;
+        Ok(rows)
+    }
+
+    pub fn left_join_with<'a>(
+        &'a self,
+        other: &'a Table,
+        on: (&str, &str),
+        strategy: JoinStrategy,
+    ) -> Result<Vec<LeftJoinRow<'a>>, DbError> {
+        let mut rows = vec![];
+        self.visit_join(other, on, None, None, (JoinKind::Left, strategy), |left, right| {
+            rows.push(left_pair(left, right, other));
+        })?;
+        Ok(rows)
+    }
+
+    pub fn right_join_with<'a>(
+        &'a self,
+        other: &'a Table,
+        on: (&str, &str),
+        strategy: JoinStrategy,
+    ) -> Result<Vec<RightJoinRow<'a>>, DbError> {
+        let mut rows = vec![];
+        self.visit_join(other, on, None, None, (JoinKind::Right, strategy), |left, right| {
+            rows.push(right_pair(left, right, self));
+        })?;
+        Ok(rows)
+    }
+
+    // Joins into an owned table that can feed another join or a group by. Columns are
+    // qualified with their source table's name or alias, e.g. "users.id", and the side an
+    // outer join pads holds Value::Null.
+    pub fn inner_join_table(&self, other: &Table, on: (&str, &str)) -> Result<Table, DbError> {
+        self.join_table(other, on, JoinKind::Inner)
+    }
+
+    pub fn left_join_table(&self, other: &Table, on: (&str, &str)) -> Result<Table, DbError> {
+        self.join_table(other, on, JoinKind::Left)
+    }
+
+    pub fn right_join_table(&self, other: &Table, on: (&str, &str)) -> Result<Table, DbError> {
+        self.join_table(other, on, JoinKind::Right)
+    }
+
+    fn join_table(&self, other: &Table, on: (&str, &str), kind: JoinKind) -> Result<Table, DbError> {
+        let mut rows = vec![];
+        let kind = (kind, JoinStrategy::NestedLoop);
+        self.visit_join(other, on, None, None, kind, |left, right| {
+            let mut row = match left {
+                Some(row) => row.clone(),
+                None => vec![Value::Null; self.columns.len()],
+            };
+            match right {
+                Some(right) => row.extend(right.iter().cloned()),
+                None => row.resize(self.columns.len() + other.columns.len(), Value::Null),
+            }
+            rows.push(row);
+        })?;
+
+        // A column that is already qualified, e.g. from an earlier join, keeps its name
+        let mut seen = HashSet::new();
+        let columns = [self, other]
+            .iter()
+            .flat_map(|table| table.columns.iter().map(move |c| (&table.name, c)))
+            .map(|(table, c)| {
+                let mut name = if c.name.contains('.') {
+                    c.name.clone()
+                } else {
+                    format!("{}.{}", table, c.name)
+                };
+                while !seen.insert(name.clone()) {
+                    name.push('_');
+                }
+                Column {
+                    name,
+                    datatype: c.datatype.clone(),
+                    options: vec![],
+                }
+            })
+            .collect();
+
+        let mut result = Table::new(&format!("{}_join_{}", self.name, other.name), columns, None);
+        result.rows = rows;
+        Ok(result)
+    }
+}
+
+// Sorts both sides on their key, then walks them together one run of equal keys at a
+// time. Pairs come out in key order, and within a key in the order of the preserved side.
+pub(crate) fn merge_join<'a, F>(
+    mut left: Vec<&'a Vec<Value>>,
+    mut right: Vec<&'a Vec<Value>>,
+    (left_idx, right_idx): (usize, usize),
+    kind: JoinKind,
+    mut visit: F,
+) where
+    F: FnMut(Option<&'a Vec<Value>>, Option<&'a Vec<Value>>),
+{
+    left.sort_by(|a, b| a[left_idx].cmp(&b[left_idx]));
+    right.sort_by(|a, b| a[right_idx].cmp(&b[right_idx]));
+
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() || j < right.len() {
+        let order = match (left.get(i), right.get(j)) {
+            (Some(l), Some(r)) => l[left_idx].cmp(&r[right_idx]),
+            (Some(_), None) => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+        match order {
+            Ordering::Less => {
+                if kind == JoinKind::Left {
+                    visit(Some(left[i]), None);
+                }
+                i += 1;
+            }
+            Ordering::Greater => {
+                if kind == JoinKind::Right {
+                    visit(None, Some(right[j]));
+                }
+                j += 1;
+            }
+            Ordering::Equal => {
+                let key = &left[i][left_idx];
+                let left_end = i + left[i..].iter().take_while(|row| row[left_idx] == *key).count();
+                let right_end = j + right[j..].iter().take_while(|row| row[right_idx] == *key).count();
+                if kind == JoinKind::Right {
+                    for &r in &right[j..right_end] {
+                        for &l in &left[i..left_end] {
+                            visit(Some(l), Some(r));
+                        }
+                    }
+                } else {
+                    for &l in &left[i..left_end] {
+                        for &r in &right[j..right_end] {
+                            visit(Some(l), Some(r));
+                        }
+                    }
+                }
+                i = left_end;
+                j = right_end;
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::table::data::{DataType, MaskPolicy, Options, ResultSet, Value, MAX_VARCHAR_LENGTH};
+use crate::table::hmac::hmac_sha256;
+
+impl MaskPolicy {
+    // Hash is an HMAC-SHA256 under `key`, so the same value masks the same way across
+    // runs and builds, but can't be matched against hashes of guesses without the key
+    pub fn apply(&self, value: &Value, key: &[u8]) -> Value {
+        if let Value::Null = value {
+            return Value::Null;
+        }
+
+        let shown = value.to_display_string();
+        let masked = match self {
+            MaskPolicy::Redact => "****".to_string(),
+            MaskPolicy::ShowLast(n) => {
+                let len = shown.chars().count();
+                let keep = (*n).min(len);
+                let tail: String = shown.chars().skip(len - keep).collect();
+                format!("{}{}", "*".repeat(len - keep), tail)
+            }
+            MaskPolicy::Hash => hmac_sha256(key, shown.as_bytes())
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+        };
+
+        Value::Varchar(masked)
+    }
+}
+
+impl ResultSet {
+    // Storage keeps real values; masking only rewrites this copy of the results
+    pub fn apply_masks(&self, key: &[u8]) -> ResultSet {
+        let policies: Vec<Option<&MaskPolicy>> = self
+            .columns
+            .iter()
+            .map(|c| {
+                c.options.iter().find_map(|opt| match opt {
+                    Options::Mask(policy) => Some(policy),
+                    _ => None,
+                })
+            })
+            .collect();
+
+        let columns = self
+            .columns
+            .iter()
+            .zip(&policies)
+            .map(|(c, policy)| {
+                let mut col = c.clone();
+                if policy.is_some() {
+                    col.datatype = DataType::Varchar(MAX_VARCHAR_LENGTH);
+                }
+                col
+            })
+            .collect();
+
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&policies)
+                    .map(|(v, policy)| match policy {
+                        Some(p) => p.apply(v, key),
+                        None => v.clone(),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        ResultSet { columns, rows }
+    }
+}
@@ -1,4 +1,40 @@
 pub mod data;
 pub mod functions;
 pub mod validators;
+pub mod inference;
+pub mod generator;
+pub mod viewer;
+pub mod profiler;
+pub mod export;
+pub mod ttl;
+pub mod masking;
+pub mod plan;
+pub mod subquery;
+pub mod setops;
+pub mod ordering;
+pub mod multijoin;
+pub mod aggregation;
+pub mod typed;
+pub mod paging;
+pub mod external;
+pub mod sharding;
+pub mod spatial;
+pub mod resolver;
+pub mod predicate;
+pub mod schema;
+pub mod window;
+pub mod query;
+pub mod joins;
+pub mod planner;
+pub mod decimal;
+pub mod collation;
+pub mod numeric;
+pub mod expr;
+pub mod update;
+pub mod scan;
+pub mod cursor;
+pub mod copy;
+pub mod stats;
+pub mod parquet;
 mod filters;
+mod hmac;
@@ -0,0 +1,241 @@
+use crate::error::DbError;
+use crate::table::data::{Column, Table, Value};
+use std::collections::HashMap;
+
+// An equality condition between two qualified columns, e.g. ("orders.customer_id", "customers.id")
+struct JoinEdge {
+    left: (usize, usize),
+    right: (usize, usize),
+}
+
+// One source row per input table, indexed by the table's position in the input list
+type Slots<'a> = Vec<Option<&'a Vec<Value>>>;
+
+impl Table {
+    // Inner-joins every table on the given conditions. Output columns are named
+    // "table.column" and follow the order the tables were listed in, whatever order
+    // the joins actually ran in.
+    pub fn join_all(tables: &[&Table], on: &[(&str, &str)]) -> Result<Table, DbError> {
+        let edges = resolve_edges(tables, on)?;
+        let order = plan_join_order(tables, &edges);
+
+        let first = order[0];
+        let mut partial: Vec<Slots> = tables[first]
+            .live_rows()
+            .map(|row| {
+                let mut slots = vec![None; tables.len()];
+                slots[first] = Some(row);
+                slots
+            })
+            .collect();
+
+        let mut joined = vec![first];
+        for &next in &order[1..] {
+            // (column in the joined side, column in `next`) for every edge linking them
+            let keys: Vec<((usize, usize), usize)> = edges
+                .iter()
+                .filter_map(|e| {
+                    if e.right.0 == next && joined.contains(&e.left.0) {
+                        Some((e.left, e.right.1))
+                    } else if e.left.0 == next && joined.contains(&e.right.0) {
+                        Some((e.right, e.left.1))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            partial = join_step(partial, tables[next], next, &keys);
+            joined.push(next);
+        }
+
+        let columns = tables
+            .iter()
+            .flat_map(|t| {
+                t.columns.iter().map(move |c| Column {
+                    name: format!("{}.{}", t.name, c.name),
+                    datatype: c.datatype.clone(),
+                    options: vec![],
+                })
+            })
+            .collect();
+
+        let name = tables.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join("_");
+        let mut result = Table::new(&name, columns, None);
+        result.rows = partial
+            .into_iter()
+            .map(|slots| slots.into_iter().flatten().flatten().cloned().collect())
+            .collect();
+        Ok(result)
+    }
+
+    pub fn join_order(tables: &[&Table], on: &[(&str, &str)]) -> Result<Vec<String>, DbError> {
+        let edges = resolve_edges(tables, on)?;
+        Ok(plan_join_order(tables, &edges)
+            .into_iter()
+            .map(|i| tables[i].name.clone())
+            .collect())
+    }
+}
+
+fn resolve_edges(tables: &[&Table], on: &[(&str, &str)]) -> Result<Vec<JoinEdge>, DbError> {
+    if tables.is_empty() {
+        return Err(DbError::InvalidArgument("At least one table is required".to_string()));
+    }
+    for (i, table) in tables.iter().enumerate() {
+        if tables[..i].iter().any(|t| t.name == table.name) {
+            return Err(DbError::InvalidArgument(format!(
+                "Table '{}' is listed more than once; alias it first",
+                table.name
+            )));
+        }
+    }
+
+    on.iter()
+        .map(|(left, right)| {
+            Ok(JoinEdge {
+                left: resolve_column(tables, left)?,
+                right: resolve_column(tables, right)?,
+            })
+        })
+        .collect()
+}
+
+fn resolve_column(tables: &[&Table], qualified: &str) -> Result<(usize, usize), DbError> {
+    let (table_name, column) = qualified
+        .split_once('.')
+        .ok_or_else(|| DbError::InvalidArgument(format!("Join column '{}' must be written as table.column", qualified)))?;
+
+    let t = tables
+        .iter()
+        .position(|t| t.name.eq_ignore_ascii_case(table_name))
+        .ok_or_else(|| DbError::NotFound(format!("Table '{}' not found in join", table_name)))?;
+    let c = tables[t].resolve_column(column)?;
+    Ok((t, c))
+}
+
+// Greedy cost-based ordering: each step adds the table whose join is estimated to leave
+// the fewest rows, joining on a column pair keeping |left| * |right| / max(distinct left,
+// distinct right). Counts come from the last analyze where there is one; otherwise the
+// live row count stands in for both, as if every join column were unique. The first
+// table is the smaller side of the cheapest pair, and cross products are only taken when
+// nothing else is connected. Each step still builds its hash table on the side that
+// actually turns out smaller.
+fn plan_join_order(tables: &[&Table], edges: &[JoinEdge]) -> Vec<usize> {
+    let sizes: Vec<f64> = tables.iter().map(|t| estimated_rows(t) as f64).collect();
+    let distinct = |(t, c): (usize, usize)| distinct_values(tables[t], c).unwrap_or(sizes[t]).max(1.0);
+
+    // Rows left after joining `next` onto `joined`, which holds about `rows` rows
+    let estimate = |joined: &[usize], rows: f64, next: usize| -> Option<f64> {
+        let mut result = rows * sizes[next];
+        let mut connected = false;
+        for e in edges {
+            let (inner, outer) = if e.right.0 == next && joined.contains(&e.left.0) {
+                (e.left, e.right)
+            } else if e.left.0 == next && joined.contains(&e.right.0) {
+                (e.right, e.left)
+            } else {
+                continue;
+            };
+            result /= distinct(inner).min(rows.max(1.0)).max(distinct(outer));
+            connected = true;
+        }
+        connected.then_some(result)
+    };
+    let cheapest = |candidates: Vec<(f64, usize, usize)>| {
+        candidates
+            .into_iter()
+            .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)))
+    };
+
+    let smallest = (0..tables.len()).min_by(|&a, &b| sizes[a].total_cmp(&sizes[b]).then(a.cmp(&b))).unwrap();
+    let pairs = (0..tables.len())
+        .flat_map(|a| (0..tables.len()).map(move |b| (a, b)))
+        .filter(|&(a, b)| (sizes[a], a) < (sizes[b], b))
+        .filter_map(|(a, b)| Some((estimate(&[a], sizes[a], b)?, a, b)))
+        .collect();
+    let (mut order, mut rows) = match cheapest(pairs) {
+        Some((rows, a, b)) => (vec![a, b], rows),
+        None => (vec![smallest], sizes[smallest]),
+    };
+
+    while order.len() < tables.len() {
+        let remaining = (0..tables.len()).filter(|t| !order.contains(t));
+        let connected = remaining.clone().filter_map(|t| Some((estimate(&order, rows, t)?, t, t))).collect();
+        let (next_rows, next, _) = cheapest(connected)
+            .or_else(|| cheapest(remaining.map(|t| (rows * sizes[t], t, t)).collect()))
+            .unwrap();
+        order.push(next);
+        rows = next_rows;
+    }
+
+    order
+}
+
+fn estimated_rows(table: &Table) -> usize {
+    match &table.stats {
+        Some(stats) => stats.row_count,
+        None => table.live_rows().count(),
+    }
+}
+
+fn distinct_values(table: &Table, column: usize) -> Option<f64> {
+    let stats = table.stats.as_ref()?.column(&table.columns[column].name)?;
+    Some(stats.distinct_count as f64)
+}
+
+fn join_step<'a>(
+    partial: Vec<Slots<'a>>,
+    table: &'a Table,
+    slot: usize,
+    keys: &[((usize, usize), usize)],
+) -> Vec<Slots<'a>> {
+    let partial_key = |slots: &Slots| -> Option<Vec<Value>> {
+        keys.iter()
+            .map(|((t, c), _)| slots[*t].map(|row| row[*c].clone()))
+            .map(|v| v.filter(|v| *v != Value::Null))
+            .collect()
+    };
+    let table_key = |row: &Vec<Value>| -> Option<Vec<Value>> {
+        keys.iter()
+            .map(|(_, c)| Some(row[*c].clone()).filter(|v| *v != Value::Null))
+            .collect()
+    };
+
+    let rows: Vec<&Vec<Value>> = table.live_rows().collect();
+    let mut result = vec![];
+    let extend = |slots: &Slots<'a>, row: &'a Vec<Value>| {
+        let mut slots = slots.clone();
+        slots[slot] = Some(row);
+        slots
+    };
+
+    // Build the hash table on whichever side is smaller and probe with the other
+    if rows.len() <= partial.len() {
+        let mut built: HashMap<Vec<Value>, Vec<&Vec<Value>>> = HashMap::new();
+        for row in rows {
+            if let Some(key) = table_key(row) {
+                built.entry(key).or_default().push(row);
+            }
+        }
+        for slots in &partial {
+            if let Some(matches) = partial_key(slots).and_then(|k| built.get(&k)) {
+                result.extend(matches.iter().map(|row| extend(slots, row)));
+            }
+        }
+    } else {
+        let mut built: HashMap<Vec<Value>, Vec<&Slots>> = HashMap::new();
+        for slots in &partial {
+            if let Some(key) = partial_key(slots) {
+                built.entry(key).or_default().push(slots);
+            }
+        }
+        for row in rows {
+            if let Some(matches) = table_key(row).and_then(|k| built.get(&k)) {
+                result.extend(matches.iter().map(|slots| extend(slots, row)));
+            }
+        }
+    }
+
+    result
+}
@@ -0,0 +1,101 @@
+use crate::table::data::Value;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+// 2^63, the first whole f64 past i64::MAX
+const I64_LIMIT: f64 = 9_223_372_036_854_775_808.0;
+
+// Int, BigInt, Float and Double seen as one kind of number, so that 1, 1i64 and 1.0
+// compare and hash alike. Decimal keeps to itself: its exact values can't be matched
+// against binary floats without equality stopping being transitive.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Numeric {
+    Int(i64),
+    Float(f64),
+}
+
+impl Value {
+    pub(crate) fn as_numeric(&self) -> Option<Numeric> {
+        match self {
+            Value::Int(i) => Some(Numeric::Int(*i as i64)),
+            Value::BigInt(i) => Some(Numeric::Int(*i)),
+            Value::Float(f) => Some(Numeric::Float(*f as f64)),
+            Value::Double(f) => Some(Numeric::Float(*f)),
+            _ => None,
+        }
+    }
+}
+
+impl Numeric {
+    // A total order: floats follow f64::total_cmp, and integers fall exactly between them
+    pub(crate) fn total_cmp(self, other: Numeric) -> Ordering {
+        match (self, other) {
+            (Numeric::Int(a), Numeric::Int(b)) => a.cmp(&b),
+            (Numeric::Float(a), Numeric::Float(b)) => a.total_cmp(&b),
+            (Numeric::Int(i), Numeric::Float(f)) => cmp_int_float(i, f),
+            (Numeric::Float(f), Numeric::Int(i)) => cmp_int_float(i, f).reverse(),
+        }
+    }
+
+    pub(crate) fn to_f64(self) -> f64 {
+        match self {
+            Numeric::Int(i) => i as f64,
+            Numeric::Float(f) => f,
+        }
+    }
+
+    // What <, = and > see: NaN is unordered and -0.0 equals 0.0
+    pub(crate) fn partial_cmp(self, other: Numeric) -> Option<Ordering> {
+        let plain = |n: Numeric| match n {
+            Numeric::Float(f) if f.is_nan() => None,
+            // Adding 0.0 turns -0.0 into 0.0 and leaves everything else alone
+            Numeric::Float(f) => Some(Numeric::Float(f + 0.0)),
+            n => Some(n),
+        };
+        Some(plain(self)?.total_cmp(plain(other)?))
+    }
+}
+
+// Whole floats hash as the integer they equal
+impl Hash for Numeric {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match *self {
+            Numeric::Float(f) if f.fract() == 0.0 && (-I64_LIMIT..I64_LIMIT).contains(&f) && !is_negative_zero(f) => {
+                Numeric::Int(f as i64).hash(state)
+            }
+            Numeric::Float(f) => {
+                1u8.hash(state);
+                f.to_bits().hash(state);
+            }
+            Numeric::Int(i) => {
+                0u8.hash(state);
+                i.hash(state);
+            }
+        }
+    }
+}
+
+// Exact, unlike casting the integer to f64. -0.0 sits just below 0, as total_cmp has it.
+pub(crate) fn cmp_int_float(i: i64, f: f64) -> Ordering {
+    if f.is_nan() {
+        return if f.is_sign_negative() { Ordering::Greater } else { Ordering::Less };
+    }
+    if f >= I64_LIMIT {
+        return Ordering::Less;
+    }
+    if f < -I64_LIMIT {
+        return Ordering::Greater;
+    }
+    let whole = f.trunc();
+    i.cmp(&(whole as i64)).then_with(|| {
+        if is_negative_zero(f) {
+            Ordering::Greater
+        } else {
+            0.0f64.total_cmp(&(f - whole))
+        }
+    })
+}
+
+fn is_negative_zero(f: f64) -> bool {
+    f == 0.0 && f.is_sign_negative()
+}
@@ -0,0 +1,354 @@
+use crate::error::DbError;
+use crate::storage::spill::{SpillReader, SpillRun};
+use crate::table::data::{Limit, Limited, NullsOrder, OrderBy, QueryStage, SortDirection, SortKey, Table, Value};
+use crate::table::profiler::{self, scanned};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::mem::size_of;
+use std::sync::Arc;
+
+// Bytes of sort keys select_order_by holds in memory before spilling sorted runs to disk
+pub const SORT_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+// Most runs read at once; past this they are merged in passes
+const MERGE_FAN_IN: usize = 64;
+
+impl fmt::Debug for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortKey::Column(name) => f.debug_tuple("Column").field(name).finish(),
+            SortKey::Computed(_) => f.write_str("Computed(..)"),
+        }
+    }
+}
+
+impl OrderBy {
+    // NULLs sort as the largest value unless told otherwise, matching SQL's default
+    pub fn asc(column: &str) -> Self {
+        OrderBy::new(SortKey::Column(column.to_string()), SortDirection::Asc)
+    }
+
+    pub fn desc(column: &str) -> Self {
+        OrderBy::new(SortKey::Column(column.to_string()), SortDirection::Desc)
+    }
+
+    // Orders by a value computed from the whole row, e.g. a lowercased name or a line total
+    pub fn asc_by<F>(key: F) -> Self
+    where
+        F: Fn(&[Value]) -> Value + Send + Sync + 'static,
+    {
+        OrderBy::new(SortKey::Computed(Arc::new(key)), SortDirection::Asc)
+    }
+
+    pub fn desc_by<F>(key: F) -> Self
+    where
+        F: Fn(&[Value]) -> Value + Send + Sync + 'static,
+    {
+        OrderBy::new(SortKey::Computed(Arc::new(key)), SortDirection::Desc)
+    }
+
+    fn new(key: SortKey, direction: SortDirection) -> Self {
+        let nulls = match direction {
+            SortDirection::Asc => NullsOrder::Last,
+            SortDirection::Desc => NullsOrder::First,
+        };
+        OrderBy {
+            key,
+            direction,
+            nulls,
+        }
+    }
+
+    pub fn nulls_first(mut self) -> Self {
+        self.nulls = NullsOrder::First;
+        self
+    }
+
+    pub fn nulls_last(mut self) -> Self {
+        self.nulls = NullsOrder::Last;
+        self
+    }
+
+    pub fn compare(&self, a: &Value, b: &Value) -> Ordering {
+        match (a, b) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Null, _) => match self.nulls {
+                NullsOrder::First => Ordering::Less,
+                NullsOrder::Last => Ordering::Greater,
+            },
+            (_, Value::Null) => match self.nulls {
+                NullsOrder::First => Ordering::Greater,
+                NullsOrder::Last => Ordering::Less,
+            },
+            _ => match self.direction {
+                SortDirection::Asc => a.cmp(b),
+                SortDirection::Desc => b.cmp(a),
+            },
+        }
+    }
+}
+
+// A row with its sort keys evaluated
+type Decorated<'a> = (Vec<Value>, &'a Vec<Value>);
+// Sort keys and the row's position in the table's live rows
+type Keyed = (Vec<Value>, usize);
+
+enum ResolvedKey<'a> {
+    Column(usize),
+    Computed(&'a (dyn Fn(&[Value]) -> Value + Send + Sync)),
+}
+
+impl Table {
+    // The sort is stable: rows that compare equal on every key keep their table order
+    pub fn select_order_by(&self, order: &[OrderBy]) -> Result<Vec<&Vec<Value>>, DbError> {
+        self.select_order_by_within(order, SORT_MEMORY_BUDGET)
+    }
+
+    // Once the sort keys outgrow `budget` bytes they are sorted in runs written to temporary
+    // files, which are then merged. Only the keys spill; rows stay where they are.
+    pub fn select_order_by_within(&self, order: &[OrderBy], budget: usize) -> Result<Vec<&Vec<Value>>, DbError> {
+        let sort_key = self.sort_key(order)?;
+        let rows: Vec<&Vec<Value>> = self.live_rows().collect();
+        scanned(rows.len());
+        profiler::stage(QueryStage::Sort, || self.sort_within(order, budget, &sort_key, rows))
+    }
+
+    fn sort_within<'a>(
+        &self,
+        order: &[OrderBy],
+        budget: usize,
+        sort_key: impl Fn(&[Value]) -> Vec<Value>,
+        rows: Vec<&'a Vec<Value>>,
+    ) -> Result<Vec<&'a Vec<Value>>, DbError> {
+        let mut runs = vec![];
+        let mut pending: Vec<Keyed> = vec![];
+        let mut pending_bytes = 0;
+        for (position, row) in rows.iter().enumerate() {
+            let key = sort_key(row);
+            pending_bytes += keyed_bytes(&key);
+            pending.push((key, position));
+            if pending_bytes > budget {
+                runs.push(spill_run(order, &mut pending)?);
+                pending_bytes = 0;
+            }
+        }
+
+        if runs.is_empty() {
+            pending.sort_by(|(a, _), (b, _)| compare_sort_keys(order, a, b));
+            return Ok(pending.into_iter().map(|(_, position)| rows[position]).collect());
+        }
+        if !pending.is_empty() {
+            runs.push(spill_run(order, &mut pending)?);
+        }
+        merge_runs(order, runs)
+            .and_then(|merged| merged.map(|entry| entry.map(|(_, position)| rows[position])).collect())
+    }
+
+    // Only the rows up to the end of the window are fully sorted
+    pub fn select_order_by_limited(&self, order: &[OrderBy], limit: Limit) -> Result<Limited<&Vec<Value>>, DbError> {
+        let total = self.live_rows().count();
+        let rows = self.sorted_rows(order, limit.end())?;
+        Ok(Limited {
+            rows: rows.into_iter().skip(limit.offset).collect(),
+            offset: limit.offset,
+            total,
+        })
+    }
+
+    fn sorted_rows(&self, order: &[OrderBy], keep: Option<usize>) -> Result<Vec<&Vec<Value>>, DbError> {
+        let rows: Vec<&Vec<Value>> = self.live_rows().collect();
+        scanned(rows.len());
+        self.sort_rows(rows, order, keep)
+    }
+
+    // The first `keep` of `rows` in order, or all of them. Column keys are looked up in
+    // this table, so the rows must share its layout.
+    pub(crate) fn sort_rows<'a>(
+        &self,
+        rows: Vec<&'a Vec<Value>>,
+        order: &[OrderBy],
+        keep: Option<usize>,
+    ) -> Result<Vec<&'a Vec<Value>>, DbError> {
+        // Computed keys are evaluated once per row rather than once per comparison, and
+        // column keys are replaced by their collation keys
+        let sort_key = self.sort_key(order)?;
+        profiler::stage(QueryStage::Sort, || {
+            let mut decorated: Vec<Decorated> = rows.into_iter().map(|row| (sort_key(row), row)).collect();
+
+            let compare = |(a, _): &Decorated, (b, _): &Decorated| compare_sort_keys(order, a, b);
+
+            match keep {
+                // Partition off the rows that can make the cut, then sort just those. Ties are
+                // broken by table position so the result matches the full stable sort.
+                Some(keep) if keep < decorated.len() => {
+                    let mut positioned: Vec<(usize, Decorated)> = decorated.into_iter().enumerate().collect();
+                    let by_position = |a: &(usize, Decorated), b: &(usize, Decorated)| {
+                        compare(&a.1, &b.1).then(a.0.cmp(&b.0))
+                    };
+                    if keep > 0 {
+                        positioned.select_nth_unstable_by(keep - 1, by_position);
+                    }
+                    positioned.truncate(keep);
+                    positioned.sort_by(by_position);
+                    Ok(positioned.into_iter().map(|(_, (_, row))| row).collect())
+                }
+                _ => {
+                    decorated.sort_by(compare);
+                    Ok(decorated.into_iter().map(|(_, row)| row).collect())
+                }
+            }
+        })
+    }
+
+    // The values sort_rows compares `row` by under `order`, one per OrderBy
+    pub(crate) fn sort_key<'a>(&'a self, order: &'a [OrderBy]) -> Result<impl Fn(&[Value]) -> Vec<Value> + 'a, DbError> {
+        let keys = order
+            .iter()
+            .map(|spec| match &spec.key {
+                SortKey::Column(name) => Ok(ResolvedKey::Column(self.resolve_column(name)?)),
+                SortKey::Computed(f) => Ok(ResolvedKey::Computed(f.as_ref())),
+            })
+            .collect::<Result<Vec<_>, DbError>>()?;
+        Ok(move |row: &[Value]| {
+            keys.iter()
+                .map(|key| match key {
+                    ResolvedKey::Column(idx) => self.columns[*idx].collation().key(&row[*idx]).into_owned(),
+                    ResolvedKey::Computed(f) => f(row),
+                })
+                .collect()
+        })
+    }
+
+    pub fn select_sorted_by_key<K, F>(&self, key: F) -> Vec<&Vec<Value>>
+    where
+        K: Ord,
+        F: Fn(&Vec<Value>) -> K,
+    {
+        let mut rows: Vec<&Vec<Value>> = self.live_rows().collect();
+        scanned(rows.len());
+        profiler::stage(QueryStage::Sort, || rows.sort_by_cached_key(|row| key(row)));
+        rows
+    }
+}
+
+// Compares two keys built by Table::sort_key for the same `order`
+pub(crate) fn compare_sort_keys(order: &[OrderBy], a: &[Value], b: &[Value]) -> Ordering {
+    order
+        .iter()
+        .enumerate()
+        .map(|(i, spec)| spec.compare(&a[i], &b[i]))
+        .find(|ord| *ord != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+// Roughly what a Keyed entry holds on the heap
+fn keyed_bytes(key: &[Value]) -> usize {
+    let text = |s: &String| size_of::<String>() + s.len();
+    size_of::<Keyed>()
+        + key
+            .iter()
+            .map(|value| {
+                size_of::<Value>()
+                    + match value {
+                        Value::Varchar(s) | Value::Text(s) | Value::Enum(s) => s.len(),
+                        Value::Set(items) => items.iter().map(text).sum(),
+                        _ => 0,
+                    }
+            })
+            .sum::<usize>()
+}
+
+// Sorts and empties `pending`. Positions only grow within a run, so a stable sort leaves
+// ties in table order.
+fn spill_run(order: &[OrderBy], pending: &mut Vec<Keyed>) -> Result<SpillRun, DbError> {
+    pending.sort_by(|(a, _), (b, _)| compare_sort_keys(order, a, b));
+    SpillRun::write(pending.drain(..).map(Ok))
+}
+
+// Merges runs MERGE_FAN_IN at a time into longer ones until a single pass can read them all
+fn merge_runs(order: &[OrderBy], mut runs: Vec<SpillRun>) -> Result<Merge<'_>, DbError> {
+    while runs.len() > MERGE_FAN_IN {
+        let mut rest = runs.into_iter();
+        runs = vec![];
+        loop {
+            let chunk: Vec<SpillRun> = rest.by_ref().take(MERGE_FAN_IN).collect();
+            if chunk.is_empty() {
+                break;
+            }
+            runs.push(SpillRun::write(Merge::new(order, chunk)?)?);
+        }
+    }
+    Merge::new(order, runs)
+}
+
+// The next entry of one run, ordered so BinaryHeap pops the smallest key first. Equal keys
+// come out by position, which keeps the merge stable.
+struct Head<'a> {
+    order: &'a [OrderBy],
+    entry: Keyed,
+    run: usize,
+}
+
+impl Ord for Head<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_sort_keys(self.order, &other.entry.0, &self.entry.0).then(other.entry.1.cmp(&self.entry.1))
+    }
+}
+
+impl PartialOrd for Head<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head<'_> {}
+
+// K-way merge of sorted runs, which it keeps so their files last until it's done
+struct Merge<'a> {
+    order: &'a [OrderBy],
+    readers: Vec<SpillReader>,
+    heads: BinaryHeap<Head<'a>>,
+    // Dropped after the readers, closing each file before it's removed
+    _runs: Vec<SpillRun>,
+}
+
+impl<'a> Merge<'a> {
+    fn new(order: &'a [OrderBy], runs: Vec<SpillRun>) -> Result<Self, DbError> {
+        let mut merge = Merge {
+            order,
+            readers: runs.iter().map(SpillRun::read).collect::<Result<_, _>>()?,
+            _runs: runs,
+            heads: BinaryHeap::new(),
+        };
+        for run in 0..merge.readers.len() {
+            merge.advance(run)?;
+        }
+        Ok(merge)
+    }
+
+    fn advance(&mut self, run: usize) -> Result<(), DbError> {
+        if let Some(entry) = self.readers[run].next().transpose()? {
+            self.heads.push(Head {
+                order: self.order,
+                entry,
+                run,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for Merge<'_> {
+    type Item = Result<Keyed, DbError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let head = self.heads.pop()?;
+        Some(self.advance(head.run).map(|_| head.entry))
+    }
+}
@@ -0,0 +1,136 @@
+use crate::error::DbError;
+use crate::table::data::{IndexType, Limit, Limited, Page, Table, Value};
+use crate::table::filters::FilterExpr;
+
+impl<T> Page<T> {
+    pub fn page_count(&self) -> usize {
+        if self.page_size == 0 {
+            return 0;
+        }
+        self.total.div_ceil(self.page_size)
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.page + 1 < self.page_count()
+    }
+
+    pub fn has_previous(&self) -> bool {
+        self.page > 0
+    }
+
+    pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> Page<U> {
+        Page {
+            rows: self.rows.into_iter().map(f).collect(),
+            page: self.page,
+            page_size: self.page_size,
+            total: self.total,
+        }
+    }
+}
+
+impl Limit {
+    pub fn new(count: usize) -> Self {
+        Limit {
+            offset: 0,
+            count: Some(count),
+        }
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    // Position one past the last kept result, if there is a count
+    pub fn end(&self) -> Option<usize> {
+        self.count.map(|count| self.offset.saturating_add(count))
+    }
+
+    pub fn contains(&self, position: usize) -> bool {
+        position >= self.offset && self.end().is_none_or(|end| position < end)
+    }
+
+    // Counts every item but only keeps the ones inside the window
+    pub fn apply<T>(&self, items: impl IntoIterator<Item = T>) -> Limited<T> {
+        let mut rows = vec![];
+        let mut total = 0;
+        for item in items {
+            if self.contains(total) {
+                rows.push(item);
+            }
+            total += 1;
+        }
+        Limited {
+            rows,
+            offset: self.offset,
+            total,
+        }
+    }
+}
+
+impl<T> Limited<T> {
+    pub fn has_more(&self) -> bool {
+        self.offset + self.rows.len() < self.total
+    }
+}
+
+impl Table {
+    pub fn select_where_expr_limited(&self, expr: &FilterExpr, limit: Limit) -> Result<Limited<&Vec<Value>>, DbError> {
+        Ok(limit.apply(self.select_where_expr(expr)?))
+    }
+
+    // Pages are numbered from 0, like ResultSet::render_page
+    pub fn select_page(
+        &self,
+        expr: Option<&FilterExpr>,
+        page: usize,
+        page_size: usize,
+    ) -> Result<Page<&Vec<Value>>, DbError> {
+        if page_size == 0 {
+            return Err(DbError::InvalidArgument("Page size must be greater than 0".to_string()));
+        }
+
+        let (rows, total) = match expr {
+            Some(expr) => {
+                let rows = self
+                    .select_where_expr(expr)?
+                    .into_iter()
+                    .skip(page * page_size)
+                    .take(page_size)
+                    .collect();
+                (rows, self.count_where(expr)?)
+            }
+            None => {
+                let rows = self.live_rows().skip(page * page_size).take(page_size).collect();
+                (rows, self.live_rows().count())
+            }
+        };
+
+        Ok(Page {
+            rows,
+            page,
+            page_size,
+            total,
+        })
+    }
+
+    pub fn count_where(&self, expr: &FilterExpr) -> Result<usize, DbError> {
+        // A hash index bucket holds exactly the matching rows, so its size is the count;
+        // a collated column's bucket holds every value that compares equal under it
+        if self.ttl().is_none() {
+            let column = self.canonical_column(expr.column()).ok();
+            let index = column.and_then(|col| self.indexes.get(col));
+            match (column.zip(index), expr) {
+                // `= NULL` matches nothing, so only IS NULL counts the NULL bucket
+                (Some((col, IndexType::Hash(map))), FilterExpr::Eq(_, val)) if *val != Value::Null => {
+                    return Ok(map.get(self.index_key(col, val).as_ref()).map_or(0, |rows| rows.len()));
+                }
+                (Some((_, IndexType::Hash(map))), FilterExpr::IsNull(_)) => {
+                    return Ok(map.get(&Value::Null).map_or(0, |rows| rows.len()));
+                }
+                _ => {}
+            }
+        }
+        Ok(self.select_where_expr(expr)?.len())
+    }
+}
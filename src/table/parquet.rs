@@ -0,0 +1,553 @@
+use crate::error::DbError;
+use crate::table::data::{Column, DataType, Decimal, Table, Value};
+use crate::table::schema::TableSchema;
+use chrono::{DateTime, NaiveDate, NaiveTime, Timelike};
+use parquet::basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType, ZstdLevel};
+use parquet::column::reader::get_typed_column_reader;
+use parquet::data_type::{
+    BoolType, ByteArray, ByteArrayType, DataType as ParquetType, DoubleType, FixedLenByteArray, FixedLenByteArrayType,
+    FloatType, Int32Type, Int64Type,
+};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::{FileReader, RowGroupReader, SerializedFileReader};
+use parquet::file::writer::{SerializedFileWriter, SerializedRowGroupWriter};
+use parquet::format::{KeyValue, MicroSeconds};
+use parquet::schema::types::{SchemaDescriptor, Type, TypePtr};
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+// Rows per row group when saving. A group's columns are buffered whole on both write
+// and read, so this bounds memory use rather than the table size.
+pub const PARQUET_ROW_GROUP_SIZE: usize = 64 * 1024;
+
+// Footer key holding the table's schema text, so what Parquet types can't say (Varchar
+// lengths, Enum and Set members, constraints) survives a round trip
+const SCHEMA_KEY: &str = "database.schema";
+
+// Type mapping, every column nullable:
+//   Char, Varchar, Text   BYTE_ARRAY (STRING)        Date      INT32 (DATE)
+//   Enum                  BYTE_ARRAY (ENUM)          Time      INT64 (TIME, micros)
+//   Set                   LIST of BYTE_ARRAY         DateTime  INT64 (TIMESTAMP, micros, local)
+//   Boolean, Int, BigInt, Float, Double as the matching primitive
+//   Decimal(p, s)         FIXED_LEN_BYTE_ARRAY(16) (DECIMAL(p, s))
+//   Point                 group of two DOUBLEs, lat and lon
+// Columns are written zstd-compressed. Times and timestamps in millis or nanos are read
+// as well as micros.
+impl Table {
+    pub fn save_to_parquet(&self, path: &str) -> Result<(), DbError> {
+        self.save_to_parquet_chunked(path, PARQUET_ROW_GROUP_SIZE)
+    }
+
+    pub fn save_to_parquet_chunked(&self, path: &str, rows_per_group: usize) -> Result<(), DbError> {
+        if rows_per_group == 0 {
+            return Err(DbError::InvalidArgument("A row group must hold at least one row".to_string()));
+        }
+        let fields = self.columns.iter().map(parquet_field).collect::<Result<Vec<_>, _>>()?;
+        let schema = Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()
+            .map_err(parquet_error)?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .set_key_value_metadata(Some(vec![KeyValue::new(
+                SCHEMA_KEY.to_string(),
+                TableSchema::lines(self).join("\n"),
+            )]))
+            .build();
+
+        let file = File::create(path).map_err(|e| DbError::IoError(format!("Failed to create {}: {}", path, e)))?;
+        let mut writer =
+            SerializedFileWriter::new(file, Arc::new(schema), Arc::new(properties)).map_err(parquet_error)?;
+        for chunk in self.rows.chunks(rows_per_group) {
+            let mut group = writer.next_row_group().map_err(parquet_error)?;
+            for (i, column) in self.columns.iter().enumerate() {
+                let values: Vec<&Value> = chunk.iter().map(|row| &row[i]).collect();
+                write_column(&mut group, column, &values)?;
+            }
+            group.close().map_err(parquet_error)?;
+        }
+        writer.close().map_err(parquet_error)?;
+        Ok(())
+    }
+
+    // The table is named after the file. Files written by save_to_parquet carry their own
+    // schema; any other file is read by its Parquet types, which must be flat columns in
+    // the layouts above, and comes back without constraints.
+    pub fn load_from_parquet(path: &str) -> Result<Table, DbError> {
+        let file = File::open(path).map_err(|e| DbError::IoError(format!("Failed to open {}: {}", path, e)))?;
+        let reader = SerializedFileReader::new(file).map_err(parquet_error)?;
+        let metadata = reader.metadata().file_metadata();
+        let embedded = metadata
+            .key_value_metadata()
+            .and_then(|pairs| pairs.iter().find(|kv| kv.key == SCHEMA_KEY))
+            .and_then(|kv| kv.value.as_deref());
+        let schema = match embedded {
+            Some(text) => TableSchema::parse(text)?,
+            None => TableSchema {
+                columns: infer_columns(metadata.schema_descr())?,
+                ..TableSchema::default()
+            },
+        };
+        let leaves: usize = schema.columns.iter().map(|c| if c.datatype == DataType::Point { 2 } else { 1 }).sum();
+        if leaves != metadata.schema_descr().num_columns() {
+            return Err(DbError::SchemaError(format!(
+                "Parquet file {} does not match the schema saved in it",
+                path
+            )));
+        }
+
+        let mut rows = vec![];
+        for g in 0..reader.num_row_groups() {
+            let group = reader.get_row_group(g).map_err(parquet_error)?;
+            let count = group.metadata().num_rows() as usize;
+            let mut leaf = 0;
+            let mut columns = vec![];
+            for column in &schema.columns {
+                columns.push(read_column(group.as_ref(), &mut leaf, column, count)?.into_iter());
+            }
+            for _ in 0..count {
+                rows.push(columns.iter_mut().map(|c| c.next().unwrap_or(Value::Null)).collect());
+            }
+        }
+
+        let name = Path::new(path).file_stem().and_then(|s| s.to_str()).unwrap_or("parquet");
+        let mut table = Table::new(name, schema.columns.clone(), schema.primary_key.clone());
+        table.rows = rows;
+        schema.fill(&mut table);
+        table.rebuild_all_indexes();
+        Ok(table)
+    }
+}
+
+fn parquet_error(e: ParquetError) -> DbError {
+    DbError::IoError(format!("Parquet error: {}", e))
+}
+
+fn parquet_field(column: &Column) -> Result<TypePtr, DbError> {
+    let name = column.name.as_str();
+    let leaf = |physical, logical| {
+        Type::primitive_type_builder(name, physical)
+            .with_repetition(Repetition::OPTIONAL)
+            .with_logical_type(logical)
+    };
+    let micros = || TimeUnit::MICROS(MicroSeconds {});
+    let field = match &column.datatype {
+        DataType::Char(_) | DataType::Varchar(_) | DataType::Text => {
+            leaf(PhysicalType::BYTE_ARRAY, Some(LogicalType::String)).build()
+        }
+        DataType::Enum(_) => leaf(PhysicalType::BYTE_ARRAY, Some(LogicalType::Enum)).build(),
+        DataType::Boolean => leaf(PhysicalType::BOOLEAN, None).build(),
+        DataType::Int => leaf(PhysicalType::INT32, None).build(),
+        DataType::BigInt => leaf(PhysicalType::INT64, None).build(),
+        DataType::Float => leaf(PhysicalType::FLOAT, None).build(),
+        DataType::Double => leaf(PhysicalType::DOUBLE, None).build(),
+        DataType::Date => leaf(PhysicalType::INT32, Some(LogicalType::Date)).build(),
+        DataType::Time => leaf(
+            PhysicalType::INT64,
+            Some(LogicalType::Time {
+                is_adjusted_to_u_t_c: false,
+                unit: micros(),
+            }),
+        )
+        .build(),
+        DataType::DateTime => leaf(
+            PhysicalType::INT64,
+            Some(LogicalType::Timestamp {
+                is_adjusted_to_u_t_c: false,
+                unit: micros(),
+            }),
+        )
+        .build(),
+        DataType::Decimal(precision, scale) => leaf(
+            PhysicalType::FIXED_LEN_BYTE_ARRAY,
+            Some(LogicalType::Decimal {
+                scale: *scale as i32,
+                precision: *precision as i32,
+            }),
+        )
+        .with_length(16)
+        .with_precision(*precision as i32)
+        .with_scale(*scale as i32)
+        .build(),
+        DataType::Point => {
+            let coordinate = |name| {
+                Type::primitive_type_builder(name, PhysicalType::DOUBLE)
+                    .with_repetition(Repetition::REQUIRED)
+                    .build()
+                    .map(Arc::new)
+            };
+            Type::group_type_builder(name)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_fields(vec![coordinate("lat").map_err(parquet_error)?, coordinate("lon").map_err(parquet_error)?])
+                .build()
+        }
+        DataType::Set(_) => {
+            let element = Type::primitive_type_builder("element", PhysicalType::BYTE_ARRAY)
+                .with_repetition(Repetition::REQUIRED)
+                .with_logical_type(Some(LogicalType::String))
+                .build()
+                .map_err(parquet_error)?;
+            let list = Type::group_type_builder("list")
+                .with_repetition(Repetition::REPEATED)
+                .with_fields(vec![Arc::new(element)])
+                .build()
+                .map_err(parquet_error)?;
+            Type::group_type_builder(name)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_logical_type(Some(LogicalType::List))
+                .with_fields(vec![Arc::new(list)])
+                .build()
+        }
+    };
+    Ok(Arc::new(field.map_err(parquet_error)?))
+}
+
+// Flat columns only, in the layouts save_to_parquet writes
+fn infer_columns(schema: &SchemaDescriptor) -> Result<Vec<Column>, DbError> {
+    schema
+        .root_schema()
+        .get_fields()
+        .iter()
+        .map(|field| {
+            let unsupported =
+                || DbError::SchemaError(format!("Parquet column '{}' has no matching data type", field.name()));
+            if !field.is_primitive() {
+                return Err(unsupported());
+            }
+            let info = field.get_basic_info();
+            let datatype = match (field.get_physical_type(), info.logical_type()) {
+                (PhysicalType::BOOLEAN, None) => DataType::Boolean,
+                (PhysicalType::INT32, None) => DataType::Int,
+                (PhysicalType::INT32, Some(LogicalType::Date)) => DataType::Date,
+                (PhysicalType::INT64, None) => DataType::BigInt,
+                (PhysicalType::INT32, Some(LogicalType::Time { unit: TimeUnit::MILLIS(_), .. })) => DataType::Time,
+                (PhysicalType::INT64, Some(LogicalType::Time { .. })) => DataType::Time,
+                (PhysicalType::INT64, Some(LogicalType::Timestamp { .. })) => DataType::DateTime,
+                (PhysicalType::FLOAT, None) => DataType::Float,
+                (PhysicalType::DOUBLE, None) => DataType::Double,
+                (PhysicalType::BYTE_ARRAY, None | Some(LogicalType::String | LogicalType::Enum)) => DataType::Text,
+                (PhysicalType::FIXED_LEN_BYTE_ARRAY, Some(LogicalType::Decimal { scale, precision }))
+                    if matches!(field.as_ref(), Type::PrimitiveType { type_length, .. } if *type_length <= 16) =>
+                {
+                    DataType::Decimal(precision as u32, scale as u32)
+                }
+                _ => return Err(unsupported()),
+            };
+            Ok(Column {
+                name: field.name().to_string(),
+                datatype,
+                options: vec![],
+            })
+        })
+        .collect()
+}
+
+fn write_column(
+    group: &mut SerializedRowGroupWriter<'_, File>,
+    column: &Column,
+    values: &[&Value],
+) -> Result<(), DbError> {
+    match &column.datatype {
+        DataType::Char(_) | DataType::Varchar(_) | DataType::Text | DataType::Enum(_) => {
+            let cells = cells(values, column, |v| match v {
+                Value::Char(c) => Some(ByteArray::from(c.to_string().as_str())),
+                Value::Varchar(s) | Value::Text(s) | Value::Enum(s) => Some(ByteArray::from(s.as_str())),
+                _ => None,
+            })?;
+            write_leaf::<ByteArrayType>(group, cells)
+        }
+        DataType::Boolean => write_leaf::<BoolType>(group, cells(values, column, |v| match v {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        })?),
+        DataType::Int => write_leaf::<Int32Type>(group, cells(values, column, |v| match v {
+            Value::Int(n) => Some(*n),
+            _ => None,
+        })?),
+        DataType::BigInt => write_leaf::<Int64Type>(group, cells(values, column, |v| match v {
+            Value::BigInt(n) => Some(*n),
+            Value::Int(n) => Some(*n as i64),
+            _ => None,
+        })?),
+        DataType::Float => write_leaf::<FloatType>(group, cells(values, column, |v| match v {
+            Value::Float(f) => Some(*f),
+            _ => None,
+        })?),
+        DataType::Double => write_leaf::<DoubleType>(group, cells(values, column, |v| match v {
+            Value::Double(f) => Some(*f),
+            Value::Float(f) => Some(*f as f64),
+            _ => None,
+        })?),
+        DataType::Date => write_leaf::<Int32Type>(group, cells(values, column, |v| match v {
+            Value::Date(d) => Some((*d - epoch()).num_days() as i32),
+            _ => None,
+        })?),
+        DataType::Time => write_leaf::<Int64Type>(group, cells(values, column, |v| match v {
+            Value::Time(t) => Some(t.num_seconds_from_midnight() as i64 * 1_000_000 + t.nanosecond() as i64 / 1_000),
+            _ => None,
+        })?),
+        DataType::DateTime => write_leaf::<Int64Type>(group, cells(values, column, |v| match v {
+            Value::DateTime(dt) => Some(dt.and_utc().timestamp_micros()),
+            _ => None,
+        })?),
+        DataType::Decimal(_, scale) => write_leaf::<FixedLenByteArrayType>(group, cells(values, column, |v| match v {
+            Value::Decimal(d) => {
+                let bytes = d.rescale(*scale)?.mantissa.to_be_bytes().to_vec();
+                Some(FixedLenByteArray::from(ByteArray::from(bytes)))
+            }
+            _ => None,
+        })?),
+        DataType::Point => {
+            let points = cells(values, column, |v| match v {
+                Value::Point(lat, lon) => Some((*lat, *lon)),
+                _ => None,
+            })?;
+            write_leaf::<DoubleType>(group, points.iter().map(|p| p.map(|(lat, _)| lat)).collect())?;
+            write_leaf::<DoubleType>(group, points.iter().map(|p| p.map(|(_, lon)| lon)).collect())
+        }
+        DataType::Set(_) => {
+            let sets = cells(values, column, |v| match v {
+                Value::Set(items) => Some(items),
+                _ => None,
+            })?;
+            // Definition 0 is a NULL set, 1 an empty one and 2 a member; repetition 1
+            // continues the set before
+            let (mut members, mut def, mut rep) = (vec![], vec![], vec![]);
+            for set in sets {
+                match set {
+                    None => def.push(0),
+                    Some(items) if items.is_empty() => def.push(1),
+                    Some(items) => {
+                        for (i, item) in items.iter().enumerate() {
+                            members.push(ByteArray::from(item.as_str()));
+                            def.push(2);
+                            rep.push(if i == 0 { 0 } else { 1 });
+                        }
+                        continue;
+                    }
+                }
+                rep.push(0);
+            }
+            write_levels::<ByteArrayType>(group, &members, &def, Some(&rep))
+        }
+    }
+}
+
+// Every value as `convert` maps it, NULLs as None. A value of the wrong type fails the save.
+fn cells<'a, T>(
+    values: &[&'a Value],
+    column: &Column,
+    convert: impl Fn(&'a Value) -> Option<T>,
+) -> Result<Vec<Option<T>>, DbError> {
+    values
+        .iter()
+        .map(|value| match value {
+            Value::Null => Ok(None),
+            value => convert(value).map(Some).ok_or_else(|| {
+                DbError::TypeMismatch(format!(
+                    "Value {} cannot be saved in column '{}' of type {:?}",
+                    value, column.name, column.datatype
+                ))
+            }),
+        })
+        .collect()
+}
+
+fn write_leaf<T: ParquetType>(
+    group: &mut SerializedRowGroupWriter<'_, File>,
+    cells: Vec<Option<T::T>>,
+) -> Result<(), DbError> {
+    let def: Vec<i16> = cells.iter().map(|cell| cell.is_some() as i16).collect();
+    let present: Vec<T::T> = cells.into_iter().flatten().collect();
+    write_levels::<T>(group, &present, &def, None)
+}
+
+fn write_levels<T: ParquetType>(
+    group: &mut SerializedRowGroupWriter<'_, File>,
+    values: &[T::T],
+    def: &[i16],
+    rep: Option<&[i16]>,
+) -> Result<(), DbError> {
+    let mut leaf = group
+        .next_column()
+        .map_err(parquet_error)?
+        .ok_or_else(|| DbError::Other("Parquet schema has fewer columns than the table".to_string()))?;
+    leaf.typed::<T>().write_batch(values, Some(def), rep).map_err(parquet_error)?;
+    leaf.close().map_err(parquet_error)
+}
+
+fn read_column(
+    group: &dyn RowGroupReader,
+    leaf: &mut usize,
+    column: &Column,
+    count: usize,
+) -> Result<Vec<Value>, DbError> {
+    let invalid = || DbError::TypeMismatch(format!("Invalid value in Parquet column '{}'", column.name));
+    let values = match &column.datatype {
+        DataType::Char(_) | DataType::Varchar(_) | DataType::Text | DataType::Enum(_) => {
+            read_leaf::<ByteArrayType>(group, leaf, count, PhysicalType::BYTE_ARRAY, |b| {
+                let s = b.as_utf8().ok()?;
+                Some(match &column.datatype {
+                    DataType::Char(1) => Value::Char(s.chars().next()?),
+                    DataType::Text => Value::Text(s.to_string()),
+                    DataType::Enum(_) => Value::Enum(s.to_string()),
+                    _ => Value::Varchar(s.to_string()),
+                })
+            })
+        }
+        DataType::Boolean => read_leaf::<BoolType>(group, leaf, count, PhysicalType::BOOLEAN, |b| Some(Value::Boolean(b))),
+        DataType::Int => read_leaf::<Int32Type>(group, leaf, count, PhysicalType::INT32, |n| Some(Value::Int(n))),
+        DataType::BigInt => read_leaf::<Int64Type>(group, leaf, count, PhysicalType::INT64, |n| Some(Value::BigInt(n))),
+        DataType::Float => read_leaf::<FloatType>(group, leaf, count, PhysicalType::FLOAT, |f| Some(Value::Float(f))),
+        DataType::Double => read_leaf::<DoubleType>(group, leaf, count, PhysicalType::DOUBLE, |f| Some(Value::Double(f))),
+        DataType::Date => read_leaf::<Int32Type>(group, leaf, count, PhysicalType::INT32, |days| {
+            epoch().checked_add_signed(chrono::Duration::days(days as i64)).map(Value::Date)
+        }),
+        DataType::Time => {
+            let per_second = ticks_per_second(group, *leaf);
+            let time = |ticks: i64| {
+                let seconds = u32::try_from(ticks.div_euclid(per_second)).ok()?;
+                let nanos = (ticks.rem_euclid(per_second) * (1_000_000_000 / per_second)) as u32;
+                NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanos).map(Value::Time)
+            };
+            // Millisecond times are the one unit stored as INT32
+            if group.metadata().column(*leaf).column_type() == PhysicalType::INT32 {
+                read_leaf::<Int32Type>(group, leaf, count, PhysicalType::INT32, |ticks| time(ticks as i64))
+            } else {
+                read_leaf::<Int64Type>(group, leaf, count, PhysicalType::INT64, time)
+            }
+        }
+        DataType::DateTime => {
+            let per_second = ticks_per_second(group, *leaf);
+            read_leaf::<Int64Type>(group, leaf, count, PhysicalType::INT64, |ticks| {
+                let nanos = (ticks.rem_euclid(per_second) * (1_000_000_000 / per_second)) as u32;
+                DateTime::from_timestamp(ticks.div_euclid(per_second), nanos).map(|dt| Value::DateTime(dt.naive_utc()))
+            })
+        }
+        DataType::Decimal(_, scale) => {
+            read_leaf::<FixedLenByteArrayType>(group, leaf, count, PhysicalType::FIXED_LEN_BYTE_ARRAY, |b| {
+                let bytes = b.data();
+                if bytes.is_empty() || bytes.len() > 16 {
+                    return None;
+                }
+                // Sign-extend to 16 bytes; files written elsewhere may use fewer
+                let mut wide = if bytes[0] & 0x80 != 0 { [0xff; 16] } else { [0; 16] };
+                wide[16 - bytes.len()..].copy_from_slice(bytes);
+                Some(Value::Decimal(Decimal {
+                    mantissa: i128::from_be_bytes(wide),
+                    scale: *scale,
+                }))
+            })
+        }
+        DataType::Point => {
+            let lat = read_leaf::<DoubleType>(group, leaf, count, PhysicalType::DOUBLE, |f| Some(Value::Double(f)))?;
+            let lon = read_leaf::<DoubleType>(group, leaf, count, PhysicalType::DOUBLE, |f| Some(Value::Double(f)))?;
+            Ok(lat
+                .into_iter()
+                .zip(lon)
+                .map(|pair| match pair {
+                    (Value::Double(lat), Value::Double(lon)) => Value::Point(lat, lon),
+                    _ => Value::Null,
+                })
+                .collect())
+        }
+        DataType::Set(_) => {
+            let (members, def, rep) = read_levels::<ByteArrayType>(group, leaf, count, PhysicalType::BYTE_ARRAY)?;
+            let mut members = members.into_iter();
+            let mut sets: Vec<Value> = vec![];
+            for (d, r) in def.into_iter().zip(rep) {
+                match (d, r) {
+                    (0, _) => sets.push(Value::Null),
+                    (1, _) => sets.push(Value::Set(vec![])),
+                    (_, r) => {
+                        let member = members.next().and_then(|b| b.as_utf8().ok().map(str::to_string));
+                        let member = member.ok_or_else(invalid)?;
+                        match sets.last_mut() {
+                            Some(Value::Set(items)) if r == 1 => items.push(member),
+                            _ => sets.push(Value::Set(vec![member])),
+                        }
+                    }
+                }
+            }
+            Ok(sets)
+        }
+    }?;
+    if values.len() != count {
+        return Err(invalid());
+    }
+    Ok(values)
+}
+
+// Ticks per second of a Time or Timestamp leaf. save_to_parquet writes micros, but
+// files from elsewhere may hold millis or nanos.
+fn ticks_per_second(group: &dyn RowGroupReader, leaf: usize) -> i64 {
+    match group.metadata().column(leaf).column_descr().logical_type() {
+        Some(LogicalType::Time { unit, .. } | LogicalType::Timestamp { unit, .. }) => match unit {
+            TimeUnit::MILLIS(_) => 1_000,
+            TimeUnit::MICROS(_) => 1_000_000,
+            TimeUnit::NANOS(_) => 1_000_000_000,
+        },
+        _ => 1_000_000,
+    }
+}
+
+// The next leaf's values, one per row, with NULL where the row has none
+fn read_leaf<T: ParquetType>(
+    group: &dyn RowGroupReader,
+    leaf: &mut usize,
+    count: usize,
+    physical: PhysicalType,
+    convert: impl Fn(T::T) -> Option<Value>,
+) -> Result<Vec<Value>, DbError> {
+    let max_def = group.metadata().column(*leaf).column_descr().max_def_level();
+    let name = group.metadata().column(*leaf).column_path().string();
+    let (values, def, _) = read_levels::<T>(group, leaf, count, physical)?;
+    let mut values = values.into_iter();
+    let mut next = || {
+        values
+            .next()
+            .and_then(&convert)
+            .ok_or_else(|| DbError::TypeMismatch(format!("Invalid value in Parquet column '{}'", name)))
+    };
+    if max_def == 0 {
+        return (0..count).map(|_| next()).collect();
+    }
+    def.into_iter()
+        .map(|d| if d == max_def { next() } else { Ok(Value::Null) })
+        .collect()
+}
+
+// Values, definition levels and repetition levels of one leaf
+type Levels<T> = (Vec<<T as ParquetType>::T>, Vec<i16>, Vec<i16>);
+
+// Raw values and definition and repetition levels of the next leaf, after checking it
+// holds the physical type expected
+fn read_levels<T: ParquetType>(
+    group: &dyn RowGroupReader,
+    leaf: &mut usize,
+    count: usize,
+    physical: PhysicalType,
+) -> Result<Levels<T>, DbError> {
+    let found = group.metadata().column(*leaf).column_type();
+    if found != physical {
+        return Err(DbError::SchemaError(format!(
+            "Parquet column {} holds {} where {} was expected",
+            *leaf + 1,
+            found,
+            physical
+        )));
+    }
+    let mut reader = get_typed_column_reader::<T>(group.get_column_reader(*leaf).map_err(parquet_error)?);
+    *leaf += 1;
+    let (mut values, mut def, mut rep) = (vec![], vec![], vec![]);
+    reader
+        .read_records(count, Some(&mut def), Some(&mut rep), &mut values)
+        .map_err(parquet_error)?;
+    Ok((values, def, rep))
+}
+
+fn epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+}
@@ -0,0 +1,168 @@
+use crate::table::data::{AccessPath, Plan, PlanNode, Table};
+use crate::table::filters::FilterExpr;
+use std::fmt;
+
+impl PlanNode {
+    pub fn estimated_rows(&self) -> usize {
+        match self {
+            PlanNode::SeqScan { rows, .. }
+            | PlanNode::IndexScan { rows, .. }
+            | PlanNode::Filter { rows, .. }
+            | PlanNode::HashJoin { rows, .. }
+            | PlanNode::Sort { rows, .. }
+            | PlanNode::Limit { rows, .. } => *rows,
+        }
+    }
+
+    pub fn children(&self) -> Vec<&PlanNode> {
+        match self {
+            PlanNode::SeqScan { .. } | PlanNode::IndexScan { .. } => vec![],
+            PlanNode::Filter { input, .. }
+            | PlanNode::Sort { input, .. }
+            | PlanNode::Limit { input, .. } => vec![input],
+            PlanNode::HashJoin { left, right, .. } => vec![left, right],
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            PlanNode::SeqScan { table, .. } => format!("SeqScan {}", table),
+            PlanNode::IndexScan { table, column, .. } => {
+                format!("IndexScan {} using {}", table, column)
+            }
+            PlanNode::Filter { predicate, .. } => format!("Filter {}", predicate),
+            PlanNode::HashJoin { on, .. } => format!("HashJoin {} = {}", on.0, on.1),
+            PlanNode::Sort { keys, .. } => format!("Sort {}", keys.join(", ")),
+            PlanNode::Limit { count, .. } => format!("Limit {}", count),
+        }
+    }
+
+    fn fmt_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        writeln!(
+            f,
+            "{}{} (rows={})",
+            "  ".repeat(depth),
+            self.label(),
+            self.estimated_rows()
+        )?;
+        for child in self.children() {
+            child.fmt_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Plan {
+    pub fn sort(self, keys: &[&str]) -> Plan {
+        let rows = self.root.estimated_rows();
+        Plan {
+            root: PlanNode::Sort {
+                keys: keys.iter().map(|k| k.to_string()).collect(),
+                input: Box::new(self.root),
+                rows,
+            },
+        }
+    }
+
+    pub fn limit(self, count: usize) -> Plan {
+        let rows = self.root.estimated_rows().min(count);
+        Plan {
+            root: PlanNode::Limit {
+                count,
+                input: Box::new(self.root),
+                rows,
+            },
+        }
+    }
+
+    pub fn hash_join(left: Plan, right: Plan, on: (&str, &str)) -> Plan {
+        // Without statistics, assume each row of the larger side finds one match
+        let rows = left.root.estimated_rows().max(right.root.estimated_rows());
+        Plan {
+            root: PlanNode::HashJoin {
+                on: (on.0.to_string(), on.1.to_string()),
+                left: Box::new(left.root),
+                right: Box::new(right.root),
+                rows,
+            },
+        }
+    }
+
+    pub fn to_dot(&self) -> String {
+        fn walk(node: &PlanNode, next_id: &mut usize, out: &mut String) -> usize {
+            let id = *next_id;
+            *next_id += 1;
+            out.push_str(&format!(
+                "  n{} [label=\"{}\\nrows={}\"];\n",
+                id,
+                node.label().replace('"', "\\\""),
+                node.estimated_rows()
+            ));
+            for child in node.children() {
+                let child_id = walk(child, next_id, out);
+                out.push_str(&format!("  n{} -> n{};\n", id, child_id));
+            }
+            id
+        }
+
+        let mut out = String::from("digraph plan {\n");
+        walk(&self.root, &mut 0, &mut out);
+        out.push_str("}\n");
+        out
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.root.fmt_tree(f, 0)
+    }
+}
+
+impl Table {
+    pub fn plan(&self, expr: Option<&FilterExpr>) -> Plan {
+        let scan = PlanNode::SeqScan {
+            table: self.name.clone(),
+            rows: self.rows.len(),
+        };
+
+        let expr = match expr {
+            Some(expr) => expr,
+            None => return Plan { root: scan },
+        };
+
+        let query = self.explain(expr);
+        let input = match query.access {
+            AccessPath::FullScan => scan,
+            access => PlanNode::IndexScan {
+                table: self.name.clone(),
+                column: access.columns().join(", "),
+                rows: query.estimated_rows,
+            },
+        };
+
+        Plan {
+            root: PlanNode::Filter {
+                predicate: expr.to_string(),
+                input: Box::new(input),
+                rows: query.estimated_rows,
+            },
+        }
+    }
+}
+
+pub(crate) fn estimate_selectivity(expr: &FilterExpr, rows: usize) -> usize {
+    match expr {
+        FilterExpr::Eq(_, _) => rows.div_ceil(10),
+        FilterExpr::Ne(_, _) => rows - rows / 10,
+        FilterExpr::IsNull(_) => rows.div_ceil(10),
+        FilterExpr::IsNotNull(_) => rows - rows / 10,
+        FilterExpr::In(_, values) => (rows.div_ceil(10) * values.len()).min(rows),
+        // Sides are treated as independent
+        FilterExpr::And(a, b) => {
+            (estimate_selectivity(a, rows) * estimate_selectivity(b, rows)).checked_div(rows).unwrap_or(0)
+        }
+        FilterExpr::Or(a, b) => (estimate_selectivity(a, rows) + estimate_selectivity(b, rows)).min(rows),
+        FilterExpr::Not(a) => rows - estimate_selectivity(a, rows),
+        _ => rows.div_ceil(3),
+    }
+}
@@ -0,0 +1,228 @@
+use crate::table::data::{AccessPath, IndexType, QueryPlan, Table, Value};
+use crate::table::filters::FilterExpr;
+use crate::table::plan::estimate_selectivity;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Bound;
+
+impl Table {
+    pub fn explain(&self, expr: &FilterExpr) -> QueryPlan {
+        let access = self.access_path(expr);
+        let estimated_rows = match self.read_path(&access) {
+            Some(candidates) => candidates.len(),
+            None => estimate_selectivity(expr, self.rows.len()),
+        };
+        QueryPlan {
+            table: self.name.clone(),
+            filter: expr.clone(),
+            access,
+            estimated_rows,
+        }
+    }
+
+    // Picks an index for each comparison that one can answer. An AND reads the
+    // intersection of its indexed sides, an OR is only indexed when both sides are, and
+    // a NOT is always a full scan.
+    pub(crate) fn access_path(&self, expr: &FilterExpr) -> AccessPath {
+        match expr {
+            FilterExpr::And(a, b) => match (self.access_path(a), self.access_path(b)) {
+                (AccessPath::FullScan, path) | (path, AccessPath::FullScan) => path,
+                (a, b) => AccessPath::Intersect(Box::new(a), Box::new(b)),
+            },
+            FilterExpr::Or(a, b) => match (self.access_path(a), self.access_path(b)) {
+                (AccessPath::FullScan, _) | (_, AccessPath::FullScan) => AccessPath::FullScan,
+                (a, b) => AccessPath::Union(Box::new(a), Box::new(b)),
+            },
+            FilterExpr::Not(_) => AccessPath::FullScan,
+            _ => self.comparison_path(expr).unwrap_or(AccessPath::FullScan),
+        }
+    }
+
+    fn comparison_path(&self, expr: &FilterExpr) -> Option<AccessPath> {
+        let column = self.canonical_column(expr.column()).ok()?;
+        let index = self.indexes.get(column)?;
+        let column = column.to_string();
+
+        let range = |lower, upper| Some(AccessPath::IndexRange { column: column.clone(), lower, upper });
+        match (index, expr) {
+            (IndexType::Disk(disk), _) if disk.is_stale() => None,
+            (IndexType::Hash(_) | IndexType::BTree(_) | IndexType::Disk(_), FilterExpr::Eq(_, value)) => Some(AccessPath::IndexLookup {
+                column: column.clone(),
+                value: value.clone(),
+            }),
+            // NULLs are indexed under Value::Null like any other value
+            (IndexType::Hash(_) | IndexType::BTree(_) | IndexType::Disk(_), FilterExpr::IsNull(_)) => Some(AccessPath::IndexLookup {
+                column: column.clone(),
+                value: Value::Null,
+            }),
+            (IndexType::BTree(_) | IndexType::Disk(_), FilterExpr::Lt(_, value)) => range(Bound::Unbounded, Bound::Excluded(value.clone())),
+            (IndexType::BTree(_) | IndexType::Disk(_), FilterExpr::Gt(_, value)) => range(Bound::Excluded(value.clone()), Bound::Unbounded),
+            (IndexType::BTree(_) | IndexType::Disk(_), FilterExpr::Le(_, value)) => range(Bound::Unbounded, Bound::Included(value.clone())),
+            (IndexType::BTree(_) | IndexType::Disk(_), FilterExpr::Ge(_, value)) => range(Bound::Included(value.clone()), Bound::Unbounded),
+            (IndexType::BTree(_) | IndexType::Disk(_), FilterExpr::Between(_, low, high)) => {
+                range(Bound::Included(low.clone()), Bound::Included(high.clone()))
+            }
+            (IndexType::Hash(_) | IndexType::BTree(_) | IndexType::Disk(_), FilterExpr::In(_, values)) => {
+                Some(AccessPath::IndexMultiLookup {
+                    column: column.clone(),
+                    values: values.clone(),
+                })
+            }
+            (IndexType::Grid(_), FilterExpr::WithinBox(..) | FilterExpr::WithinRadius(..)) => {
+                let (south_west, north_east) = expr.bounding_box()?;
+                Some(AccessPath::GridScan {
+                    column,
+                    south_west,
+                    north_east,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // Positions of the rows the path reads, or None for a full scan
+    pub(crate) fn read_path(&self, path: &AccessPath) -> Option<Vec<usize>> {
+        match path {
+            AccessPath::FullScan => None,
+            // A collated index bucket can also hold rows that differ from the value, e.g. in
+            // case; the filter is applied to every candidate afterwards
+            AccessPath::IndexLookup { column, value } => {
+                let key = self.index_key(column, value);
+                Some(self.indexes.get(column)?.lookup(key.as_ref())?.into_owned())
+            }
+            AccessPath::IndexMultiLookup { column, values } => {
+                let index = self.indexes.get(column)?;
+                let mut rows = vec![];
+                for value in values {
+                    let key = self.index_key(column, value);
+                    rows.extend(index.lookup(key.as_ref())?.iter().copied());
+                }
+                // Values may repeat, and each bucket is in table order
+                rows.sort_unstable();
+                rows.dedup();
+                Some(rows)
+            }
+            // A collated column's B-tree is ordered by collation keys, as its filters compare
+            AccessPath::IndexRange { column, lower, upper } => {
+                let key = |bound: &Bound<Value>| bound.as_ref().map(|v| self.index_key(column, v).into_owned());
+                let (lower, upper) = (key(lower), key(upper));
+                // BTreeMap::range panics on an empty range, e.g. BETWEEN 5 AND 1
+                if is_empty_range(&lower, &upper) {
+                    return Some(vec![]);
+                }
+                match self.indexes.get(column)? {
+                    IndexType::BTree(map) => Some(
+                    map.range((lower, upper))
+                        .flat_map(|(_, idxs)| idxs.iter().copied())
+                        .collect(),
+                    ),
+                    IndexType::Disk(disk) => disk.range(lower.as_ref(), upper.as_ref()),
+                    _ => None,
+                }
+            }
+            AccessPath::GridScan {
+                column,
+                south_west,
+                north_east,
+            } => match self.indexes.get(column)? {
+                IndexType::Grid(grid) => Some(grid.candidates(*south_west, *north_east)),
+                _ => None,
+            },
+            AccessPath::Intersect(a, b) => match (self.read_path(a), self.read_path(b)) {
+                (Some(a), Some(b)) => {
+                    let b: HashSet<usize> = b.into_iter().collect();
+                    Some(a.into_iter().filter(|i| b.contains(i)).collect())
+                }
+                (a, b) => a.or(b),
+            },
+            // The union comes back in table order
+            AccessPath::Union(a, b) => {
+                let mut union = self.read_path(a)?;
+                union.extend(self.read_path(b)?);
+                union.sort_unstable();
+                union.dedup();
+                Some(union)
+            }
+        }
+    }
+
+    pub(crate) fn index_candidates(&self, expr: &FilterExpr) -> Option<Vec<usize>> {
+        self.read_path(&self.access_path(expr))
+    }
+}
+
+impl AccessPath {
+    // The indexed columns the path reads, in the order it reads them
+    pub fn columns(&self) -> Vec<&str> {
+        let mut columns = vec![];
+        match self {
+            AccessPath::FullScan => {}
+            AccessPath::IndexLookup { column, .. }
+            | AccessPath::IndexMultiLookup { column, .. }
+            | AccessPath::IndexRange { column, .. }
+            | AccessPath::GridScan { column, .. } => columns.push(column.as_str()),
+            AccessPath::Intersect(a, b) | AccessPath::Union(a, b) => {
+                for column in a.columns().into_iter().chain(b.columns()) {
+                    if !columns.contains(&column) {
+                        columns.push(column);
+                    }
+                }
+            }
+        }
+        columns
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            AccessPath::FullScan => "FullScan".to_string(),
+            AccessPath::IndexLookup { column, value } => {
+                format!("IndexLookup {}", FilterExpr::Eq(column.clone(), value.clone()))
+            }
+            AccessPath::IndexMultiLookup { column, values } => {
+                format!("IndexLookup {}", FilterExpr::In(column.clone(), values.clone()))
+            }
+            AccessPath::IndexRange { column, lower, upper } => {
+                let lower = match lower {
+                    Bound::Included(v) => Some(FilterExpr::Ge(column.clone(), v.clone())),
+                    Bound::Excluded(v) => Some(FilterExpr::Gt(column.clone(), v.clone())),
+                    Bound::Unbounded => None,
+                };
+                let upper = match upper {
+                    Bound::Included(v) => Some(FilterExpr::Le(column.clone(), v.clone())),
+                    Bound::Excluded(v) => Some(FilterExpr::Lt(column.clone(), v.clone())),
+                    Bound::Unbounded => None,
+                };
+                let bounds: Vec<String> = lower.into_iter().chain(upper).map(|b| b.to_string()).collect();
+                format!("IndexRange {}", bounds.join(" AND "))
+            }
+            AccessPath::GridScan { column, .. } => format!("GridScan {}", column),
+            AccessPath::Intersect(..) => "Intersect".to_string(),
+            AccessPath::Union(..) => "Union".to_string(),
+        }
+    }
+
+    fn fmt_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        writeln!(f, "{}{}", "  ".repeat(depth), self.label())?;
+        if let AccessPath::Intersect(a, b) | AccessPath::Union(a, b) = self {
+            a.fmt_tree(f, depth + 1)?;
+            b.fmt_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_empty_range(lower: &Bound<Value>, upper: &Bound<Value>) -> bool {
+    match (lower, upper) {
+        (Bound::Included(low), Bound::Included(high)) => low > high,
+        (Bound::Included(low) | Bound::Excluded(low), Bound::Included(high) | Bound::Excluded(high)) => low >= high,
+        _ => false,
+    }
+}
+
+// EXPLAIN output: the filter, then the access path that feeds it
+impl fmt::Display for QueryPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Filter {} on {} (rows={})", self.filter, self.table, self.estimated_rows)?;
+        self.access.fmt_tree(f, 1)
+    }
+}
@@ -0,0 +1,47 @@
+use crate::error::DbError;
+use crate::table::data::{FilterExpr, Table, Value};
+
+pub type BoundPredicate<'a> = Box<dyn Fn(&Vec<Value>) -> bool + 'a>;
+pub type RowFilter<'a> = &'a dyn Fn(&Vec<Value>) -> bool;
+
+// Anything select_where, update_where and delete_where can filter rows with: a plain
+// closure over the row, or a FilterExpr, which can also narrow the scan with an index
+pub trait RowPredicate {
+    // Resolves the predicate against `table`, failing if it names a missing column
+    fn bind<'a>(&'a self, table: &'a Table) -> Result<BoundPredicate<'a>, DbError>;
+
+    // Positions of the rows that may match, when an index can narrow the scan
+    fn candidates(&self, _table: &Table) -> Option<Vec<usize>> {
+        None
+    }
+}
+
+impl<F> RowPredicate for F
+where
+    F: Fn(&Vec<Value>) -> bool,
+{
+    fn bind<'a>(&'a self, _table: &'a Table) -> Result<BoundPredicate<'a>, DbError> {
+        Ok(Box::new(self))
+    }
+}
+
+impl RowPredicate for FilterExpr {
+    fn bind<'a>(&'a self, table: &'a Table) -> Result<BoundPredicate<'a>, DbError> {
+        self.check_columns(table)?;
+        Ok(self.to_predicate(table))
+    }
+
+    fn candidates(&self, table: &Table) -> Option<Vec<usize>> {
+        table.index_candidates(self)
+    }
+}
+
+impl RowPredicate for &FilterExpr {
+    fn bind<'a>(&'a self, table: &'a Table) -> Result<BoundPredicate<'a>, DbError> {
+        (*self).bind(table)
+    }
+
+    fn candidates(&self, table: &Table) -> Option<Vec<usize>> {
+        (*self).candidates(table)
+    }
+}
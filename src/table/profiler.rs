@@ -0,0 +1,112 @@
+use crate::table::data::{QueryProfile, QueryStage, StageTiming};
+use std::cell::RefCell;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// The profile being captured on this thread, if any, and for each stage still running
+// the time spent in stages it started. Queries call `stage` along their real path; with
+// no capture running it only runs the work.
+thread_local! {
+    static CAPTURE: RefCell<Option<(QueryProfile, Vec<Duration>)>> = const { RefCell::new(None) };
+}
+
+impl QueryProfile {
+    // Runs `query` with profiling on and returns its result with where the time went.
+    // Stages are listed as they finish, each timed without the stages it ran inside it.
+    pub fn capture<T>(query: impl FnOnce() -> T) -> (T, QueryProfile) {
+        let outer = CAPTURE.with(|capture| capture.replace(Some((QueryProfile::default(), vec![]))));
+        let result = query();
+        let (profile, _) = CAPTURE.with(|capture| capture.replace(outer)).unwrap_or_default();
+        (result, profile)
+    }
+
+    pub fn record(&mut self, stage: QueryStage, started: Instant) {
+        self.stages.push(StageTiming {
+            stage,
+            elapsed: started.elapsed(),
+        });
+    }
+
+    pub fn time_in(&self, stage: QueryStage) -> Duration {
+        self.stages
+            .iter()
+            .filter(|s| s.stage == stage)
+            .map(|s| s.elapsed)
+            .sum()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|s| s.elapsed).sum()
+    }
+}
+
+fn capturing() -> bool {
+    CAPTURE.with(|capture| capture.borrow().is_some())
+}
+
+// Runs `work` as one stage of the query being captured
+pub(crate) fn stage<T>(stage: QueryStage, work: impl FnOnce() -> T) -> T {
+    if !capturing() {
+        return work();
+    }
+    CAPTURE.with(|capture| {
+        if let Some((_, nested)) = capture.borrow_mut().as_mut() {
+            nested.push(Duration::ZERO);
+        }
+    });
+    let started = Instant::now();
+    let result = work();
+    let elapsed = started.elapsed();
+    CAPTURE.with(|capture| {
+        if let Some((profile, nested)) = capture.borrow_mut().as_mut() {
+            let inner = nested.pop().unwrap_or_default();
+            if let Some(parent) = nested.last_mut() {
+                *parent += elapsed;
+            }
+            profile.stages.push(StageTiming {
+                stage,
+                elapsed: elapsed.saturating_sub(inner),
+            });
+        }
+    });
+    result
+}
+
+// Rows read from a table, counted across every scan the query makes
+pub(crate) fn scanned(rows: usize) {
+    CAPTURE.with(|capture| {
+        if let Some((profile, _)) = capture.borrow_mut().as_mut() {
+            profile.rows_scanned += rows;
+        }
+    });
+}
+
+// Rows a step produced; the last step to finish, the query itself, has the final say
+pub(crate) fn returned(rows: usize) {
+    CAPTURE.with(|capture| {
+        if let Some((profile, _)) = capture.borrow_mut().as_mut() {
+            profile.rows_returned = rows;
+        }
+    });
+}
+
+impl fmt::Display for QueryProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for timing in &self.stages {
+            writeln!(
+                f,
+                "{:<12} {:>10.3} ms",
+                format!("{:?}", timing.stage),
+                timing.elapsed.as_secs_f64() * 1000.0
+            )?;
+        }
+        write!(
+            f,
+            "{:<12} {:>10.3} ms ({} scanned, {} returned)",
+            "Total",
+            self.total().as_secs_f64() * 1000.0,
+            self.rows_scanned,
+            self.rows_returned
+        )
+    }
+}
@@ -0,0 +1,198 @@
+use crate::error::DbError;
+use crate::table::data::{AggFunc, Column, Limit, OrderBy, Table, Value};
+use crate::table::filters::FilterExpr;
+use std::collections::HashSet;
+
+// A query assembled step by step and run in one pass. Whatever order the steps are
+// chained in, they run as SQL would: filter, group, order, select, distinct, then
+// offset and limit.
+pub struct QueryBuilder<'a> {
+    table: &'a Table,
+    filter: Option<FilterExpr>,
+    group_by: Option<String>,
+    aggregates: Vec<(String, AggFunc)>,
+    order: Vec<OrderBy>,
+    columns: Option<Vec<String>>,
+    distinct: bool,
+    limit: Limit,
+}
+
+impl Table {
+    pub fn query(&self) -> QueryBuilder<'_> {
+        QueryBuilder {
+            table: self,
+            filter: None,
+            group_by: None,
+            aggregates: vec![],
+            order: vec![],
+            columns: None,
+            distinct: false,
+            limit: Limit::default(),
+        }
+    }
+}
+
+impl QueryBuilder<'_> {
+    // Filtering more than once keeps the rows matching every filter
+    pub fn filter(mut self, expr: FilterExpr) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(existing) => existing.and(expr),
+            None => expr,
+        });
+        self
+    }
+
+    // Grouped results have the group column followed by one "func(column)" column per aggregate
+    pub fn group_by(mut self, column: &str) -> Self {
+        self.group_by = Some(column.to_string());
+        self
+    }
+
+    pub fn aggregate(mut self, column: &str, func: AggFunc) -> Self {
+        self.aggregates.push((column.to_string(), func));
+        self
+    }
+
+    // Counts each non-NULL value once
+    pub fn count_distinct(self, column: &str) -> Self {
+        self.aggregate(column, AggFunc::CountDistinct)
+    }
+
+    // Joins the non-NULL values of each group, in table order
+    pub fn group_concat(self, column: &str, separator: &str) -> Self {
+        self.aggregate(column, AggFunc::GroupConcat(separator.to_string()))
+    }
+
+    // Ordering sees every column, including ones left out by select
+    pub fn order_by(mut self, order: &[OrderBy]) -> Self {
+        self.order.extend(order.iter().cloned());
+        self
+    }
+
+    pub fn select(mut self, columns: &[&str]) -> Self {
+        self.columns = Some(columns.iter().map(|c| c.to_string()).collect());
+        self
+    }
+
+    pub fn distinct(mut self) -> Self {
+        self.distinct = true;
+        self
+    }
+
+    pub fn limit(mut self, count: usize) -> Self {
+        self.limit.count = Some(count);
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.limit.offset = offset;
+        self
+    }
+
+    pub fn run(&self) -> Result<Table, DbError> {
+        let grouped;
+        let (source, rows) = match &self.group_by {
+            Some(column) => {
+                grouped = self.grouped(column)?;
+                (&grouped, grouped.rows.iter().collect())
+            }
+            None => {
+                let rows = match &self.filter {
+                    Some(expr) => self
+                        .table
+                        .matching_positions(expr)?
+                        .into_iter()
+                        .map(|i| &self.table.rows[i])
+                        .collect(),
+                    None => self.table.live_rows().collect(),
+                };
+                (self.table, rows)
+            }
+        };
+
+        // Distinct needs every row sorted; otherwise only the ones up to the limit
+        let keep = if self.distinct { None } else { self.limit.end() };
+        let rows = if self.order.is_empty() {
+            rows
+        } else {
+            source.sort_rows(rows, &self.order, keep)?
+        };
+
+        let projection = match &self.columns {
+            Some(names) => names
+                .iter()
+                .map(|name| source.resolve_column(name))
+                .collect::<Result<Vec<usize>, DbError>>()?,
+            None => (0..source.columns.len()).collect(),
+        };
+        let mut rows: Vec<Vec<Value>> = rows
+            .into_iter()
+            .map(|row| projection.iter().map(|&i| row[i].clone()).collect())
+            .collect();
+        if self.distinct {
+            let mut seen = HashSet::new();
+            rows.retain(|row| seen.insert(row.clone()));
+        }
+
+        // Constraints do not carry over to a derived result
+        let columns = projection
+            .iter()
+            .map(|&i| Column {
+                name: source.columns[i].name.clone(),
+                datatype: source.columns[i].datatype.clone(),
+                options: vec![],
+            })
+            .collect();
+        let mut result = Table::new(&self.table.name, columns, None);
+        result.rows = self.limit.apply(rows).rows;
+        Ok(result)
+    }
+
+    // One row per group, in key order, with the filter applied before grouping
+    fn grouped(&self, group_col: &str) -> Result<Table, DbError> {
+        let table = self.table;
+        let group_idx = table.resolve_column(group_col)?;
+
+        let mut columns = vec![Column {
+            name: table.columns[group_idx].name.clone(),
+            datatype: table.columns[group_idx].datatype.clone(),
+            options: vec![],
+        }];
+        for (column, func) in &self.aggregates {
+            let datatype = func.datatype(&table.columns[table.resolve_column(column)?].datatype);
+            columns.push(Column {
+                name: func.label(column),
+                datatype,
+                options: vec![],
+            });
+        }
+
+        if let Some(expr) = &self.filter {
+            expr.check_columns(table)?;
+        }
+        let predicate = self.filter.as_ref().map(|expr| expr.to_predicate(table));
+        let agg_cols: Vec<(&str, AggFunc)> = self
+            .aggregates
+            .iter()
+            .map(|(column, func)| (column.as_str(), func.clone()))
+            .collect();
+
+        let mut groups: Vec<(Value, Vec<Value>)> = table
+            .aggregate_group(group_col, &agg_cols, predicate.as_deref())?
+            .into_iter()
+            .map(|(key, results)| (key, results.iter().map(|r| r.as_value()).collect()))
+            .collect();
+        groups.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut result = Table::new(&table.name, columns, None);
+        result.rows = groups
+            .into_iter()
+            .map(|(key, aggregates)| {
+                let mut row = vec![key];
+                row.extend(aggregates);
+                row
+            })
+            .collect();
+        Ok(result)
+    }
+}
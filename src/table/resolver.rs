@@ -0,0 +1,78 @@
+use crate::error::DbError;
+use crate::table::data::{Column, Table};
+
+impl Table {
+    // Finds the column a query refers to. In order of preference:
+    //   1. an exact name match
+    //   2. a case-insensitive match on the full name
+    //   3. `table.column`, where `table` is this table's name
+    //   4. an unqualified name matching the part after the dot of an aliased `alias.column`
+    // A reference matching more than one column at the same step is ambiguous.
+    pub fn resolve_column(&self, name: &str) -> Result<usize, DbError> {
+        resolve_column_in(&self.columns, &self.name, name)
+    }
+
+    // The stored name of the column `name` resolves to, e.g. for looking up its index
+    pub fn canonical_column(&self, name: &str) -> Result<&str, DbError> {
+        self.resolve_column(name).map(|i| self.columns[i].name.as_str())
+    }
+}
+
+pub(crate) fn resolve_column_in(columns: &[Column], table_name: &str, name: &str) -> Result<usize, DbError> {
+    if let Some(i) = columns.iter().position(|c| c.name == name) {
+        return Ok(i);
+    }
+
+    let by_name = |name: &str| unique(columns, table_name, name, |c| c.name.eq_ignore_ascii_case(name));
+
+    if let Some(i) = by_name(name)? {
+        return Ok(i);
+    }
+
+    if let Some((qualifier, column)) = name.split_once('.') {
+        if qualifier.eq_ignore_ascii_case(table_name) {
+            if let Some(i) = by_name(column)? {
+                return Ok(i);
+            }
+            return unqualified(columns, table_name, column, name);
+        }
+    } else {
+        return unqualified(columns, table_name, name, name);
+    }
+
+    Err(not_found(name, table_name))
+}
+
+fn unqualified(columns: &[Column], table_name: &str, column: &str, original: &str) -> Result<usize, DbError> {
+    unique(columns, table_name, original, |c| {
+        c.name
+            .rsplit_once('.')
+            .is_some_and(|(_, suffix)| suffix.eq_ignore_ascii_case(column))
+    })?
+    .ok_or_else(|| not_found(original, table_name))
+}
+
+fn unique<F>(columns: &[Column], table_name: &str, name: &str, matches: F) -> Result<Option<usize>, DbError>
+where
+    F: Fn(&Column) -> bool,
+{
+    let found: Vec<usize> = (0..columns.len()).filter(|&i| matches(&columns[i])).collect();
+    match found.as_slice() {
+        [] => Ok(None),
+        [i] => Ok(Some(*i)),
+        _ => Err(DbError::InvalidArgument(format!(
+            "Column '{}' is ambiguous in '{}': could be {}",
+            name,
+            table_name,
+            found
+                .iter()
+                .map(|&i| columns[i].name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+fn not_found(name: &str, table_name: &str) -> DbError {
+    DbError::NotFound(format!("Column '{}' not found in '{}'", name, table_name))
+}
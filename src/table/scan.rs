@@ -0,0 +1,36 @@
+use crate::error::DbError;
+use crate::table::data::{FilterExpr, Table, Value};
+use crate::table::predicate::RowPredicate;
+
+impl Table {
+    // Yields matching live rows one at a time in table order, without collecting them.
+    // Fails up front if the filter names a column the table does not have.
+    pub fn scan<'a>(
+        &'a self,
+        filter: Option<&'a FilterExpr>,
+    ) -> Result<impl Iterator<Item = &'a [Value]> + 'a, DbError> {
+        let matches = filter.map(|expr| expr.bind(self)).transpose()?;
+        Ok(self
+            .live_rows()
+            .filter(move |row| matches.as_ref().is_none_or(|matches| matches(row)))
+            .map(Vec::as_slice))
+    }
+
+    // Like scan, but visits only the rows an index says may match, in index order. Falls
+    // back to a full scan when no index covers the filter. Only the candidate positions
+    // are collected up front, never the rows.
+    pub fn scan_indexed<'a>(
+        &'a self,
+        filter: &'a FilterExpr,
+    ) -> Result<impl Iterator<Item = &'a [Value]> + 'a, DbError> {
+        let matches = filter.bind(self)?;
+        let positions: Box<dyn Iterator<Item = usize>> = match filter.candidates(self) {
+            Some(candidates) => Box::new(candidates.into_iter()),
+            None => Box::new(0..self.rows.len()),
+        };
+        Ok(positions
+            .filter_map(move |i| self.rows.get(i))
+            .filter(move |row| !self.is_expired(row) && matches(row))
+            .map(Vec::as_slice))
+    }
+}
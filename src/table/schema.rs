@@ -0,0 +1,455 @@
+use crate::error::DbError;
+use crate::storage::atomic::{self, SyncMode};
+use crate::storage::btree::DiskIndex;
+use crate::storage::checksum::FileChecksum;
+use crate::storage::compress::Compression;
+use crate::storage::journal::Journal;
+use crate::table::data::{
+    Collation, Column, ColumnStats, DataType, DefaultExpr, FkAction, GridIndex, IndexType, MaskPolicy, Options, Table,
+    TableStats, Value,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// Schema text is line based: tab-separated fields, the first naming what the line
+// describes. A column line is followed by one line per option, e.g.
+//   column	id	Int
+//   option	NotNull
+//   option	Identity	100	10
+// Enum and Set columns list their values after the type: column	size	Enum	small	large
+// Statistics from analyze follow as a stats line and one column_stats line per column:
+//   stats	120
+//   column_stats	id	0	120	1	120
+// A compressed table ends with the codec its data files use: compression	zstd
+// A sidecar then lists the CSV files saved with it, each with its length and block CRCs:
+//   checksum	db.t.shard0.csv	70000	9a0c55e1	03be7f42
+
+// Columns, primary key, identity counters, statistics and compression of one table. Written
+// to a `.schema` sidecar next to the table's CSV and embedded in the database manifest.
+#[derive(Debug, Default)]
+pub(crate) struct TableSchema {
+    pub columns: Vec<Column>,
+    pub primary_key: Option<Vec<String>>,
+    pub sequences: Vec<(String, i64)>,
+    pub stats: Option<TableStats>,
+    pub compression: Option<Compression>,
+    // Only ever in a sidecar, never the manifest
+    pub checksums: Vec<FileChecksum>,
+}
+
+impl TableSchema {
+    pub(crate) fn lines(table: &Table) -> Vec<String> {
+        let mut lines = vec![];
+        for column in &table.columns {
+            lines.extend(column_lines(column));
+            if let Some(value) = table.sequences.get(&column.name) {
+                lines.push(join_fields(&["sequence", &column.name, &value.to_string()]));
+            }
+        }
+        if let Some(pk) = &table.primary_key {
+            let mut fields = vec!["primary_key"];
+            fields.extend(pk.iter().map(String::as_str));
+            lines.push(join_fields(&fields));
+        }
+        if let Some(stats) = &table.stats {
+            lines.push(join_fields(&["stats", &stats.row_count.to_string()]));
+            // Columns dropped since the analyze are left out
+            for column in stats.columns.iter().filter(|c| table.columns.iter().any(|t| t.name == c.column)) {
+                let mut fields = vec![
+                    "column_stats".to_string(),
+                    column.column.clone(),
+                    column.null_count.to_string(),
+                    column.distinct_count.to_string(),
+                ];
+                fields.extend([&column.min, &column.max].into_iter().flatten().map(Value::to_display_string));
+                let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+                lines.push(join_fields(&fields));
+            }
+        }
+        if let Some(compression) = table.compression {
+            lines.push(join_fields(&["compression", compression.name()]));
+        }
+        lines
+    }
+
+    // Applies a column, option, allowed, sequence, primary_key, statistics, compression or
+    // checksum line; false for any other line
+    pub(crate) fn read_line(&mut self, fields: &[String]) -> Result<bool, DbError> {
+        match fields[0].as_str() {
+            "column" => self.columns.push(parse_column(fields)?),
+            "option" => {
+                let column = self.columns.last_mut().ok_or_else(|| malformed(fields))?;
+                let opt = parse_option(fields, &column.datatype)?;
+                column.options.push(opt);
+            }
+            // Older files kept the lists on a line of their own
+            "allowed" if fields.len() > 1 => {
+                let column = self.columns.iter_mut().find(|c| c.name == fields[1]).ok_or_else(|| malformed(fields))?;
+                if let DataType::Enum(list) | DataType::Set(list) = &mut column.datatype {
+                    *list = fields[2..].to_vec();
+                }
+            }
+            "sequence" if fields.len() == 3 => {
+                let value = fields[2].parse::<i64>().map_err(|_| malformed(fields))?;
+                self.sequences.push((fields[1].clone(), value));
+            }
+            "primary_key" if fields.len() > 1 => self.primary_key = Some(fields[1..].to_vec()),
+            "stats" if fields.len() == 2 => {
+                self.stats = Some(TableStats {
+                    row_count: parse_number(&fields[1], fields)?,
+                    columns: vec![],
+                })
+            }
+            "column_stats" => {
+                let column = parse_column_stats(fields, &self.columns)?;
+                self.stats.as_mut().ok_or_else(|| malformed(fields))?.columns.push(column);
+            }
+            "compression" if fields.len() == 2 => {
+                self.compression = Some(Compression::parse(&fields[1]).ok_or_else(|| malformed(fields))?)
+            }
+            "checksum" if fields.len() >= 3 => self.checksums.push(FileChecksum {
+                file_name: fields[1].clone(),
+                len: parse_number(&fields[2], fields)?,
+                blocks: fields[3..]
+                    .iter()
+                    .map(|crc| u32::from_str_radix(crc, 16).map_err(|_| malformed(fields)))
+                    .collect::<Result<_, _>>()?,
+            }),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    pub(crate) fn read(path: &Path) -> Result<TableSchema, DbError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| DbError::IoError(format!("Failed to read schema file {}: {}", path.display(), e)))?;
+        TableSchema::parse(&contents)
+    }
+
+    // The text `lines` writes, joined by newlines
+    pub(crate) fn parse(contents: &str) -> Result<TableSchema, DbError> {
+        let mut schema = TableSchema::default();
+        for fields in contents.lines().filter(|l| !l.is_empty()).map(split_fields) {
+            if !schema.read_line(&fields)? {
+                return Err(malformed(&fields));
+            }
+        }
+        Ok(schema)
+    }
+
+    // Writes the sidecar for `table`, recording the checksums of the CSV files just saved,
+    // beside `path` for `journal` to rename into place
+    pub(crate) fn write(
+        table: &Table,
+        checksums: &[FileChecksum],
+        path: &Path,
+        sync: SyncMode,
+        journal: &mut Journal,
+    ) -> Result<(), DbError> {
+        let mut lines = TableSchema::lines(table);
+        for checksum in checksums {
+            let mut fields = vec!["checksum".to_string(), checksum.file_name.clone(), checksum.len.to_string()];
+            fields.extend(checksum.blocks.iter().map(|crc| format!("{:08x}", crc)));
+            let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+            lines.push(join_fields(&fields));
+        }
+        lines.push(String::new());
+        let temp = atomic::write_temp(path, sync, |out| Ok(out.write_all(lines.join("\n").as_bytes())?))?;
+        journal.rename(&temp, path);
+        Ok(())
+    }
+
+    // Checks every file the sidecar has a checksum for, which must all still be in `dir_path`
+    pub(crate) fn verify_files(&self, dir_path: &Path) -> Result<(), DbError> {
+        for checksum in &self.checksums {
+            let path = dir_path.join(&checksum.file_name);
+            if !path.exists() {
+                return Err(DbError::NotFound(format!("Data file {} is missing", path.display())));
+            }
+            checksum.verify(&path)?;
+        }
+        Ok(())
+    }
+
+    // Restores what the CSV cannot hold: the identity counters
+    pub(crate) fn fill(&self, table: &mut Table) {
+        table.sequences.extend(self.sequences.iter().cloned());
+        table.stats = self.stats.clone();
+        table.compression = self.compression;
+    }
+}
+
+pub(crate) fn schema_path(dir_path: &Path, db_name: &str, table_name: &str) -> PathBuf {
+    dir_path.join(format!("{}.{}.schema", db_name, table_name))
+}
+
+fn column_lines(column: &Column) -> Vec<String> {
+    let mut fields = vec!["column".to_string(), column.name.clone()];
+    match &column.datatype {
+        DataType::Enum(values) => fields.extend([vec!["Enum".to_string()], values.clone()].concat()),
+        DataType::Set(values) => fields.extend([vec!["Set".to_string()], values.clone()].concat()),
+        datatype => fields.push(format!("{:?}", datatype)),
+    }
+    let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+    let mut lines = vec![join_fields(&fields)];
+    for opt in &column.options {
+        let mut fields = vec!["option".to_string()];
+        fields.extend(option_fields(opt));
+        let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+        lines.push(join_fields(&fields));
+    }
+    lines
+}
+
+pub(crate) fn index_line(column: &str, index: &IndexType) -> String {
+    match index {
+        IndexType::Hash(_) => join_fields(&["index", column, "Hash"]),
+        IndexType::BTree(_) => join_fields(&["index", column, "BTree"]),
+        IndexType::Grid(grid) => join_fields(&["index", column, "Grid", &grid.cell_size.to_string()]),
+        IndexType::Disk(disk) => {
+            join_fields(&["index", column, "Disk", &disk.path().to_string_lossy(), &disk.id().to_string()])
+        }
+    }
+}
+
+// The column a `column` line declares; its options are added by parse_option
+fn parse_column(fields: &[String]) -> Result<Column, DbError> {
+    let [_, name, datatype, values @ ..] = fields else {
+        return Err(malformed(fields));
+    };
+    let datatype = match (parse_datatype(datatype)?, values) {
+        (DataType::Enum(_), values) => DataType::Enum(values.to_vec()),
+        (DataType::Set(_), values) => DataType::Set(values.to_vec()),
+        (datatype, []) => datatype,
+        _ => return Err(malformed(fields)),
+    };
+    Ok(Column {
+        name: name.clone(),
+        datatype,
+        options: vec![],
+    })
+}
+
+fn parse_option(fields: &[String], datatype: &DataType) -> Result<Options, DbError> {
+    let args: Vec<&str> = fields.iter().skip(2).map(String::as_str).collect();
+    let kind = fields.get(1).map(String::as_str).unwrap_or("");
+
+    let opt = match (kind, args.as_slice()) {
+        ("Unique", []) => Options::Unique,
+        ("Truncate", []) => Options::Truncate,
+        ("NotNull", []) => Options::NotNull,
+        ("Autoincrement", []) => Options::Autoincrement,
+        // Written by older versions for DEFAULT CURRENT_TIMESTAMP
+        ("DefaultNow", []) => Options::Default(DefaultExpr::CurrentTimestamp),
+        ("OnUpdateNow", []) => Options::OnUpdateNow,
+        ("References", [table]) => Options::FK(table.to_string()),
+        ("Check", [expr]) => Options::Check(expr.parse().map_err(|_| malformed(fields))?),
+        ("Generated", [expr]) => Options::Generated(expr.parse().map_err(|_| malformed(fields))?),
+        ("Default", []) => Options::Default(DefaultExpr::Literal(Value::Null)),
+        ("Default", [text]) => Options::Default(DefaultExpr::Literal(parse_default(text, datatype)?)),
+        // Older files follow these with the column's allowed values, which are skipped
+        ("DefaultEnum", [val, ..]) => Options::Default(DefaultExpr::Literal(Value::Enum(val.to_string()))),
+        ("DefaultSet", [count, rest @ ..]) => {
+            let count: usize = parse_number(count, fields)?;
+            if count > rest.len() {
+                return Err(malformed(fields));
+            }
+            Options::Default(DefaultExpr::Literal(Value::Set(to_strings(&rest[..count]))))
+        }
+        ("Identity", [start, increment]) => Options::Identity {
+            start: parse_number(start, fields)?,
+            increment: parse_number(increment, fields)?,
+        },
+        ("Ttl", [seconds]) => Options::Ttl(parse_number(seconds, fields)?),
+        ("DefaultExpr", expr) => Options::Default(parse_default_expr(expr, datatype, fields)?),
+        ("OnUpdate", ["Restrict"]) => Options::OnUpdate(FkAction::Restrict),
+        ("OnUpdate", ["Cascade"]) => Options::OnUpdate(FkAction::Cascade),
+        ("OnUpdate", ["SetNull"]) => Options::OnUpdate(FkAction::SetNull),
+        ("Collate", ["Binary"]) => Options::Collate(Collation::Binary),
+        ("Collate", ["CaseInsensitive"]) => Options::Collate(Collation::CaseInsensitive),
+        ("Collate", ["LatinFold"]) => Options::Collate(Collation::LatinFold),
+        // The name LatinFold had before it was renamed
+        ("Collate", ["Locale"]) => Options::Collate(Collation::LatinFold),
+        ("Mask", ["Redact"]) => Options::Mask(MaskPolicy::Redact),
+        ("Mask", ["Hash"]) => Options::Mask(MaskPolicy::Hash),
+        ("Mask", ["ShowLast", n]) => Options::Mask(MaskPolicy::ShowLast(parse_number(n, fields)?)),
+        _ => return Err(malformed(fields)),
+    };
+    Ok(opt)
+}
+
+// Bounds are read back as values of the column's type
+fn parse_column_stats(fields: &[String], columns: &[Column]) -> Result<ColumnStats, DbError> {
+    let (name, nulls, distinct, bounds) = match fields {
+        [_, name, nulls, distinct, bounds @ ..] if bounds.is_empty() || bounds.len() == 2 => {
+            (name, nulls, distinct, bounds)
+        }
+        _ => return Err(malformed(fields)),
+    };
+    let column = columns.iter().find(|c| &c.name == name).ok_or_else(|| malformed(fields))?;
+    let bound = |i: usize| bounds.get(i).map(|text| parse_default(text, &column.datatype)).transpose();
+    Ok(ColumnStats {
+        column: name.clone(),
+        null_count: parse_number(nulls, fields)?,
+        distinct_count: parse_number(distinct, fields)?,
+        min: bound(0)?,
+        max: bound(1)?,
+    })
+}
+
+// The fields default_expr_fields writes, e.g. `Add	30	CurrentDate`
+fn parse_default_expr(args: &[&str], datatype: &DataType, fields: &[String]) -> Result<DefaultExpr, DbError> {
+    match args {
+        ["CurrentDate"] => Ok(DefaultExpr::CurrentDate),
+        ["CurrentTimestamp"] => Ok(DefaultExpr::CurrentTimestamp),
+        ["Literal", text] => Ok(DefaultExpr::Literal(parse_default(text, datatype)?)),
+        ["Add", n, base @ ..] => Ok(DefaultExpr::Add(
+            Box::new(parse_default_expr(base, datatype, fields)?),
+            parse_number(n, fields)?,
+        )),
+        _ => Err(malformed(fields)),
+    }
+}
+
+// (column, index kind to rebuild with create_index_like)
+pub(crate) fn parse_index(fields: &[String]) -> Result<(String, IndexType), DbError> {
+    let kind = match fields.get(2).map(String::as_str) {
+        Some("Hash") if fields.len() == 3 => IndexType::Hash(HashMap::new()),
+        Some("BTree") if fields.len() == 3 => IndexType::BTree(BTreeMap::new()),
+        Some("Grid") if fields.len() == 4 => IndexType::Grid(GridIndex::new(parse_number(&fields[3], fields)?)),
+        Some("Disk") if fields.len() == 5 => {
+            IndexType::Disk(DiskIndex::unbuilt(Path::new(&fields[3]), parse_number(&fields[4], fields)?))
+        }
+        _ => return Err(malformed(fields)),
+    };
+    Ok((fields[1].clone(), kind))
+}
+
+pub(crate) fn parse_datatype(name: &str) -> Result<DataType, DbError> {
+    let unknown = || DbError::SchemaError(format!("Unknown data type '{}'", name));
+    // Written by Debug, so arguments look like "Varchar(255)" or "Decimal(10, 2)"
+    let (base, args) = match name.strip_suffix(')').and_then(|n| n.split_once('(')) {
+        Some((base, args)) => (
+            base,
+            args.split(", ").map(str::parse).collect::<Result<Vec<u32>, _>>().map_err(|_| unknown())?,
+        ),
+        None => (name, vec![]),
+    };
+    let datatype = match (base, args.as_slice()) {
+        ("Char", [length]) => DataType::Char(*length),
+        ("Varchar", [length]) => DataType::Varchar(*length),
+        ("Text", []) => DataType::Text,
+        // Their values follow as separate fields
+        ("Enum", []) => DataType::Enum(vec![]),
+        ("Set", []) => DataType::Set(vec![]),
+        ("Boolean", []) => DataType::Boolean,
+        ("Int", []) => DataType::Int,
+        ("BigInt", []) => DataType::BigInt,
+        ("Float", []) => DataType::Float,
+        ("Double", []) => DataType::Double,
+        ("Date", []) => DataType::Date,
+        ("Time", []) => DataType::Time,
+        ("DateTime", []) => DataType::DateTime,
+        ("Point", []) => DataType::Point,
+        ("Decimal", [precision, scale]) => DataType::Decimal(*precision, *scale),
+        _ => return Err(unknown()),
+    };
+    Ok(datatype)
+}
+
+// Tabs, newlines and backslashes inside a field are escaped so every line splits cleanly
+pub(crate) fn join_fields(fields: &[&str]) -> String {
+    fields
+        .iter()
+        .map(|f| f.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n"))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+pub(crate) fn split_fields(line: &str) -> Vec<String> {
+    line.split('\t')
+        .map(|field| {
+            let mut out = String::with_capacity(field.len());
+            let mut chars = field.chars();
+            while let Some(c) = chars.next() {
+                match (c, chars.clone().next()) {
+                    ('\\', Some('t')) => out.push('\t'),
+                    ('\\', Some('n')) => out.push('\n'),
+                    ('\\', Some('\\')) => out.push('\\'),
+                    _ => {
+                        out.push(c);
+                        continue;
+                    }
+                }
+                chars.next();
+            }
+            out
+        })
+        .collect()
+}
+
+fn option_fields(opt: &Options) -> Vec<String> {
+    let name = |s: &str| s.to_string();
+    match opt {
+        Options::Unique => vec![name("Unique")],
+        Options::Truncate => vec![name("Truncate")],
+        Options::NotNull => vec![name("NotNull")],
+        Options::Autoincrement => vec![name("Autoincrement")],
+        Options::OnUpdateNow => vec![name("OnUpdateNow")],
+        Options::FK(table) => vec![name("References"), table.clone()],
+        Options::Check(expr) => vec![name("Check"), expr.to_string()],
+        Options::Generated(expr) => vec![name("Generated"), expr.to_string()],
+        Options::Default(DefaultExpr::Literal(Value::Null)) => vec![name("Default")],
+        // Enum and Set defaults keep their values verbatim, one per field
+        Options::Default(DefaultExpr::Literal(Value::Enum(val))) => vec![name("DefaultEnum"), val.clone()],
+        Options::Default(DefaultExpr::Literal(Value::Set(items))) => {
+            [vec![name("DefaultSet"), items.len().to_string()], items.clone()].concat()
+        }
+        Options::Default(DefaultExpr::Literal(val)) => vec![name("Default"), val.to_display_string()],
+        // Worked-out defaults are written as their expression tree
+        Options::Default(expr) => [vec![name("DefaultExpr")], default_expr_fields(expr)].concat(),
+        Options::Identity { start, increment } => {
+            vec![name("Identity"), start.to_string(), increment.to_string()]
+        }
+        Options::Ttl(seconds) => vec![name("Ttl"), seconds.to_string()],
+        Options::Mask(MaskPolicy::Redact) => vec![name("Mask"), name("Redact")],
+        Options::Mask(MaskPolicy::Hash) => vec![name("Mask"), name("Hash")],
+        Options::Mask(MaskPolicy::ShowLast(n)) => vec![name("Mask"), name("ShowLast"), n.to_string()],
+        Options::OnUpdate(action) => vec![name("OnUpdate"), format!("{:?}", action)],
+        Options::Collate(collation) => vec![name("Collate"), format!("{:?}", collation)],
+    }
+}
+
+fn default_expr_fields(expr: &DefaultExpr) -> Vec<String> {
+    match expr {
+        DefaultExpr::Literal(value) => vec!["Literal".to_string(), value.to_display_string()],
+        DefaultExpr::CurrentDate => vec!["CurrentDate".to_string()],
+        DefaultExpr::CurrentTimestamp => vec!["CurrentTimestamp".to_string()],
+        DefaultExpr::Add(base, n) => [vec!["Add".to_string(), n.to_string()], default_expr_fields(base)].concat(),
+    }
+}
+
+// Text defaults are kept verbatim; Value::from_str would trim them
+fn parse_default(text: &str, datatype: &DataType) -> Result<Value, DbError> {
+    match datatype {
+        DataType::Varchar(_) => Ok(Value::Varchar(text.to_string())),
+        DataType::Text => Ok(Value::Text(text.to_string())),
+        DataType::Char(1) if text.chars().count() == 1 => Ok(Value::Char(text.chars().next().unwrap())),
+        DataType::Char(n) if *n > 1 => Ok(Value::Varchar(text.to_string())),
+        _ => Value::from_str(text, datatype),
+    }
+}
+
+fn to_strings(fields: &[&str]) -> Vec<String> {
+    fields.iter().map(|f| f.to_string()).collect()
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str, fields: &[String]) -> Result<T, DbError> {
+    text.parse().map_err(|_| malformed(fields))
+}
+
+fn malformed(fields: &[String]) -> DbError {
+    DbError::SchemaError(format!("Malformed schema line '{}'", fields.join(" ")))
+}
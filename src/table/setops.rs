@@ -0,0 +1,130 @@
+use crate::table::data::{Column, Table, Value};
+use std::collections::{HashMap, HashSet};
+
+impl Table {
+    pub fn union(&self, other: &Table) -> Result<Table, String> {
+        let mut seen = HashSet::new();
+        let rows = self
+            .live_rows()
+            .chain(other.live_rows())
+            .filter(|row| seen.insert(*row))
+            .cloned()
+            .collect();
+        self.set_result("union", other, rows)
+    }
+
+    pub fn union_all(&self, other: &Table) -> Result<Table, String> {
+        let rows = self.live_rows().chain(other.live_rows()).cloned().collect();
+        self.set_result("union_all", other, rows)
+    }
+
+    pub fn intersect(&self, other: &Table) -> Result<Table, String> {
+        let right: HashSet<&Vec<Value>> = other.live_rows().collect();
+        let mut seen = HashSet::new();
+        let rows = self
+            .live_rows()
+            .filter(|row| right.contains(row) && seen.insert(*row))
+            .cloned()
+            .collect();
+        self.set_result("intersect", other, rows)
+    }
+
+    // Each row appears min(left count, right count) times
+    pub fn intersect_all(&self, other: &Table) -> Result<Table, String> {
+        let mut remaining = row_counts(other);
+        let rows = self
+            .live_rows()
+            .filter(|row| take_one(&mut remaining, row))
+            .cloned()
+            .collect();
+        self.set_result("intersect_all", other, rows)
+    }
+
+    pub fn except(&self, other: &Table) -> Result<Table, String> {
+        let right: HashSet<&Vec<Value>> = other.live_rows().collect();
+        let mut seen = HashSet::new();
+        let rows = self
+            .live_rows()
+            .filter(|row| !right.contains(row) && seen.insert(*row))
+            .cloned()
+            .collect();
+        self.set_result("except", other, rows)
+    }
+
+    // Each row appears max(left count - right count, 0) times
+    pub fn except_all(&self, other: &Table) -> Result<Table, String> {
+        let mut remaining = row_counts(other);
+        let rows = self
+            .live_rows()
+            .filter(|row| !take_one(&mut remaining, row))
+            .cloned()
+            .collect();
+        self.set_result("except_all", other, rows)
+    }
+
+    fn check_set_compatible(&self, other: &Table) -> Result<(), String> {
+        if self.columns.len() != other.columns.len() {
+            return Err(format!(
+                "Tables '{}' and '{}' have different column counts ({} vs {})",
+                self.name,
+                other.name,
+                self.columns.len(),
+                other.columns.len()
+            ));
+        }
+
+        for (left, right) in self.columns.iter().zip(&other.columns) {
+            if left.name != right.name {
+                return Err(format!(
+                    "Column name mismatch: '{}.{}' vs '{}.{}'",
+                    self.name, left.name, other.name, right.name
+                ));
+            }
+            if left.datatype != right.datatype {
+                return Err(format!(
+                    "Column '{}' has type {:?} in '{}' but {:?} in '{}'",
+                    left.name, left.datatype, self.name, right.datatype, other.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_result(&self, op: &str, other: &Table, rows: Vec<Vec<Value>>) -> Result<Table, String> {
+        self.check_set_compatible(other)?;
+
+        // Constraints do not carry over to a derived result
+        let columns = self
+            .columns
+            .iter()
+            .map(|c| Column {
+                name: c.name.clone(),
+                datatype: c.datatype.clone(),
+                options: vec![],
+            })
+            .collect();
+
+        let mut result = Table::new(&format!("{}_{}_{}", self.name, op, other.name), columns, None);
+        result.rows = rows;
+        Ok(result)
+    }
+}
+
+fn row_counts(table: &Table) -> HashMap<&Vec<Value>, usize> {
+    let mut counts = HashMap::new();
+    for row in table.live_rows() {
+        *counts.entry(row).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn take_one(remaining: &mut HashMap<&Vec<Value>, usize>, row: &Vec<Value>) -> bool {
+    match remaining.get_mut(row) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            true
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,192 @@
+use crate::error::DbError;
+use crate::storage::atomic::SyncMode;
+use crate::storage::checksum::FileChecksum;
+use crate::storage::compress;
+use crate::storage::file::{holds_trees, page_path, stage_rows};
+use crate::storage::journal::{journal_path, Journal};
+use crate::table::data::{Column, Table, Value};
+use crate::table::functions::{read_csv_rows, write_csv_temp};
+use crate::table::schema::{schema_path, TableSchema};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+// Tables with a primary key and more rows than this are split across shard files on save
+pub const SHARD_ROW_THRESHOLD: usize = 10_000;
+
+impl Table {
+    // Number of shard files save_to_dir will write; 1 means a single plain file
+    pub fn shard_count(&self) -> usize {
+        if self.primary_key.is_none() || self.rows.len() <= SHARD_ROW_THRESHOLD {
+            1
+        } else {
+            self.rows.len().div_ceil(SHARD_ROW_THRESHOLD)
+        }
+    }
+
+    pub fn save_sharded(&self, dir_path: &Path, db_name: &str, shards: usize) -> Result<(), DbError> {
+        self.save_sharded_synced(dir_path, db_name, shards, SyncMode::default())
+    }
+
+    pub(crate) fn save_sharded_synced(
+        &self,
+        dir_path: &Path,
+        db_name: &str,
+        shards: usize,
+        sync: SyncMode,
+    ) -> Result<(), DbError> {
+        self.save_through_journal(dir_path, db_name, sync, |table, journal| {
+            table.stage_sharded(dir_path, db_name, shards, sync, journal)
+        })
+    }
+
+    pub(crate) fn stage_sharded(
+        &self,
+        dir_path: &Path,
+        db_name: &str,
+        shards: usize,
+        sync: SyncMode,
+        journal: &mut Journal,
+    ) -> Result<(), DbError> {
+        if shards == 0 {
+            return Err(DbError::InvalidArgument("Shard count must be at least 1".to_string()));
+        }
+        let pk_indices = self.primary_key_indices()?;
+        let existing = shard_paths(dir_path, db_name, &self.name);
+
+        let mut buckets: Vec<Vec<Vec<Value>>> = vec![Vec::new(); shards];
+        for row in &self.rows {
+            let mut hasher = DefaultHasher::new();
+            for &i in &pk_indices {
+                row[i].hash(&mut hasher);
+            }
+            buckets[(hasher.finish() % shards as u64) as usize].push(row.clone());
+        }
+
+        let paths: Vec<PathBuf> = (0..shards)
+            .map(|i| compress::path_for(&shard_path(dir_path, db_name, &self.name, i), self.compression))
+            .collect();
+        let written = thread::scope(|scope| {
+            let handles: Vec<_> = buckets
+                .iter()
+                .zip(&paths)
+                .map(|(rows, path)| scope.spawn(move || write_csv_temp(path, &self.columns, rows, sync)))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|_| Err(DbError::IoError("Shard writer panicked".to_string()))))
+                .collect::<Vec<_>>()
+        });
+        // Every shard written goes in the journal before any failure is returned, so giving
+        // up on the journal removes them all
+        let mut temps = vec![];
+        for (temp, path) in written.into_iter().zip(&paths) {
+            if let Ok(temp) = &temp {
+                journal.rename(temp, path);
+            }
+            temps.push(temp);
+        }
+        let mut checksums = vec![];
+        for (temp, path) in temps.into_iter().zip(&paths) {
+            let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            checksums.push(FileChecksum {
+                file_name,
+                ..FileChecksum::of(&temp?)?
+            });
+        }
+        TableSchema::write(self, &checksums, &schema_path(dir_path, db_name, &self.name), sync, journal)?;
+
+        // An older save's files go along with the new shards and schema coming in
+        let plain = dir_path.join(format!("{}.{}.csv", db_name, self.name));
+        let mut stale: Vec<PathBuf> = existing.into_iter().filter(|p| !paths.contains(p)).collect();
+        stale.extend(compress::variants(&plain));
+        stale.extend(compress::variants(&page_path(dir_path, db_name, &self.name)));
+        stage_stale_files(&stale, journal)
+    }
+
+    fn primary_key_indices(&self) -> Result<Vec<usize>, DbError> {
+        let pk = self
+            .primary_key
+            .as_ref()
+            .ok_or_else(|| {
+                DbError::SchemaError(format!("Table '{}' needs a primary key to be sharded", self.name))
+            })?;
+
+        pk.iter()
+            .map(|name| {
+                self.columns
+                    .iter()
+                    .position(|c| &c.name == name)
+                    .ok_or_else(|| DbError::SchemaError(format!("Primary key column '{}' not found", name)))
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn shard_path(dir_path: &Path, db_name: &str, table_name: &str, shard: usize) -> PathBuf {
+    dir_path.join(format!("{}.{}.shard{}.csv", db_name, table_name, shard))
+}
+
+// Shard files on disk for a table, in shard order
+pub(crate) fn shard_paths(dir_path: &Path, db_name: &str, table_name: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}.{}.shard", db_name, table_name);
+    let mut shards: Vec<(usize, PathBuf)> = fs::read_dir(dir_path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let index = compress::strip_extension(&file_name).strip_prefix(&prefix)?.strip_suffix(".csv")?;
+            Some((index.parse().ok()?, entry.path()))
+        })
+        .collect();
+    shards.sort_by_key(|(i, _)| *i);
+    shards.into_iter().map(|(_, path)| path).collect()
+}
+
+// Reads every shard on its own thread and concatenates the rows in shard order
+pub(crate) fn read_shards(paths: &[PathBuf], columns: &[Column]) -> Result<Vec<Vec<Value>>, DbError> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = paths
+            .iter()
+            .map(|path| scope.spawn(move || read_csv_rows(path, columns)))
+            .collect();
+
+        let mut rows = Vec::new();
+        for handle in handles {
+            rows.extend(handle.join().map_err(|_| DbError::IoError("Shard reader panicked".to_string()))??);
+        }
+        Ok(rows)
+    })
+}
+
+// Removes every file of a dropped table through `journal`: any layout, the schema sidecar
+// and a journal of its own
+pub(crate) fn remove_table_files(dir_path: &Path, db_name: &str, table_name: &str, journal: &mut Journal) {
+    let plain = dir_path.join(format!("{}.{}.csv", db_name, table_name));
+    let mut paths = shard_paths(dir_path, db_name, table_name);
+    paths.extend(compress::variants(&plain));
+    let pages = page_path(dir_path, db_name, table_name);
+    paths.extend(compress::variants(&pages));
+    paths.push(journal_path(&pages));
+    paths.push(schema_path(dir_path, db_name, table_name));
+    for path in paths.iter().filter(|p| p.exists()) {
+        journal.remove(path);
+    }
+}
+
+// Removes files an older save left through `journal`. A page file holding disk indexes
+// stays for them, with its rows emptied.
+pub(crate) fn stage_stale_files(paths: &[PathBuf], journal: &mut Journal) -> Result<(), DbError> {
+    for path in paths.iter().filter(|p| p.exists()) {
+        if holds_trees(path) {
+            stage_rows(path, &[], journal)?;
+        } else {
+            journal.remove(path);
+        }
+    }
+    Ok(())
+}
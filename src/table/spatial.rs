@@ -0,0 +1,173 @@
+use crate::table::data::{FilterExpr, GridIndex, IndexType, Table, UndoEntry, Value};
+use std::collections::HashMap;
+
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+const KM_PER_DEGREE: f64 = 111.32;
+
+impl GridIndex {
+    pub fn new(cell_size: f64) -> Self {
+        GridIndex {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    // NULLs and non-point values have no cell and are left out of the index
+    pub fn cell_of(&self, value: &Value) -> Option<(i64, i64)> {
+        match value {
+            Value::Point(lat, lon) => Some((
+                (lat / self.cell_size).floor() as i64,
+                (lon / self.cell_size).floor() as i64,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, value: &Value, row_idx: usize) {
+        if let Some(cell) = self.cell_of(value) {
+            self.cells.entry(cell).or_default().push(row_idx);
+        }
+    }
+
+    pub fn remove(&mut self, value: &Value, row_idx: usize) {
+        if let Some(cell) = self.cell_of(value) {
+            if let Some(idxs) = self.cells.get_mut(&cell) {
+                idxs.retain(|&i| i != row_idx);
+                if idxs.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    // Rows in every cell overlapping the box; callers still apply the exact filter
+    pub fn candidates(&self, south_west: (f64, f64), north_east: (f64, f64)) -> Vec<usize> {
+        let lat_range = self.cell_coord(south_west.0)..=self.cell_coord(north_east.0);
+        let lon_range = self.cell_coord(south_west.1)..=self.cell_coord(north_east.1);
+
+        let mut rows: Vec<usize> = self
+            .cells
+            .iter()
+            .filter(|((lat, lon), _)| lat_range.contains(lat) && lon_range.contains(lon))
+            .flat_map(|(_, idxs)| idxs.iter().copied())
+            .collect();
+        rows.sort_unstable();
+        rows
+    }
+
+    fn cell_coord(&self, degrees: f64) -> i64 {
+        (degrees / self.cell_size).floor() as i64
+    }
+}
+
+impl Value {
+    pub fn point(lat: f64, lon: f64) -> Result<Self, String> {
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(format!("Latitude {} is out of range", lat));
+        }
+        if !(-180.0..=180.0).contains(&lon) {
+            return Err(format!("Longitude {} is out of range", lon));
+        }
+        Ok(Value::Point(lat, lon))
+    }
+
+    // Accepts "(lat, lon)" or "lat, lon"
+    pub fn from_point_str(s: &str) -> Result<Self, String> {
+        let inner = s.trim().trim_start_matches('(').trim_end_matches(')');
+        let (lat, lon) = inner
+            .split_once(',')
+            .ok_or_else(|| format!("Invalid point '{}': expected (lat, lon)", s))?;
+        let lat = lat.trim().parse().map_err(|_| format!("Invalid latitude '{}'", lat.trim()))?;
+        let lon = lon.trim().parse().map_err(|_| format!("Invalid longitude '{}'", lon.trim()))?;
+        Value::point(lat, lon)
+    }
+
+    pub fn as_point(&self) -> Option<(f64, f64)> {
+        match self {
+            Value::Point(lat, lon) => Some((*lat, *lon)),
+            _ => None,
+        }
+    }
+}
+
+// Great-circle distance between two (lat, lon) pairs
+pub fn distance_km(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat1, lat2) = (a.0.to_radians(), b.0.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (b.1 - a.1).to_radians();
+
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+impl FilterExpr {
+    // (south-west, north-east) corners that enclose every match of a spatial filter
+    pub(crate) fn bounding_box(&self) -> Option<((f64, f64), (f64, f64))> {
+        match self {
+            FilterExpr::WithinBox(_, sw, ne) => Some((sw.as_point()?, ne.as_point()?)),
+            FilterExpr::WithinRadius(_, center, km) => {
+                let (lat, lon) = center.as_point()?;
+                let dlat = km / KM_PER_DEGREE;
+                let cos = lat.to_radians().cos();
+                // Near the poles, or across the antimeridian, search every longitude
+                let dlon = if cos < 1e-6 { 180.0 } else { km / (KM_PER_DEGREE * cos) };
+                let (west, east) = if lon - dlon < -180.0 || lon + dlon > 180.0 {
+                    (-180.0, 180.0)
+                } else {
+                    (lon - dlon, lon + dlon)
+                };
+                Some(((lat - dlat, west), (lat + dlat, east)))
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn matches_spatial(&self, value: &Value) -> bool {
+        let Some(point) = value.as_point() else {
+            return false;
+        };
+        match self {
+            FilterExpr::WithinBox(_, sw, ne) => match (sw.as_point(), ne.as_point()) {
+                (Some(sw), Some(ne)) => {
+                    (sw.0..=ne.0).contains(&point.0) && (sw.1..=ne.1).contains(&point.1)
+                }
+                _ => false,
+            },
+            FilterExpr::WithinRadius(_, center, km) => center
+                .as_point()
+                .is_some_and(|center| distance_km(center, point) <= *km),
+            _ => false,
+        }
+    }
+}
+
+impl Table {
+    // `cell_degrees` trades index size for precision; 0.1 is roughly 11 km of latitude
+    pub fn create_grid_index(&mut self, column_name: &str, cell_degrees: f64) -> Result<(), String> {
+        let replaced = self.indexes.get(column_name).map(IndexType::empty_like);
+        self.build_grid_index(column_name, cell_degrees)?;
+        self.log_change(|_| UndoEntry::CreateIndex {
+            column: column_name.to_string(),
+            replaced,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn build_grid_index(&mut self, column_name: &str, cell_degrees: f64) -> Result<(), String> {
+        let col_index = self
+            .columns
+            .iter()
+            .position(|c| c.name == column_name)
+            .ok_or_else(|| format!("Column '{}' does not exist", column_name))?;
+        if cell_degrees <= 0.0 {
+            return Err("Grid cell size must be positive".to_string());
+        }
+
+        let mut grid = GridIndex::new(cell_degrees);
+        for (i, row) in self.rows.iter().enumerate() {
+            grid.insert(&row[col_index], i);
+        }
+        self.set_index(column_name, IndexType::Grid(grid));
+        Ok(())
+    }
+}
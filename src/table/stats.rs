@@ -0,0 +1,40 @@
+use crate::table::data::{ColumnStats, Table, TableStats, Value};
+use std::collections::HashSet;
+
+impl Table {
+    // Counts and bounds over the live rows. Strings are counted and compared by the
+    // column's collation, so 'Ada' and 'ada' are one value under NOCASE.
+    pub fn analyze(&mut self) -> &TableStats {
+        let rows: Vec<&Vec<Value>> = self.live_rows().collect();
+        let columns = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let collation = column.collation();
+                let values: Vec<&Value> = rows.iter().map(|row| &row[i]).filter(|v| **v != Value::Null).collect();
+                let distinct: HashSet<_> = values.iter().map(|v| collation.key(v)).collect();
+                ColumnStats {
+                    column: column.name.clone(),
+                    null_count: rows.len() - values.len(),
+                    distinct_count: distinct.len(),
+                    min: values.iter().min_by(|a, b| collation.compare(a, b)).map(|v| (*v).clone()),
+                    max: values.iter().max_by(|a, b| collation.compare(a, b)).map(|v| (*v).clone()),
+                }
+            })
+            .collect();
+
+        let row_count = rows.len();
+        self.stats.insert(TableStats { row_count, columns })
+    }
+
+    pub fn stats(&self) -> Option<&TableStats> {
+        self.stats.as_ref()
+    }
+}
+
+impl TableStats {
+    pub fn column(&self, name: &str) -> Option<&ColumnStats> {
+        self.columns.iter().find(|c| c.column == name)
+    }
+}
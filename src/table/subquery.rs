@@ -0,0 +1,82 @@
+use crate::error::DbError;
+use crate::table::data::{AggFunc, AggregationResult, Table, Value};
+use crate::table::filters::FilterExpr;
+use std::collections::HashSet;
+
+// The current row of the outer query, as seen from inside a correlated subquery
+pub struct OuterRow<'a> {
+    table: &'a Table,
+    row: &'a [Value],
+}
+
+impl OuterRow<'_> {
+    pub fn get(&self, column: &str) -> Option<&Value> {
+        self.table
+            .resolve_column(column)
+            .ok()
+            .and_then(|idx| self.row.get(idx))
+    }
+
+    // Filter for inner rows whose `inner_column` equals this row's `outer_column`
+    pub fn correlate(&self, inner_column: &str, outer_column: &str) -> Result<FilterExpr, DbError> {
+        let value = self
+            .get(outer_column)
+            .ok_or_else(|| DbError::NotFound(format!("Column '{}' not found in outer table", outer_column)))?;
+        Ok(FilterExpr::Eq(inner_column.to_string(), value.clone()))
+    }
+}
+
+impl Table {
+    pub fn select_correlated<'a, F>(
+        &'a self,
+        inner: &Table,
+        predicate: F,
+    ) -> Result<Vec<&'a Vec<Value>>, DbError>
+    where
+        F: Fn(&OuterRow, &Table) -> Result<bool, DbError>,
+    {
+        let mut result = vec![];
+        for row in self.live_rows() {
+            let outer = OuterRow { table: self, row };
+            if predicate(&outer, inner)? {
+                result.push(row);
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn exists_where(&self, expr: &FilterExpr) -> Result<bool, DbError> {
+        Ok(!self.select_where_expr(expr)?.is_empty())
+    }
+
+    // WHERE column IN (SELECT other_column FROM other). NULL never matches, on either side.
+    pub fn select_where_in_table<'a>(
+        &'a self,
+        column: &str,
+        other: &Table,
+        other_column: &str,
+    ) -> Result<Vec<&'a Vec<Value>>, DbError> {
+        let idx = self.resolve_column(column)?;
+        let other_idx = other.resolve_column(other_column)?;
+
+        let wanted: HashSet<&Value> = other
+            .live_rows()
+            .map(|row| &row[other_idx])
+            .filter(|val| **val != Value::Null)
+            .collect();
+        self.select_where(|row: &Vec<Value>| wanted.contains(&row[idx]))
+    }
+
+    // Scalar aggregate over the rows matching `expr`, for use as a subquery value
+    pub fn aggregate_where(
+        &self,
+        expr: &FilterExpr,
+        agg_col: &str,
+        func: AggFunc,
+    ) -> Result<AggregationResult, DbError> {
+        let idx = self.resolve_column(agg_col)?;
+
+        let rows = self.select_where_expr(expr)?;
+        func.apply(&rows, idx)
+    }
+}
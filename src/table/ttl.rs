@@ -0,0 +1,59 @@
+use crate::table::data::{Options, Table, Value};
+use chrono::{Duration, Local, NaiveDateTime};
+
+impl Table {
+    // The TTL column and how many seconds past its value a row stays live
+    pub fn ttl(&self) -> Option<(usize, i64)> {
+        self.columns.iter().enumerate().find_map(|(i, col)| {
+            col.options.iter().find_map(|opt| match opt {
+                Options::Ttl(seconds) => Some((i, *seconds)),
+                _ => None,
+            })
+        })
+    }
+
+    pub fn is_expired(&self, row: &[Value]) -> bool {
+        match self.ttl() {
+            Some((col_idx, seconds)) => row_expired(row, col_idx, seconds, Local::now().naive_local()),
+            None => false,
+        }
+    }
+
+    pub fn live_rows(&self) -> Box<dyn Iterator<Item = &Vec<Value>> + '_> {
+        match self.ttl() {
+            Some((col_idx, seconds)) => {
+                let now = Local::now().naive_local();
+                Box::new(
+                    self.rows
+                        .iter()
+                        .filter(move |row| !row_expired(row, col_idx, seconds, now)),
+                )
+            }
+            None => Box::new(self.rows.iter()),
+        }
+    }
+
+    pub fn vacuum(&mut self) -> usize {
+        let expired = self.expired_positions();
+        self.delete_rows(&expired)
+    }
+
+    pub(crate) fn expired_positions(&self) -> Vec<usize> {
+        let Some((col_idx, seconds)) = self.ttl() else {
+            return vec![];
+        };
+        let now = Local::now().naive_local();
+        (0..self.rows.len())
+            .filter(|&i| row_expired(&self.rows[i], col_idx, seconds, now))
+            .collect()
+    }
+}
+
+fn row_expired(row: &[Value], col_idx: usize, seconds: i64, now: NaiveDateTime) -> bool {
+    let stamp = match row.get(col_idx) {
+        Some(Value::DateTime(dt)) => *dt,
+        Some(Value::Date(d)) => d.and_hms_opt(0, 0, 0).unwrap(),
+        _ => return false,
+    };
+    stamp + Duration::seconds(seconds) <= now
+}
@@ -0,0 +1,160 @@
+use crate::error::DbError;
+use crate::table::data::{Column, DataType, Decimal, Options, Table, Value};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Result<Self, String>;
+}
+
+macro_rules! from_value_impl {
+    ($t:ty, $name:expr, $($pat:pat => $out:expr),+ $(,)?) => {
+        impl FromValue for $t {
+            fn from_value(value: &Value) -> Result<Self, String> {
+                match value {
+                    $($pat => Ok($out),)+
+                    other => Err(format!("Expected {}, got {:?}", $name, other)),
+                }
+            }
+        }
+    };
+}
+
+from_value_impl!(i32, "Int", Value::Int(i) => *i);
+from_value_impl!(i64, "BigInt", Value::Int(i) => *i as i64, Value::BigInt(i) => *i);
+from_value_impl!(f32, "Float", Value::Float(f) => *f);
+from_value_impl!(
+    f64,
+    "a number",
+    Value::Int(i) => *i as f64,
+    Value::BigInt(i) => *i as f64,
+    Value::Float(f) => *f as f64,
+    Value::Double(f) => *f,
+);
+from_value_impl!(
+    Decimal,
+    "Decimal",
+    Value::Int(i) => Decimal::new(*i as i128, 0),
+    Value::BigInt(i) => Decimal::new(*i as i128, 0),
+    Value::Decimal(d) => *d,
+);
+from_value_impl!(bool, "Boolean", Value::Boolean(b) => *b);
+from_value_impl!(char, "Char", Value::Char(c) => *c);
+from_value_impl!(
+    String,
+    "a string",
+    Value::Char(c) => c.to_string(),
+    Value::Varchar(s) => s.clone(),
+    Value::Text(s) => s.clone(),
+    Value::Enum(s) => s.clone(),
+);
+from_value_impl!(NaiveDate, "Date", Value::Date(d) => *d);
+from_value_impl!(NaiveTime, "Time", Value::Time(t) => *t);
+from_value_impl!(NaiveDateTime, "DateTime", Value::DateTime(dt) => *dt);
+
+// NULL becomes None; any other value must convert to T
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Null => Ok(None),
+            other => T::from_value(other).map(Some),
+        }
+    }
+}
+
+impl Table {
+    pub fn column_values<T: FromValue>(
+        &self,
+        column: &str,
+    ) -> Result<impl Iterator<Item = Result<T, DbError>> + '_, DbError> {
+        let idx = self.resolve_column(column)?;
+
+        let column = column.to_string();
+        Ok(self.live_rows().map(move |row| {
+            T::from_value(&row[idx]).map_err(|e| DbError::TypeMismatch(format!("Column '{}': {}", column, e)))
+        }))
+    }
+}
+
+// The other direction: a Rust value as the Value stored for it, and the column type it
+// is stored in. Option<T> is stored as T, with None as NULL.
+pub trait ToValue {
+    fn to_value(&self) -> Value;
+}
+
+pub trait ColumnType {
+    fn datatype() -> DataType;
+
+    fn nullable() -> bool {
+        false
+    }
+}
+
+macro_rules! column_type_impl {
+    ($t:ty, $datatype:expr, $value:path) => {
+        impl ToValue for $t {
+            fn to_value(&self) -> Value {
+                $value(self.clone())
+            }
+        }
+
+        impl ColumnType for $t {
+            fn datatype() -> DataType {
+                $datatype
+            }
+        }
+    };
+}
+
+column_type_impl!(i32, DataType::Int, Value::Int);
+column_type_impl!(i64, DataType::BigInt, Value::BigInt);
+column_type_impl!(f32, DataType::Float, Value::Float);
+column_type_impl!(f64, DataType::Double, Value::Double);
+column_type_impl!(bool, DataType::Boolean, Value::Boolean);
+column_type_impl!(char, DataType::Char(1), Value::Char);
+column_type_impl!(String, DataType::Text, Value::Text);
+column_type_impl!(NaiveDate, DataType::Date, Value::Date);
+column_type_impl!(NaiveTime, DataType::Time, Value::Time);
+column_type_impl!(NaiveDateTime, DataType::DateTime, Value::DateTime);
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self) -> Value {
+        self.as_ref().map_or(Value::Null, T::to_value)
+    }
+}
+
+impl<T: ColumnType> ColumnType for Option<T> {
+    fn datatype() -> DataType {
+        T::datatype()
+    }
+
+    fn nullable() -> bool {
+        true
+    }
+}
+
+// A struct stored one field per column, in declaration order. Implemented by the db_row! macro.
+pub trait DbRow: Sized {
+    fn columns() -> Vec<Column>;
+    fn to_row(&self) -> Vec<Value>;
+    fn from_row(row: &[Value]) -> Result<Self, String>;
+}
+
+// The column db_row! declares for a field of type T; fields that are not Options are NOT NULL
+pub fn column_for<T: ColumnType>(name: &str) -> Column {
+    Column {
+        name: name.to_string(),
+        datatype: T::datatype(),
+        options: if T::nullable() { vec![] } else { vec![Options::NotNull] },
+    }
+}
+
+impl Table {
+    pub fn insert_record<T: DbRow>(&mut self, record: &T) -> Result<(), DbError> {
+        self.insert(record.to_row())
+    }
+
+    // Live rows as T, in table order. The table's columns must line up with T's fields.
+    pub fn records<T: DbRow>(&self) -> impl Iterator<Item = Result<T, String>> + '_ {
+        self.live_rows().map(|row| T::from_row(row))
+    }
+}
@@ -0,0 +1,71 @@
+use crate::error::DbError;
+use crate::table::data::{Expr, Table, Value};
+use std::collections::{HashMap, HashSet};
+
+pub type BoundUpdate<'a> = Box<dyn Fn(&[Value]) -> Result<Vec<Option<Value>>, DbError> + 'a>;
+
+// New values by column name; columns left out keep their values
+pub type UpdateSet<'a> = HashMap<&'a str, Value>;
+
+// Anything update_where can change rows with: a new value or None for each column, the
+// same for every row, or (column, expression) assignments worked out from each row
+pub trait RowUpdate {
+    // Resolves the update against `table`, failing if it names a missing column
+    fn bind<'a>(&'a self, table: &'a Table) -> Result<BoundUpdate<'a>, DbError>;
+}
+
+impl RowUpdate for Vec<Option<Value>> {
+    fn bind<'a>(&'a self, _table: &'a Table) -> Result<BoundUpdate<'a>, DbError> {
+        Ok(Box::new(move |_| Ok(self.clone())))
+    }
+}
+
+impl RowUpdate for UpdateSet<'_> {
+    fn bind<'a>(&'a self, table: &'a Table) -> Result<BoundUpdate<'a>, DbError> {
+        let updates = table.by_position(self)?;
+        Ok(Box::new(move |_| Ok(updates.clone())))
+    }
+}
+
+// Every expression sees the row as it was before the update, so `a = b, b = a` swaps
+impl RowUpdate for Vec<(&str, Expr)> {
+    fn bind<'a>(&'a self, table: &'a Table) -> Result<BoundUpdate<'a>, DbError> {
+        let mut assigned = HashSet::new();
+        let mut assignments = vec![];
+        for (name, expr) in self {
+            let i = table.resolve_column(name)?;
+            if !assigned.insert(i) {
+                return Err(DbError::InvalidArgument(format!("Column '{}' is assigned more than once", name)));
+            }
+            assignments.push((i, expr.compile(table)?));
+        }
+
+        let width = table.columns.len();
+        Ok(Box::new(move |row| {
+            let mut updates = vec![None; width];
+            for (i, value) in &assignments {
+                updates[*i] = Some(value(row)?);
+            }
+            Ok(updates)
+        }))
+    }
+}
+
+impl Table {
+    // Puts named values at their columns' positions, with None everywhere else. Errors on
+    // a name matching no column, or two names matching the same one.
+    pub(crate) fn by_position(&self, named: &HashMap<&str, Value>) -> Result<Vec<Option<Value>>, DbError> {
+        let mut values = vec![None; self.columns.len()];
+        for (name, value) in named {
+            let i = self.resolve_column(name)?;
+            if values[i].is_some() {
+                return Err(DbError::InvalidArgument(format!(
+                    "Column '{}' is given more than once",
+                    self.columns[i].name
+                )));
+            }
+            values[i] = Some(value.clone());
+        }
+        Ok(values)
+    }
+}
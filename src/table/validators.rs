@@ -1,14 +1,18 @@
-use chrono::{NaiveDate, NaiveTime, NaiveDateTime};
+use chrono::{Local, NaiveDate, NaiveTime, NaiveDateTime, TimeDelta};
 use std::collections::HashSet;
-use crate::table::data::{Table, Column, Value, Options, DataType, DBRows};
+use std::fmt;
+use crate::error::DbError;
+use crate::table::decimal::MAX_PRECISION;
+use crate::table::numeric::cmp_int_float;
+use crate::table::data::{Table, Collation, Column, Value, Options, DataType, DBRows, Decimal, DefaultExpr, FkAction};
 
 impl Table {
-    pub fn validate_schema(&self) -> Result<(), String> {
+    pub fn validate_schema(&self) -> Result<(), DbError> {
         // Check for duplicate column names
         let mut seen = HashSet::new();
         for col in &self.columns {
             if !seen.insert(&col.name) {
-                return Err(format!("Duplicate column name found: '{}'", col.name));
+                return Err(DbError::SchemaError(format!("Duplicate column name found: '{}'", col.name)));
             }
         }
 
@@ -16,10 +20,10 @@ impl Table {
         if let Some(pk_cols) = &self.primary_key {
             for pk in pk_cols {
                 if !self.columns.iter().any(|c| &c.name == pk) {
-                    return Err(format!(
+                    return Err(DbError::SchemaError(format!(
                         "Primary key column '{}' not found in table '{}'",
                         pk, self.name
-                    ));
+                    )));
                 }
             }
         }
@@ -27,14 +31,47 @@ impl Table {
         // Validate each column individually
         for col in &self.columns {
             col.validate()?;
+            if let Some(expr) = col.generated() {
+                expr.datatype(self)?;
+            }
+            self.validate_checks(col)?;
         }
 
         Ok(())
     }
 
-    pub fn validate_row(&self, row: &DBRows) -> Result<(), String> {
+    // A column's CHECKs may only name this table's columns, and must give a boolean
+    pub(crate) fn validate_checks(&self, column: &Column) -> Result<(), DbError> {
+        for opt in &column.options {
+            if let Options::Check(expr) = opt {
+                // Compiling resolves every column the expression names
+                let _ = expr.compile(self)?;
+                let datatype = expr.datatype(self)?;
+                if datatype != DataType::Boolean {
+                    return Err(DbError::SchemaError(format!(
+                        "CHECK ({}) on column '{}' gives {:?}, not a boolean",
+                        expr, column.name, datatype
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate_row(&self, row: &DBRows) -> Result<(), DbError> {
+        self.validate_row_except(row, None)
+    }
+
+    // Validates a row as a replacement for the row at `skip`, so it doesn't collide with itself
+    pub(crate) fn validate_row_except(&self, row: &DBRows, skip: Option<usize>) -> Result<(), DbError> {
+        self.validate_values(row)?;
+        self.check_unique(row, skip)
+    }
+
+    // Every check on a row that does not look at the other rows
+    pub(crate) fn validate_values(&self, row: &DBRows) -> Result<(), DbError> {
         if row.len() != self.columns.len() {
-            return Err("Row length does not match table column count".to_string());
+            return Err(DbError::InvalidArgument("Row length does not match table column count".to_string()));
         }
 
         for (i, value) in row.iter().enumerate() {
@@ -42,117 +79,181 @@ impl Table {
 
             // 1. Type compatibility
             if !value.is_type_compatible_with(&column.datatype) {
-                return Err(format!(
+                return Err(DbError::TypeMismatch(format!(
                     "Value at column '{}' does not match declared type {:?}",
                     column.name, column.datatype
-                ));
+                )));
             }
 
             // 2. NOT NULL check
             if let Value::Null = value {
                 if column.options.contains(&Options::NotNull) {
-                    return Err(format!(
+                    return Err(DbError::ConstraintViolation(format!(
                         "Column '{}' is NOT NULL but received NULL",
                         column.name
-                    ));
+                    )));
                 }
             }
 
-            // 3. Enum/Set constraints
-            match value {
-                Value::Enum(val, allowed) => {
-                    if !allowed.contains(val) {
-                        return Err(format!(
-                            "Invalid enum value '{}' in column '{}'",
-                            val, column.name
-                        ));
-                    }
+            // 3. Enum/Set membership, string length and decimal precision
+            match (value, &column.datatype) {
+                (Value::Enum(val), DataType::Enum(allowed)) if !allowed.contains(val) => {
+                    return Err(DbError::ConstraintViolation(format!(
+                        "Invalid enum value '{}' in column '{}'",
+                        val, column.name
+                    )));
                 }
-                Value::Set(vals, allowed) => {
+                (Value::Set(vals), DataType::Set(allowed)) => {
                     for v in vals {
                         if !allowed.contains(v) {
-                            return Err(format!(
+                            return Err(DbError::ConstraintViolation(format!(
                                 "Invalid set value '{}' in column '{}'",
                                 v, column.name
-                            ));
+                            )));
                         }
                     }
                 }
+                (Value::Varchar(s), DataType::Char(length) | DataType::Varchar(length))
+                    if s.chars().count() > *length as usize =>
+                {
+                    return Err(DbError::ConstraintViolation(format!(
+                        "Value in column '{}' is longer than {} characters",
+                        column.name, length
+                    )));
+                }
+                (Value::Decimal(d), DataType::Decimal(precision, scale)) if !d.fits(*precision, *scale) => {
+                    return Err(DbError::ConstraintViolation(format!(
+                        "Value {} does not fit DECIMAL({}, {}) column '{}'",
+                        d, precision, scale, column.name
+                    )));
+                }
                 _ => {}
             }
 
-            // 4. CHECK constraint (basic "col = value" syntax)
+            // 4. CHECK constraints fail only when false; NULL (unknown) passes
             for opt in &column.options {
                 if let Options::Check(expr) = opt {
-                    if let Some((col_name, expected_val)) = expr.split_once(" = ") {
-                        if col_name.trim() == column.name {
-                            if let Value::Varchar(actual) = value {
-                                if actual != &expected_val.trim().to_string() {
-                                    return Err(format!(
-                                        "CHECK failed: column '{}' must equal '{}'",
-                                        column.name, expected_val.trim()
-                                    ));
-                                }
-                            }
+                    match expr.evaluate(self, row)? {
+                        Value::Boolean(true) | Value::Null => {}
+                        Value::Boolean(false) => {
+                            return Err(DbError::ConstraintViolation(format!(
+                                "CHECK ({}) failed for column '{}'",
+                                expr, column.name
+                            )));
+                        }
+                        other => {
+                            return Err(DbError::SchemaError(format!("CHECK ({}) gave {}, not a boolean", expr, other)));
                         }
                     }
                 }
             }
         }
+        Ok(())
+    }
 
+    fn check_unique(&self, row: &DBRows, skip: Option<usize>) -> Result<(), DbError> {
         // 5. Unique constraint
         for (i, column) in self.columns.iter().enumerate() {
-            if column.options.contains(&Options::Unique) {
-                let value = &row[i];
-                for existing in &self.rows {
-                    if &existing[i] == value {
-                        return Err(format!(
-                            "Unique constraint violated in column '{}' for value '{}'",
-                            column.name,
-                            value.to_display_string()
-                        ));
-                    }
-                }
+            if column.options.contains(&Options::Unique) && self.find_duplicate(&[i], row, skip).is_some() {
+                return Err(unique_violation(column, &row[i]));
             }
         }
 
         // 6. Primary key uniqueness check
-        if let Some(pk_cols) = &self.primary_key {
-            let pk_indices: Vec<_> = pk_cols
+        if let Some(pk_indices) = self.primary_key_positions() {
+            if self.find_duplicate(&pk_indices, row, skip).is_some() {
+                return Err(primary_key_violation());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn primary_key_positions(&self) -> Option<Vec<usize>> {
+        let pk_cols = self.primary_key.as_ref()?;
+        Some(
+            pk_cols
                 .iter()
                 .filter_map(|pk| self.columns.iter().position(|c| &c.name == pk))
-                .collect();
+                .collect(),
+        )
+    }
 
-            for existing in &self.rows {
-                let is_duplicate = pk_indices.iter().all(|&i| row[i] == existing[i]);
-                if is_duplicate {
-                    return Err("Primary key constraint violated: duplicate entry".to_string());
-                }
-            }
+    // The same checks as check_unique, with the keys of every stored row collected up
+    // front so a batch of rows can be checked without probing the table for each one
+    pub(crate) fn unique_keys(&self) -> Vec<UniqueKey> {
+        self.unique_keys_except(&HashSet::new())
+    }
+
+    // Keys of every stored row but those at `skip`
+    pub(crate) fn unique_keys_except(&self, skip: &HashSet<usize>) -> Vec<UniqueKey> {
+        let mut keys: Vec<UniqueKey> = (0..self.columns.len())
+            .filter(|&i| self.columns[i].options.contains(&Options::Unique))
+            .map(|i| UniqueKey {
+                columns: vec![i],
+                primary: false,
+                taken: HashSet::new(),
+            })
+            .collect();
+        keys.extend(self.primary_key_positions().map(|columns| UniqueKey {
+            columns,
+            primary: true,
+            taken: HashSet::new(),
+        }));
+
+        for key in &mut keys {
+            key.taken = self
+                .rows
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !skip.contains(i))
+                .map(|(_, row)| key.of(self, row))
+                .collect();
         }
+        keys
+    }
 
-        Ok(())
+    // Position of a row other than `skip` that equals `row` on every column in `cols`, as
+    // each column's collation compares them.
+    // Probes the index of the first indexed column, so only rows sharing that value are compared.
+    fn find_duplicate(&self, cols: &[usize], row: &DBRows, skip: Option<usize>) -> Option<usize> {
+        let same = |j: usize| {
+            Some(j) != skip
+                && self
+                    .rows
+                    .get(j)
+                    .is_some_and(|existing| {
+                        cols.iter()
+                            .all(|&i| existing[i].collated_cmp(&row[i], self.columns[i].collation()).is_eq())
+                    })
+        };
+
+        let bucket = cols.iter().find_map(|&i| {
+            let key = self.columns[i].collation().key(&row[i]);
+            self.indexes.get(&self.columns[i].name)?.lookup(key.as_ref())
+        });
+        match bucket {
+            Some(bucket) => bucket.iter().copied().find(|&j| same(j)),
+            None => (0..self.rows.len()).find(|&j| same(j)),
+        }
     }
 
-    pub fn apply_defaults(&self, partial_row: &DBRows) -> Result<DBRows, String> {
+    pub fn apply_defaults(&self, partial_row: &DBRows) -> Result<DBRows, DbError> {
         let mut full_row = Vec::new();
         for (i, col) in self.columns.iter().enumerate() {
             let val = partial_row.get(i).cloned().unwrap_or(Value::Null);
             if let Value::Null = val {
-                if let Some(default) = col.options.iter().find_map(|opt| {
-                    if let Options::Default(v) = opt {
-                        Some(v.clone())
-                    } else {
-                        None
-                    }
+                if let Some(expr) = col.options.iter().find_map(|opt| match opt {
+                    Options::Default(expr) => Some(expr),
+                    _ => None,
                 }) {
-                    full_row.push(default);
+                    full_row.push(expr.evaluate(&col.datatype)?);
                     continue;
                 }
 
-                if col.options.contains(&Options::Autoincrement) {
-                    let id = self.generate_next_autoincrement(i)?;
-                    full_row.push(Value::Int(id));
+                if let Some((start, increment)) = col.identity() {
+                    let id = self.generate_next_identity(i, start, increment)?;
+                    full_row.push(id);
                     continue;
                 }
             }
@@ -161,21 +262,133 @@ impl Table {
         Ok(full_row)
     }
 
-    fn generate_next_autoincrement(&self, column_index: usize) -> Result<i32, String> {
-        let mut max_val = 0;
-        for row in &self.rows {
-            if let Some(Value::Int(v)) = row.get(column_index) {
-                if *v > max_val {
-                    max_val = *v;
+    fn generate_next_identity(
+        &self,
+        column_index: usize,
+        start: i64,
+        increment: i64,
+    ) -> Result<Value, DbError> {
+        // Step past the furthest value in the direction of the increment, never before start
+        let next = match self.sequence_value(column_index, increment) {
+            Some(v) => {
+                let stepped = v.checked_add(increment).ok_or_else(|| {
+                    DbError::ConstraintViolation(format!(
+                        "Identity overflow in column '{}'",
+                        self.columns[column_index].name
+                    ))
+                })?;
+                if increment > 0 {
+                    stepped.max(start)
+                } else {
+                    stepped.min(start)
                 }
             }
+            None => start,
+        };
+
+        match self.columns[column_index].datatype {
+            DataType::BigInt => Ok(Value::BigInt(next)),
+            _ => i32::try_from(next).map(Value::Int).map_err(|_| {
+                DbError::ConstraintViolation(format!(
+                    "Identity value {} out of range for Int column '{}'",
+                    next, self.columns[column_index].name
+                ))
+            }),
+        }
+    }
+
+    // The stored counter, or for a table that has not recorded one yet (e.g. built from
+    // rows directly) the furthest value in its rows
+    fn sequence_value(&self, column_index: usize, increment: i64) -> Option<i64> {
+        if let Some(&v) = self.sequences.get(&self.columns[column_index].name) {
+            return Some(v);
+        }
+        let existing = self.rows.iter().filter_map(|row| identity_value(row.get(column_index)?));
+        if increment > 0 {
+            existing.max()
+        } else {
+            existing.min()
+        }
+    }
+
+    // Called whenever a row is stored, so explicit ids move the counter on as well
+    pub(crate) fn advance_sequences(&mut self, row_idx: usize) {
+        for i in 0..self.columns.len() {
+            let Some((_, increment)) = self.columns[i].identity() else {
+                continue;
+            };
+            let Some(value) = self.rows.get(row_idx).and_then(|row| identity_value(&row[i])) else {
+                continue;
+            };
+            let furthest = match self.sequence_value(i, increment) {
+                Some(v) if increment > 0 => v.max(value),
+                Some(v) => v.min(value),
+                None => value,
+            };
+            self.sequences.insert(self.columns[i].name.clone(), furthest);
         }
-        Ok(max_val + 1)
+    }
+}
+
+// A UNIQUE column or the primary key, with the collated keys already in use
+pub(crate) struct UniqueKey {
+    columns: Vec<usize>,
+    primary: bool,
+    taken: HashSet<Vec<Value>>,
+}
+
+impl UniqueKey {
+    fn of(&self, table: &Table, row: &DBRows) -> Vec<Value> {
+        self.columns
+            .iter()
+            .map(|&i| table.columns[i].collation().key(&row[i]).into_owned())
+            .collect()
+    }
+
+    // Takes `row`'s key, failing if another row already has it
+    pub(crate) fn claim(&mut self, table: &Table, row: &DBRows) -> Result<(), DbError> {
+        if self.taken.insert(self.of(table, row)) {
+            Ok(())
+        } else if self.primary {
+            Err(primary_key_violation())
+        } else {
+            let i = self.columns[0];
+            Err(unique_violation(&table.columns[i], &row[i]))
+        }
+    }
+}
+
+fn unique_violation(column: &Column, value: &Value) -> DbError {
+    DbError::ConstraintViolation(format!(
+        "Unique constraint violated in column '{}' for value '{}'",
+        column.name,
+        value.to_display_string()
+    ))
+}
+
+fn primary_key_violation() -> DbError {
+    DbError::ConstraintViolation("Primary key constraint violated: duplicate entry".to_string())
+}
+
+fn identity_value(value: &Value) -> Option<i64> {
+    match value {
+        Value::Int(v) => Some(*v as i64),
+        Value::BigInt(v) => Some(*v),
+        _ => None,
     }
 }
 
 impl Column {
-    pub fn validate(&self) -> Result<(), String> {
+    // Autoincrement is shorthand for an identity starting at 1 and stepping by 1
+    pub fn identity(&self) -> Option<(i64, i64)> {
+        self.options.iter().find_map(|opt| match opt {
+            Options::Autoincrement => Some((1, 1)),
+            Options::Identity { start, increment } => Some((*start, *increment)),
+            _ => None,
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), DbError> {
         let mut has_not_null = false;
         let mut has_default_null = false;
         let mut has_autoincrement = false;
@@ -183,51 +396,144 @@ impl Column {
         for opt in &self.options {
             match opt {
                 Options::NotNull => has_not_null = true,
-                Options::Default(Value::Null) => has_default_null = true,
+                Options::Default(DefaultExpr::Literal(Value::Null)) => has_default_null = true,
                 Options::Autoincrement => has_autoincrement = true,
+                Options::Identity { increment, .. } => {
+                    if *increment == 0 {
+                        return Err(DbError::SchemaError(format!(
+                            "Column '{}' has IDENTITY with an increment of 0.",
+                            self.name
+                        )));
+                    }
+                    has_autoincrement = true;
+                }
                 _ => {}
             }
         }
 
+        match self.datatype {
+            DataType::Char(0) | DataType::Varchar(0) => {
+                return Err(DbError::SchemaError(format!(
+                    "Column '{}' must allow at least one character",
+                    self.name
+                )));
+            }
+            DataType::Char(_) | DataType::Varchar(_) => {}
+            _ if self.options.contains(&Options::Truncate) => {
+                return Err(DbError::SchemaError(format!(
+                    "Column '{}' is TRUNCATE but {:?} has no length to cut to",
+                    self.name, self.datatype
+                )));
+            }
+            _ => {}
+        }
+
+        let is_string = matches!(self.datatype, DataType::Char(_) | DataType::Varchar(_) | DataType::Text);
+        if self.collation() != Collation::Binary && !is_string {
+            return Err(DbError::SchemaError(format!(
+                "Column '{}' has a {} collation but {:?} is not a string type",
+                self.name,
+                self.collation(),
+                self.datatype
+            )));
+        }
+
+        let fills_itself = has_autoincrement
+            || self.options.iter().any(|opt| {
+                matches!(opt, Options::Default(_) | Options::OnUpdateNow)
+            });
+        if self.generated().is_some() && fills_itself {
+            return Err(DbError::SchemaError(format!(
+                "Column '{}' is generated, so it cannot also have a default or identity",
+                self.name
+            )));
+        }
+
+        if let DataType::Decimal(precision, scale) = self.datatype {
+            if precision == 0 || precision > MAX_PRECISION || scale > precision {
+                return Err(DbError::SchemaError(format!(
+                    "Column '{}' has an invalid DECIMAL({}, {}); precision must be 1 to {} and scale at most the precision",
+                    self.name, precision, scale, MAX_PRECISION
+                )));
+            }
+        }
+
+        if self.options.contains(&Options::OnUpdateNow) && Value::now_for(&self.datatype).is_none() {
+            return Err(DbError::SchemaError(format!(
+                "Column '{}' uses NOW but is not a Date, Time, or DateTime column",
+                self.name
+            )));
+        }
+
+        let has_ttl = self.options.iter().any(|opt| matches!(opt, Options::Ttl(_)));
+        if has_ttl && !(self.datatype == DataType::Date || self.datatype == DataType::DateTime) {
+            return Err(DbError::SchemaError(format!(
+                "Column '{}' has a TTL but is not a Date or DateTime column",
+                self.name
+            )));
+        }
+
         if has_default_null && has_not_null {
-            return Err(format!(
+            return Err(DbError::SchemaError(format!(
                 "Column '{}' cannot have both DEFAULT NULL and NOT NULL",
                 self.name
-            ));
+            )));
+        }
+
+        // Worked out once here so a default that can never fit is caught up front
+        for opt in &self.options {
+            if let Options::Default(expr) = opt {
+                let value = expr.evaluate(&self.datatype)?;
+                if !value.is_type_compatible_with(&self.datatype) {
+                    return Err(DbError::SchemaError(format!(
+                        "Default {} does not fit column '{}' of type {:?}",
+                        expr, self.name, self.datatype
+                    )));
+                }
+            }
+        }
+
+        let has_fk = self.options.iter().any(|opt| matches!(opt, Options::FK(_)));
+        let has_on_update = self.options.iter().any(|opt| matches!(opt, Options::OnUpdate(_)));
+        if has_on_update && !has_fk {
+            return Err(DbError::SchemaError(format!(
+                "Column '{}' has an ON UPDATE action but no REFERENCES",
+                self.name
+            )));
         }
 
         if has_autoincrement {
             if !(self.datatype == DataType::Int || self.datatype == DataType::BigInt) {
-                return Err(format!(
+                return Err(DbError::SchemaError(format!(
                     "Column '{}' has AUTOINCREMENT but is not Int or BigInt.",
                     self.name
-                ));
+                )));
             }
             if !has_not_null {
-                return Err(format!(
+                return Err(DbError::SchemaError(format!(
                     "Column '{}' has AUTOINCREMENT but is not marked NOT NULL.",
                     self.name
-                ));
+                )));
             }
         }
 
         for opt in &self.options {
-            if let Options::Default(Value::Enum(val, allowed)) = opt {
+            if let (Options::Default(DefaultExpr::Literal(Value::Enum(val))), DataType::Enum(allowed)) = (opt, &self.datatype) {
                 if !allowed.contains(val) {
-                    return Err(format!(
+                    return Err(DbError::SchemaError(format!(
                         "Default enum value '{}' not in allowed list for column '{}'",
                         val, self.name
-                    ));
+                    )));
                 }
             }
 
-            if let Options::Default(Value::Set(vals, allowed)) = opt {
+            if let (Options::Default(DefaultExpr::Literal(Value::Set(vals))), DataType::Set(allowed)) = (opt, &self.datatype) {
                 for v in vals {
                     if !allowed.contains(v) {
-                        return Err(format!(
+                        return Err(DbError::SchemaError(format!(
                             "Default set value '{}' not in allowed list for column '{}'",
                             v, self.name
-                        ));
+                        )));
                     }
                 }
             }
@@ -250,13 +556,54 @@ impl Value {
         NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").map(Value::DateTime)
     }
 
+    pub fn now_for(dtype: &DataType) -> Option<Self> {
+        let now = Local::now().naive_local();
+        match dtype {
+            DataType::DateTime => Some(Value::DateTime(now)),
+            DataType::Date => Some(Value::Date(now.date())),
+            DataType::Time => Some(Value::Time(now.time())),
+            _ => None,
+        }
+    }
+
+    pub fn today() -> Self {
+        Value::Date(Local::now().date_naive())
+    }
+
+    // The value to store for a number headed into a column of another numeric type, or
+    // None when it needs no conversion. Integers go into any numeric column and Float
+    // widens to Double; a value that would overflow or round is rejected.
+    pub fn coerce_numeric(&self, dtype: &DataType) -> Result<Option<Value>, DbError> {
+        let integer = match (self, dtype) {
+            (Value::Float(f), DataType::Double) => return Ok(Some(Value::Double(*f as f64))),
+            (Value::Int(i), DataType::BigInt | DataType::Float | DataType::Double) => *i as i64,
+            (Value::BigInt(i), DataType::Int | DataType::Float | DataType::Double) => *i,
+            _ => return Ok(None),
+        };
+        let coerced = match dtype {
+            DataType::Int => i32::try_from(integer).ok().map(Value::Int),
+            DataType::BigInt => Some(Value::BigInt(integer)),
+            DataType::Float => {
+                let f = integer as f32;
+                cmp_int_float(integer, f as f64).is_eq().then_some(Value::Float(f))
+            }
+            _ => {
+                let f = integer as f64;
+                cmp_int_float(integer, f).is_eq().then_some(Value::Double(f))
+            }
+        };
+        coerced.map(Some).ok_or_else(|| {
+            DbError::TypeMismatch(format!("Value {} does not fit a {:?} column exactly", integer, dtype))
+        })
+    }
+
     pub fn is_type_compatible_with(&self, dtype: &DataType) -> bool {
         match (self, dtype) {
-            (Value::Char(_), DataType::Char) => true,
-            (Value::Varchar(_), DataType::Varchar) => true,
+            (Value::Char(_), DataType::Char(_)) => true,
+            (Value::Varchar(_), DataType::Char(_) | DataType::Varchar(_)) => true,
             (Value::Text(_), DataType::Text) => true,
-            (Value::Enum(_, _), DataType::Enum) => true,
-            (Value::Set(_, _), DataType::Set) => true,
+            (Value::Enum(_), DataType::Enum(_)) => true,
+            (Value::Set(_), DataType::Set(_)) => true,
             (Value::Boolean(_), DataType::Boolean) => true,
             (Value::Int(_), DataType::Int) => true,
             (Value::BigInt(_), DataType::BigInt) => true,
@@ -265,6 +612,8 @@ impl Value {
             (Value::Date(_), DataType::Date) => true,
             (Value::Time(_), DataType::Time) => true,
             (Value::DateTime(_), DataType::DateTime) => true,
+            (Value::Point(_, _), DataType::Point) => true,
+            (Value::Decimal(_), DataType::Decimal(_, _)) => true,
             (Value::Null, _) => true, // null is allowed type-wise (check nullability separately)
             _ => false,
         }
@@ -274,8 +623,8 @@ impl Value {
         match self {
             Value::Char(c) => c.to_string(),
             Value::Varchar(s) | Value::Text(s) => s.clone(),
-            Value::Enum(val, _) => val.clone(),
-            Value::Set(vals, _) => format!("{{{}}}", vals.join(",")),
+            Value::Enum(val) => val.clone(),
+            Value::Set(vals) => format!("{{{}}}", vals.join(",")),
             Value::Boolean(b) => b.to_string(),
             Value::Int(i) => i.to_string(),
             Value::BigInt(i) => i.to_string(),
@@ -284,44 +633,138 @@ impl Value {
             Value::Date(d) => d.to_string(),
             Value::Time(t) => t.to_string(),
             Value::DateTime(dt) => dt.to_string(),
+            Value::Point(lat, lon) => format!("({}, {})", lat, lon),
+            Value::Decimal(d) => d.to_string(),
             Value::Null => "NULL".to_string(),
         }
     }
 
-    pub fn from_str(s: &str, dtype: &DataType) -> Result<Self, String> {
+    pub fn from_str(s: &str, dtype: &DataType) -> Result<Self, DbError> {
         let unquoted = s.trim().trim_matches('"');
 
         match dtype {
-            DataType::Char => {
-                if unquoted.len() == 1 {
+            DataType::Char(1) => {
+                if unquoted.chars().count() == 1 {
                     Ok(Value::Char(unquoted.chars().next().unwrap()))
                 } else {
-                    Err("Expected a single character".to_string())
+                    Err(DbError::TypeMismatch("Expected a single character".to_string()))
                 }
             }
-            DataType::Varchar | DataType::Text => Ok(Value::Varchar(unquoted.to_string())),
+            DataType::Char(_) | DataType::Varchar(_) | DataType::Text => Ok(Value::Varchar(unquoted.to_string())),
             DataType::Boolean => match unquoted {
                 "true" => Ok(Value::Boolean(true)),
                 "false" => Ok(Value::Boolean(false)),
-                _ => Err("Invalid boolean value".to_string()),
+                _ => Err(DbError::TypeMismatch("Invalid boolean value".to_string())),
             },
-            DataType::Int => unquoted.parse().map(Value::Int).map_err(|_| "Invalid int".to_string()),
-            DataType::BigInt => unquoted.parse().map(Value::BigInt).map_err(|_| "Invalid bigint".to_string()),
-            DataType::Float => unquoted.parse().map(Value::Float).map_err(|_| "Invalid float".to_string()),
-            DataType::Double => unquoted.parse().map(Value::Double).map_err(|_| "Invalid double".to_string()),
-            DataType::Date => Value::from_date_str(unquoted).map_err(|e| format!("Invalid date: {}", e)),
-            DataType::Time => Value::from_time_str(unquoted).map_err(|e| format!("Invalid time: {}", e)),
-            DataType::DateTime => Value::from_datetime_str(unquoted).map_err(|e| format!("Invalid datetime: {}", e)),
-            DataType::Enum => Ok(Value::Enum(unquoted.to_string(), vec![])), // assumes schema re-validates
-            DataType::Set => {
-                let inner = unquoted.trim_matches(|c| c == '{' || c == '}');
-                let items = if inner.is_empty() {
-                    vec![]
-                } else {
-                    inner.split(',').map(|s| s.trim().to_string()).collect()
+            DataType::Int => unquoted.parse().map(Value::Int).map_err(|_| DbError::TypeMismatch("Invalid int".to_string())),
+            DataType::BigInt => unquoted.parse().map(Value::BigInt).map_err(|_| DbError::TypeMismatch("Invalid bigint".to_string())),
+            DataType::Float => unquoted.parse().map(Value::Float).map_err(|_| DbError::TypeMismatch("Invalid float".to_string())),
+            DataType::Double => unquoted.parse().map(Value::Double).map_err(|_| DbError::TypeMismatch("Invalid double".to_string())),
+            DataType::Date => Value::from_date_str(unquoted).map_err(|e| DbError::TypeMismatch(format!("Invalid date: {}", e))),
+            DataType::Time => Value::from_time_str(unquoted).map_err(|e| DbError::TypeMismatch(format!("Invalid time: {}", e))),
+            DataType::DateTime => {
+                Value::from_datetime_str(unquoted).map_err(|e| DbError::TypeMismatch(format!("Invalid datetime: {}", e)))
+            }
+            DataType::Point => Value::from_point_str(unquoted).map_err(DbError::TypeMismatch),
+            DataType::Decimal(_, scale) => unquoted
+                .parse::<Decimal>()
+                .map_err(DbError::TypeMismatch)?
+                .rescale(*scale)
+                .map(Value::Decimal)
+                .ok_or_else(|| DbError::TypeMismatch(format!("Decimal '{}' is out of range", unquoted))),
+            // Membership is checked against the column's list when the row is validated
+            DataType::Enum(_) => Ok(Value::Enum(unquoted.to_string())),
+            DataType::Set(_) => Ok(Value::Set(set_members(unquoted))),
+        }
+    }
+}
+
+// Members separated by commas, optionally in braces as in a PostgreSQL array, e.g.
+// `a, b` or `{"a b",c}`. A member in double quotes can hold commas and \" escapes.
+fn set_members(text: &str) -> Vec<String> {
+    let inner = text.strip_prefix('{').and_then(|t| t.strip_suffix('}')).unwrap_or(text);
+    if inner.trim().is_empty() {
+        return vec![];
+    }
+    // Space around a member is dropped unless it was quoted
+    let finish = |member: &mut String, was_quoted: bool| {
+        let member = std::mem::take(member);
+        if was_quoted { member } else { member.trim().to_string() }
+    };
+    let mut members = vec![];
+    let (mut member, mut quoted, mut was_quoted, mut chars) = (String::new(), false, false, inner.chars());
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => (quoted, was_quoted) = (!quoted, true),
+            '\\' if quoted => member.extend(chars.next()),
+            ',' if !quoted => {
+                members.push(finish(&mut member, was_quoted));
+                was_quoted = false;
+            }
+            c if c.is_whitespace() && was_quoted && !quoted => {}
+            c => member.push(c),
+        }
+    }
+    members.push(finish(&mut member, was_quoted));
+    members
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.to_display_string())
+    }
+}
+
+impl DefaultExpr {
+    // CURRENT_TIMESTAMP is cut down to the column's type, and CURRENT_DATE in a DateTime
+    // column is midnight
+    pub fn evaluate(&self, dtype: &DataType) -> Result<Value, DbError> {
+        match self {
+            DefaultExpr::Literal(value) => Ok(value.clone()),
+            DefaultExpr::CurrentDate => match (Value::today(), dtype) {
+                (Value::Date(date), DataType::DateTime) => Ok(Value::DateTime(date.and_time(NaiveTime::MIN))),
+                (today, _) => Ok(today),
+            },
+            DefaultExpr::CurrentTimestamp => Value::now_for(dtype).ok_or_else(|| {
+                DbError::SchemaError(format!("CURRENT_TIMESTAMP cannot fill a {:?} column", dtype))
+            }),
+            DefaultExpr::Add(base, n) => {
+                let days = TimeDelta::try_days(*n);
+                let sum = match base.evaluate(dtype)? {
+                    Value::Date(d) => days.and_then(|days| d.checked_add_signed(days)).map(Value::Date),
+                    Value::DateTime(dt) => days.and_then(|days| dt.checked_add_signed(days)).map(Value::DateTime),
+                    Value::Int(v) => i32::try_from(*n).ok().and_then(|n| v.checked_add(n)).map(Value::Int),
+                    Value::BigInt(v) => v.checked_add(*n).map(Value::BigInt),
+                    Value::Float(v) => Some(Value::Float(v + *n as f32)),
+                    Value::Double(v) => Some(Value::Double(v + *n as f64)),
+                    Value::Decimal(v) => v.checked_add(Decimal::new(*n as i128, 0)).map(Value::Decimal),
+                    _ => None,
                 };
-                Ok(Value::Set(items, vec![])) // again, assumes schema re-validates
+                sum.ok_or_else(|| DbError::SchemaError(format!("Cannot work out default {}", self)))
             }
         }
     }
 }
+
+impl fmt::Display for DefaultExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefaultExpr::Literal(value) => write!(f, "{}", value),
+            DefaultExpr::CurrentDate => f.write_str("CURRENT_DATE"),
+            DefaultExpr::CurrentTimestamp => f.write_str("CURRENT_TIMESTAMP"),
+            DefaultExpr::Add(base, n) if *n < 0 => write!(f, "{} - {}", base, n.unsigned_abs()),
+            DefaultExpr::Add(base, n) => write!(f, "{} + {}", base, n),
+        }
+    }
+}
+
+// As written after ON UPDATE in SQL
+impl fmt::Display for FkAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FkAction::Restrict => "RESTRICT",
+            FkAction::Cascade => "CASCADE",
+            FkAction::SetNull => "SET NULL",
+        })
+    }
+}
@@ -0,0 +1,121 @@
+use crate::table::data::{Column, PagerOptions, ResultSet, Table, Value};
+use std::io::{self, BufRead, Write};
+
+impl ResultSet {
+    pub fn new(columns: Vec<Column>, rows: Vec<Vec<Value>>) -> Self {
+        ResultSet { columns, rows }
+    }
+
+    pub fn from_refs(columns: &[Column], rows: Vec<&Vec<Value>>) -> Self {
+        ResultSet {
+            columns: columns.to_vec(),
+            rows: rows.into_iter().cloned().collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn page_count(&self, page_size: usize) -> usize {
+        if page_size == 0 {
+            return 0;
+        }
+        self.rows.len().div_ceil(page_size)
+    }
+
+    pub fn render_page(&self, page: usize, options: &PagerOptions) -> String {
+        let page_size = options.page_size.max(1);
+        let start = (page * page_size).min(self.rows.len());
+        let end = (start + page_size).min(self.rows.len());
+        let width = options.max_column_width.max(1);
+
+        let mut out = String::new();
+
+        if options.show_row_numbers {
+            out.push_str(&format!("| {:>6} ", "#"));
+        }
+        for col in &self.columns {
+            out.push_str(&format!("| {:<width$} ", truncate(&col.name, width), width = width));
+        }
+        out.push_str("|\n");
+
+        for (offset, row) in self.rows[start..end].iter().enumerate() {
+            if options.show_row_numbers {
+                out.push_str(&format!("| {:>6} ", start + offset + 1));
+            }
+            for val in row {
+                out.push_str(&format!(
+                    "| {:<width$} ",
+                    truncate(&val.to_display_string(), width),
+                    width = width
+                ));
+            }
+            out.push_str("|\n");
+        }
+
+        out.push_str(&format!(
+            "-- rows {}-{} of {} (page {}/{}) --\n",
+            if end == 0 { 0 } else { start + 1 },
+            end,
+            self.rows.len(),
+            page + 1,
+            self.page_count(page_size).max(1)
+        ));
+
+        out
+    }
+
+    pub fn page_through(&self, options: &PagerOptions) -> io::Result<()> {
+        let stdin = io::stdin();
+        let stdout = io::stdout();
+        self.page_through_with(options, &mut stdin.lock(), &mut stdout.lock())
+    }
+
+    pub fn page_through_with<R: BufRead, W: Write>(
+        &self,
+        options: &PagerOptions,
+        input: &mut R,
+        output: &mut W,
+    ) -> io::Result<()> {
+        let pages = self.page_count(options.page_size.max(1)).max(1);
+
+        for page in 0..pages {
+            write!(output, "{}", self.render_page(page, options))?;
+
+            if page + 1 == pages {
+                break;
+            }
+
+            write!(output, "Press enter for more, q to quit: ")?;
+            output.flush()?;
+
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 || line.trim().eq_ignore_ascii_case("q") {
+                break;
+            }
+        }
+
+        output.flush()
+    }
+}
+
+impl Table {
+    pub fn to_result_set(&self) -> ResultSet {
+        ResultSet::new(self.columns.clone(), self.rows.clone())
+    }
+}
+
+fn truncate(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        s.to_string()
+    } else {
+        let mut cut: String = s.chars().take(width - 1).collect();
+        cut.push('…');
+        cut
+    }
+}
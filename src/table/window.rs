@@ -0,0 +1,112 @@
+use crate::error::DbError;
+use crate::table::data::{Column, DataType, OrderBy, SortKey, Table, Value, WindowFunc};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+impl Table {
+    // Copies the live rows, in table order, with `func` evaluated over each row's partition
+    // appended as column `output`. Without a partition column the whole table is one partition.
+    pub fn with_window(
+        &self,
+        func: &WindowFunc,
+        partition_by: Option<&str>,
+        order_by: &OrderBy,
+        output: &str,
+    ) -> Result<Table, DbError> {
+        if self.resolve_column(output).is_ok() {
+            return Err(DbError::SchemaError(format!(
+                "Column '{}' already exists in table '{}'",
+                output, self.name
+            )));
+        }
+        let partition_idx = partition_by.map(|name| self.resolve_column(name)).transpose()?;
+        let (source_idx, datatype) = match func {
+            WindowFunc::Lag(name, _) | WindowFunc::Lead(name, _) => {
+                let idx = self.resolve_column(name)?;
+                (Some(idx), self.columns[idx].datatype.clone())
+            }
+            _ => (None, DataType::BigInt),
+        };
+
+        let rows: Vec<&Vec<Value>> = self.live_rows().collect();
+        let keys: Vec<Value> = match &order_by.key {
+            SortKey::Column(name) => {
+                let idx = self.resolve_column(name)?;
+                rows.iter().map(|row| row[idx].clone()).collect()
+            }
+            SortKey::Computed(f) => rows.iter().map(|row| f(row)).collect(),
+        };
+
+        // Positions into `rows`, grouped by partition value in order of first appearance
+        let mut partitions: Vec<Vec<usize>> = vec![];
+        let mut slots: HashMap<&Value, usize> = HashMap::new();
+        for (i, row) in rows.iter().enumerate() {
+            let slot = match partition_idx {
+                Some(idx) => *slots.entry(&row[idx]).or_insert_with(|| {
+                    partitions.push(vec![]);
+                    partitions.len() - 1
+                }),
+                None if partitions.is_empty() => {
+                    partitions.push(vec![]);
+                    0
+                }
+                None => 0,
+            };
+            partitions[slot].push(i);
+        }
+
+        let mut results = vec![Value::Null; rows.len()];
+        for mut partition in partitions {
+            // Stable, so peers keep their table order
+            partition.sort_by(|&a, &b| order_by.compare(&keys[a], &keys[b]));
+
+            let (mut rank, mut dense_rank) = (0, 0);
+            for (pos, &i) in partition.iter().enumerate() {
+                let is_peer = pos > 0 && order_by.compare(&keys[partition[pos - 1]], &keys[i]) == Ordering::Equal;
+                if !is_peer {
+                    rank = pos + 1;
+                    dense_rank += 1;
+                }
+                let source = |other: Option<usize>| match (other.and_then(|p| partition.get(p)), source_idx) {
+                    (Some(&j), Some(idx)) => rows[j][idx].clone(),
+                    _ => Value::Null,
+                };
+                results[i] = match func {
+                    WindowFunc::RowNumber => Value::BigInt(pos as i64 + 1),
+                    WindowFunc::Rank => Value::BigInt(rank as i64),
+                    WindowFunc::DenseRank => Value::BigInt(dense_rank),
+                    WindowFunc::Lag(_, offset) => source(pos.checked_sub(*offset)),
+                    WindowFunc::Lead(_, offset) => source(pos.checked_add(*offset)),
+                };
+            }
+        }
+
+        // Constraints do not carry over to a derived result
+        let mut columns: Vec<Column> = self
+            .columns
+            .iter()
+            .map(|c| Column {
+                name: c.name.clone(),
+                datatype: c.datatype.clone(),
+                options: vec![],
+            })
+            .collect();
+        columns.push(Column {
+            name: output.to_string(),
+            datatype,
+            options: vec![],
+        });
+
+        let mut result = Table::new(&self.name, columns, None);
+        result.rows = rows
+            .into_iter()
+            .zip(results)
+            .map(|(row, value)| {
+                let mut row = row.clone();
+                row.push(value);
+                row
+            })
+            .collect();
+        Ok(result)
+    }
+}
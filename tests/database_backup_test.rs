@@ -0,0 +1,72 @@
+use database::database::config::Durability;
+use database::database::validators::Database;
+use database::table::data::{Column, DataType, Table, Value};
+use std::fs;
+
+fn users() -> Table {
+    let columns = vec![Column {
+        name: "id".to_string(),
+        datatype: DataType::Int,
+        options: vec![],
+    }];
+    let mut table = Table::new("users", columns, None);
+    table.insert(vec![Value::Int(1)]).unwrap();
+    table
+}
+
+#[test]
+fn test_backup_online_writes_every_table() {
+    let dir = std::env::temp_dir().join("backup_online_all");
+    let mut db = Database::new();
+    db.tables.insert("users".to_string(), users());
+
+    let handle = db.backup_online(dir.to_str().unwrap()).unwrap();
+
+    // The database stays usable while the backup is written
+    db.tables
+        .get_mut("users")
+        .unwrap()
+        .insert(vec![Value::Int(2)])
+        .unwrap();
+
+    assert_eq!(handle.wait().unwrap(), 1);
+    let backup = Database::load(dir.to_str().unwrap()).unwrap();
+    assert_eq!(backup.tables["users"].rows, [[Value::Int(1)]]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_backup_online_skips_uncommitted_rows() {
+    let dir = std::env::temp_dir().join("backup_online_txn");
+    let mut table = users();
+    table.begin_transaction().unwrap();
+    table.insert(vec![Value::Int(2)]).unwrap();
+
+    let mut db = Database::new();
+    db.tables.insert("users".to_string(), table);
+
+    db.backup_online(dir.to_str().unwrap()).unwrap().wait().unwrap();
+    let backup = Database::load(dir.to_str().unwrap()).unwrap();
+    assert_eq!(backup.tables["users"].rows, [[Value::Int(1)]]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_backup_online_skips_memory_only_tables() {
+    let dir = std::env::temp_dir().join("backup_online_memory");
+    let _ = fs::remove_dir_all(&dir);
+    let mut db = Database::new();
+    db.tables.insert("users".to_string(), users());
+    let mut scratch = users();
+    scratch.name = "scratch".to_string();
+    db.create_table(scratch, Durability::MemoryOnly).unwrap();
+
+    assert_eq!(db.backup_online(dir.to_str().unwrap()).unwrap().wait().unwrap(), 1);
+    let backup = Database::load(dir.to_str().unwrap()).unwrap();
+    assert!(backup.tables.contains_key("users"));
+    assert!(!backup.tables.contains_key("scratch"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,135 @@
+use database::database::batch::WriteBatch;
+use database::database::storage::StorageEngine;
+use database::database::validators::Database;
+use database::table::data::{Column, DataType, FilterExpr, Table, Value};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Default)]
+struct RecordingEngine {
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl StorageEngine for RecordingEngine {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    fn scan(&self) -> Result<Vec<Vec<Value>>, String> {
+        Ok(vec![])
+    }
+
+    fn insert(&mut self, row: &[Value]) -> Result<(), String> {
+        self.log.lock().unwrap().push(format!("insert {}", row[0]));
+        Ok(())
+    }
+
+    fn update(&mut self, position: usize, row: &[Value]) -> Result<(), String> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("update {} {}", position, row[1]));
+        Ok(())
+    }
+
+    fn delete(&mut self, positions: &[usize]) -> Result<(), String> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("delete {:?}", positions));
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.log.lock().unwrap().push("flush".to_string());
+        Ok(())
+    }
+}
+
+fn people() -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    Table::new("people", columns, Some(vec!["id".to_string()]))
+}
+
+fn row(id: i32, name: &str) -> Vec<Value> {
+    vec![Value::Int(id), Value::Varchar(name.to_string())]
+}
+
+fn id_is(id: i32) -> FilterExpr {
+    FilterExpr::Eq("id".to_string(), Value::Int(id))
+}
+
+#[test]
+fn test_batch_applies_writes_and_flushes_once() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut db = Database::new();
+    db.create_table_with_engine(people(), Box::new(RecordingEngine { log: log.clone() }))
+        .unwrap();
+
+    let affected = db
+        .batch(|b| {
+            b.insert("people", row(1, "Ann"))
+                .insert("people", row(2, "Bob"))
+                .insert("people", row(3, "Cat"));
+            b.update("people", &id_is(2), vec![None, Some(Value::Varchar("Rob".to_string()))]);
+            b.delete("people", &id_is(1));
+        })
+        .unwrap();
+    assert_eq!(affected, 5);
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            "insert 1",
+            "insert 2",
+            "insert 3",
+            "update 1 Rob",
+            "delete [0]",
+            "flush"
+        ]
+    );
+
+    // Indexes are rebuilt once the batch is done
+    let people = &db.tables["people"];
+    let found = people.select_where_expr(&id_is(2)).unwrap();
+    assert_eq!(found, vec![&row(2, "Rob")]);
+    assert!(people.select_where_expr(&id_is(1)).unwrap().is_empty());
+}
+
+#[test]
+fn test_failed_batch_changes_nothing() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut db = Database::new();
+    db.create_table_with_engine(people(), Box::new(RecordingEngine { log: log.clone() }))
+        .unwrap();
+    db.insert("people", row(1, "Ann")).unwrap();
+    log.lock().unwrap().clear();
+
+    let mut batch = WriteBatch::new();
+    batch
+        .insert("people", row(2, "Bob"))
+        .delete("people", &id_is(1))
+        .insert("people", row(2, "Dup"));
+    assert_eq!(batch.len(), 3);
+
+    let err = db.apply_batch(batch).unwrap_err();
+    assert!(err.to_string().contains("Primary key"), "{}", err);
+
+    assert!(log.lock().unwrap().is_empty());
+    let people = &db.tables["people"];
+    assert_eq!(people.rows, vec![row(1, "Ann")]);
+    assert_eq!(people.select_where_expr(&id_is(1)).unwrap().len(), 1);
+    assert!(people.select_where_expr(&id_is(2)).unwrap().is_empty());
+
+    assert!(db.batch(|b| { b.insert("missing", row(1, "x")); }).is_err());
+}
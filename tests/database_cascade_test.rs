@@ -0,0 +1,95 @@
+use database::database::validators::Database;
+use database::error::DbError;
+use database::table::data::{Column, DataType, FilterExpr, FkAction, Options, Table, Value};
+
+fn column(name: &str, options: Vec<Options>) -> Column {
+    Column {
+        name: name.to_string(),
+        datatype: DataType::Int,
+        options,
+    }
+}
+
+fn references(table: &str, action: FkAction) -> Vec<Options> {
+    vec![Options::FK(table.to_string()), Options::OnUpdate(action)]
+}
+
+// users <- accounts (cascade) <- logins (cascade), and users <- audits (set null)
+fn shop() -> Database {
+    let mut db = Database::new();
+    let tables = [
+        Table::new("users", vec![column("id", vec![])], Some(vec!["id".to_string()])),
+        Table::new(
+            "accounts",
+            vec![column("user_id", references("users", FkAction::Cascade))],
+            Some(vec!["user_id".to_string()]),
+        ),
+        Table::new(
+            "logins",
+            vec![column("id", vec![]), column("account_id", references("accounts", FkAction::Cascade))],
+            Some(vec!["id".to_string()]),
+        ),
+        Table::new("audits", vec![column("user_id", references("users", FkAction::SetNull))], None),
+    ];
+    for table in tables {
+        db.tables.insert(table.name.clone(), table);
+    }
+    for (table, row) in [
+        ("users", vec![1]),
+        ("users", vec![2]),
+        ("accounts", vec![1]),
+        ("accounts", vec![2]),
+        ("logins", vec![10, 1]),
+        ("logins", vec![11, 2]),
+        ("audits", vec![1]),
+    ] {
+        db.insert(table, row.into_iter().map(Value::Int).collect()).unwrap();
+    }
+    db
+}
+
+fn id_is(id: i32) -> FilterExpr {
+    FilterExpr::Eq("id".to_string(), Value::Int(id))
+}
+
+#[test]
+fn test_update_table_cascades_through_children() {
+    let mut db = shop();
+    let updated = db.update_table("users", &id_is(1), vec![Some(Value::Int(5))]).unwrap();
+    assert_eq!(updated, 1);
+
+    assert_eq!(db.tables["users"].rows, vec![vec![Value::Int(5)], vec![Value::Int(2)]]);
+    assert_eq!(db.tables["accounts"].rows, vec![vec![Value::Int(5)], vec![Value::Int(2)]]);
+    assert_eq!(db.tables["logins"].rows[0], vec![Value::Int(10), Value::Int(5)]);
+    assert_eq!(db.tables["logins"].rows[1], vec![Value::Int(11), Value::Int(2)]);
+    assert_eq!(db.tables["audits"].rows, vec![vec![Value::Null]]);
+    assert!(!db.in_transaction());
+
+    // Updates that leave the key alone touch no children
+    db.update_table("users", &id_is(2), vec![Some(Value::Int(2))]).unwrap();
+    assert_eq!(db.tables["accounts"].rows[1], vec![Value::Int(2)]);
+}
+
+#[test]
+fn test_restrict_rejects_and_undoes_everything() {
+    let mut db = shop();
+    let mut orders = Table::new("orders", vec![column("account_id", vec![Options::FK("accounts".to_string())])], None);
+    orders.insert(vec![Value::Int(2)]).unwrap();
+    db.tables.insert("orders".to_string(), orders);
+
+    // orders has no ON UPDATE action, so it restricts the change two levels down
+    let result = db.update_table("users", &id_is(2), vec![Some(Value::Int(7))]);
+    assert!(matches!(result, Err(DbError::ConstraintViolation(_))));
+    assert_eq!(db.tables["users"].rows, vec![vec![Value::Int(1)], vec![Value::Int(2)]]);
+    assert_eq!(db.tables["accounts"].rows, vec![vec![Value::Int(1)], vec![Value::Int(2)]]);
+    assert!(!db.in_transaction());
+
+    // Inside a transaction the failed update is undone and the transaction stays open
+    db.begin_transaction().unwrap();
+    db.update_table("users", &id_is(1), vec![Some(Value::Int(8))]).unwrap();
+    assert!(db.update_table("users", &id_is(2), vec![Some(Value::Int(7))]).is_err());
+    assert!(db.in_transaction());
+    db.commit().unwrap();
+    assert_eq!(db.tables["users"].rows, vec![vec![Value::Int(8)], vec![Value::Int(2)]]);
+    assert_eq!(db.tables["accounts"].rows, vec![vec![Value::Int(8)], vec![Value::Int(2)]]);
+}
@@ -0,0 +1,161 @@
+use database::database::config::{DatabaseConfig, Durability};
+use database::database::validators::Database;
+use database::error::DbError;
+use database::table::data::{Column, DataType, DefaultExpr, IndexType, MaskPolicy, Options, Table, Value};
+use std::fs;
+
+fn columns() -> Vec<Column> {
+    vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![Options::NotNull, Options::Identity { start: 100, increment: 10 }],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![Options::Default(DefaultExpr::Literal(Value::Varchar(" tab\there ".to_string()))), Options::Unique],
+        },
+        Column {
+            name: "size".to_string(),
+            datatype: DataType::Enum(vec!["small".to_string(), "large, extra".to_string()]),
+            options: vec![Options::Default(DefaultExpr::Literal(Value::Enum("small".to_string())))],
+        },
+        Column {
+            name: "ssn".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![Options::Mask(MaskPolicy::ShowLast(4)), Options::Check("ssn != ''".parse().unwrap())],
+        },
+        Column {
+            name: "home".to_string(),
+            datatype: DataType::Point,
+            options: vec![Options::Default(DefaultExpr::Literal(Value::Null))],
+        },
+    ]
+}
+
+#[test]
+fn test_save_and_load_round_trips_schema_and_rows() {
+    let root = std::env::temp_dir().join("db_catalog_round_trip_test");
+    let _ = fs::remove_dir_all(&root);
+    let path = root.to_str().unwrap();
+
+    let mut db = Database::with_config(DatabaseConfig::new("shop", &root));
+    let mut people = Table::new("people", columns(), Some(vec!["id".to_string()]));
+    people.indexes.clear();
+    people
+        .insert(vec![
+            Value::Int(1),
+            Value::Varchar("Alice".to_string()),
+            Value::Enum("small".to_string()),
+            Value::Varchar("123-45-6789".to_string()),
+            Value::Point(40.7, -74.0),
+        ])
+        .unwrap();
+    people.create_index("name", true).unwrap();
+    people.create_grid_index("home", 0.5).unwrap();
+    db.create_table(people, Durability::Persistent).unwrap();
+    db.create_table(Table::new("scratch", columns(), None), Durability::MemoryOnly)
+        .unwrap();
+    db.save(path).unwrap();
+
+    let loaded = Database::load(path).unwrap();
+    assert_eq!(loaded.config.name, "shop");
+    assert!(!loaded.tables.contains_key("scratch"));
+
+    let people = &loaded.tables["people"];
+    assert_eq!(people.columns, columns());
+    assert_eq!(people.primary_key, Some(vec!["id".to_string()]));
+    assert_eq!(people.rows, db.tables["people"].rows);
+
+    let mut indexed: Vec<&String> = people.indexes.keys().collect();
+    indexed.sort();
+    assert_eq!(indexed, vec!["home", "name"]);
+    assert!(matches!(people.indexes["name"], IndexType::BTree(_)));
+    match &people.indexes["home"] {
+        IndexType::Grid(grid) => assert_eq!(grid.cell_size, 0.5),
+        other => panic!("expected a grid index, got {:?}", other),
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_save_removes_files_of_dropped_tables() {
+    let root = std::env::temp_dir().join("db_catalog_drop_test");
+    let _ = fs::remove_dir_all(&root);
+    let path = root.to_str().unwrap();
+
+    let mut db = Database::with_config(DatabaseConfig::new("shop", &root));
+    db.create_table(Table::new("a", columns(), None), Durability::Persistent).unwrap();
+    db.create_table(Table::new("b", columns(), None), Durability::Persistent).unwrap();
+    db.save(path).unwrap();
+    assert!(root.join("shop.b.pages").exists());
+
+    db.drop_table("b").unwrap();
+    db.save(path).unwrap();
+    assert!(!root.join("shop.b.pages").exists());
+
+    let loaded = Database::load(path).unwrap();
+    assert_eq!(loaded.tables.len(), 1);
+    assert!(loaded.tables.contains_key("a"));
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_load_reports_missing_and_corrupt_manifests() {
+    let root = std::env::temp_dir().join("db_catalog_corrupt_test");
+    let _ = fs::remove_dir_all(&root);
+    let path = root.to_str().unwrap();
+
+    assert!(matches!(Database::load(path), Err(DbError::IoError(_))));
+
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("MANIFEST"), "database\tshop\ntable\tpeople\ncolumn\tid\tNumber\nend\n").unwrap();
+    assert_eq!(
+        Database::load(path).unwrap_err(),
+        DbError::SchemaError("Unknown data type 'Number'".to_string())
+    );
+
+    fs::write(root.join("MANIFEST"), "database\tshop\ntable\tpeople\ncolumn\tid\tInt\n").unwrap();
+    assert!(matches!(Database::load(path), Err(DbError::SchemaError(_))));
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_a_failed_save_leaves_the_last_one_whole() {
+    let root = std::env::temp_dir().join("db_catalog_failed_save_test");
+    let _ = fs::remove_dir_all(&root);
+    let path = root.to_str().unwrap();
+    let row = |id: i32| {
+        vec![
+            Value::Int(id),
+            Value::Varchar(format!("person {}", id)),
+            Value::Enum("small".to_string()),
+            Value::Varchar("123-45-6789".to_string()),
+            Value::Null,
+        ]
+    };
+
+    let mut db = Database::with_config(DatabaseConfig::new("shop", &root));
+    db.create_table(Table::new("a", columns(), None), Durability::Persistent).unwrap();
+    db.create_table(Table::new("b", columns(), None), Durability::Persistent).unwrap();
+    db.insert("a", row(1)).unwrap();
+    db.save(path).unwrap();
+
+    // Table a's new files are written before b's sidecar fails, but none of them lands
+    db.insert("a", row(2)).unwrap();
+    fs::create_dir(root.join("shop.b.schema.tmp")).unwrap();
+    assert!(db.save(path).is_err());
+    fs::remove_dir(root.join("shop.b.schema.tmp")).unwrap();
+    let names: Vec<String> = fs::read_dir(&root).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+    assert!(names.iter().all(|n| !n.ends_with(".tmp") && !n.ends_with(".journal")), "{:?}", names);
+    assert_eq!(Database::load(path).unwrap().tables["a"].rows.len(), 1);
+
+    db.save(path).unwrap();
+    assert_eq!(Database::load(path).unwrap().tables["a"].rows.len(), 2);
+
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,125 @@
+use database::database::check::IssueKind;
+use database::database::validators::Database;
+use database::table::data::{Column, DataType, Options, Table, Value};
+use std::fs;
+use std::path::PathBuf;
+
+fn people() -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![Options::NotNull],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    Table::new("people", columns, Some(vec!["id".to_string()]))
+}
+
+fn data_dir(name: &str, csv: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("testdb.people.csv"), csv).unwrap();
+    dir
+}
+
+#[test]
+fn test_check_clean_directory() {
+    let dir = data_dir("check_clean", "id,name\n\"1\",\"Alice\"\n\"2\",\"Bob\"\n");
+
+    let mut db = Database::new();
+    let mut table = people();
+    table.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string())]).unwrap();
+    db.tables.insert("people".to_string(), table);
+
+    let report = db.check(dir.to_str().unwrap());
+    assert!(report.is_ok(), "{:?}", report.issues);
+    assert_eq!(report.tables_checked, 1);
+    assert_eq!(report.rows_checked, 2);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_check_reports_type_and_key_problems() {
+    let dir = data_dir(
+        "check_dirty",
+        "id,name\n\"1\",\"Alice\"\n\"1\",\"Bob\"\n\"x\",\"Carl\"\n\"3\"\n",
+    );
+
+    let mut db = Database::new();
+    db.tables.insert("people".to_string(), people());
+
+    let report = db.check(dir.to_str().unwrap());
+    let kinds: Vec<IssueKind> = report.issues.iter().map(|i| i.kind.clone()).collect();
+
+    assert!(kinds.contains(&IssueKind::DuplicateKey));
+    assert!(kinds.contains(&IssueKind::TypeMismatch));
+    assert!(kinds.contains(&IssueKind::FieldCount));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_check_reports_missing_file() {
+    let dir = data_dir("check_missing", "id,name\n");
+
+    let mut db = Database::new();
+    db.tables.insert("people".to_string(), people());
+    let mut other = people();
+    other.name = "orders".to_string();
+    db.tables.insert("orders".to_string(), other);
+
+    let report = db.check(dir.to_str().unwrap());
+    assert!(report
+        .issues
+        .iter()
+        .any(|i| i.kind == IssueKind::MissingFile && i.table == "orders"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_check_reads_every_shard_of_a_table() {
+    let dir = std::env::temp_dir().join("check_shards");
+    let _ = fs::remove_dir_all(&dir);
+
+    let mut table = people();
+    for i in 0..20 {
+        table.insert(vec![Value::Int(i), Value::Varchar(format!("p{}", i))]).unwrap();
+    }
+    table.save_sharded(&dir, "testdb", 3).unwrap();
+
+    let mut db = Database::new();
+    db.tables.insert("people".to_string(), table);
+
+    let report = db.check(dir.to_str().unwrap());
+    assert!(report.is_ok(), "{:?}", report.issues);
+    assert_eq!(report.tables_checked, 1);
+    assert_eq!(report.rows_checked, 20);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_check_finds_duplicate_keys_across_shards() {
+    let dir = std::env::temp_dir().join("check_shard_duplicates");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("testdb.people.shard0.csv"), "id,name\n\"1\",\"Alice\"\n\"2\",\"Bob\"\n").unwrap();
+    fs::write(dir.join("testdb.people.shard1.csv"), "id,name\n\"3\",\"Carl\"\n\"1\",\"Dana\"\n").unwrap();
+
+    let mut db = Database::new();
+    db.tables.insert("people".to_string(), people());
+
+    let report = db.check(dir.to_str().unwrap());
+    let duplicates: Vec<_> = report.issues.iter().filter(|i| i.kind == IssueKind::DuplicateKey).collect();
+    assert_eq!(duplicates.len(), 1, "{:?}", report.issues);
+    assert_eq!(duplicates[0].row, Some(2));
+
+    fs::remove_dir_all(dir).unwrap();
+}
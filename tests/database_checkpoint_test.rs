@@ -0,0 +1,100 @@
+use database::database::validators::Database;
+use database::storage::atomic::{self, SyncMode};
+use database::storage::journal::{journal_path, Journal};
+use database::table::data::{Column, DataType, Table, Value};
+use std::fs;
+
+fn users() -> Table {
+    let columns = vec![Column {
+        name: "id".to_string(),
+        datatype: DataType::Int,
+        options: vec![],
+    }];
+    let mut table = Table::new("users", columns, None);
+    table.insert(vec![Value::Int(1)]).unwrap();
+    table
+}
+
+#[test]
+fn test_checkpoint_flushes_tables_and_advances() {
+    let dir = std::env::temp_dir().join("checkpoint_flush");
+    let _ = fs::remove_dir_all(&dir);
+    let path = dir.to_str().unwrap();
+
+    let mut db = Database::new();
+    db.tables.insert("users".to_string(), users());
+
+    assert_eq!(Database::last_checkpoint(path).unwrap(), None);
+    assert_eq!(db.checkpoint(path).unwrap(), 1);
+    assert_eq!(db.checkpoint(path).unwrap(), 2);
+    assert_eq!(Database::last_checkpoint(path).unwrap(), Some(2));
+    assert_eq!(Database::load(path).unwrap().tables["users"].rows, [[Value::Int(1)]]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_checkpoint_saves_only_committed_rows() {
+    let dir = std::env::temp_dir().join("checkpoint_committed");
+    let _ = fs::remove_dir_all(&dir);
+    let path = dir.to_str().unwrap();
+
+    let mut db = Database::new();
+    db.tables.insert("users".to_string(), users());
+    let users = db.tables.get_mut("users").unwrap();
+    users.begin_transaction().unwrap();
+    users.insert(vec![Value::Int(2)]).unwrap();
+    db.checkpoint(path).unwrap();
+
+    assert_eq!(Database::load(path).unwrap().tables["users"].rows, [[Value::Int(1)]]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_failed_checkpoint_keeps_the_last_one() {
+    let dir = std::env::temp_dir().join("checkpoint_failed");
+    let _ = fs::remove_dir_all(&dir);
+    let path = dir.to_str().unwrap();
+
+    let mut db = Database::new();
+    db.tables.insert("users".to_string(), users());
+    db.checkpoint(path).unwrap();
+
+    db.tables.get_mut("users").unwrap().insert(vec![Value::Int(2)]).unwrap();
+    let record = atomic::temp_path(&dir.join("CHECKPOINT"));
+    fs::create_dir(&record).unwrap();
+    assert!(db.checkpoint(path).is_err());
+    fs::remove_dir(&record).unwrap();
+    assert_eq!(Database::last_checkpoint(path).unwrap(), Some(1));
+    assert_eq!(Database::load(path).unwrap().tables["users"].rows, [[Value::Int(1)]]);
+    assert!(fs::read_dir(&dir).unwrap().all(|entry| entry.unwrap().path().extension().unwrap_or_default() != "tmp"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_checkpoint_cut_short_by_a_crash_is_finished() {
+    let dir = std::env::temp_dir().join("checkpoint_crash");
+    let _ = fs::remove_dir_all(&dir);
+    let path = dir.to_str().unwrap();
+
+    let mut db = Database::new();
+    db.tables.insert("users".to_string(), users());
+    db.checkpoint(path).unwrap();
+
+    // The second checkpoint's journal is durable, but none of it is in place yet. It's
+    // the catalog's journal, so loading would finish it too.
+    let record = dir.join("CHECKPOINT");
+    fs::write(atomic::temp_path(&record), "2").unwrap();
+    let mut journal = Journal::default();
+    journal.rename(&atomic::temp_path(&record), &record);
+    let manifest_journal = journal_path(&dir.join("MANIFEST"));
+    journal.write(&manifest_journal, SyncMode::Full).unwrap();
+
+    assert_eq!(Database::last_checkpoint(path).unwrap(), Some(2));
+    assert!(!manifest_journal.exists());
+    assert_eq!(Database::load(path).unwrap().tables["users"].rows, [[Value::Int(1)]]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
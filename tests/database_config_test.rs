@@ -0,0 +1,120 @@
+use database::database::config::{DatabaseConfig, Durability};
+use database::database::validators::Database;
+use database::error::DbError;
+use database::table::data::{Column, DataType, Options, Table, Value, View};
+use std::fs;
+
+fn columns() -> Vec<Column> {
+    vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ]
+}
+
+#[test]
+fn test_databases_in_separate_directories() {
+    let root = std::env::temp_dir().join("db_config_test");
+    let _ = fs::remove_dir_all(&root);
+
+    let mut first = Database::with_config(DatabaseConfig::new("shop", root.join("a")));
+    let mut table = Table::new("people", columns(), Some(vec!["id".to_string()]));
+    table.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string())]).unwrap();
+    first.tables.insert("people".to_string(), table);
+    first.save_all().unwrap();
+
+    assert!(root.join("a").join("shop.people.pages").exists());
+    assert!(!root.join("b").exists());
+
+    let mut second = Database::with_config(DatabaseConfig::new("shop", root.join("b")));
+    assert!(second.load_table("people", columns(), None).is_err());
+
+    let mut reopened = Database::with_config(DatabaseConfig::new("shop", root.join("a")));
+    reopened.load_table("people", columns(), Some(vec!["id".to_string()])).unwrap();
+    assert_eq!(reopened.tables["people"].rows.len(), 1);
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_default_config_uses_db_directory() {
+    let db = Database::new();
+    assert_eq!(db.config.data_dir, std::path::PathBuf::from("db"));
+    assert!(db.save_table("missing").is_err());
+}
+
+#[test]
+fn test_memory_only_tables_are_not_saved() {
+    let root = std::env::temp_dir().join("db_config_memory_test");
+    let _ = fs::remove_dir_all(&root);
+
+    let mut db = Database::with_config(DatabaseConfig::new("shop", &root));
+    db.create_table(Table::new("people", columns(), None), Durability::Persistent)
+        .unwrap();
+    db.create_table(Table::new("scratch", columns(), None), Durability::MemoryOnly)
+        .unwrap();
+    assert!(db
+        .create_table(Table::new("scratch", columns(), None), Durability::Persistent)
+        .is_err());
+
+    assert_eq!(db.durability("scratch"), Some(Durability::MemoryOnly));
+    assert_eq!(db.durability("people"), Some(Durability::Persistent));
+    assert_eq!(db.durability("missing"), None);
+
+    db.save_all().unwrap();
+    assert!(root.join("shop.people.pages").exists());
+    assert!(!root.join("shop.scratch.pages").exists());
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_rename_table_rewrites_foreign_keys() {
+    let mut db = Database::new();
+    let mut orders = columns();
+    orders[1].options.push(Options::FK("people".to_string()));
+    db.create_table(Table::new("people", columns(), Some(vec!["id".to_string()])), Durability::MemoryOnly)
+        .unwrap();
+    db.create_table(Table::new("orders", orders, None), Durability::Persistent).unwrap();
+
+    db.rename_table("people", "customers").unwrap();
+    assert!(!db.tables.contains_key("people"));
+    assert_eq!(db.tables["customers"].name, "customers");
+    assert_eq!(db.durability("customers"), Some(Durability::MemoryOnly));
+    assert_eq!(db.tables["orders"].columns[1].options, [Options::FK("customers".to_string())]);
+    db.validate_foreign_keys().unwrap();
+
+    assert!(matches!(db.rename_table("people", "x"), Err(DbError::NotFound(_))));
+    assert!(matches!(db.rename_table("customers", "orders"), Err(DbError::SchemaError(_))));
+    db.create_view("recent", View::new("orders")).unwrap();
+    assert!(matches!(db.rename_table("orders", "purchases"), Err(DbError::SchemaError(_))));
+}
+
+#[test]
+fn test_copy_table_with_and_without_data() {
+    let mut db = Database::new();
+    let mut people = Table::new("people", columns(), Some(vec!["id".to_string()]));
+    people.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string())]).unwrap();
+    db.create_table(people, Durability::MemoryOnly).unwrap();
+
+    db.copy_table("people", "backup", true).unwrap();
+    db.copy_table("people", "empty", false).unwrap();
+    assert_eq!(db.tables["backup"].rows.len(), 1);
+    assert!(db.tables["empty"].rows.is_empty());
+    assert_eq!(db.tables["empty"].primary_key, Some(vec!["id".to_string()]));
+
+    // The copies are independent of the source, and the empty one has no stale index entries
+    let row = vec![Value::Int(1), Value::Varchar("Bob".to_string())];
+    db.tables.get_mut("empty").unwrap().insert(row.clone()).unwrap();
+    assert!(db.tables.get_mut("backup").unwrap().insert(row).is_err());
+    assert_eq!(db.tables["people"].rows.len(), 1);
+    assert!(matches!(db.copy_table("people", "backup", true), Err(DbError::SchemaError(_))));
+    assert!(matches!(db.copy_table("missing", "x", true), Err(DbError::NotFound(_))));
+}
@@ -0,0 +1,160 @@
+use database::database::config::Durability;
+use database::database::diff::ColumnDiff;
+use database::database::validators::Database;
+use database::table::data::{Column, DataType, DefaultExpr, Options, Table, Value};
+
+fn column(name: &str, datatype: DataType, options: Vec<Options>) -> Column {
+    Column {
+        name: name.to_string(),
+        datatype,
+        options,
+    }
+}
+
+fn db(tables: Vec<Table>) -> Database {
+    let mut db = Database::new();
+    for table in tables {
+        db.create_table(table, Durability::MemoryOnly).unwrap();
+    }
+    db
+}
+
+fn users_v1() -> Table {
+    Table::new(
+        "users",
+        vec![
+            column("id", DataType::Int, vec![]),
+            column("name", DataType::Varchar(40), vec![]),
+            column("nickname", DataType::Text, vec![]),
+        ],
+        Some(vec!["id".to_string()]),
+    )
+}
+
+fn users_v2() -> Table {
+    Table::new(
+        "users",
+        vec![
+            column("id", DataType::BigInt, vec![]),
+            column("name", DataType::Varchar(40), vec![Options::NotNull, Options::Unique]),
+            column("email", DataType::Text, vec![]),
+        ],
+        Some(vec!["id".to_string()]),
+    )
+}
+
+#[test]
+fn test_diff_lists_table_and_column_changes() {
+    let old = db(vec![users_v1(), Table::new("legacy", vec![column("x", DataType::Int, vec![])], None)]);
+    let new = db(vec![users_v2(), Table::new("posts", vec![column("id", DataType::Int, vec![])], None)]);
+
+    let diff = old.diff_schema(&new);
+    let added: Vec<&str> = diff.added_tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(added, ["posts"]);
+    assert_eq!(diff.removed_tables, ["legacy"]);
+
+    let users = &diff.changed_tables[0];
+    assert_eq!(users.table, "users");
+    assert_eq!(users.added_columns, [column("email", DataType::Text, vec![])]);
+    assert_eq!(users.removed_columns, ["nickname"]);
+    assert_eq!(
+        users.changed_columns,
+        [
+            ColumnDiff {
+                column: "id".to_string(),
+                datatype: Some((DataType::Int, DataType::BigInt)),
+                ..ColumnDiff::default()
+            },
+            ColumnDiff {
+                column: "name".to_string(),
+                added_options: vec![Options::NotNull, Options::Unique],
+                ..ColumnDiff::default()
+            },
+        ]
+    );
+    assert!(users.primary_key.is_none());
+
+    assert!(new.diff_schema(&new).is_empty());
+}
+
+#[test]
+fn test_diff_renders_alter_statements() {
+    let mut target = users_v1();
+    target.columns[2].options.push(Options::Unique);
+    target.primary_key = Some(vec!["id".to_string(), "name".to_string()]);
+    let old = db(vec![users_v1()]);
+    let new = db(vec![target, Table::new("tags", vec![column("tag", DataType::Text, vec![])], None)]);
+
+    let statements: Vec<String> = old.diff_schema(&new).alter_ops().iter().map(|op| op.to_string()).collect();
+    assert_eq!(
+        statements,
+        [
+            "CREATE TABLE \"tags\" (\n  \"tag\" TEXT\n);",
+            "ALTER TABLE \"users\" DROP CONSTRAINT \"users_pkey\";",
+            "ALTER TABLE \"users\" ADD CONSTRAINT \"users_nickname_key\" UNIQUE (\"nickname\");",
+            "ALTER TABLE \"users\" ADD PRIMARY KEY (\"id\", \"name\");",
+        ]
+    );
+
+    // Reversed, the same changes undo each other
+    let back: Vec<String> = new.diff_schema(&old).alter_ops().iter().map(|op| op.to_string()).collect();
+    assert_eq!(
+        back,
+        [
+            "ALTER TABLE \"users\" DROP CONSTRAINT \"users_pkey\";",
+            "ALTER TABLE \"users\" DROP CONSTRAINT \"users_nickname_key\";",
+            "ALTER TABLE \"users\" ADD PRIMARY KEY (\"id\");",
+            "DROP TABLE \"tags\";",
+        ]
+    );
+}
+
+#[test]
+fn test_diff_renders_postgres_option_changes() {
+    let mut target = users_v1();
+    target.columns[1].options = vec![Options::NotNull, Options::Default(DefaultExpr::Literal(Value::Varchar("anon".to_string())))];
+    target.columns[2].options = vec![Options::Check("nickname != ''".parse().unwrap())];
+    let (old, new) = (db(vec![users_v1()]), db(vec![target]));
+
+    let statements: Vec<String> = old.diff_schema(&new).alter_ops().iter().map(|op| op.to_string()).collect();
+    assert_eq!(
+        statements,
+        [
+            "ALTER TABLE \"users\" ALTER COLUMN \"name\" SET NOT NULL;",
+            "ALTER TABLE \"users\" ALTER COLUMN \"name\" SET DEFAULT 'anon';",
+            "ALTER TABLE \"users\" ADD CONSTRAINT \"users_nickname_check\" CHECK (nickname != '');",
+        ]
+    );
+    let back: Vec<String> = new.diff_schema(&old).alter_ops().iter().map(|op| op.to_string()).collect();
+    assert_eq!(
+        back,
+        [
+            "ALTER TABLE \"users\" ALTER COLUMN \"name\" DROP NOT NULL;",
+            "ALTER TABLE \"users\" ALTER COLUMN \"name\" DROP DEFAULT;",
+            "ALTER TABLE \"users\" DROP CONSTRAINT \"users_nickname_check\";",
+        ]
+    );
+}
+
+#[test]
+fn test_added_tables_come_after_the_tables_they_reference() {
+    let references = |name: &str, target: &str| {
+        Table::new(name, vec![column("parent", DataType::Int, vec![Options::FK(target.to_string())])], None)
+    };
+    let new = db(vec![
+        references("a_items", "b_orders"),
+        references("b_orders", "c_customers"),
+        Table::new("c_customers", vec![column("id", DataType::Int, vec![])], Some(vec!["id".to_string()])),
+    ]);
+
+    let created: Vec<String> = db(vec![])
+        .diff_schema(&new)
+        .alter_ops()
+        .iter()
+        .map(|op| op.to_string().lines().next().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        created,
+        ["CREATE TABLE \"c_customers\" (", "CREATE TABLE \"b_orders\" (", "CREATE TABLE \"a_items\" ("]
+    );
+}
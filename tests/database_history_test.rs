@@ -0,0 +1,134 @@
+use chrono::NaiveDate;
+use database::database::history::AsOf;
+use database::database::config::DatabaseConfig;
+use database::database::validators::Database;
+use database::table::data::{Column, DataType, FilterExpr, Table, Value};
+
+fn people() -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    Table::new("people", columns, Some(vec!["id".to_string()]))
+}
+
+fn row(id: i32, name: &str) -> Vec<Value> {
+    vec![Value::Int(id), Value::Varchar(name.to_string())]
+}
+
+fn id_is(id: i32) -> FilterExpr {
+    FilterExpr::Eq("id".to_string(), Value::Int(id))
+}
+
+#[test]
+fn test_table_as_of_transaction() {
+    let mut db = Database::new();
+    db.tables.insert("people".to_string(), people());
+    db.retain_history("people").unwrap();
+
+    db.insert("people", row(1, "Ann")).unwrap();
+    db.insert("people", row(2, "Bob")).unwrap();
+    let after_inserts = db.last_txn();
+
+    db.update_where("people", &id_is(1), vec![None, Some(Value::Varchar("Anna".to_string()))])
+        .unwrap();
+    let after_update = db.last_txn();
+
+    db.delete_where("people", &id_is(1)).unwrap();
+    db.batch(|b| {
+        b.insert("people", row(3, "Cat"));
+        b.delete("people", &id_is(2));
+    })
+    .unwrap();
+
+    assert_eq!(db.tables["people"].rows, vec![row(3, "Cat")]);
+
+    let then = db.table_as_of("people", AsOf::Txn(after_inserts)).unwrap();
+    assert_eq!(then.rows, vec![row(1, "Ann"), row(2, "Bob")]);
+    assert_eq!(then.select_where_expr(&id_is(2)).unwrap().len(), 1);
+
+    let then = db.table_as_of("people", AsOf::Txn(after_update)).unwrap();
+    assert_eq!(then.rows, vec![row(1, "Anna"), row(2, "Bob")]);
+
+    let then = db.table_as_of("people", AsOf::Txn(after_update + 1)).unwrap();
+    assert_eq!(then.rows, vec![row(2, "Bob")]);
+
+    let now = db.table_as_of("people", AsOf::Txn(db.last_txn())).unwrap();
+    assert_eq!(now, db.tables["people"]);
+
+    let empty = db.table_as_of("people", AsOf::Txn(0)).unwrap();
+    assert!(empty.rows.is_empty());
+}
+
+#[test]
+fn test_history_limits() {
+    let mut db = Database::new();
+    db.tables.insert("people".to_string(), people());
+    db.insert("people", row(1, "Ann")).unwrap();
+
+    assert!(db.table_as_of("people", AsOf::Txn(0)).is_err());
+
+    db.retain_history("people").unwrap();
+    assert!(db.table_as_of("people", AsOf::Txn(0)).is_err());
+
+    let long_ago = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    assert!(db.table_as_of("people", AsOf::Time(long_ago)).is_err());
+
+    db.insert("people", row(2, "Bob")).unwrap();
+    let now = chrono::Local::now().naive_local();
+    assert_eq!(db.table_as_of("people", AsOf::Time(now)).unwrap().rows.len(), 2);
+
+    assert!(db.retain_history("missing").is_err());
+}
+
+#[test]
+fn test_history_finds_rows_by_value() {
+    let mut db = Database::new();
+    db.tables.insert("people".to_string(), people());
+    db.retain_history("people").unwrap();
+    for (id, name) in [(1, "Ann"), (2, "Bob"), (3, "Cat")] {
+        db.insert("people", row(id, name)).unwrap();
+    }
+    let before_update = db.last_txn();
+    db.update_where("people", &id_is(3), vec![None, Some(Value::Varchar("Cy".to_string()))])
+        .unwrap();
+
+    // Ann goes behind the history's back, so Cy is no longer where the update left it
+    db.tables.get_mut("people").unwrap().delete_where(id_is(1)).unwrap();
+    let then = db.table_as_of("people", AsOf::Txn(before_update)).unwrap();
+    assert_eq!(then.rows, vec![row(2, "Bob"), row(3, "Cat")]);
+    assert!(db.table_as_of("people", AsOf::Txn(0)).is_err());
+}
+
+#[test]
+fn test_history_covers_table_changes_and_is_bounded() {
+    let mut db = Database::with_config(DatabaseConfig {
+        history_limit: 3,
+        ..DatabaseConfig::default()
+    });
+    db.tables.insert("people".to_string(), people());
+    db.retain_history("people").unwrap();
+    db.insert("people", row(1, "Ann")).unwrap();
+    let before_drop = db.last_txn();
+
+    db.alter_table("people", |t| t.drop_column("name")).unwrap();
+    let then = db.table_as_of("people", AsOf::Txn(before_drop)).unwrap();
+    assert_eq!(then.rows, vec![row(1, "Ann")]);
+    assert_eq!(then.columns.len(), 2);
+
+    // Three entries are kept, and the transactions before them are forgotten
+    for id in 2..5 {
+        db.insert("people", vec![Value::Int(id)]).unwrap();
+    }
+    assert_eq!(db.history.tables["people"].entries.len(), 3);
+    assert!(db.table_as_of("people", AsOf::Txn(before_drop)).is_err());
+    assert_eq!(db.table_as_of("people", AsOf::Txn(before_drop + 1)).unwrap().rows.len(), 1);
+}
@@ -0,0 +1,91 @@
+use database::database::config::Durability;
+use database::database::validators::Database;
+use database::table::data::{Column, DataType, DefaultExpr, FilterExpr, Options, Table, Value, View};
+
+fn column(name: &str, datatype: DataType, options: Vec<Options>) -> Column {
+    Column {
+        name: name.to_string(),
+        datatype,
+        options,
+    }
+}
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+fn shop() -> Database {
+    let mut db = Database::new();
+    let mut customers = Table::new(
+        "customers",
+        vec![
+            column("id", DataType::Int, vec![]),
+            column("email", DataType::Varchar(60), vec![Options::Unique, Options::NotNull]),
+            column("tier", DataType::Int, vec![Options::Default(DefaultExpr::Literal(Value::Int(1)))]),
+        ],
+        Some(vec!["id".to_string()]),
+    );
+    customers.insert(vec![Value::Int(1), Value::Varchar("a@x.io".to_string()), Value::Int(2)]).unwrap();
+    let orders = Table::new(
+        "orders",
+        vec![
+            column("id", DataType::Int, vec![]),
+            column("customer_id", DataType::Int, vec![Options::FK("customers".to_string())]),
+        ],
+        Some(vec!["id".to_string()]),
+    );
+    db.create_table(customers, Durability::MemoryOnly).unwrap();
+    db.create_table(orders, Durability::MemoryOnly).unwrap();
+    db.create_view("everyone", View::new("customers")).unwrap();
+    db
+}
+
+#[test]
+fn test_information_schema_lists_tables_and_columns() {
+    let schema = shop().information_schema();
+    assert_eq!(
+        schema.tables.rows,
+        [
+            vec![text("customers"), text("BASE TABLE"), Value::BigInt(1), text("memory")],
+            vec![text("orders"), text("BASE TABLE"), Value::BigInt(0), text("memory")],
+            vec![text("everyone"), text("VIEW"), Value::Null, Value::Null],
+        ]
+    );
+
+    let customers = schema
+        .columns
+        .query()
+        .filter(FilterExpr::Eq("table_name".to_string(), text("customers")))
+        .run()
+        .unwrap();
+    assert_eq!(
+        customers.rows,
+        [
+            vec![text("customers"), text("id"), Value::Int(1), text("Int"), Value::Boolean(false), Value::Null],
+            vec![text("customers"), text("email"), Value::Int(2), text("Varchar(60)"), Value::Boolean(false), Value::Null],
+            vec![text("customers"), text("tier"), Value::Int(3), text("Int"), Value::Boolean(true), text("1")],
+        ]
+    );
+}
+
+#[test]
+fn test_information_schema_lists_indexes_and_constraints() {
+    let schema = shop().information_schema();
+    let indexed: Vec<(&Value, &Value)> = schema.indexes.rows.iter().map(|row| (&row[0], &row[1])).collect();
+    assert_eq!(
+        indexed,
+        [(&text("customers"), &text("email")), (&text("customers"), &text("id")), (&text("orders"), &text("id"))]
+    );
+
+    let foreign_keys = schema
+        .constraints
+        .query()
+        .filter(FilterExpr::Eq("constraint_type".to_string(), text("FOREIGN KEY")))
+        .run()
+        .unwrap();
+    assert_eq!(foreign_keys.rows, [vec![text("orders"), text("customer_id"), text("FOREIGN KEY"), text("customers")]]);
+
+    // The snapshots join like any tables
+    let joined = schema.columns.inner_join_table(&schema.constraints, ("column_name", "column_name")).unwrap();
+    assert!(joined.rows.len() >= 4);
+}
@@ -0,0 +1,97 @@
+use database::database::config::{DatabaseConfig, Durability};
+use database::database::migrations::MIGRATIONS_TABLE;
+use database::database::validators::Database;
+use database::error::DbError;
+use database::table::data::{Column, DataType, Options, Table, Value};
+use std::fs;
+
+fn column(name: &str, datatype: DataType) -> Column {
+    Column {
+        name: name.to_string(),
+        datatype,
+        options: vec![],
+    }
+}
+
+fn register(db: &mut Database) {
+    db.add_migration(2, "seed users", |db| {
+        db.insert("users", vec![Value::Int(1), Value::Varchar("ada".to_string())])?;
+        Ok(())
+    })
+    .unwrap();
+    db.add_migration(1, "create users", |db| {
+        let users = Table::new(
+            "users",
+            vec![column("id", DataType::Int), column("name", DataType::Varchar(40))],
+            Some(vec!["id".to_string()]),
+        );
+        db.create_table(users, Durability::Persistent)?;
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_migrate_applies_pending_steps_in_order() {
+    let mut db = Database::new();
+    register(&mut db);
+    assert!(db.add_migration(1, "again", |_| Ok(())).is_err());
+    assert_eq!(db.pending_migrations(), [1, 2]);
+
+    assert_eq!(db.migrate().unwrap(), [1, 2]);
+    assert_eq!(db.tables["users"].rows.len(), 1);
+    assert_eq!(db.applied_migrations(), [1, 2]);
+
+    // Applied steps are not run again
+    assert!(db.migrate().unwrap().is_empty());
+    assert_eq!(db.tables["users"].rows.len(), 1);
+}
+
+#[test]
+fn test_failed_migration_is_rolled_back() {
+    let mut db = Database::new();
+    register(&mut db);
+    db.add_migration(3, "broken", |db| {
+        db.create_table(Table::new("audit", vec![column("note", DataType::Text)], None), Durability::Persistent)?;
+        db.insert("users", vec![Value::Int(2), Value::Varchar("bob".to_string())])?;
+        db.tables.get_mut("users").unwrap().add_option("name", Options::Unique)?;
+        Err(DbError::Other("step failed".to_string()))
+    })
+    .unwrap();
+
+    assert_eq!(db.migrate(), Err(DbError::Other("step failed".to_string())));
+    assert_eq!(db.applied_migrations(), [1, 2]);
+    assert!(!db.tables.contains_key("audit"));
+    assert_eq!(db.tables["users"].rows.len(), 1);
+    assert!(db.tables["users"].columns[1].options.is_empty());
+    assert!(!db.in_transaction());
+    assert_eq!(db.pending_migrations(), [3]);
+
+    db.begin_transaction().unwrap();
+    assert!(matches!(db.migrate(), Err(DbError::TransactionError(_))));
+}
+
+#[test]
+fn test_applied_versions_survive_save_and_load() {
+    let root = std::env::temp_dir().join("db_migrations_test");
+    let _ = fs::remove_dir_all(&root);
+    let path = root.to_str().unwrap();
+
+    let mut db = Database::with_config(DatabaseConfig::new("app", &root));
+    register(&mut db);
+    db.migrate().unwrap();
+    db.save(path).unwrap();
+
+    let mut loaded = Database::load(path).unwrap();
+    assert!(loaded.tables.contains_key(MIGRATIONS_TABLE));
+    register(&mut loaded);
+    loaded.add_migration(3, "index names", |db| {
+        db.tables.get_mut("users").unwrap().create_index("name", false)?;
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(loaded.migrate().unwrap(), [3]);
+    assert_eq!(loaded.tables["users"].rows.len(), 1);
+
+    fs::remove_dir_all(&root).unwrap();
+}
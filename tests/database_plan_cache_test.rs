@@ -0,0 +1,95 @@
+use database::database::plan_cache::normalize_query;
+use database::database::validators::Database;
+use database::table::data::{Column, DataType, FilterExpr, PlanNode, Table, Value};
+
+fn setup() -> Database {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "age".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("users", columns, None);
+    for i in 1..=10 {
+        table.insert(vec![Value::Int(i), Value::Int(20 + i)]).unwrap();
+    }
+
+    let mut db = Database::new();
+    db.tables.insert("users".to_string(), table);
+    db
+}
+
+#[test]
+fn test_normalize_query_keeps_literals() {
+    assert_eq!(
+        normalize_query("  SELECT *\n  FROM Users WHERE name = 'Ann  Lee' ; "),
+        "select * from Users where name = 'Ann  Lee'"
+    );
+    // Only keywords fold, never names or words inside literals
+    assert_eq!(
+        normalize_query("Select Id From USERS Where Name = 'AND' and Id In (1, 2)"),
+        "select Id from USERS where Name = 'AND' and Id in (1, 2)"
+    );
+}
+
+#[test]
+fn test_prepare_keys_plans_by_value_type() {
+    let mut db = setup();
+    db.prepare("users", Some(&FilterExpr::Eq("id".to_string(), Value::Int(3)))).unwrap();
+    db.prepare("users", Some(&FilterExpr::Eq("id".to_string(), Value::Double(3.0)))).unwrap();
+    db.prepare("users", Some(&FilterExpr::Eq("ID".to_string(), Value::Int(3)))).unwrap();
+
+    assert_eq!(db.plan_cache.misses, 3);
+    assert_eq!(db.plan_cache.hits, 0);
+    assert_eq!(db.plan_cache.len(), 3);
+}
+
+#[test]
+fn test_prepare_reuses_cached_plan() {
+    let mut db = setup();
+    let expr = FilterExpr::Eq("id".to_string(), Value::Int(3));
+
+    let first = db.prepare("users", Some(&expr)).unwrap();
+    let second = db.prepare("users", Some(&expr)).unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(db.plan_cache.misses, 1);
+    assert_eq!(db.plan_cache.hits, 1);
+    assert!(db.plan_cache.contains("SELECT * FROM users WHERE id = 3"));
+}
+
+#[test]
+fn test_prepare_replans_after_index_added() {
+    let mut db = setup();
+    let expr = FilterExpr::Eq("id".to_string(), Value::Int(3));
+
+    let before = db.prepare("users", Some(&expr)).unwrap();
+    assert!(matches!(before.root, PlanNode::Filter { ref input, .. } if matches!(**input, PlanNode::SeqScan { .. })));
+
+    db.tables
+        .get_mut("users")
+        .unwrap()
+        .create_index("id", false)
+        .unwrap();
+
+    let after = db.prepare("users", Some(&expr)).unwrap();
+    assert!(matches!(after.root, PlanNode::Filter { ref input, .. } if matches!(**input, PlanNode::IndexScan { .. })));
+    assert_eq!(db.plan_cache.misses, 2);
+    assert_eq!(db.plan_cache.len(), 1);
+}
+
+#[test]
+fn test_prepare_drops_entries_for_missing_table() {
+    let mut db = setup();
+    db.prepare("users", None).unwrap();
+    db.tables.remove("users");
+
+    assert!(db.prepare("users", None).is_err());
+    assert!(db.plan_cache.is_empty());
+}
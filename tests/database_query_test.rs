@@ -0,0 +1,131 @@
+use database::database::config::Durability;
+use database::database::storage::MemoryEngine;
+use database::database::validators::Database;
+use database::error::DbError;
+use database::table::data::{Column, DataType, FilterExpr, Options, OrderBy, QuerySpec, Table, Value, View};
+
+fn column(name: &str, datatype: DataType, options: Vec<Options>) -> Column {
+    Column {
+        name: name.to_string(),
+        datatype,
+        options,
+    }
+}
+
+fn shop() -> Database {
+    let mut db = Database::new();
+    let mut customers = Table::new(
+        "customers",
+        vec![column("id", DataType::Int, vec![]), column("name", DataType::Varchar(40), vec![])],
+        Some(vec!["id".to_string()]),
+    );
+    for (id, name) in [(1, "ada"), (2, "bob")] {
+        customers.insert(vec![Value::Int(id), Value::Varchar(name.to_string())]).unwrap();
+    }
+
+    let mut orders = Table::new(
+        "orders",
+        vec![
+            column("id", DataType::Int, vec![]),
+            column("customer_id", DataType::Int, vec![Options::FK("customers".to_string())]),
+            column("total", DataType::Int, vec![]),
+        ],
+        Some(vec!["id".to_string()]),
+    );
+    for (id, customer, total) in [(10, 1, 30), (11, 2, 5), (12, 1, 12)] {
+        orders.insert(vec![Value::Int(id), Value::Int(customer), Value::Int(total)]).unwrap();
+    }
+
+    db.create_table(customers, Durability::MemoryOnly).unwrap();
+    db.create_table(orders, Durability::MemoryOnly).unwrap();
+    db
+}
+
+#[test]
+fn test_select_filters_orders_and_limits() {
+    let db = shop();
+    let spec = QuerySpec::new()
+        .filter(FilterExpr::Gt("total".to_string(), Value::Int(10)))
+        .order_by(&[OrderBy::desc("total")])
+        .select(&["id"])
+        .limit(1);
+    let result = db.select("orders", spec).unwrap();
+    assert_eq!(result.rows, [vec![Value::Int(10)]]);
+
+    assert!(matches!(db.select("missing", QuerySpec::new()), Err(DbError::NotFound(_))));
+}
+
+#[test]
+fn test_select_joins_along_foreign_keys() {
+    let db = shop();
+    let spec = QuerySpec::new()
+        .join("orders")
+        .filter(FilterExpr::Eq("customers.name".to_string(), Value::Varchar("ada".to_string())))
+        .order_by(&[OrderBy::asc("orders.id")])
+        .select(&["orders.id", "name"]);
+    let result = db.select("customers", spec).unwrap();
+    assert_eq!(
+        result.rows,
+        [
+            vec![Value::Int(10), Value::Varchar("ada".to_string())],
+            vec![Value::Int(12), Value::Varchar("ada".to_string())],
+        ]
+    );
+
+    // Either side may hold the foreign key
+    let from_orders = db.select("orders", QuerySpec::new().join("customers")).unwrap();
+    assert_eq!(from_orders.rows.len(), 3);
+
+    let mut db = db;
+    db.create_table(Table::new("notes", vec![column("text", DataType::Text, vec![])], None), Durability::MemoryOnly)
+        .unwrap();
+    assert!(db.select("orders", QuerySpec::new().join("notes")).is_err());
+}
+
+#[test]
+fn test_select_reads_views() {
+    let mut db = shop();
+    let big = View::new("orders").filter(FilterExpr::Ge("total".to_string(), Value::Int(12)));
+    db.create_view("big_orders", big).unwrap();
+
+    let result = db
+        .select("big_orders", QuerySpec::new().order_by(&[OrderBy::asc("total")]).select(&["id"]))
+        .unwrap();
+    assert_eq!(result.rows, [vec![Value::Int(12)], vec![Value::Int(10)]]);
+}
+
+#[test]
+fn test_insert_into_select() {
+    let mut db = shop();
+    let columns = vec![column("id", DataType::Int, vec![]), column("name", DataType::Varchar(40), vec![])];
+    db.create_table(Table::new("big_spenders", columns, None), Durability::MemoryOnly).unwrap();
+
+    let spec = QuerySpec::new()
+        .join("orders")
+        .filter(FilterExpr::Ge("orders.total".to_string(), Value::Int(12)))
+        .select(&["orders.id", "customers.name"]);
+    assert_eq!(db.insert_into_select("big_spenders", "customers", spec).unwrap(), 2);
+
+    let ids: Vec<&Value> = db.tables["big_spenders"].rows.iter().map(|row| &row[0]).collect();
+    assert_eq!(ids, [&Value::Int(10), &Value::Int(12)]);
+    assert!(db.insert_into_select("missing", "orders", QuerySpec::new()).is_err());
+}
+
+#[test]
+fn test_insert_into_select_reaches_the_engine_and_history() {
+    let mut db = shop();
+    let columns = vec![column("id", DataType::Int, vec![]), column("name", DataType::Varchar(40), vec![])];
+    let target = Table::new("big_spenders", columns, Some(vec!["id".to_string()]));
+    db.create_table_with_engine(target, Box::<MemoryEngine>::default()).unwrap();
+    db.retain_history("big_spenders").unwrap();
+    let before = db.last_txn();
+
+    let spec = || QuerySpec::new().join("orders").select(&["orders.id", "customers.name"]);
+    assert_eq!(db.insert_into_select("big_spenders", "customers", spec()).unwrap(), 3);
+    assert_eq!(db.engines["big_spenders"].scan().unwrap(), db.tables["big_spenders"].rows);
+    assert!(db.last_txn() > before);
+
+    // A second copy clashes on the key, and neither the table nor the engine changes
+    assert!(db.insert_into_select("big_spenders", "customers", spec()).is_err());
+    assert_eq!(db.engines["big_spenders"].scan().unwrap().len(), 3);
+}
@@ -0,0 +1,184 @@
+use database::database::session::Policy;
+use database::database::validators::Database;
+use database::table::data::{Column, DataType, FilterExpr, MaskPolicy, Options, Table, Value};
+use std::collections::HashMap;
+
+fn db() -> Database {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "tenant_id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "note".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    let mut notes = Table::new("notes", columns, Some(vec!["id".to_string()]));
+    for (id, tenant) in [(1, 10), (2, 10), (3, 20)] {
+        notes
+            .insert(vec![
+                Value::Int(id),
+                Value::Int(tenant),
+                Value::Varchar(format!("note {}", id)),
+            ])
+            .unwrap();
+    }
+
+    let mut db = Database::new();
+    db.tables.insert("notes".to_string(), notes);
+    db.add_policy("notes", Policy::attribute(FilterExpr::Eq, "tenant_id", "tenant_id"))
+        .unwrap();
+    db
+}
+
+fn tenant(id: i32) -> HashMap<String, Value> {
+    let mut attributes = HashMap::new();
+    attributes.insert("tenant_id".to_string(), Value::Int(id));
+    attributes
+}
+
+#[test]
+fn test_session_select_only_sees_own_rows() {
+    let mut db = db();
+    let session = db.session(tenant(10));
+
+    let rows = session
+        .select_result("notes", &FilterExpr::Gt("id".to_string(), Value::Int(0)))
+        .unwrap()
+        .rows;
+    assert_eq!(rows.len(), 2);
+
+    let other = session
+        .select_result("notes", &FilterExpr::Eq("id".to_string(), Value::Int(3)))
+        .unwrap()
+        .rows;
+    assert!(other.is_empty());
+}
+
+#[test]
+fn test_session_update_and_delete_are_scoped() {
+    let mut db = db();
+    let mut session = db.session(tenant(20));
+
+    let updated = session
+        .update(
+            "notes",
+            &FilterExpr::Gt("id".to_string(), Value::Int(0)),
+            vec![None, None, Some(Value::Varchar("edited".to_string()))],
+        )
+        .unwrap();
+    assert_eq!(updated, 1);
+
+    let deleted = session
+        .delete("notes", &FilterExpr::Eq("id".to_string(), Value::Int(1)))
+        .unwrap();
+    assert_eq!(deleted, 0);
+
+    let moved = session.update(
+        "notes",
+        &FilterExpr::Eq("id".to_string(), Value::Int(3)),
+        vec![None, Some(Value::Int(10)), None],
+    );
+    assert!(moved.is_err());
+
+    assert_eq!(db.tables["notes"].rows.len(), 3);
+    assert_eq!(
+        db.tables["notes"].rows[2][2],
+        Value::Varchar("edited".to_string())
+    );
+}
+
+#[test]
+fn test_session_insert_and_missing_attribute() {
+    let mut db = db();
+
+    let mut session = db.session(tenant(10));
+    let foreign = session.insert(
+        "notes",
+        vec![Value::Int(4), Value::Int(20), Value::Varchar("x".to_string())],
+    );
+    assert!(foreign.is_err());
+
+    let anonymous = db.session(HashMap::new());
+    assert!(anonymous
+        .select_result("notes", &FilterExpr::Gt("id".to_string(), Value::Int(0)))
+        .is_err());
+}
+
+#[test]
+fn test_session_reads_are_masked() {
+    let mut db = db();
+    db.tables.get_mut("notes").unwrap().columns[2]
+        .options
+        .push(Options::Mask(MaskPolicy::Redact));
+    let session = db.session(tenant(10));
+
+    let rows = session
+        .select_result("notes", &FilterExpr::Gt("id".to_string(), Value::Int(0)))
+        .unwrap()
+        .rows;
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|row| row[2] == Value::Varchar("****".to_string())));
+}
+
+#[test]
+fn test_session_policies_are_any_filter() {
+    // Tenant 0's notes are shared with everyone, but only tenants write them
+    let mut db = db();
+    db.policies.clear();
+    let shared = Policy::attribute(FilterExpr::Eq, "tenant_id", "tenant_id")
+        .or(FilterExpr::Eq("tenant_id".to_string(), Value::Int(0)));
+    db.add_policy("notes", shared).unwrap();
+    let unknown = Policy::attribute(FilterExpr::Eq, "owner", "tenant_id");
+    assert!(db.add_policy("notes", unknown).is_err());
+
+    let mut session = db.session(tenant(20));
+    session
+        .insert("notes", vec![Value::Int(4), Value::Int(0), Value::Varchar("shared".to_string())])
+        .unwrap();
+    let all = FilterExpr::Gt("id".to_string(), Value::Int(0));
+    let ids: Vec<Value> = session.select_result("notes", &all).unwrap().rows.into_iter().map(|r| r[0].clone()).collect();
+    assert_eq!(ids, [Value::Int(3), Value::Int(4)]);
+
+    // Moving a row to tenant 0 keeps it in reach; to tenant 10 doesn't
+    let row = FilterExpr::Eq("id".to_string(), Value::Int(3));
+    assert_eq!(session.update("notes", &row, vec![None, Some(Value::Int(0)), None]).unwrap(), 1);
+    assert!(session.update("notes", &row, vec![None, Some(Value::Int(10)), None]).is_err());
+}
+
+#[test]
+fn test_session_policy_columns_survive_schema_changes() {
+    let mut db = db();
+    assert!(db.alter_table("notes", |t| t.rename_column("tenant_id", "org_id")).is_err());
+    assert!(db.tables["notes"].resolve_column("tenant_id").is_ok());
+
+    // Changed behind `alter_table`'s back, the policy fails to bind instead of panicking
+    db.tables.get_mut("notes").unwrap().rename_column("tenant_id", "org_id").unwrap();
+    let session = db.session(tenant(10));
+    assert!(session
+        .select_result("notes", &FilterExpr::Gt("id".to_string(), Value::Int(0)))
+        .is_err());
+}
+
+#[test]
+fn test_session_literal_strings_are_not_attributes() {
+    let mut db = db();
+    db.policies.clear();
+    db.add_policy("notes", FilterExpr::Ne("note".to_string(), Value::Varchar(":tenant_id".to_string())))
+        .unwrap();
+
+    let session = db.session(HashMap::new());
+    let rows = session
+        .select_result("notes", &FilterExpr::Gt("id".to_string(), Value::Int(0)))
+        .unwrap()
+        .rows;
+    assert_eq!(rows.len(), 3);
+}
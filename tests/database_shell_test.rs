@@ -0,0 +1,145 @@
+use database::database::config::{DatabaseConfig, Durability};
+use database::database::shell::Shell;
+use database::database::validators::Database;
+use database::table::data::{Column, DataType, Options, Table, Value};
+use std::io::Cursor;
+
+fn shell() -> Shell {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![Options::NotNull, Options::Autoincrement],
+        },
+        Column {
+            name: "email".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![Options::Unique],
+        },
+    ];
+    let mut users = Table::new("users", columns, Some(vec!["id".to_string()]));
+    users.insert(vec![Value::Null, Value::Varchar("a@b.c".to_string())]).unwrap();
+
+    let mut db = Database::new();
+    db.tables.insert("users".to_string(), users);
+    Shell::new(db)
+}
+
+#[test]
+fn test_dot_tables_and_schema() {
+    let mut shell = shell();
+
+    assert_eq!(shell.run_command(".tables").unwrap(), "users\n");
+
+    let schema = shell.run_command(".schema users").unwrap();
+    assert!(schema.contains("NOT NULL, AUTOINCREMENT"));
+    assert!(schema.contains("UNIQUE"));
+    assert!(schema.contains("PRIMARY KEY (id)"));
+
+    assert!(shell.run_command(".schema missing").is_err());
+}
+
+#[test]
+fn test_dot_indexes_and_stats() {
+    let mut shell = shell();
+
+    let indexes = shell.run_command(".indexes users").unwrap();
+    assert!(indexes.contains("id"));
+    assert!(indexes.contains("email"));
+    assert!(indexes.contains("HASH"));
+
+    // The unique column is indexed alongside the primary key
+    let stats = shell.run_command(".stats").unwrap();
+    assert!(stats.contains("users: 1 rows, 2 columns, 2 indexes"));
+}
+
+#[test]
+fn test_run_loop_with_timing() {
+    let mut shell = shell();
+    let mut input = Cursor::new(".timing on\n.tables\n.quit\n.tables\n");
+    let mut output = Vec::new();
+
+    shell.run(&mut input, &mut output).unwrap();
+
+    let text = String::from_utf8(output).unwrap();
+    assert!(shell.timing);
+    assert!(text.contains("Run Time:"));
+    assert_eq!(text.matches("users").count(), 1);
+}
+
+#[test]
+fn test_memory_only_tables_are_flagged() {
+    use database::database::config::Durability;
+
+    let mut db = Database::new();
+    let columns = vec![Column {
+        name: "id".to_string(),
+        datatype: DataType::Int,
+        options: vec![],
+    }];
+    db.create_table(Table::new("scratch", columns, None), Durability::MemoryOnly)
+        .unwrap();
+    let mut shell = Shell::new(db);
+
+    assert!(shell.run_command(".schema scratch").unwrap().contains("MEMORY ONLY"));
+    assert!(shell
+        .run_command(".stats")
+        .unwrap()
+        .contains("scratch: 0 rows, 1 columns, 0 indexes (memory only)"));
+}
+
+#[test]
+fn test_sql_statements_run_in_the_shell() {
+    let mut shell = shell();
+
+    let out = shell
+        .run_command("INSERT INTO users (email) VALUES ('d@e.f'); SELECT email FROM users ORDER BY email DESC")
+        .unwrap();
+    assert!(out.starts_with("1 rows affected\n"), "{}", out);
+    assert!(out.find("d@e.f").unwrap() < out.find("a@b.c").unwrap());
+    assert!(out.ends_with("(2 rows)\n"));
+
+    assert!(shell.run_command("SELEC * FROM users").is_err());
+}
+
+#[test]
+fn test_dot_open_loads_a_saved_database() {
+    let dir = std::env::temp_dir().join("shell_open");
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut saved = Database::with_config(DatabaseConfig::new("shop", &dir));
+    let users = shell().db.tables.remove("users").unwrap();
+    saved.create_table(users, Durability::Persistent).unwrap();
+    saved.save(dir.to_str().unwrap()).unwrap();
+
+    let mut shell = Shell::new(Database::new());
+    assert_eq!(shell.run_command(".tables").unwrap(), "");
+    shell.run_command(&format!(".open {}", dir.display())).unwrap();
+    assert_eq!(shell.run_command(".tables").unwrap(), "users\n");
+    assert!(shell.run_command("SELECT email FROM users").unwrap().contains("a@b.c"));
+
+    assert!(shell.run_command(".open").is_err());
+    assert!(shell.run_command(".open /no/such/database").is_err());
+    assert_eq!(shell.run_command(".tables").unwrap(), "users\n");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_run_loop_pages_query_results() {
+    let mut shell = shell();
+    for i in 0..24 {
+        shell.run_command(&format!("INSERT INTO users (email) VALUES ('{}@b.c')", i)).unwrap();
+    }
+    shell.pager.page_size = 10;
+
+    // Enter shows the second page, q stops before the third, and the shell goes on
+    let mut input = Cursor::new("SELECT * FROM users\n\nq\n.tables\n");
+    let mut output = Vec::new();
+    shell.run(&mut input, &mut output).unwrap();
+
+    let text = String::from_utf8(output).unwrap();
+    assert!(text.contains("-- rows 1-10 of 25 (page 1/3) --"), "{}", text);
+    assert!(text.contains("-- rows 11-20 of 25 (page 2/3) --"));
+    assert!(!text.contains("rows 21-25"));
+    assert_eq!(text.matches("Press enter for more").count(), 2);
+    assert!(text.contains("(25 rows)\ndb> users\n"));
+}
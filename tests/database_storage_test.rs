@@ -0,0 +1,169 @@
+use database::database::storage::{MemoryEngine, StorageEngine};
+use database::database::validators::Database;
+use chrono::{Duration, Local};
+use database::error::DbError;
+use database::table::data::{Column, DataType, FilterExpr, Options, Table, Value};
+use std::sync::{Arc, Mutex};
+
+// Records every call so tests can see what the database sent to the engine
+#[derive(Debug, Default)]
+struct RecordingEngine {
+    log: Arc<Mutex<Vec<String>>>,
+    seed: Vec<Vec<Value>>,
+}
+
+impl StorageEngine for RecordingEngine {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    fn scan(&self) -> Result<Vec<Vec<Value>>, String> {
+        Ok(self.seed.clone())
+    }
+
+    fn insert(&mut self, row: &[Value]) -> Result<(), String> {
+        self.log.lock().unwrap().push(format!("insert {}", row[0]));
+        Ok(())
+    }
+
+    fn update(&mut self, position: usize, row: &[Value]) -> Result<(), String> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("update {} {}", position, row[1]));
+        Ok(())
+    }
+
+    fn delete(&mut self, positions: &[usize]) -> Result<(), String> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("delete {:?}", positions));
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.log.lock().unwrap().push("flush".to_string());
+        Ok(())
+    }
+}
+
+fn table(name: &str) -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    Table::new(name, columns, Some(vec!["id".to_string()]))
+}
+
+fn row(id: i32, name: &str) -> Vec<Value> {
+    vec![Value::Int(id), Value::Varchar(name.to_string())]
+}
+
+#[test]
+fn test_engine_receives_writes() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let engine = RecordingEngine {
+        log: log.clone(),
+        seed: vec![row(1, "Ann")],
+    };
+
+    let mut db = Database::new();
+    db.create_table_with_engine(table("people"), Box::new(engine))
+        .unwrap();
+    assert_eq!(db.tables["people"].rows.len(), 1);
+    assert_eq!(db.engine_name("people"), Some("recording"));
+
+    db.insert("people", row(2, "Bob")).unwrap();
+    db.update_where(
+        "people",
+        &FilterExpr::Eq("id".to_string(), Value::Int(2)),
+        vec![None, Some(Value::Varchar("Rob".to_string()))],
+    )
+    .unwrap();
+    db.delete_where("people", &FilterExpr::Eq("id".to_string(), Value::Int(1)))
+        .unwrap();
+    db.flush_all().unwrap();
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec!["insert 2", "update 1 Rob", "delete [0]", "flush"]
+    );
+    assert_eq!(db.tables["people"].rows, vec![row(2, "Rob")]);
+}
+
+#[test]
+fn test_memory_engine_tracks_table() {
+    let mut seeded = table("people");
+    seeded.insert(row(1, "Ann")).unwrap();
+
+    let mut db = Database::new();
+    db.create_table_with_engine(seeded, Box::<MemoryEngine>::default())
+        .unwrap();
+    db.insert("people", row(2, "Bob")).unwrap();
+    db.delete_where(
+        "people",
+        &FilterExpr::Eq("name".to_string(), Value::Varchar("Ann".to_string())),
+    )
+    .unwrap();
+
+    let engine = &db.engines["people"];
+    assert_eq!(engine.scan().unwrap(), vec![row(2, "Bob")]);
+    assert!(db
+        .create_table_with_engine(table("people"), Box::<MemoryEngine>::default())
+        .is_err());
+}
+
+#[test]
+fn test_tables_without_engine_default_to_memory() {
+    let mut db = Database::new();
+    db.tables.insert("plain".to_string(), table("plain"));
+
+    assert_eq!(db.engine_name("plain"), Some("memory"));
+    assert_eq!(db.engine_name("missing"), None);
+    db.insert("plain", row(1, "Ann")).unwrap();
+    assert!(db.flush("plain").is_ok());
+    assert!(db.flush("missing").is_err());
+}
+
+#[test]
+fn test_vacuum_and_alter_table_reach_the_engine() {
+    let mut people = table("people");
+    people.columns.push(Column {
+        name: "seen".to_string(),
+        datatype: DataType::DateTime,
+        options: vec![Options::Ttl(60)],
+    });
+    let mut db = Database::new();
+    db.create_table_with_engine(people, Box::<MemoryEngine>::default()).unwrap();
+    let now = Local::now().naive_local();
+    for (id, seen) in [(1, now), (2, now - Duration::hours(1)), (3, now), (4, now - Duration::hours(2))] {
+        db.insert("people", vec![Value::Int(id), Value::Varchar("x".to_string()), Value::DateTime(seen)]).unwrap();
+    }
+
+    assert_eq!(db.vacuum("people").unwrap(), 2);
+    let ids = |db: &Database| -> Vec<Value> { db.engines["people"].scan().unwrap().iter().map(|r| r[0].clone()).collect() };
+    assert_eq!(ids(&db), [Value::Int(1), Value::Int(3)]);
+
+    db.alter_table("people", |t| t.drop_column("seen")).unwrap();
+    assert_eq!(db.engines["people"].scan().unwrap(), db.tables["people"].rows);
+    assert_eq!(db.tables["people"].rows[0].len(), 2);
+
+    // A change that fails part way is undone, and the engine never hears of it
+    let failed = db.alter_table("people", |t| {
+        t.rename_column("name", "label")?;
+        t.drop_column("id")
+    });
+    assert!(failed.is_err());
+    assert!(db.tables["people"].resolve_column("name").is_ok());
+    assert!(db.alter_table("people", |_| Err::<(), _>(DbError::Other("no".to_string()))).is_err());
+    assert_eq!(db.engines["people"].scan().unwrap(), db.tables["people"].rows);
+}
@@ -0,0 +1,271 @@
+use database::database::config::Durability;
+use database::database::storage::StorageEngine;
+use database::database::validators::Database;
+use database::error::DbError;
+use database::table::data::{Column, DataType, FilterExpr, Table, Value};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Default)]
+struct RecordingEngine {
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl StorageEngine for RecordingEngine {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    fn scan(&self) -> Result<Vec<Vec<Value>>, String> {
+        Ok(vec![])
+    }
+
+    fn insert(&mut self, row: &[Value]) -> Result<(), String> {
+        self.log.lock().unwrap().push(format!("insert {}", row[0]));
+        Ok(())
+    }
+
+    fn update(&mut self, position: usize, _row: &[Value]) -> Result<(), String> {
+        self.log.lock().unwrap().push(format!("update {}", position));
+        Ok(())
+    }
+
+    fn delete(&mut self, positions: &[usize]) -> Result<(), String> {
+        self.log.lock().unwrap().push(format!("delete {:?}", positions));
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.log.lock().unwrap().push("flush".to_string());
+        Ok(())
+    }
+}
+
+// Stores rows like the memory engine, but refuses to insert the row with id `refuse`
+#[derive(Debug, Default)]
+struct FailingEngine {
+    rows: Arc<Mutex<Vec<Vec<Value>>>>,
+    refuse: i32,
+}
+
+impl StorageEngine for FailingEngine {
+    fn name(&self) -> &'static str {
+        "failing"
+    }
+
+    fn scan(&self) -> Result<Vec<Vec<Value>>, String> {
+        Ok(self.rows.lock().unwrap().clone())
+    }
+
+    fn insert(&mut self, row: &[Value]) -> Result<(), String> {
+        if row[0] == Value::Int(self.refuse) {
+            return Err("disk full".to_string());
+        }
+        self.rows.lock().unwrap().push(row.to_vec());
+        Ok(())
+    }
+
+    fn update(&mut self, position: usize, row: &[Value]) -> Result<(), String> {
+        self.rows.lock().unwrap()[position] = row.to_vec();
+        Ok(())
+    }
+
+    fn delete(&mut self, positions: &[usize]) -> Result<(), String> {
+        for &position in positions.iter().rev() {
+            self.rows.lock().unwrap().remove(position);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+fn table(name: &str, key: &str) -> Table {
+    let columns = vec![
+        Column {
+            name: key.to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    Table::new(name, columns, Some(vec![key.to_string()]))
+}
+
+fn row(id: i32, name: &str) -> Vec<Value> {
+    vec![Value::Int(id), Value::Varchar(name.to_string())]
+}
+
+fn users_and_logins() -> Database {
+    let mut db = Database::new();
+    db.tables.insert("users".to_string(), table("users", "id"));
+    db.tables.insert("logins".to_string(), table("logins", "user_id"));
+    db.insert("users", row(1, "Ann")).unwrap();
+    db
+}
+
+#[test]
+fn test_rollback_restores_every_table() {
+    let mut db = users_and_logins();
+    db.begin_transaction().unwrap();
+    assert!(db.in_transaction());
+
+    db.insert("users", row(2, "Bob")).unwrap();
+    db.insert("logins", row(2, "laptop")).unwrap();
+    db.delete_where("users", &FilterExpr::Eq("id".to_string(), Value::Int(1)))
+        .unwrap();
+    db.rollback().unwrap();
+
+    assert!(!db.in_transaction());
+    assert_eq!(db.tables["users"].rows, vec![row(1, "Ann")]);
+    assert!(db.tables["logins"].rows.is_empty());
+
+    // Indexes agree with the restored rows
+    let found = db.tables["users"].select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(1))).unwrap();
+    assert_eq!(found, vec![&row(1, "Ann")]);
+    assert!(db.insert("users", row(2, "Bob")).is_ok());
+}
+
+#[test]
+fn test_commit_holds_engine_writes_until_the_end() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut db = Database::new();
+    db.create_table_with_engine(table("users", "id"), Box::new(RecordingEngine { log: log.clone() }))
+        .unwrap();
+    db.tables.insert("logins".to_string(), table("logins", "user_id"));
+
+    db.begin_transaction().unwrap();
+    db.insert("users", row(1, "Ann")).unwrap();
+    db.insert("logins", row(1, "phone")).unwrap();
+    db.update_where(
+        "users",
+        &FilterExpr::Eq("id".to_string(), Value::Int(1)),
+        vec![None, Some(Value::Varchar("Anne".to_string()))],
+    )
+    .unwrap();
+    assert!(log.lock().unwrap().is_empty());
+
+    db.commit().unwrap();
+    assert_eq!(*log.lock().unwrap(), vec!["insert 1", "update 0", "flush"]);
+    assert_eq!(db.tables["users"].rows, vec![row(1, "Anne")]);
+    assert_eq!(db.tables["logins"].rows, vec![row(1, "phone")]);
+    assert!(db.tables["users"].transaction_backup.is_none());
+
+    // A rolled back transaction never reaches the engine
+    log.lock().unwrap().clear();
+    db.begin_transaction().unwrap();
+    db.insert("users", row(2, "Bob")).unwrap();
+    db.rollback().unwrap();
+    assert!(log.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_transaction_state_errors() {
+    let mut db = users_and_logins();
+    assert!(matches!(db.commit(), Err(DbError::TransactionError(_))));
+    assert!(matches!(db.rollback(), Err(DbError::TransactionError(_))));
+
+    db.begin_transaction().unwrap();
+    assert!(matches!(db.begin_transaction(), Err(DbError::TransactionError(_))));
+    db.commit().unwrap();
+
+    // A table with its own transaction open can't join a database-wide one
+    db.tables.get_mut("users").unwrap().begin_transaction().unwrap();
+    assert!(db.begin_transaction().is_err());
+    assert!(!db.in_transaction());
+}
+
+#[test]
+fn test_failed_batch_inside_a_transaction_keeps_earlier_writes() {
+    let mut db = users_and_logins();
+    db.begin_transaction().unwrap();
+    db.insert("users", row(2, "Bob")).unwrap();
+
+    // The duplicate key fails the batch after its first insert went in
+    let failed = db.batch(|b| {
+        b.insert("users", row(3, "Cat")).insert("users", row(1, "Dup"));
+    });
+    assert!(failed.is_err());
+    assert_eq!(db.tables["users"].rows, vec![row(1, "Ann"), row(2, "Bob")]);
+
+    db.rollback().unwrap();
+    assert_eq!(db.tables["users"].rows, vec![row(1, "Ann")]);
+}
+
+#[test]
+fn test_failed_engine_write_undoes_the_table_change() {
+    let stored = Arc::new(Mutex::new(vec![]));
+    let mut db = Database::new();
+    let engine = FailingEngine { rows: stored.clone(), refuse: 3 };
+    db.create_table_with_engine(table("users", "id"), Box::new(engine)).unwrap();
+    db.insert("users", row(1, "Ann")).unwrap();
+
+    assert!(db.insert("users", row(3, "Cat")).is_err());
+    assert_eq!(db.tables["users"].rows, vec![row(1, "Ann")]);
+    // The key is free again, so the index was rebuilt with the row gone
+    assert!(db.tables["users"].select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(3))).unwrap().is_empty());
+
+    // Part way through a batch, every row comes back out of table and engine
+    let failed = db.batch(|b| {
+        b.insert("users", row(2, "Bob")).insert("users", row(3, "Cat"));
+    });
+    assert!(failed.is_err());
+    assert_eq!(db.tables["users"].rows, vec![row(1, "Ann")]);
+    assert_eq!(*stored.lock().unwrap(), vec![row(1, "Ann")]);
+}
+
+#[test]
+fn test_failed_commit_rolls_the_transaction_back() {
+    let stored = Arc::new(Mutex::new(vec![]));
+    let mut db = Database::new();
+    let engine = FailingEngine { rows: stored.clone(), refuse: 3 };
+    db.create_table_with_engine(table("users", "id"), Box::new(engine)).unwrap();
+    db.tables.insert("logins".to_string(), table("logins", "user_id"));
+    db.insert("users", row(1, "Ann")).unwrap();
+
+    db.begin_transaction().unwrap();
+    db.insert("logins", row(1, "phone")).unwrap();
+    db.insert("users", row(2, "Bob")).unwrap();
+    db.insert("users", row(3, "Cat")).unwrap();
+    assert!(matches!(db.commit(), Err(DbError::TransactionError(_))));
+
+    assert!(!db.in_transaction());
+    assert_eq!(db.tables["users"].rows, vec![row(1, "Ann")]);
+    assert!(db.tables["logins"].rows.is_empty());
+    assert!(db.tables["users"].transaction_backup.is_none());
+    assert_eq!(*stored.lock().unwrap(), vec![row(1, "Ann")]);
+}
+
+#[test]
+fn test_rollback_undoes_created_and_dropped_tables() {
+    let mut db = users_and_logins();
+    db.begin_transaction().unwrap();
+    db.insert("users", row(2, "Bob")).unwrap();
+    db.create_table(table("orders", "id"), Durability::Persistent).unwrap();
+    db.insert("orders", row(1, "book")).unwrap();
+    let dropped = db.drop_table("users").unwrap();
+    assert_eq!(dropped.rows, vec![row(1, "Ann"), row(2, "Bob")]);
+    // Dropped and created again under the same name, the original still comes back
+    db.create_table(table("users", "id"), Durability::MemoryOnly).unwrap();
+    db.rollback().unwrap();
+
+    assert!(!db.tables.contains_key("orders"));
+    assert_eq!(db.tables["users"].rows, vec![row(1, "Ann")]);
+    assert!(db.tables["users"].transaction_backup.is_none());
+    assert_eq!(db.durability("users"), Some(Durability::Persistent));
+
+    // Committed, the same changes stay
+    db.begin_transaction().unwrap();
+    db.create_table(table("orders", "id"), Durability::Persistent).unwrap();
+    db.insert("orders", row(1, "book")).unwrap();
+    db.drop_table("logins").unwrap();
+    db.commit().unwrap();
+    assert_eq!(db.tables["orders"].rows, vec![row(1, "book")]);
+    assert!(db.tables["orders"].transaction_backup.is_none());
+    assert!(!db.tables.contains_key("logins"));
+}
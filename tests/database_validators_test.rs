@@ -1,5 +1,6 @@
 use database::database::validators::Database;
 use database::table::data::{Table, Column, DataType, Options};
+use database::error::DbError;
 
 use std::collections::HashMap;
 
@@ -12,6 +13,11 @@ fn test_validate_foreign_keys_valid() {
         columns: vec![],
         rows: vec![],
         primary_key: None,
+        indexes: HashMap::new(),
+        sequences: HashMap::new(),
+        transaction_backup: None,
+        stats: None,
+        compression: None,
     };
 
     let referencing_table = Table {
@@ -23,12 +29,20 @@ fn test_validate_foreign_keys_valid() {
         }],
         rows: vec![],
         primary_key: None,
+        indexes: HashMap::new(),
+        sequences: HashMap::new(),
+        transaction_backup: None,
+        stats: None,
+        compression: None,
     };
 
     tables.insert("users".to_string(), referenced_table);
     tables.insert("orders".to_string(), referencing_table);
 
-    let db = Database { tables };
+    let db = Database {
+        tables,
+        ..Database::new()
+    };
 
     assert!(db.validate_foreign_keys().is_ok());
 }
@@ -46,17 +60,27 @@ fn test_validate_foreign_keys_missing_table() {
         }],
         rows: vec![],
         primary_key: None,
+        indexes: HashMap::new(),
+        sequences: HashMap::new(),
+        transaction_backup: None,
+        stats: None,
+        compression: None,
     };
 
     tables.insert("orders".to_string(), referencing_table);
 
-    let db = Database { tables };
+    let db = Database {
+        tables,
+        ..Database::new()
+    };
 
     let result = db.validate_foreign_keys();
 
     assert!(result.is_err());
     assert_eq!(
         result.unwrap_err(),
-        "Table 'orders' has a foreign key to missing table 'users'."
+        DbError::ConstraintViolation(
+            "Table 'orders' has a foreign key to missing table 'users'.".to_string()
+        )
     );
 }
@@ -0,0 +1,111 @@
+use chrono::NaiveDate;
+use database::database::config::{DatabaseConfig, Durability};
+use database::database::validators::Database;
+use database::error::DbError;
+use database::table::data::{Column, DataType, FilterExpr, Table, Value, View};
+use std::fs;
+
+fn column(name: &str, datatype: DataType) -> Column {
+    Column {
+        name: name.to_string(),
+        datatype,
+        options: vec![],
+    }
+}
+
+fn date(y: i32, m: u32, d: u32) -> Value {
+    Value::Date(NaiveDate::from_ymd_opt(y, m, d).unwrap())
+}
+
+fn shop(root: &std::path::Path) -> Database {
+    let mut db = Database::with_config(DatabaseConfig::new("shop", root));
+    let mut customers = Table::new(
+        "customers",
+        vec![column("id", DataType::Int), column("name", DataType::Varchar(40))],
+        Some(vec!["id".to_string()]),
+    );
+    customers.insert(vec![Value::Int(1), Value::Varchar("ada".to_string())]).unwrap();
+    customers.insert(vec![Value::Int(2), Value::Varchar("bob".to_string())]).unwrap();
+
+    let mut orders = Table::new(
+        "orders",
+        vec![
+            column("id", DataType::Int),
+            column("customer_id", DataType::Int),
+            column("placed", DataType::Date),
+        ],
+        Some(vec!["id".to_string()]),
+    );
+    orders.insert(vec![Value::Int(10), Value::Int(1), date(2024, 1, 5)]).unwrap();
+    orders.insert(vec![Value::Int(11), Value::Int(2), date(2023, 6, 1)]).unwrap();
+
+    db.create_table(customers, Durability::Persistent).unwrap();
+    db.create_table(orders, Durability::Persistent).unwrap();
+    db
+}
+
+fn recent_orders() -> View {
+    View::new("orders")
+        .join("customers", "orders.customer_id", "customers.id")
+        .filter(FilterExpr::Ge("orders.placed".to_string(), date(2024, 1, 1)))
+        .select_exprs(vec!["orders.id".parse().unwrap(), "UPPER(customers.name)".parse().unwrap()])
+}
+
+#[test]
+fn test_views_see_current_data() {
+    let mut db = shop(&std::env::temp_dir());
+    db.create_view("recent_orders", recent_orders()).unwrap();
+
+    let view = db.query_view("recent_orders").unwrap();
+    let names: Vec<&str> = view.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["orders.id", "UPPER(customers.name)"]);
+    assert_eq!(view.rows, [vec![Value::Int(10), Value::Varchar("ADA".to_string())]]);
+
+    db.tables
+        .get_mut("orders")
+        .unwrap()
+        .insert(vec![Value::Int(12), Value::Int(2), date(2024, 3, 9)])
+        .unwrap();
+    assert_eq!(db.query_view("recent_orders").unwrap().rows.len(), 2);
+
+    // A view with no joins or columns keeps the table's own column names
+    db.create_view("all_customers", View::new("customers")).unwrap();
+    let all = db.query_view("all_customers").unwrap();
+    assert_eq!(all.columns[1].name, "name");
+    assert_eq!(all.rows.len(), 2);
+
+    db.drop_view("all_customers").unwrap();
+    assert!(matches!(db.query_view("all_customers"), Err(DbError::NotFound(_))));
+}
+
+#[test]
+fn test_create_view_checks_the_definition() {
+    let mut db = shop(&std::env::temp_dir());
+    assert!(db.create_view("v", View::new("missing")).is_err());
+    assert!(db.create_view("v", View::new("orders").select(&["total"])).is_err());
+    assert!(db.create_view("orders", View::new("customers")).is_err());
+    assert!(db.views.is_empty());
+
+    db.create_view("v", View::new("orders")).unwrap();
+    assert!(db.create_table(Table::new("v", vec![], None), Durability::Persistent).is_err());
+
+    db.drop_table("orders").unwrap();
+    assert!(db.query_view("v").is_err());
+}
+
+#[test]
+fn test_views_are_saved_with_the_catalog() {
+    let root = std::env::temp_dir().join("db_views_catalog_test");
+    let _ = fs::remove_dir_all(&root);
+    let path = root.to_str().unwrap();
+
+    let mut db = shop(&root);
+    db.create_view("recent_orders", recent_orders()).unwrap();
+    db.save(path).unwrap();
+
+    let loaded = Database::load(path).unwrap();
+    assert_eq!(loaded.views["recent_orders"], recent_orders());
+    assert_eq!(loaded.query_view("recent_orders").unwrap().rows, db.query_view("recent_orders").unwrap().rows);
+
+    fs::remove_dir_all(&root).unwrap();
+}
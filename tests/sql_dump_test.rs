@@ -0,0 +1,259 @@
+use database::database::config::Durability;
+use database::database::validators::Database;
+use database::table::data::{Collation, Column, DataType, DefaultExpr, FkAction, MaskPolicy, Options, Table, Value};
+use std::fs;
+
+fn sizes() -> Vec<String> {
+    vec!["small".to_string(), "large".to_string()]
+}
+
+fn shop() -> Database {
+    let mut db = Database::new();
+
+    let customers = Table::new(
+        "customers",
+        vec![
+            Column {
+                name: "id".to_string(),
+                datatype: DataType::Int,
+                options: vec![Options::Identity { start: 100, increment: 5 }],
+            },
+            Column {
+                name: "name".to_string(),
+                datatype: DataType::Text,
+                options: vec![Options::NotNull, Options::Unique],
+            },
+            Column {
+                name: "ssn".to_string(),
+                datatype: DataType::Varchar(255),
+                options: vec![Options::Mask(MaskPolicy::Redact)],
+            },
+        ],
+        Some(vec!["id".to_string()]),
+    );
+    db.create_table(customers, Durability::Persistent).unwrap();
+
+    // Sorts before "customers" but references it, so it must be dumped after
+    let mut baskets = Table::new(
+        "baskets",
+        vec![
+            Column {
+                name: "customer".to_string(),
+                datatype: DataType::Int,
+                options: vec![Options::FK("customers".to_string())],
+            },
+            Column {
+                name: "size".to_string(),
+                datatype: DataType::Enum(sizes()),
+                options: vec![Options::Default(DefaultExpr::Literal(Value::Enum("small".to_string())))],
+            },
+            Column {
+                name: "grade".to_string(),
+                datatype: DataType::Char(1),
+                options: vec![],
+            },
+            Column {
+                name: "created".to_string(),
+                datatype: DataType::DateTime,
+                options: vec![Options::Default(DefaultExpr::CurrentTimestamp)],
+            },
+        ],
+        None,
+    );
+    baskets
+        .insert(vec![
+            Value::Int(100),
+            Value::Enum("large".to_string()),
+            Value::Char(' '),
+            Value::from_datetime_str("2024-03-01 12:30:00").unwrap(),
+        ])
+        .unwrap();
+    db.create_table(baskets, Durability::Persistent).unwrap();
+
+    let customers = db.tables.get_mut("customers").unwrap();
+    customers
+        .insert(vec![Value::Null, Value::Text("O'Brien".to_string()), Value::Null])
+        .unwrap();
+    db
+}
+
+#[test]
+fn test_dump_lists_referenced_tables_first() {
+    let dump = shop().sql_dump();
+
+    let customers = dump.find("CREATE TABLE \"customers\"").unwrap();
+    let baskets = dump.find("CREATE TABLE \"baskets\"").unwrap();
+    assert!(customers < baskets);
+
+    // ENUMs are PostgreSQL types of their own
+    assert!(dump.contains("CREATE TYPE \"baskets_size\" AS ENUM ('small', 'large');\nCREATE TABLE \"baskets\""));
+    assert!(dump.contains("\"size\" \"baskets_size\" DEFAULT 'small'"));
+    assert!(dump.contains("\"id\" INTEGER GENERATED BY DEFAULT AS IDENTITY (START WITH 100 INCREMENT BY 5)"));
+    assert!(dump.contains("INSERT INTO \"customers\" VALUES (100, 'O''Brien', NULL);"));
+    assert!(dump.contains("  PRIMARY KEY (\"id\")\n);"));
+    // Masking has no SQL form
+    assert!(!dump.contains("Redact"));
+}
+
+#[test]
+fn test_restore_rebuilds_tables_and_rows() {
+    let db = shop();
+    let path = std::env::temp_dir().join("sql_dump_round_trip.sql");
+    let path = path.to_str().unwrap();
+    db.dump_sql(path).unwrap();
+
+    let mut restored = Database::new();
+    restored.restore_sql(path).unwrap();
+    fs::remove_file(path).unwrap();
+
+    for name in ["customers", "baskets"] {
+        let (original, copy) = (&db.tables[name], &restored.tables[name]);
+        assert_eq!(copy.rows, original.rows);
+        assert_eq!(copy.primary_key, original.primary_key);
+    }
+    assert_eq!(restored.tables["baskets"].columns, db.tables["baskets"].columns);
+    assert_eq!(restored.tables["customers"].columns[2].options, vec![]);
+
+    // Enum values inserted after the restore are still checked against the allowed list
+    assert!(restored
+        .execute("INSERT INTO baskets (customer, size) VALUES (100, 'huge')")
+        .is_err());
+    assert_eq!(
+        restored
+            .execute("INSERT INTO baskets (customer, size, grade) VALUES (100, 'small', 'A')")
+            .unwrap()
+            .affected(),
+        Some(1)
+    );
+}
+
+fn column(name: &str, datatype: DataType, options: Vec<Options>) -> Column {
+    Column {
+        name: name.to_string(),
+        datatype,
+        options,
+    }
+}
+
+#[test]
+fn test_dump_writes_postgres_forms_that_restore() {
+    let mut db = shop();
+    let mut orders = Table::new(
+        "orders",
+        vec![
+            column("id", DataType::Int, vec![Options::NotNull, Options::Autoincrement]),
+            column(
+                "customer",
+                DataType::Int,
+                vec![Options::FK("customers".to_string()), Options::OnUpdate(FkAction::Cascade), Options::NotNull],
+            ),
+            column("extras", DataType::Set(sizes()), vec![]),
+            column("note", DataType::Varchar(40), vec![Options::Collate(Collation::CaseInsensitive), Options::Unique]),
+            column(
+                "due",
+                DataType::DateTime,
+                vec![Options::Default(DefaultExpr::Add(Box::new(DefaultExpr::CurrentTimestamp), 7))],
+            ),
+            column("touched", DataType::DateTime, vec![Options::OnUpdateNow]),
+            column("double_id", DataType::Int, vec![Options::Generated("id * 2".parse().unwrap())]),
+        ],
+        Some(vec!["id".to_string()]),
+    );
+    orders
+        .insert(vec![
+            Value::Null,
+            Value::Int(100),
+            Value::Set(vec!["small".to_string(), "large".to_string()]),
+            Value::Varchar("say \"hi\", twice".to_string()),
+            Value::from_datetime_str("2024-03-08 12:00:00").unwrap(),
+            Value::Null,
+            Value::Null,
+        ])
+        .unwrap();
+    db.create_table(orders, Durability::Persistent).unwrap();
+
+    let dump = db.sql_dump();
+    for form in [
+        "CREATE COLLATION IF NOT EXISTS \"nocase\" (provider = icu, locale = 'und-u-ks-level2', deterministic = false);",
+        "\"id\" INTEGER NOT NULL GENERATED BY DEFAULT AS IDENTITY",
+        "\"customer\" INTEGER REFERENCES \"customers\" ON UPDATE CASCADE NOT NULL",
+        "\"extras\" \"orders_extras\"[]",
+        "\"note\" VARCHAR(40) COLLATE \"nocase\" UNIQUE",
+        "DEFAULT CURRENT_TIMESTAMP + INTERVAL '7 days'",
+        "\"touched\" TIMESTAMP,",
+        "GENERATED ALWAYS AS (id * 2) STORED",
+        "'{\"small\",\"large\"}'",
+    ] {
+        assert!(dump.contains(form), "{} not in\n{}", form, dump);
+    }
+    for mysql_only in ["AUTOINCREMENT", "ON UPDATE CURRENT_TIMESTAMP", "ENUM("] {
+        assert!(!dump.contains(mysql_only), "{}", mysql_only);
+    }
+
+    let path = std::env::temp_dir().join("sql_dump_postgres_forms.sql");
+    let path = path.to_str().unwrap();
+    db.dump_sql(path).unwrap();
+    let mut restored = Database::new();
+    restored.restore_sql(path).unwrap();
+    fs::remove_file(path).unwrap();
+
+    // Everything but ON UPDATE CURRENT_TIMESTAMP comes back
+    let (original, copy) = (&db.tables["orders"], &restored.tables["orders"]);
+    assert_eq!(copy.rows, original.rows);
+    assert_eq!(copy.columns[..5], original.columns[..5]);
+    assert_eq!(copy.columns[5].options, vec![]);
+    assert_eq!(copy.columns[6], original.columns[6]);
+}
+
+#[test]
+fn test_restore_reads_postgres_and_sqlite_dumps() {
+    let dir = std::env::temp_dir();
+    let restore = |name: &str, sql: &str| {
+        let path = dir.join(name);
+        fs::write(&path, sql).unwrap();
+        let mut db = Database::new();
+        db.restore_sql(path.to_str().unwrap()).unwrap();
+        fs::remove_file(path).unwrap();
+        db
+    };
+
+    let postgres = restore(
+        "sql_dump_postgres.sql",
+        "SET client_encoding = 'UTF8';
+        SET standard_conforming_strings = on;
+        CREATE COLLATION IF NOT EXISTS nocase (provider = icu, locale = 'und-u-ks-level2', deterministic = false);
+        CREATE TYPE size AS ENUM ('small', 'large');
+        CREATE TABLE baskets (
+            id integer GENERATED BY DEFAULT AS IDENTITY (START WITH 10 INCREMENT BY 10),
+            size size DEFAULT 'small',
+            extras size[],
+            label text COLLATE \"C\",
+            due date DEFAULT CURRENT_DATE - INTERVAL '1 day',
+            PRIMARY KEY (id)
+        );
+        INSERT INTO baskets (size, extras, label) VALUES ('large', '{small,\"large\"}', 'Gift');",
+    );
+    let baskets = &postgres.tables["baskets"];
+    assert_eq!(baskets.columns[1].datatype, DataType::Enum(sizes()));
+    assert_eq!(baskets.columns[2].datatype, DataType::Set(sizes()));
+    assert_eq!(baskets.columns[3].collation(), Collation::Binary);
+    assert_eq!(
+        baskets.columns[4].options,
+        [Options::Default(DefaultExpr::Add(Box::new(DefaultExpr::CurrentDate), -1))]
+    );
+    assert_eq!(baskets.rows[0][..3], [Value::Int(10), Value::Enum("large".to_string()), Value::Set(sizes())]);
+
+    let sqlite = restore(
+        "sql_dump_sqlite.sql",
+        "PRAGMA foreign_keys=OFF;
+        BEGIN TRANSACTION;
+        CREATE TABLE customers(id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL COLLATE NOCASE);
+        INSERT INTO customers VALUES(1,'Ann');
+        INSERT INTO customers VALUES(2,'bob');
+        COMMIT;",
+    );
+    let customers = &sqlite.tables["customers"];
+    assert_eq!(customers.columns[0].options, [Options::Autoincrement]);
+    assert_eq!(customers.columns[1].collation(), Collation::CaseInsensitive);
+    assert_eq!(customers.rows.len(), 2);
+}
@@ -0,0 +1,138 @@
+use database::database::validators::Database;
+use database::sql::executor::QueryResult;
+use database::table::data::Value;
+
+fn people() -> Database {
+    let mut db = Database::new();
+    db.execute_script(
+        "CREATE TABLE people (id INT PRIMARY KEY AUTOINCREMENT, name VARCHAR NOT NULL, \
+             dept VARCHAR, age INT DEFAULT 30);
+         INSERT INTO people (name, dept, age) VALUES ('Ann', 'eng', 41), ('Bob', 'ops', 25);
+         INSERT INTO people (name, dept) VALUES ('Cat', 'eng');",
+    )
+    .unwrap();
+    db
+}
+
+fn rows(db: &mut Database, sql: &str) -> Vec<Vec<Value>> {
+    db.execute(sql).unwrap().rows().unwrap().rows.clone()
+}
+
+#[test]
+fn test_select_where_order_by_and_group_by() {
+    let mut db = people();
+
+    let names = rows(&mut db, "SELECT name, id FROM people WHERE age >= 30 ORDER BY name DESC");
+    assert_eq!(
+        names,
+        vec![
+            vec![Value::Varchar("Cat".to_string()), Value::Int(3)],
+            vec![Value::Varchar("Ann".to_string()), Value::Int(1)],
+        ]
+    );
+
+    let result = db
+        .execute("SELECT dept, count(*), max(age) FROM people GROUP BY dept ORDER BY dept")
+        .unwrap();
+    let result = result.rows().unwrap();
+    let headers: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(headers, vec!["dept", "count(*)", "max(age)"]);
+    assert_eq!(
+        result.rows[0],
+        vec![Value::Varchar("eng".to_string()), Value::BigInt(2), Value::Int(41)]
+    );
+
+    let total = rows(&mut db, "SELECT COUNT(*), sum(age) FROM people WHERE dept = 'eng'");
+    assert_eq!(total, vec![vec![Value::BigInt(2), Value::Double(71.0)]]);
+
+    assert!(db.execute("SELECT name, count(*) FROM people GROUP BY dept").is_err());
+
+    let spread = rows(&mut db, "SELECT MEDIAN(age), VARIANCE(age) FROM people");
+    assert_eq!(spread, vec![vec![Value::Double(30.0), Value::Double(67.0)]]);
+
+    let depts = rows(&mut db, "SELECT COUNT(DISTINCT dept), COUNT(dept) FROM people");
+    assert_eq!(depts, vec![vec![Value::BigInt(2), Value::BigInt(3)]]);
+    assert!(db.execute("SELECT missing FROM people").is_err());
+
+    let page = rows(&mut db, "SELECT name FROM people ORDER BY age DESC LIMIT 1 OFFSET 1");
+    assert_eq!(page, vec![vec![Value::Varchar("Cat".to_string())]]);
+    let skipped = rows(&mut db, "SELECT dept FROM people GROUP BY dept OFFSET 1");
+    assert_eq!(skipped, vec![vec![Value::Varchar("ops".to_string())]]);
+    assert!(db.execute("SELECT name FROM people LIMIT -1").is_err());
+}
+
+#[test]
+fn test_update_delete_and_drop() {
+    let mut db = people();
+
+    let updated = db.execute("UPDATE people SET dept = 'ops', age = 26 WHERE name = 'Ann'").unwrap();
+    assert_eq!(updated.affected(), Some(1));
+    assert_eq!(rows(&mut db, "SELECT * FROM people WHERE dept = 'ops'").len(), 2);
+
+    // A failed statement leaves the table as it was
+    assert!(db.execute("UPDATE people SET name = NULL").is_err());
+    assert_eq!(rows(&mut db, "SELECT name FROM people WHERE name = 'Bob'").len(), 1);
+
+    assert_eq!(db.execute("DELETE FROM people WHERE age < 30").unwrap().affected(), Some(2));
+    assert_eq!(db.execute("DELETE FROM people").unwrap().affected(), Some(1));
+    assert!(db.tables["people"].rows.is_empty());
+
+    assert!(matches!(db.execute("DROP TABLE people").unwrap(), QueryResult::Done));
+    assert!(db.execute("SELECT * FROM people").is_err());
+    assert!(db.execute("DROP TABLE people").is_err());
+    assert!(db.execute("DROP TABLE IF EXISTS people").is_ok());
+}
+
+#[test]
+fn test_insert_checks_values_against_columns() {
+    let mut db = people();
+
+    assert!(db.execute("INSERT INTO people (name) VALUES ('Dee', 'x')").is_err());
+    assert!(db.execute("INSERT INTO people (name, age) VALUES ('Dee', 'old')").is_err());
+    assert!(db.execute("INSERT INTO people (nope) VALUES (1)").is_err());
+    assert_eq!(db.tables["people"].rows.len(), 3);
+
+    // A bad row fails the whole statement, even one that only clashes with its neighbour
+    assert!(db.execute("INSERT INTO people (name, dept) VALUES ('Dee', 'ops'), (NULL, 'ops')").is_err());
+    assert!(db.execute("INSERT INTO people (id, name) VALUES (7, 'Dee'), (7, 'Eve')").is_err());
+    assert_eq!(db.tables["people"].rows.len(), 3);
+    db.execute("INSERT INTO people (name) VALUES ('Dee')").unwrap();
+    assert_eq!(db.tables["people"].rows[3][0], Value::Int(4));
+
+    db.execute("CREATE TABLE IF NOT EXISTS people (id INT)").unwrap();
+    assert!(db.execute("CREATE TABLE people (id INT)").is_err());
+    assert_eq!(db.tables["people"].columns.len(), 4);
+}
+
+#[test]
+fn test_where_null_tests() {
+    let mut db = people();
+    db.execute("INSERT INTO people (name) VALUES ('Dan')").unwrap();
+
+    assert!(rows(&mut db, "SELECT name FROM people WHERE dept = NULL").is_empty());
+    assert_eq!(rows(&mut db, "SELECT name FROM people WHERE dept != 'eng'").len(), 1);
+    assert_eq!(
+        rows(&mut db, "SELECT name FROM people WHERE dept IS NULL"),
+        vec![vec![Value::Varchar("Dan".to_string())]]
+    );
+    assert_eq!(rows(&mut db, "SELECT name FROM people WHERE dept IS NOT NULL").len(), 3);
+    assert!(db.execute("SELECT name FROM people WHERE dept IS 'eng'").is_err());
+}
+
+#[test]
+fn test_where_and_or_not() {
+    let mut db = people();
+    db.execute("CREATE TABLE t (id INT, name VARCHAR, flag BOOLEAN)").unwrap();
+    db.execute("INSERT INTO t VALUES (1, 'a', true), (1, 'b', false), (2, 'c', true)").unwrap();
+    assert_eq!(
+        rows(&mut db, "SELECT name FROM t WHERE id = 1 AND flag = true"),
+        vec![vec![Value::Varchar("a".to_string())]]
+    );
+    assert_eq!(rows(&mut db, "SELECT name FROM t WHERE id = 2 OR NOT flag = true").len(), 2);
+
+    let updated = db.execute("UPDATE people SET age = 50 WHERE dept = 'eng' AND NOT name = 'Ann'").unwrap();
+    assert_eq!(updated.affected(), Some(1));
+    let deleted = db.execute("DELETE FROM people WHERE (age = 50 OR age < 30) AND dept IS NOT NULL").unwrap();
+    assert_eq!(deleted.affected(), Some(2));
+    assert_eq!(rows(&mut db, "SELECT name FROM people"), vec![vec![Value::Varchar("Ann".to_string())]]);
+}
@@ -0,0 +1,208 @@
+use database::sql::ast::{CompareOp, Condition, Literal, SelectItem, Statement, WhereExpr};
+use database::sql::parser::{parse, parse_statement};
+use database::table::data::{AggFunc, Collation, Column, DataType, DefaultExpr, Expr, FkAction, Options, ScalarFunc, SortDirection, Value, MAX_VARCHAR_LENGTH};
+
+#[test]
+fn test_parse_create_and_select() {
+    let create = parse_statement(
+        "create table people (id INT PRIMARY KEY AUTOINCREMENT, name VARCHAR(40) NOT NULL, \
+         active BOOLEAN DEFAULT true);",
+    )
+    .unwrap();
+    match create {
+        Statement::CreateTable { name, columns, primary_key, .. } => {
+            assert_eq!(name, "people");
+            assert_eq!(primary_key, Some(vec!["id".to_string()]));
+            assert_eq!(columns[1].datatype, DataType::Varchar(40));
+            assert_eq!(columns[1].options, vec![Options::NotNull]);
+            assert_eq!(columns[2].options, vec![Options::Default(DefaultExpr::Literal(Value::Boolean(true)))]);
+        }
+        other => panic!("unexpected statement {:?}", other),
+    }
+
+    let select = match parse_statement(
+        "SELECT dept, COUNT(*), avg(people.age) FROM people WHERE name <> 'O''Brien' \
+         GROUP BY dept ORDER BY dept DESC",
+    )
+    .unwrap()
+    {
+        Statement::Select(select) => select,
+        other => panic!("unexpected statement {:?}", other),
+    };
+    assert_eq!(
+        select.items,
+        vec![
+            SelectItem::Column("dept".to_string()),
+            SelectItem::Aggregate { func: AggFunc::Count, column: None },
+            SelectItem::Aggregate { func: AggFunc::Avg, column: Some("people.age".to_string()) },
+        ]
+    );
+    assert_eq!(
+        select.filter,
+        Some(WhereExpr::Compare(Condition {
+            column: "name".to_string(),
+            op: CompareOp::Ne,
+            value: Literal::Str("O'Brien".to_string()),
+        }))
+    );
+    assert_eq!(select.group_by.as_deref(), Some("dept"));
+    assert_eq!(select.order_by, vec![("dept".to_string(), SortDirection::Desc)]);
+}
+
+#[test]
+fn test_parse_errors_and_scripts() {
+    let statements = parse("DELETE FROM t; ; UPDATE t SET a = -1.5 WHERE b >= 2;").unwrap();
+    assert_eq!(statements.len(), 2);
+    assert!(matches!(&statements[1], Statement::Update { assignments, .. }
+        if assignments == &vec![("a".to_string(), Literal::Number("-1.5".to_string()))]));
+
+    let err = parse_statement("SELECT FROM people").unwrap_err();
+    assert!(err.contains("Expected a name but found 'FROM'"), "{}", err);
+    assert!(parse_statement("SELECT * FROM t WHERE a = 'open").is_err());
+    assert!(parse_statement("INSERT INTO t VALUES (1) extra").is_err());
+    assert!(parse_statement("SELECT 1; SELECT 2").is_err());
+    assert!(parse_statement("CREATE TABLE t (a BLOB)").is_err());
+}
+
+#[test]
+fn test_parse_boolean_where() {
+    let cond = |column: &str, value: &str| {
+        Box::new(WhereExpr::Compare(Condition {
+            column: column.to_string(),
+            op: CompareOp::Eq,
+            value: Literal::Number(value.to_string()),
+        }))
+    };
+    let select = match parse_statement("SELECT * FROM t WHERE a = 1 OR NOT b = 2 AND c = 3").unwrap() {
+        Statement::Select(select) => select,
+        other => panic!("unexpected statement {:?}", other),
+    };
+    assert_eq!(
+        select.filter,
+        Some(WhereExpr::Or(
+            cond("a", "1"),
+            Box::new(WhereExpr::And(Box::new(WhereExpr::Not(cond("b", "2"))), cond("c", "3")))
+        ))
+    );
+
+    let delete = parse_statement("DELETE FROM t WHERE (a = 1 OR b = 2) AND c = 3").unwrap();
+    assert!(matches!(delete, Statement::Delete { filter: Some(WhereExpr::And(left, _)), .. }
+        if matches!(*left, WhereExpr::Or(..))));
+    assert!(parse_statement("SELECT * FROM t WHERE a = 1 AND").is_err());
+    assert!(parse_statement("SELECT * FROM t WHERE (a = 1").is_err());
+}
+
+#[test]
+fn test_parse_foreign_key_update_actions() {
+    let create = parse_statement(
+        "CREATE TABLE logins (user_id INT REFERENCES users ON UPDATE CASCADE, \
+         device_id INT REFERENCES devices ON UPDATE SET NULL, \
+         owner_id INT REFERENCES users ON UPDATE NO ACTION)",
+    )
+    .unwrap();
+    match create {
+        Statement::CreateTable { columns, .. } => {
+            let fk = |table: &str, action| vec![Options::FK(table.to_string()), Options::OnUpdate(action)];
+            assert_eq!(columns[0].options, fk("users", FkAction::Cascade));
+            assert_eq!(columns[1].options, fk("devices", FkAction::SetNull));
+            assert_eq!(columns[2].options, fk("users", FkAction::Restrict));
+        }
+        other => panic!("unexpected statement {:?}", other),
+    }
+    assert!(parse_statement("CREATE TABLE t (a INT REFERENCES u ON UPDATE NOTHING)").is_err());
+}
+
+#[test]
+fn test_parse_default_expressions() {
+    let create = parse_statement(
+        "CREATE TABLE members (joined DATE DEFAULT CURRENT_DATE, expires DATE DEFAULT CURRENT_DATE + 30, \
+         quota INT DEFAULT 100 - 10, seen DATETIME DEFAULT CURRENT_TIMESTAMP)",
+    )
+    .unwrap();
+    match create {
+        Statement::CreateTable { columns, .. } => {
+            let add = |base, n| Options::Default(DefaultExpr::Add(Box::new(base), n));
+            assert_eq!(columns[0].options, vec![Options::Default(DefaultExpr::CurrentDate)]);
+            assert_eq!(columns[1].options, vec![add(DefaultExpr::CurrentDate, 30)]);
+            assert_eq!(columns[2].options, vec![add(DefaultExpr::Literal(Value::Int(100)), -10)]);
+            assert_eq!(columns[3].options, vec![Options::Default(DefaultExpr::CurrentTimestamp)]);
+        }
+        other => panic!("unexpected statement {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_minus_without_spaces() {
+    let create = parse_statement(
+        "CREATE TABLE members (expires DATE DEFAULT CURRENT_DATE-30, quota INT DEFAULT -5, \
+         renews DATE DEFAULT CURRENT_DATE-INTERVAL '7 days')",
+    )
+    .unwrap();
+    match create {
+        Statement::CreateTable { columns, .. } => {
+            assert_eq!(columns[0].options, vec![Options::Default(DefaultExpr::Add(Box::new(DefaultExpr::CurrentDate), -30))]);
+            assert_eq!(columns[1].options, vec![Options::Default(DefaultExpr::Literal(Value::Int(-5)))]);
+            assert_eq!(columns[2].options, vec![Options::Default(DefaultExpr::Add(Box::new(DefaultExpr::CurrentDate), -7))]);
+        }
+        other => panic!("unexpected statement {:?}", other),
+    }
+
+    // A negative number still reads as one after an operator or a keyword
+    let select = parse_statement("SELECT * FROM members WHERE quota = -5 LIMIT 3").unwrap();
+    assert!(format!("{:?}", select).contains("\"-5\""), "{:?}", select);
+}
+
+#[test]
+fn test_parse_decimal_columns() {
+    let create = parse_statement("CREATE TABLE items (price DECIMAL(8, 2), weight NUMERIC(6), count NUMERIC)").unwrap();
+    match create {
+        Statement::CreateTable { columns, .. } => {
+            let types: Vec<DataType> = columns.into_iter().map(|c| c.datatype).collect();
+            assert_eq!(types, [DataType::Decimal(8, 2), DataType::Decimal(6, 0), DataType::Decimal(38, 0)]);
+        }
+        other => panic!("unexpected statement {:?}", other),
+    }
+    assert!(parse_statement("CREATE TABLE t (a DECIMAL(8, x))").is_err());
+}
+
+#[test]
+fn test_parse_string_lengths() {
+    let create = parse_statement("CREATE TABLE codes (a CHAR, b CHAR(3), c VARCHAR, d VARCHAR(12))").unwrap();
+    match create {
+        Statement::CreateTable { columns, .. } => {
+            let types: Vec<DataType> = columns.into_iter().map(|c| c.datatype).collect();
+            assert_eq!(
+                types,
+                [DataType::Char(1), DataType::Char(3), DataType::Varchar(MAX_VARCHAR_LENGTH), DataType::Varchar(12)]
+            );
+        }
+        other => panic!("unexpected statement {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_collations() {
+    let create = parse_statement("CREATE TABLE users (name VARCHAR(40) COLLATE NOCASE UNIQUE, city TEXT COLLATE LATIN_FOLD)").unwrap();
+    match create {
+        Statement::CreateTable { columns, .. } => {
+            let collations: Vec<Collation> = columns.iter().map(Column::collation).collect();
+            assert_eq!(collations, [Collation::CaseInsensitive, Collation::LatinFold]);
+            assert!(columns[0].options.contains(&Options::Unique));
+        }
+        other => panic!("unexpected statement {:?}", other),
+    }
+    assert!(parse_statement("CREATE TABLE users (name TEXT COLLATE FRENCH)").is_err());
+}
+
+#[test]
+fn test_parse_generated_columns() {
+    let create = parse_statement("CREATE TABLE users (name TEXT, shout TEXT GENERATED ALWAYS AS (UPPER(name)))").unwrap();
+    match create {
+        Statement::CreateTable { columns, .. } => {
+            let expected = Expr::Call(ScalarFunc::Upper, vec![Expr::column("name")]);
+            assert_eq!(columns[1].options, [Options::Generated(expected)]);
+        }
+        other => panic!("unexpected statement {:?}", other),
+    }
+    assert!(parse_statement("CREATE TABLE users (name TEXT, shout TEXT GENERATED ALWAYS AS (SHOUT(name)))").is_err());
+}
@@ -0,0 +1,148 @@
+use database::database::config::{DatabaseConfig, Durability};
+use database::database::validators::Database;
+use database::error::DbError;
+use database::storage::atomic::{self, SyncMode};
+use database::storage::file::stage_rows;
+use database::storage::journal::{journal_path, Journal};
+use database::table::data::{Column, DataType, Table, Value};
+use database::table::sharding::SHARD_ROW_THRESHOLD;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn columns() -> Vec<Column> {
+    vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "item".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ]
+}
+
+fn orders(count: i32) -> Table {
+    let mut table = Table::new("orders", columns(), Some(vec!["id".to_string()]));
+    for i in 0..count {
+        table.insert(vec![Value::Int(i), Value::Varchar(format!("item {}", i))]).unwrap();
+    }
+    table
+}
+
+fn files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> =
+        fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_failed_write_keeps_the_old_file() {
+    let dir = temp_dir("atomic_failed_write");
+    let path = dir.join("data.txt");
+    atomic::write_bytes(&path, b"first", SyncMode::Full).unwrap();
+
+    let result = atomic::write_file(&path, SyncMode::Full, |out| {
+        std::io::Write::write_all(out, b"half of the second")?;
+        Err(DbError::Other("crashed mid-write".to_string()))
+    });
+    assert!(result.is_err());
+    assert_eq!(fs::read(&path).unwrap(), b"first");
+    assert_eq!(files(&dir), vec!["data.txt"]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_saves_round_trip_under_every_sync_mode() {
+    for sync in [SyncMode::Off, SyncMode::Normal, SyncMode::Full] {
+        let dir = temp_dir(&format!("atomic_sync_{:?}", sync));
+        let mut config = DatabaseConfig::new("db", &dir);
+        config.sync = sync;
+        let mut db = Database::with_config(config);
+        db.create_table(orders(50), Durability::Persistent).unwrap();
+        db.save(dir.to_str().unwrap()).unwrap();
+        orders(3).save_as_view_synced(&dir, "db", "recent", sync).unwrap();
+
+        assert!(files(&dir).iter().all(|name| !name.ends_with(".tmp")), "{:?}", files(&dir));
+        let loaded = Database::load(dir.to_str().unwrap()).unwrap();
+        assert_eq!(loaded.tables["orders"].rows, orders(50).rows);
+        let view = fs::read_to_string(dir.join("db.recent.view.csv")).unwrap();
+        assert_eq!(view.lines().count(), 4);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
+
+#[test]
+fn test_switching_layouts_keeps_a_loadable_table() {
+    let dir = temp_dir("atomic_layouts");
+    let load = || Table::load_from_dir(&dir, "db", "orders", columns(), Some(vec!["id".to_string()]));
+
+    let sharded = orders(SHARD_ROW_THRESHOLD as i32 * 2 + 1);
+    sharded.save_to_dir(&dir, "db").unwrap();
+    assert_eq!(load().unwrap().rows.len(), sharded.rows.len());
+
+    // Shrinking to one page file drops the shards only after the pages are written
+    orders(10).save_to_dir(&dir, "db").unwrap();
+    assert_eq!(files(&dir), vec!["db.orders.pages", "db.orders.schema"]);
+    assert_eq!(load().unwrap().rows, orders(10).rows);
+
+    sharded.save_to_dir(&dir, "db").unwrap();
+    assert!(!dir.join("db.orders.pages").exists());
+    assert!(files(&dir).iter().all(|name| !name.ends_with(".tmp")));
+    assert_eq!(load().unwrap().rows.len(), sharded.rows.len());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_a_crash_mid_save_keeps_data_and_schema_together() {
+    let dir = temp_dir("atomic_crash");
+    let other = temp_dir("atomic_crash_other");
+    let load = || Table::load_from_dir(&dir, "db", "orders", columns(), Some(vec!["id".to_string()]));
+    let sharded = orders(SHARD_ROW_THRESHOLD as i32 * 2 + 1);
+    sharded.save_to_dir(&dir, "db").unwrap();
+    let shards: Vec<PathBuf> = files(&dir).iter().filter(|n| n.contains("shard")).map(|n| dir.join(n)).collect();
+
+    // What saving ten rows over the shards journals: the pages, the new sidecar, and
+    // the shards going
+    orders(10).save_to_dir(&other, "db").unwrap();
+    let schema = dir.join("db.orders.schema");
+    let journal_file = journal_path(&dir.join("db.orders.pages"));
+    let journal = |dir: &Path| {
+        fs::copy(other.join("db.orders.schema"), atomic::temp_path(&schema)).unwrap();
+        let mut journal = Journal::default();
+        stage_rows(&dir.join("db.orders.pages"), &orders(10).rows, &mut journal).unwrap();
+        journal.rename(&atomic::temp_path(&schema), &schema);
+        for shard in &shards {
+            journal.remove(shard);
+        }
+        journal.write(&journal_file, SyncMode::Full).unwrap();
+    };
+
+    // Cut short, the journal is thrown away along with the save
+    journal(&dir);
+    let bytes = fs::read(&journal_file).unwrap();
+    fs::write(&journal_file, &bytes[..bytes.len() - 10]).unwrap();
+    assert_eq!(load().unwrap().rows.len(), sharded.rows.len());
+    assert!(!journal_file.exists());
+
+    // Whole, it's finished on the next load
+    journal(&dir);
+    assert_eq!(load().unwrap().rows, orders(10).rows);
+    assert_eq!(files(&dir), vec!["db.orders.pages", "db.orders.schema"]);
+
+    fs::remove_dir_all(dir).unwrap();
+    fs::remove_dir_all(other).unwrap();
+}
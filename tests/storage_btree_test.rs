@@ -0,0 +1,171 @@
+use database::database::config::{DatabaseConfig, Durability};
+use database::database::history::AsOf;
+use database::database::validators::Database;
+use database::storage::btree::{DiskIndex, MAX_KEY_BYTES};
+use database::storage::page::PAGE_SIZE;
+use database::table::data::{Column, DataType, FilterExpr, IndexType, Table, Value};
+use std::fs;
+use std::path::PathBuf;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn orders(count: i32) -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "customer".to_string(),
+            datatype: DataType::Varchar(600),
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("orders", columns, Some(vec!["id".to_string()]));
+    for i in 0..count {
+        table.insert(vec![Value::Int(i), Value::Varchar(format!("customer {}", i % 97))]).unwrap();
+    }
+    table
+}
+
+fn ids(rows: Vec<&Vec<Value>>) -> Vec<Value> {
+    rows.into_iter().map(|row| row[0].clone()).collect()
+}
+
+fn disk<'a>(table: &'a Table, column: &str) -> &'a DiskIndex {
+    match &table.indexes[column] {
+        IndexType::Disk(disk) => disk,
+        other => panic!("expected a disk index, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_disk_index_answers_lookups_and_ranges() {
+    let dir = temp_dir("btree_lookups");
+    let mut table = orders(5000);
+    let scanned_eq = ids(table.select_where_expr(&FilterExpr::Eq("customer".to_string(), Value::Varchar("customer 5".to_string()))).unwrap());
+    let scanned_range = ids(table.select_where_expr(&FilterExpr::Between("id".to_string(), Value::Int(1200), Value::Int(1300))).unwrap());
+
+    table.create_disk_index("customer", &dir, "shop").unwrap();
+    table.create_disk_index("id", &dir, "shop").unwrap();
+    // Both trees share the table's page file, with many leaves under internal nodes
+    assert_eq!(disk(&table, "id").path(), dir.join("shop.orders.pages"));
+    assert_ne!(disk(&table, "id").id(), disk(&table, "customer").id());
+    assert!(fs::metadata(dir.join("shop.orders.pages")).unwrap().len() > 40 * PAGE_SIZE as u64);
+
+    let lookup = FilterExpr::Eq("customer".to_string(), Value::Varchar("customer 5".to_string()));
+    assert_eq!(ids(table.select_where_expr(&lookup).unwrap()), scanned_eq);
+    let range = FilterExpr::Between("id".to_string(), Value::Int(1200), Value::Int(1300));
+    assert_eq!(ids(table.select_where_expr(&range).unwrap()), scanned_range);
+    assert!(table.explain(&range).to_string().contains("IndexRange"), "{}", table.explain(&range));
+
+    // A copy of the table indexes in memory rather than share the original's tree
+    assert!(matches!(table.clone().indexes["id"], IndexType::BTree(_)));
+    let index = disk(&table, "id");
+    assert_eq!(index.entry_count(), Some(5000));
+    assert_eq!(index.get(&Value::Int(4999)), Some(vec![4999]));
+    assert_eq!(index.get(&Value::Int(5000)), Some(vec![]));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_disk_index_follows_writes() {
+    let dir = temp_dir("btree_writes");
+    let mut table = orders(10);
+    table.create_disk_index("id", &dir, "shop").unwrap();
+
+    // Enough inserts to split leaves and the root
+    for i in (10..3000).rev() {
+        table.insert(vec![Value::Int(i), Value::Varchar("late".to_string())]).unwrap();
+    }
+    table
+        .update_where(
+            FilterExpr::Eq("id".to_string(), Value::Int(7)),
+            vec![Some(Value::Int(-7)), None],
+        )
+        .unwrap();
+    table.delete_where(FilterExpr::Between("id".to_string(), Value::Int(0), Value::Int(2))).unwrap();
+
+    let index = disk(&table, "id");
+    assert!(!index.is_stale());
+    assert_eq!(index.entry_count(), Some(table.rows.len()));
+    for (key, row) in index.entries().unwrap() {
+        assert_eq!(table.rows[row][0], key);
+    }
+    assert_eq!(ids(table.select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(-7))).unwrap()), [Value::Int(-7)]);
+    assert!(table.select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(7))).unwrap().is_empty());
+    assert_eq!(table.select_where_expr(&FilterExpr::Ge("id".to_string(), Value::Int(2990))).unwrap().len(), 10);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_disk_index_cuts_long_keys_and_survives_a_reload() {
+    let root = temp_dir("btree_reload");
+    let mut table = orders(0);
+    let prefix = "x".repeat(MAX_KEY_BYTES);
+    for (i, suffix) in ["a", "b", "c"].into_iter().enumerate() {
+        table.insert(vec![Value::Int(i as i32), Value::Varchar(format!("{}{}", prefix, suffix))]).unwrap();
+    }
+    table.create_disk_index("customer", &root, "shop").unwrap();
+
+    // The index can't tell the keys apart, but the filter still does
+    let wanted = FilterExpr::Eq("customer".to_string(), Value::Varchar(format!("{}b", prefix)));
+    assert_eq!(ids(table.select_where_expr(&wanted).unwrap()), [Value::Int(1)]);
+    let after = FilterExpr::Gt("customer".to_string(), Value::Varchar(format!("{}a", prefix)));
+    assert_eq!(ids(table.select_where_expr(&after).unwrap()), [Value::Int(1), Value::Int(2)]);
+
+    let mut db = Database::with_config(DatabaseConfig::new("shop", &root));
+    db.create_table(table, Durability::Persistent).unwrap();
+    db.save(root.to_str().unwrap()).unwrap();
+    let loaded = Database::load(root.to_str().unwrap()).unwrap();
+    let orders = &loaded.tables["orders"];
+    assert!(!disk(orders, "customer").is_stale());
+    assert_eq!(disk(orders, "customer").path(), root.join("shop.orders.pages"));
+    assert_eq!(ids(orders.select_where_expr(&wanted).unwrap()), [Value::Int(1)]);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_copies_of_a_table_leave_its_disk_index_alone() {
+    let dir = temp_dir("btree_copies");
+    let mut db = Database::with_config(DatabaseConfig::new("shop", &dir));
+    db.create_table(orders(0), Durability::Persistent).unwrap();
+    db.retain_history("orders").unwrap();
+    for i in 0..500 {
+        db.insert("orders", vec![Value::Int(i), Value::Varchar("early".to_string())]).unwrap();
+    }
+    let table = db.tables.get_mut("orders").unwrap();
+    table.create_disk_index("id", &dir, "shop").unwrap();
+    let before = disk(table, "id").entries().unwrap();
+
+    let txn = db.last_txn();
+    db.delete_where("orders", &FilterExpr::Lt("id".to_string(), Value::Int(100))).unwrap();
+    db.copy_table("orders", "archive", false).unwrap();
+    let past = db.table_as_of("orders", AsOf::Txn(txn)).unwrap();
+    assert!(matches!(past.indexes["id"], IndexType::BTree(_)));
+    assert_eq!(ids(past.select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(5))).unwrap()), [Value::Int(5)]);
+
+    let table = db.tables.get_mut("orders").unwrap();
+    table.begin_transaction().unwrap();
+    table.delete_where(FilterExpr::Lt("id".to_string(), Value::Int(200))).unwrap();
+    assert_eq!(table.committed().rows.len(), 400);
+
+    // The original's tree still has every row it holds, and only those
+    let index = disk(table, "id");
+    assert_eq!(index.entry_count(), Some(300));
+    let mut expected: Vec<(Value, usize)> = before[200..].iter().map(|(key, row)| (key.clone(), row - 200)).collect();
+    expected.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(index.entries().unwrap(), expected);
+    assert!(db.tables["archive"].indexes.values().all(|index| !matches!(index, IndexType::Disk(_))));
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,149 @@
+use database::database::check::IssueKind;
+use database::database::config::{DatabaseConfig, Durability};
+use database::database::validators::Database;
+use database::error::DbError;
+use database::storage::atomic::SyncMode;
+use database::storage::checksum::{FileChecksum, BLOCK_SIZE};
+use database::storage::codec::encode_row;
+use database::storage::file::{read_rows, write_rows};
+use database::storage::page::{Page, PAGE_SIZE};
+use database::table::data::{Column, DataType, Table, Value};
+use database::table::sharding::SHARD_ROW_THRESHOLD;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn columns() -> Vec<Column> {
+    vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "note".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ]
+}
+
+fn notes(count: i32) -> Table {
+    let mut table = Table::new("notes", columns(), Some(vec!["id".to_string()]));
+    for i in 0..count {
+        table.insert(vec![Value::Int(i), Value::Varchar(format!("note number {}", i))]).unwrap();
+    }
+    table
+}
+
+fn load(dir: &Path) -> Result<Table, DbError> {
+    Table::load_from_dir(dir, "db", "notes", columns(), Some(vec!["id".to_string()]))
+}
+
+fn corruption_offset(result: Result<impl std::fmt::Debug, DbError>) -> u64 {
+    match result {
+        Err(DbError::Corruption { offset, .. }) => offset,
+        other => panic!("expected corruption, got {:?}", other),
+    }
+}
+
+fn edit(path: &Path, f: impl FnOnce(&mut Vec<u8>)) {
+    let mut bytes = fs::read(path).unwrap();
+    f(&mut bytes);
+    fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn test_page_files_report_the_corrupt_page() {
+    let dir = temp_dir("checksum_pages");
+    let table = notes(500);
+    table.save_to_dir(&dir, "db").unwrap();
+    let path = dir.join("db.notes.pages");
+    let len = fs::metadata(&path).unwrap().len() as usize;
+    assert!(len > 4 * PAGE_SIZE);
+
+    // One flipped bit in the middle of page 3
+    edit(&path, |bytes| bytes[3 * PAGE_SIZE + 2000] ^= 1);
+    assert!(!Page::checksum_matches(&fs::read(&path).unwrap()[3 * PAGE_SIZE..4 * PAGE_SIZE]));
+    assert_eq!(corruption_offset(load(&dir)), 3 * PAGE_SIZE as u64);
+
+    let mut db = Database::with_config(DatabaseConfig::new("db", &dir));
+    db.create_table(notes(500), Durability::MemoryOnly).unwrap();
+    let report = db.check(dir.to_str().unwrap());
+    assert!(report.issues.iter().any(|issue| issue.kind == IssueKind::Corrupted), "{:?}", report.issues);
+
+    // Cut short, even on a page boundary
+    table.save_to_dir(&dir, "db").unwrap();
+    edit(&path, |bytes| bytes.truncate(len - PAGE_SIZE));
+    assert_eq!(corruption_offset(load(&dir)), (len - PAGE_SIZE) as u64);
+    edit(&path, |bytes| bytes.truncate(len - PAGE_SIZE - 10));
+    assert_eq!(corruption_offset(load(&dir)), (len - PAGE_SIZE - 10) as u64);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_truncated_shards_fail_to_load() {
+    let dir = temp_dir("checksum_shards");
+    notes(SHARD_ROW_THRESHOLD as i32 + 1).save_to_dir(&dir, "db").unwrap();
+    let shard = dir.join("db.notes.shard1.csv");
+    let len = fs::metadata(&shard).unwrap().len();
+    assert!(len > BLOCK_SIZE as u64 + 100);
+    assert_eq!(load(&dir).unwrap().rows.len(), SHARD_ROW_THRESHOLD + 1);
+
+    // Losing the last lines still leaves a well-formed CSV
+    edit(&shard, |bytes| {
+        let cut = bytes[..bytes.len() - 1].iter().rposition(|&b| b == b'\n').unwrap() + 1;
+        bytes.truncate(cut);
+    });
+    let cut_len = fs::metadata(&shard).unwrap().len();
+    assert_eq!(corruption_offset(load(&dir)), cut_len);
+
+    notes(SHARD_ROW_THRESHOLD as i32 + 1).save_to_dir(&dir, "db").unwrap();
+    edit(&shard, |bytes| bytes[BLOCK_SIZE + 100] = b'7');
+    assert_eq!(corruption_offset(load(&dir)), BLOCK_SIZE as u64);
+
+    fs::remove_file(&shard).unwrap();
+    assert!(matches!(load(&dir), Err(DbError::NotFound(_))));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_checksums_of_files_and_older_page_files() {
+    let dir = temp_dir("checksum_files");
+    let path = dir.join("data.bin");
+    fs::write(&path, vec![5; BLOCK_SIZE * 2 + 10]).unwrap();
+    let checksum = FileChecksum::of(&path).unwrap();
+    assert_eq!(checksum.file_name, "data.bin");
+    assert_eq!((checksum.len, checksum.blocks.len()), (BLOCK_SIZE as u64 * 2 + 10, 3));
+    checksum.verify(&path).unwrap();
+    edit(&path, |bytes| bytes.push(0));
+    assert_eq!(corruption_offset(checksum.verify(&path)), BLOCK_SIZE as u64 * 2 + 10);
+
+    // Page files from before checksums still load, unverified
+    let pages = dir.join("t.pages");
+    let rows = vec![vec![Value::Int(1), Value::Varchar("old".to_string())]];
+    let mut bytes = vec![0; 2 * PAGE_SIZE];
+    bytes[..8].copy_from_slice(b"DBPAGES1");
+    bytes[8..12].copy_from_slice(&(PAGE_SIZE as u32).to_le_bytes());
+    let mut page = Page::new();
+    page.insert(&[&[0][..], &encode_row(&rows[0])].concat()).unwrap();
+    bytes[PAGE_SIZE..].copy_from_slice(page.as_bytes());
+    bytes[2 * PAGE_SIZE - 1] ^= 0xff;
+    fs::write(&pages, bytes).unwrap();
+    assert_eq!(read_rows(&pages).unwrap(), rows);
+
+    // Saving over one moves it to the current format
+    write_rows(&pages, &rows, SyncMode::default()).unwrap();
+    assert_eq!(&fs::read(&pages).unwrap()[..8], b"DBPAGES3");
+    assert_eq!(read_rows(&pages).unwrap(), rows);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,47 @@
+use chrono::{NaiveDate, NaiveTime};
+use database::error::DbError;
+use database::storage::codec::{decode_row, encode_row};
+use database::table::data::{Decimal, Value};
+
+#[test]
+fn test_rows_round_trip_every_value_type() {
+    let day = NaiveDate::from_ymd_opt(1815, 12, 10).unwrap();
+    let time = NaiveTime::from_hms_nano_opt(9, 30, 15, 123_456_789).unwrap();
+    let row = vec![
+        Value::Null,
+        Value::Char('é'),
+        Value::Varchar("Ada".to_string()),
+        Value::Text("line\nbreak, \"quoted\"".to_string()),
+        Value::Enum("large".to_string()),
+        Value::Set(vec!["a".to_string(), "b,c".to_string()]),
+        Value::Boolean(true),
+        Value::Int(-42),
+        Value::BigInt(i64::MIN),
+        Value::Float(1.5),
+        Value::Double(f64::MAX),
+        Value::Date(day),
+        Value::Time(time),
+        Value::DateTime(day.and_time(time)),
+        Value::Point(-12.5, 130.25),
+        Value::Decimal(Decimal::new(-123_456_789_012_345_678_901, 7)),
+    ];
+    let decoded = decode_row(&encode_row(&row)).unwrap();
+    assert_eq!(decoded, row);
+    // Values keep their exact variant, not just an equal one
+    assert!(matches!(decoded[2], Value::Varchar(_)));
+    assert!(matches!(decoded[8], Value::BigInt(i64::MIN)));
+}
+
+#[test]
+fn test_decode_rejects_damaged_rows() {
+    let bytes = encode_row(&[Value::Int(1), Value::Text("hello".to_string())]);
+    assert!(matches!(decode_row(&bytes[..bytes.len() - 1]), Err(DbError::IoError(_))));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(decode_row(&trailing).is_err());
+
+    let mut unknown_tag = bytes;
+    unknown_tag[4] = 99;
+    assert!(decode_row(&unknown_tag).is_err());
+}
@@ -0,0 +1,126 @@
+use database::database::config::{DatabaseConfig, Durability};
+use database::database::validators::Database;
+use database::storage::compress::{self, Compression};
+use database::table::data::{Column, DataType, FilterExpr, Table, Value};
+use database::table::sharding::SHARD_ROW_THRESHOLD;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn columns() -> Vec<Column> {
+    vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "city".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ]
+}
+
+fn visits(count: i32) -> Table {
+    let mut table = Table::new("visits", columns(), Some(vec!["id".to_string()]));
+    let cities = ["Springfield, Illinois", "Portland, Oregon", "Portland, Maine"];
+    for i in 0..count {
+        table.insert(vec![Value::Int(i), Value::Varchar(cities[i as usize % 3].to_string())]).unwrap();
+    }
+    table
+}
+
+fn files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> =
+        fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+    names.sort();
+    names
+}
+
+fn size(path: PathBuf) -> u64 {
+    fs::metadata(path).unwrap().len()
+}
+
+#[test]
+fn test_compressed_page_files_round_trip() {
+    let dir = temp_dir("compress_pages");
+    let mut table = visits(2000);
+    table.save_to_dir(&dir, "db").unwrap();
+    let plain = size(dir.join("db.visits.pages"));
+
+    for compression in Compression::ALL {
+        table.compression = Some(compression);
+        table.save_to_dir(&dir, "db").unwrap();
+        let file_name = format!("db.visits.pages.{}", compression.extension());
+        // Each save leaves only its own data file behind
+        assert_eq!(files(&dir), vec![file_name.clone(), "db.visits.schema".to_string()]);
+        assert!(size(dir.join(&file_name)) * 10 < plain, "{} is barely smaller", file_name);
+
+        let loaded = Table::load_from_dir(&dir, "db", "visits", columns(), None).unwrap();
+        assert_eq!(loaded.rows, table.rows);
+        assert_eq!(loaded.compression, Some(compression));
+    }
+
+    table.compression = None;
+    table.save_to_dir(&dir, "db").unwrap();
+    assert_eq!(files(&dir), vec!["db.visits.pages", "db.visits.schema"]);
+    assert_eq!(Table::load_from_dir(&dir, "db", "visits", columns(), None).unwrap().compression, None);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_compressed_shards_load_and_pass_checks() {
+    let dir = temp_dir("compress_shards");
+    let mut table = visits(SHARD_ROW_THRESHOLD as i32 * 2 + 1);
+    table.compression = Some(Compression::Gzip);
+
+    let mut db = Database::with_config(DatabaseConfig::new("db", &dir));
+    db.create_table(table, Durability::Persistent).unwrap();
+    db.save(dir.to_str().unwrap()).unwrap();
+    let shards: Vec<String> = files(&dir).into_iter().filter(|name| name.contains(".shard")).collect();
+    assert_eq!(shards, vec!["db.visits.shard0.csv.gz", "db.visits.shard1.csv.gz", "db.visits.shard2.csv.gz"]);
+
+    let loaded = Database::load(dir.to_str().unwrap()).unwrap();
+    let visits = &loaded.tables["visits"];
+    assert_eq!(visits.rows.len(), SHARD_ROW_THRESHOLD * 2 + 1);
+    assert_eq!(visits.compression, Some(Compression::Gzip));
+    let found = visits.select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(20000))).unwrap();
+    assert_eq!(found[0][1], Value::Varchar("Portland, Maine".to_string()));
+
+    let report = loaded.check(dir.to_str().unwrap());
+    assert!(report.is_ok(), "{:?}", report.issues);
+    assert_eq!(report.rows_checked, SHARD_ROW_THRESHOLD * 2 + 1);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_compression_follows_the_extension() {
+    let dir = temp_dir("compress_extension");
+    let plain = dir.join("notes.csv");
+    assert_eq!(Compression::Zstd.path(&plain), dir.join("notes.csv.zst"));
+    assert_eq!(Compression::of(&dir.join("notes.csv.gz")), Some(Compression::Gzip));
+    assert_eq!(Compression::of(&plain), None);
+    assert_eq!(compress::strip_extension("notes.csv.zst"), "notes.csv");
+    assert_eq!(Compression::parse("ZSTD"), Some(Compression::Zstd));
+
+    let text = "a line said twice\n".repeat(100);
+    compress::write(&Compression::Gzip.path(&plain), text.as_bytes()).unwrap();
+    assert_eq!(compress::find(&plain), Some(dir.join("notes.csv.gz")));
+    assert_eq!(compress::read(&dir.join("notes.csv.gz")).unwrap(), text.as_bytes());
+    assert!(size(dir.join("notes.csv.gz")) < 100);
+
+    // Misnamed data fails to decompress instead of reading as garbage
+    fs::write(dir.join("fake.csv.zst"), text.as_bytes()).unwrap();
+    assert!(compress::read(&dir.join("fake.csv.zst")).is_err());
+
+    fs::remove_dir_all(dir).unwrap();
+}
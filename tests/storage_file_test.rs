@@ -0,0 +1,129 @@
+use database::error::DbError;
+use database::storage::atomic::SyncMode;
+use database::storage::file::{pack_rows, read_rows, stage_rows, write_rows};
+use database::storage::journal::{journal_path, Journal};
+use database::storage::page::PAGE_SIZE;
+use database::table::data::{Column, DataType, Table, Value};
+use std::fs;
+use std::path::PathBuf;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn rows(count: i32) -> Vec<Vec<Value>> {
+    (0..count)
+        .map(|i| vec![Value::Int(i), Value::Varchar(format!("row number {}", i))])
+        .collect()
+}
+
+#[test]
+fn test_appending_rows_rewrites_only_the_last_pages() {
+    let dir = temp_dir("storage_file_append");
+    let path = dir.join("t.pages");
+    let mut data = rows(1000);
+
+    let first = write_rows(&path, &data, SyncMode::default()).unwrap();
+    let pages = pack_rows(&data).len();
+    assert!(pages > 5);
+    assert_eq!(first, pages + 1);
+    assert_eq!(fs::metadata(&path).unwrap().len() as usize, (pages + 1) * PAGE_SIZE);
+
+    // Nothing changed, nothing written
+    assert_eq!(write_rows(&path, &data, SyncMode::default()).unwrap(), 0);
+
+    data.push(vec![Value::Int(1000), Value::Varchar("appended".to_string())]);
+    assert!(write_rows(&path, &data, SyncMode::default()).unwrap() <= 2);
+    assert_eq!(read_rows(&path).unwrap(), data);
+
+    // Shrinking trims the file
+    write_rows(&path, &data[..3], SyncMode::default()).unwrap();
+    assert_eq!(fs::metadata(&path).unwrap().len() as usize, 2 * PAGE_SIZE);
+    assert_eq!(read_rows(&path).unwrap(), data[..3]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_rows_larger_than_a_page_span_pages() {
+    let dir = temp_dir("storage_file_large");
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "body".to_string(),
+            datatype: DataType::Text,
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("docs", columns.clone(), Some(vec!["id".to_string()]));
+    table.insert(vec![Value::Int(1), Value::Text("short".to_string())]).unwrap();
+    table.insert(vec![Value::Int(2), Value::Text("x".repeat(3 * PAGE_SIZE))]).unwrap();
+    table.insert(vec![Value::Int(3), Value::Null]).unwrap();
+    assert!(pack_rows(&table.rows).len() >= 4);
+
+    table.save_to_dir(&dir, "testdb").unwrap();
+    assert!(dir.join("testdb.docs.pages").exists());
+    assert!(!dir.join("testdb.docs.csv").exists());
+    let loaded = Table::load_from_dir(&dir, "testdb", "docs", columns, Some(vec!["id".to_string()])).unwrap();
+    assert_eq!(loaded.rows, table.rows);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_read_rejects_files_that_are_not_page_files() {
+    let dir = temp_dir("storage_file_corrupt");
+    let path = dir.join("t.pages");
+    fs::write(&path, "id,name\n1,Ada\n").unwrap();
+    assert!(matches!(read_rows(&path), Err(DbError::IoError(_))));
+
+    write_rows(&path, &rows(10), SyncMode::default()).unwrap();
+    let mut bytes = fs::read(&path).unwrap();
+    // Point the first slot of the first data page outside the page
+    bytes[PAGE_SIZE + 4..PAGE_SIZE + 6].copy_from_slice(&[0xff, 0xff]);
+    fs::write(&path, bytes).unwrap();
+    assert!(read_rows(&path).is_err());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_a_crash_mid_save_leaves_the_old_rows_or_the_new() {
+    let dir = temp_dir("storage_file_journal");
+    let path = dir.join("t.pages");
+    let old = rows(500);
+    write_rows(&path, &old, SyncMode::default()).unwrap();
+    let mut new = rows(700);
+    new[3][1] = Value::Varchar("changed".to_string());
+
+    // Crashing once the journal is written: the next open finishes the save
+    let mut journal = Journal::default();
+    assert!(stage_rows(&path, &new, &mut journal).unwrap() > 1);
+    journal.write(&journal_path(&path), SyncMode::Full).unwrap();
+    assert_eq!(read_rows(&path).unwrap(), new);
+    assert!(!journal_path(&path).exists());
+
+    // Crashing while the journal is written: the save never happened
+    let mut journal = Journal::default();
+    stage_rows(&path, &old, &mut journal).unwrap();
+    journal.write(&journal_path(&path), SyncMode::Full).unwrap();
+    let cut = fs::metadata(journal_path(&path)).unwrap().len() - 100;
+    fs::OpenOptions::new().write(true).open(journal_path(&path)).unwrap().set_len(cut).unwrap();
+    assert_eq!(read_rows(&path).unwrap(), new);
+    assert!(!journal_path(&path).exists());
+
+    // Pages a shrink frees at the end of the file are cut off
+    write_rows(&path, &rows(50), SyncMode::default()).unwrap();
+    let len = fs::metadata(&path).unwrap().len() as usize;
+    assert_eq!(len, (pack_rows(&rows(50)).len() + 1) * PAGE_SIZE);
+    assert_eq!(read_rows(&path).unwrap(), rows(50));
+
+    fs::remove_dir_all(dir).unwrap();
+}
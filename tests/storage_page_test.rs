@@ -0,0 +1,38 @@
+use database::storage::page::{Page, PAGE_SIZE};
+
+#[test]
+fn test_page_stores_cells_in_slots() {
+    let mut page = Page::new();
+    assert!(page.is_empty());
+    let room = page.room();
+
+    assert_eq!(page.insert(b"first"), Some(0));
+    assert_eq!(page.insert(b""), Some(1));
+    assert_eq!(page.insert(b"third"), Some(2));
+    assert_eq!(page.slot_count(), 3);
+    assert_eq!(page.cell(0), Some(&b"first"[..]));
+    assert_eq!(page.cell(1), Some(&b""[..]));
+    assert_eq!(page.cells().collect::<Vec<_>>(), [&b"first"[..], b"", b"third"]);
+    assert_eq!(page.cell(3), None);
+    // Each cell costs its bytes and a slot
+    assert_eq!(page.room(), room - 10 - 3 * 4);
+
+    let copy = Page::from_bytes(page.as_bytes().to_vec()).unwrap();
+    assert_eq!(copy, page);
+}
+
+#[test]
+fn test_page_rejects_cells_that_do_not_fit() {
+    let mut page = Page::new();
+    let big = vec![7; page.room()];
+    assert_eq!(page.insert(&big), Some(0));
+    assert_eq!(page.room(), 0);
+    assert_eq!(page.insert(b"x"), None);
+    assert_eq!(page.slot_count(), 1);
+
+    assert!(Page::from_bytes(vec![0; PAGE_SIZE - 1]).is_none());
+    // A slot pointing past the end of the page
+    let mut bytes = Page::new().as_bytes().to_vec();
+    bytes[..8].copy_from_slice(&[1, 0, 0, 16, 0xf0, 0x0f, 0x20, 0]);
+    assert!(Page::from_bytes(bytes).is_none());
+}
@@ -0,0 +1,130 @@
+use database::table::data::{AggFunc, AggregationResult, Column, DataType, Table, Value};
+
+fn sales() -> Table {
+    let columns = vec![
+        Column {
+            name: "region".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+        Column {
+            name: "amount".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("sales", columns, None);
+    for (region, amount) in [("east", 10), ("east", 30), ("west", 5)] {
+        table
+            .insert(vec![Value::Varchar(region.to_string()), Value::Int(amount)])
+            .unwrap();
+    }
+    table
+}
+
+#[test]
+fn test_aggregation_result_accessors() {
+    let results = sales()
+        .aggregate_all(&[("amount", AggFunc::Sum), ("amount", AggFunc::Count), ("amount", AggFunc::Max)])
+        .unwrap();
+
+    assert_eq!(results[0].as_f64(), Some(45.0));
+    assert_eq!(results[0].as_count(), None);
+    assert_eq!(results[1].as_count(), Some(3));
+    assert_eq!(results[1].as_value(), Value::BigInt(3));
+    assert_eq!(results[2].as_value(), Value::Int(30));
+    assert_eq!(results[2].as_f64(), Some(30.0));
+}
+
+#[test]
+fn test_aggregation_result_equality_and_display() {
+    let by_region = sales().aggregate("region", "amount", AggFunc::Avg).unwrap();
+    let east = by_region[&Value::Varchar("east".to_string())].clone();
+
+    assert_eq!(east, AggregationResult::Avg(Value::Double(20.0)));
+    assert_eq!(east.to_string(), "20");
+    assert_eq!(AggregationResult::Min(Value::Null).to_string(), "NULL");
+    assert_eq!(AggregationResult::Min(Value::Varchar("a".to_string())).as_f64(), None);
+}
+
+#[test]
+fn test_spread_aggregates_skip_nulls() {
+    let mut table = sales();
+    for amount in [Value::Int(20), Value::Null] {
+        table.insert(vec![Value::Varchar("east".to_string()), amount]).unwrap();
+    }
+    table.insert(vec![Value::Varchar("north".to_string()), Value::Null]).unwrap();
+
+    let funcs = [
+        ("amount", AggFunc::Variance),
+        ("amount", AggFunc::StdDev),
+        ("amount", AggFunc::Median),
+        ("amount", AggFunc::Percentile(0.25)),
+    ];
+    let by_region = table.aggregate_group("region", &funcs, None).unwrap();
+
+    // east holds 10, 30 and 20 once the NULL is left out
+    let east = &by_region[&Value::Varchar("east".to_string())];
+    assert_eq!(east[0], AggregationResult::Variance(Some(100.0)));
+    assert_eq!(east[1].as_f64(), Some(10.0));
+    assert_eq!(east[2], AggregationResult::Median(Some(20.0)));
+    assert_eq!(east[3].as_value(), Value::Double(15.0));
+
+    // One value has no sample spread, and none has no median either
+    let west = &by_region[&Value::Varchar("west".to_string())];
+    assert_eq!(west[1], AggregationResult::StdDev(None));
+    assert_eq!(west[2].as_value(), Value::Double(5.0));
+    let north = &by_region[&Value::Varchar("north".to_string())];
+    assert_eq!(north[2].as_value(), Value::Null);
+    assert_eq!(north[2].to_string(), "NULL");
+
+    assert!(table.aggregate_all(&[("amount", AggFunc::Percentile(1.5))]).is_err());
+}
+
+#[test]
+fn test_min_and_max_skip_nulls() {
+    let mut table = sales();
+    table.insert(vec![Value::Varchar("west".to_string()), Value::Null]).unwrap();
+    table.insert(vec![Value::Varchar("north".to_string()), Value::Null]).unwrap();
+
+    let by_region = table.aggregate_group("region", &[("amount", AggFunc::Min), ("amount", AggFunc::Max)], None).unwrap();
+    let west = &by_region[&Value::Varchar("west".to_string())];
+    assert_eq!(west[0], AggregationResult::Min(Value::Int(5)));
+    assert_eq!(west[1], AggregationResult::Max(Value::Int(5)));
+    let north = &by_region[&Value::Varchar("north".to_string())];
+    assert_eq!(north[1].as_value(), Value::Null);
+}
+
+#[test]
+fn test_agg_funcs_read_back_as_written() {
+    let funcs = [
+        AggFunc::Sum,
+        AggFunc::CountDistinct,
+        AggFunc::Variance,
+        AggFunc::Percentile(0.9),
+        AggFunc::GroupConcat("; ".to_string()),
+    ];
+    for func in funcs {
+        assert_eq!(func.to_string().parse::<AggFunc>(), Ok(func));
+    }
+    assert_eq!("VAR".parse::<AggFunc>(), Ok(AggFunc::Variance));
+    assert_eq!("group_concat".parse::<AggFunc>(), Ok(AggFunc::GroupConcat(",".to_string())));
+    assert!("mode".parse::<AggFunc>().is_err());
+    assert!("percentile(high)".parse::<AggFunc>().is_err());
+
+    assert_eq!(AggFunc::Percentile(0.9).label("amount"), "percentile(amount, 0.9)");
+    assert_eq!(AggFunc::CountDistinct.datatype(&DataType::Varchar(10)), DataType::BigInt);
+}
+
+#[test]
+fn test_avg_of_no_values_is_null() {
+    let mut table = sales();
+    table.insert(vec![Value::Varchar("north".to_string()), Value::Null]).unwrap();
+
+    let by_region = table.aggregate("region", "amount", AggFunc::Avg).unwrap();
+    let north = &by_region[&Value::Varchar("north".to_string())];
+    assert_eq!(north, &AggregationResult::Avg(Value::Null));
+    assert_eq!(north.as_value(), Value::Null);
+    assert_eq!(north.to_string(), "NULL");
+    assert_eq!(by_region[&Value::Varchar("west".to_string())].as_f64(), Some(5.0));
+}
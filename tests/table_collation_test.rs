@@ -0,0 +1,142 @@
+use database::table::data::{Collation, Column, DataType, FilterExpr, OrderBy, Options, Table, Value};
+use std::fs;
+
+fn names(collation: Collation, extra: Vec<Options>) -> Table {
+    let columns = vec![Column {
+        name: "name".to_string(),
+        datatype: DataType::Varchar(40),
+        options: [vec![Options::Collate(collation)], extra].concat(),
+    }];
+    Table::new("names", columns, None)
+}
+
+fn text(s: &str) -> Value {
+    Value::Varchar(s.to_string())
+}
+
+fn sorted(table: &Table) -> Vec<Value> {
+    let rows = table.select_order_by(&[OrderBy::asc("name")]).unwrap();
+    rows.into_iter().map(|row| row[0].clone()).collect()
+}
+
+#[test]
+fn test_collations_compare_values() {
+    assert!(text("apple").collated_cmp(&text("APPLE"), Collation::CaseInsensitive).is_eq());
+    assert!(text("apple").collated_cmp(&text("APPLE"), Collation::Binary).is_gt());
+    assert!(text("Émile").collated_cmp(&text("Emily"), Collation::LatinFold).is_lt());
+    assert!(text("Émile").collated_cmp(&text("Emily"), Collation::Binary).is_gt());
+    // LatinFold only orders by accent and case; different spellings stay distinct
+    assert!(text("Émile").collated_cmp(&text("emile"), Collation::LatinFold).is_ne());
+    assert!(Value::Int(2).collated_cmp(&Value::Int(10), Collation::CaseInsensitive).is_lt());
+}
+
+#[test]
+fn test_unique_uses_the_column_collation() {
+    let mut table = names(Collation::CaseInsensitive, vec![Options::Unique]);
+    table.insert(vec![text("bob")]).unwrap();
+    assert!(table.insert(vec![text("BOB")]).is_err());
+
+    table.create_index("name", false).unwrap();
+    assert!(table.insert(vec![text("Bob")]).is_err());
+    table.insert(vec![text("ann")]).unwrap();
+
+    let mut binary = names(Collation::Binary, vec![Options::Unique]);
+    binary.insert(vec![text("bob")]).unwrap();
+    binary.insert(vec![text("BOB")]).unwrap();
+}
+
+#[test]
+fn test_order_by_uses_the_column_collation() {
+    let mut table = names(Collation::CaseInsensitive, vec![]);
+    for name in ["cherry", "Banana", "apple"] {
+        table.insert(vec![text(name)]).unwrap();
+    }
+    assert_eq!(sorted(&table), [text("apple"), text("Banana"), text("cherry")]);
+
+    let mut table = names(Collation::LatinFold, vec![]);
+    for name in ["Emily", "Zoe", "Émile", "Emil"] {
+        table.insert(vec![text(name)]).unwrap();
+    }
+    assert_eq!(sorted(&table), [text("Emil"), text("Émile"), text("Emily"), text("Zoe")]);
+}
+
+#[test]
+fn test_collated_indexes_agree_with_a_scan() {
+    let mut table = names(Collation::CaseInsensitive, vec![]);
+    for name in ["Ann", "ann", "Bob", "cat"] {
+        table.insert(vec![text(name)]).unwrap();
+    }
+    let filters = [
+        FilterExpr::Eq("name".to_string(), text("ann")),
+        FilterExpr::In("name".to_string(), vec![text("Bob"), text("CAT")]),
+        FilterExpr::Between("name".to_string(), text("B"), text("b")),
+    ];
+    let scanned: Vec<Vec<Vec<Value>>> = filters
+        .iter()
+        .map(|f| table.select_where_expr(f).unwrap().into_iter().cloned().collect())
+        .collect();
+
+    table.create_index("name", true).unwrap();
+    for (filter, expected) in filters.iter().zip(&scanned) {
+        let indexed: Vec<Vec<Value>> = table.select_where_expr(filter).unwrap().into_iter().cloned().collect();
+        assert_eq!(&indexed, expected);
+        assert_eq!(table.count_where(filter).unwrap(), expected.len());
+    }
+
+    // Both spellings of ann compare equal, and so are both updated
+    table.update_where(&filters[0], vec![Some(text("ANN"))]).unwrap();
+    assert_eq!(table.select_where_expr(&FilterExpr::Eq("name".to_string(), text("ANN"))).unwrap().len(), 2);
+}
+
+#[test]
+fn test_filters_compare_under_the_column_collation() {
+    let mut table = names(Collation::CaseInsensitive, vec![]);
+    for name in ["Ann", "ann", "Bob", "cat", "Dan"] {
+        table.insert(vec![text(name)]).unwrap();
+    }
+    let found = |table: &Table, filter: FilterExpr| -> Vec<Value> {
+        table.select_where_expr(&filter).unwrap().into_iter().map(|row| row[0].clone()).collect()
+    };
+    assert_eq!(found(&table, FilterExpr::Eq("name".to_string(), text("ANN"))), [text("Ann"), text("ann")]);
+    assert_eq!(found(&table, FilterExpr::Ne("name".to_string(), text("ann"))).len(), 3);
+    assert_eq!(found(&table, FilterExpr::Lt("name".to_string(), text("B"))), [text("Ann"), text("ann")]);
+    let range = FilterExpr::Between("name".to_string(), text("b"), text("CAT"));
+    assert_eq!(found(&table, range.clone()), [text("Bob"), text("cat")]);
+
+    // A collated B-tree answers ranges in the same order its filters compare
+    table.create_index("name", true).unwrap();
+    assert!(table.explain(&range).to_string().contains("IndexRange"), "{}", table.explain(&range));
+    assert_eq!(found(&table, range), [text("Bob"), text("cat")]);
+    assert_eq!(table.count_where(&FilterExpr::Eq("name".to_string(), text("aNN"))).unwrap(), 2);
+
+    // LatinFold ranges ignore accents, but equality still wants the same spelling
+    let mut table = names(Collation::LatinFold, vec![]);
+    for name in ["Émile", "Zoe", "Emily"] {
+        table.insert(vec![text(name)]).unwrap();
+    }
+    assert_eq!(found(&table, FilterExpr::Lt("name".to_string(), text("F"))), [text("Émile"), text("Emily")]);
+    assert!(found(&table, FilterExpr::Eq("name".to_string(), text("emile"))).is_empty());
+}
+
+#[test]
+fn test_collations_survive_save_and_load() {
+    let mut table = names(Collation::LatinFold, vec![]);
+    table.insert(vec![text("Émile")]).unwrap();
+    table.save_to_file("collationtest").unwrap();
+
+    let loaded = Table::load_from_file("collationtest", "names").unwrap();
+    assert_eq!(loaded.columns[0].collation(), Collation::LatinFold);
+
+    fs::remove_file("db/collationtest.names.pages").unwrap();
+    fs::remove_file("db/collationtest.names.schema").unwrap();
+}
+
+#[test]
+fn test_collations_only_apply_to_strings() {
+    let column = Column {
+        name: "age".to_string(),
+        datatype: DataType::Int,
+        options: vec![Options::Collate(Collation::CaseInsensitive)],
+    };
+    assert!(column.validate().is_err());
+}
@@ -0,0 +1,55 @@
+use database::error::DbError;
+use database::table::data::{Column, DataType, DefaultExpr, Options, Table, Value};
+
+fn column(name: &str, datatype: DataType, options: Vec<Options>) -> Column {
+    Column {
+        name: name.to_string(),
+        datatype,
+        options,
+    }
+}
+
+fn staging() -> Table {
+    let columns = vec![
+        column("name", DataType::Text, vec![]),
+        column("id", DataType::Int, vec![]),
+    ];
+    let mut table = Table::new("staging", columns, None);
+    for (id, name) in [(1, "ada"), (2, "bob")] {
+        table.insert(vec![Value::Text(name.to_string()), Value::Int(id)]).unwrap();
+    }
+    table
+}
+
+#[test]
+fn test_insert_from_matches_columns_by_name() {
+    let columns = vec![
+        column("id", DataType::BigInt, vec![]),
+        column("name", DataType::Varchar(10), vec![]),
+        column("active", DataType::Boolean, vec![Options::Default(DefaultExpr::Literal(Value::Boolean(true)))]),
+    ];
+    let mut people = Table::new("people", columns, Some(vec!["id".to_string()]));
+
+    assert_eq!(people.insert_from(&staging()).unwrap(), 2);
+    assert_eq!(
+        people.rows[1],
+        [Value::BigInt(2), Value::Varchar("bob".to_string()), Value::Boolean(true)]
+    );
+
+    // The second copy clashes on the primary key, so none of it goes in
+    assert!(matches!(people.insert_from(&staging()), Err(DbError::ConstraintViolation(_))));
+    assert_eq!(people.rows.len(), 2);
+}
+
+#[test]
+fn test_insert_from_rejects_incompatible_schemas() {
+    let mut dates = Table::new("dates", vec![column("id", DataType::Date, vec![]), column("name", DataType::Text, vec![])], None);
+    assert!(matches!(dates.insert_from(&staging()), Err(DbError::SchemaError(_))));
+
+    let mut narrow = Table::new("narrow", vec![column("id", DataType::Int, vec![])], None);
+    assert!(narrow.insert_from(&staging()).is_err());
+
+    let mut short = Table::new("short", vec![column("id", DataType::Int, vec![]), column("name", DataType::Varchar(2), vec![])], None);
+    assert!(short.insert_from(&staging()).is_err());
+    assert!(short.rows.is_empty());
+}
@@ -0,0 +1,113 @@
+use database::table::data::{Column, Cursor, DataType, OrderBy, Table, Value};
+
+fn scores() -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "score".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("scores", columns, Some(vec!["id".to_string()]));
+    for (id, score) in [(1, 50), (2, 70), (3, 50), (4, 90), (5, 70)] {
+        table.insert(vec![Value::Int(id), Value::Int(score)]).unwrap();
+    }
+    table
+}
+
+fn ids(page: &[&Vec<Value>]) -> Vec<Value> {
+    page.iter().map(|row| row[0].clone()).collect()
+}
+
+#[test]
+fn test_cursor_pages_by_key() {
+    let table = scores();
+    let mut cursor = table.cursor(&[OrderBy::desc("score")], 2).unwrap();
+
+    // Ties on score are broken by the primary key
+    assert_eq!(ids(&cursor.next_page(&table).unwrap()), [Value::Int(4), Value::Int(2)]);
+    assert_eq!(ids(&cursor.next_page(&table).unwrap()), [Value::Int(5), Value::Int(1)]);
+    assert_eq!(ids(&cursor.next_page(&table).unwrap()), [Value::Int(3)]);
+    assert!(cursor.next_page(&table).unwrap().is_empty());
+}
+
+#[test]
+fn test_cursor_is_stable_across_inserts() {
+    let mut table = scores();
+    let mut cursor = table.cursor(&[OrderBy::asc("score")], 2).unwrap();
+    assert_eq!(ids(&cursor.next_page(&table).unwrap()), [Value::Int(1), Value::Int(3)]);
+
+    // A row sorting before the cursor is not seen; offset paging would repeat id 3
+    table.insert(vec![Value::Int(0), Value::Int(10)]).unwrap();
+    table.insert(vec![Value::Int(6), Value::Int(80)]).unwrap();
+    let mut seen = vec![];
+    loop {
+        let page = cursor.next_page(&table).unwrap();
+        if page.is_empty() {
+            break;
+        }
+        seen.extend(ids(&page));
+    }
+    assert_eq!(seen, [Value::Int(2), Value::Int(5), Value::Int(6), Value::Int(4)]);
+}
+
+fn drain(cursor: &mut Cursor, table: &Table) -> Vec<Value> {
+    let mut seen = vec![];
+    loop {
+        let page = cursor.next_page(table).unwrap();
+        if page.is_empty() {
+            return seen;
+        }
+        seen.extend(ids(&page));
+    }
+}
+
+#[test]
+fn test_cursor_rejects_bad_arguments() {
+    let table = scores();
+    assert!(table.cursor(&[OrderBy::asc("missing")], 2).is_err());
+    assert!(table.cursor(&[OrderBy::asc("score")], 0).is_err());
+}
+
+#[test]
+fn test_cursor_without_a_key_pages_duplicate_rows() {
+    let mut table = scores();
+    table.primary_key = None;
+    for _ in 0..3 {
+        table.insert(vec![Value::Int(7), Value::Int(50)]).unwrap();
+    }
+
+    // Rows equal on every sort key come in table order, each exactly once
+    let mut cursor = table.cursor(&[OrderBy::asc("score")], 2).unwrap();
+    assert_eq!(
+        drain(&mut cursor, &table),
+        [1, 3, 7, 7, 7, 2, 5, 4].map(Value::Int)
+    );
+}
+
+#[test]
+fn test_cursor_seeks_through_an_index() {
+    let mut table = scores();
+    table.insert(vec![Value::Int(6), Value::Null]).unwrap();
+    table.insert(vec![Value::Int(7), Value::Int(70)]).unwrap();
+    let scanned: Vec<Vec<Value>> = [OrderBy::asc("score"), OrderBy::desc("score"), OrderBy::asc("score").nulls_first()]
+        .into_iter()
+        .map(|order| drain(&mut table.cursor(&[order], 2).unwrap(), &table))
+        .collect();
+
+    table.create_index("score", true).unwrap();
+    for (order, expected) in [OrderBy::asc("score"), OrderBy::desc("score"), OrderBy::asc("score").nulls_first()]
+        .into_iter()
+        .zip(&scanned)
+    {
+        assert_eq!(&drain(&mut table.cursor(&[order], 2).unwrap(), &table), expected);
+    }
+    assert_eq!(scanned[0], [1, 3, 2, 5, 7, 4, 6].map(Value::Int));
+    assert_eq!(scanned[1], [6, 4, 2, 5, 7, 1, 3].map(Value::Int));
+    assert_eq!(scanned[2], [6, 1, 3, 2, 5, 7, 4].map(Value::Int));
+}
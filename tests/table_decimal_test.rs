@@ -0,0 +1,90 @@
+use database::table::data::{AggFunc, Column, DataType, Decimal, Expr, Table, Value};
+use std::collections::HashSet;
+
+fn dec(text: &str) -> Decimal {
+    text.parse().unwrap()
+}
+
+fn prices() -> Table {
+    let columns = vec![Column {
+        name: "price".to_string(),
+        datatype: DataType::Decimal(5, 2),
+        options: vec![],
+    }];
+    Table::new("prices", columns, None)
+}
+
+#[test]
+fn test_decimal_arithmetic_is_exact() {
+    assert_eq!(dec("0.1").checked_add(dec("0.2")).unwrap(), dec("0.3"));
+    assert_eq!(dec("1.25").checked_sub(dec("2")).unwrap().to_string(), "-0.75");
+    assert_eq!(dec("-1.5").checked_mul(dec("0.25")).unwrap().to_string(), "-0.375");
+    assert_eq!(dec("2.345").rescale(2).unwrap().to_string(), "2.35");
+    assert_eq!(dec("-2.345").rescale(2).unwrap().to_string(), "-2.35");
+    assert!(dec("170141183460469231731687303715884105727").checked_add(dec("1")).is_none());
+    assert!("1e5".parse::<Decimal>().is_err());
+}
+
+#[test]
+fn test_decimals_compare_by_value_across_scales() {
+    assert_eq!(dec("1.5"), dec("1.50"));
+    assert!(dec("-0.5") < dec("0.25"));
+    assert!(dec("10") > dec("9.999"));
+    assert!(dec("-1.1") < dec("-1.05"));
+
+    let set: HashSet<Value> = [Value::Decimal(dec("1.5")), Value::Decimal(dec("1.500"))].into();
+    assert_eq!(set.len(), 1);
+
+    let mut values = [dec("2"), dec("-3.5"), dec("0.001"), dec("-3.25")];
+    values.sort();
+    let sorted: Vec<String> = values.iter().map(|d| d.to_string()).collect();
+    assert_eq!(sorted, ["-3.5", "-3.25", "0.001", "2"]);
+}
+
+#[test]
+fn test_inserts_are_checked_against_precision() {
+    let mut table = prices();
+    table.insert(vec![Value::Decimal(dec("2.5"))]).unwrap();
+    table.insert(vec![Value::Decimal(dec("999.994"))]).unwrap();
+    assert_eq!(table.rows[0][0].to_display_string(), "2.50");
+    assert_eq!(table.rows[1][0].to_display_string(), "999.99");
+
+    // Rounds up to 1000.00, which needs six digits
+    assert!(table.insert(vec![Value::Decimal(dec("999.995"))]).is_err());
+    assert!(table.insert(vec![Value::Decimal(dec("1234"))]).is_err());
+    assert!(table.insert(vec![Value::Double(1.5)]).is_err());
+
+    let parsed = Value::from_str("12.3", &DataType::Decimal(5, 2)).unwrap();
+    assert_eq!(parsed.to_display_string(), "12.30");
+}
+
+#[test]
+fn test_invalid_decimal_columns_are_rejected() {
+    for datatype in [DataType::Decimal(0, 0), DataType::Decimal(39, 2), DataType::Decimal(4, 5)] {
+        let column = Column {
+            name: "amount".to_string(),
+            datatype,
+            options: vec![],
+        };
+        assert!(column.validate().is_err());
+    }
+}
+
+#[test]
+fn test_decimal_sums_averages_and_quotients_stay_exact() {
+    let mut table = prices();
+    for price in ["0.10", "0.20", "0.20"] {
+        table.insert(vec![Value::Decimal(dec(price))]).unwrap();
+    }
+    table.insert(vec![Value::Null]).unwrap();
+
+    let results = table.aggregate_all(&[("price", AggFunc::Sum), ("price", AggFunc::Avg)]).unwrap();
+    assert_eq!(results[0].as_value(), Value::Decimal(dec("0.50")));
+    assert_eq!(results[1].as_value().to_display_string(), "0.166667");
+    assert_eq!(AggFunc::Avg.datatype(&DataType::Decimal(5, 2)), DataType::Decimal(38, 6));
+
+    let quotient = |text: &str| text.parse::<Expr>().unwrap().compile(&table).unwrap()(&table.rows[0]).unwrap();
+    assert_eq!(quotient("price / 3"), Value::Decimal(dec("0.033333")));
+    assert_eq!(dec("-2").checked_div(dec("3"), 2).unwrap().to_string(), "-0.67");
+    assert!(dec("1").checked_div(dec("0"), 2).is_none());
+}
@@ -0,0 +1,81 @@
+use database::table::data::{Column, DataType, ResultSet, Table, Value};
+
+fn table(name: &str) -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    Table::new(name, columns, None)
+}
+
+#[test]
+fn test_result_set_to_csv() {
+    let mut users = table("users");
+    users.insert(vec![Value::Int(1), Value::Varchar("Smith, Jo".to_string())]).unwrap();
+    users.insert(vec![Value::Int(2), Value::Null]).unwrap();
+
+    let mut out = Vec::new();
+    users.to_result_set().to_csv(&mut out).unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "id,name\n1,\"Smith, Jo\"\n2,\n"
+    );
+}
+
+#[test]
+fn test_result_set_to_json() {
+    let mut users = table("users");
+    users.insert(vec![Value::Int(1), Value::Varchar("say \"hi\"".to_string())]).unwrap();
+    users.insert(vec![Value::Int(2), Value::Null]).unwrap();
+
+    let mut out = Vec::new();
+    users.to_result_set().to_json(&mut out).unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "[{\"id\":1,\"name\":\"say \\\"hi\\\"\"},{\"id\":2,\"name\":null}]\n"
+    );
+}
+
+#[test]
+fn test_json_export_escapes_control_characters() {
+    let mut users = table("users");
+    users.insert(vec![Value::Int(1), Value::Varchar("a\u{8}b\u{c}c\u{1}d\u{1F600}".to_string())]).unwrap();
+
+    let mut out = Vec::new();
+    users.to_result_set().to_json(&mut out).unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "[{\"id\":1,\"name\":\"a\\bb\\fc\\u0001d\u{1F600}\"}]\n"
+    );
+}
+
+#[test]
+fn test_join_results_export() {
+    let mut users = table("users");
+    users.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string())]).unwrap();
+    users.insert(vec![Value::Int(2), Value::Varchar("Bob".to_string())]).unwrap();
+    let mut orders = table("orders");
+    orders.insert(vec![Value::Int(1), Value::Varchar("Book".to_string())]).unwrap();
+
+    let joined = users.left_join(&orders, ("id", "id")).unwrap();
+    let rs = ResultSet::from_join(&users, &orders, &joined);
+
+    let mut out = Vec::new();
+    rs.to_csv(&mut out).unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "users.id,users.name,orders.id,orders.name\n1,Alice,1,Book\n2,Bob,,\n"
+    );
+}
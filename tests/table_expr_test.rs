@@ -0,0 +1,187 @@
+use chrono::NaiveDate;
+use database::database::config::Durability;
+use database::database::validators::Database;
+use database::table::data::{Column, DataType, DefaultExpr, Expr, FilterExpr, Options, Table, Value};
+use std::fs;
+
+fn people(extra: Vec<Column>) -> Table {
+    let column = |name: &str, datatype| Column {
+        name: name.to_string(),
+        datatype,
+        options: vec![],
+    };
+    let columns = vec![
+        column("name", DataType::Varchar(40)),
+        column("score", DataType::Double),
+        column("joined", DataType::Date),
+    ];
+    Table::new("people", [columns, extra].concat(), None)
+}
+
+fn date(y: i32, m: u32, d: u32) -> Value {
+    Value::Date(NaiveDate::from_ymd_opt(y, m, d).unwrap())
+}
+
+fn eval(table: &Table, row: &[Value], text: &str) -> Value {
+    text.parse::<Expr>().unwrap().evaluate(table, row).unwrap()
+}
+
+#[test]
+fn test_scalar_functions() {
+    let table = people(vec![]);
+    let row = [Value::Varchar("Ada Lovelace".to_string()), Value::Double(-2.45), date(2024, 1, 30)];
+    let text = |s: &str| Value::Varchar(s.to_string());
+
+    assert_eq!(eval(&table, &row, "UPPER(name)"), text("ADA LOVELACE"));
+    assert_eq!(eval(&table, &row, "LOWER(SUBSTR(name, 5))"), text("lovelace"));
+    assert_eq!(eval(&table, &row, "SUBSTR(name, 1, 3)"), text("Ada"));
+    assert_eq!(eval(&table, &row, "LENGTH(name)"), Value::Int(12));
+    assert_eq!(eval(&table, &row, "ROUND(score, 1)"), Value::Double(-2.5));
+    assert_eq!(eval(&table, &row, "ABS(ROUND(score))"), Value::Double(2.0));
+    assert_eq!(eval(&table, &row, "ROUND(1250, -2)"), Value::Int(1300));
+    assert_eq!(eval(&table, &row, "DATE_ADD(joined, 2)"), date(2024, 2, 1));
+    assert_eq!(eval(&table, &row, "EXTRACT(MONTH FROM joined)"), Value::Int(1));
+    assert_eq!(eval(&table, &row, "joined >= '2024-01-01' AND LENGTH(name) > 20"), Value::Boolean(false));
+
+    // NULL flows through functions and leaves comparisons unknown
+    let blank = [Value::Null, Value::Null, Value::Null];
+    assert_eq!(eval(&table, &blank, "UPPER(name)"), Value::Null);
+    assert_eq!(eval(&table, &blank, "score > 1 OR TRUE"), Value::Boolean(true));
+    assert_eq!(eval(&table, &blank, "NOT score > 1"), Value::Null);
+
+    assert!("ABS(score, 2)".parse::<Expr>().unwrap().compile(&table).is_err());
+    assert!("UPPER(nickname)".parse::<Expr>().unwrap().compile(&table).is_err());
+    assert!("FROB(name)".parse::<Expr>().is_err());
+}
+
+#[test]
+fn test_expressions_read_back_as_written() {
+    for text in [
+        "UPPER(name)",
+        "SUBSTR(name, 1, 3) = 'Ada'",
+        "EXTRACT(YEAR FROM joined) >= 2020 AND (score < 1.5 OR NOT active)",
+        "ROUND(score, -1) != 10.0",
+        "(score + 1) * 2 - score / 4 > 0",
+        "score - (1 - score) = joined - 3",
+    ] {
+        let expr: Expr = text.parse().unwrap();
+        assert_eq!(expr.to_string(), text);
+        assert_eq!(expr.to_string().parse::<Expr>().unwrap(), expr);
+    }
+}
+
+#[test]
+fn test_select_exprs_projects_expressions() {
+    let mut table = people(vec![]);
+    table.insert(vec![Value::Varchar("ada".to_string()), Value::Double(9.5), date(2019, 5, 1)]).unwrap();
+    table.insert(vec![Value::Varchar("bob".to_string()), Value::Double(4.0), date(2023, 8, 9)]).unwrap();
+
+    let exprs: Vec<Expr> = ["name", "UPPER(name)", "EXTRACT(YEAR FROM joined)"]
+        .iter()
+        .map(|text| text.parse().unwrap())
+        .collect();
+    let filter = FilterExpr::Gt("score".to_string(), Value::Double(5.0));
+    let result = table.select_exprs(&exprs, Some(&filter)).unwrap();
+
+    let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["name", "UPPER(name)", "EXTRACT(YEAR FROM joined)"]);
+    assert_eq!(result.columns[2].datatype, DataType::Int);
+    assert_eq!(result.rows, [vec![Value::Varchar("ada".to_string()), Value::Varchar("ADA".to_string()), Value::Int(2019)]]);
+}
+
+#[test]
+fn test_check_constraints_are_expressions() {
+    let mut table = people(vec![]);
+    table.columns[1].options.push(Options::Check("score >= 0 AND score <= 10".parse().unwrap()));
+    table.columns[0].options.push(Options::Check("LENGTH(name) > 1".parse().unwrap()));
+
+    table.insert(vec![Value::Varchar("ada".to_string()), Value::Double(7.0), Value::Null]).unwrap();
+    assert!(table.insert(vec![Value::Varchar("bob".to_string()), Value::Double(11.0), Value::Null]).is_err());
+    assert!(table.insert(vec![Value::Varchar("c".to_string()), Value::Double(1.0), Value::Null]).is_err());
+    // Unknown is not false
+    table.insert(vec![Value::Varchar("dee".to_string()), Value::Null, Value::Null]).unwrap();
+
+}
+
+#[test]
+fn test_check_constraints_are_resolved_when_defined() {
+    // A bare word is a column, and this table has no column called active
+    let mut table = people(vec![]);
+    table.columns[0].options.push(Options::Check("name = active".parse().unwrap()));
+    assert!(table.validate_schema().unwrap_err().to_string().contains("active"));
+    let mut db = Database::new();
+    assert!(db.create_table(table, Durability::Persistent).is_err());
+
+    let mut table = people(vec![]);
+    assert!(table.add_option("score", Options::Check("score + 1".parse().unwrap())).is_err());
+    assert!(table.add_option("score", Options::Check("bonus > 0".parse().unwrap())).is_err());
+    table.add_option("score", Options::Check("score < 100".parse().unwrap())).unwrap();
+    assert!(table.insert(vec![Value::Varchar("ada".to_string()), Value::Double(150.0), Value::Null]).is_err());
+
+    let column = Column {
+        name: "rank".to_string(),
+        datatype: DataType::Int,
+        options: vec![Options::Check("rank > missing".parse().unwrap())],
+    };
+    assert!(table.alter_add_column(column).is_err());
+    assert_eq!(table.columns.len(), 3);
+}
+
+#[test]
+fn test_generated_columns() {
+    let generated = |name: &str, datatype, text: &str| Column {
+        name: name.to_string(),
+        datatype,
+        options: vec![Options::Generated(text.parse().unwrap())],
+    };
+    let mut table = people(vec![
+        generated("initial", DataType::Varchar(1), "UPPER(SUBSTR(name, 1, 1))"),
+        generated("year", DataType::BigInt, "EXTRACT(YEAR FROM joined)"),
+    ]);
+    table.validate_schema().unwrap();
+
+    let row = vec![Value::Varchar("ada".to_string()), Value::Double(1.0), date(2021, 3, 4), Value::Null, Value::Null];
+    table.insert(row.clone()).unwrap();
+    assert_eq!(table.rows[0][3..], [Value::Varchar("A".to_string()), Value::BigInt(2021)]);
+
+    table.update_where(
+        FilterExpr::Eq("name".to_string(), Value::Varchar("ada".to_string())),
+        vec![Some(Value::Varchar("zoe".to_string())), None, None, None, None],
+    )
+    .unwrap();
+    assert_eq!(table.rows[0][3], Value::Varchar("Z".to_string()));
+
+    let mut set = row.clone();
+    set[4] = Value::BigInt(1999);
+    assert!(table.insert(set).is_err());
+
+    table.save_to_file("exprtest").unwrap();
+    let loaded = Table::load_from_file("exprtest", "people").unwrap();
+    assert_eq!(loaded.columns[3].options, table.columns[3].options);
+    fs::remove_file("db/exprtest.people.pages").unwrap();
+    fs::remove_file("db/exprtest.people.schema").unwrap();
+
+    let mut broken = people(vec![generated("shout", DataType::Text, "UPPER(nickname)")]);
+    assert!(broken.validate_schema().is_err());
+    broken.columns[3] = generated("shout", DataType::Text, "UPPER(name)");
+    broken.columns[3].options.push(Options::Default(DefaultExpr::Literal(Value::Text("x".to_string()))));
+    assert!(broken.validate_schema().is_err());
+}
+
+#[test]
+fn test_arithmetic() {
+    let table = people(vec![]);
+    let row = [Value::Varchar("ada".to_string()), Value::Double(2.5), date(2024, 2, 28)];
+
+    assert_eq!(eval(&table, &row, "1 + 2 * 3"), Value::Int(7));
+    assert_eq!(eval(&table, &row, "7 / 2 - 10"), Value::Int(-7));
+    assert_eq!(eval(&table, &row, "score*2-1"), Value::Double(4.0));
+    assert_eq!(eval(&table, &row, "2147483648 + 1"), Value::BigInt(2147483649));
+    assert_eq!(eval(&table, &row, "joined + 2"), date(2024, 3, 1));
+    assert_eq!(eval(&table, &row, "score + NULL"), Value::Null);
+
+    // Int stays Int, so this overflows rather than widening
+    for text in ["2147483647 + 1", "score / 0", "name + 1", "joined * 2"] {
+        assert!(text.parse::<Expr>().unwrap().evaluate(&table, &row).is_err(), "{}", text);
+    }
+}
@@ -0,0 +1,227 @@
+use database::table::data::{FilterExpr, Value};
+
+#[test]
+fn test_filter_expr_display() {
+    assert_eq!(FilterExpr::Ge("age".to_string(), Value::Int(18)).to_string(), "age >= 18");
+    assert_eq!(FilterExpr::Ne("active".to_string(), Value::Boolean(false)).to_string(), "active != false");
+    assert_eq!(FilterExpr::Eq("note".to_string(), Value::Null).to_string(), "note = NULL");
+}
+
+#[test]
+fn test_filter_expr_display_quotes_text() {
+    let expr = FilterExpr::Eq("name".to_string(), Value::Varchar("O'Brien".to_string()));
+    assert_eq!(expr.to_string(), "name = 'O''Brien'");
+
+    let expr = FilterExpr::Lt("day".to_string(), Value::from_date_str("2024-01-31").unwrap());
+    assert_eq!(format!("{}", expr), "day < '2024-01-31'");
+}
+
+#[test]
+fn test_filter_expr_parses_display_form() {
+    let exprs = vec![
+        FilterExpr::Ge("age".to_string(), Value::Int(18)),
+        FilterExpr::Ne("active".to_string(), Value::Boolean(false)),
+        FilterExpr::Eq("name".to_string(), Value::Varchar("O'Brien".to_string())),
+        FilterExpr::Lt("score".to_string(), Value::Double(2.5)),
+        FilterExpr::Eq("note".to_string(), Value::Null),
+    ];
+    for expr in exprs {
+        assert_eq!(expr.to_string().parse::<FilterExpr>().unwrap(), expr);
+    }
+
+    assert_eq!(
+        "id<>7".parse::<FilterExpr>().unwrap(),
+        FilterExpr::Ne("id".to_string(), Value::Int(7))
+    );
+    assert!("age 18".parse::<FilterExpr>().is_err());
+    assert!("name = Bob".parse::<FilterExpr>().is_err());
+}
+
+#[test]
+fn test_table_parse_filter_types_by_column() {
+    use database::table::data::{Column, DataType, Table};
+
+    let columns = vec![
+        Column {
+            name: "day".to_string(),
+            datatype: DataType::Date,
+            options: vec![],
+        },
+        Column {
+            name: "total".to_string(),
+            datatype: DataType::BigInt,
+            options: vec![],
+        },
+    ];
+    let table = Table::new("sales", columns, None);
+
+    let expr = FilterExpr::Lt("day".to_string(), Value::from_date_str("2024-01-31").unwrap());
+    assert_eq!(table.parse_filter(&expr.to_string()).unwrap(), expr);
+    assert_eq!(
+        table.parse_filter("total > 5").unwrap(),
+        FilterExpr::Gt("total".to_string(), Value::BigInt(5))
+    );
+    assert!(table.parse_filter("missing = 1").is_err());
+    assert!(table.parse_filter("day = 'soon'").is_err());
+}
+
+#[test]
+fn test_filter_expr_is_serde_serializable() {
+    fn assert_serde<T: serde::Serialize + serde::de::DeserializeOwned>() {}
+    assert_serde::<FilterExpr>();
+    assert_serde::<Value>();
+}
+
+#[test]
+fn test_combined_filters_select_rows_and_round_trip() {
+    use database::filter;
+    use database::table::data::{Column, DataType, Table};
+
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "team".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("players", columns, None);
+    for (id, team) in [(1, "red"), (2, "blue"), (3, "red"), (4, "green")] {
+        table.insert(vec![Value::Int(id), Value::Varchar(team.to_string())]).unwrap();
+    }
+    table.create_index("team", false).unwrap();
+
+    let red = Value::Varchar("red".to_string());
+    let green = Value::Varchar("green".to_string());
+    let expr = filter!(((col "team" == red) or (col "team" == green)) and (not (col "id" == Value::Int(3))));
+    assert_eq!(
+        expr,
+        FilterExpr::Eq("team".to_string(), red.clone())
+            .or(FilterExpr::Eq("team".to_string(), green.clone()))
+            .and(FilterExpr::Eq("id".to_string(), Value::Int(3)).not())
+    );
+
+    let ids: Vec<&Value> = table.select_where_expr(&expr).unwrap().into_iter().map(|row| &row[0]).collect();
+    assert_eq!(ids, vec![&Value::Int(1), &Value::Int(4)]);
+
+    assert_eq!(expr.to_string(), "(team = 'red' OR team = 'green') AND NOT id = 3");
+    assert_eq!(expr.to_string().parse::<FilterExpr>().unwrap(), expr);
+    assert_eq!(table.parse_filter("NOT (id > 1 and team = 'red') OR id = 4").unwrap().to_string(), "NOT (id > 1 AND team = 'red') OR id = 4");
+
+    let missing = FilterExpr::Eq("id".to_string(), Value::Int(1)).and(FilterExpr::Eq("age".to_string(), Value::Int(1)));
+    assert!(table.select_columns(&["id"], Some(&missing)).is_err());
+}
+
+#[test]
+fn test_set_filters_round_trip() {
+    use database::table::data::{Column, DataType, Table};
+
+    let set = |items: &[&str]| Value::Set(items.iter().map(|s| s.to_string()).collect());
+    let expr = FilterExpr::Eq("tags".to_string(), set(&["a,b", "it's", "x AND y", "{}"]))
+        .or(FilterExpr::In("tags".to_string(), vec![set(&[]), set(&["c", "d"])]));
+    assert_eq!(
+        expr.to_string(),
+        "tags = {'a,b', 'it''s', 'x AND y', '{}'} OR tags IN ({}, {'c', 'd'})"
+    );
+    assert_eq!(expr.to_string().parse::<FilterExpr>().unwrap(), expr);
+
+    let columns = vec![
+        Column {
+            name: "tags".to_string(),
+            datatype: DataType::Set(vec!["a,b".to_string(), "c".to_string()]),
+            options: vec![],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(20),
+            options: vec![],
+        },
+    ];
+    let table = Table::new("posts", columns, None);
+    let typed = FilterExpr::Ne("tags".to_string(), set(&["a,b", "c"]));
+    assert_eq!(table.parse_filter(&typed.to_string()).unwrap(), typed);
+    assert!(table.parse_filter("name = {'a'}").is_err());
+    assert!("tags = {a}".parse::<FilterExpr>().is_err());
+}
+
+#[test]
+fn test_between_and_in_parse_and_display() {
+    let expr: FilterExpr = "age BETWEEN 18 AND 30 AND name IN ('ann', 'o''neil') OR id in (1,2)".parse().unwrap();
+    assert_eq!(
+        expr,
+        FilterExpr::Between("age".to_string(), Value::Int(18), Value::Int(30))
+            .and(FilterExpr::In(
+                "name".to_string(),
+                vec![Value::Varchar("ann".to_string()), Value::Varchar("o'neil".to_string())]
+            ))
+            .or(FilterExpr::In("id".to_string(), vec![Value::Int(1), Value::Int(2)]))
+    );
+    assert_eq!(
+        expr.to_string(),
+        "age BETWEEN 18 AND 30 AND name IN ('ann', 'o''neil') OR id IN (1, 2)"
+    );
+    assert_eq!(expr.to_string().parse::<FilterExpr>().unwrap(), expr);
+
+    let between = FilterExpr::Between("age".to_string(), Value::Int(18), Value::Int(30));
+    assert!(between.matches(&Value::Int(18)) && between.matches(&Value::Int(30)));
+    assert!(!between.matches(&Value::Int(31)) && !between.matches(&Value::Null));
+    assert!(!FilterExpr::In("id".to_string(), vec![]).matches(&Value::Int(1)));
+    assert!("age BETWEEN 18".parse::<FilterExpr>().is_err());
+}
+
+#[test]
+fn test_comparisons_with_null_are_unknown() {
+    use database::table::data::{Column, DataType, Table};
+
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "note".to_string(),
+            datatype: DataType::Text,
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("notes", columns, None);
+    table.insert(vec![Value::Int(1), Value::Text("hi".to_string())]).unwrap();
+    table.insert(vec![Value::Int(2), Value::Null]).unwrap();
+    fn ids(table: &Table, expr: FilterExpr) -> Vec<Value> {
+        table.select_where_expr(&expr).unwrap().iter().map(|row| row[0].clone()).collect()
+    }
+
+    let note = || "note".to_string();
+    let hi = || Value::Text("hi".to_string());
+    let second = || FilterExpr::Eq("id".to_string(), Value::Int(2));
+    assert!(ids(&table, FilterExpr::Eq(note(), Value::Null)).is_empty());
+    assert!(ids(&table, FilterExpr::Ne(note(), Value::Null)).is_empty());
+    assert_eq!(ids(&table, FilterExpr::Ne(note(), hi())), []);
+    assert_eq!(ids(&table, FilterExpr::Eq(note(), hi()).not()), []);
+    assert_eq!(ids(&table, FilterExpr::In(note(), vec![Value::Null]).not()), []);
+    // Unknown OR true is true, and unknown AND false is false
+    assert_eq!(ids(&table, FilterExpr::Eq(note(), hi()).or(second())), [Value::Int(1), Value::Int(2)]);
+    let first = FilterExpr::Eq("id".to_string(), Value::Int(1));
+    assert_eq!(ids(&table, FilterExpr::Eq(note(), hi()).and(first).not()), [Value::Int(2)]);
+
+    assert_eq!(ids(&table, FilterExpr::IsNull(note())), [Value::Int(2)]);
+    assert_eq!(ids(&table, FilterExpr::IsNotNull(note())), [Value::Int(1)]);
+    table.create_index("note", false).unwrap();
+    assert_eq!(ids(&table, FilterExpr::IsNull(note())), [Value::Int(2)]);
+    assert_eq!(table.count_where(&FilterExpr::IsNull(note())).unwrap(), 1);
+    assert_eq!(table.count_where(&FilterExpr::Eq(note(), Value::Null)).unwrap(), 0);
+}
+
+#[test]
+fn test_null_tests_parse_and_display() {
+    let expr = FilterExpr::IsNull("note".to_string()).or(FilterExpr::IsNotNull("id".to_string()).not());
+    assert_eq!(expr.to_string(), "note IS NULL OR NOT id IS NOT NULL");
+    assert_eq!(expr.to_string().parse::<FilterExpr>().unwrap(), expr);
+    assert_eq!("note is  not null".parse::<FilterExpr>().unwrap(), FilterExpr::IsNotNull("note".to_string()));
+    assert!("note IS EMPTY".parse::<FilterExpr>().is_err());
+}
@@ -1,4 +1,5 @@
-use database::table::data::{Table, Column, DataType, Value};
+use database::table::data::{AggFunc, Table, Column, DataType, DefaultExpr, FilterExpr, Options, Value};
+use database::error::DbError;
 use std::fs;
 
 fn basic_columns() -> Vec<Column> {
@@ -10,7 +11,7 @@ fn basic_columns() -> Vec<Column> {
         },
         Column {
             name: "name".to_string(),
-            datatype: DataType::Varchar,
+            datatype: DataType::Varchar(255),
             options: vec![],
         },
     ]
@@ -39,7 +40,9 @@ fn test_insert_type_mismatch() {
     assert!(result.is_err());
     assert_eq!(
         result.unwrap_err(),
-        "Type mismatch at column id: expected Int, got Varchar(\"Not an Int\")"
+        DbError::TypeMismatch(
+            "Type mismatch at column id: expected Int, got Varchar(\"Not an Int\")".to_string()
+        )
     );
 }
 
@@ -54,11 +57,32 @@ fn test_select_all_and_select_where() {
     let all = table.select_all();
     assert_eq!(all.len(), 2);
 
-    let filtered = table.select_where(|row| row[0] == Value::Int(1));
+    let filtered = table.select_where(|row: &Vec<Value>| row[0] == Value::Int(1)).unwrap();
     assert_eq!(filtered.len(), 1);
     assert_eq!(filtered[0][1], Value::Varchar("Alice".to_string()));
 }
 
+#[test]
+fn test_select_columns_projects_matching_rows() {
+    let mut table = Table::new("people", basic_columns(), None);
+    table.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string())]).unwrap();
+    table.insert(vec![Value::Int(2), Value::Varchar("Bob".to_string())]).unwrap();
+
+    let names = table.select_columns(&["NAME"], None).unwrap();
+    assert_eq!(names.columns, vec![basic_columns()[1].clone()]);
+    assert_eq!(
+        names.rows,
+        vec![vec![Value::Varchar("Alice".to_string())], vec![Value::Varchar("Bob".to_string())]]
+    );
+
+    let filter = FilterExpr::Gt("id".to_string(), Value::Int(1));
+    let picked = table.select_columns(&["name", "people.id"], Some(&filter)).unwrap();
+    assert_eq!(picked.rows, vec![vec![Value::Varchar("Bob".to_string()), Value::Int(2)]]);
+
+    assert!(matches!(table.select_columns(&["age"], None), Err(DbError::NotFound(_))));
+    assert!(matches!(table.select_columns(&[], None), Err(DbError::InvalidArgument(_))));
+}
+
 #[test]
 fn test_update_where() {
     let columns = basic_columns();
@@ -67,11 +91,11 @@ fn test_update_where() {
     table.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string())]).unwrap();
 
     let result = table.update_where(
-        |row| row[0] == Value::Int(1),
+        |row: &Vec<Value>| row[0] == Value::Int(1),
         vec![None, Some(Value::Varchar("Alicia".to_string()))],
     );
 
-    assert!(result.is_ok());
+    assert_eq!(result.unwrap(), 1);
 
     let updated = table.select_all();
     assert_eq!(updated[0][1], Value::Varchar("Alicia".to_string()));
@@ -85,7 +109,8 @@ fn test_delete_where() {
     table.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string())]).unwrap();
     table.insert(vec![Value::Int(2), Value::Varchar("Bob".to_string())]).unwrap();
 
-    table.delete_where(|row| row[0] == Value::Int(1));
+    let deleted = table.delete_where(|row: &Vec<Value>| row[0] == Value::Int(1)).unwrap();
+    assert_eq!(deleted, 1);
 
     let remaining = table.select_all();
     assert_eq!(remaining.len(), 1);
@@ -102,11 +127,395 @@ fn test_save_and_load_file() {
 
     table.save_to_file("testdb").unwrap();
 
-    let loaded = Table::load_from_file("testdb", "people", columns.clone(), None).unwrap();
+    let loaded = Table::load_from_file("testdb", "people").unwrap();
 
+    assert_eq!(loaded.columns, columns);
     assert_eq!(loaded.rows.len(), 2);
     assert_eq!(loaded.rows[0][1], Value::Varchar("Alice".to_string()));
 
     // Clean up
-    fs::remove_file("db/testdb.people.csv").unwrap();
+    fs::remove_file("db/testdb.people.pages").unwrap();
+    fs::remove_file("db/testdb.people.schema").unwrap();
+}
+
+#[test]
+fn test_update_where_stamps_on_update_now_columns() {
+    use database::table::data::{FilterExpr, Options};
+
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "updated_at".to_string(),
+            datatype: DataType::DateTime,
+            options: vec![Options::OnUpdateNow],
+        },
+    ];
+    let mut table = Table::new("audit", columns, Some(vec!["id".to_string()]));
+    let old = Value::from_datetime_str("2000-01-01 00:00:00").unwrap();
+    table.insert(vec![Value::Int(1), old.clone()]).unwrap();
+
+    table
+        .update_where(FilterExpr::Eq("id".to_string(), Value::Int(1)), vec![None, None])
+        .unwrap();
+
+    assert!(table.rows[0][1] > old);
+}
+
+#[test]
+fn test_aggregate_all_without_group_by() {
+    use database::table::data::AggregationResult;
+
+    let columns = vec![Column {
+        name: "amount".to_string(),
+        datatype: DataType::Int,
+        options: vec![],
+    }];
+    let mut table = Table::new("sales", columns, None);
+    for amount in [10, 20, 30, 40] {
+        table.insert(vec![Value::Int(amount)]).unwrap();
+    }
+
+    let results = table.aggregate_all(&[("amount", AggFunc::Sum), ("amount", AggFunc::Avg)]).unwrap();
+    assert!(matches!(results[0], AggregationResult::Sum(Value::Double(s)) if s == 100.0));
+    assert!(matches!(results[1], AggregationResult::Avg(Value::Double(a)) if a == 25.0));
+
+    let large = |row: &Vec<Value>| row[0] > Value::Int(15);
+    let results = table
+        .aggregate_all_where(&[("amount", AggFunc::Count), ("amount", AggFunc::Min)], Some(&large))
+        .unwrap();
+    assert!(matches!(results[0], AggregationResult::Count(3)));
+    assert!(matches!(results[1], AggregationResult::Min(Value::Int(20))));
+
+    assert!(table.aggregate_all(&[("missing", AggFunc::Sum)]).is_err());
+}
+
+#[test]
+fn test_join_filters_are_pushed_into_inputs() {
+    use database::table::data::FilterExpr;
+
+    let mut people = Table::new("people", basic_columns(), Some(vec!["id".to_string()]));
+    people.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string())]).unwrap();
+    people.insert(vec![Value::Int(2), Value::Varchar("Bob".to_string())]).unwrap();
+
+    let mut pets = Table::new("pets", basic_columns(), None);
+    pets.insert(vec![Value::Int(1), Value::Varchar("Rex".to_string())]).unwrap();
+    pets.insert(vec![Value::Int(1), Value::Varchar("Tom".to_string())]).unwrap();
+    pets.insert(vec![Value::Int(2), Value::Varchar("Kit".to_string())]).unwrap();
+
+    let only_alice = FilterExpr::Eq("id".to_string(), Value::Int(1));
+    let not_rex = FilterExpr::Ne("name".to_string(), Value::Varchar("Rex".to_string()));
+
+    let inner = people
+        .inner_join_filtered(&pets, ("id", "id"), Some(&only_alice), Some(&not_rex))
+        .unwrap();
+    assert_eq!(inner.len(), 1);
+    assert_eq!(inner[0].1[1], Some(&Value::Varchar("Tom".to_string())));
+
+    let no_pets = FilterExpr::Eq("name".to_string(), Value::Varchar("None".to_string()));
+    let left = people
+        .left_join_filtered(&pets, ("id", "id"), None, Some(&no_pets))
+        .unwrap();
+    assert_eq!(left.len(), 2);
+    assert!(left.iter().all(|(_, right)| right.iter().all(|v| v.is_none())));
+
+    let missing = FilterExpr::Eq("missing".to_string(), Value::Int(1));
+    assert!(people
+        .right_join_filtered(&pets, ("id", "id"), Some(&missing), None)
+        .is_err());
+}
+
+#[test]
+fn test_for_each_where_stops_early() {
+    use database::table::data::FilterExpr;
+    use std::ops::ControlFlow;
+
+    let mut table = Table::new("people", basic_columns(), None);
+    for i in 1..=10 {
+        table.insert(vec![Value::Int(i), Value::Varchar(format!("p{}", i))]).unwrap();
+    }
+
+    let mut visited = vec![];
+    let result = table.for_each_where(FilterExpr::Gt("id".to_string(), Value::Int(3)), |row| {
+        visited.push(row[0].clone());
+        if visited.len() == 3 {
+            ControlFlow::Break(row[1].clone())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }).unwrap();
+
+    assert_eq!(result, ControlFlow::Break(Value::Varchar("p6".to_string())));
+    assert_eq!(visited, vec![Value::Int(4), Value::Int(5), Value::Int(6)]);
+
+    let mut count = 0;
+    let result: ControlFlow<()> = table.for_each_where(FilterExpr::Le("id".to_string(), Value::Int(4)), |_| {
+        count += 1;
+        ControlFlow::Continue(())
+    }).unwrap();
+    assert_eq!(result, ControlFlow::Continue(()));
+    assert_eq!(count, 4);
+}
+
+#[test]
+fn test_select_where_rejects_unknown_columns() {
+    use database::table::data::FilterExpr;
+    use std::ops::ControlFlow;
+
+    let table = Table::new("people", basic_columns(), None);
+    let missing = FilterExpr::Eq("missing".to_string(), Value::Int(1));
+    assert!(table.select_where(&missing).is_err());
+    assert!(table.for_each_where(&missing, |_| ControlFlow::<()>::Continue(())).is_err());
+}
+
+#[test]
+fn test_clone_is_independent_and_keeps_index_kinds() {
+    use database::table::data::{FilterExpr, IndexType};
+
+    let mut table = Table::new("people", basic_columns(), Some(vec!["id".to_string()]));
+    for i in 1..=5 {
+        table.insert(vec![Value::Int(i), Value::Varchar(format!("p{}", i))]).unwrap();
+    }
+    table.create_index("name", true).unwrap();
+
+    let mut copy = table.clone();
+    assert!(matches!(copy.indexes.get("name"), Some(IndexType::BTree(_))));
+    assert!(matches!(copy.indexes.get("id"), Some(IndexType::Hash(_))));
+
+    copy.insert(vec![Value::Int(6), Value::Varchar("p6".to_string())]).unwrap();
+    assert_eq!(table.rows.len(), 5);
+    assert_eq!(copy.rows.len(), 6);
+
+    let found = copy.select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(6))).unwrap();
+    assert_eq!(found.len(), 1);
+    assert!(table
+        .select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(6)))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_table_equality_ignores_index_state() {
+    let mut a = Table::new("people", basic_columns(), None);
+    a.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string())]).unwrap();
+
+    let mut b = a.clone();
+    b.create_index("name", true).unwrap();
+    assert_eq!(a, b);
+    assert_eq!(a.content_hash(), b.content_hash());
+
+    b.insert(vec![Value::Int(2), Value::Varchar("Bob".to_string())]).unwrap();
+    assert_ne!(a, b);
+    assert_ne!(a.content_hash(), b.content_hash());
+
+    let mut c = a.clone();
+    c.columns[1].name = "full_name".to_string();
+    assert_ne!(a, c);
+}
+
+#[test]
+fn test_errors_report_their_kind() {
+    let mut table = Table::new("people", basic_columns(), Some(vec!["id".to_string()]));
+    table.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string())]).unwrap();
+
+    let duplicate = table.insert(vec![Value::Int(1), Value::Varchar("Again".to_string())]);
+    assert!(matches!(duplicate, Err(DbError::ConstraintViolation(_))));
+    assert!(matches!(table.create_index("age", false), Err(DbError::NotFound(_))));
+    assert!(matches!(table.drop_column("id"), Err(DbError::SchemaError(_))));
+    assert!(matches!(table.commit_transaction(), Err(DbError::TransactionError(_))));
+
+    let missing = Table::load_from_dir(
+        std::path::Path::new("db/does-not-exist"),
+        "nodb",
+        "people",
+        basic_columns(),
+        None,
+    );
+    assert!(matches!(missing, Err(DbError::IoError(_))));
+}
+
+#[test]
+fn test_transaction_logs_only_touched_rows() {
+    let mut table = Table::new("people", basic_columns(), Some(vec!["id".to_string()]));
+    for (id, name) in [(1, "Ann"), (2, "Bob"), (3, "Cat")] {
+        table.insert(vec![Value::Int(id), Value::Varchar(name.to_string())]).unwrap();
+    }
+    let committed = table.rows.clone();
+
+    table.begin_transaction().unwrap();
+    table.insert(vec![Value::Int(4), Value::Varchar("Dan".to_string())]).unwrap();
+    table
+        .update_where(
+            FilterExpr::Eq("id".to_string(), Value::Int(2)),
+            vec![None, Some(Value::Varchar("Rob".to_string()))],
+        )
+        .unwrap();
+    table.delete_where(FilterExpr::Eq("id".to_string(), Value::Int(1))).unwrap();
+
+    // One entry per write, each holding just the rows it changed
+    let log = table.transaction_backup.as_ref().unwrap();
+    assert_eq!(log.len(), 3);
+    assert_eq!(table.committed().rows, committed);
+
+    table.rollback_transaction().unwrap();
+    assert_eq!(table.rows, committed);
+    assert_eq!(table.select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(4))).unwrap().len(), 0);
+    assert!(table.insert(vec![Value::Int(4), Value::Varchar("Dan".to_string())]).is_ok());
+}
+
+#[test]
+fn test_rollback_restores_schema_changes() {
+    let mut table = Table::new("people", basic_columns(), Some(vec!["id".to_string()]));
+    table.insert(vec![Value::Int(1), Value::Varchar("Ann".to_string())]).unwrap();
+    let before = table.clone();
+
+    table.begin_transaction().unwrap();
+    table
+        .alter_add_column(Column {
+            name: "age".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        })
+        .unwrap();
+    table.insert(vec![Value::Int(2), Value::Varchar("Bob".to_string()), Value::Int(40)]).unwrap();
+    table.create_index("age", true).unwrap();
+    table.rename_column("id", "person_id").unwrap();
+    table.drop_column("name").unwrap();
+    assert_eq!(table.columns.len(), 2);
+
+    // The committed view still has the old schema
+    let committed = table.committed();
+    assert_eq!(committed.columns, before.columns);
+    assert_eq!(committed.rows, before.rows);
+
+    table.rollback_transaction().unwrap();
+    assert_eq!(table, before);
+    assert_eq!(table.primary_key, Some(vec!["id".to_string()]));
+    assert!(!table.indexes.contains_key("age"));
+    assert!(table.indexes.contains_key("id"));
+    assert!(table.insert(vec![Value::Int(1), Value::Varchar("Dup".to_string())]).is_err());
+}
+
+#[test]
+fn test_insert_many_is_all_or_nothing() {
+    let mut columns = basic_columns();
+    columns.push(Column {
+        name: "serial".to_string(),
+        datatype: DataType::BigInt,
+        options: vec![Options::Identity { start: 1, increment: 1 }],
+    });
+    columns[1].options.push(Options::Unique);
+    let mut table = Table::new("people", columns, Some(vec!["id".to_string()]));
+    table.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string()), Value::Null]).unwrap();
+
+    let row = |id: i32, name: &str| vec![Value::Int(id), Value::Varchar(name.to_string()), Value::Null];
+    let count = table.insert_many((2..1002).map(|id| row(id, &format!("p{}", id))).collect()).unwrap();
+    assert_eq!(count, 1000);
+    assert_eq!(table.rows[1000][2], Value::BigInt(1001));
+
+    // Indexes cover the new rows
+    let found = table.select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(500))).unwrap();
+    assert_eq!(found[0][1], Value::Varchar("p500".to_string()));
+
+    // Clashing within the batch or with a stored row leaves the table untouched
+    for batch in [vec![row(2000, "x"), row(2000, "y")], vec![row(2001, "z"), row(2002, "Alice")]] {
+        assert!(matches!(table.insert_many(batch), Err(DbError::ConstraintViolation(_))));
+        assert_eq!(table.rows.len(), 1001);
+    }
+    table.insert(row(2003, "q")).unwrap();
+    assert_eq!(table.rows[1001][2], Value::BigInt(1002));
+}
+
+#[test]
+fn test_dml_returning_rows() {
+    let mut columns = basic_columns();
+    columns[0].options.push(Options::Identity { start: 1, increment: 1 });
+    columns[1].options.push(Options::Default(DefaultExpr::Literal(Value::Varchar("anon".to_string()))));
+    let mut table = Table::new("people", columns, Some(vec!["id".to_string()]));
+
+    let inserted = table.insert_returning(vec![Value::Null, Value::Null]).unwrap();
+    assert_eq!(inserted, [Value::Int(1), Value::Varchar("anon".to_string())]);
+    let bob = table.insert_returning(vec![Value::Null, Value::Varchar("Bob".to_string())]).unwrap();
+    assert_eq!(bob[0], Value::Int(2));
+
+    let updated = table
+        .update_where_returning(
+            FilterExpr::Eq("id".to_string(), Value::Int(2)),
+            vec![None, Some(Value::Varchar("Rob".to_string()))],
+        )
+        .unwrap();
+    assert_eq!(updated, [vec![Value::Int(2), Value::Varchar("Rob".to_string())]]);
+
+    let deleted = table.delete_where_returning(FilterExpr::Lt("id".to_string(), Value::Int(2))).unwrap();
+    assert_eq!(deleted, [inserted]);
+    assert_eq!(table.rows.len(), 1);
+}
+
+#[test]
+fn test_add_and_drop_options_check_existing_rows() {
+    let mut table = Table::new("people", basic_columns(), None);
+    table.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string())]).unwrap();
+    table.insert(vec![Value::Int(2), Value::Null]).unwrap();
+    table.insert(vec![Value::Int(3), Value::Varchar("Alice".to_string())]).unwrap();
+
+    assert!(matches!(table.add_option("name", Options::NotNull), Err(DbError::ConstraintViolation(_))));
+    match table.add_option("name", Options::Unique) {
+        Err(DbError::ConstraintViolation(message)) => assert!(message.contains("Alice")),
+        other => panic!("expected a duplicate error, got {:?}", other),
+    }
+    assert!(table.add_option("id", Options::Check("id < 3".parse().unwrap())).is_err());
+    assert!(table.columns[1].options.is_empty());
+
+    table.add_option("id", Options::Unique).unwrap();
+    assert!(table.indexes.contains_key("id"));
+    assert!(table.insert(vec![Value::Int(1), Value::Null]).is_err());
+    assert!(table.add_option("id", Options::Unique).is_err());
+
+    table.add_option("name", Options::Default(DefaultExpr::Literal(Value::Varchar("anon".to_string())))).unwrap();
+    table.insert(vec![Value::Int(4), Value::Null]).unwrap();
+    assert_eq!(table.rows[3][1], Value::Varchar("anon".to_string()));
+
+    let dropped = table.drop_option("name", &Options::Default(DefaultExpr::Literal(Value::Null))).unwrap();
+    assert_eq!(dropped, Options::Default(DefaultExpr::Literal(Value::Varchar("anon".to_string()))));
+    table.drop_option("id", &Options::Unique).unwrap();
+    table.insert(vec![Value::Int(1), Value::Null]).unwrap();
+    assert!(table.drop_option("id", &Options::NotNull).is_err());
+}
+
+#[test]
+fn test_added_options_roll_back_with_the_transaction() {
+    let mut table = Table::new("people", basic_columns(), None);
+    table.begin_transaction().unwrap();
+    table.add_option("id", Options::Unique).unwrap();
+    table.rollback_transaction().unwrap();
+
+    assert!(table.columns[0].options.is_empty());
+    assert!(!table.indexes.contains_key("id"));
+}
+
+#[test]
+fn test_set_primary_key_checks_existing_rows() {
+    let mut table = Table::new("people", basic_columns(), None);
+    table.insert(vec![Value::Int(1), Value::Varchar("Alice".to_string())]).unwrap();
+    table.insert(vec![Value::Int(1), Value::Varchar("Bob".to_string())]).unwrap();
+
+    assert!(matches!(table.set_primary_key(Some(vec!["id".to_string()])), Err(DbError::ConstraintViolation(_))));
+    assert!(table.set_primary_key(Some(vec!["missing".to_string()])).is_err());
+    assert!(table.set_primary_key(Some(vec!["id".to_string(), "ID".to_string()])).is_err());
+    assert_eq!(table.primary_key, None);
+
+    table.set_primary_key(Some(vec!["ID".to_string(), "name".to_string()])).unwrap();
+    assert_eq!(table.primary_key, Some(vec!["id".to_string(), "name".to_string()]));
+    assert!(table.indexes.contains_key("id") && table.indexes.contains_key("name"));
+    assert!(table.insert(vec![Value::Int(1), Value::Varchar("Bob".to_string())]).is_err());
+
+    table.set_primary_key(None).unwrap();
+    table.insert(vec![Value::Int(1), Value::Varchar("Bob".to_string())]).unwrap();
+    assert!(table.set_primary_key(Some(vec!["name".to_string()])).is_err());
+
+    table.insert(vec![Value::Int(2), Value::Null]).unwrap();
+    assert!(table.set_primary_key(Some(vec!["id".to_string(), "name".to_string()])).is_err());
 }
@@ -0,0 +1,174 @@
+use database::table::data::{Column, DataType, GeneratorSpec, Options, Table, Value};
+use std::collections::{HashMap, HashSet};
+
+fn columns() -> Vec<Column> {
+    vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![Options::NotNull, Options::Autoincrement],
+        },
+        Column {
+            name: "email".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![Options::Unique],
+        },
+        Column {
+            name: "role".to_string(),
+            datatype: DataType::Enum(vec!["admin".to_string(), "user".to_string(), "owner".to_string()]),
+            options: vec![],
+        },
+        Column {
+            name: "joined".to_string(),
+            datatype: DataType::Date,
+            options: vec![],
+        },
+    ]
+}
+
+fn spec() -> GeneratorSpec {
+    let mut enum_values = HashMap::new();
+    enum_values.insert(
+        "role".to_string(),
+        vec!["admin".to_string(), "user".to_string()],
+    );
+    GeneratorSpec {
+        enum_values,
+        ..GeneratorSpec::default()
+    }
+}
+
+#[test]
+fn test_generate_rows_respects_constraints() {
+    let mut table = Table::new("people", columns(), Some(vec!["id".to_string()]));
+
+    let inserted = table.generate_rows(50, spec()).unwrap();
+    assert_eq!(inserted, 50);
+    assert_eq!(table.rows.len(), 50);
+
+    let emails: HashSet<&Value> = table.rows.iter().map(|r| &r[1]).collect();
+    assert_eq!(emails.len(), 50);
+
+    let (start, end) = GeneratorSpec::default().date_range;
+    for row in &table.rows {
+        match &row[2] {
+            Value::Enum(val) => assert!(val == "admin" || val == "user"),
+            other => panic!("expected enum, got {:?}", other),
+        }
+        match &row[3] {
+            Value::Date(d) => assert!(*d >= start && *d <= end),
+            other => panic!("expected date, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_generate_rows_is_deterministic_for_a_seed() {
+    let mut first = Table::new("a", columns(), None);
+    let mut second = Table::new("b", columns(), None);
+
+    first.generate_rows(10, spec()).unwrap();
+    second.generate_rows(10, spec()).unwrap();
+
+    assert_eq!(first.rows, second.rows);
+}
+
+#[test]
+fn test_generate_rows_requires_enum_values() {
+    // Without a spec the column's own list is used
+    let mut table = Table::new("people", columns(), None);
+    table.generate_rows(5, GeneratorSpec::default()).unwrap();
+
+    let mut columns = columns();
+    columns[2].datatype = DataType::Enum(vec![]);
+    let mut table = Table::new("people", columns, None);
+    let result = table.generate_rows(1, GeneratorSpec::default());
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_generate_rows_keeps_unique_values_unique_across_calls() {
+    let mut table = Table::new("people", columns(), Some(vec!["id".to_string()]));
+    table.generate_rows(5, spec()).unwrap();
+    table.generate_rows(5, spec()).unwrap();
+    let emails: HashSet<&Value> = table.rows.iter().map(|r| &r[1]).collect();
+    assert_eq!(emails.len(), 10);
+
+    // Unique columns get values of their own type
+    let column = |name: &str, datatype| Column {
+        name: name.to_string(),
+        datatype,
+        options: vec![Options::Unique],
+    };
+    let mut table = Table::new(
+        "codes",
+        vec![
+            column("letter", DataType::Char(1)),
+            column("day", DataType::Date),
+            column("role", DataType::Enum(vec!["admin".to_string(), "user".to_string()])),
+        ],
+        None,
+    );
+    table.generate_rows(2, GeneratorSpec::default()).unwrap();
+    let start = GeneratorSpec::default().date_range.0;
+    assert_eq!(
+        table.rows[1],
+        [
+            Value::Char('b'),
+            Value::Date(start.succ_opt().unwrap()),
+            Value::Enum("user".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_generate_rows_inserts_all_or_nothing() {
+    let mut table = Table::new(
+        "flags",
+        vec![Column {
+            name: "on".to_string(),
+            datatype: DataType::Boolean,
+            options: vec![Options::Unique],
+        }],
+        None,
+    );
+    // A unique Boolean has two values to give
+    assert!(table.generate_rows(3, GeneratorSpec::default()).is_err());
+    assert!(table.rows.is_empty());
+    assert_eq!(table.generate_rows(2, GeneratorSpec::default()).unwrap(), 2);
+
+    // The whole i64 range is a valid spec
+    let mut table = Table::new(
+        "big",
+        vec![Column {
+            name: "n".to_string(),
+            datatype: DataType::BigInt,
+            options: vec![Options::NotNull],
+        }],
+        None,
+    );
+    let spec = GeneratorSpec {
+        int_range: (i64::MIN, i64::MAX),
+        ..GeneratorSpec::default()
+    };
+    assert_eq!(table.generate_rows(20, spec).unwrap(), 20);
+}
+
+#[test]
+fn test_generate_rows_leaves_generated_columns_to_insert() {
+    let mut columns = columns();
+    columns.push(Column {
+        name: "next_id".to_string(),
+        datatype: DataType::Int,
+        options: vec![Options::Generated("id + 1".parse().unwrap())],
+    });
+    let mut table = Table::new("people", columns, Some(vec!["id".to_string()]));
+
+    table.generate_rows(10, spec()).unwrap();
+    for row in &table.rows {
+        match (&row[0], &row[4]) {
+            (Value::Int(id), Value::Int(next)) => assert_eq!(*next, id + 1),
+            other => panic!("expected ints, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use database::table::data::{DataType, Table, Value, MAX_VARCHAR_LENGTH};
+use chrono::NaiveDate;
+use std::fs;
+
+fn write_csv(file_name: &str, contents: &str) -> String {
+    let path = std::env::temp_dir().join(file_name);
+    fs::write(&path, contents).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn test_infer_columns_from_csv() {
+    let path = write_csv(
+        "infer_columns.csv",
+        "id,price,joined,name\n1,9.99,2024-01-01,Alice\n2,10,2024-02-03,Bob\n",
+    );
+
+    let columns = Table::infer_columns_from_csv(&path, 10).unwrap();
+    let types: Vec<DataType> = columns.iter().map(|c| c.datatype.clone()).collect();
+
+    assert_eq!(
+        types,
+        vec![DataType::Int, DataType::Double, DataType::Date, DataType::Varchar(MAX_VARCHAR_LENGTH)]
+    );
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_infer_from_csv_loads_rows() {
+    let path = write_csv(
+        "infer_people.csv",
+        "id,joined,name\n1,2024-01-01,Alice\n2,,Bob\n",
+    );
+
+    let table = Table::infer_from_csv(&path).unwrap();
+
+    assert_eq!(table.name, "infer_people");
+    assert_eq!(table.rows.len(), 2);
+    assert_eq!(
+        table.rows[0][1],
+        Value::Date(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap())
+    );
+    assert_eq!(table.rows[1][1], Value::Null);
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_infer_from_csv_widens_for_late_rows() {
+    let mut contents = "id,score,code\n".to_string();
+    for i in 0..500 {
+        contents.push_str(&format!("{},{},{}\n", i, i, i));
+    }
+    contents.push_str("3000000000,2.5,A7\n");
+    let path = write_csv("infer_late.csv", &contents);
+
+    let table = Table::infer_from_csv(&path).unwrap();
+    let types: Vec<DataType> = table.columns.iter().map(|c| c.datatype.clone()).collect();
+    assert_eq!(types, vec![DataType::BigInt, DataType::Double, DataType::Varchar(MAX_VARCHAR_LENGTH)]);
+    assert_eq!(table.rows.len(), 501);
+    assert_eq!(table.rows[500][0], Value::BigInt(3_000_000_000));
+    assert_eq!(table.rows[0][2], Value::Varchar("0".to_string()));
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_infer_mixed_column_falls_back_to_varchar() {
+    let path = write_csv("infer_mixed.csv", "code\n12\nA7\n");
+
+    let columns = Table::infer_columns_from_csv(&path, 10).unwrap();
+    assert_eq!(columns[0].datatype, DataType::Varchar(MAX_VARCHAR_LENGTH));
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_from_rows_infers_and_widens_types() {
+    let rows = vec![
+        vec![Value::Int(1), Value::Int(5), Value::Null],
+        vec![Value::Int(2), Value::Double(2.5), Value::Null],
+        vec![Value::Int(3), Value::BigInt(7), Value::Null],
+    ];
+
+    let table = Table::from_rows("computed", &["id", "score", "note"], rows, &[]).unwrap();
+
+    let types: Vec<DataType> = table.columns.iter().map(|c| c.datatype.clone()).collect();
+    assert_eq!(types, vec![DataType::Int, DataType::Double, DataType::Varchar(MAX_VARCHAR_LENGTH)]);
+    assert_eq!(table.rows[0][1], Value::Double(5.0));
+    assert_eq!(table.rows[2][1], Value::Double(7.0));
+}
+
+#[test]
+fn test_from_rows_overrides_and_errors() {
+    let rows = vec![vec![Value::Int(1)], vec![Value::Int(2)]];
+    let table = Table::from_rows("t", &["n"], rows, &[("n", DataType::BigInt)]).unwrap();
+    assert_eq!(table.columns[0].datatype, DataType::BigInt);
+    assert_eq!(table.rows[1][0], Value::BigInt(2));
+
+    let mixed = vec![vec![Value::Int(1)], vec![Value::Varchar("x".to_string())]];
+    assert!(Table::from_rows("t", &["n"], mixed, &[]).is_err());
+
+    let short = vec![vec![Value::Int(1)]];
+    assert!(Table::from_rows("t", &["a", "b"], short, &[]).is_err());
+}
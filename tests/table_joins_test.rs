@@ -0,0 +1,118 @@
+use database::table::data::{Column, DataType, JoinStrategy, Table, Value};
+
+fn table(name: &str, key: &str, rows: &[(i32, &str)]) -> Table {
+    let columns = vec![
+        Column {
+            name: key.to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "label".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new(name, columns, None);
+    for (id, label) in rows {
+        table
+            .insert(vec![Value::Int(*id), Value::Varchar(label.to_string())])
+            .unwrap();
+    }
+    table
+}
+
+fn users() -> Table {
+    table("users", "id", &[(3, "cid"), (1, "ann"), (2, "bob"), (4, "dee")])
+}
+
+fn orders() -> Table {
+    table("orders", "user_id", &[(2, "pen"), (3, "ink"), (2, "pad"), (9, "cup")])
+}
+
+fn sorted<T: Ord>(mut rows: Vec<T>) -> Vec<T> {
+    rows.sort();
+    rows
+}
+
+#[test]
+fn test_sort_merge_matches_nested_loop() {
+    let (users, orders) = (users(), orders());
+    let on = ("id", "user_id");
+
+    for strategy in [JoinStrategy::NestedLoop, JoinStrategy::SortMerge] {
+        assert_eq!(
+            sorted(users.inner_join_with(&orders, on, strategy).unwrap()),
+            sorted(users.inner_join(&orders, on).unwrap())
+        );
+        assert_eq!(
+            sorted(users.left_join_with(&orders, on, strategy).unwrap()),
+            sorted(users.left_join(&orders, on).unwrap())
+        );
+        assert_eq!(
+            sorted(users.right_join_with(&orders, on, strategy).unwrap()),
+            sorted(users.right_join(&orders, on).unwrap())
+        );
+    }
+}
+
+#[test]
+fn test_sort_merge_returns_pairs_in_key_order() {
+    let (users, orders) = (users(), orders());
+
+    let rows = users
+        .left_join_with(&orders, ("id", "user_id"), JoinStrategy::SortMerge)
+        .unwrap();
+    let pairs: Vec<(&Value, Option<&Value>)> = rows.iter().map(|(l, r)| (l[1], r[1])).collect();
+
+    let text = |s: &str| Value::Varchar(s.to_string());
+    let (ann, bob, cid, dee) = (text("ann"), text("bob"), text("cid"), text("dee"));
+    let (pen, pad, ink) = (text("pen"), text("pad"), text("ink"));
+    // Equal keys keep their table order
+    assert_eq!(
+        pairs,
+        vec![
+            (&ann, None),
+            (&bob, Some(&pen)),
+            (&bob, Some(&pad)),
+            (&cid, Some(&ink)),
+            (&dee, None),
+        ]
+    );
+
+    assert!(users
+        .inner_join_with(&orders, ("id", "missing"), JoinStrategy::SortMerge)
+        .is_err());
+}
+
+#[test]
+fn test_join_tables_chain_into_another_join() {
+    let (users, orders) = (users(), orders());
+    let products = table("products", "sku", &[(1, "pen"), (2, "pad")]);
+    let text = |s: &str| Value::Varchar(s.to_string());
+
+    let joined = users.left_join_table(&orders, ("id", "user_id")).unwrap();
+    let names: Vec<&str> = joined.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["users.id", "users.label", "orders.user_id", "orders.label"]);
+    assert_eq!(joined.rows.len(), 5);
+    assert_eq!(
+        joined.rows[1],
+        vec![Value::Int(1), text("ann"), Value::Null, Value::Null]
+    );
+    // Unqualified names that both sides share are ambiguous
+    assert!(joined.resolve_column("label").is_err());
+
+    let chained = joined
+        .inner_join_table(&products, ("orders.label", "label"))
+        .unwrap();
+    let names: Vec<&str> = chained.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["users.id", "users.label", "orders.user_id", "orders.label", "products.sku", "products.label"]
+    );
+    let skus: Vec<&Value> = chained.rows.iter().map(|row| &row[4]).collect();
+    assert_eq!(skus, vec![&Value::Int(1), &Value::Int(2)]);
+
+    let right = users.right_join_table(&orders, ("id", "user_id")).unwrap();
+    assert_eq!(right.rows[3], vec![Value::Null, Value::Null, Value::Int(9), text("cup")]);
+}
@@ -0,0 +1,85 @@
+use database::database::session::UNMASKED;
+use database::database::validators::Database;
+use database::table::data::{Column, DataType, FilterExpr, MaskPolicy, Options, Table, Value};
+use std::collections::HashMap;
+
+fn db() -> Database {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "card".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![Options::Mask(MaskPolicy::ShowLast(4))],
+        },
+        Column {
+            name: "ssn".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![Options::Mask(MaskPolicy::Redact)],
+        },
+    ];
+    let mut customers = Table::new("customers", columns, None);
+    customers
+        .insert(vec![
+            Value::Int(1),
+            Value::Varchar("4111111111111234".to_string()),
+            Value::Null,
+        ])
+        .unwrap();
+
+    let mut db = Database::new();
+    db.tables.insert("customers".to_string(), customers);
+    db
+}
+
+#[test]
+fn test_mask_policies() {
+    let value = Value::Varchar("secret-value".to_string());
+    assert_eq!(
+        MaskPolicy::ShowLast(4).apply(&value, b""),
+        Value::Varchar("********alue".to_string())
+    );
+    assert_eq!(MaskPolicy::Redact.apply(&value, b""), Value::Varchar("****".to_string()));
+    assert_eq!(MaskPolicy::Hash.apply(&value, b"key"), MaskPolicy::Hash.apply(&value, b"key"));
+    assert_ne!(MaskPolicy::Hash.apply(&value, b"key"), value);
+    assert_ne!(MaskPolicy::Hash.apply(&value, b"key"), MaskPolicy::Hash.apply(&value, b"other"));
+    assert_eq!(MaskPolicy::Redact.apply(&Value::Null, b""), Value::Null);
+}
+
+#[test]
+fn test_hash_mask_is_hmac_sha256() {
+    // RFC 4231 test cases 2 and 6: a short key, and one longer than a block
+    let value = Value::Varchar("what do ya want for nothing?".to_string());
+    assert_eq!(
+        MaskPolicy::Hash.apply(&value, b"Jefe"),
+        Value::Varchar("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843".to_string())
+    );
+    let value = Value::Varchar("Test Using Larger Than Block-Size Key - Hash Key First".to_string());
+    assert_eq!(
+        MaskPolicy::Hash.apply(&value, &[0xaa; 131]),
+        Value::Varchar("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54".to_string())
+    );
+}
+
+#[test]
+fn test_session_results_are_masked_without_privilege() {
+    let mut db = db();
+    let all = FilterExpr::Gt("id".to_string(), Value::Int(0));
+
+    let mut session = db.session(HashMap::new());
+    let masked = session.select_result("customers", &all).unwrap();
+    assert_eq!(masked.rows[0][1], Value::Varchar("************1234".to_string()));
+    assert_eq!(masked.rows[0][2], Value::Null);
+
+    session.grant(UNMASKED);
+    let clear = session.select_result("customers", &all).unwrap();
+    assert_eq!(clear.rows[0][1], Value::Varchar("4111111111111234".to_string()));
+
+    assert_eq!(
+        db.tables["customers"].rows[0][1],
+        Value::Varchar("4111111111111234".to_string())
+    );
+}
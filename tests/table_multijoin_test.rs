@@ -0,0 +1,84 @@
+use database::table::data::{Column, DataType, Table, Value};
+
+fn int_table(name: &str, cols: &[&str], rows: &[Vec<i32>]) -> Table {
+    let columns = cols
+        .iter()
+        .map(|c| Column {
+            name: c.to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        })
+        .collect();
+    let mut table = Table::new(name, columns, None);
+    for row in rows {
+        table.insert(row.iter().map(|v| Value::Int(*v)).collect()).unwrap();
+    }
+    table
+}
+
+fn setup() -> (Table, Table, Table) {
+    let customers = int_table("customers", &["id", "region"], &[vec![1, 10], vec![2, 20]]);
+    let regions = int_table("regions", &["id"], &[vec![10]]);
+    let orders = int_table(
+        "orders",
+        &["id", "customer_id"],
+        &(1..=20).map(|i| vec![i, i % 3]).collect::<Vec<_>>(),
+    );
+    (customers, regions, orders)
+}
+
+#[test]
+fn test_join_order_starts_from_smallest_connected_table() {
+    let (customers, regions, orders) = setup();
+    let on = [("orders.customer_id", "customers.id"), ("customers.region", "regions.id")];
+
+    let order = Table::join_order(&[&orders, &customers, &regions], &on).unwrap();
+    assert_eq!(order, vec!["regions", "customers", "orders"]);
+}
+
+#[test]
+fn test_join_all_keeps_listed_column_order() {
+    let (customers, regions, orders) = setup();
+    let on = [("orders.customer_id", "customers.id"), ("customers.region", "regions.id")];
+
+    let joined = Table::join_all(&[&orders, &customers, &regions], &on).unwrap();
+    let names: Vec<&str> = joined.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["orders.id", "orders.customer_id", "customers.id", "customers.region", "regions.id"]
+    );
+
+    let mut order_ids: Vec<Value> = joined.rows.iter().map(|r| r[0].clone()).collect();
+    order_ids.sort();
+    let expected: Vec<Value> = (1..=20).filter(|i| i % 3 == 1).map(Value::Int).collect();
+    assert_eq!(order_ids, expected);
+    assert!(joined.rows.iter().all(|r| r[1] == r[2] && r[3] == r[4]));
+}
+
+#[test]
+fn test_join_all_rejects_unqualified_columns() {
+    let (customers, _, orders) = setup();
+    assert!(Table::join_all(&[&orders, &customers], &[("customer_id", "customers.id")]).is_err());
+}
+
+#[test]
+fn test_join_order_uses_statistics() {
+    let mut tags = int_table("tags", &["tag"], &vec![vec![7]; 5]);
+    let mut items = int_table("items", &["id", "tag"], &(1..=50).map(|i| vec![i, 7]).collect::<Vec<_>>());
+    let mut sales = int_table("sales", &["item_id"], &(0..100).map(|i| vec![i % 50 + 1]).collect::<Vec<_>>());
+    let on = [("tags.tag", "items.tag"), ("items.id", "sales.item_id")];
+
+    // Without statistics every join column is taken to be unique, so tags looks cheapest
+    let order = Table::join_order(&[&sales, &items, &tags], &on).unwrap();
+    assert_eq!(order, vec!["tags", "items", "sales"]);
+
+    // Every tag is the same, so tags x items is 250 rows where items x sales is 100
+    tags.analyze();
+    items.analyze();
+    sales.analyze();
+    let order = Table::join_order(&[&sales, &items, &tags], &on).unwrap();
+    assert_eq!(order, vec!["items", "sales", "tags"]);
+
+    let joined = Table::join_all(&[&sales, &items, &tags], &on).unwrap();
+    assert_eq!(joined.rows.len(), 500);
+}
@@ -0,0 +1,83 @@
+use database::table::data::{Column, DataType, FilterExpr, Table, Value};
+use std::collections::HashSet;
+
+fn measurements() -> Table {
+    let column = |name: &str, datatype| Column {
+        name: name.to_string(),
+        datatype,
+        options: vec![],
+    };
+    let columns = vec![
+        column("small", DataType::Int),
+        column("big", DataType::BigInt),
+        column("single", DataType::Float),
+        column("double", DataType::Double),
+    ];
+    Table::new("measurements", columns, None)
+}
+
+#[test]
+fn test_numbers_compare_across_widths() {
+    assert_eq!(Value::Int(1), Value::BigInt(1));
+    assert_eq!(Value::BigInt(1), Value::Double(1.0));
+    assert_eq!(Value::Float(0.5), Value::Double(0.5));
+    assert!(Value::Int(2) < Value::Double(2.5) && Value::Double(2.5) < Value::BigInt(3));
+    assert!(Value::Double(-2.0) < Value::Double(-1.0));
+
+    // Compared exactly rather than through f64, which can't tell these apart
+    let edge = 1i64 << 53;
+    assert_ne!(Value::BigInt(edge + 1), Value::Double(edge as f64));
+    assert!(Value::BigInt(edge + 1) > Value::Double(edge as f64));
+    assert!(Value::Double(f64::NAN).partial_cmp(&Value::Int(0)).is_none());
+
+    let set: HashSet<Value> = [Value::Int(7), Value::BigInt(7), Value::Float(7.0), Value::Double(7.0)].into();
+    assert_eq!(set.len(), 1);
+}
+
+#[test]
+fn test_integers_are_widened_on_insert() {
+    let mut table = measurements();
+    table.insert(vec![Value::BigInt(5), Value::Int(5), Value::Int(5), Value::Float(0.5)]).unwrap();
+    let row = &table.rows[0];
+    assert!(matches!(row[..], [Value::Int(5), Value::BigInt(5), Value::Float(_), Value::Double(_)]));
+    assert_eq!(row[3], Value::Double(0.5));
+
+    table.update_where(
+        FilterExpr::Eq("small".to_string(), Value::Int(5)),
+        vec![None, None, None, Some(Value::BigInt(9))],
+    )
+    .unwrap();
+    assert!(matches!(table.rows[0][3], Value::Double(f) if f == 9.0));
+}
+
+#[test]
+fn test_coercions_that_lose_information_fail() {
+    let mut table = measurements();
+    let null = || Value::Null;
+    assert!(table.insert(vec![Value::BigInt(i64::MAX), null(), null(), null()]).is_err());
+    assert!(table.insert(vec![null(), null(), Value::Int(16_777_217), null()]).is_err());
+    assert!(table.insert(vec![null(), null(), null(), Value::BigInt((1 << 53) + 1)]).is_err());
+    assert!(table.insert(vec![null(), Value::Double(1.0), null(), null()]).is_err());
+    assert!(table.rows.is_empty());
+}
+
+#[test]
+fn test_filters_and_indexes_compare_across_widths() {
+    let mut table = measurements();
+    for i in 0..6 {
+        let row = vec![Value::Int(i), Value::BigInt(i as i64), Value::Null, Value::Double(i as f64 / 2.0)];
+        table.insert(row).unwrap();
+    }
+    let filters = [
+        FilterExpr::Eq("big".to_string(), Value::Int(3)),
+        FilterExpr::Gt("big".to_string(), Value::Double(3.5)),
+        FilterExpr::Between("double".to_string(), Value::Int(1), Value::BigInt(2)),
+    ];
+    let scanned: Vec<usize> = filters.iter().map(|f| table.select_where_expr(f).unwrap().len()).collect();
+    assert_eq!(scanned, [1, 2, 3]);
+
+    table.create_index("big", true).unwrap();
+    table.create_index("double", true).unwrap();
+    let indexed: Vec<usize> = filters.iter().map(|f| table.select_where_expr(f).unwrap().len()).collect();
+    assert_eq!(indexed, scanned);
+}
@@ -0,0 +1,147 @@
+use database::table::data::{Column, DataType, OrderBy, Table, Value};
+use std::sync::Mutex;
+
+fn scores() -> Table {
+    let columns = vec![
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+        Column {
+            name: "score".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("scores", columns, None);
+    let rows = [("a", Some(3)), ("b", None), ("c", Some(1)), ("d", Some(3)), ("e", None)];
+    for (name, score) in rows {
+        table
+            .insert(vec![
+                Value::Varchar(name.to_string()),
+                score.map(Value::Int).unwrap_or(Value::Null),
+            ])
+            .unwrap();
+    }
+    table
+}
+
+fn names(rows: &[&Vec<Value>]) -> Vec<String> {
+    rows.iter().map(|r| r[0].to_display_string()).collect()
+}
+
+#[test]
+fn test_order_by_default_null_placement() {
+    let table = scores();
+
+    let asc = table.select_order_by(&[OrderBy::asc("score")]).unwrap();
+    assert_eq!(names(&asc), vec!["c", "a", "d", "b", "e"]);
+
+    let desc = table.select_order_by(&[OrderBy::desc("score")]).unwrap();
+    assert_eq!(names(&desc), vec!["b", "e", "a", "d", "c"]);
+}
+
+#[test]
+fn test_order_by_explicit_nulls() {
+    let table = scores();
+
+    let rows = table
+        .select_order_by(&[OrderBy::asc("score").nulls_first(), OrderBy::desc("name")])
+        .unwrap();
+    assert_eq!(names(&rows), vec!["e", "b", "c", "d", "a"]);
+
+    let rows = table
+        .select_order_by(&[OrderBy::desc("score").nulls_last()])
+        .unwrap();
+    assert_eq!(names(&rows), vec!["a", "d", "c", "b", "e"]);
+}
+
+#[test]
+fn test_order_by_unknown_column() {
+    assert!(scores().select_order_by(&[OrderBy::asc("missing")]).is_err());
+}
+
+#[test]
+fn test_order_by_computed_key_is_stable() {
+    let mut table = scores();
+    table.rows[2][0] = Value::Varchar("A".to_string());
+
+    let rows = table
+        .select_order_by(&[OrderBy::asc_by(|row| {
+            Value::Varchar(row[0].to_display_string().to_lowercase())
+        })])
+        .unwrap();
+    assert_eq!(names(&rows), vec!["a", "A", "b", "d", "e"]);
+
+    let rows = table
+        .select_order_by(&[OrderBy::desc_by(|row| match row[1] {
+            Value::Int(s) => Value::Int(s * 10),
+            _ => Value::Null,
+        })
+        .nulls_last()])
+        .unwrap();
+    assert_eq!(names(&rows), vec!["a", "d", "A", "b", "e"]);
+}
+
+#[test]
+fn test_sorted_by_key_closure() {
+    let table = scores();
+
+    let rows = table.select_sorted_by_key(|row| match row[1] {
+        Value::Int(s) => (0, -s),
+        _ => (1, 0),
+    });
+    assert_eq!(names(&rows), vec!["a", "d", "c", "b", "e"]);
+}
+
+fn many_scores(count: i32) -> Table {
+    let mut table = Table::new("many", scores().columns, None);
+    for i in 0..count {
+        let score = if i % 11 == 0 { Value::Null } else { Value::Int((i * 7919) % 50) };
+        table.insert(vec![Value::Varchar(format!("row {}", i)), score]).unwrap();
+    }
+    table
+}
+
+// Held by the tests that spill, so none counts another's runs as left behind
+static SPILLING: Mutex<()> = Mutex::new(());
+
+fn spilled_runs() -> usize {
+    let prefix = format!("sort-{}-", std::process::id());
+    std::fs::read_dir(std::env::temp_dir())
+        .unwrap()
+        .filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().starts_with(&prefix))
+        .count()
+}
+
+#[test]
+fn test_order_by_spills_past_budget_and_keeps_ties_stable() {
+    let _spilling = SPILLING.lock().unwrap();
+    let table = many_scores(3000);
+    let order = [OrderBy::desc("score").nulls_last(), OrderBy::asc("name")];
+    let in_memory = table.select_order_by(&order).unwrap();
+
+    let spilled = table.select_order_by_within(&order, 16 * 1024).unwrap();
+    assert_eq!(names(&spilled), names(&in_memory));
+
+    // Ties on the only key come back in table order, as with the in-memory sort
+    let by_score = [OrderBy::asc("score")];
+    let spilled = table.select_order_by_within(&by_score, 4 * 1024).unwrap();
+    assert_eq!(names(&spilled), names(&table.select_order_by(&by_score).unwrap()));
+    assert_eq!(names(&spilled[..2]), vec!["row 50", "row 100"]);
+    assert_eq!(spilled_runs(), 0);
+}
+
+#[test]
+fn test_order_by_merges_many_runs_in_passes() {
+    let _spilling = SPILLING.lock().unwrap();
+    let table = many_scores(5000);
+    let order = [OrderBy::asc_by(|row| Value::Varchar(row[0].to_display_string().chars().rev().collect()))];
+
+    // A run per row, far more than one merge pass reads at once
+    let spilled = table.select_order_by_within(&order, 0).unwrap();
+    assert_eq!(names(&spilled), names(&table.select_order_by(&order).unwrap()));
+    assert_eq!(spilled.len(), 5000);
+    assert_eq!(spilled_runs(), 0);
+}
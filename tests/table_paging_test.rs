@@ -0,0 +1,114 @@
+use database::table::data::{Column, DataType, FilterExpr, Limit, OrderBy, Table, Value};
+
+fn numbers() -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "parity".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("numbers", columns, Some(vec!["id".to_string()]));
+    for i in 1..=11 {
+        let parity = if i % 2 == 0 { "even" } else { "odd" };
+        table
+            .insert(vec![Value::Int(i), Value::Varchar(parity.to_string())])
+            .unwrap();
+    }
+    table
+}
+
+#[test]
+fn test_select_page_carries_totals() {
+    let table = numbers();
+
+    let page = table.select_page(None, 2, 4).unwrap();
+    assert_eq!(page.total, 11);
+    assert_eq!(page.page_count(), 3);
+    assert_eq!(page.rows.len(), 3);
+    assert_eq!(page.rows[0][0], Value::Int(9));
+    assert!(!page.has_next());
+    assert!(page.has_previous());
+
+    let ids = page.map(|row| row[0].clone());
+    assert_eq!(ids.rows, vec![Value::Int(9), Value::Int(10), Value::Int(11)]);
+}
+
+#[test]
+fn test_select_page_with_filter_uses_index_count() {
+    let mut table = numbers();
+    table.create_index("parity", false).unwrap();
+
+    let odd = FilterExpr::Eq("parity".to_string(), Value::Varchar("odd".to_string()));
+    let page = table.select_page(Some(&odd), 0, 4).unwrap();
+    assert_eq!(page.total, 6);
+    assert_eq!(page.rows.len(), 4);
+    assert!(page.has_next());
+
+    let past_end = table.select_page(Some(&odd), 5, 4).unwrap();
+    assert!(past_end.rows.is_empty());
+    assert_eq!(past_end.total, 6);
+}
+
+#[test]
+fn test_select_page_rejects_bad_input() {
+    let table = numbers();
+    assert!(table.select_page(None, 0, 0).is_err());
+    let missing = FilterExpr::Eq("missing".to_string(), Value::Int(1));
+    assert!(table.select_page(Some(&missing), 0, 5).is_err());
+}
+
+#[test]
+fn test_limit_and_offset_report_the_total() {
+    let table = numbers();
+    let odd = FilterExpr::Eq("parity".to_string(), Value::Varchar("odd".to_string()));
+
+    let window = table.select_where_expr_limited(&odd, Limit::new(2).offset(1)).unwrap();
+    let ids: Vec<&Value> = window.rows.iter().map(|row| &row[0]).collect();
+    assert_eq!(ids, vec![&Value::Int(3), &Value::Int(5)]);
+    assert_eq!(window.total, 6);
+    assert!(window.has_more());
+
+    // Ties on parity keep table order, exactly as in the full sort
+    let order = [OrderBy::desc("parity")];
+    let full = table.select_order_by(&order).unwrap();
+    let window = table.select_order_by_limited(&order, Limit::new(4).offset(3)).unwrap();
+    assert_eq!(window.rows, full[3..7].to_vec());
+    assert_eq!(window.total, 11);
+
+    let rest = table.select_order_by_limited(&order, Limit::default().offset(9)).unwrap();
+    assert_eq!(rest.rows, full[9..].to_vec());
+    assert!(!rest.has_more());
+}
+
+#[test]
+fn test_join_limited_builds_only_the_window() {
+    let left = numbers();
+    let right = numbers();
+
+    let full = left.left_join(&right, ("parity", "parity")).unwrap();
+    let window = left
+        .left_join_limited(&right, ("parity", "parity"), None, None, Limit::new(5).offset(60))
+        .unwrap();
+    assert_eq!(window.total, full.len());
+    assert_eq!(window.rows, full[60..].to_vec());
+
+    let even = FilterExpr::Eq("parity".to_string(), Value::Varchar("even".to_string()));
+    let inner = left
+        .inner_join_limited(&right, ("id", "id"), Some(&even), None, Limit::new(2))
+        .unwrap();
+    assert_eq!(inner.total, 5);
+    assert_eq!(inner.rows.len(), 2);
+    assert_eq!(*inner.rows[1].0[0], Value::Int(4));
+
+    let right_window = left
+        .right_join_limited(&right, ("id", "id"), Some(&even), None, Limit::new(1).offset(10))
+        .unwrap();
+    assert_eq!(right_window.total, 11);
+    assert_eq!(right_window.rows[0].0, vec![None, None]);
+}
@@ -0,0 +1,236 @@
+use chrono::{NaiveDate, NaiveTime};
+use database::error::DbError;
+use database::table::data::{Column, DataType, Decimal, Options, Table, Value};
+use std::fs;
+
+fn path(name: &str) -> String {
+    let dir = std::env::temp_dir().join("table_parquet_test");
+    fs::create_dir_all(&dir).unwrap();
+    dir.join(name).to_string_lossy().to_string()
+}
+
+fn column(name: &str, datatype: DataType, options: Vec<Options>) -> Column {
+    Column {
+        name: name.to_string(),
+        datatype,
+        options,
+    }
+}
+
+fn everything() -> Table {
+    let columns = vec![
+        column("id", DataType::Int, vec![]),
+        column("code", DataType::Char(1), vec![]),
+        column("name", DataType::Varchar(20), vec![Options::NotNull]),
+        column("notes", DataType::Text, vec![]),
+        column("size", DataType::Enum(vec!["S".to_string(), "M".to_string(), "L".to_string()]), vec![]),
+        column("tags", DataType::Set(vec!["new".to_string(), "sale".to_string()]), vec![]),
+        column("active", DataType::Boolean, vec![]),
+        column("views", DataType::BigInt, vec![]),
+        column("ratio", DataType::Float, vec![]),
+        column("score", DataType::Double, vec![]),
+        column("born", DataType::Date, vec![]),
+        column("opens", DataType::Time, vec![]),
+        column("seen", DataType::DateTime, vec![]),
+        column("location", DataType::Point, vec![]),
+        column("price", DataType::Decimal(10, 2), vec![]),
+    ];
+    let mut table = Table::new("everything", columns, Some(vec!["id".to_string()]));
+    let day = NaiveDate::from_ymd_opt(1969, 7, 20).unwrap();
+    let time = NaiveTime::from_hms_micro_opt(20, 17, 40, 250).unwrap();
+    table
+        .insert(vec![
+            Value::Int(1),
+            Value::Char('a'),
+            Value::Varchar("Widget".to_string()),
+            Value::Text("first".to_string()),
+            Value::Enum("M".to_string()),
+            Value::Set(vec!["new".to_string(), "sale".to_string()]),
+            Value::Boolean(true),
+            Value::BigInt(9_000_000_000),
+            Value::Float(0.5),
+            Value::Double(-2.25),
+            Value::Date(day),
+            Value::Time(time),
+            Value::DateTime(day.and_time(time)),
+            Value::Point(51.5, -0.12),
+            Value::Decimal(Decimal::new(-1999, 2)),
+        ])
+        .unwrap();
+    table
+        .insert(vec![
+            Value::Int(2),
+            Value::Null,
+            Value::Varchar("Gadget".to_string()),
+            Value::Null,
+            Value::Null,
+            Value::Set(vec![]),
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+        ])
+        .unwrap();
+    table
+        .insert(vec![
+            Value::Int(3),
+            Value::Char('c'),
+            Value::Varchar("Gizmo".to_string()),
+            Value::Text("".to_string()),
+            Value::Enum("L".to_string()),
+            Value::Null,
+            Value::Boolean(false),
+            Value::BigInt(-1),
+            Value::Float(1.0),
+            Value::Double(0.0),
+            Value::Date(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()),
+            Value::Time(NaiveTime::from_hms_opt(0, 0, 0).unwrap()),
+            Value::DateTime(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap().and_hms_opt(23, 59, 59).unwrap()),
+            Value::Point(-33.9, 151.2),
+            Value::Decimal(Decimal::new(5, 0)),
+        ])
+        .unwrap();
+    table
+}
+
+#[test]
+fn test_parquet_round_trip_keeps_rows_and_schema() {
+    let table = everything();
+    let file = path("everything.parquet");
+    // One row per group, so values are stitched back together across groups
+    table.save_to_parquet_chunked(&file, 1).unwrap();
+
+    let loaded = Table::load_from_parquet(&file).unwrap();
+    assert_eq!(loaded.name, "everything");
+    assert_eq!(loaded.columns, table.columns);
+    assert_eq!(loaded.primary_key, table.primary_key);
+    assert_eq!(loaded.rows, table.rows);
+    assert!(loaded.indexes.contains_key("id"));
+
+    // Every column chunk is compressed
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    let reader = SerializedFileReader::new(fs::File::open(&file).unwrap()).unwrap();
+    let group = reader.metadata().row_group(0);
+    assert!(group.columns().iter().all(|c| matches!(c.compression(), parquet::basic::Compression::ZSTD(_))));
+
+    // The key still holds after loading
+    let mut loaded = loaded;
+    let duplicate = loaded.insert(table.rows[0].clone());
+    assert!(duplicate.is_err());
+}
+
+#[test]
+fn test_parquet_reads_files_without_an_embedded_schema() {
+    use parquet::data_type::{ByteArray, ByteArrayType, Int32Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::sync::Arc;
+
+    let file = path("foreign.parquet");
+    let schema = parse_message_type(
+        "message foreign { required int32 id; optional binary city (STRING); optional int32 founded (DATE); }",
+    )
+    .unwrap();
+    let mut writer = SerializedFileWriter::new(
+        fs::File::create(&file).unwrap(),
+        Arc::new(schema),
+        Arc::new(WriterProperties::builder().build()),
+    )
+    .unwrap();
+    let mut group = writer.next_row_group().unwrap();
+    let mut ids = group.next_column().unwrap().unwrap();
+    ids.typed::<Int32Type>().write_batch(&[1, 2], None, None).unwrap();
+    ids.close().unwrap();
+    let mut cities = group.next_column().unwrap().unwrap();
+    cities
+        .typed::<ByteArrayType>()
+        .write_batch(&[ByteArray::from("Rome")], Some(&[1, 0]), None)
+        .unwrap();
+    cities.close().unwrap();
+    let mut founded = group.next_column().unwrap().unwrap();
+    founded.typed::<Int32Type>().write_batch(&[-1], Some(&[0, 1]), None).unwrap();
+    founded.close().unwrap();
+    group.close().unwrap();
+    writer.close().unwrap();
+
+    let loaded = Table::load_from_parquet(&file).unwrap();
+    let types: Vec<&DataType> = loaded.columns.iter().map(|c| &c.datatype).collect();
+    assert_eq!(types, [&DataType::Int, &DataType::Text, &DataType::Date]);
+    assert_eq!(loaded.primary_key, None);
+    assert_eq!(
+        loaded.rows,
+        [
+            vec![Value::Int(1), Value::Text("Rome".to_string()), Value::Null],
+            vec![Value::Int(2), Value::Null, Value::Date(NaiveDate::from_ymd_opt(1969, 12, 31).unwrap())],
+        ]
+    );
+}
+
+#[test]
+fn test_parquet_reads_every_time_unit() {
+    use chrono::DateTime;
+    use parquet::basic::Compression;
+    use parquet::data_type::{Int32Type, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::sync::Arc;
+
+    let file = path("time_units.parquet");
+    let schema = parse_message_type(
+        "message units {
+            required int32 opens (TIME(MILLIS, false));
+            required int64 closes (TIME(NANOS, false));
+            required int64 created (TIMESTAMP(MILLIS, false));
+            required int64 updated (TIMESTAMP(NANOS, true));
+        }",
+    )
+    .unwrap();
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer =
+        SerializedFileWriter::new(fs::File::create(&file).unwrap(), Arc::new(schema), Arc::new(properties)).unwrap();
+    let mut group = writer.next_row_group().unwrap();
+    let mut opens = group.next_column().unwrap().unwrap();
+    opens.typed::<Int32Type>().write_batch(&[9 * 3_600_000 + 250], None, None).unwrap();
+    opens.close().unwrap();
+    for ticks in [17 * 3_600_000_000_000 + 5, 1_000_000_000_123, 1_000_000_000_123_456_789] {
+        let mut leaf = group.next_column().unwrap().unwrap();
+        leaf.typed::<Int64Type>().write_batch(&[ticks], None, None).unwrap();
+        leaf.close().unwrap();
+    }
+    group.close().unwrap();
+    writer.close().unwrap();
+
+    let loaded = Table::load_from_parquet(&file).unwrap();
+    let types: Vec<&DataType> = loaded.columns.iter().map(|c| &c.datatype).collect();
+    assert_eq!(types, [&DataType::Time, &DataType::Time, &DataType::DateTime, &DataType::DateTime]);
+    let at = |secs, nanos| Value::DateTime(DateTime::from_timestamp(secs, nanos).unwrap().naive_utc());
+    assert_eq!(
+        loaded.rows,
+        [vec![
+            Value::Time(NaiveTime::from_hms_milli_opt(9, 0, 0, 250).unwrap()),
+            Value::Time(NaiveTime::from_hms_nano_opt(17, 0, 0, 5).unwrap()),
+            at(1_000_000_000, 123_000_000),
+            at(1_000_000_000, 123_456_789),
+        ]]
+    );
+}
+
+#[test]
+fn test_parquet_rejects_bad_input() {
+    let table = everything();
+    let err = table.save_to_parquet_chunked(&path("empty_groups.parquet"), 0).unwrap_err();
+    assert!(matches!(err, DbError::InvalidArgument(_)));
+
+    let file = path("not_parquet.parquet");
+    fs::write(&file, "id,name\n1,Ada\n").unwrap();
+    assert!(matches!(Table::load_from_parquet(&file), Err(DbError::IoError(_))));
+
+    assert!(matches!(Table::load_from_parquet(&path("missing.parquet")), Err(DbError::IoError(_))));
+}
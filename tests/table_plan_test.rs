@@ -0,0 +1,155 @@
+use database::table::data::{AccessPath, Column, DataType, FilterExpr, Plan, PlanNode, Table, Value};
+use std::ops::Bound;
+
+fn users(indexed: bool) -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "age".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+    ];
+    let pk = if indexed { Some(vec!["id".to_string()]) } else { None };
+    let mut table = Table::new("users", columns, pk);
+    for i in 1..=30 {
+        table.insert(vec![Value::Int(i), Value::Int(i % 5 + 15)]).unwrap();
+    }
+    table
+}
+
+#[test]
+fn test_plan_uses_index_scan_for_indexed_eq() {
+    let table = users(true);
+    let plan = table.plan(Some(&FilterExpr::Eq("id".to_string(), Value::Int(7))));
+
+    match &plan.root {
+        PlanNode::Filter { input, rows, .. } => {
+            assert_eq!(*rows, 1);
+            assert!(matches!(**input, PlanNode::IndexScan { .. }));
+        }
+        other => panic!("unexpected plan {:?}", other),
+    }
+}
+
+#[test]
+fn test_plan_display_tree() {
+    let table = users(false);
+    let plan = table
+        .plan(Some(&FilterExpr::Ge("age".to_string(), Value::Int(18))))
+        .sort(&["age"])
+        .limit(5);
+
+    assert_eq!(
+        plan.to_string(),
+        "Limit 5 (rows=5)\n  Sort age (rows=10)\n    Filter age >= 18 (rows=10)\n      SeqScan users (rows=30)\n"
+    );
+}
+
+#[test]
+fn test_plan_dot_output() {
+    let left = users(false).plan(None);
+    let right = users(true).plan(None);
+    let plan = Plan::hash_join(left, right, ("id", "id"));
+
+    let dot = plan.to_dot();
+    assert!(dot.starts_with("digraph plan {"));
+    assert!(dot.contains("n0 [label=\"HashJoin id = id\\nrows=30\"];"));
+    assert!(dot.contains("n0 -> n1;"));
+    assert!(dot.contains("n0 -> n2;"));
+}
+
+#[test]
+fn test_explain_picks_lookup_range_or_full_scan() {
+    let mut table = users(true);
+    table.create_index("age", true).unwrap();
+    let id_is_7 = FilterExpr::Eq("id".to_string(), Value::Int(7));
+    let age_over_18 = FilterExpr::Gt("age".to_string(), Value::Int(18));
+
+    let lookup = table.explain(&id_is_7);
+    assert_eq!(
+        lookup.access,
+        AccessPath::IndexLookup { column: "id".to_string(), value: Value::Int(7) }
+    );
+    assert_eq!(lookup.estimated_rows, 1);
+
+    // B-tree indexes answer equality too, and a strict bound excludes its endpoint
+    assert_eq!(table.explain(&FilterExpr::Eq("age".to_string(), Value::Int(16))).estimated_rows, 6);
+    let range = table.explain(&age_over_18);
+    assert_eq!(
+        range.access,
+        AccessPath::IndexRange {
+            column: "age".to_string(),
+            lower: Bound::Excluded(Value::Int(18)),
+            upper: Bound::Unbounded,
+        }
+    );
+    assert_eq!(range.estimated_rows, 6);
+
+    let either = table.explain(&id_is_7.clone().or(age_over_18.clone()));
+    assert!(matches!(either.access, AccessPath::Union(..)));
+    assert_eq!(either.estimated_rows, 7);
+    assert_eq!(
+        either.to_string(),
+        "Filter id = 7 OR age > 18 on users (rows=7)\n  Union\n    IndexLookup id = 7\n    IndexRange age > 18\n"
+    );
+
+    let negated = table.explain(&age_over_18.clone().not());
+    assert_eq!(negated.access, AccessPath::FullScan);
+    let rows = table.select_where_expr(&age_over_18.not()).unwrap();
+    assert_eq!(rows.len(), 24);
+}
+
+#[test]
+fn test_explain_intersects_indexed_sides_of_and() {
+    let mut table = users(true);
+    table.create_index("age", true).unwrap();
+    let expr = FilterExpr::Lt("id".to_string(), Value::Int(10))
+        .and(FilterExpr::Eq("age".to_string(), Value::Int(15)))
+        .and(FilterExpr::Ne("id".to_string(), Value::Int(20)));
+
+    // Only the age index is usable: id has a hash index, which can't answer `<`
+    let plan = table.explain(&expr);
+    assert_eq!(plan.access.columns(), vec!["age"]);
+    let ids: Vec<&Value> = table.select_where_expr(&expr).unwrap().iter().map(|r| &r[0]).collect();
+    assert_eq!(ids, vec![&Value::Int(5)]);
+
+    let expr = FilterExpr::Eq("id".to_string(), Value::Int(10)).and(FilterExpr::Eq("age".to_string(), Value::Int(15)));
+    let plan = table.explain(&expr);
+    assert!(matches!(plan.access, AccessPath::Intersect(..)));
+    assert_eq!(plan.estimated_rows, 1);
+    assert_eq!(table.select_where_expr(&expr).unwrap().len(), 1);
+}
+
+#[test]
+fn test_explain_uses_indexes_for_inclusive_ranges_and_in_lists() {
+    let mut table = users(true);
+    table.create_index("age", true).unwrap();
+
+    let between = FilterExpr::Between("age".to_string(), Value::Int(16), Value::Int(17));
+    let plan = table.explain(&between);
+    assert_eq!(plan.access.label(), "IndexRange age >= 16 AND age <= 17");
+    assert_eq!(plan.estimated_rows, 12);
+    assert_eq!(table.select_where_expr(&between).unwrap().len(), 12);
+
+    let at_most = FilterExpr::Le("age".to_string(), Value::Int(15));
+    assert_eq!(table.explain(&at_most).estimated_rows, 6);
+    let at_least = FilterExpr::Ge("age".to_string(), Value::Int(19));
+    assert_eq!(table.explain(&at_least).estimated_rows, 6);
+
+    // A reversed range reads nothing rather than panicking
+    let empty = FilterExpr::Between("age".to_string(), Value::Int(19), Value::Int(15));
+    assert_eq!(table.explain(&empty).estimated_rows, 0);
+    assert!(table.select_where_expr(&empty).unwrap().is_empty());
+
+    let listed = FilterExpr::In("id".to_string(), vec![Value::Int(9), Value::Int(3), Value::Int(9), Value::Int(99)]);
+    let plan = table.explain(&listed);
+    assert!(matches!(plan.access, AccessPath::IndexMultiLookup { .. }));
+    assert_eq!(plan.estimated_rows, 2);
+    let ids: Vec<&Value> = table.select_where_expr(&listed).unwrap().iter().map(|r| &r[0]).collect();
+    assert_eq!(ids, vec![&Value::Int(3), &Value::Int(9)]);
+}
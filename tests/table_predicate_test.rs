@@ -0,0 +1,60 @@
+use database::table::data::{Column, DataType, FilterExpr, Table, Value};
+
+fn people() -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("people", columns, Some(vec!["id".to_string()]));
+    for (id, name) in [(1, "Ann"), (2, "Bob"), (3, "Cat")] {
+        table
+            .insert(vec![Value::Int(id), Value::Varchar(name.to_string())])
+            .unwrap();
+    }
+    table
+}
+
+#[test]
+fn test_closures_and_filters_select_the_same_rows() {
+    let table = people();
+    let expr = FilterExpr::Gt("id".to_string(), Value::Int(1));
+
+    let by_expr = table.select_where(&expr).unwrap();
+    let by_closure = table.select_where(|row: &Vec<Value>| row[0] > Value::Int(1)).unwrap();
+    assert_eq!(by_expr, by_closure);
+    assert_eq!(table.select_where(expr.clone()).unwrap(), by_expr);
+    assert_eq!(table.select_where_expr(&expr).unwrap(), by_expr);
+}
+
+#[test]
+fn test_update_and_delete_without_an_index() {
+    let mut table = people();
+
+    // "name" has no index, so these scan every row
+    let updated = table
+        .update_where(
+            FilterExpr::Eq("name".to_string(), Value::Varchar("Bob".to_string())),
+            vec![None, Some(Value::Varchar("Rob".to_string()))],
+        )
+        .unwrap();
+    assert_eq!(updated, 1);
+    assert_eq!(table.rows[1][1], Value::Varchar("Rob".to_string()));
+
+    let deleted = table
+        .delete_where(FilterExpr::Ne("name".to_string(), Value::Varchar("Rob".to_string())))
+        .unwrap();
+    assert_eq!(deleted, 2);
+    assert_eq!(table.rows.len(), 1);
+
+    let missing = FilterExpr::Eq("age".to_string(), Value::Int(1));
+    assert!(table.update_where(&missing, vec![None, None]).is_err());
+    assert!(table.delete_where(&missing).is_err());
+}
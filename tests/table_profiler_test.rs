@@ -0,0 +1,102 @@
+use database::database::validators::Database;
+use database::table::data::{Column, DataType, FilterExpr, OrderBy, QueryProfile, QueryStage, Table, Value};
+
+fn table(name: &str, indexed: bool) -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    let pk = if indexed { Some(vec!["id".to_string()]) } else { None };
+    let mut table = Table::new(name, columns, pk);
+    for i in 1..=10 {
+        table
+            .insert(vec![Value::Int(i), Value::Varchar(format!("user{}", i))])
+            .unwrap();
+    }
+    table
+}
+
+#[test]
+fn test_profiled_select_uses_index() {
+    let table = table("users", true);
+    let (rows, profile) =
+        QueryProfile::capture(|| table.select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(3))).unwrap());
+
+    assert_eq!(rows.len(), 1);
+    assert_eq!(profile.rows_scanned, 1);
+    assert_eq!(profile.rows_returned, 1);
+
+    let stages: Vec<QueryStage> = profile.stages.iter().map(|s| s.stage).collect();
+    assert_eq!(
+        stages,
+        vec![
+            QueryStage::Parse,
+            QueryStage::IndexLookup,
+            QueryStage::Filter,
+            QueryStage::Materialize
+        ]
+    );
+}
+
+#[test]
+fn test_profiled_select_full_scan() {
+    let table = table("users", false);
+    let (rows, profile) =
+        QueryProfile::capture(|| table.select_where_expr(&FilterExpr::Gt("id".to_string(), Value::Int(7))).unwrap());
+
+    assert_eq!(rows.len(), 3);
+    assert_eq!(profile.rows_scanned, 10);
+    assert!(profile.total() >= profile.time_in(QueryStage::Filter));
+}
+
+#[test]
+fn test_profiled_join() {
+    let left = table("a", false);
+    let right = table("b", false);
+    let (joined, profile) = QueryProfile::capture(|| left.inner_join(&right, ("id", "id")).unwrap());
+
+    // Each side is read once, and the count is of pairs actually joined
+    assert_eq!(joined.len(), 10);
+    assert_eq!(profile.rows_scanned, 20);
+    assert_eq!(profile.rows_returned, 10);
+    assert!(profile.to_string().contains("Join"));
+
+    let filter = FilterExpr::Le("id".to_string(), Value::Int(4));
+    let (joined, profile) =
+        QueryProfile::capture(|| left.inner_join_filtered(&right, ("id", "id"), Some(&filter), None).unwrap());
+    assert_eq!(joined.len(), 4);
+    assert_eq!((profile.rows_scanned, profile.rows_returned), (20, 4));
+    let stages: Vec<QueryStage> = profile.stages.iter().map(|s| s.stage).collect();
+    assert_eq!(stages.last(), Some(&QueryStage::Join));
+}
+
+#[test]
+fn test_profiling_covers_sorts_and_sql() {
+    let table = table("users", false);
+    let (rows, profile) = QueryProfile::capture(|| table.select_order_by(&[OrderBy::desc("id")]).unwrap());
+    assert_eq!(rows[0][0], Value::Int(10));
+    let stages: Vec<QueryStage> = profile.stages.iter().map(|s| s.stage).collect();
+    assert_eq!(stages, [QueryStage::Sort]);
+    assert_eq!(profile.rows_scanned, 10);
+
+    let mut db = Database::new();
+    db.tables.insert("users".to_string(), table);
+    let (result, profile) =
+        QueryProfile::capture(|| db.execute("SELECT name FROM users WHERE id > 3 ORDER BY name LIMIT 2").unwrap());
+    assert_eq!(result.rows().unwrap().len(), 2);
+    for stage in [QueryStage::Parse, QueryStage::Filter, QueryStage::Sort, QueryStage::Materialize] {
+        assert!(profile.stages.iter().any(|s| s.stage == stage), "{:?} missing from\n{}", stage, profile);
+    }
+    assert_eq!((profile.rows_scanned, profile.rows_returned), (10, 2));
+
+    // Without a capture nothing is recorded, and the query runs the same
+    assert_eq!(db.execute("SELECT name FROM users WHERE id > 3").unwrap().rows().unwrap().len(), 7);
+}
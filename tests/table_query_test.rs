@@ -0,0 +1,131 @@
+use database::table::data::{AggFunc, Column, DataType, FilterExpr, OrderBy, Table, Value};
+
+fn sales() -> Table {
+    let columns = vec![
+        Column {
+            name: "region".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+        Column {
+            name: "product".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+        Column {
+            name: "amount".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("sales", columns, None);
+    let rows = [
+        ("north", "tea", 5),
+        ("south", "tea", 12),
+        ("north", "coffee", 20),
+        ("east", "tea", 8),
+        ("south", "coffee", 3),
+        ("north", "tea", 9),
+    ];
+    for (region, product, amount) in rows {
+        table
+            .insert(vec![
+                Value::Varchar(region.to_string()),
+                Value::Varchar(product.to_string()),
+                Value::Int(amount),
+            ])
+            .unwrap();
+    }
+    table
+}
+
+fn text(s: &str) -> Value {
+    Value::Varchar(s.to_string())
+}
+
+#[test]
+fn test_filter_order_distinct_and_limit_in_one_query() {
+    let table = sales();
+
+    let result = table
+        .query()
+        .filter(FilterExpr::Eq("product".to_string(), text("tea")))
+        .order_by(&[OrderBy::desc("amount")])
+        .select(&["region"])
+        .distinct()
+        .offset(1)
+        .limit(2)
+        .run()
+        .unwrap();
+
+    // Tea by amount: south 12, north 9, east 8, north 5
+    assert_eq!(result.columns.len(), 1);
+    assert_eq!(result.columns[0].name, "region");
+    assert_eq!(result.rows, vec![vec![text("north")], vec![text("east")]]);
+}
+
+#[test]
+fn test_grouped_query_orders_by_aggregate() {
+    let table = sales();
+
+    let result = table
+        .query()
+        .filter(FilterExpr::Gt("amount".to_string(), Value::Int(4)))
+        .group_by("region")
+        .aggregate("amount", AggFunc::Sum)
+        .aggregate("amount", AggFunc::Count)
+        .order_by(&[OrderBy::desc("sum(amount)")])
+        .run()
+        .unwrap();
+
+    let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["region", "sum(amount)", "count(amount)"]);
+    assert_eq!(
+        result.rows,
+        vec![
+            vec![text("north"), Value::Double(34.0), Value::BigInt(3)],
+            vec![text("south"), Value::Double(12.0), Value::BigInt(1)],
+            vec![text("east"), Value::Double(8.0), Value::BigInt(1)],
+        ]
+    );
+}
+
+#[test]
+fn test_query_reports_unknown_columns() {
+    let table = sales();
+
+    assert!(table.query().select(&["missing"]).run().is_err());
+    assert!(table
+        .query()
+        .filter(FilterExpr::Eq("missing".to_string(), Value::Int(1)))
+        .run()
+        .is_err());
+    assert!(table.query().group_by("region").order_by(&[OrderBy::asc("amount")]).run().is_err());
+}
+
+#[test]
+fn test_count_distinct_and_group_concat_skip_nulls() {
+    let mut table = sales();
+    table.insert(vec![text("east"), Value::Null, Value::Int(1)]).unwrap();
+    table.insert(vec![text("west"), Value::Null, Value::Int(2)]).unwrap();
+
+    let result = table
+        .query()
+        .group_by("region")
+        .count_distinct("product")
+        .group_concat("product", "; ")
+        .run()
+        .unwrap();
+
+    let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["region", "count(DISTINCT product)", "group_concat(product, ; )"]);
+    assert_eq!(
+        result.rows,
+        vec![
+            vec![text("east"), Value::BigInt(1), Value::Text("tea".to_string())],
+            vec![text("north"), Value::BigInt(2), Value::Text("tea; coffee; tea".to_string())],
+            vec![text("south"), Value::BigInt(2), Value::Text("tea; coffee".to_string())],
+            vec![text("west"), Value::BigInt(0), Value::Null],
+        ]
+    );
+}
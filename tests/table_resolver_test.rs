@@ -0,0 +1,106 @@
+use database::table::data::{Column, DataType, FilterExpr, OrderBy, Table, Value};
+use database::error::DbError;
+
+fn column(name: &str, datatype: DataType) -> Column {
+    Column {
+        name: name.to_string(),
+        datatype,
+        options: vec![],
+    }
+}
+
+fn people() -> Table {
+    let columns = vec![
+        column("Id", DataType::Int),
+        column("Name", DataType::Varchar(255)),
+    ];
+    let mut table = Table::new("people", columns, Some(vec!["Id".to_string()]));
+    for (id, name) in [(2, "Bob"), (1, "Ann"), (3, "Cat")] {
+        table
+            .insert(vec![Value::Int(id), Value::Varchar(name.to_string())])
+            .unwrap();
+    }
+    table
+}
+
+#[test]
+fn test_resolve_column_rules() {
+    let table = people();
+    assert_eq!(table.resolve_column("Id"), Ok(0));
+    assert_eq!(table.resolve_column("name"), Ok(1));
+    assert_eq!(table.resolve_column("PEOPLE.name"), Ok(1));
+    assert_eq!(table.canonical_column("people.id"), Ok("Id"));
+    assert!(table.resolve_column("pets.name").is_err());
+    assert!(table.resolve_column("age").is_err());
+
+    let aliased = table.with_alias("p");
+    assert_eq!(aliased.resolve_column("p.Name"), Ok(1));
+    assert_eq!(aliased.resolve_column("name"), Ok(1));
+
+    let mixed = Table::new(
+        "mixed",
+        vec![
+            column("a.id", DataType::Int),
+            column("b.id", DataType::Int),
+            column("id", DataType::Int),
+        ],
+        None,
+    );
+    // An exact match wins over suffix matches
+    assert_eq!(mixed.resolve_column("id"), Ok(2));
+    assert_eq!(mixed.resolve_column("B.ID"), Ok(1));
+
+    let ambiguous = Table::new(
+        "pairs",
+        vec![column("a.id", DataType::Int), column("b.id", DataType::Int)],
+        None,
+    );
+    let err = ambiguous.resolve_column("id").unwrap_err();
+    assert!(matches!(err, DbError::InvalidArgument(_)));
+    assert!(err.to_string().contains("ambiguous"), "{}", err);
+    assert!(matches!(table.resolve_column("age"), Err(DbError::NotFound(_))));
+}
+
+#[test]
+fn test_query_apis_accept_resolved_names() {
+    let mut table = people();
+
+    // Filters still use the index stored under the declared name
+    let expr = FilterExpr::Eq("people.ID".to_string(), Value::Int(1));
+    assert_eq!(table.select_where_expr(&expr).unwrap().len(), 1);
+    assert!(table
+        .plan(Some(&expr))
+        .to_string()
+        .contains("IndexScan people using Id"));
+
+    let sorted = table.select_order_by(&[OrderBy::desc("name")]).unwrap();
+    assert_eq!(sorted[0][1], Value::Varchar("Cat".to_string()));
+
+    let groups = table.group_by("NAME", None).unwrap();
+    assert_eq!(groups.len(), 3);
+
+    let mut pets = Table::new(
+        "pets",
+        vec![
+            column("owner_id", DataType::Int),
+            column("pet", DataType::Varchar(255)),
+        ],
+        None,
+    );
+    pets.insert(vec![Value::Int(1), Value::Varchar("Rex".to_string())])
+        .unwrap();
+    let joined = table.inner_join(&pets, ("id", "PETS.OWNER_ID")).unwrap();
+    assert_eq!(joined.len(), 1);
+
+    table
+        .update_where(
+            FilterExpr::Eq("id".to_string(), Value::Int(2)),
+            vec![None, Some(Value::Varchar("Rob".to_string()))],
+        )
+        .unwrap();
+    table.delete_where(FilterExpr::Eq("ID".to_string(), Value::Int(3))).unwrap();
+    assert_eq!(table.rows.len(), 2);
+
+    let parsed = table.parse_filter("name = 'Rob'").unwrap();
+    assert_eq!(table.select_where_expr(&parsed).unwrap().len(), 1);
+}
@@ -0,0 +1,69 @@
+use database::error::DbError;
+use database::table::data::{Column, DataType, FilterExpr, Table, Value};
+
+fn numbers(count: i32) -> Table {
+    let columns = vec![
+        Column {
+            name: "n".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "parity".to_string(),
+            datatype: DataType::Varchar(4),
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("numbers", columns, None);
+    for n in (0..count).rev() {
+        let parity = if n % 2 == 0 { "even" } else { "odd" };
+        table.insert(vec![Value::Int(n), Value::Varchar(parity.to_string())]).unwrap();
+    }
+    table
+}
+
+#[test]
+fn test_scan_is_lazy() {
+    let table = numbers(1000);
+    let filter = FilterExpr::Eq("parity".to_string(), Value::Varchar("odd".to_string()));
+
+    let first: Vec<&[Value]> = table.scan(Some(&filter)).unwrap().take(2).collect();
+    assert_eq!(first[0][0], Value::Int(999));
+    assert_eq!(first[1][0], Value::Int(997));
+
+    assert_eq!(table.scan(None).unwrap().count(), 1000);
+    let total: i64 = table
+        .scan(Some(&filter))
+        .unwrap()
+        .map(|row| match row[0] {
+            Value::Int(n) => n as i64,
+            _ => 0,
+        })
+        .sum();
+    assert_eq!(total, 250_000);
+}
+
+#[test]
+fn test_scan_indexed_matches_scan() {
+    let mut table = numbers(50);
+    let filter = FilterExpr::Between("n".to_string(), Value::Int(10), Value::Int(14));
+
+    let scanned: Vec<Vec<Value>> = table.scan(Some(&filter)).unwrap().map(<[Value]>::to_vec).collect();
+    assert_eq!(scanned.len(), 5);
+    // No index yet, so this is a plain scan
+    assert_eq!(table.scan_indexed(&filter).unwrap().map(<[Value]>::to_vec).collect::<Vec<_>>(), scanned);
+
+    table.create_index("n", true).unwrap();
+    let indexed: Vec<&[Value]> = table.scan_indexed(&filter).unwrap().collect();
+    let keys: Vec<&Value> = indexed.iter().map(|row| &row[0]).collect();
+    assert_eq!(keys, [&Value::Int(10), &Value::Int(11), &Value::Int(12), &Value::Int(13), &Value::Int(14)]);
+}
+
+#[test]
+fn test_scan_rejects_unknown_columns() {
+    let table = numbers(5);
+    let filter = FilterExpr::Eq("missing".to_string(), Value::Int(1));
+
+    assert!(matches!(table.scan(Some(&filter)), Err(DbError::NotFound(_))));
+    assert!(matches!(table.scan_indexed(&filter), Err(DbError::NotFound(_))));
+}
@@ -0,0 +1,146 @@
+use database::error::DbError;
+use database::table::data::{Column, DataType, DefaultExpr, Options, Table, Value};
+use std::fs;
+
+fn sizes() -> Vec<String> {
+    vec!["small".to_string(), "medium".to_string(), "large".to_string()]
+}
+
+fn toppings() -> Vec<String> {
+    vec!["cheese".to_string(), "ham".to_string(), "olives".to_string()]
+}
+
+#[test]
+fn test_load_from_file_infers_schema_and_allowed_lists() {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![Options::NotNull],
+        },
+        Column {
+            name: "size".to_string(),
+            datatype: DataType::Enum(sizes()),
+            options: vec![],
+        },
+        Column {
+            name: "toppings".to_string(),
+            datatype: DataType::Set(toppings()),
+            options: vec![Options::Default(DefaultExpr::Literal(Value::Set(vec![])))],
+        },
+    ];
+    let mut table = Table::new("pizzas", columns.clone(), Some(vec!["id".to_string()]));
+    table
+        .insert(vec![
+            Value::Int(1),
+            Value::Enum("large".to_string()),
+            Value::Set(vec!["ham".to_string()]),
+        ])
+        .unwrap();
+    table.save_to_file("schematest").unwrap();
+
+    let mut loaded = Table::load_from_file("schematest", "pizzas").unwrap();
+    assert_eq!(loaded.columns, columns);
+    assert_eq!(loaded.primary_key, Some(vec!["id".to_string()]));
+    assert_eq!(loaded.rows, table.rows);
+
+    // Validation still sees the allowed values after a reload
+    assert!(loaded
+        .insert(vec![
+            Value::Int(2),
+            Value::Enum("huge".to_string()),
+            Value::Set(vec![]),
+        ])
+        .is_err());
+
+    fs::remove_file("db/schematest.pizzas.pages").unwrap();
+    fs::remove_file("db/schematest.pizzas.schema").unwrap();
+}
+
+#[test]
+fn test_identity_counter_survives_save_and_load() {
+    let columns = vec![Column {
+        name: "id".to_string(),
+        datatype: DataType::Int,
+        options: vec![Options::NotNull, Options::Autoincrement],
+    }];
+    let mut table = Table::new("tickets", columns, None);
+    for _ in 0..3 {
+        table.insert(vec![Value::Null]).unwrap();
+    }
+    table.delete_where(|row: &Vec<Value>| row[0] == Value::Int(3)).unwrap();
+    table.save_to_file("sequencetest").unwrap();
+
+    let mut loaded = Table::load_from_file("sequencetest", "tickets").unwrap();
+    loaded.insert(vec![Value::Null]).unwrap();
+    assert_eq!(loaded.rows[2][0], Value::Int(4));
+
+    fs::remove_file("db/sequencetest.tickets.pages").unwrap();
+    fs::remove_file("db/sequencetest.tickets.schema").unwrap();
+}
+
+#[test]
+fn test_default_expressions_survive_save_and_load() {
+    let columns = vec![Column {
+        name: "expires".to_string(),
+        datatype: DataType::Date,
+        options: vec![Options::Default(DefaultExpr::Add(
+            Box::new(DefaultExpr::Literal(Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()))),
+            -7,
+        ))],
+    }];
+    let table = Table::new("passes", columns.clone(), None);
+    table.save_to_file("defaultexprtest").unwrap();
+
+    let loaded = Table::load_from_file("defaultexprtest", "passes").unwrap();
+    assert_eq!(loaded.columns, columns);
+
+    fs::remove_file("db/defaultexprtest.passes.pages").unwrap();
+    fs::remove_file("db/defaultexprtest.passes.schema").unwrap();
+}
+
+#[test]
+fn test_load_from_file_without_schema_fails() {
+    let err = Table::load_from_file("schematest", "missing").unwrap_err();
+    assert!(matches!(err, DbError::IoError(_)));
+}
+
+#[test]
+fn test_decimal_columns_survive_save_and_load() {
+    let columns = vec![Column {
+        name: "balance".to_string(),
+        datatype: DataType::Decimal(12, 4),
+        options: vec![],
+    }];
+    let mut table = Table::new("accounts", columns, None);
+    let balance = Value::from_str("-1050.0625", &DataType::Decimal(12, 4)).unwrap();
+    table.insert(vec![balance.clone()]).unwrap();
+    table.save_to_file("decimaltest").unwrap();
+
+    let loaded = Table::load_from_file("decimaltest", "accounts").unwrap();
+    assert_eq!(loaded.columns[0].datatype, DataType::Decimal(12, 4));
+    assert_eq!(loaded.rows[0][0], balance);
+    assert_eq!(loaded.rows[0][0].to_display_string(), "-1050.0625");
+
+    fs::remove_file("db/decimaltest.accounts.pages").unwrap();
+    fs::remove_file("db/decimaltest.accounts.schema").unwrap();
+}
+
+#[test]
+fn test_older_schema_files_keep_their_allowed_lists() {
+    fs::create_dir_all("db").unwrap();
+    fs::write(
+        "db/legacytest.pizzas.schema",
+        "column\tsize\tEnum\noption\tDefaultEnum\tsmall\tsmall\tlarge\nallowed\tsize\tsmall\tlarge\n",
+    )
+    .unwrap();
+    fs::write("db/legacytest.pizzas.csv", "size\n\"large\"\n").unwrap();
+
+    let loaded = Table::load_from_file("legacytest", "pizzas").unwrap();
+    assert_eq!(loaded.columns[0].datatype, DataType::Enum(vec!["small".to_string(), "large".to_string()]));
+    assert_eq!(loaded.columns[0].options, vec![Options::Default(DefaultExpr::Literal(Value::Enum("small".to_string())))]);
+    assert_eq!(loaded.rows[0][0], Value::Enum("large".to_string()));
+
+    fs::remove_file("db/legacytest.pizzas.csv").unwrap();
+    fs::remove_file("db/legacytest.pizzas.schema").unwrap();
+}
@@ -0,0 +1,58 @@
+use database::table::data::{Column, DataType, Table, Value};
+
+fn table(name: &str, ids: &[i32]) -> Table {
+    let columns = vec![Column {
+        name: "id".to_string(),
+        datatype: DataType::Int,
+        options: vec![],
+    }];
+    let mut table = Table::new(name, columns, None);
+    for id in ids {
+        table.insert(vec![Value::Int(*id)]).unwrap();
+    }
+    table
+}
+
+fn ids(table: &Table) -> Vec<i32> {
+    table
+        .rows
+        .iter()
+        .map(|r| match r[0] {
+            Value::Int(i) => i,
+            _ => panic!("expected int"),
+        })
+        .collect()
+}
+
+#[test]
+fn test_union_and_union_all() {
+    let a = table("a", &[1, 2, 2, 3]);
+    let b = table("b", &[3, 4]);
+
+    assert_eq!(ids(&a.union(&b).unwrap()), vec![1, 2, 3, 4]);
+    assert_eq!(ids(&a.union_all(&b).unwrap()), vec![1, 2, 2, 3, 3, 4]);
+}
+
+#[test]
+fn test_intersect_and_except_all_keep_multiplicity() {
+    let a = table("a", &[1, 1, 1, 2, 3]);
+    let b = table("b", &[1, 1, 3, 3]);
+
+    assert_eq!(ids(&a.intersect(&b).unwrap()), vec![1, 3]);
+    assert_eq!(ids(&a.intersect_all(&b).unwrap()), vec![1, 1, 3]);
+    assert_eq!(ids(&a.except(&b).unwrap()), vec![2]);
+    assert_eq!(ids(&a.except_all(&b).unwrap()), vec![1, 2]);
+}
+
+#[test]
+fn test_set_ops_reject_mismatched_schemas() {
+    let a = table("a", &[1]);
+
+    let mut renamed = table("b", &[1]);
+    renamed.columns[0].name = "other".to_string();
+    assert!(a.union(&renamed).unwrap_err().contains("name mismatch"));
+
+    let mut retyped = table("c", &[]);
+    retyped.columns[0].datatype = DataType::BigInt;
+    assert!(a.intersect(&retyped).is_err());
+}
@@ -0,0 +1,105 @@
+use database::table::data::{Column, DataType, FilterExpr, Table, Value};
+use database::table::sharding::SHARD_ROW_THRESHOLD;
+use std::fs;
+use std::path::PathBuf;
+
+fn columns() -> Vec<Column> {
+    vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ]
+}
+
+fn people(count: i32) -> Table {
+    let mut table = Table::new("people", columns(), Some(vec!["id".to_string()]));
+    for i in 0..count {
+        table.insert(vec![Value::Int(i), Value::Varchar(format!("p{}", i))]).unwrap();
+    }
+    table
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn csv_files(dir: &PathBuf) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".csv"))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_sharded_save_and_load_round_trip() {
+    let dir = temp_dir("sharding_round_trip");
+    let table = people(100);
+
+    table.save_sharded(&dir, "testdb", 4).unwrap();
+    assert_eq!(
+        csv_files(&dir),
+        vec![
+            "testdb.people.shard0.csv",
+            "testdb.people.shard1.csv",
+            "testdb.people.shard2.csv",
+            "testdb.people.shard3.csv",
+        ]
+    );
+
+    let loaded =
+        Table::load_from_dir(&dir, "testdb", "people", columns(), Some(vec!["id".to_string()]))
+            .unwrap();
+    assert_eq!(loaded.rows.len(), 100);
+
+    let found = loaded.select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(42))).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0][1], Value::Varchar("p42".to_string()));
+
+    // Saving a small table again replaces the shards with a single page file
+    people(3).save_to_dir(&dir, "testdb").unwrap();
+    assert!(csv_files(&dir).is_empty());
+    assert!(dir.join("testdb.people.pages").exists());
+    assert!(dir.join("testdb.people.schema").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_large_tables_shard_automatically() {
+    let dir = temp_dir("sharding_auto");
+    let table = people(SHARD_ROW_THRESHOLD as i32 + 1);
+    assert_eq!(table.shard_count(), 2);
+    assert_eq!(people(10).shard_count(), 1);
+
+    table.save_to_dir(&dir, "testdb").unwrap();
+    assert_eq!(csv_files(&dir).len(), 2);
+
+    let loaded =
+        Table::load_from_dir(&dir, "testdb", "people", columns(), Some(vec!["id".to_string()]))
+            .unwrap();
+    assert_eq!(loaded.rows.len(), table.rows.len());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_sharding_requires_primary_key() {
+    let dir = temp_dir("sharding_no_pk");
+    let mut table = Table::new("log", columns(), None);
+    table.insert(vec![Value::Int(1), Value::Varchar("a".to_string())]).unwrap();
+
+    assert!(table.save_sharded(&dir, "testdb", 2).is_err());
+    assert_eq!(table.shard_count(), 1);
+}
@@ -0,0 +1,102 @@
+use database::table::data::{Column, DataType, FilterExpr, IndexType, PlanNode, Table, Value};
+use database::table::spatial::distance_km;
+
+fn places() -> Table {
+    let columns = vec![
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+        Column {
+            name: "loc".to_string(),
+            datatype: DataType::Point,
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("places", columns, Some(vec!["name".to_string()]));
+    for (name, lat, lon) in [
+        ("nyc", 40.7128, -74.0060),
+        ("newark", 40.7357, -74.1724),
+        ("philly", 39.9526, -75.1652),
+        ("boston", 42.3601, -71.0589),
+        ("london", 51.5074, -0.1278),
+    ] {
+        table
+            .insert(vec![Value::Varchar(name.to_string()), Value::point(lat, lon).unwrap()])
+            .unwrap();
+    }
+    table.insert(vec![Value::Varchar("nowhere".to_string()), Value::Null]).unwrap();
+    table
+}
+
+fn names(rows: Vec<&Vec<Value>>) -> Vec<String> {
+    let mut names: Vec<String> = rows.iter().map(|r| r[0].to_string()).collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_point_values() {
+    assert!(Value::point(91.0, 0.0).is_err());
+    assert!(Value::point(0.0, -181.0).is_err());
+
+    let p = Value::from_str("(40.5, -74.25)", &DataType::Point).unwrap();
+    assert_eq!(p, Value::Point(40.5, -74.25));
+    assert_eq!(p.to_string(), "(40.5, -74.25)");
+    assert!(Value::from_str("40.5", &DataType::Point).is_err());
+
+    let nyc_to_london = distance_km((40.7128, -74.0060), (51.5074, -0.1278));
+    assert!((nyc_to_london - 5570.0).abs() < 10.0, "{}", nyc_to_london);
+}
+
+#[test]
+fn test_within_box_and_radius() {
+    let mut table = places();
+    let boxed = FilterExpr::WithinBox(
+        "loc".to_string(),
+        Value::Point(39.0, -76.0),
+        Value::Point(41.0, -73.0),
+    );
+    let near_nyc = FilterExpr::WithinRadius("loc".to_string(), Value::Point(40.7128, -74.0060), 20.0);
+
+    let expected_box = vec!["newark", "nyc", "philly"];
+    let expected_radius = vec!["newark", "nyc"];
+    assert_eq!(names(table.select_where_expr(&boxed).unwrap()), expected_box);
+    assert_eq!(names(table.select_where_expr(&near_nyc).unwrap()), expected_radius);
+
+    // The grid index narrows the scan without changing the answer
+    table.create_grid_index("loc", 0.5).unwrap();
+    assert!(matches!(table.indexes.get("loc"), Some(IndexType::Grid(_))));
+    match table.plan(Some(&boxed)).root {
+        PlanNode::Filter { input, .. } => {
+            assert!(matches!(*input, PlanNode::IndexScan { rows: 3, .. }), "{:?}", input)
+        }
+        other => panic!("unexpected plan {:?}", other),
+    }
+    assert_eq!(names(table.select_where_expr(&boxed).unwrap()), expected_box);
+    assert_eq!(names(table.select_where_expr(&near_nyc).unwrap()), expected_radius);
+
+    assert_eq!(table.delete_where(&near_nyc).unwrap(), 2);
+    assert_eq!(names(table.select_where_expr(&boxed).unwrap()), vec!["philly"]);
+    assert_eq!(table.clone().select_where_expr(&boxed).unwrap().len(), 1);
+}
+
+#[test]
+fn test_spatial_filter_text_round_trip() {
+    let table = places();
+    let boxed = FilterExpr::WithinBox(
+        "loc".to_string(),
+        Value::Point(39.0, -76.5),
+        Value::Point(41.0, -73.0),
+    );
+    let near = FilterExpr::WithinRadius("loc".to_string(), Value::Point(40.7, -74.0), 12.5);
+
+    assert_eq!(boxed.to_string(), "loc WITHIN BOX (39, -76.5) (41, -73)");
+    assert_eq!(near.to_string(), "loc WITHIN 12.5 KM OF (40.7, -74)");
+    assert_eq!(boxed.to_string().parse::<FilterExpr>().unwrap(), boxed);
+    assert_eq!(table.parse_filter("loc within 12.5 km of (40.7, -74)").unwrap(), near);
+
+    assert!(table.parse_filter("name WITHIN 5 KM OF (1, 2)").is_err());
+    assert!("loc WITHIN BOX (1, 2)".parse::<FilterExpr>().is_err());
+}
@@ -0,0 +1,80 @@
+use database::database::config::{DatabaseConfig, Durability};
+use database::database::validators::Database;
+use database::table::data::{Collation, Column, ColumnStats, DataType, Options, Table, Value};
+use std::fs;
+
+fn people() -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "name".to_string(),
+            datatype: DataType::Varchar(20),
+            options: vec![Options::Collate(Collation::CaseInsensitive)],
+        },
+        Column {
+            name: "age".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("people", columns, Some(vec!["id".to_string()]));
+    for (id, name, age) in [(1, "Ada", Some(36)), (2, "ada", None), (3, "Bob", Some(29)), (4, "cy", None)] {
+        let age = age.map_or(Value::Null, Value::Int);
+        table.insert(vec![Value::Int(id), Value::Varchar(name.to_string()), age]).unwrap();
+    }
+    table
+}
+
+#[test]
+fn test_analyze_counts_nulls_distinct_values_and_bounds() {
+    let mut table = people();
+    assert!(table.stats().is_none());
+
+    let stats = table.analyze().clone();
+    assert_eq!(stats.row_count, 4);
+    assert_eq!(
+        stats.column("age"),
+        Some(&ColumnStats {
+            column: "age".to_string(),
+            null_count: 2,
+            distinct_count: 2,
+            min: Some(Value::Int(29)),
+            max: Some(Value::Int(36)),
+        })
+    );
+    // NOCASE counts Ada and ada once and sorts cy last
+    let name = stats.column("name").unwrap();
+    assert_eq!(name.distinct_count, 3);
+    assert_eq!(name.max, Some(Value::Varchar("cy".to_string())));
+
+    // Stats are a snapshot until the next analyze
+    table.insert(vec![Value::Int(5), Value::Varchar("dee".to_string()), Value::Null]).unwrap();
+    assert_eq!(table.stats().unwrap().row_count, 4);
+    assert_eq!(table.analyze().column("age").unwrap().null_count, 3);
+}
+
+#[test]
+fn test_stats_are_saved_with_the_schema() {
+    let root = std::env::temp_dir().join("table_stats_test");
+    let _ = fs::remove_dir_all(&root);
+    let path = root.to_str().unwrap();
+
+    let mut db = Database::with_config(DatabaseConfig::new("stats", &root));
+    let mut table = Table::new("people", people().columns, Some(vec!["id".to_string()]));
+    table.insert(vec![Value::Int(1), Value::Varchar("Ada".to_string()), Value::Int(36)]).unwrap();
+    table.insert(vec![Value::Int(2), Value::Varchar("Bob".to_string()), Value::Int(29)]).unwrap();
+    table.analyze();
+    db.create_table(table, Durability::Persistent).unwrap();
+    db.create_table(Table::new("empty", people().columns, None), Durability::Persistent).unwrap();
+    db.save(path).unwrap();
+
+    let loaded = Database::load(path).unwrap();
+    assert_eq!(loaded.tables["people"].stats(), db.tables["people"].stats());
+    assert!(loaded.tables["empty"].stats().is_none());
+
+    fs::remove_dir_all(&root).unwrap();
+}
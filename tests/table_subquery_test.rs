@@ -0,0 +1,118 @@
+use database::table::data::{AggFunc, AggregationResult, Column, DataType, Table, Value};
+
+fn orders() -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "customer".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+        Column {
+            name: "amount".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("orders", columns, Some(vec!["id".to_string()]));
+    let rows = [(1, "ann", 10), (2, "ann", 30), (3, "bob", 5), (4, "bob", 7), (5, "bob", 9)];
+    for (id, customer, amount) in rows {
+        table
+            .insert(vec![
+                Value::Int(id),
+                Value::Varchar(customer.to_string()),
+                Value::Int(amount),
+            ])
+            .unwrap();
+    }
+    table
+}
+
+fn customers() -> Table {
+    let columns = vec![Column {
+        name: "name".to_string(),
+        datatype: DataType::Varchar(255),
+        options: vec![],
+    }];
+    let mut table = Table::new("customers", columns, None);
+    for name in ["ann", "bob", "cid"] {
+        table.insert(vec![Value::Varchar(name.to_string())]).unwrap();
+    }
+    table
+}
+
+#[test]
+fn test_orders_above_customer_average() {
+    let orders = orders();
+
+    let rows = orders
+        .select_correlated(&orders, |outer, inner| {
+            let filter = outer.correlate("customer", "customer")?;
+            match inner.aggregate_where(&filter, "amount", AggFunc::Avg)? {
+                AggregationResult::Avg(Value::Double(avg)) => {
+                    Ok(matches!(outer.get("amount"), Some(Value::Int(a)) if *a as f64 > avg))
+                }
+                _ => unreachable!(),
+            }
+        })
+        .unwrap();
+
+    let ids: Vec<&Value> = rows.iter().map(|r| &r[0]).collect();
+    assert_eq!(ids, vec![&Value::Int(2), &Value::Int(5)]);
+}
+
+#[test]
+fn test_correlated_exists() {
+    let customers = customers();
+    let orders = orders();
+
+    let rows = customers
+        .select_correlated(&orders, |outer, inner| {
+            Ok(!inner.exists_where(&outer.correlate("customer", "name")?).unwrap())
+        })
+        .unwrap();
+
+    assert_eq!(rows, vec![&vec![Value::Varchar("cid".to_string())]]);
+}
+
+#[test]
+fn test_correlate_unknown_outer_column() {
+    let customers = customers();
+    let orders = orders();
+
+    let result = customers.select_correlated(&orders, |outer, inner| {
+        Ok(inner.exists_where(&outer.correlate("customer", "missing")?).unwrap())
+    });
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_select_where_in_table() {
+    let customers = customers();
+    let mut orders = orders();
+    orders
+        .insert(vec![Value::Int(6), Value::Null, Value::Int(1)])
+        .unwrap();
+
+    let rows = customers.select_where_in_table("name", &orders, "customer").unwrap();
+    let names: Vec<&Value> = rows.iter().map(|r| &r[0]).collect();
+    assert_eq!(
+        names,
+        vec![&Value::Varchar("ann".to_string()), &Value::Varchar("bob".to_string())]
+    );
+
+    let ids: Vec<&Value> = orders
+        .select_where_in_table("customer", &customers, "name")
+        .unwrap()
+        .iter()
+        .map(|r| &r[0])
+        .collect();
+    assert_eq!(ids.len(), 5);
+
+    assert!(customers.select_where_in_table("name", &orders, "missing").is_err());
+}
@@ -0,0 +1,63 @@
+use database::table::data::{Column, DataType, FilterExpr, Options, Table, Value};
+use chrono::{Duration, Local};
+
+fn sessions() -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "created_at".to_string(),
+            datatype: DataType::DateTime,
+            options: vec![Options::Ttl(3600)],
+        },
+    ];
+    let mut table = Table::new("sessions", columns, Some(vec!["id".to_string()]));
+
+    let now = Local::now().naive_local();
+    table.insert(vec![Value::Int(1), Value::DateTime(now)]).unwrap();
+    table
+        .insert(vec![Value::Int(2), Value::DateTime(now - Duration::hours(2))])
+        .unwrap();
+    table
+}
+
+#[test]
+fn test_expired_rows_are_skipped_by_queries() {
+    let table = sessions();
+
+    assert_eq!(table.select_all().len(), 1);
+    assert!(table
+        .select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(2)))
+        .unwrap()
+        .is_empty());
+    assert_eq!(table.rows.len(), 2);
+}
+
+#[test]
+fn test_vacuum_removes_expired_rows() {
+    let mut table = sessions();
+
+    assert_eq!(table.vacuum(), 1);
+    assert_eq!(table.rows.len(), 1);
+    assert_eq!(table.rows[0][0], Value::Int(1));
+    assert_eq!(
+        table
+            .select_where_expr(&FilterExpr::Eq("id".to_string(), Value::Int(1)))
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn test_ttl_requires_timestamp_column() {
+    let column = Column {
+        name: "name".to_string(),
+        datatype: DataType::Varchar(255),
+        options: vec![Options::Ttl(60)],
+    };
+    assert!(column.validate().is_err());
+}
@@ -0,0 +1,116 @@
+use chrono::NaiveDate;
+use database::db_row;
+use database::error::DbError;
+use database::table::data::{Column, DataType, Options, Table, Value};
+use database::table::typed::DbRow;
+
+db_row! {
+    #[derive(Debug, Clone, PartialEq)]
+    struct Person {
+        id: i32,
+        name: String,
+        born: Option<NaiveDate>,
+    }
+}
+
+fn events() -> Table {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "title".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+        Column {
+            name: "day".to_string(),
+            datatype: DataType::Date,
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("events", columns, None);
+    table
+        .insert(vec![
+            Value::Int(1),
+            Value::Varchar("launch".to_string()),
+            Value::Date(NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()),
+        ])
+        .unwrap();
+    table
+        .insert(vec![Value::Int(2), Value::Varchar("retro".to_string()), Value::Null])
+        .unwrap();
+    table
+}
+
+#[test]
+fn test_column_values_typed() {
+    let table = events();
+
+    let ids: Vec<i32> = table.column_values::<i32>("id").unwrap().collect::<Result<_, _>>().unwrap();
+    assert_eq!(ids, vec![1, 2]);
+
+    let total: f64 = table.column_values::<f64>("id").unwrap().map(|v| v.unwrap()).sum();
+    assert_eq!(total, 3.0);
+
+    let titles: Vec<String> = table
+        .column_values("title")
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(titles, vec!["launch", "retro"]);
+
+    let days: Vec<Option<NaiveDate>> = table
+        .column_values("day")
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(days, vec![NaiveDate::from_ymd_opt(2024, 5, 1), None]);
+}
+
+#[test]
+fn test_column_values_type_mismatch() {
+    let table = events();
+
+    let result: Result<Vec<i32>, DbError> = table.column_values("title").unwrap().collect();
+    assert!(result.unwrap_err().to_string().contains("Column 'title'"));
+
+    let days: Result<Vec<NaiveDate>, DbError> = table.column_values("day").unwrap().collect();
+    assert!(days.is_err());
+
+    assert!(table.column_values::<i32>("missing").is_err());
+}
+
+#[test]
+fn test_db_row_maps_structs_to_rows() {
+    let columns = Person::columns();
+    let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["id", "name", "born"]);
+    assert_eq!(columns[1].datatype, DataType::Text);
+    assert_eq!(columns[0].options, [Options::NotNull]);
+    assert!(columns[2].options.is_empty());
+
+    let mut table = Table::new("people", columns, Some(vec!["id".to_string()]));
+    let ada = Person {
+        id: 1,
+        name: "Ada".to_string(),
+        born: NaiveDate::from_ymd_opt(1815, 12, 10),
+    };
+    let bob = Person {
+        id: 2,
+        name: "Bob".to_string(),
+        born: None,
+    };
+    table.insert_record(&ada).unwrap();
+    table.insert_record(&bob).unwrap();
+    assert_eq!(table.rows[1], [Value::Int(2), Value::Text("Bob".to_string()), Value::Null]);
+
+    let people: Vec<Person> = table.records().collect::<Result<_, _>>().unwrap();
+    assert_eq!(people, [ada, bob]);
+
+    let err = Person::from_row(&[Value::Int(3), Value::Null, Value::Null]).unwrap_err();
+    assert!(err.contains("Field 'name'"));
+    assert!(Person::from_row(&[Value::Int(3)]).is_err());
+}
@@ -0,0 +1,107 @@
+use database::table::data::{Column, DataType, DefaultExpr, Expr, FilterExpr, Options, Table, Value};
+use database::table::update::UpdateSet;
+use std::collections::HashMap;
+
+fn players() -> Table {
+    let column = |name: &str, datatype, options| Column {
+        name: name.to_string(),
+        datatype,
+        options,
+    };
+    let columns = vec![
+        column("name", DataType::Varchar(20), vec![]),
+        column("score", DataType::Int, vec![Options::Check("score <= 100".parse().unwrap())]),
+        column("bonus", DataType::Int, vec![]),
+    ];
+    let mut table = Table::new("players", columns, None);
+    for (name, score, bonus) in [("ada", 50, 5), ("bob", 95, 10), ("cy", 10, 0)] {
+        table.insert(vec![Value::Varchar(name.to_string()), Value::Int(score), Value::Int(bonus)]).unwrap();
+    }
+    table
+}
+
+fn expr(text: &str) -> Expr {
+    text.parse().unwrap()
+}
+
+fn column(table: &Table, i: usize) -> Vec<Value> {
+    table.rows.iter().map(|row| row[i].clone()).collect()
+}
+
+#[test]
+fn test_updates_can_use_the_current_row() {
+    let mut table = players();
+    let count = table
+        .update_where(
+            FilterExpr::Lt("score".to_string(), Value::Int(90)),
+            vec![("score", expr("score + bonus * 2")), ("name", expr("UPPER(name)"))],
+        )
+        .unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(column(&table, 1), [Value::Int(60), Value::Int(95), Value::Int(10)]);
+    assert_eq!(table.rows[0][0], Value::Varchar("ADA".to_string()));
+
+    // Each assignment reads the row from before the update
+    table.update_where(|_: &Vec<Value>| true, vec![("score", expr("bonus")), ("bonus", expr("score"))]).unwrap();
+    assert_eq!(column(&table, 1), [Value::Int(5), Value::Int(10), Value::Int(0)]);
+    assert_eq!(column(&table, 2), [Value::Int(60), Value::Int(95), Value::Int(10)]);
+}
+
+#[test]
+fn test_expression_updates_are_checked_as_a_whole() {
+    let mut table = players();
+    // bob would pass 100, so nobody changes
+    assert!(table.update_where(|_: &Vec<Value>| true, vec![("score", expr("score + 10"))]).is_err());
+    assert_eq!(column(&table, 1), [Value::Int(50), Value::Int(95), Value::Int(10)]);
+
+    assert!(table.update_where(|_: &Vec<Value>| true, vec![("rank", expr("1"))]).is_err());
+    assert!(table.update_where(|_: &Vec<Value>| true, vec![("score", expr("1")), ("score", expr("2"))]).is_err());
+    assert!(table.update_where(|_: &Vec<Value>| true, vec![("score", expr("name"))]).is_err());
+
+    // Plain value updates still work as before
+    table.update_where(|_: &Vec<Value>| true, vec![None, None, Some(Value::Int(1))]).unwrap();
+    assert_eq!(column(&table, 2), [Value::Int(1), Value::Int(1), Value::Int(1)]);
+}
+
+#[test]
+fn test_insert_and_update_by_column_name() {
+    let mut table = players();
+    table.columns[2].options.push(Options::Default(DefaultExpr::Literal(Value::Int(3))));
+
+    table
+        .insert_named(HashMap::from([("score", Value::Int(70)), ("name", Value::Varchar("dee".to_string()))]))
+        .unwrap();
+    assert_eq!(table.rows[3], [Value::Varchar("dee".to_string()), Value::Int(70), Value::Int(3)]);
+
+    assert!(table.insert_named(HashMap::from([("rank", Value::Int(1))])).is_err());
+    assert!(table.insert_named(HashMap::from([("score", Value::Int(1)), ("SCORE", Value::Int(2))])).is_err());
+    assert_eq!(table.rows.len(), 4);
+
+    let set: UpdateSet = HashMap::from([("bonus", Value::Int(0))]);
+    let count = table.update_where(FilterExpr::Gt("score".to_string(), Value::Int(60)), set).unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(column(&table, 2), [Value::Int(5), Value::Int(0), Value::Int(0), Value::Int(0)]);
+
+    let unknown: UpdateSet = HashMap::from([("rank", Value::Int(1))]);
+    assert!(table.update_where(|_: &Vec<Value>| true, unknown).is_err());
+}
+
+#[test]
+fn test_updated_rows_stay_unique_among_themselves() {
+    let mut table = players();
+    table.columns[0].options.push(Options::Unique);
+
+    // Two rows given the same name clash with each other, not with a stored row
+    let renamed = table.update_where(
+        FilterExpr::Lt("score".to_string(), Value::Int(90)),
+        vec![Some(Value::Varchar("dee".to_string())), None, None],
+    );
+    assert!(renamed.is_err());
+    assert!(table.update_where(FilterExpr::Gt("score".to_string(), Value::Int(0)), vec![("name", expr("'x'"))]).is_err());
+    assert_eq!(table.rows[0][0], Value::Varchar("ada".to_string()));
+
+    // Rows trading values within one update are fine
+    table.columns[1].options.push(Options::Unique);
+    table.update_where(|_: &Vec<Value>| true, vec![("score", expr("105 - score"))]).unwrap();
+    assert_eq!(column(&table, 1), [Value::Int(55), Value::Int(10), Value::Int(95)]);
+}
@@ -1,4 +1,5 @@
-use database::table::data::{Table, Column, DataType, Value, Options};
+use database::table::data::{Table, Column, DataType, DefaultExpr, Value, Options};
+use database::error::DbError;
 
 fn col(name: &str, dtype: DataType, options: Vec<Options>) -> Column {
     Column {
@@ -21,7 +22,7 @@ fn test_validate_schema_duplicate_columns() {
     let table = Table::new("users", columns, None);
     let result = table.validate_schema();
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("Duplicate column name"));
+    assert!(result.unwrap_err().to_string().contains("Duplicate column name"));
 }
 
 #[test]
@@ -30,52 +31,52 @@ fn test_validate_schema_missing_primary_key_column() {
     let table = Table::new("users", columns, Some(vec!["not_id".to_string()]));
     let result = table.validate_schema();
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("Primary key column"));
+    assert!(result.unwrap_err().to_string().contains("Primary key column"));
 }
 
 #[test]
 fn test_validate_row_type_mismatch() {
     let columns = vec![col("id", DataType::Int, vec![])];
-    let mut table = Table::new("test", columns, None);
+    let table = Table::new("test", columns, None);
     let row = vec![Value::Varchar("oops".to_string())];
     let result = table.validate_row(&row);
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("does not match declared type"));
+    assert!(result.unwrap_err().to_string().contains("does not match declared type"));
 }
 
 #[test]
 fn test_validate_row_not_null_violation() {
-    let columns = vec![col("name", DataType::Varchar, vec![Options::NotNull])];
-    let mut table = Table::new("test", columns, None);
+    let columns = vec![col("name", DataType::Varchar(255), vec![Options::NotNull])];
+    let table = Table::new("test", columns, None);
     let row = vec![Value::Null];
     let result = table.validate_row(&row);
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("NOT NULL"));
+    assert!(result.unwrap_err().to_string().contains("NOT NULL"));
 }
 
 #[test]
 fn test_validate_row_enum_constraint_violation() {
     let allowed = vec!["Red".to_string(), "Blue".to_string()];
-    let columns = vec![col("color", DataType::Enum, vec![])];
-    let mut table = Table::new("test", columns, None);
-    let row = vec![Value::Enum("Green".to_string(), allowed)];
+    let columns = vec![col("color", DataType::Enum(allowed), vec![])];
+    let table = Table::new("test", columns, None);
+    let row = vec![Value::Enum("Green".to_string())];
     let result = table.validate_row(&row);
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("Invalid enum value"));
+    assert!(result.unwrap_err().to_string().contains("Invalid enum value"));
 }
 
 #[test]
 fn test_validate_row_check_constraint_failure() {
     let columns = vec![col(
         "status",
-        DataType::Varchar,
-        vec![Options::Check("status = active".to_string())],
+        DataType::Varchar(255),
+        vec![Options::Check("status = 'active'".parse().unwrap())],
     )];
-    let mut table = Table::new("test", columns, None);
+    let table = Table::new("test", columns, None);
     let row = vec![Value::Varchar("inactive".to_string())];
     let result = table.validate_row(&row);
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("CHECK failed"));
+    assert!(result.unwrap_err().to_string().contains("CHECK (status = 'active') failed"));
 }
 
 #[test]
@@ -85,14 +86,14 @@ fn test_validate_row_unique_violation() {
     table.insert(vec![int_val(1)]).unwrap();
     let result = table.validate_row(&vec![int_val(1)]);
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("Unique constraint violated"));
+    assert!(result.unwrap_err().to_string().contains("Unique constraint violated"));
 }
 
 #[test]
 fn test_validate_row_primary_key_violation() {
     let columns = vec![
         col("id", DataType::Int, vec![]),
-        col("name", DataType::Varchar, vec![]),
+        col("name", DataType::Varchar(255), vec![]),
     ];
     let mut table = Table::new("test", columns, Some(vec!["id".to_string()]));
     table.insert(vec![int_val(1), Value::Varchar("Alice".to_string())]).unwrap();
@@ -100,7 +101,7 @@ fn test_validate_row_primary_key_violation() {
     assert!(result.is_err());
     assert_eq!(
         result.unwrap_err(),
-        "Primary key constraint violated: duplicate entry"
+        DbError::ConstraintViolation("Primary key constraint violated: duplicate entry".to_string())
     );
 }
 
@@ -114,8 +115,8 @@ fn test_apply_defaults_and_autoincrement() {
         ),
         col(
             "role",
-            DataType::Varchar,
-            vec![Options::Default(Value::Varchar("user".to_string()))],
+            DataType::Varchar(255),
+            vec![Options::Default(DefaultExpr::Literal(Value::Varchar("user".to_string())))],
         ),
     ];
     let mut table = Table::new("accounts", columns, None);
@@ -129,3 +130,212 @@ fn test_apply_defaults_and_autoincrement() {
     let second = table.apply_defaults(&vec![Value::Null, Value::Null]).unwrap();
     assert_eq!(second[0], int_val(2));
 }
+
+#[test]
+fn test_identity_start_and_increment() {
+    let columns = vec![col(
+        "id",
+        DataType::Int,
+        vec![Options::NotNull, Options::Identity { start: 1000, increment: 10 }],
+    )];
+    let mut table = Table::new("orders", columns, None);
+    table.insert(vec![Value::Null]).unwrap();
+    table.insert(vec![Value::Null]).unwrap();
+
+    assert_eq!(table.rows[0][0], int_val(1000));
+    assert_eq!(table.rows[1][0], int_val(1010));
+}
+
+#[test]
+fn test_identity_bigint_and_zero_increment() {
+    let columns = vec![col(
+        "id",
+        DataType::BigInt,
+        vec![Options::NotNull, Options::Identity { start: 5, increment: 1 }],
+    )];
+    let mut table = Table::new("events", columns, None);
+    table.insert(vec![Value::Null]).unwrap();
+    assert_eq!(table.rows[0][0], Value::BigInt(5));
+
+    let bad = col(
+        "id",
+        DataType::Int,
+        vec![Options::NotNull, Options::Identity { start: 1, increment: 0 }],
+    );
+    assert!(bad.validate().is_err());
+}
+
+#[test]
+fn test_default_now_fills_timestamps() {
+    let columns = vec![
+        col("id", DataType::Int, vec![]),
+        col("created_at", DataType::DateTime, vec![Options::Default(DefaultExpr::CurrentTimestamp)]),
+        col("created_on", DataType::Date, vec![Options::Default(DefaultExpr::CurrentTimestamp)]),
+    ];
+    let table = Table::new("audit", columns, None);
+
+    let row = table.apply_defaults(&vec![int_val(1), Value::Null, Value::Null]).unwrap();
+    assert!(matches!(row[1], Value::DateTime(_)));
+    assert_eq!(row[2], Value::today());
+
+    let bad = col("name", DataType::Varchar(255), vec![Options::OnUpdateNow]);
+    assert!(bad.validate().is_err());
+}
+
+#[test]
+fn test_value_display_matches_display_string() {
+    let date = Value::from_date_str("2024-02-29").unwrap();
+    assert_eq!(format!("{}", date), "2024-02-29");
+    assert_eq!(Value::Null.to_string(), "NULL");
+    // Width, alignment and precision apply to the value itself
+    assert_eq!(format!("[{:>5}]", Value::Int(42)), "[   42]");
+    assert_eq!(format!("[{:<6}]", Value::Null), "[NULL  ]");
+    assert_eq!(format!("[{:^7}]", Value::Varchar("ab".to_string())), "[  ab   ]");
+    assert_eq!(format!("[{:.4}]", date), "[2024]");
+}
+
+#[test]
+fn test_unique_and_primary_key_checks_follow_updates_and_deletes() {
+    let columns = vec![
+        col("id", DataType::Int, vec![]),
+        col("part", DataType::Int, vec![]),
+        col("code", DataType::Varchar(255), vec![Options::Unique]),
+    ];
+    let pk = Some(vec!["id".to_string(), "part".to_string()]);
+    let mut table = Table::new("parts", columns, pk);
+    let code = |s: &str| Value::Varchar(s.to_string());
+    table.insert(vec![int_val(1), int_val(1), code("a")]).unwrap();
+    table.insert(vec![int_val(1), int_val(2), code("b")]).unwrap();
+
+    // Only the whole key has to be unique
+    assert!(table.insert(vec![int_val(1), int_val(3), code("c")]).is_ok());
+    assert!(table.insert(vec![int_val(1), int_val(2), code("d")]).is_err());
+    assert!(table.insert(vec![int_val(2), int_val(1), code("b")]).is_err());
+
+    // A row may keep its own values, and freed values can be reused
+    table.update_where(|row: &Vec<Value>| row[2] == code("b"), vec![None, None, Some(code("b"))]).unwrap();
+    table.update_where(|row: &Vec<Value>| row[2] == code("b"), vec![None, None, Some(code("e"))]).unwrap();
+    assert!(table.insert(vec![int_val(3), int_val(1), code("b")]).is_ok());
+    table.delete_where(|row: &Vec<Value>| row[1] == int_val(1)).unwrap();
+    assert!(table.insert(vec![int_val(1), int_val(1), code("a")]).is_ok());
+    assert!(table.insert(vec![int_val(9), int_val(9), code("e")]).is_err());
+
+    // Tables without indexes still catch duplicates by scanning
+    let mut unindexed = table.clone();
+    unindexed.indexes.clear();
+    assert!(unindexed.validate_row(&vec![int_val(1), int_val(1), code("z")]).is_err());
+    assert!(unindexed.validate_row(&vec![int_val(7), int_val(7), code("c")]).is_err());
+    assert!(unindexed.validate_row(&vec![int_val(7), int_val(7), code("z")]).is_ok());
+}
+
+#[test]
+fn test_identity_counter_skips_explicit_and_deleted_ids() {
+    let columns = vec![col("id", DataType::Int, vec![Options::NotNull, Options::Autoincrement])];
+    let mut table = Table::new("tickets", columns, Some(vec!["id".to_string()]));
+    table.insert(vec![Value::Null]).unwrap();
+    table.insert(vec![int_val(10)]).unwrap();
+    table.insert(vec![Value::Null]).unwrap();
+    assert_eq!(table.rows[2][0], int_val(11));
+
+    // Deleting the newest rows does not free their ids
+    table.delete_where(|row: &Vec<Value>| row[0] > int_val(1)).unwrap();
+    table.insert(vec![Value::Null]).unwrap();
+    assert_eq!(table.rows[1][0], int_val(12));
+    assert_eq!(table.sequences.get("id"), Some(&12));
+}
+
+#[test]
+fn test_bigint_identity_goes_past_int_range() {
+    let start = i32::MAX as i64;
+    let columns = vec![col(
+        "id",
+        DataType::BigInt,
+        vec![Options::NotNull, Options::Identity { start, increment: 1 }],
+    )];
+    let mut table = Table::new("events", columns, None);
+    table.insert(vec![Value::Null]).unwrap();
+    table.insert(vec![Value::Null]).unwrap();
+    assert_eq!(table.rows[1][0], Value::BigInt(start + 1));
+
+    // An Int column refuses an id it cannot hold
+    let columns = vec![col(
+        "id",
+        DataType::Int,
+        vec![Options::NotNull, Options::Identity { start, increment: 1 }],
+    )];
+    let mut table = Table::new("events", columns, None);
+    table.insert(vec![Value::Null]).unwrap();
+    assert!(table.insert(vec![Value::Null]).is_err());
+}
+
+#[test]
+fn test_default_expressions_are_worked_out_at_insert() {
+    let in_30_days = DefaultExpr::Add(Box::new(DefaultExpr::CurrentDate), 30);
+    let columns = vec![
+        col("id", DataType::Int, vec![]),
+        col("joined", DataType::Date, vec![Options::Default(DefaultExpr::CurrentDate)]),
+        col("expires", DataType::DateTime, vec![Options::Default(in_30_days)]),
+        col(
+            "quota",
+            DataType::BigInt,
+            vec![Options::Default(DefaultExpr::Add(Box::new(DefaultExpr::Literal(Value::BigInt(100))), -10))],
+        ),
+    ];
+    let mut table = Table::new("members", columns, None);
+    table.insert(vec![int_val(1), Value::Null, Value::Null, Value::Null]).unwrap();
+
+    let today = chrono::Local::now().date_naive();
+    let row = &table.rows[0];
+    assert_eq!(row[1], Value::Date(today));
+    assert_eq!(row[2], Value::DateTime((today + chrono::Days::new(30)).and_hms_opt(0, 0, 0).unwrap()));
+    assert_eq!(row[3], Value::BigInt(90));
+
+    // A default that can never fit its column is rejected with the schema
+    let bad = col("age", DataType::Int, vec![Options::Default(DefaultExpr::CurrentTimestamp)]);
+    assert!(bad.validate().is_err());
+    let bad = col("born", DataType::Date, vec![Options::Default(DefaultExpr::Literal(int_val(3)))]);
+    assert!(bad.validate().is_err());
+}
+
+#[test]
+fn test_string_lengths_are_enforced() {
+    let columns = vec![
+        col("code", DataType::Char(3), vec![]),
+        col("name", DataType::Varchar(5), vec![]),
+        col("note", DataType::Varchar(4), vec![Options::Truncate]),
+    ];
+    let mut table = Table::new("items", columns, None);
+    let text = |s: &str| Value::Varchar(s.to_string());
+
+    table.insert(vec![text("abc"), text("héllo"), text("short note")]).unwrap();
+    assert_eq!(table.rows[0][2], text("shor"));
+
+    let result = table.insert(vec![text("abcd"), text("x"), Value::Null]);
+    assert!(result.unwrap_err().to_string().contains("longer than 3 characters"));
+    assert!(table.insert(vec![text("a"), text("sixsix"), Value::Null]).is_err());
+
+    let updated = table.update_where(|_: &Vec<Value>| true, vec![None, None, Some(text("another"))]).unwrap();
+    assert_eq!(updated, 1);
+    assert_eq!(table.rows[0][2], text("anot"));
+
+    assert!(col("flag", DataType::Boolean, vec![Options::Truncate]).validate().is_err());
+    assert!(col("empty", DataType::Varchar(0), vec![]).validate().is_err());
+}
+
+#[test]
+fn test_set_values_are_checked_against_the_column_list() {
+    let tags = vec!["new".to_string(), "sale".to_string(), "clearance".to_string()];
+    let columns = vec![col("tags", DataType::Set(tags.clone()), vec![])];
+    let mut table = Table::new("items", columns, None);
+
+    let parsed = Value::from_str("{clearance, new, clearance}", &DataType::Set(tags)).unwrap();
+    table.insert(vec![parsed]).unwrap();
+    assert_eq!(table.rows[0][0], Value::Set(vec!["new".to_string(), "clearance".to_string()]));
+
+    let result = table.insert(vec![Value::Set(vec!["used".to_string()])]);
+    assert!(result.unwrap_err().to_string().contains("Invalid set value 'used'"));
+
+    let old = Options::Default(DefaultExpr::Literal(Value::Set(vec!["old".to_string()])));
+    let bad_default = col("tags", DataType::Set(vec!["new".to_string()]), vec![old]);
+    assert!(bad_default.validate().is_err());
+}
@@ -0,0 +1,61 @@
+use database::table::data::{Column, DataType, PagerOptions, ResultSet, Value};
+use std::io::Cursor;
+
+fn result_set(n: i32) -> ResultSet {
+    let columns = vec![
+        Column {
+            name: "id".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+        Column {
+            name: "description".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+    ];
+    let rows = (1..=n)
+        .map(|i| {
+            vec![
+                Value::Int(i),
+                Value::Varchar("a rather long description".to_string()),
+            ]
+        })
+        .collect();
+    ResultSet::new(columns, rows)
+}
+
+#[test]
+fn test_render_page_truncates_and_numbers_rows() {
+    let rs = result_set(5);
+    let options = PagerOptions {
+        page_size: 2,
+        max_column_width: 8,
+        show_row_numbers: true,
+    };
+
+    let page = rs.render_page(1, &options);
+
+    assert!(page.contains("a rathe…"));
+    assert!(page.contains("descrip…"));
+    assert!(page.contains("|      3 "));
+    assert!(page.contains("-- rows 3-4 of 5 (page 2/3) --"));
+    assert!(!page.contains("|      5 "));
+}
+
+#[test]
+fn test_page_through_stops_on_quit() {
+    let rs = result_set(5);
+    let options = PagerOptions {
+        page_size: 2,
+        ..PagerOptions::default()
+    };
+
+    let mut input = Cursor::new("\nq\n");
+    let mut output = Vec::new();
+    rs.page_through_with(&options, &mut input, &mut output).unwrap();
+
+    let text = String::from_utf8(output).unwrap();
+    assert!(text.contains("page 2/3"));
+    assert!(!text.contains("page 3/3"));
+}
@@ -0,0 +1,90 @@
+use database::table::data::{Column, DataType, OrderBy, Table, Value, WindowFunc};
+
+fn scores() -> Table {
+    let columns = vec![
+        Column {
+            name: "player".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+        Column {
+            name: "team".to_string(),
+            datatype: DataType::Varchar(255),
+            options: vec![],
+        },
+        Column {
+            name: "points".to_string(),
+            datatype: DataType::Int,
+            options: vec![],
+        },
+    ];
+    let mut table = Table::new("scores", columns, None);
+    let rows = [("ann", "red", 10), ("bob", "blue", 7), ("cid", "red", 30), ("dee", "red", 10), ("eve", "blue", 9)];
+    for (player, team, points) in rows {
+        table
+            .insert(vec![
+                Value::Varchar(player.to_string()),
+                Value::Varchar(team.to_string()),
+                Value::Int(points),
+            ])
+            .unwrap();
+    }
+    table
+}
+
+fn appended(table: &Table) -> Vec<Value> {
+    table.rows.iter().map(|row| row.last().unwrap().clone()).collect()
+}
+
+#[test]
+fn test_rankings_within_partitions() {
+    let table = scores();
+    let order = OrderBy::desc("points");
+
+    let row_number = table.with_window(&WindowFunc::RowNumber, Some("team"), &order, "n").unwrap();
+    let rank = table.with_window(&WindowFunc::Rank, Some("team"), &order, "rank").unwrap();
+    let dense = table.with_window(&WindowFunc::DenseRank, None, &order, "dense").unwrap();
+
+    // Rows stay in table order; ann and dee tie within red
+    let big = |ns: [i64; 5]| ns.map(Value::BigInt).to_vec();
+    assert_eq!(appended(&row_number), big([2, 2, 1, 3, 1]));
+    assert_eq!(appended(&rank), big([2, 2, 1, 2, 1]));
+    assert_eq!(appended(&dense), big([2, 4, 1, 2, 3]));
+    assert_eq!(rank.columns[3].name, "rank");
+    assert_eq!(rank.columns[3].datatype, DataType::BigInt);
+}
+
+#[test]
+fn test_lag_and_lead_read_neighbouring_rows() {
+    let table = scores();
+    let order = OrderBy::asc("points");
+
+    let lag = table
+        .with_window(&WindowFunc::Lag("player".to_string(), 1), Some("team"), &order, "previous")
+        .unwrap();
+    let lead = table
+        .with_window(&WindowFunc::Lead("points".to_string(), 2), None, &order, "later")
+        .unwrap();
+
+    let name = |s: &str| Value::Varchar(s.to_string());
+    assert_eq!(
+        appended(&lag),
+        vec![Value::Null, Value::Null, name("dee"), name("ann"), name("bob")]
+    );
+    assert_eq!(lag.columns[3].datatype, DataType::Varchar(255));
+    // Sorted by points: bob 7, eve 9, ann 10, dee 10, cid 30
+    assert_eq!(
+        appended(&lead),
+        vec![Value::Int(30), Value::Int(10), Value::Null, Value::Null, Value::Int(10)]
+    );
+}
+
+#[test]
+fn test_window_rejects_unknown_or_duplicate_columns() {
+    let table = scores();
+    let order = OrderBy::asc("points");
+
+    assert!(table.with_window(&WindowFunc::RowNumber, Some("missing"), &order, "n").is_err());
+    assert!(table.with_window(&WindowFunc::RowNumber, None, &OrderBy::asc("missing"), "n").is_err());
+    assert!(table.with_window(&WindowFunc::RowNumber, None, &order, "points").is_err());
+}